@@ -3,6 +3,8 @@
 //! This module defines the [`AppAction`] enum, which represents instructions
 //! produced by the [`crate::App`] state machine for the runtime to execute.
 
+use std::path::PathBuf;
+
 use lockframe_core::mls::RoomId;
 
 /// Actions produced by the App state machine.
@@ -14,6 +16,14 @@ pub enum AppAction {
     /// Quit the application.
     Quit,
 
+    /// Surface a notification to the user.
+    Notify {
+        /// Short notification title.
+        title: String,
+        /// Notification body text.
+        body: String,
+    },
+
     /// Connect to server.
     Connect {
         /// Server address (host:port).
@@ -56,4 +66,13 @@ pub enum AppAction {
         /// User ID to add.
         user_id: u64,
     },
+
+    /// Write a room's message history to a file.
+    ExportTranscript {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+        /// Destination file. The extension picks the
+        /// [`crate::TranscriptFormat`].
+        path: PathBuf,
+    },
 }
@@ -12,12 +12,18 @@
 //! - Tracks the list of rooms, unread badges, and the currently active room.
 //! - Stores terminal dimensions to handle resize events.
 //! - Tracks high-level connection state for UI feedback.
+//! - Holds the [`KeyBindings`] frontends use to map keys to commands.
+//! - Emits notifications for messages arriving in inactive rooms.
+//! - Searches the active room's history and tracks the selected match.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use lockframe_core::mls::RoomId;
 
-use crate::{AppAction, AppEvent, ConnectionState, RoomState};
+use crate::{
+    AppAction, AppEvent, ConnectionState, DEFAULT_MAX_HISTORY, KeyBindings, Message, RoomState,
+    Search,
+};
 
 /// Application state machine.
 ///
@@ -29,7 +35,7 @@ pub struct App {
     state: ConnectionState,
     /// Server address for connection.
     server_addr: String,
-    /// Per-room state (messages, members, unread).
+    /// Per-room state (messages, members, unread count).
     rooms: HashMap<RoomId, RoomState>,
     /// Currently active room. `None` if no room is selected.
     active_room: Option<RoomId>,
@@ -37,11 +43,25 @@ pub struct App {
     terminal_size: (u16, u16),
     /// Transient status message. `None` if no message.
     status_message: Option<String>,
+    /// Key to command mapping consulted by frontends.
+    key_bindings: KeyBindings,
+    /// Only notify for messages containing this text. `None` notifies for
+    /// every message in an inactive room.
+    mention_pattern: Option<String>,
+    /// Message history cap for rooms joined from now on.
+    max_history: usize,
+    /// Search in the active room. `None` if not searching.
+    search: Option<Search>,
 }
 
 impl App {
     /// Create a new App with the given server address.
     pub fn new(server_addr: String) -> Self {
+        Self::new_with_bindings(server_addr, KeyBindings::default())
+    }
+
+    /// Create a new App with a custom key binding map.
+    pub fn new_with_bindings(server_addr: String, key_bindings: KeyBindings) -> Self {
         Self {
             state: ConnectionState::Disconnected,
             server_addr,
@@ -49,10 +69,15 @@ impl App {
             active_room: None,
             terminal_size: (80, 24),
             status_message: None,
+            key_bindings,
+            mention_pattern: None,
+            max_history: DEFAULT_MAX_HISTORY,
+            search: None,
         }
     }
 
     /// Process an event and return actions.
+    #[allow(clippy::too_many_lines)]
     pub fn handle(&mut self, event: AppEvent) -> Vec<AppAction> {
         match event {
             AppEvent::Tick => vec![],
@@ -70,7 +95,10 @@ impl App {
             },
             AppEvent::RoomJoined { room_id } => {
                 let is_new = !self.rooms.contains_key(&room_id);
-                self.rooms.entry(room_id).or_insert_with(|| RoomState::new(room_id));
+                let max_history = self.max_history;
+                self.rooms
+                    .entry(room_id)
+                    .or_insert_with(|| RoomState::with_max_history(room_id, max_history));
                 if self.active_room.is_none() {
                     self.active_room = Some(room_id);
                 }
@@ -81,16 +109,59 @@ impl App {
             },
             AppEvent::RoomLeft { room_id } => {
                 self.rooms.remove(&room_id);
+                self.refresh_search(room_id);
                 if self.active_room == Some(room_id) {
                     self.active_room = self.rooms.keys().next().copied();
                 }
                 vec![AppAction::Render]
             },
-            AppEvent::MessageReceived { room_id, sender_id, content } => {
+            AppEvent::MessageReceived { room_id, sender_id, content, log_index, timestamp } => {
+                let message = Message { sender_id, content, log_index, timestamp, redacted: false };
+                let inactive = self.active_room != Some(room_id);
+                let notify = if inactive { self.notification_for(room_id, &message) } else { None };
+
                 if let Some(room) = self.rooms.get_mut(&room_id) {
-                    room.add_message(sender_id, content);
-                    if self.active_room != Some(room_id) {
-                        room.unread = true;
+                    if inactive {
+                        room.unread_count = room.unread_count.saturating_add(1);
+                    }
+                    room.add_message(message);
+                } else {
+                    return vec![AppAction::Render];
+                }
+                self.refresh_search(room_id);
+
+                let mut actions = vec![AppAction::Render];
+                actions.extend(notify);
+                actions
+            },
+            AppEvent::MessageEdited { room_id, sender_id, target_log_index, content } => {
+                if let Some(room) = self.rooms.get_mut(&room_id) {
+                    room.edit_message(target_log_index, sender_id, content);
+                }
+                self.refresh_search(room_id);
+                vec![AppAction::Render]
+            },
+            AppEvent::MessageRedacted { room_id, target_log_index } => {
+                if let Some(room) = self.rooms.get_mut(&room_id) {
+                    room.redact_message(target_log_index);
+                }
+                self.refresh_search(room_id);
+                vec![AppAction::Render]
+            },
+            AppEvent::MessageExpired { room_id, log_index } => {
+                if let Some(room) = self.rooms.get_mut(&room_id) {
+                    room.remove_message(log_index);
+                }
+                self.refresh_search(room_id);
+                vec![AppAction::Render]
+            },
+            AppEvent::RoomMetaChanged { room_id, name, topic } => {
+                if let Some(room) = self.rooms.get_mut(&room_id) {
+                    if name.is_some() {
+                        room.name = name;
+                    }
+                    if topic.is_some() {
+                        room.topic = topic;
                     }
                 }
                 vec![AppAction::Render]
@@ -108,6 +179,11 @@ impl App {
                 }
                 vec![AppAction::Render]
             },
+            AppEvent::Disconnected { reason } => {
+                let actions = self.connect();
+                self.status_message = Some(format!("Disconnected: {reason}, reconnecting"));
+                actions
+            },
             AppEvent::Error { message } => {
                 self.status_message = Some(format!("Error: {message}"));
                 vec![AppAction::Render]
@@ -115,6 +191,124 @@ impl App {
         }
     }
 
+    /// Recompute matches after `room_id`'s messages changed.
+    ///
+    /// Keeps the selected position where it still exists and ends the search
+    /// once nothing matches.
+    fn refresh_search(&mut self, room_id: RoomId) {
+        let Some(search) = self.search.as_mut().filter(|s| s.room_id == room_id) else {
+            return;
+        };
+        let hits = self.rooms.get(&room_id).map(|room| room.search(&search.term));
+        match hits {
+            Some(hits) if !hits.is_empty() => {
+                search.current = search.current.min(hits.len().saturating_sub(1));
+                search.hits = hits;
+            },
+            _ => self.search = None,
+        }
+    }
+
+    /// Build a `Notify` action for a message in an inactive room.
+    ///
+    /// `None` for our own messages, or when a mention pattern is configured
+    /// and the message does not contain it.
+    fn notification_for(&self, room_id: RoomId, message: &Message) -> Option<AppAction> {
+        if let ConnectionState::Connected { sender_id, .. } = self.state
+            && sender_id == message.sender_id
+        {
+            return None;
+        }
+
+        let content = message.content_str();
+        if let Some(pattern) = &self.mention_pattern
+            && !content.contains(pattern.as_str())
+        {
+            return None;
+        }
+
+        Some(AppAction::Notify {
+            title: format!("#{:04x}", room_id as u16),
+            body: format!("<{:04x}> {content}", message.sender_id as u16),
+        })
+    }
+
+    /// Restrict notifications to messages containing `pattern` (e.g. the
+    /// user's nick). `None` notifies for every message in an inactive room.
+    pub fn set_mention_pattern(&mut self, pattern: Option<String>) {
+        self.mention_pattern = pattern;
+    }
+
+    /// Keep at most `max_history` messages per room, evicting the oldest.
+    ///
+    /// Applies to rooms joined after the call.
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.max_history = max_history;
+    }
+
+    /// Search the active room's history for `term`, ignoring case.
+    ///
+    /// Selects the newest match. Redacted messages and content that is not
+    /// valid UTF-8 are skipped.
+    pub fn search(&mut self, term: &str) -> Vec<AppAction> {
+        let Some(room) = self.active_room_state() else {
+            self.status_message = Some("No active room to search".into());
+            return vec![AppAction::Render];
+        };
+
+        let search = Search {
+            room_id: room.room_id,
+            term: term.to_string(),
+            hits: room.search(term),
+            current: 0,
+        };
+        if search.hits.is_empty() {
+            self.search = None;
+            self.status_message = Some(format!("No matches for \"{term}\""));
+        } else {
+            let current = search.hits.len().saturating_sub(1);
+            self.search = Some(Search { current, ..search });
+            self.report_search();
+        }
+        vec![AppAction::Render]
+    }
+
+    /// Select the next older match, wrapping to the newest.
+    pub fn older_search_hit(&mut self) -> Vec<AppAction> {
+        if let Some(search) = &mut self.search {
+            search.current = search.current.checked_sub(1).unwrap_or(search.hits.len() - 1);
+            self.report_search();
+        }
+        vec![AppAction::Render]
+    }
+
+    /// Select the next newer match, wrapping to the oldest.
+    pub fn newer_search_hit(&mut self) -> Vec<AppAction> {
+        if let Some(search) = &mut self.search {
+            search.current = (search.current + 1) % search.hits.len();
+            self.report_search();
+        }
+        vec![AppAction::Render]
+    }
+
+    /// End the current search.
+    pub fn clear_search(&mut self) -> Vec<AppAction> {
+        self.search = None;
+        vec![AppAction::Render]
+    }
+
+    /// Show the selected match's position in the status bar.
+    fn report_search(&mut self) {
+        if let Some(search) = &self.search {
+            self.status_message = Some(format!(
+                "\"{}\": match {} of {}",
+                search.term,
+                search.current + 1,
+                search.hits.len()
+            ));
+        }
+    }
+
     /// Set a status message to display to the user.
     pub fn set_status(&mut self, message: impl Into<String>) {
         self.status_message = Some(message.into());
@@ -153,11 +347,50 @@ impl App {
         vec![AppAction::AddMember { room_id, user_id }, AppAction::Render]
     }
 
+    /// Open a direct-message room with `user_id`.
+    ///
+    /// The room ID is derived from both user IDs, so either side messaging
+    /// the other lands in the same room. If the room already exists it
+    /// becomes active; otherwise it is created and the user invited.
+    pub fn direct_message(&mut self, user_id: u64) -> Vec<AppAction> {
+        let ConnectionState::Connected { sender_id, .. } = self.state else {
+            self.status_message = Some("Not connected".to_string());
+            return vec![AppAction::Render];
+        };
+
+        if user_id == sender_id {
+            self.status_message = Some("Cannot message yourself".to_string());
+            return vec![AppAction::Render];
+        }
+
+        let room_id = direct_room_id(sender_id, user_id);
+        if self.rooms.contains_key(&room_id) {
+            self.set_active_room(room_id);
+            return vec![AppAction::Render];
+        }
+
+        self.status_message = Some(format!("Opening direct messages with user {user_id}..."));
+        vec![
+            AppAction::CreateRoom { room_id },
+            AppAction::AddMember { room_id, user_id },
+            AppAction::Render,
+        ]
+    }
+
     /// Send a message to the specified room.
     pub fn send_message(&self, room_id: RoomId, content: Vec<u8>) -> Vec<AppAction> {
         vec![AppAction::SendMessage { room_id, content }, AppAction::Render]
     }
 
+    /// Export the active room's history to `path`.
+    pub fn export_transcript(&mut self, path: PathBuf) -> Vec<AppAction> {
+        let Some(room_id) = self.active_room else {
+            self.status_message = Some("No active room to export".into());
+            return vec![AppAction::Render];
+        };
+        vec![AppAction::ExportTranscript { room_id, path }]
+    }
+
     /// Quit the application.
     pub fn quit(&self) -> Vec<AppAction> {
         vec![AppAction::Quit]
@@ -167,8 +400,11 @@ impl App {
     pub fn set_active_room(&mut self, room_id: RoomId) {
         if self.rooms.contains_key(&room_id) {
             self.active_room = Some(room_id);
+            if self.search.as_ref().is_some_and(|s| s.room_id != room_id) {
+                self.search = None;
+            }
             if let Some(room) = self.rooms.get_mut(&room_id) {
-                room.unread = false;
+                room.unread_count = 0;
             }
         }
     }
@@ -207,6 +443,22 @@ impl App {
     pub fn status_message(&self) -> Option<&str> {
         self.status_message.as_deref()
     }
+
+    /// Search in the active room. `None` if not searching.
+    pub fn active_search(&self) -> Option<&Search> {
+        self.search.as_ref()
+    }
+
+    /// Key to command mapping.
+    pub fn key_bindings(&self) -> &KeyBindings {
+        &self.key_bindings
+    }
+}
+
+/// Room ID for direct messages between two users, independent of order.
+fn direct_room_id(a: u64, b: u64) -> RoomId {
+    let (low, high) = if a < b { (a, b) } else { (b, a) };
+    (RoomId::from(high) << 64) | RoomId::from(low)
 }
 
 #[cfg(test)]
@@ -227,6 +479,8 @@ mod tests {
             room_id: 1,
             sender_id: 42,
             content: b"hello".to_vec(),
+            log_index: Some(0),
+            timestamp: Some(0),
         });
 
         assert_eq!(app.rooms.get(&1).map(|r| r.messages.len()), Some(1));
@@ -236,6 +490,125 @@ mod tests {
         assert_eq!(app.rooms.get(&1).map(|r| r.messages.len()), Some(1));
     }
 
+    fn receive(app: &mut App, room_id: RoomId, log_index: u64) {
+        let _ = app.handle(AppEvent::MessageReceived {
+            room_id,
+            sender_id: 7,
+            content: format!("m{log_index}").into_bytes(),
+            log_index: Some(log_index),
+            timestamp: None,
+        });
+    }
+
+    #[test]
+    fn exceeding_max_history_evicts_oldest_and_marks_truncated() {
+        let mut app = connected_app();
+        app.set_max_history(3);
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+
+        for log_index in 0..3 {
+            receive(&mut app, 1, log_index);
+        }
+        assert!(!app.rooms[&1].truncated);
+
+        for log_index in 3..5 {
+            receive(&mut app, 1, log_index);
+        }
+        let room = &app.rooms[&1];
+        assert!(room.truncated);
+        let indices: Vec<_> = room.messages.iter().map(|m| m.log_index).collect();
+        assert_eq!(indices, [Some(2), Some(3), Some(4)]);
+    }
+
+    #[test]
+    fn late_message_older_than_history_is_dropped() {
+        let mut app = connected_app();
+        app.set_max_history(2);
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        for log_index in [5, 6] {
+            receive(&mut app, 1, log_index);
+        }
+
+        receive(&mut app, 1, 1);
+
+        let indices: Vec<_> = app.rooms[&1].messages.iter().map(|m| m.log_index).collect();
+        assert_eq!(indices, [Some(5), Some(6)]);
+        assert!(app.rooms[&1].truncated);
+    }
+
+    fn receive_content(app: &mut App, log_index: u64, content: &[u8]) {
+        let _ = app.handle(AppEvent::MessageReceived {
+            room_id: 1,
+            sender_id: 7,
+            content: content.to_vec(),
+            log_index: Some(log_index),
+            timestamp: None,
+        });
+    }
+
+    #[test]
+    fn search_selects_newest_matching_message() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        receive_content(&mut app, 0, b"Meet at noon");
+        receive_content(&mut app, 1, b"unrelated");
+        receive_content(&mut app, 2, b"see you at the MEETING");
+
+        let _ = app.search("meet");
+
+        let search = app.active_search().unwrap();
+        assert_eq!(search.hits, [0, 2]);
+        assert_eq!(search.current_hit(), Some(2));
+        assert_eq!(app.status_message(), Some("\"meet\": match 2 of 2"));
+
+        let _ = app.older_search_hit();
+        assert_eq!(app.active_search().unwrap().current_hit(), Some(0));
+    }
+
+    #[test]
+    fn search_without_matches_reports_and_clears() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        receive_content(&mut app, 0, b"hello");
+        let _ = app.search("hello");
+        assert!(app.active_search().is_some());
+
+        let actions = app.search("goodbye");
+
+        assert_eq!(actions, vec![AppAction::Render]);
+        assert!(app.active_search().is_none());
+        assert_eq!(app.status_message(), Some("No matches for \"goodbye\""));
+    }
+
+    #[test]
+    fn search_ignores_binary_and_redacted_content() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        receive_content(&mut app, 0, b"key \xff\xfe blob");
+        receive_content(&mut app, 1, b"key in the clear");
+        receive_content(&mut app, 2, b"key to redact");
+        let _ = app.handle(AppEvent::MessageRedacted { room_id: 1, target_log_index: 2 });
+
+        let _ = app.search("key");
+
+        assert_eq!(app.active_search().unwrap().hits, [1]);
+    }
+
+    #[test]
+    fn export_targets_active_room() {
+        let mut app = connected_app();
+        assert_eq!(app.export_transcript("chat.txt".into()), vec![AppAction::Render]);
+
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 2 });
+        app.set_active_room(2);
+
+        assert_eq!(app.export_transcript("chat.txt".into()), vec![AppAction::ExportTranscript {
+            room_id: 2,
+            path: "chat.txt".into()
+        }]);
+    }
+
     #[test]
     fn api_create_room() {
         let mut app = connected_app();
@@ -300,6 +673,16 @@ mod tests {
         assert!(matches!(app.state, ConnectionState::Connecting));
     }
 
+    #[test]
+    fn disconnect_shows_reason_and_reconnects() {
+        let mut app = connected_app();
+        let actions = app.handle(AppEvent::Disconnected { reason: "idle timeout".into() });
+
+        assert!(matches!(actions.as_slice(), [AppAction::Connect { .. }, AppAction::Render]));
+        assert!(matches!(app.state, ConnectionState::Connecting));
+        assert_eq!(app.status_message.as_deref(), Some("Disconnected: idle timeout, reconnecting"));
+    }
+
     #[test]
     fn api_set_active_room() {
         let mut app = connected_app();
@@ -313,4 +696,220 @@ mod tests {
         app.set_active_room(999);
         assert_eq!(app.active_room, Some(2));
     }
+
+    #[test]
+    fn messages_ordered_by_log_index() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+
+        for (log_index, content) in [(2, "third"), (0, "first"), (1, "second")] {
+            let _ = app.handle(AppEvent::MessageReceived {
+                room_id: 1,
+                sender_id: 7,
+                content: content.as_bytes().to_vec(),
+                log_index: Some(log_index),
+                timestamp: Some(1_000 + log_index),
+            });
+        }
+
+        let room = app.active_room_state().unwrap();
+        let indices: Vec<_> = room.messages.iter().map(|m| m.log_index).collect();
+        assert_eq!(indices, vec![Some(0), Some(1), Some(2)]);
+
+        let contents: Vec<_> = room.messages.iter().map(Message::content_str).collect();
+        assert_eq!(contents, vec!["first", "second", "third"]);
+        assert_eq!(room.messages[2].timestamp, Some(1_002));
+    }
+
+    #[test]
+    fn message_edit_replaces_content_from_original_sender() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        let _ = app.handle(AppEvent::MessageReceived {
+            room_id: 1,
+            sender_id: 7,
+            content: b"helo".to_vec(),
+            log_index: Some(0),
+            timestamp: None,
+        });
+
+        // Edits claiming a different author are ignored
+        let _ = app.handle(AppEvent::MessageEdited {
+            room_id: 1,
+            sender_id: 8,
+            target_log_index: 0,
+            content: b"spoofed".to_vec(),
+        });
+        assert_eq!(app.active_room_state().unwrap().messages[0].content_str(), "helo");
+
+        let _ = app.handle(AppEvent::MessageEdited {
+            room_id: 1,
+            sender_id: 7,
+            target_log_index: 0,
+            content: b"hello".to_vec(),
+        });
+        assert_eq!(app.active_room_state().unwrap().messages[0].content_str(), "hello");
+    }
+
+    #[test]
+    fn message_redaction_leaves_tombstone() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        let _ = app.handle(AppEvent::MessageReceived {
+            room_id: 1,
+            sender_id: 7,
+            content: b"spam".to_vec(),
+            log_index: Some(0),
+            timestamp: None,
+        });
+
+        let _ = app.handle(AppEvent::MessageRedacted { room_id: 1, target_log_index: 0 });
+
+        let message = &app.active_room_state().unwrap().messages[0];
+        assert!(message.redacted);
+        assert!(message.content.is_empty());
+        assert_eq!(message.sender_id, 7);
+    }
+
+    #[test]
+    fn expired_message_is_removed_without_tombstone() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        for log_index in 0..2 {
+            let _ = app.handle(AppEvent::MessageReceived {
+                room_id: 1,
+                sender_id: 7,
+                content: b"hi".to_vec(),
+                log_index: Some(log_index),
+                timestamp: None,
+            });
+        }
+
+        let _ = app.handle(AppEvent::MessageExpired { room_id: 1, log_index: 0 });
+
+        let messages = &app.active_room_state().unwrap().messages;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].log_index, Some(1));
+    }
+
+    #[test]
+    fn room_meta_updates_only_changed_fields() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+
+        let _ = app.handle(AppEvent::RoomMetaChanged {
+            room_id: 1,
+            name: Some("ops".into()),
+            topic: Some("on-call".into()),
+        });
+        let _ = app.handle(AppEvent::RoomMetaChanged {
+            room_id: 1,
+            name: None,
+            topic: Some("incident review".into()),
+        });
+
+        let room = app.active_room_state().unwrap();
+        assert_eq!(room.name.as_deref(), Some("ops"));
+        assert_eq!(room.topic.as_deref(), Some("incident review"));
+    }
+
+    #[test]
+    fn unread_count_accumulates_and_resets_on_switch() {
+        let mut app = connected_app();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 2 });
+        assert_eq!(app.active_room, Some(1));
+
+        for room_id in [1, 2, 2, 2] {
+            let _ = app.handle(AppEvent::MessageReceived {
+                room_id,
+                sender_id: 7,
+                content: b"hi".to_vec(),
+                log_index: None,
+                timestamp: None,
+            });
+        }
+
+        // Messages to the active room are never counted as unread
+        assert_eq!(app.rooms[&1].unread_count, 0);
+        assert_eq!(app.rooms[&2].unread_count, 3);
+
+        app.set_active_room(2);
+        assert_eq!(app.rooms[&2].unread_count, 0);
+    }
+
+    #[test]
+    fn mention_in_inactive_room_notifies() {
+        let mut app = connected_app();
+        app.set_mention_pattern(Some("alice".into()));
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 2 });
+
+        let actions = app.handle(AppEvent::MessageReceived {
+            room_id: 2,
+            sender_id: 7,
+            content: b"hey alice, ping".to_vec(),
+            log_index: Some(0),
+            timestamp: None,
+        });
+        assert!(actions.iter().any(|a| matches!(
+            a,
+            AppAction::Notify { body, .. } if body.contains("hey alice")
+        )));
+
+        // Messages without the mention stay silent
+        let actions = app.handle(AppEvent::MessageReceived {
+            room_id: 2,
+            sender_id: 7,
+            content: b"unrelated".to_vec(),
+            log_index: Some(1),
+            timestamp: None,
+        });
+        assert!(!actions.iter().any(|a| matches!(a, AppAction::Notify { .. })));
+    }
+
+    #[test]
+    fn active_room_message_does_not_notify() {
+        let mut app = connected_app();
+        app.set_mention_pattern(Some("alice".into()));
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+
+        let actions = app.handle(AppEvent::MessageReceived {
+            room_id: 1,
+            sender_id: 7,
+            content: b"hey alice".to_vec(),
+            log_index: Some(0),
+            timestamp: None,
+        });
+        assert_eq!(actions, vec![AppAction::Render]);
+    }
+
+    #[test]
+    fn direct_message_room_is_shared_by_both_users() {
+        assert_eq!(direct_room_id(7, 42), direct_room_id(42, 7));
+        assert_ne!(direct_room_id(7, 42), direct_room_id(7, 43));
+    }
+
+    #[test]
+    fn direct_message_to_existing_room_switches_to_it() {
+        let mut app = connected_app();
+        let room_id = direct_room_id(42, 7);
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 1 });
+        let _ = app.handle(AppEvent::RoomJoined { room_id });
+
+        let actions = app.direct_message(7);
+        assert_eq!(actions, vec![AppAction::Render]);
+        assert_eq!(app.active_room, Some(room_id));
+    }
+
+    #[test]
+    fn direct_message_requires_connection_and_other_user() {
+        let mut app = App::new("localhost:8080".into());
+        assert_eq!(app.direct_message(7), vec![AppAction::Render]);
+        assert_eq!(app.status_message(), Some("Not connected"));
+
+        let mut app = connected_app();
+        assert_eq!(app.direct_message(42), vec![AppAction::Render]);
+        assert_eq!(app.status_message(), Some("Cannot message yourself"));
+    }
 }
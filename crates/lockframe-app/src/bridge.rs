@@ -13,8 +13,12 @@
 //!   [`crate::AppEvent`]s to update the UI.
 //! - Manages time ticks generically to support both real-time execution and
 //!   deterministic simulation.
+//! - Sends frames the server rate-limited again once its advised delay has
+//!   passed, on a later tick.
 
-use lockframe_client::{Client, ClientAction, ClientError, ClientEvent, ClientIdentity};
+use lockframe_client::{
+    Client, ClientAction, ClientConfig, ClientError, ClientEvent, ClientIdentity, Resend, SendRetry,
+};
 use lockframe_core::env::Environment;
 use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::session::SyncRequest};
 
@@ -25,16 +29,20 @@ use crate::{AppAction, AppEvent};
 /// Generic over Environment to support both production and simulation.
 /// The Instant type is determined by the Environment's associated type.
 pub struct Bridge<E: Environment> {
+    env: E,
     client: Client<E>,
     outgoing: Vec<Frame>,
+    retry: SendRetry,
+    /// Rate-limited frames and when the server refused them.
+    resends: Vec<(Resend, E::Instant)>,
 }
 
 impl<E: Environment> Bridge<E> {
     /// Create a new Bridge with the given environment and sender ID.
     pub fn new(env: E, sender_id: u64) -> Self {
         let identity = ClientIdentity::new(sender_id);
-        let client = Client::new(env, identity);
-        Self { client, outgoing: Vec::new() }
+        let client = Client::new(env.clone(), identity, ClientConfig::default());
+        Self { env, client, outgoing: Vec::new(), retry: SendRetry::default(), resends: Vec::new() }
     }
 
     /// Client's stable sender ID.
@@ -62,6 +70,8 @@ impl<E: Environment> Bridge<E> {
                         room_id,
                         sender_id: self.client.sender_id(),
                         content,
+                        log_index: None,
+                        timestamp: None,
                     });
                 }
                 events
@@ -83,18 +93,38 @@ impl<E: Environment> Bridge<E> {
                     self.client.handle(ClientEvent::FetchAndAddMember { room_id, user_id });
                 self.handle_client_result(result)
             },
-            AppAction::Render | AppAction::Quit | AppAction::Connect { .. } => vec![],
+            AppAction::Render
+            | AppAction::Quit
+            | AppAction::Notify { .. }
+            | AppAction::Connect { .. }
+            | AppAction::ExportTranscript { .. } => vec![],
         }
     }
 
     /// Handle a frame from the server.
+    ///
+    /// A `RATE_LIMITED` error for a frame with attempts left schedules a
+    /// resend instead of surfacing the error.
     pub fn handle_frame(&mut self, frame: Frame) -> Vec<AppEvent> {
+        if let Some(resend) = self.retry.on_frame(&frame) {
+            self.resends.push((resend, self.env.now()));
+            return vec![];
+        }
         let result = self.client.handle(ClientEvent::FrameReceived(frame));
         self.handle_client_result(result)
     }
 
     /// Process a time tick.
     pub fn handle_tick(&mut self, now: E::Instant) -> Vec<AppEvent> {
+        let (due, waiting) = std::mem::take(&mut self.resends)
+            .into_iter()
+            .partition(|(resend, refused_at)| now - *refused_at >= resend.delay);
+        self.resends = waiting;
+        for (resend, _) in due {
+            self.outgoing.push(resend.frame.clone());
+            self.retry.resent(resend);
+        }
+
         let result = self.client.handle(ClientEvent::Tick { now });
         self.handle_client_result(result)
     }
@@ -114,21 +144,54 @@ impl<E: Environment> Bridge<E> {
         }
     }
 
+    #[allow(clippy::too_many_lines)]
     fn process_client_actions(&mut self, actions: Vec<ClientAction>) -> Vec<AppEvent> {
         let mut events = Vec::new();
 
         for action in actions {
             match action {
                 ClientAction::Send(frame) => {
+                    self.retry.sent(&frame);
                     self.outgoing.push(frame);
                 },
-                ClientAction::DeliverMessage { room_id, sender_id, plaintext, .. } => {
+                ClientAction::DeliverMessage {
+                    room_id,
+                    sender_id,
+                    plaintext,
+                    log_index,
+                    timestamp,
+                } => {
                     events.push(AppEvent::MessageReceived {
                         room_id,
                         sender_id,
                         content: plaintext,
+                        log_index: Some(log_index),
+                        timestamp: Some(timestamp),
+                    });
+                },
+                ClientAction::MessageEdited {
+                    room_id,
+                    sender_id,
+                    target_log_index,
+                    plaintext,
+                    ..
+                } => {
+                    events.push(AppEvent::MessageEdited {
+                        room_id,
+                        sender_id,
+                        target_log_index,
+                        content: plaintext,
                     });
                 },
+                ClientAction::MessageRedacted { room_id, target_log_index, .. } => {
+                    events.push(AppEvent::MessageRedacted { room_id, target_log_index });
+                },
+                ClientAction::MessageExpired { room_id, log_index } => {
+                    events.push(AppEvent::MessageExpired { room_id, log_index });
+                },
+                ClientAction::RoomMetaChanged { room_id, name, topic, .. } => {
+                    events.push(AppEvent::RoomMetaChanged { room_id, name, topic });
+                },
                 ClientAction::RoomRemoved { room_id, .. } => {
                     events.push(AppEvent::RoomLeft { room_id });
                 },
@@ -136,25 +199,61 @@ impl<E: Environment> Bridge<E> {
                     events.push(AppEvent::RoomJoined { room_id: snapshot.room_id });
                 },
                 ClientAction::RequestSync { from_epoch, .. } => {
-                    let payload = SyncRequest { from_log_index: from_epoch, limit: 100 };
+                    let payload = SyncRequest {
+                        from_log_index: from_epoch,
+                        limit: 100,
+                        from_timestamp: None,
+                    };
                     if let Ok(frame) = Payload::SyncRequest(payload)
                         .into_frame(FrameHeader::new(Opcode::SyncRequest))
                     {
                         self.outgoing.push(frame);
                     }
                 },
-                ClientAction::MemberAdded { room_id, user_id } => {
-                    events.push(AppEvent::MemberAdded { room_id, member_id: user_id });
+                ClientAction::InviteeRejected { room_id, user_id, reason } => {
+                    events.push(AppEvent::Error {
+                        message: format!("can't add user {user_id} to room {room_id:x}: {reason}"),
+                    });
+                },
+                ClientAction::SyncStalled { room_id, reason } => {
+                    events.push(AppEvent::Error {
+                        message: format!("sync stalled for room {room_id:x}: {reason}"),
+                    });
+                },
+                ClientAction::ServerError { room_id, code, message, .. } => {
+                    events.push(AppEvent::Error {
+                        message: format!(
+                            "server error {code:#06x} for room {room_id:x}: {message}"
+                        ),
+                    });
+                },
+                ClientAction::Disconnected { reason } => {
+                    events.push(AppEvent::Disconnected { reason });
+                },
+                ClientAction::MembersChanged { room_id, added, removed } => {
+                    events.extend(
+                        added
+                            .into_iter()
+                            .map(|member_id| AppEvent::MemberAdded { room_id, member_id }),
+                    );
+                    events.extend(
+                        removed
+                            .into_iter()
+                            .map(|member_id| AppEvent::MemberRemoved { room_id, member_id }),
+                    );
                 },
                 ClientAction::KeyPackageNeeded { reason } => {
+                    // A Welcome arrived that none of our KeyPackages match. Publish a fresh one
+                    // so the inviter can retry the add; the failed Welcome itself is not
+                    // sequenced and cannot be recovered through sync.
                     tracing::warn!(%reason, "KeyPackage needed, auto-republishing");
-                    if let Ok(actions) = self.client.handle(ClientEvent::PublishKeyPackage) {
-                        events.extend(self.process_client_actions(actions));
-                    }
+                    let result = self.client.handle(ClientEvent::PublishKeyPackage);
+                    events.extend(self.handle_client_result(result));
                 },
                 ClientAction::RoomJoined { room_id, .. } => {
                     events.push(AppEvent::RoomJoined { room_id });
-                    let payload = SyncRequest { from_log_index: 0, limit: 1000 };
+                    let payload =
+                        SyncRequest { from_log_index: 0, limit: 1000, from_timestamp: None };
 
                     if let Ok(mut frame) = Payload::SyncRequest(payload)
                         .into_frame(FrameHeader::new(Opcode::SyncRequest))
@@ -163,7 +262,18 @@ impl<E: Environment> Bridge<E> {
                         self.outgoing.push(frame);
                     }
                 },
-                ClientAction::Log { .. } | ClientAction::KeyPackagePublished => {},
+                // A buffered commit's gap is filled by the RequestSync beside it.
+                // Our own adds and removes are reported by the MembersChanged
+                // their commit produces once it is sequenced
+                ClientAction::Log { .. }
+                | ClientAction::MemberAdded { .. }
+                | ClientAction::MemberRemoved { .. }
+                | ClientAction::CommitBuffered { .. }
+                | ClientAction::MessageQueued { .. }
+                | ClientAction::KeyPackagePublished
+                | ClientAction::InviteeValidated { .. }
+                | ClientAction::TypingChanged { .. }
+                | ClientAction::RoomListReceived { .. } => {},
             }
         }
 
@@ -173,7 +283,10 @@ impl<E: Environment> Bridge<E> {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use lockframe_core::env::test_utils::MockEnv;
+    use lockframe_proto::payloads::{ErrorPayload, mls::KeyPackageFetchPayload};
 
     use super::*;
 
@@ -205,4 +318,97 @@ mod tests {
         });
         assert!(events.iter().any(|e| matches!(e, AppEvent::Error { .. })));
     }
+
+    #[test]
+    fn rate_limited_message_is_resent_after_advised_delay() {
+        let env = MockEnv::with_crypto_rng();
+        let mut bridge: Bridge<MockEnv> = Bridge::new(env.clone(), 42);
+        let _ = bridge.process_app_action(AppAction::CreateRoom { room_id: 1 });
+        let _ = bridge.take_outgoing();
+        let _ = bridge
+            .process_app_action(AppAction::SendMessage { room_id: 1, content: b"hello".to_vec() });
+        let sent = bridge.take_outgoing().remove(0);
+
+        let mut error = Payload::Error(ErrorPayload::rate_limited(2))
+            .into_frame(FrameHeader::new(Opcode::Error))
+            .unwrap();
+        error.header.set_room_id(1);
+        error.header.set_request_id(sent.header.request_id());
+        assert!(bridge.handle_frame(error).is_empty());
+
+        let _ = bridge.handle_tick(env.now());
+        assert!(bridge.take_outgoing().is_empty());
+
+        env.advance_time(Duration::from_secs(2));
+        let _ = bridge.handle_tick(env.now());
+        let resent = bridge.take_outgoing();
+        assert_eq!(resent.len(), 1);
+        assert_eq!(resent[0].header.signature(), sent.header.signature());
+    }
+
+    #[test]
+    fn key_package_needed_republishes() {
+        let mut bridge: Bridge<MockEnv> = Bridge::new(MockEnv::new(), 42);
+
+        // Welcome that matches none of our (nonexistent) KeyPackages
+        let mut header = FrameHeader::new(Opcode::Welcome);
+        header.set_room_id(0x1234);
+        let welcome = Frame::new(header, vec![1, 2, 3, 4]);
+
+        let events = bridge.handle_frame(welcome);
+        assert!(!events.iter().any(|e| matches!(e, AppEvent::Error { .. })));
+
+        let outgoing = bridge.take_outgoing();
+        assert!(
+            outgoing.iter().any(|f| f.header.opcode_enum() == Some(Opcode::KeyPackagePublish)),
+            "Expected KeyPackagePublish frame, got: {outgoing:?}"
+        );
+    }
+
+    #[test]
+    fn member_added_maps_to_one_app_event_once_sequenced() {
+        let mut alice: Bridge<MockEnv> = Bridge::new(MockEnv::with_crypto_rng(), 1);
+        let mut bob: Bridge<MockEnv> = Bridge::new(MockEnv::with_crypto_rng(), 2);
+
+        let _ = bob.process_app_action(AppAction::PublishKeyPackage);
+        let published = bob
+            .take_outgoing()
+            .into_iter()
+            .find_map(|f| match Payload::from_frame(&f) {
+                Ok(Payload::KeyPackagePublish(req)) => Some(req),
+                _ => None,
+            })
+            .expect("Bob should publish a KeyPackage");
+
+        let _ = alice.process_app_action(AppAction::CreateRoom { room_id: 7 });
+        let _ = alice.process_app_action(AppAction::AddMember { room_id: 7, user_id: 2 });
+        let _ = alice.take_outgoing();
+
+        let response = Payload::KeyPackageFetch(KeyPackageFetchPayload {
+            user_id: 2,
+            key_package_bytes: published.key_package_bytes,
+            hash_ref: published.hash_ref,
+        })
+        .into_frame(FrameHeader::new(Opcode::KeyPackageFetch))
+        .unwrap();
+
+        let added = |events: &[AppEvent]| {
+            events
+                .iter()
+                .filter(|e| matches!(e, AppEvent::MemberAdded { room_id: 7, member_id: 2 }))
+                .count()
+        };
+
+        // Nothing is reported until the commit comes back sequenced
+        let events = alice.handle_frame(response);
+        assert_eq!(added(&events), 0, "unexpected MemberAdded before the echo: {events:?}");
+
+        let commit = alice
+            .take_outgoing()
+            .into_iter()
+            .find(|f| f.header.opcode_enum() == Some(Opcode::Commit))
+            .expect("Alice should send a Commit");
+        let events = alice.handle_frame(commit);
+        assert_eq!(added(&events), 1, "Expected one MemberAdded for room 7, got: {events:?}");
+    }
 }
@@ -49,6 +49,48 @@ pub enum AppEvent {
         sender_id: u64,
         /// Message content bytes.
         content: Vec<u8>,
+        /// Server-assigned log index. `None` for local echoes.
+        log_index: Option<u64>,
+        /// HLC timestamp in milliseconds. `None` for local echoes.
+        timestamp: Option<u64>,
+    },
+
+    /// Earlier message edited by its sender.
+    MessageEdited {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+        /// ID of the sender (author of the original message).
+        sender_id: u64,
+        /// Log index of the message being edited.
+        target_log_index: u64,
+        /// Replacement content bytes.
+        content: Vec<u8>,
+    },
+
+    /// Earlier message redacted.
+    MessageRedacted {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+        /// Log index of the redacted message.
+        target_log_index: u64,
+    },
+
+    /// Earlier message expired.
+    MessageExpired {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+        /// Log index of the expired message.
+        log_index: u64,
+    },
+
+    /// Room name or topic changed.
+    RoomMetaChanged {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+        /// New room name, if it changed.
+        name: Option<String>,
+        /// New room topic, if it changed.
+        topic: Option<String>,
     },
 
     /// Member added to room.
@@ -67,6 +109,12 @@ pub enum AppEvent {
         member_id: u64,
     },
 
+    /// Server ended the session.
+    Disconnected {
+        /// Reason the server gave.
+        reason: String,
+    },
+
     /// Error occurred.
     Error {
         /// Error description.
@@ -0,0 +1,87 @@
+//! Room transcript export.
+//!
+//! [`render_transcript`] turns a room's decrypted history into text, and the
+//! [`FileSystem`] trait lets the [`crate::Runtime`] write it for
+//! [`crate::AppAction::ExportTranscript`] without touching the real disk in
+//! tests.
+//!
+//! Paths ending in `.jsonl` get one JSON object per message; anything else
+//! gets one plaintext line per message.
+
+use std::{fmt::Write as _, io, path::Path};
+
+use serde_json::json;
+
+use crate::RoomState;
+
+/// Writes exported files.
+pub trait FileSystem: Send {
+    /// Create or replace the file at `path` with `contents`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the underlying I/O error if the file cannot be written.
+    fn write(&mut self, path: &Path, contents: &[u8]) -> io::Result<()>;
+}
+
+/// [`FileSystem`] backed by [`std::fs`].
+///
+/// Used by [`crate::Runtime`] unless another file system is configured.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+}
+
+/// Layout of an exported transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    /// `<timestamp> <sender_id> <content>` per line.
+    Text,
+    /// A JSON object with `sender_id`, `timestamp` and `content` per line.
+    JsonLines,
+}
+
+impl TranscriptFormat {
+    /// Pick the format from the file extension.
+    pub fn for_path(path: &Path) -> Self {
+        if path.extension().is_some_and(|ext| ext == "jsonl") {
+            Self::JsonLines
+        } else {
+            Self::Text
+        }
+    }
+}
+
+/// Render `room`'s messages, oldest first.
+///
+/// Timestamps are milliseconds since the Unix epoch (`-` or `null` when
+/// unknown). Content that is not valid UTF-8 is converted lossily, and
+/// redacted messages are written as `[redacted]` (`null` in JSON).
+pub fn render_transcript(room: &RoomState, format: TranscriptFormat) -> String {
+    let mut out = String::new();
+    for message in &room.messages {
+        match format {
+            TranscriptFormat::Text => {
+                let timestamp =
+                    message.timestamp.map_or_else(|| "-".to_string(), |t| t.to_string());
+                let content =
+                    if message.redacted { "[redacted]".into() } else { message.content_str() };
+                let _ = writeln!(out, "{timestamp} <{}> {content}", message.sender_id);
+            },
+            TranscriptFormat::JsonLines => {
+                let content = (!message.redacted).then(|| message.content_str());
+                let line = json!({
+                    "sender_id": message.sender_id,
+                    "timestamp": message.timestamp,
+                    "content": content,
+                });
+                let _ = writeln!(out, "{line}");
+            },
+        }
+    }
+    out
+}
@@ -0,0 +1,160 @@
+//! Configurable key bindings.
+//!
+//! This module defines the [`KeyBindings`] map, which translates a pressed key
+//! plus modifiers into a semantic [`AppCommand`]. Frontends consult the map
+//! before falling back to text editing, so users can remap keys without
+//! touching input handling.
+
+use std::collections::HashMap;
+
+/// Key input events from the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyInput {
+    /// Character input.
+    Char(char),
+    /// Enter/Return key.
+    Enter,
+    /// Backspace key.
+    Backspace,
+    /// Delete key.
+    Delete,
+    /// Tab key.
+    Tab,
+    /// Escape key.
+    Esc,
+    /// Left arrow.
+    Left,
+    /// Right arrow.
+    Right,
+    /// Up arrow.
+    Up,
+    /// Down arrow.
+    Down,
+    /// Home key.
+    Home,
+    /// End key.
+    End,
+}
+
+/// Modifier keys held while a key was pressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct KeyModifiers {
+    /// Control key held.
+    pub ctrl: bool,
+    /// Alt/Option key held.
+    pub alt: bool,
+    /// Shift key held.
+    pub shift: bool,
+}
+
+impl KeyModifiers {
+    /// No modifiers held.
+    pub const NONE: Self = Self { ctrl: false, alt: false, shift: false };
+
+    /// Only Control held.
+    pub const CTRL: Self = Self { ctrl: true, alt: false, shift: false };
+
+    /// Only Alt held.
+    pub const ALT: Self = Self { ctrl: false, alt: true, shift: false };
+
+    /// Only Shift held.
+    pub const SHIFT: Self = Self { ctrl: false, alt: false, shift: true };
+}
+
+/// Semantic commands a key binding can trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppCommand {
+    /// Quit the application.
+    Quit,
+    /// Switch to the next room in sorted order, wrapping around.
+    CycleRoom,
+    /// Discard the contents of the input buffer.
+    ClearInput,
+}
+
+/// Map from key plus modifiers to [`AppCommand`].
+///
+/// Keys without a binding fall through to text editing. The default map
+/// reproduces the built-in behavior: Esc quits and Tab cycles rooms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    bindings: HashMap<(KeyInput, KeyModifiers), AppCommand>,
+}
+
+impl KeyBindings {
+    /// Create an empty map with no bindings.
+    pub fn empty() -> Self {
+        Self { bindings: HashMap::new() }
+    }
+
+    /// Bind `key` with `modifiers` to `command`, replacing any previous
+    /// binding for that combination.
+    pub fn bind(&mut self, key: KeyInput, modifiers: KeyModifiers, command: AppCommand) {
+        self.bindings.insert((key, modifiers), command);
+    }
+
+    /// Remove the binding for `key` with `modifiers`, returning the command it
+    /// was bound to. `None` if the combination was unbound.
+    pub fn unbind(&mut self, key: KeyInput, modifiers: KeyModifiers) -> Option<AppCommand> {
+        self.bindings.remove(&(key, modifiers))
+    }
+
+    /// Command bound to `key` with `modifiers`. `None` if unbound.
+    pub fn command_for(&self, key: KeyInput, modifiers: KeyModifiers) -> Option<AppCommand> {
+        self.bindings.get(&(key, modifiers)).copied()
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut bindings = Self::empty();
+        bindings.bind(KeyInput::Esc, KeyModifiers::NONE, AppCommand::Quit);
+        bindings.bind(KeyInput::Tab, KeyModifiers::NONE, AppCommand::CycleRoom);
+        bindings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_match_builtin_keys() {
+        let bindings = KeyBindings::default();
+
+        assert_eq!(bindings.command_for(KeyInput::Esc, KeyModifiers::NONE), Some(AppCommand::Quit));
+        assert_eq!(
+            bindings.command_for(KeyInput::Tab, KeyModifiers::NONE),
+            Some(AppCommand::CycleRoom)
+        );
+        assert_eq!(bindings.command_for(KeyInput::Char('q'), KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn modifiers_distinguish_bindings() {
+        let mut bindings = KeyBindings::empty();
+        bindings.bind(KeyInput::Char('q'), KeyModifiers::CTRL, AppCommand::Quit);
+
+        assert_eq!(
+            bindings.command_for(KeyInput::Char('q'), KeyModifiers::CTRL),
+            Some(AppCommand::Quit)
+        );
+        assert_eq!(bindings.command_for(KeyInput::Char('q'), KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn rebind_replaces_previous_command() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(KeyInput::Esc, KeyModifiers::NONE, AppCommand::ClearInput);
+
+        assert_eq!(
+            bindings.command_for(KeyInput::Esc, KeyModifiers::NONE),
+            Some(AppCommand::ClearInput)
+        );
+        assert_eq!(
+            bindings.unbind(KeyInput::Esc, KeyModifiers::NONE),
+            Some(AppCommand::ClearInput)
+        );
+        assert_eq!(bindings.command_for(KeyInput::Esc, KeyModifiers::NONE), None);
+    }
+}
@@ -9,13 +9,18 @@
 //! - [`App`]: Application state (rooms, connection, status)
 //! - [`Bridge`]: Protocol bridge (translates App actions to Client events)
 //! - [`Driver`]: Trait for platform-specific I/O abstraction
+//! - [`KeyBindings`]: Configurable map from keys to [`AppCommand`]s
 //! - [`Runtime`]: Generic orchestration loop using Driver
+//! - [`Notifier`]: Trait for platform-specific notifications
 
 mod action;
 mod app;
 mod bridge;
 mod driver;
 mod event;
+mod export;
+mod keybindings;
+mod notifier;
 mod runtime;
 mod state;
 
@@ -24,5 +29,8 @@ pub use app::App;
 pub use bridge::Bridge;
 pub use driver::Driver;
 pub use event::AppEvent;
+pub use export::{FileSystem, StdFileSystem, TranscriptFormat, render_transcript};
+pub use keybindings::{AppCommand, KeyBindings, KeyInput, KeyModifiers};
+pub use notifier::{LogNotifier, Notifier};
 pub use runtime::Runtime;
-pub use state::{ConnectionState, Message, RoomState};
+pub use state::{ConnectionState, DEFAULT_MAX_HISTORY, Message, RoomState, Search};
@@ -0,0 +1,23 @@
+//! Platform notification hook.
+//!
+//! The [`Notifier`] trait lets frontends surface [`crate::AppAction::Notify`]
+//! through a platform mechanism (desktop notifications, terminal bell, etc.).
+//! The [`crate::Runtime`] dispatches every `Notify` action to its notifier.
+
+/// Delivers user-facing notifications.
+pub trait Notifier: Send {
+    /// Show a notification with the given title and body.
+    fn notify(&mut self, title: &str, body: &str);
+}
+
+/// Notifier that only records notifications in the trace log.
+///
+/// Used by [`crate::Runtime`] when no platform notifier is configured.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&mut self, title: &str, body: &str) {
+        tracing::info!(%title, %body, "notification");
+    }
+}
@@ -4,13 +4,18 @@
 //! - [`App`]: UI state machine
 //! - [`Bridge`]: Protocol bridge to Client
 //! - [`Driver`]: Platform-specific I/O
+//! - [`Notifier`]: Platform-specific notifications
+//! - [`FileSystem`]: Where exported transcripts are written
 
-use std::{ops::Sub, time::Duration};
+use std::{ops::Sub, path::Path, time::Duration};
 
-use lockframe_core::env::Environment;
+use lockframe_core::{env::Environment, mls::RoomId};
 use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::session::Hello};
 
-use crate::{App, AppAction, AppEvent, Bridge, Driver};
+use crate::{
+    App, AppAction, AppEvent, Bridge, Driver, FileSystem, LogNotifier, Notifier, StdFileSystem,
+    TranscriptFormat, render_transcript,
+};
 
 /// Generic runtime that orchestrates App, Bridge, and Driver.
 ///
@@ -26,6 +31,8 @@ where
     driver: D,
     app: App,
     bridge: Bridge<E>,
+    notifier: Box<dyn Notifier>,
+    fs: Box<dyn FileSystem>,
     server_addr: String,
 }
 
@@ -39,7 +46,32 @@ where
     pub fn new(driver: D, env: E, sender_id: u64, server_addr: String) -> Self {
         let app = App::new(server_addr.clone());
         let bridge = Bridge::new(env, sender_id);
-        Self { driver, app, bridge, server_addr }
+        Self {
+            driver,
+            app,
+            bridge,
+            notifier: Box::new(LogNotifier),
+            fs: Box::new(StdFileSystem),
+            server_addr,
+        }
+    }
+
+    /// Replace the notifier used for [`AppAction::Notify`].
+    ///
+    /// Defaults to [`LogNotifier`].
+    #[must_use]
+    pub fn with_notifier(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifier = Box::new(notifier);
+        self
+    }
+
+    /// Replace the file system used for [`AppAction::ExportTranscript`].
+    ///
+    /// Defaults to [`StdFileSystem`].
+    #[must_use]
+    pub fn with_file_system(mut self, fs: impl FileSystem + 'static) -> Self {
+        self.fs = Box::new(fs);
+        self
     }
 
     /// Run the main event loop.
@@ -113,6 +145,11 @@ where
                 match action {
                     AppAction::Render => self.driver.render(&self.app)?,
                     AppAction::Quit => return Ok(true),
+                    AppAction::Notify { title, body } => self.notifier.notify(&title, &body),
+                    AppAction::ExportTranscript { room_id, path } => {
+                        self.export_transcript(room_id, &path);
+                        self.driver.render(&self.app)?;
+                    },
                     AppAction::Connect { server_addr: _ } => {
                         self.connect().await?;
                     },
@@ -180,6 +217,10 @@ where
                     }
                 },
                 AppAction::Quit => {},
+                AppAction::Notify { title, body } => self.notifier.notify(&title, &body),
+                AppAction::ExportTranscript { room_id, path } => {
+                    self.export_transcript(room_id, &path);
+                },
 
                 // Protocol actions shouldn't happen in sync contexts
                 AppAction::Connect { .. }
@@ -195,6 +236,27 @@ where
         }
     }
 
+    /// Write `room_id`'s transcript to `path` and report the outcome in the
+    /// status bar.
+    fn export_transcript(&mut self, room_id: RoomId, path: &Path) {
+        let Some(room) = self.app.rooms().get(&room_id) else {
+            self.app.set_status(format!("Export failed: not in room {room_id}"));
+            return;
+        };
+
+        let transcript = render_transcript(room, TranscriptFormat::for_path(path));
+        let count = room.messages.len();
+        match self.fs.write(path, transcript.as_bytes()) {
+            Ok(()) => {
+                self.app.set_status(format!("Exported {count} messages to {}", path.display()));
+            },
+            Err(e) => {
+                tracing::warn!("Failed to export transcript to {}: {}", path.display(), e);
+                self.app.set_status(format!("Export failed: {e}"));
+            },
+        }
+    }
+
     /// Process events from Bridge back to App.
     async fn process_bridge_events(&mut self, events: Vec<AppEvent>) -> Result<bool, D::Error> {
         for event in events {
@@ -257,3 +319,120 @@ where
         &mut self.app
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        convert::Infallible,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    use lockframe_core::env::test_utils::{MockEnv, VirtualInstant};
+
+    use super::*;
+
+    /// Driver that does nothing; these tests feed the runtime directly.
+    struct NullDriver;
+
+    impl Driver for NullDriver {
+        type Error = Infallible;
+        type Instant = VirtualInstant;
+
+        async fn poll_event(&mut self, _app: &mut App) -> Result<Vec<AppAction>, Self::Error> {
+            Ok(vec![])
+        }
+
+        async fn send_frame(&mut self, _frame: Frame) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn recv_frame(&mut self) -> Option<Frame> {
+            None
+        }
+
+        async fn connect(&mut self, _addr: &str) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            false
+        }
+
+        fn now(&self) -> Self::Instant {
+            MockEnv::new().now()
+        }
+
+        fn render(&mut self, _app: &App) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn stop(&mut self) {}
+    }
+
+    /// In-memory file system shared with the test.
+    #[derive(Clone, Default)]
+    struct FakeFs(Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>);
+
+    impl FileSystem for FakeFs {
+        fn write(&mut self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+            self.0.lock().unwrap().insert(path.to_path_buf(), contents.to_vec());
+            Ok(())
+        }
+    }
+
+    fn runtime_with_room(fs: FakeFs) -> Runtime<NullDriver, MockEnv> {
+        let mut runtime = Runtime::new(NullDriver, MockEnv::new(), 1, "localhost:4433".into())
+            .with_file_system(fs);
+        let app = runtime.app_mut();
+        let _ = app.handle(AppEvent::RoomJoined { room_id: 9 });
+        let _ = app.handle(AppEvent::MessageReceived {
+            room_id: 9,
+            sender_id: 2,
+            content: b"hi there".to_vec(),
+            log_index: Some(0),
+            timestamp: Some(1_000),
+        });
+        let _ = app.handle(AppEvent::MessageReceived {
+            room_id: 9,
+            sender_id: 3,
+            content: vec![0xff, b'!'],
+            log_index: Some(1),
+            timestamp: None,
+        });
+        runtime
+    }
+
+    #[test]
+    fn export_writes_plaintext_transcript() {
+        let fs = FakeFs::default();
+        let mut runtime = runtime_with_room(fs.clone());
+
+        let actions = runtime.app_mut().export_transcript("room.txt".into());
+        runtime.process_actions_sync(actions);
+
+        let files = fs.0.lock().unwrap();
+        let written = String::from_utf8(files[Path::new("room.txt")].clone()).unwrap();
+        assert_eq!(written, "1000 <2> hi there\n- <3> \u{fffd}!\n");
+        assert_eq!(runtime.app().status_message(), Some("Exported 2 messages to room.txt"));
+    }
+
+    #[test]
+    fn export_writes_json_lines_for_jsonl_path() {
+        let fs = FakeFs::default();
+        let mut runtime = runtime_with_room(fs.clone());
+
+        let actions = runtime.app_mut().export_transcript("room.jsonl".into());
+        runtime.process_actions_sync(actions);
+
+        let files = fs.0.lock().unwrap();
+        let written = String::from_utf8(files[Path::new("room.jsonl")].clone()).unwrap();
+        let lines: Vec<serde_json::Value> =
+            written.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines, [
+            serde_json::json!({ "sender_id": 2, "timestamp": 1_000, "content": "hi there" }),
+            serde_json::json!({ "sender_id": 3, "timestamp": null, "content": "\u{fffd}!" }),
+        ]);
+    }
+}
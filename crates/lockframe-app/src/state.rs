@@ -11,6 +11,9 @@ use std::collections::HashSet;
 
 use lockframe_core::mls::RoomId;
 
+/// Default number of messages a [`RoomState`] keeps.
+pub const DEFAULT_MAX_HISTORY: usize = 1_000;
+
 /// Connection state.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConnectionState {
@@ -36,19 +39,146 @@ pub struct RoomState {
     pub messages: Vec<Message>,
     /// Member IDs in this room.
     pub members: HashSet<u64>,
-    /// Room has unread messages.
-    pub unread: bool,
+    /// Room name set by an admin.
+    pub name: Option<String>,
+    /// Room topic set by an admin.
+    pub topic: Option<String>,
+    /// Number of messages received while the room was inactive.
+    pub unread_count: usize,
+    /// Most messages kept; older ones are evicted first.
+    pub max_history: usize,
+    /// Messages have been evicted to stay within `max_history`.
+    pub truncated: bool,
 }
 
 impl RoomState {
-    /// Create empty room state.
+    /// Create empty room state keeping [`DEFAULT_MAX_HISTORY`] messages.
     pub fn new(room_id: RoomId) -> Self {
-        Self { room_id, messages: Vec::new(), members: HashSet::new(), unread: false }
+        Self::with_max_history(room_id, DEFAULT_MAX_HISTORY)
+    }
+
+    /// Create empty room state keeping at most `max_history` messages.
+    pub fn with_max_history(room_id: RoomId, max_history: usize) -> Self {
+        Self {
+            room_id,
+            messages: Vec::new(),
+            members: HashSet::new(),
+            name: None,
+            topic: None,
+            unread_count: 0,
+            max_history,
+            truncated: false,
+        }
+    }
+
+    /// Add a message to this room, keeping sequenced messages in `log_index`
+    /// order.
+    ///
+    /// Messages without a log index (local echoes) are appended as-is. A
+    /// sequenced message is inserted after the last message that is either
+    /// unsequenced or has a lower or equal log index, so out-of-order delivery
+    /// still yields index order.
+    ///
+    /// Once the room holds more than `max_history` messages the oldest are
+    /// evicted and `truncated` is set. Eviction only happens at the front, so
+    /// positions counted from the newest message stay put; a sequenced message
+    /// older than everything kept is dropped straight away.
+    pub fn add_message(&mut self, message: Message) {
+        if let Some(log_index) = message.log_index {
+            let position = self
+                .messages
+                .iter()
+                .rposition(|m| m.log_index.is_none_or(|i| i <= log_index))
+                .map_or(0, |p| p.saturating_add(1));
+            self.messages.insert(position, message);
+        } else {
+            self.messages.push(message);
+        }
+        self.evict_overflow();
+    }
+
+    /// Indices of messages whose text contains `term`, ignoring case.
+    ///
+    /// Redacted messages and content that is not valid UTF-8 never match.
+    pub fn search(&self, term: &str) -> Vec<usize> {
+        let term = term.to_lowercase();
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| !m.redacted)
+            .filter_map(|(i, m)| std::str::from_utf8(&m.content).ok().map(|text| (i, text)))
+            .filter(|(_, text)| text.to_lowercase().contains(&term))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Drop the oldest messages beyond `max_history`.
+    fn evict_overflow(&mut self) {
+        let excess = self.messages.len().saturating_sub(self.max_history);
+        if excess > 0 {
+            self.messages.drain(..excess);
+            self.truncated = true;
+        }
+    }
+
+    /// Replace the content of the sequenced message at `log_index`.
+    ///
+    /// Only applies if the message was sent by `sender_id`. Returns `true` if
+    /// a message was updated.
+    pub fn edit_message(&mut self, log_index: u64, sender_id: u64, content: Vec<u8>) -> bool {
+        let Some(message) = self
+            .messages
+            .iter_mut()
+            .find(|m| m.log_index == Some(log_index) && m.sender_id == sender_id)
+        else {
+            return false;
+        };
+        message.content = content;
+        true
+    }
+
+    /// Replace the sequenced message at `log_index` with a tombstone.
+    ///
+    /// The message keeps its place, sender and timestamp; only the content is
+    /// dropped. Returns `true` if a message was redacted.
+    pub fn redact_message(&mut self, log_index: u64) -> bool {
+        let Some(message) = self.messages.iter_mut().find(|m| m.log_index == Some(log_index))
+        else {
+            return false;
+        };
+        message.content.clear();
+        message.redacted = true;
+        true
+    }
+
+    /// Remove the sequenced message at `log_index` from history.
+    ///
+    /// Unlike [`Self::redact_message`] no tombstone is left. Returns `true` if
+    /// a message was removed.
+    pub fn remove_message(&mut self, log_index: u64) -> bool {
+        let before = self.messages.len();
+        self.messages.retain(|m| m.log_index != Some(log_index));
+        self.messages.len() != before
     }
+}
+
+/// Search through one room's message history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+    /// Room being searched.
+    pub room_id: RoomId,
+    /// Text being searched for.
+    pub term: String,
+    /// Indices into the room's messages that match, oldest first. Never empty.
+    pub hits: Vec<usize>,
+    /// Position in `hits` of the selected match.
+    pub current: usize,
+}
 
-    /// Add a message to this room.
-    pub fn add_message(&mut self, sender_id: u64, content: Vec<u8>) {
-        self.messages.push(Message { sender_id, content });
+impl Search {
+    /// Index into the room's messages of the selected match.
+    pub fn current_hit(&self) -> Option<usize> {
+        self.hits.get(self.current).copied()
     }
 }
 
@@ -59,6 +189,12 @@ pub struct Message {
     pub sender_id: u64,
     /// Message content bytes.
     pub content: Vec<u8>,
+    /// Server-assigned log index. `None` for local messages not yet sequenced.
+    pub log_index: Option<u64>,
+    /// HLC timestamp in milliseconds since the Unix epoch. `None` if unknown.
+    pub timestamp: Option<u64>,
+    /// Content was removed by a redaction.
+    pub redacted: bool,
 }
 
 impl Message {
@@ -10,7 +10,10 @@
 use lockframe_app::{App, AppAction, AppEvent, Bridge};
 use lockframe_core::env::Environment;
 use lockframe_harness::SimEnv;
-use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::mls::GroupInfoPayload};
+use lockframe_proto::{
+    Frame, FrameHeader, Opcode, Payload,
+    payloads::mls::{GroupInfoPayload, KeyPackageFetchPayload},
+};
 
 /// Create a connected App ready for testing.
 fn connected_app(sender_id: u64) -> App {
@@ -38,7 +41,11 @@ fn process_actions<E: Environment>(
                     app.handle(event);
                 }
             },
-            AppAction::Render | AppAction::Quit | AppAction::Connect { .. } => {},
+            AppAction::Render
+            | AppAction::Quit
+            | AppAction::Notify { .. }
+            | AppAction::Connect { .. }
+            | AppAction::ExportTranscript { .. } => {},
         }
     }
 
@@ -205,6 +212,42 @@ fn set_active_room_switches_rooms() {
     assert_eq!(app.active_room(), Some(100), "After second set, active should be 100");
 }
 
+#[test]
+fn direct_message_to_offline_user_keeps_room() {
+    let env = SimEnv::with_seed(42);
+    let sender_id = 1;
+    let peer_id = 2;
+    let mut app = connected_app(sender_id);
+    let mut bridge: Bridge<SimEnv> = Bridge::new(env, sender_id);
+
+    let actions = app.direct_message(peer_id);
+    let frames = process_actions(&mut app, &mut bridge, actions);
+
+    // Oracle: DM room exists and a KeyPackage fetch for the peer was sent
+    assert_eq!(app.rooms().len(), 1, "DM room should exist");
+    let fetches = frames_by_opcode(&frames, Opcode::KeyPackageFetch);
+    assert_eq!(fetches.len(), 1, "Should fetch peer's KeyPackage");
+
+    // Peer is offline: server has no KeyPackage for them
+    let empty = KeyPackageFetchPayload {
+        user_id: peer_id,
+        key_package_bytes: Vec::new(),
+        hash_ref: Vec::new(),
+    };
+    let response = Payload::KeyPackageFetch(empty)
+        .into_frame(FrameHeader::new(Opcode::KeyPackageFetch))
+        .expect("Create frame");
+    receive_frame(&mut app, &mut bridge, response);
+
+    // Oracle: no error surfaced, room still usable
+    assert_eq!(app.rooms().len(), 1, "DM room should survive missing KeyPackage");
+    assert!(
+        !app.status_message().is_some_and(|m| m.starts_with("Error")),
+        "Missing KeyPackage should not be an error: {:?}",
+        app.status_message()
+    );
+}
+
 #[test]
 fn leave_removes_room() {
     let env = SimEnv::with_seed(42);
@@ -56,7 +56,11 @@ fn process_actions(app: &mut App, bridge: &mut Bridge<SimEnv>, actions: Vec<AppA
                     app.handle(event);
                 }
             },
-            AppAction::Render | AppAction::Quit | AppAction::Connect { .. } => {},
+            AppAction::Render
+            | AppAction::Quit
+            | AppAction::Notify { .. }
+            | AppAction::Connect { .. }
+            | AppAction::ExportTranscript { .. } => {},
         }
     }
 }
@@ -0,0 +1,238 @@
+//! Script runner over a live connection.
+
+use std::{io::Write, time::Duration};
+
+use lockframe_client::{
+    Client, ClientAction, ClientConfig, ClientError, ClientEvent, ClientIdentity, Transcript,
+    transport::{ConnectedClient, TransportError},
+};
+use lockframe_core::env::Environment;
+use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::session::Hello};
+use thiserror::Error;
+
+use crate::{Command, DEFAULT_TIMEOUT, parse_line};
+
+/// How often the client gets a `Tick` while waiting for frames.
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bot errors.
+#[derive(Debug, Error)]
+pub enum BotError {
+    /// A script line could not be parsed.
+    #[error("line {line}: {message}")]
+    Script {
+        /// 1-based line number.
+        line: usize,
+        /// What was wrong with it.
+        message: String,
+    },
+
+    /// The client rejected a command.
+    #[error("client error: {0}")]
+    Client(#[from] ClientError),
+
+    /// The connection failed.
+    #[error("transport error: {0}")]
+    Transport(#[from] TransportError),
+
+    /// The server closed the connection.
+    #[error("connection closed")]
+    Disconnected,
+
+    /// Nothing matching arrived in time.
+    #[error("timed out waiting for {0}")]
+    Timeout(String),
+}
+
+/// Headless client that runs script commands against a server.
+///
+/// Every event the client handles is written to the output as a JSON line,
+/// with message plaintext included (see [`Transcript`]).
+pub struct Bot<E: Environment> {
+    client: Client<E>,
+    env: E,
+    connection: ConnectedClient,
+}
+
+impl<E: Environment> Bot<E> {
+    /// Authenticate as `sender_id` over `connection` and start recording to
+    /// `output`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server does not answer the Hello.
+    pub async fn connect(
+        env: E,
+        sender_id: u64,
+        connection: ConnectedClient,
+        output: impl Write + Send + 'static,
+    ) -> Result<Self, BotError> {
+        let mut client =
+            Client::new(env.clone(), ClientIdentity::new(sender_id), ClientConfig::default());
+        client.set_transcript(Transcript::new(output).with_plaintext(true));
+        let mut bot = Self { client, env, connection };
+
+        let hello = Hello {
+            version: 1,
+            capabilities: Vec::new(),
+            sender_id: Some(sender_id),
+            auth_token: None,
+        };
+        let frame = Payload::Hello(hello)
+            .into_frame(FrameHeader::new(Opcode::Hello))
+            .map_err(|e| TransportError::Protocol(e.to_string()))?;
+        bot.send(frame).await?;
+        bot.await_frame(Opcode::HelloReply, DEFAULT_TIMEOUT).await?;
+        Ok(bot)
+    }
+
+    /// Run every command in `script`, stopping at the first failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first parse, client, connection or timeout error.
+    pub async fn run_script(&mut self, script: &str) -> Result<(), BotError> {
+        for (index, line) in script.lines().enumerate() {
+            let command = parse_line(line)
+                .map_err(|message| BotError::Script { line: index + 1, message })?;
+            if let Some(command) = command {
+                self.execute(command).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one command.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client rejects the command, the connection
+    /// fails, or an `expect` or `ping` times out.
+    pub async fn execute(&mut self, command: Command) -> Result<(), BotError> {
+        let event = match command {
+            Command::PublishKeyPackage => ClientEvent::PublishKeyPackage,
+            Command::CreateRoom { room_id } => ClientEvent::CreateRoom { room_id },
+            Command::JoinRoom { room_id } => ClientEvent::ExternalJoin { room_id },
+            Command::AddMember { room_id, user_id } => {
+                ClientEvent::FetchAndAddMember { room_id, user_id }
+            },
+            Command::Send { room_id, text } => {
+                ClientEvent::SendMessage { room_id, plaintext: text.into_bytes() }
+            },
+            Command::LeaveRoom { room_id } => ClientEvent::LeaveRoom { room_id },
+            Command::Ping => {
+                self.send(Frame::new(FrameHeader::new(Opcode::Ping), Vec::new())).await?;
+                return self.await_frame(Opcode::Pong, DEFAULT_TIMEOUT).await;
+            },
+            Command::Wait(duration) => {
+                self.pump(duration, |_, _| false).await?;
+                return Ok(());
+            },
+            Command::Expect { action, timeout } => {
+                let found = self
+                    .pump(timeout, |_, actions| actions.iter().any(|a| action_name(a) == action))
+                    .await?;
+                return if found { Ok(()) } else { Err(BotError::Timeout(action)) };
+            },
+        };
+
+        let actions = self.client.handle(event)?;
+        self.dispatch(&actions).await
+    }
+
+    /// Stop the connection.
+    pub fn stop(&self) {
+        self.connection.stop();
+    }
+
+    /// Process frames until one with `opcode` arrives.
+    async fn await_frame(&mut self, opcode: Opcode, timeout: Duration) -> Result<(), BotError> {
+        let found =
+            self.pump(timeout, |frame, _| frame.header.opcode_enum() == Some(opcode)).await?;
+        if found { Ok(()) } else { Err(BotError::Timeout(format!("{opcode:?}"))) }
+    }
+
+    /// Feed incoming frames and ticks to the client for up to `timeout`.
+    ///
+    /// Returns `true` as soon as `done` accepts a frame and the actions the
+    /// client produced for it. Client errors for individual frames are
+    /// recorded in the transcript and otherwise ignored.
+    async fn pump(
+        &mut self,
+        timeout: Duration,
+        mut done: impl FnMut(&Frame, &[ClientAction]) -> bool,
+    ) -> Result<bool, BotError> {
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        let mut ticks = tokio::time::interval(TICK_INTERVAL);
+
+        loop {
+            tokio::select! {
+                frame = self.connection.from_server.recv() => {
+                    let frame = frame.ok_or(BotError::Disconnected)?;
+                    let actions = match frame.header.opcode_enum() {
+                        Some(Opcode::HelloReply | Opcode::Pong) => Vec::new(),
+                        _ => self.client.handle(ClientEvent::FrameReceived(frame.clone())).unwrap_or_default(),
+                    };
+                    self.dispatch(&actions).await?;
+                    if done(&frame, &actions) {
+                        return Ok(true);
+                    }
+                },
+                Some(error) = self.connection.errors.recv() => return Err(error.into()),
+                _ = ticks.tick() => {
+                    let now = self.env.now();
+                    if let Ok(actions) = self.client.handle(ClientEvent::Tick { now }) {
+                        self.dispatch(&actions).await?;
+                    }
+                },
+                () = &mut deadline => return Ok(false),
+            }
+        }
+    }
+
+    /// Send the frames among `actions`.
+    async fn dispatch(&mut self, actions: &[ClientAction]) -> Result<(), BotError> {
+        for action in actions {
+            if let ClientAction::Send(frame) = action {
+                self.send(frame.clone()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(&mut self, frame: Frame) -> Result<(), BotError> {
+        self.connection.to_server.send(frame).await.map_err(|_| BotError::Disconnected)
+    }
+}
+
+/// `ClientAction` variant name, as matched by `expect`.
+fn action_name(action: &ClientAction) -> &'static str {
+    match action {
+        ClientAction::Send(_) => "Send",
+        ClientAction::DeliverMessage { .. } => "DeliverMessage",
+        ClientAction::MessageQueued { .. } => "MessageQueued",
+        ClientAction::MessageEdited { .. } => "MessageEdited",
+        ClientAction::MessageRedacted { .. } => "MessageRedacted",
+        ClientAction::MessageExpired { .. } => "MessageExpired",
+        ClientAction::RoomMetaChanged { .. } => "RoomMetaChanged",
+        ClientAction::TypingChanged { .. } => "TypingChanged",
+        ClientAction::RoomListReceived { .. } => "RoomListReceived",
+        ClientAction::RequestSync { .. } => "RequestSync",
+        ClientAction::SyncStalled { .. } => "SyncStalled",
+        ClientAction::CommitBuffered { .. } => "CommitBuffered",
+        ClientAction::PersistRoom(_) => "PersistRoom",
+        ClientAction::RoomRemoved { .. } => "RoomRemoved",
+        ClientAction::ServerError { .. } => "ServerError",
+        ClientAction::Disconnected { .. } => "Disconnected",
+        ClientAction::Log { .. } => "Log",
+        ClientAction::InviteeValidated { .. } => "InviteeValidated",
+        ClientAction::InviteeRejected { .. } => "InviteeRejected",
+        ClientAction::MemberAdded { .. } => "MemberAdded",
+        ClientAction::MemberRemoved { .. } => "MemberRemoved",
+        ClientAction::MembersChanged { .. } => "MembersChanged",
+        ClientAction::KeyPackagePublished => "KeyPackagePublished",
+        ClientAction::KeyPackageNeeded { .. } => "KeyPackageNeeded",
+        ClientAction::RoomJoined { .. } => "RoomJoined",
+    }
+}
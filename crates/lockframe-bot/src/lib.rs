@@ -0,0 +1,12 @@
+//! Headless Lockframe client for bots and load testing.
+//!
+//! A [`Bot`] drives the regular [`lockframe_client::Client`] state machine
+//! over a [`lockframe_client::transport::ConnectedClient`], running commands
+//! from a line-based script (see [`script`]) and printing every handled event
+//! and the actions it produced as JSON lines.
+
+mod bot;
+pub mod script;
+
+pub use bot::{Bot, BotError};
+pub use script::{Command, DEFAULT_TIMEOUT, parse_line};
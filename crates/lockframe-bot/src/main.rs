@@ -0,0 +1,81 @@
+//! Lockframe bot entry point.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use lockframe_bot::Bot;
+use lockframe_client::transport::{self, TlsMode, TransportConfig};
+use lockframe_core::{env::Environment, system_env::SystemEnv};
+use tokio::io::AsyncReadExt;
+
+/// Headless Lockframe client
+#[derive(Parser, Debug)]
+#[command(name = "lockframe-bot")]
+#[command(about = "Run a command script against a Lockframe server, printing JSON actions")]
+#[command(version)]
+struct Args {
+    /// Server socket address, or a `ws://`/`wss://` URL
+    #[arg(short, long, default_value = "127.0.0.1:4433")]
+    server: String,
+
+    /// Server name for TLS SNI and certificate verification
+    #[arg(long, default_value = "localhost")]
+    server_name: String,
+
+    /// Trust only the server certificate with this SHA-256 fingerprint (hex)
+    #[arg(long, value_parser = parse_fingerprint)]
+    cert_fingerprint: Option<[u8; 32]>,
+
+    /// Accept any server certificate (development only)
+    #[arg(long)]
+    insecure: bool,
+
+    /// Sender ID to authenticate as (random if omitted)
+    #[arg(long)]
+    sender_id: Option<u64>,
+
+    /// Script file to run (reads stdin if omitted)
+    script: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let script = if let Some(path) = &args.script {
+        tokio::fs::read_to_string(path).await?
+    } else {
+        let mut script = String::new();
+        tokio::io::stdin().read_to_string(&mut script).await?;
+        script
+    };
+
+    let env = SystemEnv::new();
+    let sender_id = args.sender_id.unwrap_or_else(|| Environment::random_u64(&env));
+    let config = TransportConfig {
+        tls_mode: if args.insecure { TlsMode::Insecure } else { TlsMode::Secure },
+        server_name: args.server_name,
+        expected_cert_fingerprint: args.cert_fingerprint,
+        ..TransportConfig::default()
+    };
+    let connection = transport::connect_with_config(&args.server, config).await?;
+    let mut bot = Bot::connect(env, sender_id, connection, std::io::stdout()).await?;
+
+    let result = bot.run_script(&script).await;
+    bot.stop();
+    Ok(result?)
+}
+
+/// Parse a SHA-256 fingerprint written as 64 hex digits.
+fn parse_fingerprint(hex: &str) -> Result<[u8; 32], String> {
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err("expected 64 hex digits".to_string());
+    }
+
+    let mut fingerprint = [0u8; 32];
+    for (byte, pair) in fingerprint.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|e| e.to_string())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|e| format!("invalid hex {pair:?}: {e}"))?;
+    }
+    Ok(fingerprint)
+}
@@ -0,0 +1,178 @@
+//! Bot script parsing.
+//!
+//! A script is one command per line. Blank lines and lines starting with `#`
+//! are skipped. Room and user IDs may be decimal or `0x`-prefixed hex.
+//!
+//! ```text
+//! publish                          # publish a KeyPackage
+//! create <room_id>                 # create a room
+//! join <room_id>                   # join a room via external commit
+//! add <room_id> <user_id>          # fetch a KeyPackage and add the user
+//! send <room_id> <text...>         # send the rest of the line
+//! leave <room_id>                  # leave a room
+//! ping                             # wait until the server has handled
+//!                                  # everything sent so far
+//! wait <ms>                        # keep processing frames for a while
+//! expect <action> [timeout_ms]     # wait for a ClientAction by variant name
+//! ```
+
+use std::time::Duration;
+
+use lockframe_core::mls::RoomId;
+use lockframe_proto::ids;
+
+/// How long `expect` and `ping` wait when the script gives no timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One script command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Publish a `KeyPackage` to the server registry.
+    PublishKeyPackage,
+
+    /// Create a new room.
+    CreateRoom {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+    },
+
+    /// Join an existing room via external commit.
+    JoinRoom {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+    },
+
+    /// Fetch a user's `KeyPackage` and add them to a room.
+    AddMember {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+        /// User ID to add.
+        user_id: u64,
+    },
+
+    /// Send a message.
+    Send {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+        /// Message text.
+        text: String,
+    },
+
+    /// Leave a room.
+    LeaveRoom {
+        /// 128-bit room UUID.
+        room_id: RoomId,
+    },
+
+    /// Round-trip a Ping, so every earlier frame has been handled.
+    Ping,
+
+    /// Keep processing incoming frames for a while.
+    Wait(Duration),
+
+    /// Process incoming frames until the client produces an action.
+    Expect {
+        /// `ClientAction` variant name, e.g. `DeliverMessage`.
+        action: String,
+        /// How long to wait before failing.
+        timeout: Duration,
+    },
+}
+
+/// Parse one script line. `Ok(None)` for blank lines and comments.
+///
+/// # Errors
+///
+/// Returns a description of the problem for unknown commands or bad
+/// arguments.
+pub fn parse_line(line: &str) -> Result<Option<Command>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let args: Vec<&str> = rest.split_whitespace().collect();
+
+    let command = match command {
+        "publish" => Command::PublishKeyPackage,
+        "create" => Command::CreateRoom { room_id: room_arg(&args, "create <room_id>")? },
+        "join" => Command::JoinRoom { room_id: room_arg(&args, "join <room_id>")? },
+        "add" => {
+            let usage = "add <room_id> <user_id>";
+            let user_id = args
+                .get(1)
+                .ok_or_else(|| format!("usage: {usage}"))?
+                .parse::<ids::MemberId>()
+                .map_err(|e| e.to_string())?
+                .get();
+            Command::AddMember { room_id: room_arg(&args, usage)?, user_id }
+        },
+        "send" => {
+            let room_id = room_arg(&args, "send <room_id> <text>")?;
+            let text = rest.trim_start().split_once(char::is_whitespace).map_or("", |(_, t)| t);
+            Command::Send { room_id, text: text.trim().to_string() }
+        },
+        "leave" => Command::LeaveRoom { room_id: room_arg(&args, "leave <room_id>")? },
+        "ping" => Command::Ping,
+        "wait" => {
+            let ms = args.first().ok_or("usage: wait <ms>")?;
+            Command::Wait(millis(ms)?)
+        },
+        "expect" => {
+            let action = args.first().ok_or("usage: expect <action> [timeout_ms]")?;
+            let timeout = args.get(1).map_or(Ok(DEFAULT_TIMEOUT), |ms| millis(ms))?;
+            Command::Expect { action: (*action).to_string(), timeout }
+        },
+        other => return Err(format!("unknown command: {other}")),
+    };
+    Ok(Some(command))
+}
+
+fn room_arg(args: &[&str], usage: &str) -> Result<RoomId, String> {
+    let id = args.first().ok_or_else(|| format!("usage: {usage}"))?;
+    id.parse::<ids::RoomId>().map(ids::RoomId::get).map_err(|e| e.to_string())
+}
+
+fn millis(arg: &str) -> Result<Duration, String> {
+    arg.parse().map(Duration::from_millis).map_err(|e| format!("invalid milliseconds: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_line("publish"), Ok(Some(Command::PublishKeyPackage)));
+        assert_eq!(
+            parse_line("add 0x10 2"),
+            Ok(Some(Command::AddMember { room_id: 16, user_id: 2 }))
+        );
+        assert_eq!(
+            parse_line("send 16  hello  world "),
+            Ok(Some(Command::Send { room_id: 16, text: "hello  world".into() }))
+        );
+        assert_eq!(
+            parse_line("expect DeliverMessage 250"),
+            Ok(Some(Command::Expect {
+                action: "DeliverMessage".into(),
+                timeout: Duration::from_millis(250)
+            }))
+        );
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comments() {
+        assert_eq!(parse_line("   "), Ok(None));
+        assert_eq!(parse_line("# create 1"), Ok(None));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse_line("create").is_err());
+        assert!(parse_line("add 1 bob").is_err());
+        assert!(parse_line("wait soon").is_err());
+        assert!(parse_line("dance").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Integration tests running bot scripts against a real server.
+
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use lockframe_bot::Bot;
+use lockframe_client::transport::{self, ConnectedClient, TransportConfig};
+use lockframe_server::{DriverConfig, Server, ServerRuntimeConfig, SystemEnv};
+use serde_json::Value;
+
+/// Writer whose bytes stay readable after the bot takes ownership.
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    #[allow(clippy::expect_used)]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("buffer lock").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuf {
+    /// Every action recorded so far.
+    #[allow(clippy::expect_used)]
+    fn actions(&self) -> Vec<Value> {
+        let bytes = self.0.lock().expect("buffer lock").clone();
+        String::from_utf8(bytes)
+            .expect("transcript is UTF-8")
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("transcript line is JSON"))
+            .filter_map(|line| line.get("actions").and_then(Value::as_array).cloned())
+            .flatten()
+            .collect()
+    }
+}
+
+/// Start a real server, spawn its run loop, and return the address.
+#[allow(clippy::expect_used)]
+fn start_server() -> String {
+    let config = ServerRuntimeConfig {
+        bind_address: "127.0.0.1:0".to_string(),
+        cert_path: None,
+        key_path: None,
+        driver: DriverConfig::default(),
+        enable_0rtt: false,
+        ws_bind_address: None,
+    };
+    let server = Server::bind(config).expect("valid server config");
+    let addr = server.local_addr().expect("underlying socket").to_string();
+    tokio::spawn(async move {
+        let _ = server.run().await;
+    });
+    addr
+}
+
+/// Connect with retry while the server starts.
+#[allow(clippy::expect_used)]
+async fn connect(addr: &str) -> ConnectedClient {
+    let config = TransportConfig {
+        connect_timeout: Duration::from_millis(100),
+        ..TransportConfig::development()
+    };
+    for _ in 0..19 {
+        if let Ok(connection) = transport::connect_with_config(addr, config.clone()).await {
+            return connection;
+        }
+        tokio::task::yield_now().await;
+    }
+    transport::connect_with_config(addr, config).await.expect("server should accept")
+}
+
+/// Publish a `KeyPackage` and wait until the server has stored it.
+const BOB_SETUP: &str = "\
+publish
+ping
+";
+
+/// The Welcome persists the room at epoch 1, then Alice's message arrives.
+const BOB_RECEIVE: &str = "\
+expect PersistRoom
+expect DeliverMessage
+";
+
+/// Add Bob, wait for the commit's echo, then send at the new epoch.
+const ALICE_SEND: &str = "\
+create 0x10
+add 0x10 2
+expect MemberAdded
+expect PersistRoom
+send 0x10 hello from a script
+";
+
+#[tokio::test]
+async fn scripted_message_round_trips_between_bots() {
+    let addr = start_server();
+    let bob_out = SharedBuf::default();
+    let mut alice = Bot::connect(SystemEnv::new(), 1, connect(&addr).await, SharedBuf::default())
+        .await
+        .unwrap();
+    let mut bob =
+        Bot::connect(SystemEnv::new(), 2, connect(&addr).await, bob_out.clone()).await.unwrap();
+
+    bob.run_script(BOB_SETUP).await.unwrap();
+    let bob_task = tokio::spawn(async move { bob.run_script(BOB_RECEIVE).await });
+    alice.run_script(ALICE_SEND).await.unwrap();
+    bob_task.await.unwrap().unwrap();
+
+    let delivered: Vec<Value> =
+        bob_out.actions().into_iter().filter(|a| a["type"] == "DeliverMessage").collect();
+    assert_eq!(delivered.len(), 1);
+    assert_eq!(delivered[0]["plaintext"], "hello from a script");
+    assert_eq!(delivered[0]["sender_id"], 1);
+    assert_eq!(delivered[0]["room_id"], format!("{:032x}", 0x10));
+}
+
+#[tokio::test]
+async fn expect_times_out_with_named_action() {
+    let addr = start_server();
+    let mut bot = Bot::connect(SystemEnv::new(), 3, connect(&addr).await, SharedBuf::default())
+        .await
+        .unwrap();
+
+    let error = bot.run_script("expect DeliverMessage 50").await.unwrap_err();
+    assert_eq!(error.to_string(), "timed out waiting for DeliverMessage");
+}
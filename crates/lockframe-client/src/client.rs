@@ -4,25 +4,32 @@
 //! memberships and orchestrates MLS operations with sender key encryption.
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     slice,
     time::Duration,
 };
 
 use lockframe_core::{
     env::Environment,
+    message_ids::RecentMessageIds,
     mls::{MlsAction, MlsGroup, PendingJoinState, RoomId},
 };
 use lockframe_crypto::{EncryptedMessage as CryptoEncryptedMessage, NONCE_RANDOM_SIZE};
 use lockframe_proto::{
     Frame, FrameHeader, Opcode, Payload,
     payloads::{
-        app::EncryptedMessage,
+        ErrorPayload,
+        app::{Edit, EncryptedMessage, Typing},
         mls::{GroupInfoPayload, KeyPackageFetchPayload, KeyPackagePublishRequest},
-        session::SyncResponse,
+        moderation::{Redact, RoomMeta},
+        session::{RoomListRequest, SyncResponse},
     },
 };
 
+#[cfg(feature = "key-export")]
+use crate::room_keys::{ImportedRoom, RoomKeyBundle};
+#[cfg(feature = "transcript")]
+use crate::transcript::Transcript;
 use crate::{
     error::ClientError,
     event::{ClientAction, ClientEvent, RoomStateSnapshot},
@@ -38,11 +45,10 @@ const SENDER_KEY_CONTEXT: &[u8] = b"";
 /// Size of the sender key secret in bytes.
 const SENDER_KEY_SECRET_SIZE: usize = 32;
 
-/// Timeout for pending commits before requesting sync (30 seconds).
-const COMMIT_TIMEOUT: Duration = Duration::from_secs(30);
-
-/// Timeout for pending `KeyPackage` fetch operations (1 minute).
-const KEY_PACKAGE_FETCH_TIMEOUT: Duration = Duration::from_mins(1);
+/// Maximum `SyncResponse` pages in one sync before giving up.
+///
+/// Bounds the sync loop against a server that always reports `has_more`.
+const MAX_SYNC_PAGES: u32 = 1_000;
 
 /// Client identity.
 ///
@@ -73,6 +79,163 @@ struct RoomState<E: Environment> {
 
     /// Our leaf index in the MLS tree.
     my_leaf_index: u32,
+
+    /// Message IDs already delivered, so retried sends are delivered once.
+    delivered_message_ids: RecentMessageIds,
+
+    /// Delivered messages that expire, as `(expires_at, log_index)`.
+    expiring: BTreeSet<(u64, u64)>,
+
+    /// Commits from future epochs, keyed by the epoch they apply to, with
+    /// the time they were buffered.
+    buffered_commits: BTreeMap<u64, (Frame, E::Instant)>,
+
+    /// `GroupInfo` exported for an epoch, reused until the next commit.
+    group_info: Option<(u64, Vec<u8>)>,
+
+    /// `(epoch, tree_hash)` for every epoch this room passed through.
+    #[cfg(feature = "epoch-history")]
+    epoch_history: Vec<(u64, [u8; 32])>,
+}
+
+impl<E: Environment> RoomState<E> {
+    /// Drop buffered commits that waited longer than `ttl` and request the
+    /// gap up to the newest of them again.
+    fn expire_buffered_commits(
+        &mut self,
+        room_id: RoomId,
+        now: E::Instant,
+        ttl: Duration,
+    ) -> Vec<ClientAction> {
+        let mut newest_expired = None;
+        self.buffered_commits.retain(|&epoch, (_, buffered_at)| {
+            let stale = now - *buffered_at > ttl;
+            if stale {
+                newest_expired = newest_expired.max(Some(epoch));
+            }
+            !stale
+        });
+
+        let Some(to_epoch) = newest_expired else {
+            return Vec::new();
+        };
+        let from_epoch = self.mls_group.epoch();
+        vec![
+            ClientAction::Log {
+                message: format!(
+                    "Buffered commits in room {room_id:x} expired waiting for epoch {from_epoch}, resyncing"
+                ),
+            },
+            ClientAction::RequestSync { room_id, from_epoch, to_epoch },
+        ]
+    }
+
+    fn new(
+        mls_group: MlsGroup<E>,
+        sender_keys: SenderKeyStore,
+        my_leaf_index: u32,
+        message_id_history: usize,
+    ) -> Self {
+        let room = Self {
+            mls_group,
+            sender_keys,
+            my_leaf_index,
+            delivered_message_ids: RecentMessageIds::with_capacity(message_id_history),
+            expiring: BTreeSet::new(),
+            buffered_commits: BTreeMap::new(),
+            group_info: None,
+            #[cfg(feature = "epoch-history")]
+            epoch_history: Vec::new(),
+        };
+        #[cfg(feature = "epoch-history")]
+        let room = {
+            let mut room = room;
+            room.record_epoch();
+            room
+        };
+        room
+    }
+
+    /// Member IDs currently in the MLS group.
+    fn member_set(&self) -> BTreeSet<u64> {
+        self.mls_group
+            .member_leaf_indices()
+            .into_iter()
+            .filter_map(|leaf| self.mls_group.member_id_by_leaf_index(leaf))
+            .collect()
+    }
+
+    /// Append the current epoch and tree hash to the history.
+    #[cfg(feature = "epoch-history")]
+    fn record_epoch(&mut self) {
+        if let Ok(state) = self.mls_group.export_group_state() {
+            self.epoch_history.push((self.mls_group.epoch(), state.tree_hash));
+        }
+    }
+}
+
+/// Progress of a multi-page sync for one room.
+#[derive(Debug, Default, Clone, Copy)]
+struct SyncProgress {
+    /// Pages received since the sync started.
+    pages: u32,
+    /// Highest log index received so far.
+    last_log_index: Option<u64>,
+}
+
+/// Client tuning knobs.
+///
+/// Defaults suit low-latency deployments; high-latency or embedded
+/// deployments can lengthen the timeouts, and memory-constrained ones can
+/// shrink the limits on pending state.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// How long a sent commit may stay unconfirmed before it is dropped and a
+    /// sync requested
+    pub commit_timeout: Duration,
+    /// How long a `KeyPackage` fetch for an add may stay unanswered before the
+    /// pending add is dropped
+    pub key_package_fetch_timeout: Duration,
+    /// How long a `GroupInfo` request for an external join may stay
+    /// unanswered before the pending join is dropped
+    pub group_info_fetch_timeout: Duration,
+    /// Generated `KeyPackage`s kept awaiting a Welcome; the oldest is evicted
+    /// when a new one would exceed this
+    pub max_pending_joins: usize,
+    /// Outstanding `KeyPackage` fetches for adds; further adds are rejected
+    pub max_pending_adds: usize,
+    /// Outstanding `GroupInfo` requests for external joins; further joins are
+    /// rejected
+    pub max_pending_external_joins: usize,
+    /// Delivered message IDs remembered per room for duplicate suppression;
+    /// the oldest is forgotten first
+    pub message_id_history: usize,
+    /// How far a frame's HLC timestamp may run ahead of our wall clock before
+    /// the frame is rejected
+    pub max_skew: Duration,
+    /// Commits from future epochs held per room until the missing ones
+    /// arrive; further ones are dropped and left to sync
+    pub max_buffered_commits: usize,
+    /// How long a buffered commit waits for the epochs before it before it
+    /// is dropped and the gap is synced again
+    pub buffered_commit_ttl: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            commit_timeout: Duration::from_secs(30),
+            key_package_fetch_timeout: Duration::from_mins(1),
+            group_info_fetch_timeout: Duration::from_mins(1),
+            max_pending_joins: 64,
+            max_pending_adds: 64,
+            max_pending_external_joins: 16,
+            message_id_history: 4096,
+            max_skew: Duration::from_mins(5),
+            max_buffered_commits: 16,
+            buffered_commit_ttl: Duration::from_mins(1),
+        }
+    }
 }
 
 /// State stored between `KeyPackage` generation and Welcome receipt.
@@ -86,6 +249,9 @@ pub struct Client<E: Environment> {
     /// Client identity.
     identity: ClientIdentity,
 
+    /// Timeouts and other tuning.
+    config: ClientConfig,
+
     /// Active room memberships.
     rooms: HashMap<RoomId, RoomState<E>>,
 
@@ -93,27 +259,59 @@ pub struct Client<E: Environment> {
     /// Maps `KeyPackage` hash to pending state.
     pending_joins: HashMap<Vec<u8>, PendingJoin<E>>,
 
+    /// `KeyPackage` hashes in `pending_joins`, oldest first.
+    pending_join_order: VecDeque<Vec<u8>>,
+
     /// Pending add member operations.
     /// Maps (`room_id`, `user_id`) to timestamp for completing the add.
     pending_adds: HashMap<(RoomId, u64), E::Instant>,
 
     /// Pending external joins awaiting `GroupInfo` responses.
-    pending_external_joins: HashSet<RoomId>,
+    /// Maps `room_id` to when the `GroupInfo` was requested.
+    pending_external_joins: HashMap<RoomId, E::Instant>,
+
+    /// Rooms with a multi-page sync in progress.
+    sync_progress: HashMap<RoomId, SyncProgress>,
+
+    /// `request_id` of the last frame sent. The server echoes it on errors.
+    last_request_id: u32,
+
+    /// Debug transcript of every handled event.
+    #[cfg(feature = "transcript")]
+    transcript: Option<Transcript>,
+
+    /// Rooms readable through imported keys, without membership.
+    #[cfg(feature = "key-export")]
+    imported_rooms: HashMap<RoomId, ImportedRoom>,
 }
 
 impl<E: Environment> Client<E> {
-    /// Create a new client with the given identity.
-    pub fn new(env: E, identity: ClientIdentity) -> Self {
+    /// Create a new client with the given identity and configuration.
+    pub fn new(env: E, identity: ClientIdentity, config: ClientConfig) -> Self {
         Self {
             env,
             identity,
+            config,
             rooms: HashMap::new(),
             pending_joins: HashMap::new(),
+            pending_join_order: VecDeque::new(),
             pending_adds: HashMap::new(),
-            pending_external_joins: HashSet::new(),
+            pending_external_joins: HashMap::new(),
+            sync_progress: HashMap::new(),
+            last_request_id: 0,
+            #[cfg(feature = "transcript")]
+            transcript: None,
+            #[cfg(feature = "key-export")]
+            imported_rooms: HashMap::new(),
         }
     }
 
+    /// Record every subsequent [`Self::handle`] call to `transcript`.
+    #[cfg(feature = "transcript")]
+    pub fn set_transcript(&mut self, transcript: Transcript) {
+        self.transcript = Some(transcript);
+    }
+
     /// Client's stable sender ID used in frame headers.
     pub fn sender_id(&self) -> u64 {
         self.identity.sender_id
@@ -145,6 +343,16 @@ impl<E: Environment> Client<E> {
             .map(|state| state.tree_hash)
     }
 
+    /// Epochs this client saw for a room, oldest first, as
+    /// `(epoch, tree_hash)`. `None` if not a member.
+    ///
+    /// Starts at the epoch the client created or joined the room in and gains
+    /// an entry per applied commit. For debugging convergence issues.
+    #[cfg(feature = "epoch-history")]
+    pub fn epoch_history(&self, room_id: RoomId) -> Option<&[(u64, [u8; 32])]> {
+        self.rooms.get(&room_id).map(|r| r.epoch_history.as_slice())
+    }
+
     /// Member IDs in a room. `None` if not a member or export fails.
     ///
     /// Returns all member IDs (`sender_ids`) currently in the MLS group.
@@ -155,19 +363,109 @@ impl<E: Environment> Client<E> {
             .map(|state| state.members)
     }
 
+    /// Number of members in a room. `None` if not a member.
+    ///
+    /// Cheaper than [`Self::member_ids`] when only the size is needed.
+    pub fn member_count(&self, room_id: RoomId) -> Option<usize> {
+        self.rooms.get(&room_id).map(|r| r.mls_group.member_count())
+    }
+
+    /// Leaf index of each member in a room's ratchet tree, keyed by member
+    /// ID. `None` if not a member.
+    ///
+    /// Sender keys are derived per leaf, so every member must agree on this
+    /// mapping to decrypt each other's messages.
+    pub fn member_leaf_indices(&self, room_id: RoomId) -> Option<BTreeMap<u64, u32>> {
+        let group = &self.rooms.get(&room_id)?.mls_group;
+        let leaves = group
+            .member_leaf_indices()
+            .into_iter()
+            .filter_map(|leaf| group.member_id_by_leaf_index(leaf).map(|id| (id, leaf)))
+            .collect();
+        Some(leaves)
+    }
+
+    /// Signed `GroupInfo` for a room's current epoch, as external joiners
+    /// need it. `None` if not a member or the export fails.
+    ///
+    /// Exported lazily and at most once per epoch: the bytes are cached until
+    /// the group's epoch changes.
+    pub fn group_info(&mut self, room_id: RoomId) -> Option<&[u8]> {
+        let room = self.rooms.get_mut(&room_id)?;
+        let epoch = room.mls_group.epoch();
+        if room.group_info.as_ref().is_none_or(|(cached, _)| *cached != epoch) {
+            let bytes = room.mls_group.export_group_info().ok()?;
+            room.group_info = Some((epoch, bytes));
+        }
+        room.group_info.as_ref().map(|(_, bytes)| bytes.as_slice())
+    }
+
+    /// Export the keys that decrypt a room's messages at its current epoch.
+    /// `None` if not a member or the MLS export fails.
+    ///
+    /// The bundle lets a client that is not in the room read it, e.g. a
+    /// bridge to another protocol. It holds the epoch's sender key secret:
+    /// see [`RoomKeyBundle`] before handing it to anyone.
+    #[cfg(feature = "key-export")]
+    pub fn export_room_keys(&self, room_id: RoomId) -> Option<RoomKeyBundle> {
+        let group = &self.rooms.get(&room_id)?.mls_group;
+        let sender_key_secret = group
+            .export_secret(SENDER_KEY_LABEL, SENDER_KEY_CONTEXT, SENDER_KEY_SECRET_SIZE)
+            .ok()?;
+
+        Some(RoomKeyBundle {
+            room_id,
+            epoch: group.epoch(),
+            sender_key_secret,
+            members: self.member_leaf_indices(room_id)?,
+            group_state: group.export_group_state().ok()?,
+        })
+    }
+
+    /// Decrypt a room's messages with keys exported by a member.
+    ///
+    /// Application messages for the room at the bundle's epoch are then
+    /// verified and delivered as if this client were a member, but it can't
+    /// send to the room and frames from other epochs are rejected. Replaces
+    /// keys imported earlier for the same room.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::RoomAlreadyExists`] if this client is already a
+    /// member of the room.
+    #[cfg(feature = "key-export")]
+    pub fn import_room_keys(&mut self, bundle: RoomKeyBundle) -> Result<(), ClientError> {
+        let room_id = bundle.room_id;
+        if self.rooms.contains_key(&room_id) {
+            return Err(ClientError::RoomAlreadyExists { room_id });
+        }
+        self.imported_rooms.insert(room_id, ImportedRoom::new(bundle));
+        Ok(())
+    }
+
     /// Generate a `KeyPackage` for this client to join a room.
     ///
     /// The returned `KeyPackage` should be sent to the room creator who will
     /// add this client via `AddMembers`. The client stores the cryptographic
     /// state internally and uses it when the Welcome message arrives.
     ///
+    /// At most `max_pending_joins` states are kept; generating another evicts
+    /// the oldest, so a Welcome for that `KeyPackage` can no longer be used.
+    ///
     /// Returns (serialized `KeyPackage` bytes, `KeyPackage` hash ref).
     pub fn generate_key_package(&mut self) -> Result<(Vec<u8>, Vec<u8>), ClientError> {
         let (kp_bytes, hash_ref, pending_state) =
             MlsGroup::generate_key_package(self.env.clone(), self.identity.sender_id)
                 .map_err(|e| ClientError::Mls { reason: e.to_string() })?;
 
-        self.pending_joins.insert(hash_ref.clone(), pending_state);
+        if self.pending_joins.insert(hash_ref.clone(), pending_state).is_none() {
+            self.pending_join_order.push_back(hash_ref.clone());
+        }
+        while self.pending_joins.len() > self.config.max_pending_joins
+            && let Some(oldest) = self.pending_join_order.pop_front()
+        {
+            self.pending_joins.remove(&oldest);
+        }
 
         Ok((kp_bytes, hash_ref))
     }
@@ -176,11 +474,63 @@ impl<E: Environment> Client<E> {
     pub fn handle(
         &mut self,
         event: ClientEvent<E::Instant>,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        #[cfg(feature = "transcript")]
+        if let Some(event_json) = self.transcript.as_ref().map(|t| t.event_json(&event)) {
+            let result = self.handle_event(event).map(|actions| self.with_request_ids(actions));
+            if let Some(transcript) = &mut self.transcript {
+                transcript.record(&event_json, &result);
+            }
+            return result;
+        }
+
+        self.handle_event(event).map(|actions| self.with_request_ids(actions))
+    }
+
+    /// Give every outgoing frame without one a `request_id`.
+    ///
+    /// Signed frames get theirs in [`Self::app_frame`], since the signature
+    /// covers it.
+    fn with_request_ids(&mut self, mut actions: Vec<ClientAction>) -> Vec<ClientAction> {
+        for action in &mut actions {
+            if let ClientAction::Send(frame) = action
+                && frame.header.request_id() == 0
+            {
+                frame.header.set_request_id(self.next_request_id());
+            }
+        }
+        actions
+    }
+
+    /// Next `request_id`, skipping 0, which means "none".
+    fn next_request_id(&mut self) -> u32 {
+        self.last_request_id = self.last_request_id.checked_add(1).unwrap_or(1);
+        self.last_request_id
+    }
+
+    fn handle_event(
+        &mut self,
+        event: ClientEvent<E::Instant>,
     ) -> Result<Vec<ClientAction>, ClientError> {
         match event {
             ClientEvent::CreateRoom { room_id } => self.handle_create_room(room_id),
+            ClientEvent::SetTyping { room_id, is_typing } => {
+                self.handle_set_typing(room_id, is_typing)
+            },
+            ClientEvent::EditMessage { room_id, target_log_index, plaintext } => {
+                self.handle_edit_message(room_id, target_log_index, &plaintext)
+            },
+            ClientEvent::RedactMessage { room_id, target_log_index, reason } => {
+                self.handle_redact_message(room_id, target_log_index, reason)
+            },
+            ClientEvent::SetRoomMeta { room_id, name, topic } => {
+                self.handle_set_room_meta(room_id, name, topic)
+            },
             ClientEvent::SendMessage { room_id, plaintext } => {
-                self.handle_send_message(room_id, &plaintext)
+                self.handle_send_message(room_id, &plaintext, None)
+            },
+            ClientEvent::SendExpiringMessage { room_id, plaintext, expires_at } => {
+                self.handle_send_message(room_id, &plaintext, Some(expires_at))
             },
             ClientEvent::FrameReceived(frame) => self.handle_frame(&frame),
             ClientEvent::Tick { now } => self.handle_tick(now),
@@ -192,11 +542,13 @@ impl<E: Environment> Client<E> {
             ClientEvent::RemoveMembers { room_id, member_ids } => {
                 self.handle_remove_members(room_id, &member_ids)
             },
+            ClientEvent::SelfUpdate { room_id } => self.handle_self_update(room_id),
             ClientEvent::PublishKeyPackage => self.handle_publish_key_package(),
             ClientEvent::FetchAndAddMember { room_id, user_id } => {
                 self.handle_fetch_and_add_member(room_id, user_id)
             },
             ClientEvent::ExternalJoin { room_id } => self.handle_external_join(room_id),
+            ClientEvent::RequestRoomList => self.handle_request_room_list(),
         }
     }
 
@@ -216,7 +568,8 @@ impl<E: Environment> Client<E> {
         let initial_state =
             mls_group.export_state().map_err(|e| ClientError::Mls { reason: e.to_string() })?;
 
-        let room_state = RoomState { mls_group, sender_keys, my_leaf_index };
+        let room_state =
+            RoomState::new(mls_group, sender_keys, my_leaf_index, self.config.message_id_history);
         self.rooms.insert(room_id, room_state);
 
         let mut actions = self.convert_mls_actions(room_id, mls_actions);
@@ -238,20 +591,115 @@ impl<E: Environment> Client<E> {
         &self,
         mls_group: &MlsGroup<E>,
     ) -> Result<SenderKeyStore, ClientError> {
-        let epoch_secret = mls_group
-            .export_secret(SENDER_KEY_LABEL, SENDER_KEY_CONTEXT, SENDER_KEY_SECRET_SIZE)
-            .map_err(|e| ClientError::Mls { reason: e.to_string() })?;
-
+        let epoch_secret = sender_key_secret(mls_group)?;
         let member_indices = mls_group.member_leaf_indices();
 
         Ok(SenderKeyStore::initialize_epoch(&epoch_secret, mls_group.epoch(), &member_indices))
     }
 
+    /// Rederive sender keys from MLS group state to replace `current`.
+    ///
+    /// Fails instead of diverging if the group is still at `current`'s epoch
+    /// but lists different members.
+    fn reinitialize_sender_keys(
+        current: &SenderKeyStore,
+        mls_group: &MlsGroup<E>,
+    ) -> Result<SenderKeyStore, ClientError> {
+        let epoch_secret = sender_key_secret(mls_group)?;
+        let member_indices = mls_group.member_leaf_indices();
+
+        Ok(current.reinitialize(&epoch_secret, mls_group.epoch(), &member_indices)?)
+    }
+
     fn handle_send_message(
         &mut self,
         room_id: RoomId,
         plaintext: &[u8],
+        expires_at: Option<u64>,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        let message_id = self.env.random_u128();
+        let mut encrypted = self.encrypt_for_room(room_id, plaintext)?;
+        encrypted.message_id = Some(message_id);
+        encrypted.expires_at = expires_at;
+        let payload = serialize_encrypted_message(&encrypted);
+        let frame = self.app_frame(room_id, Opcode::AppMessage, payload)?;
+
+        Ok(vec![ClientAction::Send(frame), ClientAction::MessageQueued {
+            room_id,
+            message_id,
+            log_index: None,
+        }])
+    }
+
+    fn handle_edit_message(
+        &mut self,
+        room_id: RoomId,
+        target_log_index: u64,
+        plaintext: &[u8],
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        let new_ciphertext = self.encrypt_for_room(room_id, plaintext)?;
+
+        let mut payload = Vec::new();
+        Payload::AppEdit(Edit { target_log_index, new_ciphertext })
+            .encode(&mut payload)
+            .map_err(|e| ClientError::InvalidFrame { reason: e.to_string() })?;
+        let frame = self.app_frame(room_id, Opcode::AppEdit, payload)?;
+
+        Ok(vec![ClientAction::Send(frame)])
+    }
+
+    fn handle_redact_message(
+        &mut self,
+        room_id: RoomId,
+        message_log_index: u64,
+        reason: String,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        let redact = Redact { message_log_index, reason, moderator_id: self.identity.sender_id };
+
+        let mut payload = Vec::new();
+        Payload::Redact(redact)
+            .encode(&mut payload)
+            .map_err(|e| ClientError::InvalidFrame { reason: e.to_string() })?;
+        let frame = self.app_frame(room_id, Opcode::Redact, payload)?;
+
+        Ok(vec![ClientAction::Send(frame)])
+    }
+
+    fn handle_set_room_meta(
+        &mut self,
+        room_id: RoomId,
+        name: Option<String>,
+        topic: Option<String>,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        let mut payload = Vec::new();
+        Payload::RoomMeta(RoomMeta { name, topic })
+            .encode(&mut payload)
+            .map_err(|e| ClientError::InvalidFrame { reason: e.to_string() })?;
+        let frame = self.app_frame(room_id, Opcode::RoomMeta, payload)?;
+
+        Ok(vec![ClientAction::Send(frame)])
+    }
+
+    fn handle_set_typing(
+        &mut self,
+        room_id: RoomId,
+        is_typing: bool,
     ) -> Result<Vec<ClientAction>, ClientError> {
+        let mut payload = Vec::new();
+        Payload::Typing(Typing { is_typing })
+            .encode(&mut payload)
+            .map_err(|e| ClientError::InvalidFrame { reason: e.to_string() })?;
+        let frame = self.app_frame(room_id, Opcode::Typing, payload)?;
+
+        Ok(vec![ClientAction::Send(frame)])
+    }
+
+    /// Encrypt plaintext with our sender key for the room's current epoch.
+    fn encrypt_for_room(
+        &mut self,
+        room_id: RoomId,
+        plaintext: &[u8],
+    ) -> Result<EncryptedMessage, ClientError> {
         let room = self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
 
         let mut random_bytes = [0u8; NONCE_RANDOM_SIZE];
@@ -260,25 +708,34 @@ impl<E: Environment> Client<E> {
         let crypto_encrypted =
             room.sender_keys.encrypt(room.my_leaf_index, plaintext, random_bytes)?;
 
-        let encrypted = crypto_to_proto_encrypted(&crypto_encrypted);
-        let payload = serialize_encrypted_message(&encrypted);
+        Ok(crypto_to_proto_encrypted(&crypto_encrypted))
+    }
+
+    /// Build a signed application frame for the room's current epoch.
+    fn app_frame(
+        &mut self,
+        room_id: RoomId,
+        opcode: Opcode,
+        payload: Vec<u8>,
+    ) -> Result<Frame, ClientError> {
+        let request_id = self.next_request_id();
+        let room = self.rooms.get(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
 
         let payload_len: u32 = payload
             .len()
             .try_into()
             .map_err(|_| ClientError::InvalidFrame { reason: "Payload too large".to_string() })?;
 
-        let mut header = FrameHeader::new(Opcode::AppMessage);
-        header.set_room_id(room_id);
-        header.set_sender_id(self.identity.sender_id);
-        header.set_epoch(room.mls_group.epoch());
-        header.set_payload_size(payload_len);
+        let header = FrameHeader::builder(opcode)
+            .room_id(room_id)
+            .sender_id(self.identity.sender_id)
+            .epoch(room.mls_group.epoch())
+            .hlc_timestamp(self.env.wall_clock())
+            .payload_size(payload_len)
+            .request_id(request_id)
+            .sign_with(&room.mls_group);
 
-        room.mls_group.sign_frame_header(&mut header);
-
-        let frame = Frame::new(header, payload);
-
-        Ok(vec![ClientAction::Send(frame)])
+        Ok(Frame::new(header, payload))
     }
 
     fn handle_frame(&mut self, frame: &Frame) -> Result<Vec<ClientAction>, ClientError> {
@@ -293,15 +750,19 @@ impl<E: Environment> Client<E> {
                 // Ignore session-level responses (handled at transport layer)
                 Ok(vec![])
             },
-            Opcode::Error => Ok(vec![ClientAction::Log {
-                message: format!("Server error: room_id={room_id:x}"),
-            }]),
+            Opcode::Error => self.handle_error_frame(room_id, frame),
+            Opcode::Goodbye => Self::handle_goodbye(frame),
             Opcode::AppMessage => self.handle_app_message(room_id, frame),
+            Opcode::AppEdit => self.handle_app_edit(room_id, frame),
+            Opcode::Typing => self.handle_typing(room_id, frame),
+            Opcode::Redact => self.handle_redact(room_id, frame),
+            Opcode::RoomMeta => self.handle_room_meta(room_id, frame),
             Opcode::Commit | Opcode::ExternalCommit => self.handle_commit(room_id, frame),
             Opcode::Welcome => self.handle_welcome(room_id, frame),
             Opcode::SyncResponse => self.handle_sync_response(room_id, frame),
             Opcode::KeyPackageFetch => self.handle_key_package_fetch_response(frame),
             Opcode::GroupInfo => self.handle_group_info_response(frame),
+            Opcode::RoomListResponse => self.handle_room_list_response(frame),
             _ => {
                 let room =
                     self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
@@ -323,18 +784,284 @@ impl<E: Environment> Client<E> {
         frame: &Frame,
     ) -> Result<Vec<ClientAction>, ClientError> {
         if frame.header.sender_id() == self.identity.sender_id {
-            // Skip our own messages - we already have the plaintext locally
-            // and our sender ratchet has already advanced past this generation
+            // Skip decrypting our own messages - we already have the plaintext
+            // locally and our sender ratchet has already advanced past this
+            // generation. Only report the log index the server assigned.
+            let message = deserialize_encrypted_message(&frame.payload).ok();
+            if let Some(expires_at) = message.as_ref().and_then(|m| m.expires_at) {
+                self.schedule_expiry(room_id, expires_at, frame.header.log_index());
+            }
+            let message_id = message.and_then(|m| m.message_id);
+            return Ok(message_id
+                .map(|message_id| ClientAction::MessageQueued {
+                    room_id,
+                    message_id,
+                    log_index: Some(frame.header.log_index()),
+                })
+                .into_iter()
+                .collect());
+        }
+
+        #[cfg(feature = "key-export")]
+        if !self.rooms.contains_key(&room_id)
+            && let Some(room) = self.imported_rooms.get_mut(&room_id)
+        {
+            return Self::handle_imported_app_message(room_id, room, frame);
+        }
+
+        if let Some(actions) = self.verify_app_frame(room_id, frame)? {
+            return Ok(actions);
+        }
+
+        let proto_encrypted = deserialize_encrypted_message(&frame.payload)
+            .map_err(|e| ClientError::InvalidFrame { reason: e })?;
+
+        // A retried send arrives again under the same ID; its generation was
+        // already consumed, so skip it before touching the ratchet
+        let message_id = proto_encrypted.message_id;
+        if let Some(id) = message_id
+            && self.rooms.get(&room_id).is_some_and(|r| r.delivered_message_ids.contains(id))
+        {
+            return Ok(vec![ClientAction::Log {
+                message: format!("Dropped duplicate message {id:x} in room {room_id:x}"),
+            }]);
+        }
+
+        let (sender_id, plaintext) = self.decrypt_app_content(room_id, frame, &proto_encrypted)?;
+
+        if let Some(id) = message_id
+            && let Some(room) = self.rooms.get_mut(&room_id)
+        {
+            room.delivered_message_ids.insert(id);
+        }
+
+        // Decrypted anyway so the sender's ratchet stays in step, but a
+        // message that has already expired is never shown
+        if let Some(expires_at) = proto_encrypted.expires_at {
+            if expires_at <= self.env.wall_clock() {
+                return Ok(vec![ClientAction::Log {
+                    message: format!(
+                        "Dropped expired message {} in room {room_id:x}",
+                        frame.header.log_index()
+                    ),
+                }]);
+            }
+            self.schedule_expiry(room_id, expires_at, frame.header.log_index());
+        }
+
+        Ok(vec![ClientAction::DeliverMessage {
+            room_id,
+            sender_id,
+            plaintext,
+            log_index: frame.header.log_index(),
+            timestamp: frame.header.hlc_timestamp(),
+        }])
+    }
+
+    /// Handle an application message for a room we only have imported keys
+    /// for.
+    #[cfg(feature = "key-export")]
+    fn handle_imported_app_message(
+        room_id: RoomId,
+        room: &mut ImportedRoom,
+        frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        room.verify(frame)?;
+
+        let proto_encrypted = deserialize_encrypted_message(&frame.payload)
+            .map_err(|e| ClientError::InvalidFrame { reason: e })?;
+        let sender_id = room.member_id_by_leaf_index(proto_encrypted.sender_index);
+        if sender_id != Some(frame.header.sender_id()) {
+            return Err(ClientError::InvalidFrame {
+                reason: format!(
+                    "sender_id mismatch: header claims {}, but sender_index {} belongs to {sender_id:?}",
+                    frame.header.sender_id(),
+                    proto_encrypted.sender_index
+                ),
+            });
+        }
+
+        let plaintext = room.sender_keys.decrypt(&proto_to_crypto_encrypted(&proto_encrypted))?;
+
+        Ok(vec![ClientAction::DeliverMessage {
+            room_id,
+            sender_id: frame.header.sender_id(),
+            plaintext,
+            log_index: frame.header.log_index(),
+            timestamp: frame.header.hlc_timestamp(),
+        }])
+    }
+
+    /// Handle an edit of an earlier application message.
+    ///
+    /// The server only sequences edits from the original sender, so the
+    /// verified sender of the edit is also the author of the target message.
+    fn handle_app_edit(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        if frame.header.sender_id() == self.identity.sender_id {
+            // Same as our own messages: plaintext is local, ratchet has advanced
             return Ok(vec![]);
         }
 
+        if let Some(actions) = self.verify_app_frame(room_id, frame)? {
+            return Ok(actions);
+        }
+
+        let edit = match Payload::from_frame(frame) {
+            Ok(Payload::AppEdit(edit)) => edit,
+            Ok(_) => {
+                return Err(ClientError::InvalidFrame {
+                    reason: "expected AppEdit payload".to_string(),
+                });
+            },
+            Err(e) => return Err(ClientError::InvalidFrame { reason: e.to_string() }),
+        };
+
+        let (sender_id, plaintext) =
+            self.decrypt_app_content(room_id, frame, &edit.new_ciphertext)?;
+
+        Ok(vec![ClientAction::MessageEdited {
+            room_id,
+            sender_id,
+            target_log_index: edit.target_log_index,
+            plaintext,
+            log_index: frame.header.log_index(),
+            timestamp: frame.header.hlc_timestamp(),
+        }])
+    }
+
+    /// Handle a redaction of an earlier message.
+    ///
+    /// The server only sequences redactions from the message's author or a
+    /// room admin. Unlike edits, our own echo is delivered too: redactions
+    /// carry no ciphertext, and the echo is how the sender learns it was
+    /// sequenced.
+    fn handle_redact(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        if let Some(actions) = self.verify_app_frame(room_id, frame)? {
+            return Ok(actions);
+        }
+
+        let redact = match Payload::from_frame(frame) {
+            Ok(Payload::Redact(redact)) => redact,
+            Ok(_) => {
+                return Err(ClientError::InvalidFrame {
+                    reason: "expected Redact payload".to_string(),
+                });
+            },
+            Err(e) => return Err(ClientError::InvalidFrame { reason: e.to_string() }),
+        };
+
+        let moderator_id = frame.header.sender_id();
+        if redact.moderator_id != moderator_id {
+            return Err(ClientError::InvalidFrame {
+                reason: format!(
+                    "redaction claims moderator {} but was signed by {moderator_id}",
+                    redact.moderator_id
+                ),
+            });
+        }
+
+        Ok(vec![ClientAction::MessageRedacted {
+            room_id,
+            moderator_id,
+            target_log_index: redact.message_log_index,
+            reason: redact.reason,
+            log_index: frame.header.log_index(),
+        }])
+    }
+
+    /// Handle a name or topic change.
+    ///
+    /// The server only sequences changes from room admins, so any change
+    /// that verifies is applied. Our own echo is delivered like a redaction's.
+    fn handle_room_meta(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        if let Some(actions) = self.verify_app_frame(room_id, frame)? {
+            return Ok(actions);
+        }
+
+        let meta = match Payload::from_frame(frame) {
+            Ok(Payload::RoomMeta(meta)) => meta,
+            Ok(_) => {
+                return Err(ClientError::InvalidFrame {
+                    reason: "expected RoomMeta payload".to_string(),
+                });
+            },
+            Err(e) => return Err(ClientError::InvalidFrame { reason: e.to_string() }),
+        };
+
+        Ok(vec![ClientAction::RoomMetaChanged {
+            room_id,
+            sender_id: frame.header.sender_id(),
+            name: meta.name,
+            topic: meta.topic,
+        }])
+    }
+
+    /// Handle a typing indicator from another member.
+    ///
+    /// Indicators from other epochs are dropped rather than triggering a
+    /// sync; they are ephemeral and stale by the time we could catch up.
+    fn handle_typing(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        let sender_id = frame.header.sender_id();
+        if sender_id == self.identity.sender_id {
+            return Ok(vec![]);
+        }
+
+        let room = self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
+        if frame.header.epoch() != room.mls_group.epoch() {
+            return Ok(vec![]);
+        }
+
+        room.mls_group
+            .validate_frame(frame)
+            .map_err(|e| ClientError::InvalidFrame { reason: e.to_string() })?;
+
+        let is_typing = match Payload::from_frame(frame) {
+            Ok(Payload::Typing(typing)) => typing.is_typing,
+            Ok(_) => {
+                return Err(ClientError::InvalidFrame {
+                    reason: "expected Typing payload".to_string(),
+                });
+            },
+            Err(e) => return Err(ClientError::InvalidFrame { reason: e.to_string() }),
+        };
+
+        Ok(vec![ClientAction::TypingChanged { room_id, sender_id, is_typing }])
+    }
+
+    /// Check an application frame's epoch and signature.
+    ///
+    /// Returns sync actions instead of an error when the frame is from an
+    /// epoch we don't have yet.
+    fn verify_app_frame(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
+    ) -> Result<Option<Vec<ClientAction>>, ClientError> {
+        self.check_clock_skew(frame)?;
+
         let room = self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
 
         let frame_epoch = frame.header.epoch();
         let room_epoch = room.mls_group.epoch();
 
         if frame_epoch != room_epoch {
-            return Ok(vec![
+            return Ok(Some(vec![
                 ClientAction::Log {
                     message: format!(
                         "Epoch mismatch for room {room_id:x}: frame {frame_epoch}, room {room_epoch}. Requesting sync."
@@ -345,16 +1072,44 @@ impl<E: Environment> Client<E> {
                     from_epoch: room_epoch,
                     to_epoch: frame_epoch,
                 },
-            ]);
+            ]));
         }
 
-        let validation_state = room.mls_group.export_validation_state();
         room.mls_group
-            .validate_frame(frame, Some(&validation_state))
+            .validate_frame(frame)
             .map_err(|e| ClientError::InvalidFrame { reason: e.to_string() })?;
 
-        let proto_encrypted = deserialize_encrypted_message(&frame.payload)
-            .map_err(|e| ClientError::InvalidFrame { reason: e })?;
+        Ok(None)
+    }
+
+    /// Reject a frame stamped further ahead of our wall clock than
+    /// `max_skew`, so one sender's bad clock can't pin ordering in the future.
+    fn check_clock_skew(&self, frame: &Frame) -> Result<(), ClientError> {
+        let timestamp = frame.header.hlc_timestamp();
+        let local_time = self.env.wall_clock();
+        let max_skew_ms = u64::try_from(self.config.max_skew.as_millis()).unwrap_or(u64::MAX);
+
+        if timestamp > local_time.saturating_add(max_skew_ms) {
+            return Err(ClientError::ClockSkew {
+                sender_id: frame.header.sender_id(),
+                timestamp,
+                local_time,
+                max_skew_ms,
+            });
+        }
+        Ok(())
+    }
+
+    /// Decrypt sender-key encrypted content carried by `frame`.
+    ///
+    /// Returns the verified sender ID with the plaintext.
+    fn decrypt_app_content(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
+        proto_encrypted: &EncryptedMessage,
+    ) -> Result<(u64, Vec<u8>), ClientError> {
+        let room = self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
 
         // Verify sender_id in header matches the sender_index from the encrypted
         // payload. This prevents forgery where an attacker repackages a message
@@ -379,37 +1134,129 @@ impl<E: Environment> Client<E> {
             });
         }
 
-        let encrypted = proto_to_crypto_encrypted(&proto_encrypted);
+        let encrypted = proto_to_crypto_encrypted(proto_encrypted);
         let plaintext = room.sender_keys.decrypt(&encrypted)?;
 
-        Ok(vec![ClientAction::DeliverMessage {
-            room_id,
-            sender_id: verified_sender_id,
-            plaintext,
-            log_index: frame.header.log_index(),
-            timestamp: frame.header.hlc_timestamp(),
-        }])
+        Ok((verified_sender_id, plaintext))
     }
 
     /// Handle MLS commit (epoch transition).
+    ///
+    /// A commit for a later epoch than ours is buffered until the commits
+    /// before it arrive (directly or via sync), then applied in order.
     fn handle_commit(
         &mut self,
         room_id: RoomId,
         frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        let room = self.rooms.get(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
+        if let Ok(commit_epoch) = MlsGroup::<E>::message_epoch(frame)
+            && commit_epoch > room.mls_group.epoch()
+        {
+            return self.buffer_commit(room_id, commit_epoch, frame);
+        }
+
+        let mut actions = self.apply_commit(room_id, frame)?;
+        actions.extend(self.apply_buffered_commits(room_id));
+        Ok(actions)
+    }
+
+    /// Hold a commit for a future epoch and request the gap.
+    ///
+    /// Sync is requested whenever the commit lies beyond every buffered
+    /// epoch, covering the stretch since the last buffered commit. Commits
+    /// landing inside an already-requested range wait on that sync.
+    fn buffer_commit(
+        &mut self,
+        room_id: RoomId,
+        commit_epoch: u64,
+        frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        let room = self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
+        let room_epoch = room.mls_group.epoch();
+        let synced_to = room.buffered_commits.last_key_value().map_or(room_epoch, |(&e, _)| e);
+
+        let mut actions = Vec::new();
+        if room.buffered_commits.len() < self.config.max_buffered_commits
+            || room.buffered_commits.contains_key(&commit_epoch)
+        {
+            room.buffered_commits.insert(commit_epoch, (frame.clone(), self.env.now()));
+            actions.push(ClientAction::CommitBuffered { room_id, commit_epoch, room_epoch });
+        } else {
+            actions.push(ClientAction::Log {
+                message: format!(
+                    "Commit buffer full for room {room_id:x}, dropping commit for epoch {commit_epoch}"
+                ),
+            });
+        }
+
+        if commit_epoch > synced_to {
+            actions.push(ClientAction::RequestSync {
+                room_id,
+                from_epoch: synced_to,
+                to_epoch: commit_epoch,
+            });
+        }
+
+        Ok(actions)
+    }
+
+    /// Apply buffered commits that now follow on from the room's epoch.
+    ///
+    /// Stops at the first gap or failure; a commit that fails to apply is
+    /// dropped so sync can replace it.
+    fn apply_buffered_commits(&mut self, room_id: RoomId) -> Vec<ClientAction> {
+        let mut actions = Vec::new();
+
+        while let Some(room) = self.rooms.get_mut(&room_id) {
+            let epoch = room.mls_group.epoch();
+            room.buffered_commits = room.buffered_commits.split_off(&epoch);
+            let Some((frame, _)) = room.buffered_commits.remove(&epoch) else {
+                break;
+            };
+
+            match self.apply_commit(room_id, &frame) {
+                Ok(applied) => actions.extend(applied),
+                Err(e) => {
+                    actions.push(ClientAction::Log {
+                        message: format!(
+                            "Buffered commit for epoch {epoch} in room {room_id:x} failed: {e}"
+                        ),
+                    });
+                    break;
+                },
+            }
+        }
+
+        actions
+    }
+
+    /// Merge a commit for the room's current epoch.
+    ///
+    /// Commits for later epochs are buffered by the caller, so the group
+    /// normally advances one epoch here and sender keys are rederived for it.
+    /// If it lands anywhere but one past the epoch the keys were last derived
+    /// for, the epochs in between were never keyed: buffered commits for them
+    /// are dropped and a sync is requested for their messages.
+    fn apply_commit(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
     ) -> Result<Vec<ClientAction>, ClientError> {
         let is_own_commit = frame.header.sender_id() == self.identity.sender_id;
 
         let Some(room) = self.rooms.get_mut(&room_id) else {
             return Err(ClientError::RoomNotFound { room_id });
         };
+        let keyed_epoch = room.sender_keys.epoch();
+        let members_before = room.member_set();
 
         let mut actions = {
             if is_own_commit && room.mls_group.has_pending_commit() {
-                let mls_actions = room
-                    .mls_group
+                room.mls_group
                     .merge_pending_commit()
                     .map_err(|e| ClientError::Mls { reason: e.to_string() })?;
-                self.convert_mls_actions(room_id, mls_actions)
+                Vec::new()
             } else if is_own_commit && !room.mls_group.has_mls_pending_commit() {
                 // The MLS group is already at the committed epoch, so we should
                 // skip processing entirely to avoid reinitializing sender keys.
@@ -433,7 +1280,7 @@ impl<E: Environment> Client<E> {
 
         let (new_sender_keys, new_leaf_index, epoch, my_leaf_index) = {
             let room = self.rooms.get(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
-            let sender_keys = self.initialize_sender_keys(&room.mls_group)?;
+            let sender_keys = Self::reinitialize_sender_keys(&room.sender_keys, &room.mls_group)?;
             let leaf_index = room.mls_group.own_leaf_index();
             let epoch = room.mls_group.epoch();
             (sender_keys, leaf_index, epoch, leaf_index)
@@ -442,6 +1289,31 @@ impl<E: Environment> Client<E> {
         let room = self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
         room.sender_keys = new_sender_keys;
         room.my_leaf_index = new_leaf_index;
+        #[cfg(feature = "epoch-history")]
+        room.record_epoch();
+
+        if epoch != keyed_epoch.saturating_add(1) {
+            room.buffered_commits = room.buffered_commits.split_off(&epoch);
+            actions.push(ClientAction::Log {
+                message: format!(
+                    "Room {room_id:x} moved from epoch {keyed_epoch} to {epoch}, rekeyed and syncing"
+                ),
+            });
+            actions.push(ClientAction::RequestSync {
+                room_id,
+                from_epoch: keyed_epoch,
+                to_epoch: epoch,
+            });
+        }
+
+        let members_after = room.member_set();
+        if members_before != members_after {
+            actions.push(ClientAction::MembersChanged {
+                room_id,
+                added: members_after.difference(&members_before).copied().collect(),
+                removed: members_before.difference(&members_after).copied().collect(),
+            });
+        }
 
         actions.push(ClientAction::PersistRoom(RoomStateSnapshot {
             room_id,
@@ -453,20 +1325,45 @@ impl<E: Environment> Client<E> {
             my_leaf_index,
         }));
 
+        // Only the committer publishes the new epoch's GroupInfo, so each
+        // epoch is exported once rather than by every member
+        if is_own_commit {
+            actions.extend(self.publish_group_info(room_id));
+        }
+
         Ok(actions)
     }
 
+    /// Publish a room's `GroupInfo` for its current epoch so external joiners
+    /// can fetch it from the server.
+    fn publish_group_info(&mut self, room_id: RoomId) -> Vec<ClientAction> {
+        let Some(epoch) = self.rooms.get(&room_id).map(|room| room.mls_group.epoch()) else {
+            return Vec::new();
+        };
+        let Some(group_info_bytes) = self.group_info(room_id).map(<[u8]>::to_vec) else {
+            return vec![ClientAction::Log {
+                message: format!("Failed to export GroupInfo for room {room_id:x}"),
+            }];
+        };
+        self.convert_mls_actions(room_id, vec![MlsAction::PublishGroupInfo {
+            room_id,
+            epoch,
+            group_info_bytes,
+        }])
+    }
+
     /// Try to join a room using a pending `KeyPackage` state.
     ///
-    /// Tries each pending `KeyPackage` state until one succeeds. On success,
-    /// the matching state is consumed. On failure, all tried states are
-    /// consumed (caller should generate new `KeyPackages` if needed).
+    /// Tries each pending `KeyPackage` state, oldest first, until one
+    /// succeeds. On success, the matching state is consumed. On failure, all
+    /// tried states are consumed (caller should generate new `KeyPackages` if
+    /// needed).
     fn try_join_from_welcome(
         &mut self,
         room_id: RoomId,
         welcome_bytes: &[u8],
     ) -> Result<(MlsGroup<E>, Vec<MlsAction>), ClientError> {
-        let pending_hashes: Vec<Vec<u8>> = self.pending_joins.keys().cloned().collect();
+        let pending_hashes: Vec<Vec<u8>> = self.pending_join_order.iter().cloned().collect();
 
         if pending_hashes.is_empty() {
             return Err(ClientError::Mls {
@@ -477,6 +1374,7 @@ impl<E: Environment> Client<E> {
         let mut last_error = None;
 
         for hash_ref in pending_hashes {
+            self.pending_join_order.retain(|h| *h != hash_ref);
             if let Some(pending_state) = self.pending_joins.remove(&hash_ref) {
                 match MlsGroup::join_from_welcome(
                     room_id,
@@ -535,7 +1433,8 @@ impl<E: Environment> Client<E> {
         let sender_keys = self.initialize_sender_keys(&mls_group)?;
         let my_leaf_index = mls_group.own_leaf_index();
 
-        let room_state = RoomState { mls_group, sender_keys, my_leaf_index };
+        let room_state =
+            RoomState::new(mls_group, sender_keys, my_leaf_index, self.config.message_id_history);
         let current_epoch = room_state.mls_group.epoch();
 
         let mls_state = room_state
@@ -582,7 +1481,8 @@ impl<E: Environment> Client<E> {
         let sender_keys = self.initialize_sender_keys(&mls_group)?;
         let my_leaf_index = mls_group.own_leaf_index();
 
-        let room_state = RoomState { mls_group, sender_keys, my_leaf_index };
+        let room_state =
+            RoomState::new(mls_group, sender_keys, my_leaf_index, self.config.message_id_history);
         self.rooms.insert(room_id, room_state);
 
         let mut actions = self.convert_mls_actions(room_id, mls_actions);
@@ -598,6 +1498,8 @@ impl<E: Environment> Client<E> {
     /// Processes frames from the sync response in order to catch up
     /// to the server's epoch. Each frame is decoded and processed
     /// sequentially. If `has_more` is true, emits another `RequestSync` action.
+    /// A snapshot ahead of our epoch means the commits we need were pruned, so
+    /// the room is rejoined by external commit instead.
     fn handle_sync_response(
         &mut self,
         room_id: RoomId,
@@ -609,6 +1511,7 @@ impl<E: Environment> Client<E> {
             })?;
 
         let mut all_actions = Vec::new();
+        let mut page_last_log_index = None;
 
         all_actions.push(ClientAction::Log {
             message: format!(
@@ -619,10 +1522,39 @@ impl<E: Environment> Client<E> {
             ),
         });
 
+        if let Some(snapshot) = &sync_response.snapshot
+            && self.rooms.get(&room_id).is_some_and(|r| r.mls_group.epoch() < snapshot.epoch)
+        {
+            self.sync_progress.remove(&room_id);
+            all_actions.push(ClientAction::Log {
+                message: format!(
+                    "Commits before log index {} in room {room_id:x} were pruned, rejoining",
+                    snapshot.first_log_index
+                ),
+            });
+            // Our group can't follow the retained log, so it is replaced by the
+            // one the external join creates
+            self.rooms.remove(&room_id);
+            all_actions.push(ClientAction::RoomRemoved {
+                room_id,
+                reason: "History pruned, rejoining".to_string(),
+            });
+            // The room is gone either way, so a failed rejoin is reported
+            // alongside its removal rather than in place of it
+            match self.handle_external_join(room_id) {
+                Ok(actions) => all_actions.extend(actions),
+                Err(e) => all_actions.push(ClientAction::Log {
+                    message: format!("Failed to rejoin room {room_id:x}: {e}"),
+                }),
+            }
+            return Ok(all_actions);
+        }
+
         for (i, frame_bytes) in sync_response.frames.iter().enumerate() {
             let sync_frame = Frame::decode(frame_bytes).map_err(|e| ClientError::InvalidFrame {
                 reason: format!("Failed to decode sync frame {i}: {e}"),
             })?;
+            page_last_log_index = page_last_log_index.max(Some(sync_frame.header.log_index()));
 
             match self.handle_frame(&sync_frame) {
                 Ok(actions) => all_actions.extend(actions),
@@ -637,6 +1569,25 @@ impl<E: Environment> Client<E> {
         }
 
         if sync_response.has_more {
+            // Only follow up if this page moved us forward, so a server that
+            // keeps reporting more without new frames can't loop us forever
+            let progress = self.sync_progress.entry(room_id).or_default();
+            progress.pages += 1;
+            let advanced = page_last_log_index
+                .is_some_and(|index| progress.last_log_index.is_none_or(|last| index > last));
+
+            if !advanced || progress.pages >= MAX_SYNC_PAGES {
+                let reason = if advanced {
+                    format!("exceeded {MAX_SYNC_PAGES} sync pages")
+                } else {
+                    format!("sync page {} did not advance the log", progress.pages)
+                };
+                self.sync_progress.remove(&room_id);
+                all_actions.push(ClientAction::SyncStalled { room_id, reason });
+                return Ok(all_actions);
+            }
+            progress.last_log_index = page_last_log_index;
+
             // More frames avaliable
             let current_epoch = self.rooms.get(&room_id).map_or(0, |r| r.mls_group.epoch());
 
@@ -653,6 +1604,7 @@ impl<E: Environment> Client<E> {
                 ),
             });
         } else {
+            self.sync_progress.remove(&room_id);
             all_actions.push(ClientAction::Log {
                 message: format!(
                     "Sync complete for room {room_id:x}, now at epoch {}",
@@ -693,6 +1645,18 @@ impl<E: Environment> Client<E> {
             .remove_members(member_ids)
             .map_err(|e| ClientError::Mls { reason: e.to_string() })?;
 
+        let mut actions = self.convert_mls_actions(room_id, mls_actions);
+        actions.extend(
+            member_ids.iter().map(|&user_id| ClientAction::MemberRemoved { room_id, user_id }),
+        );
+        Ok(actions)
+    }
+
+    fn handle_self_update(&mut self, room_id: RoomId) -> Result<Vec<ClientAction>, ClientError> {
+        let room = self.rooms.get_mut(&room_id).ok_or(ClientError::RoomNotFound { room_id })?;
+        let mls_actions =
+            room.mls_group.self_update().map_err(|e| ClientError::Mls { reason: e.to_string() })?;
+
         Ok(self.convert_mls_actions(room_id, mls_actions))
     }
 
@@ -728,6 +1692,12 @@ impl<E: Environment> Client<E> {
             return Err(ClientError::RoomNotFound { room_id });
         }
 
+        let limit = self.config.max_pending_adds;
+        if self.pending_adds.len() >= limit && !self.pending_adds.contains_key(&(room_id, user_id))
+        {
+            return Err(ClientError::PendingLimitReached { kind: "adds", limit });
+        }
+
         self.pending_adds.insert((room_id, user_id), self.env.now());
 
         let payload =
@@ -744,7 +1714,10 @@ impl<E: Environment> Client<E> {
 
     /// Handle `KeyPackage` fetch response.
     ///
-    /// Completes a pending add operation by using the fetched `KeyPackage`.
+    /// Completes a pending add operation by using the fetched `KeyPackage`,
+    /// once it validates for every room waiting on it. If it is invalid or
+    /// belongs to someone else, every pending add for the user is dropped
+    /// with an [`ClientAction::InviteeRejected`] per room.
     fn handle_key_package_fetch_response(
         &mut self,
         frame: &Frame,
@@ -784,9 +1757,44 @@ impl<E: Environment> Client<E> {
             });
         }
 
-        let mut actions = Vec::new();
+        // Check the package in every room before committing in any, so a bad
+        // package is refused as a whole rather than failing each add commit
         let key_package_bytes = payload.key_package_bytes;
+        let mut validated = Vec::with_capacity(matching_entries.len());
+        let mut rejected = None;
+        for &(room_id, user_id) in &matching_entries {
+            let Some(room) = self.rooms.get(&room_id) else {
+                continue;
+            };
+            match room.mls_group.validate_key_package_bytes(&key_package_bytes) {
+                Ok(info) if info.member_id == user_id => {
+                    validated.push(ClientAction::InviteeValidated {
+                        room_id,
+                        user_id,
+                        ciphersuite: info.ciphersuite.into(),
+                    });
+                },
+                Ok(info) => {
+                    rejected = Some(format!("KeyPackage belongs to user {}", info.member_id));
+                    break;
+                },
+                Err(e) => {
+                    rejected = Some(e.to_string());
+                    break;
+                },
+            }
+        }
+        if let Some(reason) = rejected {
+            return Ok(matching_entries
+                .into_iter()
+                .map(|(room_id, user_id)| {
+                    self.pending_adds.remove(&(room_id, user_id));
+                    ClientAction::InviteeRejected { room_id, user_id, reason: reason.clone() }
+                })
+                .collect());
+        }
 
+        let mut actions = validated;
         for (room_id, user_id) in matching_entries {
             self.pending_adds.remove(&(room_id, user_id));
 
@@ -816,7 +1824,67 @@ impl<E: Environment> Client<E> {
             }
         }
 
-        Ok(actions)
+        Ok(actions)
+    }
+
+    /// Ask the server for the rooms the authenticated user has joined.
+    fn handle_request_room_list(&self) -> Result<Vec<ClientAction>, ClientError> {
+        let frame = Payload::RoomListRequest(RoomListRequest {})
+            .into_frame(FrameHeader::new(Opcode::RoomListRequest))
+            .map_err(|e| ClientError::InvalidFrame { reason: e.to_string() })?;
+
+        Ok(vec![ClientAction::Send(frame)])
+    }
+
+    /// Surface a server `Error` to the application.
+    ///
+    /// An error for a room with a pending external join means the server
+    /// can't provide its `GroupInfo`, so the join is abandoned.
+    fn handle_error_frame(
+        &mut self,
+        room_id: RoomId,
+        frame: &Frame,
+    ) -> Result<Vec<ClientAction>, ClientError> {
+        self.pending_external_joins.remove(&room_id);
+        match Payload::from_frame(frame) {
+            Ok(Payload::Error(error)) if error.code == ErrorPayload::ROOM_FULL => {
+                Err(ClientError::RoomFull { room_id, reason: error.message })
+            },
+            Ok(Payload::Error(error)) => Ok(vec![ClientAction::ServerError {
+                room_id,
+                code: error.code,
+                message: error.message,
+                retry_after: error.retry_after,
+            }]),
+            Ok(_) => {
+                Err(ClientError::InvalidFrame { reason: "expected Error payload".to_string() })
+            },
+            Err(e) => Err(ClientError::InvalidFrame { reason: e.to_string() }),
+        }
+    }
+
+    fn handle_goodbye(frame: &Frame) -> Result<Vec<ClientAction>, ClientError> {
+        match Payload::from_frame(frame) {
+            Ok(Payload::Goodbye(goodbye)) => {
+                Ok(vec![ClientAction::Disconnected { reason: goodbye.reason }])
+            },
+            Ok(_) => {
+                Err(ClientError::InvalidFrame { reason: "expected Goodbye payload".to_string() })
+            },
+            Err(e) => Err(ClientError::InvalidFrame { reason: e.to_string() }),
+        }
+    }
+
+    fn handle_room_list_response(&self, frame: &Frame) -> Result<Vec<ClientAction>, ClientError> {
+        match Payload::from_frame(frame) {
+            Ok(Payload::RoomListResponse(response)) => {
+                Ok(vec![ClientAction::RoomListReceived { room_ids: response.room_ids }])
+            },
+            Ok(_) => Err(ClientError::InvalidFrame {
+                reason: "expected RoomListResponse payload".to_string(),
+            }),
+            Err(e) => Err(ClientError::InvalidFrame { reason: e.to_string() }),
+        }
     }
 
     /// Handle external join request.
@@ -828,7 +1896,14 @@ impl<E: Environment> Client<E> {
             return Err(ClientError::RoomAlreadyExists { room_id });
         }
 
-        self.pending_external_joins.insert(room_id);
+        let limit = self.config.max_pending_external_joins;
+        if self.pending_external_joins.len() >= limit
+            && !self.pending_external_joins.contains_key(&room_id)
+        {
+            return Err(ClientError::PendingLimitReached { kind: "external joins", limit });
+        }
+
+        self.pending_external_joins.insert(room_id, self.env.now());
 
         let payload = lockframe_proto::payloads::mls::GroupInfoRequest { room_id };
 
@@ -857,7 +1932,7 @@ impl<E: Environment> Client<E> {
 
         let room_id = payload.room_id;
 
-        if !self.pending_external_joins.remove(&room_id) {
+        if self.pending_external_joins.remove(&room_id).is_none() {
             return Err(ClientError::InvalidFrame {
                 reason: format!("No pending external join for room {room_id:032x}"),
             });
@@ -880,7 +1955,8 @@ impl<E: Environment> Client<E> {
         let initial_state =
             mls_group.export_state().map_err(|e| ClientError::Mls { reason: e.to_string() })?;
 
-        let room_state = RoomState { mls_group, sender_keys, my_leaf_index };
+        let room_state =
+            RoomState::new(mls_group, sender_keys, my_leaf_index, self.config.message_id_history);
         self.rooms.insert(room_id, room_state);
 
         let mut actions = self.convert_mls_actions(room_id, mls_actions);
@@ -897,19 +1973,28 @@ impl<E: Environment> Client<E> {
         Ok(actions)
     }
 
+    /// Remove `log_index` from `room_id`'s history once `expires_at` passes.
+    fn schedule_expiry(&mut self, room_id: RoomId, expires_at: u64, log_index: u64) {
+        if let Some(room) = self.rooms.get_mut(&room_id) {
+            room.expiring.insert((expires_at, log_index));
+        }
+    }
+
     /// Handle tick (timeout processing).
     ///
     /// Checks all rooms for pending commits that have timed out.
     /// Also cleans up stale pending `KeyPackage` fetch operations.
     /// For rooms with timed-out commits, clears the pending state and emits
-    /// `RequestSync` actions.
+    /// `RequestSync` actions. Delivered messages whose expiry has passed are
+    /// reported with `MessageExpired`.
     fn handle_tick(&mut self, now: E::Instant) -> Result<Vec<ClientAction>, ClientError> {
         let mut actions = Vec::new();
+        let wall_clock = self.env.wall_clock();
 
         let stale_adds: Vec<(RoomId, u64)> = self
             .pending_adds
             .iter()
-            .filter(|((_, _), timestamp)| now - **timestamp > KEY_PACKAGE_FETCH_TIMEOUT)
+            .filter(|((_, _), timestamp)| now - **timestamp > self.config.key_package_fetch_timeout)
             .map(|((room_id, user_id), _)| (*room_id, *user_id))
             .collect();
 
@@ -924,8 +2009,21 @@ impl<E: Environment> Client<E> {
             }
         }
 
+        let timeout = self.config.group_info_fetch_timeout;
+        self.pending_external_joins.retain(|&room_id, requested_at| {
+            let stale = now - *requested_at > timeout;
+            if stale {
+                actions.push(ClientAction::Log {
+                    message: format!(
+                        "GroupInfo fetch timeout for room {room_id:x}, abandoning external join"
+                    ),
+                });
+            }
+            !stale
+        });
+
         for (&room_id, room) in &mut self.rooms {
-            if room.mls_group.is_commit_timeout(now, COMMIT_TIMEOUT) {
+            if room.mls_group.is_commit_timeout(now, self.config.commit_timeout) {
                 let current_epoch = room.mls_group.epoch();
                 room.mls_group
                     .clear_pending_commit()
@@ -943,6 +2041,20 @@ impl<E: Environment> Client<E> {
                     ),
                 });
             }
+
+            actions.extend(room.expire_buffered_commits(
+                room_id,
+                now,
+                self.config.buffered_commit_ttl,
+            ));
+
+            let pending = room.expiring.split_off(&(wall_clock.saturating_add(1), 0));
+            let expired = std::mem::replace(&mut room.expiring, pending);
+            actions.extend(
+                expired
+                    .into_iter()
+                    .map(|(_, log_index)| ClientAction::MessageExpired { room_id, log_index }),
+            );
         }
 
         Ok(actions)
@@ -983,10 +2095,13 @@ impl<E: Environment> Client<E> {
                 MlsAction::PublishGroupInfo { room_id: info_room_id, epoch, group_info_bytes } => {
                     let payload =
                         GroupInfoPayload { room_id: info_room_id, epoch, group_info_bytes };
+                    // The server only stores GroupInfo addressed to its room
+                    let mut header = FrameHeader::new(Opcode::GroupInfo);
+                    header.set_room_id(info_room_id);
+                    header.set_sender_id(self.identity.sender_id);
+                    header.set_epoch(epoch);
 
-                    match Payload::GroupInfo(payload)
-                        .into_frame(FrameHeader::new(Opcode::GroupInfo))
-                    {
+                    match Payload::GroupInfo(payload).into_frame(header) {
                         Ok(frame) => ClientAction::Send(frame),
                         Err(e) => ClientAction::Log {
                             message: format!("Failed to create GroupInfo frame: {e:?}"),
@@ -999,6 +2114,13 @@ impl<E: Environment> Client<E> {
     }
 }
 
+/// Sender key secret exported from the group's current epoch.
+fn sender_key_secret<E: Environment>(mls_group: &MlsGroup<E>) -> Result<Vec<u8>, ClientError> {
+    mls_group
+        .export_secret(SENDER_KEY_LABEL, SENDER_KEY_CONTEXT, SENDER_KEY_SECRET_SIZE)
+        .map_err(|e| ClientError::Mls { reason: e.to_string() })
+}
+
 fn crypto_to_proto_encrypted(crypto: &CryptoEncryptedMessage) -> EncryptedMessage {
     EncryptedMessage {
         epoch: crypto.epoch,
@@ -1007,6 +2129,8 @@ fn crypto_to_proto_encrypted(crypto: &CryptoEncryptedMessage) -> EncryptedMessag
         nonce: crypto.nonce,
         ciphertext: crypto.ciphertext.clone(),
         push_keys: None, // Not implemented yet
+        message_id: None,
+        expires_at: None,
     }
 }
 
@@ -1037,6 +2161,7 @@ mod tests {
     use std::time::Duration;
 
     use lockframe_core::env::test_utils::MockEnv;
+    use lockframe_proto::payloads::session::{Goodbye, SyncSnapshot};
 
     use super::*;
 
@@ -1044,7 +2169,7 @@ mod tests {
     fn create_client() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let client: Client<MockEnv> = Client::new(env, identity);
+        let client: Client<MockEnv> = Client::new(env, identity, ClientConfig::default());
 
         assert_eq!(client.sender_id(), 42);
         assert_eq!(client.room_count(), 0);
@@ -1054,7 +2179,7 @@ mod tests {
     fn create_room() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id = 0x1234_5678_9abc_def0_u128;
         let actions = client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1064,11 +2189,31 @@ mod tests {
         assert!(!actions.is_empty());
     }
 
+    #[test]
+    fn snapshot_debug_omits_mls_state() {
+        let env = MockEnv::with_crypto_rng();
+        let mut client = Client::new(env, ClientIdentity::new(42), ClientConfig::default());
+
+        let actions = client.handle(ClientEvent::CreateRoom { room_id: 0x1234 }).unwrap();
+        let snapshot = actions
+            .iter()
+            .find_map(|a| match a {
+                ClientAction::PersistRoom(snapshot) => Some(snapshot),
+                _ => None,
+            })
+            .unwrap();
+
+        let debug = format!("{snapshot:?}");
+        assert!(!snapshot.mls_state.is_empty());
+        assert!(!debug.contains(&format!("{:?}", snapshot.mls_state)));
+        assert!(debug.contains(&format!("mls_state_len: {}", snapshot.mls_state.len())));
+    }
+
     #[test]
     fn create_duplicate_room_fails() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id = 0x1234_u128;
         client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1081,7 +2226,7 @@ mod tests {
     fn send_message_to_unknown_room_fails() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let result = client.handle(ClientEvent::SendMessage {
             room_id: 0x9999_u128,
@@ -1095,7 +2240,7 @@ mod tests {
     fn leave_room() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id = 0x1234_u128;
         client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1110,17 +2255,109 @@ mod tests {
     fn leave_unknown_room_fails() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let result = client.handle(ClientEvent::LeaveRoom { room_id: 0x9999_u128 });
         assert!(matches!(result, Err(ClientError::RoomNotFound { .. })));
     }
 
+    #[test]
+    fn room_full_error_frame_is_typed_error() {
+        let env = MockEnv::new();
+        let identity = ClientIdentity::new(42);
+        let mut client = Client::new(env, identity, ClientConfig::default());
+
+        let room_id = 0x1234_u128;
+        let mut frame = Payload::Error(ErrorPayload::room_full(room_id, 2))
+            .into_frame(FrameHeader::new(Opcode::Error))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+
+        let result = client.handle(ClientEvent::FrameReceived(frame));
+        assert!(matches!(result, Err(ClientError::RoomFull { room_id: 0x1234, .. })));
+    }
+
+    #[test]
+    fn error_frames_become_server_error_actions() {
+        let env = MockEnv::new();
+        let identity = ClientIdentity::new(42);
+        let mut client = Client::new(env, identity, ClientConfig::default());
+
+        let room_id = 0x1234_u128;
+        let cases = [
+            (ErrorPayload::frame_rejected("bad"), ErrorPayload::FRAME_REJECTED),
+            (ErrorPayload::room_not_found(room_id), ErrorPayload::ROOM_NOT_FOUND),
+            (ErrorPayload::storage_error("disk"), ErrorPayload::STORAGE_ERROR),
+            (ErrorPayload::invalid_payload("cbor"), ErrorPayload::INVALID_PAYLOAD),
+            (ErrorPayload::mls_error("epoch"), ErrorPayload::MLS_ERROR),
+            (ErrorPayload::sequencer_error("gap"), ErrorPayload::SEQUENCER_ERROR),
+            (ErrorPayload::keypackage_not_found(7), ErrorPayload::KEYPACKAGE_NOT_FOUND),
+            (ErrorPayload::unauthenticated("login"), ErrorPayload::UNAUTHENTICATED),
+            (ErrorPayload::unsupported_version(2, 1), ErrorPayload::UNSUPPORTED_VERSION),
+            (ErrorPayload::forbidden("admin only"), ErrorPayload::FORBIDDEN),
+        ];
+
+        for (payload, expected_code) in cases {
+            let expected_message = payload.message.clone();
+            let mut frame =
+                Payload::Error(payload).into_frame(FrameHeader::new(Opcode::Error)).unwrap();
+            frame.header.set_room_id(room_id);
+
+            let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+            assert!(
+                matches!(
+                    actions.as_slice(),
+                    [ClientAction::ServerError { room_id: 0x1234, code, message, retry_after: None }]
+                        if *code == expected_code && *message == expected_message
+                ),
+                "code {expected_code:#06x} produced {actions:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn server_error_carries_retry_after() {
+        let env = MockEnv::new();
+        let identity = ClientIdentity::new(42);
+        let mut client = Client::new(env, identity, ClientConfig::default());
+
+        let payload = ErrorPayload {
+            code: ErrorPayload::FRAME_REJECTED,
+            message: "slow down".to_string(),
+            retry_after: Some(5),
+        };
+        let frame = Payload::Error(payload).into_frame(FrameHeader::new(Opcode::Error)).unwrap();
+
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(matches!(actions.as_slice(), [ClientAction::ServerError {
+            room_id: 0,
+            retry_after: Some(5),
+            ..
+        }]));
+    }
+
+    #[test]
+    fn goodbye_produces_disconnected() {
+        let env = MockEnv::new();
+        let identity = ClientIdentity::new(42);
+        let mut client = Client::new(env, identity, ClientConfig::default());
+
+        let goodbye = Goodbye { reason: "server shutting down".to_string() };
+        let frame =
+            Payload::Goodbye(goodbye).into_frame(FrameHeader::new(Opcode::Goodbye)).unwrap();
+
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(matches!(
+            actions.as_slice(),
+            [ClientAction::Disconnected { reason }] if reason == "server shutting down"
+        ));
+    }
+
     #[test]
     fn send_message_produces_encrypted_frame() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id = 0x1234_u128;
         client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1129,8 +2366,14 @@ mod tests {
             .handle(ClientEvent::SendMessage { room_id, plaintext: b"Hello, World!".to_vec() })
             .unwrap();
 
-        // Should produce a Send action with encrypted frame
-        assert_eq!(actions.len(), 1);
+        // Should produce a Send action with encrypted frame, then report the
+        // queued message
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(actions[1], ClientAction::MessageQueued {
+            room_id: 0x1234,
+            log_index: None,
+            ..
+        }));
         match &actions[0] {
             ClientAction::Send(frame) => {
                 assert_eq!(frame.header.opcode_enum(), Some(Opcode::AppMessage));
@@ -1149,7 +2392,7 @@ mod tests {
     fn app_message_with_invalid_signature_is_rejected() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id = 0x1234_u128;
         client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1180,7 +2423,7 @@ mod tests {
 
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id = 0x1234_u128;
         client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1208,11 +2451,105 @@ mod tests {
         assert_eq!(room.sender_keys.generation(0), Some(1)); // Now at gen 1
     }
 
+    #[test]
+    fn commit_timeout_is_configurable() {
+        let env = MockEnv::new();
+        let config =
+            ClientConfig { commit_timeout: Duration::from_secs(1), ..ClientConfig::default() };
+        let mut client = Client::new(env.clone(), ClientIdentity::new(42), config);
+
+        let room_id = 0x1234_u128;
+        client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+        client.handle(ClientEvent::SelfUpdate { room_id }).unwrap();
+
+        let requests_sync = |actions: &[ClientAction]| {
+            actions.iter().any(|a| matches!(a, ClientAction::RequestSync { .. }))
+        };
+
+        env.advance_time(Duration::from_millis(999));
+        let actions = client.handle(ClientEvent::Tick { now: env.now() }).unwrap();
+        assert!(!requests_sync(&actions), "commit timed out early");
+
+        env.advance_time(Duration::from_millis(1));
+        let actions = client.handle(ClientEvent::Tick { now: env.now() }).unwrap();
+        assert!(requests_sync(&actions), "commit should time out after 1s");
+        assert!(!client.rooms[&room_id].mls_group.has_pending_commit());
+    }
+
+    #[test]
+    fn pending_joins_evict_oldest_key_package() {
+        let config = ClientConfig { max_pending_joins: 2, ..ClientConfig::default() };
+        let mut client = Client::new(MockEnv::new(), ClientIdentity::new(42), config);
+
+        let (_, first) = client.generate_key_package().unwrap();
+        let (_, second) = client.generate_key_package().unwrap();
+        let (_, third) = client.generate_key_package().unwrap();
+
+        assert_eq!(client.pending_joins.len(), 2);
+        assert!(!client.pending_joins.contains_key(&first), "oldest should be evicted");
+        assert!(client.pending_joins.contains_key(&second));
+        assert!(client.pending_joins.contains_key(&third));
+        assert_eq!(client.pending_join_order, [second, third]);
+    }
+
+    #[test]
+    fn pending_adds_limit_rejects_new_adds() {
+        let config = ClientConfig { max_pending_adds: 1, ..ClientConfig::default() };
+        let mut client = Client::new(MockEnv::new(), ClientIdentity::new(42), config);
+        let room_id = 0x1234_u128;
+        client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+
+        client.handle(ClientEvent::FetchAndAddMember { room_id, user_id: 1 }).unwrap();
+        let result = client.handle(ClientEvent::FetchAndAddMember { room_id, user_id: 2 });
+        assert!(matches!(result, Err(ClientError::PendingLimitReached { limit: 1, .. })));
+        assert!(!client.pending_adds.contains_key(&(room_id, 2)));
+
+        // Retrying an add that is already pending is not a new entry
+        client.handle(ClientEvent::FetchAndAddMember { room_id, user_id: 1 }).unwrap();
+        assert_eq!(client.pending_adds.len(), 1);
+    }
+
+    #[test]
+    fn pending_external_joins_limit_rejects_new_joins() {
+        let config = ClientConfig { max_pending_external_joins: 1, ..ClientConfig::default() };
+        let mut client = Client::new(MockEnv::new(), ClientIdentity::new(42), config);
+
+        client.handle(ClientEvent::ExternalJoin { room_id: 1 }).unwrap();
+        let result = client.handle(ClientEvent::ExternalJoin { room_id: 2 });
+        assert!(matches!(result, Err(ClientError::PendingLimitReached { limit: 1, .. })));
+
+        client.handle(ClientEvent::ExternalJoin { room_id: 1 }).unwrap();
+        assert_eq!(client.pending_external_joins.len(), 1);
+    }
+
+    #[test]
+    fn pending_external_joins_clear_on_error_and_timeout() {
+        let env = MockEnv::new();
+        let mut client = Client::new(env.clone(), ClientIdentity::new(42), ClientConfig::default());
+
+        // The server has no GroupInfo for the room
+        client.handle(ClientEvent::ExternalJoin { room_id: 1 }).unwrap();
+        let mut frame = Payload::Error(ErrorPayload::room_not_found(1))
+            .into_frame(FrameHeader::new(Opcode::Error))
+            .unwrap();
+        frame.header.set_room_id(1);
+        client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(client.pending_external_joins.is_empty());
+
+        // The request is never answered
+        client.handle(ClientEvent::ExternalJoin { room_id: 2 }).unwrap();
+        client.handle(ClientEvent::Tick { now: env.now() }).unwrap();
+        assert!(client.pending_external_joins.contains_key(&2));
+        env.advance_time(ClientConfig::default().group_info_fetch_timeout + Duration::from_secs(1));
+        client.handle(ClientEvent::Tick { now: env.now() }).unwrap();
+        assert!(client.pending_external_joins.is_empty());
+    }
+
     #[test]
     fn pending_adds_timeout_cleanup() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env.clone(), identity);
+        let mut client = Client::new(env.clone(), identity, ClientConfig::default());
 
         let room_id = 0x1234_u128;
         client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1226,7 +2563,9 @@ mod tests {
 
         // Simulate time passing beyond timeout
         let mut current_time = env.now();
-        current_time = current_time + KEY_PACKAGE_FETCH_TIMEOUT + Duration::from_secs(1);
+        current_time = current_time
+            + ClientConfig::default().key_package_fetch_timeout
+            + Duration::from_secs(1);
         let actions = client.handle(ClientEvent::Tick { now: current_time }).unwrap();
 
         // Verify pending add was cleaned up
@@ -1242,7 +2581,7 @@ mod tests {
     fn pending_adds_multiple_rooms_same_user() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id1 = 0x1234_u128;
         let room_id2 = 0x5678_u128;
@@ -1269,35 +2608,97 @@ mod tests {
             .into_frame(FrameHeader::new(Opcode::KeyPackageFetch))
             .unwrap();
 
+        // A bad package is refused for every room before any add commit is built
         let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        let mut rejected: Vec<RoomId> = actions
+            .iter()
+            .map(|a| match a {
+                ClientAction::InviteeRejected { room_id, user_id: 123, .. } => *room_id,
+                other => panic!("expected InviteeRejected, got {other:?}"),
+            })
+            .collect();
+        rejected.sort_unstable();
+        assert_eq!(rejected, vec![room_id1, room_id2]);
 
         // Verify both pending adds were cleaned up
         assert!(!client.pending_adds.contains_key(&(room_id1, user_id)));
         assert!(!client.pending_adds.contains_key(&(room_id2, user_id)));
         assert_eq!(client.pending_adds.len(), 0);
+    }
 
-        // Verify both rooms were processed and failed with invalid KeyPackage
-        assert!(actions.iter().any(|action| {
-            matches!(action, ClientAction::Log { message } if
-                message.contains(&format!("Failed to add user {user_id} to room {room_id1:x}")))
-        }));
-        assert!(actions.iter().any(|action| {
-            matches!(action, ClientAction::Log { message } if
-                message.contains(&format!("Failed to add user {user_id} to room {room_id2:x}")))
-        }));
+    #[test]
+    fn fetched_key_package_is_validated_before_add() {
+        let mut alice = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(1),
+            ClientConfig::default(),
+        );
+        let mut bob = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(2),
+            ClientConfig::default(),
+        );
+        let room_id = 0x42_u128;
+        alice.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+
+        let fetched = |user_id, key_package_bytes| {
+            Payload::KeyPackageFetch(KeyPackageFetchPayload {
+                user_id,
+                key_package_bytes,
+                hash_ref: Vec::new(),
+            })
+            .into_frame(FrameHeader::new(Opcode::KeyPackageFetch))
+            .unwrap()
+        };
+
+        // Tampered signature: refused with a typed error, nothing committed
+        let (mut tampered, _) = bob.generate_key_package().unwrap();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        alice.handle(ClientEvent::FetchAndAddMember { room_id, user_id: 2 }).unwrap();
+        let actions = alice.handle(ClientEvent::FrameReceived(fetched(2, tampered))).unwrap();
+        assert!(
+            matches!(actions[..], [ClientAction::InviteeRejected {
+                room_id: 0x42,
+                user_id: 2,
+                ..
+            }]),
+            "{actions:?}"
+        );
+        assert!(!alice.rooms[&room_id].mls_group.has_pending_commit());
+
+        // Bob's package served for another user is refused too
+        let (kp_bytes, _) = bob.generate_key_package().unwrap();
+        alice.handle(ClientEvent::FetchAndAddMember { room_id, user_id: 3 }).unwrap();
+        let actions =
+            alice.handle(ClientEvent::FrameReceived(fetched(3, kp_bytes.clone()))).unwrap();
+        assert!(
+            matches!(actions[..], [ClientAction::InviteeRejected { user_id: 3, .. }]),
+            "{actions:?}"
+        );
 
-        // Verify exactly 2 failure actions (one for each room)
-        let failure_actions: Vec<_> = actions.iter().filter(|action| {
-            matches!(action, ClientAction::Log { message } if message.contains("Failed to add user"))
-        }).collect();
-        assert_eq!(failure_actions.len(), 2);
+        // A valid package is reported, then committed
+        alice.handle(ClientEvent::FetchAndAddMember { room_id, user_id: 2 }).unwrap();
+        let actions = alice.handle(ClientEvent::FrameReceived(fetched(2, kp_bytes))).unwrap();
+        let validated = actions
+            .iter()
+            .position(|a| matches!(a, ClientAction::InviteeValidated { room_id: 0x42, user_id: 2, ciphersuite }
+                if *ciphersuite == u16::from(lockframe_core::mls::CIPHERSUITE)))
+            .expect("InviteeValidated");
+        let commit = actions
+            .iter()
+            .position(|a| {
+                matches!(a, ClientAction::Send(f) if f.header.opcode_enum() == Some(Opcode::Commit))
+            })
+            .expect("add commit");
+        assert!(validated < commit);
+        assert!(actions.iter().any(|a| matches!(a, ClientAction::MemberAdded { user_id: 2, .. })));
     }
 
     #[test]
     fn welcome_without_pending_keypackage_emits_keypackage_needed() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         // No pending KeyPackage - client never called generate_key_package
 
@@ -1323,7 +2724,7 @@ mod tests {
     fn welcome_with_wrong_keypackage_emits_keypackage_needed() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         // Generate a KeyPackage (creates pending state)
         let (_kp_bytes, _hash_ref) = client.generate_key_package().unwrap();
@@ -1351,7 +2752,7 @@ mod tests {
     fn welcome_to_existing_room_returns_error() {
         let env = MockEnv::new();
         let identity = ClientIdentity::new(42);
-        let mut client = Client::new(env, identity);
+        let mut client = Client::new(env, identity, ClientConfig::default());
 
         let room_id = 0x1234_u128;
         client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
@@ -1366,4 +2767,451 @@ mod tests {
         // This should be a hard error (protocol violation)
         assert!(matches!(result, Err(ClientError::RoomAlreadyExists { .. })));
     }
+
+    #[test]
+    fn remove_members_emits_member_removed() {
+        let mut alice = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(1),
+            ClientConfig::default(),
+        );
+        let mut bob = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(2),
+            ClientConfig::default(),
+        );
+        let room_id = 0x42_u128;
+
+        alice.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+        let (kp_bytes, _hash_ref) = bob.generate_key_package().unwrap();
+
+        let actions = alice
+            .handle(ClientEvent::AddMembers { room_id, key_packages: vec![kp_bytes] })
+            .unwrap();
+        let commit = actions
+            .iter()
+            .find_map(|a| match a {
+                ClientAction::Send(f) if f.header.opcode_enum() == Some(Opcode::Commit) => {
+                    Some(f.clone())
+                },
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(alice.member_count(room_id), Some(1));
+        alice.handle(ClientEvent::FrameReceived(commit)).unwrap();
+        assert_eq!(alice.member_count(room_id), Some(2));
+        assert_eq!(alice.member_count(0x99), None);
+
+        let actions =
+            alice.handle(ClientEvent::RemoveMembers { room_id, member_ids: vec![2] }).unwrap();
+        let commit = actions
+            .iter()
+            .find_map(|a| match a {
+                ClientAction::Send(f) if f.header.opcode_enum() == Some(Opcode::Commit) => {
+                    Some(f.clone())
+                },
+                _ => None,
+            })
+            .unwrap();
+        alice.handle(ClientEvent::FrameReceived(commit)).unwrap();
+        assert_eq!(alice.member_count(room_id), Some(1));
+        assert!(
+            actions
+                .iter()
+                .any(|a| matches!(a, ClientAction::MemberRemoved { room_id: r, user_id: 2 } if *r == room_id)),
+            "Expected MemberRemoved, got: {actions:?}"
+        );
+    }
+
+    #[test]
+    fn room_list_request_and_response() {
+        let env = MockEnv::new();
+        let mut client = Client::new(env, ClientIdentity::new(42), ClientConfig::default());
+
+        let actions = client.handle(ClientEvent::RequestRoomList).unwrap();
+        assert!(matches!(
+            &actions[..],
+            [ClientAction::Send(frame)] if frame.header.opcode_enum() == Some(Opcode::RoomListRequest)
+        ));
+
+        let response =
+            lockframe_proto::payloads::session::RoomListResponse { room_ids: vec![1, 2] };
+        let frame = Payload::RoomListResponse(response)
+            .into_frame(FrameHeader::new(Opcode::RoomListResponse))
+            .unwrap();
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(matches!(
+            &actions[..],
+            [ClientAction::RoomListReceived { room_ids }] if room_ids == &vec![1, 2]
+        ));
+    }
+
+    fn sync_response_frame(room_id: RoomId, log_indices: &[u64], has_more: bool) -> Frame {
+        let frames = log_indices
+            .iter()
+            .map(|&log_index| {
+                let mut header = FrameHeader::new(Opcode::AppMessage);
+                header.set_room_id(room_id);
+                header.set_log_index(log_index);
+                let mut buf = Vec::new();
+                Frame::new(header, Vec::new()).encode(&mut buf).unwrap();
+                buf
+            })
+            .collect();
+        let response = SyncResponse { frames, has_more, server_epoch: 0, snapshot: None };
+        let mut frame = Payload::SyncResponse(response)
+            .into_frame(FrameHeader::new(Opcode::SyncResponse))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        frame
+    }
+
+    fn requests_sync(actions: &[ClientAction]) -> bool {
+        actions.iter().any(|a| matches!(a, ClientAction::RequestSync { .. }))
+    }
+
+    #[test]
+    fn sync_stalls_when_pages_do_not_advance() {
+        let mut client =
+            Client::new(MockEnv::new(), ClientIdentity::new(42), ClientConfig::default());
+        let room_id = 0x42_u128;
+
+        // The first page advances, so the client asks for more
+        let frame = sync_response_frame(room_id, &[0, 1], true);
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(requests_sync(&actions));
+
+        // The server repeats the same page and still claims more
+        let frame = sync_response_frame(room_id, &[0, 1], true);
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(!requests_sync(&actions));
+        assert!(
+            actions.iter().any(
+                |a| matches!(a, ClientAction::SyncStalled { room_id: r, .. } if *r == room_id)
+            )
+        );
+
+        // An empty page that claims more is also a stall
+        let frame = sync_response_frame(room_id, &[], true);
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(actions.iter().any(|a| matches!(a, ClientAction::SyncStalled { .. })));
+    }
+
+    #[test]
+    fn sync_behind_snapshot_rejoins_room() {
+        let mut client = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(42),
+            ClientConfig::default(),
+        );
+        let room_id = 0x42_u128;
+        client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+
+        let snapshot = SyncSnapshot { first_log_index: 5, epoch: 3, group_info: vec![1] };
+        let response = SyncResponse {
+            frames: Vec::new(),
+            has_more: true,
+            server_epoch: 3,
+            snapshot: Some(snapshot),
+        };
+        let mut frame = Payload::SyncResponse(response)
+            .into_frame(FrameHeader::new(Opcode::SyncResponse))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(!requests_sync(&actions));
+        assert!(actions.iter().any(|a| matches!(
+            a,
+            ClientAction::Send(f) if f.header.opcode_enum() == Some(Opcode::GroupInfoRequest)
+        )));
+        assert!(client.pending_external_joins.contains_key(&room_id));
+    }
+
+    #[test]
+    fn sync_behind_snapshot_reports_removal_when_rejoin_fails() {
+        let config = ClientConfig { max_pending_external_joins: 1, ..ClientConfig::default() };
+        let mut client = Client::new(MockEnv::with_crypto_rng(), ClientIdentity::new(42), config);
+        let room_id = 0x42_u128;
+        client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+        client.handle(ClientEvent::ExternalJoin { room_id: 0x43 }).unwrap();
+
+        let snapshot = SyncSnapshot { first_log_index: 5, epoch: 3, group_info: vec![1] };
+        let response = SyncResponse {
+            frames: Vec::new(),
+            has_more: true,
+            server_epoch: 3,
+            snapshot: Some(snapshot),
+        };
+        let mut frame = Payload::SyncResponse(response)
+            .into_frame(FrameHeader::new(Opcode::SyncResponse))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(
+            actions.iter().any(
+                |a| matches!(a, ClientAction::RoomRemoved { room_id: r, .. } if *r == room_id)
+            )
+        );
+        assert!(!actions.iter().any(|a| matches!(a, ClientAction::Send(_))));
+        assert!(!client.rooms.contains_key(&room_id));
+        assert!(!client.pending_external_joins.contains_key(&room_id));
+    }
+
+    #[test]
+    fn sync_stops_after_max_pages() {
+        let mut client =
+            Client::new(MockEnv::new(), ClientIdentity::new(42), ClientConfig::default());
+        let room_id = 0x42_u128;
+
+        for page in 0..u64::from(MAX_SYNC_PAGES) - 1 {
+            let frame = sync_response_frame(room_id, &[page], true);
+            let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+            assert!(requests_sync(&actions), "page {page} should request more");
+        }
+
+        let frame = sync_response_frame(room_id, &[u64::from(MAX_SYNC_PAGES)], true);
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(!requests_sync(&actions));
+        assert!(actions.iter().any(|a| matches!(a, ClientAction::SyncStalled { .. })));
+
+        // A completed sync resets the guard for the next one
+        let frame = sync_response_frame(room_id, &[0], false);
+        client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        let frame = sync_response_frame(room_id, &[0], true);
+        let actions = client.handle(ClientEvent::FrameReceived(frame)).unwrap();
+        assert!(requests_sync(&actions));
+    }
+
+    #[cfg(feature = "epoch-history")]
+    #[test]
+    fn epoch_history_records_each_commit() {
+        let mut alice = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(1),
+            ClientConfig::default(),
+        );
+        let room_id = 0x42_u128;
+        alice.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+
+        for user_id in [2, 3] {
+            let mut joiner = Client::new(
+                MockEnv::with_crypto_rng(),
+                ClientIdentity::new(user_id),
+                ClientConfig::default(),
+            );
+            let (kp_bytes, _hash_ref) = joiner.generate_key_package().unwrap();
+            let actions = alice
+                .handle(ClientEvent::AddMembers { room_id, key_packages: vec![kp_bytes] })
+                .unwrap();
+            let commit = actions
+                .iter()
+                .find_map(|a| match a {
+                    ClientAction::Send(f) if f.header.opcode_enum() == Some(Opcode::Commit) => {
+                        Some(f.clone())
+                    },
+                    _ => None,
+                })
+                .unwrap();
+            alice.handle(ClientEvent::FrameReceived(commit)).unwrap();
+        }
+
+        let history = alice.epoch_history(room_id).unwrap();
+        assert_eq!(history.iter().map(|(epoch, _)| *epoch).collect::<Vec<_>>(), vec![0, 1, 2]);
+        let hashes: std::collections::HashSet<_> = history.iter().map(|(_, hash)| *hash).collect();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(alice.epoch_history(0x99), None);
+    }
+
+    #[test]
+    fn commit_that_skips_epochs_rekeys_and_syncs() {
+        let room_id = 0x1234_u128;
+        let client = |user_id| {
+            Client::new(
+                MockEnv::with_crypto_rng(),
+                ClientIdentity::new(user_id),
+                ClientConfig::default(),
+            )
+        };
+        let sent = |actions: &[ClientAction], opcode| {
+            actions
+                .iter()
+                .find_map(|a| match a {
+                    ClientAction::Send(f) if f.header.opcode_enum() == Some(opcode) => {
+                        Some(f.clone())
+                    },
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        let mut alice = client(1);
+        let mut bob = client(2);
+        alice.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+        let (kp_bytes, _hash_ref) = bob.generate_key_package().unwrap();
+        let actions = alice
+            .handle(ClientEvent::AddMembers { room_id, key_packages: vec![kp_bytes] })
+            .unwrap();
+        alice.handle(ClientEvent::FrameReceived(sent(&actions, Opcode::Commit))).unwrap();
+        bob.handle(ClientEvent::FrameReceived(sent(&actions, Opcode::Welcome))).unwrap();
+        assert_eq!(bob.epoch(room_id), Some(1));
+
+        let mut commits = Vec::new();
+        for _ in 0..2 {
+            let actions = alice.handle(ClientEvent::SelfUpdate { room_id }).unwrap();
+            let commit = sent(&actions, Opcode::Commit);
+            alice.handle(ClientEvent::FrameReceived(commit.clone())).unwrap();
+            commits.push(commit);
+        }
+
+        // Bob's group takes the first commit without rekeying, so the next
+        // commit lands two epochs past his sender keys
+        let room = bob.rooms.get_mut(&room_id).unwrap();
+        room.mls_group.process_message(&commits[0]).unwrap();
+        assert_eq!(room.sender_keys.epoch(), 1);
+
+        let actions = bob.handle(ClientEvent::FrameReceived(commits[1].clone())).unwrap();
+        assert!(actions.iter().any(|a| matches!(a, ClientAction::RequestSync {
+            from_epoch: 1,
+            to_epoch: 3,
+            ..
+        })));
+        let room = &bob.rooms[&room_id];
+        assert_eq!(room.sender_keys.epoch(), 3);
+        assert_eq!(room.sender_keys.member_count(), 2);
+
+        // Keys match Alice's again
+        let actions = alice
+            .handle(ClientEvent::SendMessage { room_id, plaintext: b"rekeyed".to_vec() })
+            .unwrap();
+        let actions =
+            bob.handle(ClientEvent::FrameReceived(sent(&actions, Opcode::AppMessage))).unwrap();
+        assert!(actions.iter().any(|a| matches!(
+            a,
+            ClientAction::DeliverMessage { plaintext, .. } if plaintext == b"rekeyed"
+        )));
+    }
+
+    #[test]
+    fn group_info_is_exported_once_per_epoch() {
+        let room_id = 0x1234_u128;
+        let mut alice = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(1),
+            ClientConfig::default(),
+        );
+        alice.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+
+        let first = alice.group_info(room_id).unwrap().as_ptr();
+        let second = alice.group_info(room_id).unwrap().as_ptr();
+        assert_eq!(first, second, "same epoch must reuse the cached bytes");
+        let before = alice.group_info(room_id).unwrap().to_vec();
+
+        let actions = alice.handle(ClientEvent::SelfUpdate { room_id }).unwrap();
+        let commit = actions
+            .iter()
+            .find_map(|a| match a {
+                ClientAction::Send(f) if f.header.opcode_enum() == Some(Opcode::Commit) => {
+                    Some(f.clone())
+                },
+                _ => None,
+            })
+            .unwrap();
+        let actions = alice.handle(ClientEvent::FrameReceived(commit)).unwrap();
+        let published = actions
+            .iter()
+            .find_map(|a| match a {
+                ClientAction::Send(f) if f.header.opcode_enum() == Some(Opcode::GroupInfo) => {
+                    match Payload::from_frame(f).unwrap() {
+                        Payload::GroupInfo(info) => Some(info),
+                        _ => None,
+                    }
+                },
+                _ => None,
+            })
+            .unwrap();
+
+        // The commit's published GroupInfo replaces the cache
+        assert_eq!(published.epoch, 1);
+        assert_eq!(alice.rooms[&room_id].group_info.as_ref().map(|(epoch, _)| *epoch), Some(1));
+        let after = alice.group_info(room_id).unwrap().to_vec();
+        assert_eq!(after, published.group_info_bytes);
+        assert_ne!(after, before);
+    }
+
+    #[test]
+    fn commits_report_member_diffs() {
+        let room_id = 0x1234_u128;
+        let client = |user_id| {
+            Client::new(
+                MockEnv::with_crypto_rng(),
+                ClientIdentity::new(user_id),
+                ClientConfig::default(),
+            )
+        };
+        let sent = |actions: &[ClientAction], opcode| {
+            actions
+                .iter()
+                .find_map(|a| match a {
+                    ClientAction::Send(f) if f.header.opcode_enum() == Some(opcode) => {
+                        Some(f.clone())
+                    },
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let diffs = |actions: &[ClientAction]| -> Vec<(Vec<u64>, Vec<u64>)> {
+            actions
+                .iter()
+                .filter_map(|a| match a {
+                    ClientAction::MembersChanged { room_id: r, added, removed }
+                        if *r == room_id =>
+                    {
+                        Some((added.clone(), removed.clone()))
+                    },
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let mut alice = client(1);
+        let mut bob = client(2);
+        let mut carol = client(3);
+        alice.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+
+        let (kp_bytes, _hash_ref) = bob.generate_key_package().unwrap();
+        let actions = alice
+            .handle(ClientEvent::AddMembers { room_id, key_packages: vec![kp_bytes] })
+            .unwrap();
+        let commit = sent(&actions, Opcode::Commit);
+        bob.handle(ClientEvent::FrameReceived(sent(&actions, Opcode::Welcome))).unwrap();
+        let actions = alice.handle(ClientEvent::FrameReceived(commit)).unwrap();
+        assert_eq!(diffs(&actions), vec![(vec![2], vec![])]);
+
+        let (kp_bytes, _hash_ref) = carol.generate_key_package().unwrap();
+        let actions = alice
+            .handle(ClientEvent::AddMembers { room_id, key_packages: vec![kp_bytes] })
+            .unwrap();
+        let commit = sent(&actions, Opcode::Commit);
+        carol.handle(ClientEvent::FrameReceived(sent(&actions, Opcode::Welcome))).unwrap();
+        let actions = alice.handle(ClientEvent::FrameReceived(commit.clone())).unwrap();
+        assert_eq!(diffs(&actions), vec![(vec![3], vec![])]);
+        let actions = bob.handle(ClientEvent::FrameReceived(commit)).unwrap();
+        assert_eq!(diffs(&actions), vec![(vec![3], vec![])]);
+
+        let actions =
+            alice.handle(ClientEvent::RemoveMembers { room_id, member_ids: vec![2] }).unwrap();
+        let commit = sent(&actions, Opcode::Commit);
+        let actions = alice.handle(ClientEvent::FrameReceived(commit.clone())).unwrap();
+        assert_eq!(diffs(&actions), vec![(vec![], vec![2])]);
+        let actions = carol.handle(ClientEvent::FrameReceived(commit)).unwrap();
+        assert_eq!(diffs(&actions), vec![(vec![], vec![2])]);
+
+        // Commits that keep the member set emit nothing
+        let actions = alice.handle(ClientEvent::SelfUpdate { room_id }).unwrap();
+        let actions =
+            alice.handle(ClientEvent::FrameReceived(sent(&actions, Opcode::Commit))).unwrap();
+        assert!(diffs(&actions).is_empty());
+    }
 }
@@ -0,0 +1,297 @@
+//! Transport-independent connection handle.
+//!
+//! [`ConnectedClient`] bridges channels of [`Frame`]s to anything that can
+//! send and receive whole frames, described by [`FrameTransport`]. QUIC and
+//! WebSocket transports live in [`crate::transport`]; [`StreamFrames`] adapts
+//! any byte stream pair (TCP, a simulated socket, an in-memory duplex).
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use lockframe_core::env::Environment;
+use lockframe_proto::{Frame, FrameHeader};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::{Mutex, mpsc, watch},
+};
+
+use crate::Resend;
+
+/// Transport errors.
+#[derive(Debug, Error)]
+pub enum TransportError {
+    /// Connection failed.
+    #[error("connection failed: {0}")]
+    Connection(String),
+
+    /// Stream error.
+    #[error("stream error: {0}")]
+    Stream(String),
+
+    /// Protocol error.
+    #[error("protocol error: {0}")]
+    Protocol(String),
+}
+
+/// Frame-level connection to a server.
+///
+/// Sending and receiving may run concurrently from different tasks.
+#[async_trait]
+pub trait FrameTransport: Send + Sync + 'static {
+    /// Send one frame.
+    async fn send_frame(&self, frame: &Frame) -> Result<(), TransportError>;
+
+    /// Receive the next frame, or `None` once the connection has closed.
+    async fn recv_frame(&self) -> Result<Option<Frame>, TransportError>;
+
+    /// Close the connection. Later sends fail and receives return `None`.
+    fn close(&self);
+}
+
+#[async_trait]
+impl FrameTransport for Box<dyn FrameTransport> {
+    async fn send_frame(&self, frame: &Frame) -> Result<(), TransportError> {
+        (**self).send_frame(frame).await
+    }
+
+    async fn recv_frame(&self) -> Result<Option<Frame>, TransportError> {
+        (**self).recv_frame().await
+    }
+
+    fn close(&self) {
+        (**self).close();
+    }
+}
+
+/// What happened to a connection's 0-RTT early data.
+///
+/// Only QUIC sends early data; other transports leave this at the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EarlyDataStatus {
+    /// The connection resumed a session and tried 0-RTT.
+    pub attempted: bool,
+    /// Whether the server accepted the early data, once the handshake is done.
+    ///
+    /// Rejected early frames are sent again after the handshake.
+    pub accepted: Option<bool>,
+    /// Opcodes of frames sent as early data, in order.
+    pub sent: Vec<u16>,
+    /// Opcodes of frames held back until the handshake finished, in order.
+    pub deferred: Vec<u16>,
+}
+
+/// Handle to a connected client.
+///
+/// Provides channels for frame transport. Frames are sent/received via
+/// the channels, and an internal task drives the [`FrameTransport`].
+pub struct ConnectedClient<T: FrameTransport = Box<dyn FrameTransport>> {
+    /// Send frames to the server.
+    pub to_server: mpsc::Sender<Frame>,
+    /// Receive frames from the server.
+    pub from_server: mpsc::Receiver<Frame>,
+    /// Connection errors. Receiving an error means the connection is dead.
+    pub errors: mpsc::Receiver<TransportError>,
+    /// 0-RTT outcome. Stays at the default unless 0-RTT was attempted.
+    pub early_data: watch::Receiver<EarlyDataStatus>,
+    /// The underlying transport.
+    transport: Arc<T>,
+    /// Abort handle to stop the connection task.
+    abort_handle: tokio::task::AbortHandle,
+}
+
+impl<T: FrameTransport> ConnectedClient<T> {
+    /// Start bridging `transport` to channels.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn new(transport: T) -> Self {
+        Self::with_early_data(transport, watch::channel(EarlyDataStatus::default()).1)
+    }
+
+    /// Start bridging `transport`, reporting 0-RTT through `early_data`.
+    pub(crate) fn with_early_data(
+        transport: T,
+        early_data: watch::Receiver<EarlyDataStatus>,
+    ) -> Self {
+        let transport = Arc::new(transport);
+        let (to_server_tx, to_server_rx) = mpsc::channel::<Frame>(32);
+        let (from_server_tx, from_server_rx) = mpsc::channel::<Frame>(32);
+        let (error_tx, error_rx) = mpsc::channel::<TransportError>(1);
+
+        let handle = tokio::spawn(run_connection(
+            Arc::clone(&transport),
+            to_server_rx,
+            from_server_tx,
+            error_tx,
+        ));
+
+        Self {
+            to_server: to_server_tx,
+            from_server: from_server_rx,
+            errors: error_rx,
+            early_data,
+            transport,
+            abort_handle: handle.abort_handle(),
+        }
+    }
+
+    /// The underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Stop the connection.
+    pub fn stop(&self) {
+        self.transport.close();
+        self.abort_handle.abort();
+    }
+
+    /// Wait out a rate-limited frame's advised delay, then send it again.
+    ///
+    /// Record the resend with [`crate::SendRetry::resent`] afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TransportError::Stream` if the connection task has stopped.
+    pub async fn resend<E: Environment>(
+        &self,
+        env: &E,
+        resend: &Resend,
+    ) -> Result<(), TransportError> {
+        resend.wait(env).await;
+        self.to_server
+            .send(resend.frame.clone())
+            .await
+            .map_err(|e| TransportError::Stream(format!("channel send failed: {e}")))
+    }
+}
+
+/// Run the connection, bridging between channels and the transport.
+async fn run_connection<T: FrameTransport>(
+    transport: Arc<T>,
+    mut to_server: mpsc::Receiver<Frame>,
+    from_server: mpsc::Sender<Frame>,
+    errors: mpsc::Sender<TransportError>,
+) {
+    let reader = Arc::clone(&transport);
+    let recv_errors = errors.clone();
+    let recv_handle = tokio::spawn(async move {
+        loop {
+            match reader.recv_frame().await {
+                Ok(Some(frame)) => {
+                    if from_server.send(frame).await.is_err() {
+                        break;
+                    }
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = recv_errors.send(e).await;
+                    break;
+                },
+            }
+        }
+    });
+
+    while let Some(frame) = to_server.recv().await {
+        if let Err(e) = transport.send_frame(&frame).await {
+            let _ = errors.send(e).await;
+            break;
+        }
+    }
+
+    transport.close();
+    recv_handle.abort();
+}
+
+/// Frames over a byte stream pair.
+///
+/// Frames are written back to back; each one's header announces its size.
+pub struct StreamFrames<W, R> {
+    send: Mutex<W>,
+    recv: Mutex<(R, BytesMut)>,
+    closed: watch::Sender<bool>,
+}
+
+impl<W, R> StreamFrames<W, R>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    /// Wrap the send and receive halves of a stream.
+    pub fn new(send: W, recv: R) -> Self {
+        Self {
+            send: Mutex::new(send),
+            recv: Mutex::new((recv, BytesMut::with_capacity(65536))),
+            closed: watch::channel(false).0,
+        }
+    }
+}
+
+#[async_trait]
+impl<W, R> FrameTransport for StreamFrames<W, R>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    async fn send_frame(&self, frame: &Frame) -> Result<(), TransportError> {
+        if *self.closed.borrow() {
+            return Err(TransportError::Stream("connection closed".to_string()));
+        }
+        let mut buf = Vec::new();
+        frame
+            .encode(&mut buf)
+            .map_err(|e| TransportError::Protocol(format!("encode failed: {e}")))?;
+        self.send
+            .lock()
+            .await
+            .write_all(&buf)
+            .await
+            .map_err(|e| TransportError::Stream(format!("write failed: {e}")))
+    }
+
+    async fn recv_frame(&self) -> Result<Option<Frame>, TransportError> {
+        let mut closed = self.closed.subscribe();
+        let mut recv = self.recv.lock().await;
+        let (stream, buf) = &mut *recv;
+        tokio::select! {
+            read = read_frame_bytes(stream, buf) => {
+                if !read.map_err(|e| TransportError::Stream(format!("read failed: {e}")))? {
+                    return Ok(None);
+                }
+                Frame::decode(buf)
+                    .map(Some)
+                    .map_err(|e| TransportError::Protocol(format!("frame decode failed: {e}")))
+            },
+            _ = closed.wait_for(|closed| *closed) => Ok(None),
+        }
+    }
+
+    fn close(&self) {
+        self.closed.send_replace(true);
+    }
+}
+
+/// Read one encoded frame into `buf`.
+///
+/// Returns `false` when the stream ends before a new frame starts.
+pub(crate) async fn read_frame_bytes<R>(recv: &mut R, buf: &mut BytesMut) -> std::io::Result<bool>
+where
+    R: AsyncRead + Unpin,
+{
+    buf.clear();
+    buf.resize(FrameHeader::SIZE, 0);
+    match recv.read_exact(&mut buf[..]).await {
+        Ok(_) => {},
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+        Err(e) => return Err(e),
+    }
+
+    let body_size = FrameHeader::from_bytes(buf)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?
+        .body_size();
+
+    buf.resize(FrameHeader::SIZE + body_size, 0);
+    recv.read_exact(&mut buf[FrameHeader::SIZE..]).await?;
+    Ok(true)
+}
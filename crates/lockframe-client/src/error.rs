@@ -63,6 +63,39 @@ pub enum ClientError {
         /// Target epoch to sync to.
         target_epoch: u64,
     },
+
+    /// Too many operations of one kind are awaiting a server response.
+    #[error("too many pending {kind} (limit {limit})")]
+    PendingLimitReached {
+        /// Kind of pending operation (e.g. "adds").
+        kind: &'static str,
+        /// Configured limit.
+        limit: usize,
+    },
+
+    /// Server refused to add a member because the room is at its cap.
+    #[error("room full: {room_id:x}: {reason}")]
+    RoomFull {
+        /// Room that is full.
+        room_id: RoomId,
+        /// Server's explanation.
+        reason: String,
+    },
+
+    /// Frame's HLC timestamp is too far ahead of the local wall clock.
+    #[error(
+        "clock skew: frame from {sender_id} stamped {timestamp}ms, local clock {local_time}ms (max skew {max_skew_ms}ms)"
+    )]
+    ClockSkew {
+        /// Sender whose clock is ahead.
+        sender_id: u64,
+        /// Frame's HLC timestamp (Unix milliseconds).
+        timestamp: u64,
+        /// Local wall clock when the frame arrived (Unix milliseconds).
+        local_time: u64,
+        /// Configured tolerance in milliseconds.
+        max_skew_ms: u64,
+    },
 }
 
 impl ClientError {
@@ -82,7 +115,10 @@ impl ClientError {
             Self::RoomNotFound { .. }
             | Self::RoomAlreadyExists { .. }
             | Self::EpochMismatch { .. }
-            | Self::SyncRequired { .. } => false,
+            | Self::SyncRequired { .. }
+            | Self::PendingLimitReached { .. }
+            | Self::RoomFull { .. }
+            | Self::ClockSkew { .. } => false,
         }
     }
 }
@@ -109,6 +145,30 @@ mod tests {
         assert!(!err.is_fatal());
     }
 
+    #[test]
+    fn pending_limit_is_transient() {
+        let err = ClientError::PendingLimitReached { kind: "adds", limit: 4 };
+        assert!(!err.is_fatal());
+        assert_eq!(err.to_string(), "too many pending adds (limit 4)");
+    }
+
+    #[test]
+    fn room_full_is_not_fatal() {
+        let err = ClientError::RoomFull { room_id: 0x10, reason: "full".to_string() };
+        assert!(!err.is_fatal());
+    }
+
+    #[test]
+    fn clock_skew_is_transient() {
+        let err = ClientError::ClockSkew {
+            sender_id: 2,
+            timestamp: 10_001,
+            local_time: 5_000,
+            max_skew_ms: 5_000,
+        };
+        assert!(!err.is_fatal());
+    }
+
     #[test]
     fn error_display() {
         let err = ClientError::EpochMismatch { expected: 5, actual: 3 };
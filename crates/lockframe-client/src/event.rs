@@ -34,6 +34,64 @@ pub enum ClientEvent<I = std::time::Instant> {
         plaintext: Vec<u8>,
     },
 
+    /// Application wants to send a message that disappears at `expires_at`.
+    ///
+    /// The server stops serving the message once it expires, and every
+    /// member, the sender included, receives [`ClientAction::MessageExpired`].
+    SendExpiringMessage {
+        /// Target room.
+        room_id: RoomId,
+        /// Message plaintext.
+        plaintext: Vec<u8>,
+        /// Wall-clock expiry, Unix milliseconds.
+        expires_at: u64,
+    },
+
+    /// Application wants to edit a message it previously sent.
+    EditMessage {
+        /// Target room.
+        room_id: RoomId,
+        /// Log index of the message being edited.
+        target_log_index: u64,
+        /// Replacement plaintext.
+        plaintext: Vec<u8>,
+    },
+
+    /// Application wants to redact a message in a room.
+    ///
+    /// The sequenced redaction is echoed back, so the sender receives
+    /// [`ClientAction::MessageRedacted`] like every other member.
+    RedactMessage {
+        /// Target room.
+        room_id: RoomId,
+        /// Log index of the message being redacted.
+        target_log_index: u64,
+        /// Reason shown alongside the tombstone.
+        reason: String,
+    },
+
+    /// Application wants to change a room's name or topic.
+    ///
+    /// `None` keeps the current value. The server only sequences the change
+    /// from room admins; everyone, the sender included, then receives
+    /// [`ClientAction::RoomMetaChanged`].
+    SetRoomMeta {
+        /// Target room.
+        room_id: RoomId,
+        /// New display name.
+        name: Option<String>,
+        /// New topic.
+        topic: Option<String>,
+    },
+
+    /// Application's local user started or stopped typing.
+    SetTyping {
+        /// Target room.
+        room_id: RoomId,
+        /// Whether the user is typing.
+        is_typing: bool,
+    },
+
     /// Application wants to create a new room.
     CreateRoom {
         /// Room ID to create.
@@ -70,6 +128,15 @@ pub enum ClientEvent<I = std::time::Instant> {
         member_ids: Vec<u64>,
     },
 
+    /// Application wants to rotate this client's key material in a room.
+    ///
+    /// Sends an MLS self-update commit that advances the epoch without
+    /// changing membership.
+    SelfUpdate {
+        /// Target room.
+        room_id: RoomId,
+    },
+
     /// Publish our `KeyPackage` to the server registry.
     ///
     /// This makes our `KeyPackage` available for other clients to fetch
@@ -88,6 +155,11 @@ pub enum ClientEvent<I = std::time::Instant> {
         user_id: u64,
     },
 
+    /// Ask the server which rooms the authenticated user has joined.
+    ///
+    /// The reply arrives as [`ClientAction::RoomListReceived`].
+    RequestRoomList,
+
     /// Application wants to join a room via external commit.
     ///
     /// This initiates an external join flow where the client:
@@ -101,7 +173,7 @@ pub enum ClientEvent<I = std::time::Instant> {
 }
 
 /// Serializable snapshot of room state for persistence.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct RoomStateSnapshot {
     /// Room identifier.
     pub room_id: RoomId,
@@ -113,6 +185,18 @@ pub struct RoomStateSnapshot {
     pub my_leaf_index: u32,
 }
 
+/// Elides `mls_state`, which holds the group's secret key schedule.
+impl std::fmt::Debug for RoomStateSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RoomStateSnapshot")
+            .field("room_id", &format!("{:032x}", self.room_id))
+            .field("epoch", &self.epoch)
+            .field("mls_state_len", &self.mls_state.len())
+            .field("my_leaf_index", &self.my_leaf_index)
+            .finish()
+    }
+}
+
 /// Actions the client produces for the caller to execute.
 #[derive(Debug, Clone)]
 pub enum ClientAction {
@@ -133,6 +217,91 @@ pub enum ClientAction {
         timestamp: u64,
     },
 
+    /// One of our messages was queued for sending or sequenced.
+    ///
+    /// Emitted with `log_index: None` alongside the `Send`, then again with
+    /// the server-assigned index when the echo (or a sync) brings it back.
+    /// `message_id` is the same both times, so the application can match the
+    /// two and use the index for a later edit or redaction.
+    MessageQueued {
+        /// Room the message was sent to.
+        room_id: RoomId,
+        /// Random ID carried in the encrypted payload.
+        message_id: u128,
+        /// Log index, once the server has sequenced the message.
+        log_index: Option<u64>,
+    },
+
+    /// Deliver a decrypted edit of an earlier message to the application.
+    MessageEdited {
+        /// Room the edit is from.
+        room_id: RoomId,
+        /// Sender's stable ID (the original message's author).
+        sender_id: u64,
+        /// Log index of the message being edited.
+        target_log_index: u64,
+        /// Decrypted replacement plaintext.
+        plaintext: Vec<u8>,
+        /// Log index of the edit itself.
+        log_index: u64,
+        /// Edit timestamp (HLC).
+        timestamp: u64,
+    },
+
+    /// A message was redacted; the application should replace it with a
+    /// tombstone.
+    MessageRedacted {
+        /// Room the redaction is from.
+        room_id: RoomId,
+        /// Verified sender of the redaction.
+        moderator_id: u64,
+        /// Log index of the redacted message.
+        target_log_index: u64,
+        /// Reason given for the redaction.
+        reason: String,
+        /// Log index of the redaction itself.
+        log_index: u64,
+    },
+
+    /// A delivered message expired; the application should remove it from
+    /// history.
+    MessageExpired {
+        /// Room the message is in.
+        room_id: RoomId,
+        /// Log index of the expired message.
+        log_index: u64,
+    },
+
+    /// A room's name or topic changed.
+    RoomMetaChanged {
+        /// Room that changed.
+        room_id: RoomId,
+        /// Verified sender of the change.
+        sender_id: u64,
+        /// New display name, or `None` if unchanged.
+        name: Option<String>,
+        /// New topic, or `None` if unchanged.
+        topic: Option<String>,
+    },
+
+    /// Another member started or stopped typing.
+    ///
+    /// Ephemeral: never replayed by sync.
+    TypingChanged {
+        /// Room the indicator is for.
+        room_id: RoomId,
+        /// Member who is typing.
+        sender_id: u64,
+        /// Whether they are typing.
+        is_typing: bool,
+    },
+
+    /// Server listed the rooms the authenticated user has joined.
+    RoomListReceived {
+        /// Joined room IDs in ascending order.
+        room_ids: Vec<RoomId>,
+    },
+
     /// Request missing commits for epoch sync.
     ///
     /// The caller should fetch commits from the server and feed
@@ -146,6 +315,31 @@ pub enum ClientAction {
         to_epoch: u64,
     },
 
+    /// Sync for a room was abandoned.
+    ///
+    /// Emitted instead of another [`ClientAction::RequestSync`] when a page
+    /// claimed more frames but did not advance the log, or the sync exceeded
+    /// its page limit. The room may be missing frames.
+    SyncStalled {
+        /// Room whose sync stopped.
+        room_id: RoomId,
+        /// Why the sync was abandoned.
+        reason: String,
+    },
+
+    /// A commit for a later epoch arrived before the ones preceding it.
+    ///
+    /// The commit is held and applied once the missing commits arrive,
+    /// directly or through the accompanying [`ClientAction::RequestSync`].
+    CommitBuffered {
+        /// Room the commit belongs to.
+        room_id: RoomId,
+        /// Epoch the commit applies to.
+        commit_epoch: u64,
+        /// Our epoch when it arrived.
+        room_epoch: u64,
+    },
+
     /// Persist room state.
     ///
     /// The caller decides the storage backend.
@@ -159,16 +353,68 @@ pub enum ClientAction {
         reason: String,
     },
 
+    /// Server rejected a request with an `Error` frame.
+    ///
+    /// Carries the parsed `ErrorPayload` so the application can react to
+    /// the specific code (e.g. back off for `retry_after` seconds).
+    ServerError {
+        /// Room the error refers to (0 for requests outside a room).
+        room_id: RoomId,
+        /// `ErrorPayload` code.
+        code: u16,
+        /// Human-readable message from the server.
+        message: String,
+        /// Seconds the server asks us to wait before retrying.
+        retry_after: Option<u64>,
+    },
+
+    /// Server ended the session with a `Goodbye` frame.
+    ///
+    /// The server closes the connection after sending it. The application
+    /// should show `reason` and reconnect if appropriate.
+    Disconnected {
+        /// Reason the server gave.
+        reason: String,
+    },
+
     /// Log message for debugging.
     Log {
         /// Log message.
         message: String,
     },
 
+    /// Fetched `KeyPackage` passed validation.
+    ///
+    /// Emitted before the add commit, once the package's signatures,
+    /// lifetime and ciphersuite have been checked and its credential names
+    /// `user_id`.
+    InviteeValidated {
+        /// Room the invitee is being added to.
+        room_id: RoomId,
+        /// User the `KeyPackage` belongs to.
+        user_id: u64,
+        /// MLS ciphersuite the `KeyPackage` was built for.
+        ciphersuite: u16,
+    },
+
+    /// Fetched `KeyPackage` can't be used to add the invitee to a room.
+    ///
+    /// Emitted for every room waiting on the package. Nothing is committed;
+    /// the invitee must publish a new `KeyPackage`.
+    InviteeRejected {
+        /// Room the invitee was to be added to.
+        room_id: RoomId,
+        /// User the `KeyPackage` was fetched for.
+        user_id: u64,
+        /// Why the `KeyPackage` was refused.
+        reason: String,
+    },
+
     /// Member was added to a room.
     ///
     /// Emitted after successfully fetching a `KeyPackage` and adding
-    /// the member via MLS.
+    /// the member via MLS, before the commit is sequenced. Its
+    /// [`ClientAction::MembersChanged`] follows once it is.
     MemberAdded {
         /// Room the member was added to.
         room_id: RoomId,
@@ -176,6 +422,31 @@ pub enum ClientAction {
         user_id: u64,
     },
 
+    /// Member was removed from a room.
+    ///
+    /// Emitted after committing the removal via MLS, before the commit is
+    /// sequenced. Its [`ClientAction::MembersChanged`] follows once it is.
+    MemberRemoved {
+        /// Room the member was removed from.
+        room_id: RoomId,
+        /// User ID that was removed.
+        user_id: u64,
+    },
+
+    /// A commit changed who is in a room.
+    ///
+    /// Emitted once per applied commit, own or remote, that adds or removes
+    /// members, with the difference between the member sets before and after
+    /// it. Both lists are sorted.
+    MembersChanged {
+        /// Room whose membership changed.
+        room_id: RoomId,
+        /// Users who joined with the commit.
+        added: Vec<u64>,
+        /// Users who left or were removed with the commit.
+        removed: Vec<u64>,
+    },
+
     /// `KeyPackage` was published successfully.
     KeyPackagePublished,
 
@@ -16,29 +16,73 @@
 //! - [`SenderKeyStore`]: Per-room sender key ratchet management
 //! - [`ClientEvent`]: Events fed into the client
 //! - [`ClientAction`]: Actions produced by the client
+//! - [`SendRetry`]: Resends frames the server rate-limited
+//!
+//! # Connection (optional)
+//!
+//! With the `connection` feature enabled, this crate provides
+//! [`connection::ConnectedClient`], which drives any
+//! [`connection::FrameTransport`] from a background task, and
+//! [`connection::StreamFrames`] for plain byte streams.
 //!
 //! # Transport (optional)
 //!
 //! With the `transport` feature enabled, this crate also provides:
-//! - [`transport::ConnectedClient`]: Client with QUIC transport
+//! - [`transport::QuicFrames`]: QUIC frame transport
 //! - [`transport::connect`]: Connect to a server (development mode)
 //! - [`transport::connect_with_config`]: Connect with custom TLS configuration
 //! - [`transport::TlsMode`]: Secure or insecure TLS verification
 //! - [`transport::TransportConfig`]: Transport configuration options
+//! - [`transport::Connector`]: Reusable connector that can resume with 0-RTT
+//! - [`transport::ws::WsTransport`]: WebSocket transport for networks that
+//!   block QUIC
+//!
+//! # Epoch history (optional)
+//!
+//! With the `epoch-history` feature enabled, [`Client::epoch_history`] returns
+//! the `(epoch, tree_hash)` of every epoch a room passed through on this
+//! client, for auditing and debugging divergence.
+//!
+//! # Key export (optional)
+//!
+//! With the `key-export` feature enabled, [`Client::export_room_keys`] hands
+//! out a [`RoomKeyBundle`] that lets another client decrypt a room at the
+//! current epoch via [`Client::import_room_keys`], for bridges and gateways,
+//! and [`SenderKeyStore::decrypt_with_key_export`] returns the one-time key
+//! each message was decrypted with, for compliance archives. Both hand out
+//! secret key material.
+//!
+//! # Transcript (optional)
+//!
+//! With the `transcript` feature enabled, [`Client::set_transcript`] attaches
+//! a [`Transcript`] that writes every event and its resulting actions as
+//! newline-delimited JSON, with secrets redacted.
 
 mod client;
 mod error;
 mod event;
+mod retry;
+#[cfg(feature = "key-export")]
+mod room_keys;
 mod sender_key_store;
+#[cfg(feature = "transcript")]
+mod transcript;
 
+#[cfg(feature = "connection")]
+pub mod connection;
 #[cfg(feature = "transport")]
 pub mod transport;
 
-pub use client::{Client, ClientIdentity};
+pub use client::{Client, ClientConfig, ClientIdentity};
 pub use error::ClientError;
 pub use event::{ClientAction, ClientEvent, RoomStateSnapshot};
 pub use lockframe_core::{
     env::Environment,
     mls::{MemberId, RoomId},
 };
+pub use retry::{DEFAULT_MAX_SEND_ATTEMPTS, Resend, SendRetry};
+#[cfg(feature = "key-export")]
+pub use room_keys::RoomKeyBundle;
 pub use sender_key_store::SenderKeyStore;
+#[cfg(feature = "transcript")]
+pub use transcript::Transcript;
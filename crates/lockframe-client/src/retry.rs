@@ -0,0 +1,213 @@
+//! Resending frames the server rate-limited.
+//!
+//! The server answers every sequenced room frame, either by echoing it back
+//! with its log index or with an `Error` frame carrying the frame's
+//! `request_id`. Answers can arrive in any order: echoed control frames
+//! (commits, welcomes) overtake queued application traffic, and rooms are
+//! processed independently. [`SendRetry`] keeps unanswered frames by
+//! `request_id`, which [`crate::Client`] gives every frame it sends, and
+//! settles each when its echo or error arrives. A `RATE_LIMITED` error turns
+//! into a [`Resend`] that the driver waits out with [`Resend::wait`] before
+//! sending the frame again.
+
+use std::{collections::HashMap, time::Duration};
+
+use lockframe_core::env::Environment;
+use lockframe_proto::{Frame, Opcode, Payload, payloads::ErrorPayload};
+
+/// Default number of times a frame is sent before giving up.
+pub const DEFAULT_MAX_SEND_ATTEMPTS: u32 = 3;
+
+/// Delay used when a `RATE_LIMITED` error carries no `retry_after`.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A rate-limited frame to send again once `delay` has passed.
+#[derive(Debug, Clone)]
+pub struct Resend {
+    /// Frame exactly as it was first sent.
+    pub frame: Frame,
+    /// How long the server asked us to wait.
+    pub delay: Duration,
+    /// Send attempt this resend will be (the first send is attempt 1).
+    pub attempt: u32,
+}
+
+impl Resend {
+    /// Sleep for the advised delay.
+    pub async fn wait<E: Environment>(&self, env: &E) {
+        env.sleep(self.delay).await;
+    }
+}
+
+#[derive(Debug)]
+struct InFlight {
+    frame: Frame,
+    attempt: u32,
+}
+
+/// Tracks sent room frames until the server answers them.
+#[derive(Debug)]
+pub struct SendRetry {
+    max_attempts: u32,
+    in_flight: HashMap<u32, InFlight>,
+}
+
+impl SendRetry {
+    /// Create a tracker that sends each frame at most `max_attempts` times.
+    pub fn new(max_attempts: u32) -> Self {
+        Self { max_attempts: max_attempts.max(1), in_flight: HashMap::new() }
+    }
+
+    /// Record a frame just sent to the server.
+    ///
+    /// Ephemeral frames (typing, receipts, presence) are not echoed to their
+    /// sender and are ignored, as are frames outside a room and frames without
+    /// a `request_id`.
+    pub fn sent(&mut self, frame: &Frame) {
+        self.track(frame.clone(), 1);
+    }
+
+    /// Record that `resend` was sent again.
+    pub fn resent(&mut self, resend: Resend) {
+        self.track(resend.frame, resend.attempt);
+    }
+
+    /// Match a frame from the server against the frame it answers.
+    ///
+    /// Returns a [`Resend`] when the server rate-limited that frame and it
+    /// has attempts left. Echoes and other errors settle the frame.
+    pub fn on_frame(&mut self, frame: &Frame) -> Option<Resend> {
+        let request_id = frame.header.request_id();
+        let pending = self.in_flight.get(&request_id)?;
+
+        if frame.header.opcode_enum() != Some(Opcode::Error) {
+            // Other members' frames carry their own request ids
+            let echo = frame.header.sender_id() == pending.frame.header.sender_id()
+                && frame.header.signature() == pending.frame.header.signature();
+            if echo {
+                self.in_flight.remove(&request_id);
+            }
+            return None;
+        }
+
+        let pending = self.in_flight.remove(&request_id)?;
+        match Payload::from_frame(frame) {
+            Ok(Payload::Error(error))
+                if error.code == ErrorPayload::RATE_LIMITED
+                    && pending.attempt < self.max_attempts =>
+            {
+                let delay = error.retry_after.map_or(DEFAULT_RETRY_DELAY, Duration::from_secs);
+                Some(Resend { frame: pending.frame, delay, attempt: pending.attempt + 1 })
+            },
+            _ => None,
+        }
+    }
+
+    /// Number of frames still waiting for the server's answer.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    fn track(&mut self, frame: Frame, attempt: u32) {
+        let request_id = frame.header.request_id();
+        let ephemeral = matches!(
+            frame.header.opcode_enum(),
+            Some(Opcode::Typing | Opcode::AppReceipt | Opcode::Presence)
+        );
+        if frame.header.room_id() == 0 || request_id == 0 || ephemeral {
+            return;
+        }
+        self.in_flight.insert(request_id, InFlight { frame, attempt });
+    }
+}
+
+impl Default for SendRetry {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SEND_ATTEMPTS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lockframe_core::mls::RoomId;
+    use lockframe_proto::FrameHeader;
+
+    use super::*;
+
+    const ROOM: RoomId = 0x42;
+
+    fn sent_frame(opcode: Opcode, request_id: u32) -> Frame {
+        let mut header = FrameHeader::new(opcode);
+        header.set_room_id(ROOM);
+        header.set_sender_id(1);
+        header.set_request_id(request_id);
+        header.set_signature([request_id as u8; 64]);
+        Frame::new(header, &b"ciphertext"[..])
+    }
+
+    fn error_frame(request_id: u32, error: ErrorPayload) -> Frame {
+        let mut frame = Payload::Error(error).into_frame(FrameHeader::new(Opcode::Error)).unwrap();
+        frame.header.set_room_id(ROOM);
+        frame.header.set_request_id(request_id);
+        frame
+    }
+
+    #[test]
+    fn rate_limited_frame_is_resent_until_attempts_run_out() {
+        let mut retry = SendRetry::new(2);
+        retry.sent(&sent_frame(Opcode::AppMessage, 1));
+
+        let resend = retry.on_frame(&error_frame(1, ErrorPayload::rate_limited(7))).unwrap();
+        assert_eq!(resend.delay, Duration::from_secs(7));
+        assert_eq!(resend.attempt, 2);
+        assert_eq!(resend.frame.header.request_id(), 1);
+
+        retry.resent(resend);
+        assert!(retry.on_frame(&error_frame(1, ErrorPayload::rate_limited(7))).is_none());
+        assert_eq!(retry.in_flight(), 0);
+    }
+
+    #[test]
+    fn errors_settle_the_frame_they_name() {
+        let mut retry = SendRetry::default();
+        retry.sent(&sent_frame(Opcode::AppMessage, 1));
+        retry.sent(&sent_frame(Opcode::AppMessage, 2));
+
+        let resend = retry.on_frame(&error_frame(2, ErrorPayload::rate_limited(1))).unwrap();
+        assert_eq!(resend.frame.header.request_id(), 2);
+        assert!(retry.on_frame(&error_frame(1, ErrorPayload::frame_rejected("no"))).is_none());
+        assert_eq!(retry.in_flight(), 0);
+
+        // Errors for untracked requests are ignored
+        assert!(retry.on_frame(&error_frame(3, ErrorPayload::rate_limited(1))).is_none());
+    }
+
+    #[test]
+    fn echoes_settle_only_our_own_frames() {
+        let mut retry = SendRetry::default();
+        retry.sent(&sent_frame(Opcode::AppMessage, 1));
+        retry.sent(&sent_frame(Opcode::Commit, 2));
+
+        // Another member's frame that happens to reuse our request id
+        let mut other = sent_frame(Opcode::AppMessage, 1);
+        other.header.set_sender_id(2);
+        assert!(retry.on_frame(&other).is_none());
+        assert_eq!(retry.in_flight(), 2);
+
+        let mut commit = sent_frame(Opcode::Commit, 2);
+        commit.header.set_log_index(9);
+        assert!(retry.on_frame(&commit).is_none());
+        assert_eq!(retry.in_flight(), 1);
+
+        let resend = retry.on_frame(&error_frame(1, ErrorPayload::rate_limited(1))).unwrap();
+        assert_eq!(resend.frame.header.opcode_enum(), Some(Opcode::AppMessage));
+    }
+
+    #[test]
+    fn untracked_frames_are_ignored() {
+        let mut retry = SendRetry::default();
+        retry.sent(&sent_frame(Opcode::Typing, 1));
+        retry.sent(&sent_frame(Opcode::AppMessage, 0));
+        assert_eq!(retry.in_flight(), 0);
+    }
+}
@@ -0,0 +1,116 @@
+//! Room key export for bridges and gateways.
+//!
+//! A [`RoomKeyBundle`] carries everything needed to verify and decrypt one
+//! room's application messages at one epoch: the sender key secret exported
+//! from the MLS group, the leaf index of every member, and the public group
+//! state (epoch, tree hash, member list and member signing keys). It carries
+//! no private MLS keys, so a client that imports it can read the room but
+//! cannot send, commit, or follow the room into the next epoch.
+//!
+//! # Security
+//!
+//! The sender key secret decrypts every message any member sends in that
+//! epoch. Anyone holding a bundle can read the room as if they were a member,
+//! and MLS forward secrecy no longer protects that epoch. Only hand bundles
+//! to components you would trust with a member's device, transfer them over
+//! an authenticated, encrypted channel, and drop them once the epoch is over.
+
+use std::{collections::BTreeMap, fmt};
+
+use lockframe_core::mls::{
+    MlsGroupState, MlsValidator, RoomId, ValidationResult, VerifyingKeyCache,
+};
+use lockframe_proto::Frame;
+
+use crate::{error::ClientError, sender_key_store::SenderKeyStore};
+
+/// Sender key material for one room at one epoch.
+///
+/// Sensitive: see the [module documentation](self). `Debug` output omits
+/// the secret.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RoomKeyBundle {
+    /// Room the keys belong to.
+    pub room_id: RoomId,
+
+    /// MLS epoch the keys are valid for.
+    pub epoch: u64,
+
+    /// Sender key secret exported from the MLS key schedule.
+    pub sender_key_secret: Vec<u8>,
+
+    /// Leaf index of each member, keyed by member ID.
+    pub members: BTreeMap<u64, u32>,
+
+    /// Public group state for checking frame signatures.
+    pub group_state: MlsGroupState,
+}
+
+impl fmt::Debug for RoomKeyBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoomKeyBundle")
+            .field("room_id", &self.room_id)
+            .field("epoch", &self.epoch)
+            .field("sender_key_secret_len", &self.sender_key_secret.len())
+            .field("members", &self.members)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Read-only room state built from an imported [`RoomKeyBundle`].
+pub(crate) struct ImportedRoom {
+    /// Epoch of the imported keys.
+    epoch: u64,
+
+    /// Sender ratchets for every member at that epoch.
+    pub(crate) sender_keys: SenderKeyStore,
+
+    /// Member ID at each leaf index.
+    senders: BTreeMap<u32, u64>,
+
+    /// Public keys for signature checks.
+    group_state: MlsGroupState,
+
+    /// Parsed signature keys, so each member's key is parsed once.
+    key_cache: VerifyingKeyCache,
+}
+
+impl ImportedRoom {
+    /// Derive sender ratchets from `bundle`.
+    pub(crate) fn new(bundle: RoomKeyBundle) -> Self {
+        let leaves: Vec<u32> = bundle.members.values().copied().collect();
+        let sender_keys =
+            SenderKeyStore::initialize_epoch(&bundle.sender_key_secret, bundle.epoch, &leaves);
+        let senders = bundle.members.into_iter().map(|(member, leaf)| (leaf, member)).collect();
+
+        Self {
+            epoch: bundle.epoch,
+            sender_keys,
+            senders,
+            group_state: bundle.group_state,
+            key_cache: VerifyingKeyCache::new(),
+        }
+    }
+
+    /// Check a frame's epoch, sender membership, and signature.
+    pub(crate) fn verify(&mut self, frame: &Frame) -> Result<(), ClientError> {
+        let actual = frame.header.epoch();
+        if actual != self.epoch {
+            return Err(ClientError::EpochMismatch { expected: self.epoch, actual });
+        }
+        match MlsValidator::validate_frame(
+            frame,
+            self.epoch,
+            &self.group_state,
+            &mut self.key_cache,
+        ) {
+            ValidationResult::Accept => Ok(()),
+            ValidationResult::Reject { reason } => Err(ClientError::InvalidFrame { reason }),
+        }
+    }
+
+    /// Member ID at `leaf_index`.
+    pub(crate) fn member_id_by_leaf_index(&self, leaf_index: u32) -> Option<u64> {
+        self.senders.get(&leaf_index).copied()
+    }
+}
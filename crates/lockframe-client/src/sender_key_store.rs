@@ -7,8 +7,8 @@
 use std::collections::HashMap;
 
 use lockframe_crypto::{
-    EncryptedMessage, NONCE_RANDOM_SIZE, SenderKeyError, SymmetricRatchet, decrypt_message,
-    derive_sender_key_seed, encrypt_message,
+    EncryptedMessage, MessageKey, NONCE_RANDOM_SIZE, SenderKeyError, SymmetricRatchet,
+    decrypt_message, derive_sender_key_seed, encrypt_message,
 };
 
 /// Manages sender key ratchets for all members in a room.
@@ -35,16 +35,57 @@ impl SenderKeyStore {
     /// Called after MLS commit advances the epoch. Derives fresh
     /// ratchets for all members from the epoch secret.
     pub fn initialize_epoch(epoch_secret: &[u8], epoch: u64, member_indices: &[u32]) -> Self {
-        let mut ratchets = HashMap::with_capacity(member_indices.len());
+        Self::from_seeds(
+            epoch,
+            member_indices.iter().map(|&sender_index| {
+                (sender_index, derive_sender_key_seed(epoch_secret, epoch, sender_index))
+            }),
+        )
+    }
 
-        for &sender_index in member_indices {
-            let seed = derive_sender_key_seed(epoch_secret, epoch, sender_index);
-            ratchets.insert(sender_index, SymmetricRatchet::new(&seed));
-        }
+    /// Build a store for `epoch` from explicit per-sender seeds.
+    ///
+    /// Every ratchet starts at generation 0. Unlike
+    /// [`Self::initialize_epoch`] nothing is derived from MLS, so tests and
+    /// callers with their own key schedule can set up a store directly.
+    /// Stores built from the same seeds produce the same keys.
+    pub fn from_seeds(epoch: u64, seeds: impl IntoIterator<Item = (u32, [u8; 32])>) -> Self {
+        let ratchets = seeds
+            .into_iter()
+            .map(|(sender_index, seed)| (sender_index, SymmetricRatchet::new(&seed)))
+            .collect();
 
         Self { epoch, ratchets }
     }
 
+    /// Initialize sender keys for `epoch` to replace this store.
+    ///
+    /// Moving to another epoch is the same as [`Self::initialize_epoch`].
+    /// Re-initializing this store's own epoch must use the same member set:
+    /// a different one means the caller's view of the group changed without
+    /// an epoch transition, and the keys would silently diverge from every
+    /// other member's.
+    ///
+    /// # Errors
+    ///
+    /// - `SenderKeyError::MemberSetChanged` if `epoch` is this store's epoch
+    ///   and `member_indices` differ from its members
+    pub fn reinitialize(
+        &self,
+        epoch_secret: &[u8],
+        epoch: u64,
+        member_indices: &[u32],
+    ) -> Result<Self, SenderKeyError> {
+        if epoch == self.epoch
+            && (member_indices.len() != self.ratchets.len()
+                || !member_indices.iter().all(|index| self.ratchets.contains_key(index)))
+        {
+            return Err(SenderKeyError::MemberSetChanged { epoch });
+        }
+
+        Ok(Self::initialize_epoch(epoch_secret, epoch, member_indices))
+    }
+
     /// Current MLS epoch for this room.
     pub fn epoch(&self) -> u64 {
         self.epoch
@@ -91,6 +132,33 @@ impl SenderKeyStore {
     /// - `SenderKeyError::DecryptionFailed` if authentication failed (tampering
     ///   or wrong key)
     pub fn decrypt(&mut self, encrypted: &EncryptedMessage) -> Result<Vec<u8>, SenderKeyError> {
+        let message_key = self.message_key(encrypted)?;
+        decrypt_message(encrypted, &message_key)
+    }
+
+    /// Decrypt a message like [`Self::decrypt`], also returning the one-time
+    /// message key that decrypted it.
+    ///
+    /// For deployments that must archive decrypted content: the caller can
+    /// keep the ciphertext with this key, or re-encrypt the plaintext under
+    /// an archival key. Anyone holding the key can read the message, so it
+    /// must never be logged or stored unprotected.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::decrypt`].
+    #[cfg(feature = "key-export")]
+    pub fn decrypt_with_key_export(
+        &mut self,
+        encrypted: &EncryptedMessage,
+    ) -> Result<(Vec<u8>, MessageKey), SenderKeyError> {
+        let message_key = self.message_key(encrypted)?;
+        let plaintext = decrypt_message(encrypted, &message_key)?;
+        Ok((plaintext, message_key))
+    }
+
+    /// Advance the sender's ratchet to the message's generation.
+    fn message_key(&mut self, encrypted: &EncryptedMessage) -> Result<MessageKey, SenderKeyError> {
         if encrypted.epoch != self.epoch {
             return Err(SenderKeyError::EpochMismatch {
                 expected: self.epoch,
@@ -103,8 +171,33 @@ impl SenderKeyStore {
             .get_mut(&encrypted.sender_index)
             .ok_or(SenderKeyError::UnknownSender { sender_index: encrypted.sender_index })?;
 
-        let message_key = ratchet.advance_to(encrypted.generation)?;
-        decrypt_message(encrypted, &message_key)
+        ratchet.advance_to(encrypted.generation)
+    }
+
+    /// Skip a sender's ratchet forward so its next key is for `generation`.
+    ///
+    /// The skipped keys are discarded, as if those messages had been sent
+    /// and received. Skipping to the current generation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// - `SenderKeyError::UnknownSender` if `sender_index` is not in this store
+    /// - `SenderKeyError::RatchetTooFarBehind` if the ratchet is already past
+    ///   `generation`, or `generation` is further ahead than a ratchet may skip
+    pub fn advance_to(&mut self, sender_index: u32, generation: u32) -> Result<(), SenderKeyError> {
+        let ratchet = self
+            .ratchets
+            .get_mut(&sender_index)
+            .ok_or(SenderKeyError::UnknownSender { sender_index })?;
+
+        let current = ratchet.generation();
+        if generation < current {
+            return Err(SenderKeyError::RatchetTooFarBehind { current, requested: generation });
+        }
+        if generation > current {
+            ratchet.advance_to(generation - 1)?;
+        }
+        Ok(())
     }
 
     /// Current generation for a sender's ratchet. `None` if sender not
@@ -236,6 +329,24 @@ mod tests {
         assert!(matches!(result, Err(SenderKeyError::RatchetTooFarBehind { .. })));
     }
 
+    #[cfg(feature = "key-export")]
+    #[test]
+    fn exported_message_key_decrypts_same_ciphertext() {
+        let members = vec![0, 1];
+        let mut sender = SenderKeyStore::initialize_epoch(&test_epoch_secret(), 1, &members);
+        let _ = sender.encrypt(1, b"first", [0; NONCE_RANDOM_SIZE]).unwrap();
+        let encrypted = sender.encrypt(1, b"archived", [7; NONCE_RANDOM_SIZE]).unwrap();
+
+        let mut receiver = SenderKeyStore::initialize_epoch(&test_epoch_secret(), 1, &members);
+        let (plaintext, key) = receiver.decrypt_with_key_export(&encrypted).unwrap();
+        assert_eq!(plaintext, b"archived");
+        assert_eq!(key.generation(), 1);
+
+        // The ratchet has moved on, but the exported key still opens it
+        assert!(receiver.decrypt(&encrypted).is_err());
+        assert_eq!(decrypt_message(&encrypted, &key).unwrap(), b"archived");
+    }
+
     #[test]
     fn different_epochs_produce_different_keys() {
         let members = vec![0];
@@ -250,4 +361,77 @@ mod tests {
         // Same plaintext, different epochs = different ciphertext
         assert_ne!(msg1.ciphertext, msg2.ciphertext);
     }
+
+    #[test]
+    fn reinitializing_epoch_with_different_members_is_rejected() {
+        let epoch_secret = test_epoch_secret();
+        let store = SenderKeyStore::initialize_epoch(&epoch_secret, 1, &[0, 1, 3]);
+
+        // Same epoch, same members (in any order): fine
+        let same = store.reinitialize(&epoch_secret, 1, &[3, 0, 1]).unwrap();
+        assert_eq!(same.member_count(), 3);
+
+        // Same epoch, different members: detected rather than diverging
+        for members in [&[0, 1][..], &[0, 1, 2], &[0, 1, 3, 4]] {
+            assert!(matches!(
+                store.reinitialize(&epoch_secret, 1, members),
+                Err(SenderKeyError::MemberSetChanged { epoch: 1 })
+            ));
+        }
+
+        // A new epoch may have any members
+        let next = store.reinitialize(&epoch_secret, 2, &[0, 1]).unwrap();
+        assert_eq!((next.epoch(), next.member_count()), (2, 2));
+    }
+
+    #[test]
+    fn manually_built_store_round_trips_at_chosen_generation() {
+        let seeds = [(0, [1u8; 32]), (4, [2u8; 32])];
+        let mut sender = SenderKeyStore::from_seeds(7, seeds);
+        let mut receiver = SenderKeyStore::from_seeds(7, seeds);
+        assert_eq!((sender.epoch(), sender.member_count()), (7, 2));
+        assert_eq!(sender.generation(4), Some(0));
+
+        sender.advance_to(4, 20).unwrap();
+        assert_eq!(sender.generation(4), Some(20));
+        let encrypted = sender.encrypt(4, b"at twenty", [3; NONCE_RANDOM_SIZE]).unwrap();
+        assert_eq!((encrypted.epoch, encrypted.sender_index, encrypted.generation), (7, 4, 20));
+
+        // Positioned at the same generation, the receiver decrypts in step
+        receiver.advance_to(4, 20).unwrap();
+        assert_eq!(receiver.decrypt(&encrypted).unwrap(), b"at twenty");
+        assert_eq!(receiver.generation(4), sender.generation(4));
+
+        // Other senders' ratchets are untouched
+        let encrypted = sender.encrypt(0, b"first", [4; NONCE_RANDOM_SIZE]).unwrap();
+        assert_eq!(encrypted.generation, 0);
+        assert_eq!(receiver.decrypt(&encrypted).unwrap(), b"first");
+
+        // Seeds reproduce the same keys as derivation from the same material
+        let derived = derive_sender_key_seed(&test_epoch_secret(), 1, 0);
+        let mut manual = SenderKeyStore::from_seeds(1, [(0, derived)]);
+        let mut initialized = SenderKeyStore::initialize_epoch(&test_epoch_secret(), 1, &[0]);
+        let encrypted = manual.encrypt(0, b"same keys", [5; NONCE_RANDOM_SIZE]).unwrap();
+        assert_eq!(initialized.decrypt(&encrypted).unwrap(), b"same keys");
+    }
+
+    #[test]
+    fn advance_to_rejects_unknown_sender_and_rewinding() {
+        let mut store = SenderKeyStore::from_seeds(1, [(0, [9u8; 32])]);
+
+        store.advance_to(0, 0).unwrap();
+        assert_eq!(store.generation(0), Some(0));
+        store.advance_to(0, 3).unwrap();
+        store.advance_to(0, 3).unwrap();
+        assert_eq!(store.generation(0), Some(3));
+
+        assert!(matches!(
+            store.advance_to(0, 2),
+            Err(SenderKeyError::RatchetTooFarBehind { current: 3, requested: 2 })
+        ));
+        assert!(matches!(
+            store.advance_to(1, 5),
+            Err(SenderKeyError::UnknownSender { sender_index: 1 })
+        ));
+    }
 }
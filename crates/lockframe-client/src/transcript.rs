@@ -0,0 +1,444 @@
+//! Newline-delimited JSON transcript of client events and actions.
+//!
+//! With the `transcript` feature enabled, a [`Transcript`] attached through
+//! [`crate::Client::set_transcript`] records one JSON line per
+//! [`crate::Client::handle`] call: the event, then either the actions it
+//! produced or the error it returned.
+//!
+//! Secrets never reach the writer. Frames are summarized by their routing
+//! header and payload length, and MLS material (Welcomes, `KeyPackages`,
+//! group state) by length only. Message plaintext is replaced by its length
+//! unless the caller opts in with [`Transcript::with_plaintext`].
+
+use std::{fmt, io::Write};
+
+use lockframe_proto::{Frame, Opcode};
+use serde_json::{Value, json};
+
+use crate::{ClientAction, ClientError, ClientEvent};
+
+/// Writes a JSON line per handled event.
+pub struct Transcript {
+    writer: Box<dyn Write + Send>,
+    include_plaintext: bool,
+}
+
+impl Transcript {
+    /// Record to `writer`, with plaintext redacted.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self { writer: Box::new(writer), include_plaintext: false }
+    }
+
+    /// Record message plaintext instead of only its length.
+    ///
+    /// Only for local debugging: the transcript then holds decrypted
+    /// conversation content.
+    #[must_use]
+    pub fn with_plaintext(mut self, include_plaintext: bool) -> Self {
+        self.include_plaintext = include_plaintext;
+        self
+    }
+
+    /// Summarize `event` before it is consumed by the client.
+    pub(crate) fn event_json<I>(&self, event: &ClientEvent<I>) -> Value {
+        match event {
+            ClientEvent::FrameReceived(frame) => {
+                json!({ "type": "FrameReceived", "frame": frame_json(frame) })
+            },
+            ClientEvent::Tick { .. } => json!({ "type": "Tick" }),
+            ClientEvent::SendMessage { room_id, plaintext } => json!({
+                "type": "SendMessage",
+                "room_id": room_hex(*room_id),
+                "plaintext": self.plaintext_json(plaintext),
+            }),
+            ClientEvent::SendExpiringMessage { room_id, plaintext, expires_at } => json!({
+                "type": "SendExpiringMessage",
+                "room_id": room_hex(*room_id),
+                "plaintext": self.plaintext_json(plaintext),
+                "expires_at": expires_at,
+            }),
+            ClientEvent::EditMessage { room_id, target_log_index, plaintext } => json!({
+                "type": "EditMessage",
+                "room_id": room_hex(*room_id),
+                "target_log_index": target_log_index,
+                "plaintext": self.plaintext_json(plaintext),
+            }),
+            ClientEvent::RedactMessage { room_id, target_log_index, reason } => json!({
+                "type": "RedactMessage",
+                "room_id": room_hex(*room_id),
+                "target_log_index": target_log_index,
+                "reason": reason,
+            }),
+            ClientEvent::SetRoomMeta { room_id, name, topic } => json!({
+                "type": "SetRoomMeta",
+                "room_id": room_hex(*room_id),
+                "name": name,
+                "topic": topic,
+            }),
+            ClientEvent::SetTyping { room_id, is_typing } => json!({
+                "type": "SetTyping",
+                "room_id": room_hex(*room_id),
+                "is_typing": is_typing,
+            }),
+            ClientEvent::CreateRoom { room_id } => {
+                json!({ "type": "CreateRoom", "room_id": room_hex(*room_id) })
+            },
+            ClientEvent::JoinRoom { room_id, welcome } => json!({
+                "type": "JoinRoom",
+                "room_id": room_hex(*room_id),
+                "welcome_len": welcome.len(),
+            }),
+            ClientEvent::LeaveRoom { room_id } => {
+                json!({ "type": "LeaveRoom", "room_id": room_hex(*room_id) })
+            },
+            ClientEvent::AddMembers { room_id, key_packages } => json!({
+                "type": "AddMembers",
+                "room_id": room_hex(*room_id),
+                "key_package_count": key_packages.len(),
+            }),
+            ClientEvent::RemoveMembers { room_id, member_ids } => json!({
+                "type": "RemoveMembers",
+                "room_id": room_hex(*room_id),
+                "member_ids": member_ids,
+            }),
+            ClientEvent::SelfUpdate { room_id } => {
+                json!({ "type": "SelfUpdate", "room_id": room_hex(*room_id) })
+            },
+            ClientEvent::PublishKeyPackage => json!({ "type": "PublishKeyPackage" }),
+            ClientEvent::FetchAndAddMember { room_id, user_id } => json!({
+                "type": "FetchAndAddMember",
+                "room_id": room_hex(*room_id),
+                "user_id": user_id,
+            }),
+            ClientEvent::RequestRoomList => json!({ "type": "RequestRoomList" }),
+            ClientEvent::ExternalJoin { room_id } => {
+                json!({ "type": "ExternalJoin", "room_id": room_hex(*room_id) })
+            },
+        }
+    }
+
+    /// Write one line for an event and its outcome.
+    ///
+    /// Write failures are ignored: a broken debug sink must not change how
+    /// the client handles events.
+    pub(crate) fn record(
+        &mut self,
+        event: &Value,
+        result: &Result<Vec<ClientAction>, ClientError>,
+    ) {
+        let line = match result {
+            Ok(actions) => {
+                let actions: Vec<Value> = actions.iter().map(|a| self.action_json(a)).collect();
+                json!({ "event": event, "actions": actions })
+            },
+            Err(error) => json!({ "event": event, "error": error.to_string() }),
+        };
+        let _ = writeln!(self.writer, "{line}");
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn action_json(&self, action: &ClientAction) -> Value {
+        match action {
+            ClientAction::Send(frame) => json!({ "type": "Send", "frame": frame_json(frame) }),
+            ClientAction::DeliverMessage {
+                room_id,
+                sender_id,
+                plaintext,
+                log_index,
+                timestamp,
+            } => {
+                json!({
+                    "type": "DeliverMessage",
+                    "room_id": room_hex(*room_id),
+                    "sender_id": sender_id,
+                    "plaintext": self.plaintext_json(plaintext),
+                    "log_index": log_index,
+                    "timestamp": timestamp,
+                })
+            },
+            ClientAction::MessageQueued { room_id, message_id, log_index } => json!({
+                "type": "MessageQueued",
+                "room_id": room_hex(*room_id),
+                "message_id": format!("{message_id:032x}"),
+                "log_index": log_index,
+            }),
+            ClientAction::MessageEdited {
+                room_id,
+                sender_id,
+                target_log_index,
+                plaintext,
+                log_index,
+                timestamp,
+            } => json!({
+                "type": "MessageEdited",
+                "room_id": room_hex(*room_id),
+                "sender_id": sender_id,
+                "target_log_index": target_log_index,
+                "plaintext": self.plaintext_json(plaintext),
+                "log_index": log_index,
+                "timestamp": timestamp,
+            }),
+            ClientAction::MessageRedacted {
+                room_id,
+                moderator_id,
+                target_log_index,
+                reason,
+                log_index,
+            } => json!({
+                "type": "MessageRedacted",
+                "room_id": room_hex(*room_id),
+                "moderator_id": moderator_id,
+                "target_log_index": target_log_index,
+                "reason": reason,
+                "log_index": log_index,
+            }),
+            ClientAction::MessageExpired { room_id, log_index } => json!({
+                "type": "MessageExpired",
+                "room_id": room_hex(*room_id),
+                "log_index": log_index,
+            }),
+            ClientAction::RoomMetaChanged { room_id, sender_id, name, topic } => json!({
+                "type": "RoomMetaChanged",
+                "room_id": room_hex(*room_id),
+                "sender_id": sender_id,
+                "name": name,
+                "topic": topic,
+            }),
+            ClientAction::TypingChanged { room_id, sender_id, is_typing } => json!({
+                "type": "TypingChanged",
+                "room_id": room_hex(*room_id),
+                "sender_id": sender_id,
+                "is_typing": is_typing,
+            }),
+            ClientAction::RoomListReceived { room_ids } => json!({
+                "type": "RoomListReceived",
+                "room_ids": room_ids.iter().map(|id| room_hex(*id)).collect::<Vec<_>>(),
+            }),
+            ClientAction::RequestSync { room_id, from_epoch, to_epoch } => json!({
+                "type": "RequestSync",
+                "room_id": room_hex(*room_id),
+                "from_epoch": from_epoch,
+                "to_epoch": to_epoch,
+            }),
+            ClientAction::SyncStalled { room_id, reason } => json!({
+                "type": "SyncStalled",
+                "room_id": room_hex(*room_id),
+                "reason": reason,
+            }),
+            ClientAction::CommitBuffered { room_id, commit_epoch, room_epoch } => json!({
+                "type": "CommitBuffered",
+                "room_id": room_hex(*room_id),
+                "commit_epoch": commit_epoch,
+                "room_epoch": room_epoch,
+            }),
+            ClientAction::PersistRoom(snapshot) => json!({
+                "type": "PersistRoom",
+                "room_id": room_hex(snapshot.room_id),
+                "epoch": snapshot.epoch,
+                "mls_state_len": snapshot.mls_state.len(),
+            }),
+            ClientAction::RoomRemoved { room_id, reason } => json!({
+                "type": "RoomRemoved",
+                "room_id": room_hex(*room_id),
+                "reason": reason,
+            }),
+            ClientAction::ServerError { room_id, code, message, retry_after } => json!({
+                "type": "ServerError",
+                "room_id": room_hex(*room_id),
+                "code": code,
+                "message": message,
+                "retry_after": retry_after,
+            }),
+            ClientAction::Disconnected { reason } => {
+                json!({ "type": "Disconnected", "reason": reason })
+            },
+            ClientAction::Log { message } => json!({ "type": "Log", "message": message }),
+            ClientAction::InviteeValidated { room_id, user_id, ciphersuite } => json!({
+                "type": "InviteeValidated",
+                "room_id": room_hex(*room_id),
+                "user_id": user_id,
+                "ciphersuite": ciphersuite,
+            }),
+            ClientAction::InviteeRejected { room_id, user_id, reason } => json!({
+                "type": "InviteeRejected",
+                "room_id": room_hex(*room_id),
+                "user_id": user_id,
+                "reason": reason,
+            }),
+            ClientAction::MemberAdded { room_id, user_id } => json!({
+                "type": "MemberAdded",
+                "room_id": room_hex(*room_id),
+                "user_id": user_id,
+            }),
+            ClientAction::MemberRemoved { room_id, user_id } => json!({
+                "type": "MemberRemoved",
+                "room_id": room_hex(*room_id),
+                "user_id": user_id,
+            }),
+            ClientAction::MembersChanged { room_id, added, removed } => json!({
+                "type": "MembersChanged",
+                "room_id": room_hex(*room_id),
+                "added": added,
+                "removed": removed,
+            }),
+            ClientAction::KeyPackagePublished => json!({ "type": "KeyPackagePublished" }),
+            ClientAction::KeyPackageNeeded { reason } => {
+                json!({ "type": "KeyPackageNeeded", "reason": reason })
+            },
+            ClientAction::RoomJoined { room_id, epoch } => json!({
+                "type": "RoomJoined",
+                "room_id": room_hex(*room_id),
+                "epoch": epoch,
+            }),
+        }
+    }
+
+    fn plaintext_json(&self, plaintext: &[u8]) -> Value {
+        if self.include_plaintext {
+            json!(String::from_utf8_lossy(plaintext))
+        } else {
+            json!({ "redacted_len": plaintext.len() })
+        }
+    }
+}
+
+impl fmt::Debug for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transcript")
+            .field("writer", &"<dyn Write>")
+            .field("include_plaintext", &self.include_plaintext)
+            .finish()
+    }
+}
+
+/// Routing header and payload size of a frame. The payload itself is
+/// ciphertext or MLS material and is never written.
+///
+/// Welcome frames carry a `recipient_id` where other frames have a
+/// `log_index`.
+fn frame_json(frame: &Frame) -> Value {
+    let header = &frame.header;
+    let mut value = json!({
+        "opcode": header.opcode_enum().map_or_else(
+            || format!("{:#06x}", header.opcode()),
+            |opcode| format!("{opcode:?}"),
+        ),
+        "room_id": room_hex(header.room_id()),
+        "sender_id": header.sender_id(),
+        "epoch": header.epoch(),
+        "payload_len": frame.payload.len(),
+    });
+    if header.opcode_enum() == Some(Opcode::Welcome) {
+        value["recipient_id"] = json!(header.recipient_id());
+    } else {
+        value["log_index"] = json!(header.log_index());
+    }
+    value
+}
+
+/// Room IDs don't fit a JSON number, so they are written as hex.
+fn room_hex(room_id: u128) -> String {
+    format!("{room_id:032x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use lockframe_core::env::test_utils::MockEnv;
+
+    use super::*;
+    use crate::{Client, ClientConfig, ClientIdentity};
+
+    /// Writer whose bytes stay readable after the client takes ownership.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn create_and_send(transcript: Transcript) {
+        let mut client = Client::new(
+            MockEnv::with_crypto_rng(),
+            ClientIdentity::new(7),
+            ClientConfig::default(),
+        );
+        client.set_transcript(transcript);
+
+        let room_id = 0x42;
+        client.handle(ClientEvent::CreateRoom { room_id }).unwrap();
+        client
+            .handle(ClientEvent::SendMessage { room_id, plaintext: b"secret hello".to_vec() })
+            .unwrap();
+        let result = client.handle(ClientEvent::CreateRoom { room_id });
+        assert!(result.is_err());
+    }
+
+    fn lines(buf: &SharedBuf) -> Vec<Value> {
+        let bytes = buf.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn records_create_and_send_with_plaintext_redacted() {
+        let buf = SharedBuf::default();
+        create_and_send(Transcript::new(buf.clone()));
+
+        let raw = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        assert!(!raw.contains("secret hello"));
+
+        let lines = lines(&buf);
+        assert_eq!(lines.len(), 3);
+
+        assert_eq!(lines[0]["event"]["type"], "CreateRoom");
+        assert_eq!(lines[0]["event"]["room_id"], format!("{:032x}", 0x42));
+        let created: Vec<&Value> =
+            lines[0]["actions"].as_array().unwrap().iter().map(|a| &a["type"]).collect();
+        assert!(created.contains(&&json!("PersistRoom")));
+
+        assert_eq!(lines[1]["event"]["type"], "SendMessage");
+        assert_eq!(lines[1]["event"]["plaintext"]["redacted_len"], 12);
+        let sent = &lines[1]["actions"][0];
+        assert_eq!(sent["type"], "Send");
+        assert_eq!(sent["frame"]["opcode"], "AppMessage");
+        assert_eq!(sent["frame"]["sender_id"], 7);
+
+        assert_eq!(lines[2]["event"]["type"], "CreateRoom");
+        assert!(lines[2]["error"].as_str().unwrap().contains("room already exists"));
+        assert!(lines[2].get("actions").is_none());
+    }
+
+    #[test]
+    fn welcome_frames_record_recipient() {
+        let mut header = lockframe_proto::FrameHeader::new(Opcode::Welcome);
+        header.set_recipient_id(9);
+        let value = frame_json(&Frame::new(header, Vec::new()));
+
+        assert_eq!(value["recipient_id"], 9);
+        assert!(value.get("log_index").is_none());
+    }
+
+    #[test]
+    fn plaintext_is_opt_in() {
+        let buf = SharedBuf::default();
+        create_and_send(Transcript::new(buf.clone()).with_plaintext(true));
+
+        let lines = lines(&buf);
+        assert_eq!(lines[1]["event"]["plaintext"], "secret hello");
+    }
+}
@@ -0,0 +1,595 @@
+//! QUIC transport for the client.
+//!
+//! Provides [`QuicFrames`], the QUIC [`FrameTransport`] behind
+//! [`ConnectedClient`]. This is a thin layer that just sends/receives frames -
+//! protocol logic remains in the Sans-IO [`crate::Client`].
+//!
+//! Where QUIC is blocked, [`connect_with_config`] also accepts `ws://` and
+//! `wss://` URLs and carries the same frames over a WebSocket ([`ws`]).
+//!
+//! # TLS Modes
+//!
+//! - Secure (default): Verifies server certificates against system roots. Use
+//!   this for production deployments with CA-signed certificates.
+//! - Insecure: Accepts any certificate without verification. Use this only for
+//!   development with self-signed certificates.
+//!
+//! Setting [`TransportConfig::expected_cert_fingerprint`] pins the server's
+//! certificate instead: the handshake succeeds only if the leaf certificate's
+//! SHA-256 matches, whichever mode is selected. This is how to trust a
+//! self-signed server without accepting every certificate.
+//!
+//! # 0-RTT
+//!
+//! With [`TransportConfig::enable_0rtt`], a [`Connector`] that reconnects to a
+//! server it has already talked to sends frames as TLS early data, before the
+//! handshake finishes. Only frames that pass [`is_early_data_safe`] go out
+//! early; the first frame that does not, and everything after it, waits for
+//! the handshake so ordering is preserved. [`ConnectedClient::early_data`]
+//! reports what was sent early and what was deferred.
+//!
+//! Early data has no replay protection: an attacker who captures it can send
+//! it to the server again, possibly after a server restart. That is why only
+//! idempotent reads (sync, room list, group info) qualify. Commits, messages
+//! and anything else that changes state are never sent as early data.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use lockframe_proto::{ALPN_PROTOCOL, Frame, Opcode};
+use quinn::{ClientConfig, Connecting, Endpoint, RecvStream, SendStream, ZeroRttAccepted};
+use rustls::{
+    DigitallySignedStruct, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, WebPkiSupportedAlgorithms},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, watch};
+
+use crate::connection::read_frame_bytes;
+pub use crate::connection::{ConnectedClient, EarlyDataStatus, FrameTransport, TransportError};
+
+pub mod ws;
+
+const TRANSPORT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// TLS verification mode for client connections.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TlsMode {
+    /// Verify server certificate against system roots (production).
+    #[default]
+    Secure,
+    /// Accept any certificate without verification (development only).
+    Insecure,
+}
+
+// Configuration for client transport.
+//
+/// Use [`TransportConfig::default`] for standard settings:
+/// - Secure TLS
+/// - `server_name = "localhost"`
+/// - `connect_timeout = 5s`
+/// - ALPN `"lockframe"`, no certificate pin
+/// - 0-RTT disabled
+///
+/// Convenience constructors are provided for common environments.
+#[derive(Debug, Clone)]
+pub struct TransportConfig {
+    /// TLS verification mode.
+    pub tls_mode: TlsMode,
+
+    /// Server name used for TLS SNI.
+    pub server_name: String,
+
+    /// Maximum time to wait for connection establishment.
+    pub connect_timeout: Duration,
+
+    /// ALPN protocol IDs offered to the server, in preference order.
+    pub alpn_protocols: Vec<Vec<u8>>,
+
+    /// SHA-256 of the server's leaf certificate (DER).
+    ///
+    /// When set, the handshake fails unless the server presents exactly this
+    /// certificate, overriding `tls_mode`.
+    pub expected_cert_fingerprint: Option<[u8; 32]>,
+
+    /// Send idempotent frames as 0-RTT early data when resuming a session.
+    ///
+    /// Only takes effect for reconnects through the same [`Connector`]. See
+    /// the module docs for the replay risks.
+    pub enable_0rtt: bool,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            tls_mode: TlsMode::default(),
+            server_name: "localhost".to_string(),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            alpn_protocols: vec![ALPN_PROTOCOL.to_vec()],
+            expected_cert_fingerprint: None,
+            enable_0rtt: false,
+        }
+    }
+}
+
+impl TransportConfig {
+    /// Create config for development environments (insecure TLS).
+    pub fn development() -> Self {
+        Self { tls_mode: TlsMode::Insecure, ..Default::default() }
+    }
+
+    /// Create config for production with an explicit server name.
+    pub fn production(server_name: impl Into<String>) -> Self {
+        Self { tls_mode: TlsMode::Secure, server_name: server_name.into(), ..Default::default() }
+    }
+
+    /// Create config that trusts only the certificate with this SHA-256
+    /// fingerprint (e.g. a self-signed server's).
+    pub fn pinned(fingerprint: [u8; 32]) -> Self {
+        Self { expected_cert_fingerprint: Some(fingerprint), ..Default::default() }
+    }
+}
+
+/// Whether a frame may be sent as 0-RTT early data.
+///
+/// True only for idempotent reads, since early data can be replayed.
+pub fn is_early_data_safe(frame: &Frame) -> bool {
+    matches!(
+        frame.header.opcode_enum(),
+        Some(Opcode::SyncRequest | Opcode::RoomListRequest | Opcode::GroupInfoRequest)
+    )
+}
+
+/// Connect to a Lockframe server via QUIC with default config.
+///
+/// Uses development mode (insecure TLS) for backwards compatibility.
+/// For production, use [`connect_with_config`] with secure TLS.
+pub async fn connect(server_addr: &str) -> Result<ConnectedClient, TransportError> {
+    connect_with_config(server_addr, TransportConfig::development()).await
+}
+
+/// Connect to a Lockframe server via QUIC with custom config.
+///
+/// `server_addr` is a socket address for QUIC, or a `ws://`/`wss://` URL for
+/// the WebSocket transport, which has no 0-RTT.
+///
+/// # TLS Modes
+///
+/// - `TlsMode::Secure`: Verifies server certificate against system roots.
+/// - `TlsMode::Insecure`: Accepts any certificate (development only).
+///
+/// A configured `expected_cert_fingerprint` takes precedence over both.
+///
+/// # Errors
+///
+/// Returns `TransportError::Connection` if the connection times out or fails.
+pub async fn connect_with_config(
+    server_addr: &str,
+    config: TransportConfig,
+) -> Result<ConnectedClient, TransportError> {
+    if server_addr.starts_with("ws://") || server_addr.starts_with("wss://") {
+        return ws::connect(server_addr, &config).await;
+    }
+    Connector::new(config)?.connect(server_addr).await
+}
+
+/// Reusable connection factory.
+///
+/// Connections made through one `Connector` share a QUIC endpoint and a TLS
+/// session cache, so a reconnect can resume the previous session and, with
+/// [`TransportConfig::enable_0rtt`], send early data.
+pub struct Connector {
+    endpoint: Endpoint,
+    config: TransportConfig,
+}
+
+impl Connector {
+    /// Create a connector with `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TransportError::Connection` if the TLS config is invalid or
+    /// the local endpoint cannot be bound.
+    pub fn new(config: TransportConfig) -> Result<Self, TransportError> {
+        let client_config = client_config(&config)?;
+
+        #[allow(clippy::expect_used)]
+        let mut endpoint = Endpoint::client(
+            "0.0.0.0:0".parse().expect("invariant: literal socket address '0.0.0.0:0' is valid"),
+        )
+        .map_err(|e| TransportError::Connection(format!("endpoint creation failed: {e}")))?;
+        endpoint.set_default_client_config(client_config);
+
+        Ok(Self { endpoint, config })
+    }
+
+    /// Connect to a Lockframe server.
+    ///
+    /// With 0-RTT enabled and a resumable session, this returns before the
+    /// handshake completes; see [`ConnectedClient::early_data`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `TransportError::Connection` if the connection times out or
+    /// fails.
+    pub async fn connect(&self, server_addr: &str) -> Result<ConnectedClient, TransportError> {
+        let addr: SocketAddr = server_addr
+            .parse()
+            .map_err(|e| TransportError::Connection(format!("invalid address: {e}")))?;
+
+        let connecting = self
+            .endpoint
+            .connect(addr, &self.config.server_name)
+            .map_err(|e| TransportError::Connection(format!("connect failed: {e}")))?;
+
+        let (connection, zero_rtt) = if self.config.enable_0rtt {
+            match connecting.into_0rtt() {
+                Ok((connection, accepted)) => (connection, Some(accepted)),
+                Err(connecting) => (self.handshake(connecting).await?, None),
+            }
+        } else {
+            (self.handshake(connecting).await?, None)
+        };
+
+        let (frames, early_data) = QuicFrames::new(connection, zero_rtt);
+        Ok(ConnectedClient::with_early_data(Box::new(frames), early_data))
+    }
+
+    /// Move every connection from this connector onto a fresh local socket.
+    ///
+    /// Call this after the network changes (e.g. Wi-Fi to cellular). QUIC
+    /// migrates open connections to the new path, so sessions, streams and
+    /// room subscriptions carry on without reconnecting.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TransportError::Connection` if the new socket cannot be bound.
+    pub fn rebind(&self) -> Result<(), TransportError> {
+        let socket = std::net::UdpSocket::bind("0.0.0.0:0")
+            .map_err(|e| TransportError::Connection(format!("socket bind failed: {e}")))?;
+        self.endpoint
+            .rebind(socket)
+            .map_err(|e| TransportError::Connection(format!("rebind failed: {e}")))
+    }
+
+    /// Local address of the connector's socket.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TransportError::Connection` if the socket has no address.
+    pub fn local_addr(&self) -> Result<SocketAddr, TransportError> {
+        self.endpoint
+            .local_addr()
+            .map_err(|e| TransportError::Connection(format!("no local address: {e}")))
+    }
+
+    /// Wait for a full handshake, bounded by the connect timeout.
+    async fn handshake(&self, connecting: Connecting) -> Result<quinn::Connection, TransportError> {
+        tokio::time::timeout(self.config.connect_timeout, connecting)
+            .await
+            .map_err(|_| {
+                TransportError::Connection(format!(
+                    "connection timed out after {:?}",
+                    self.config.connect_timeout
+                ))
+            })?
+            .map_err(|e| TransportError::Connection(format!("connection failed: {e}")))
+    }
+}
+
+/// Frames over a QUIC connection.
+///
+/// Frames go out on one client-opened stream and arrive on one server-opened
+/// stream. During a 0-RTT handshake, sends are gated as described in the
+/// module docs.
+pub struct QuicFrames {
+    connection: quinn::Connection,
+    send: Arc<Mutex<QuicSend>>,
+    recv: Mutex<(Option<RecvStream>, BytesMut)>,
+}
+
+/// Outbound half of [`QuicFrames`].
+struct QuicSend {
+    stream: Option<SendStream>,
+    early: Option<EarlyFrames>,
+    status: watch::Sender<EarlyDataStatus>,
+}
+
+/// Frames sent or held back while 0-RTT is in flight.
+#[derive(Default)]
+struct EarlyFrames {
+    sent: Vec<Frame>,
+    deferred: Vec<Frame>,
+}
+
+impl QuicFrames {
+    /// Wrap `connection`, gating sends on `zero_rtt` if the handshake is still
+    /// in flight.
+    fn new(
+        connection: quinn::Connection,
+        zero_rtt: Option<ZeroRttAccepted>,
+    ) -> (Self, watch::Receiver<EarlyDataStatus>) {
+        let (status, early_data) = watch::channel(EarlyDataStatus {
+            attempted: zero_rtt.is_some(),
+            ..EarlyDataStatus::default()
+        });
+        let send = Arc::new(Mutex::new(QuicSend {
+            stream: None,
+            early: zero_rtt.is_some().then(EarlyFrames::default),
+            status,
+        }));
+        if let Some(accepted) = zero_rtt {
+            tokio::spawn(finish_early_data(connection.clone(), accepted, Arc::clone(&send)));
+        }
+
+        let recv = Mutex::new((None, BytesMut::with_capacity(65536)));
+        (Self { connection, send, recv }, early_data)
+    }
+}
+
+#[async_trait]
+impl FrameTransport for QuicFrames {
+    async fn send_frame(&self, frame: &Frame) -> Result<(), TransportError> {
+        let mut guard = self.send.lock().await;
+        let send = &mut *guard;
+        let opcode = frame.header.opcode();
+
+        // Once a frame is deferred, everything after it is too
+        if let Some(early) = &mut send.early
+            && !(early.deferred.is_empty() && is_early_data_safe(frame))
+        {
+            early.deferred.push(frame.clone());
+            send.status.send_modify(|s| s.deferred.push(opcode));
+            return Ok(());
+        }
+
+        send.write(&self.connection, frame).await?;
+        if let Some(early) = &mut send.early {
+            early.sent.push(frame.clone());
+            send.status.send_modify(|s| s.sent.push(opcode));
+        }
+        Ok(())
+    }
+
+    async fn recv_frame(&self) -> Result<Option<Frame>, TransportError> {
+        let mut guard = self.recv.lock().await;
+        let (slot, buf) = &mut *guard;
+        let stream = match slot.take() {
+            Some(stream) => stream,
+            None => self.connection.accept_uni().await.map_err(|e| {
+                TransportError::Connection(format!("failed to accept server stream: {e}"))
+            })?,
+        };
+        let stream = slot.insert(stream);
+
+        if !read_frame_bytes(stream, buf)
+            .await
+            .map_err(|e| TransportError::Stream(format!("read failed: {e}")))?
+        {
+            return Ok(None);
+        }
+        Frame::decode(buf)
+            .map(Some)
+            .map_err(|e| TransportError::Protocol(format!("frame decode failed: {e}")))
+    }
+
+    fn close(&self) {
+        self.connection.close(0u32.into(), b"client closed");
+    }
+}
+
+impl QuicSend {
+    /// Write a frame, opening the outbound stream on first use.
+    async fn write(
+        &mut self,
+        connection: &quinn::Connection,
+        frame: &Frame,
+    ) -> Result<(), TransportError> {
+        if self.stream.is_none() {
+            let (send, _recv) = connection.open_bi().await.map_err(|e| {
+                TransportError::Connection(format!("failed to open outbound stream: {e}"))
+            })?;
+            self.stream = Some(send);
+        }
+        let Some(stream) = &mut self.stream else { unreachable!("stream opened above") };
+
+        let mut buf = Vec::new();
+        frame
+            .encode(&mut buf)
+            .map_err(|e| TransportError::Protocol(format!("encode failed: {e}")))?;
+        stream
+            .write_all(&buf)
+            .await
+            .map_err(|e| TransportError::Stream(format!("write failed: {e}")))
+    }
+}
+
+/// Release frames held back during 0-RTT once the handshake completes.
+///
+/// If the server rejected the early data, the early frames are sent again on
+/// a fresh stream before the deferred ones.
+async fn finish_early_data(
+    connection: quinn::Connection,
+    accepted: ZeroRttAccepted,
+    send: Arc<Mutex<QuicSend>>,
+) {
+    let accepted = accepted.await;
+    let mut guard = send.lock().await;
+    let send = &mut *guard;
+    send.status.send_modify(|s| s.accepted = Some(accepted));
+
+    let Some(early) = send.early.take() else { return };
+    let resend: &[Frame] = if accepted {
+        &[]
+    } else {
+        send.stream = None;
+        &early.sent
+    };
+    for frame in resend.iter().chain(&early.deferred) {
+        if send.write(&connection, frame).await.is_err() {
+            connection.close(0u32.into(), b"failed to send deferred frames");
+            return;
+        }
+    }
+}
+
+/// Build the rustls config for `config`'s TLS settings.
+fn rustls_config(config: &TransportConfig) -> rustls::ClientConfig {
+    let builder = rustls::ClientConfig::builder();
+    let mut crypto = match (config.expected_cert_fingerprint, config.tls_mode) {
+        (Some(fingerprint), _) => builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier::new(fingerprint)))
+            .with_no_client_auth(),
+        (None, TlsMode::Secure) => {
+            let roots =
+                webpki_roots::TLS_SERVER_ROOTS.iter().cloned().collect::<rustls::RootCertStore>();
+            builder.with_root_certificates(roots).with_no_client_auth()
+        },
+        (None, TlsMode::Insecure) => builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(InsecureCertVerifier))
+            .with_no_client_auth(),
+    };
+
+    crypto.alpn_protocols.clone_from(&config.alpn_protocols);
+    crypto.enable_early_data = config.enable_0rtt;
+    crypto
+}
+
+/// Build the QUIC client config for `config`'s TLS settings.
+fn client_config(config: &TransportConfig) -> Result<ClientConfig, TransportError> {
+    let crypto = rustls_config(config);
+    let mut client_config = ClientConfig::new(Arc::new(
+        quinn::crypto::rustls::QuicClientConfig::try_from(crypto)
+            .map_err(|e| TransportError::Connection(format!("TLS config error: {e}")))?,
+    ));
+
+    let mut transport = quinn::TransportConfig::default();
+    #[allow(clippy::expect_used)]
+    transport.max_idle_timeout(Some(
+        TRANSPORT_IDLE_TIMEOUT
+            .try_into()
+            .expect("invariant: 30s timeout within IdleTimeout bounds"),
+    ));
+    client_config.transport_config(Arc::new(transport));
+
+    Ok(client_config)
+}
+
+/// Certificate verifier that accepts only one pinned leaf certificate.
+///
+/// The chain and server name are not checked; the fingerprint match is the
+/// whole trust decision. Handshake signatures are still verified, so the
+/// server must hold the pinned certificate's private key.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    fingerprint: [u8; 32],
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl PinnedCertVerifier {
+    fn new(fingerprint: [u8; 32]) -> Self {
+        let algorithms = CryptoProvider::get_default().map_or_else(
+            || rustls::crypto::ring::default_provider().signature_verification_algorithms,
+            |provider| provider.signature_verification_algorithms,
+        );
+        Self { fingerprint, algorithms }
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if Sha256::digest(end_entity).as_slice() == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// Certificate verifier that accepts any certificate (insecure, for
+/// development).
+#[derive(Debug)]
+struct InsecureCertVerifier;
+
+impl ServerCertVerifier for InsecureCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![
+            SignatureScheme::RSA_PKCS1_SHA256,
+            SignatureScheme::RSA_PKCS1_SHA384,
+            SignatureScheme::RSA_PKCS1_SHA512,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::ECDSA_NISTP521_SHA512,
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::RSA_PSS_SHA384,
+            SignatureScheme::RSA_PSS_SHA512,
+            SignatureScheme::ED25519,
+        ]
+    }
+}
@@ -0,0 +1,359 @@
+//! WebSocket transport for networks that block QUIC.
+//!
+//! Corporate proxies and browsers often pass only HTTP(S), so UDP-based QUIC
+//! never connects. [`WsTransport`] carries the same frames over a WebSocket.
+//! A WebSocket has no stream multiplexing, so each [`WsConnection`] exposes
+//! exactly one bidirectional stream.
+//!
+//! # Framing
+//!
+//! Each binary WebSocket message holds one frame: a big-endian `u32` length
+//! followed by the encoded [`Frame`]. The prefix lets the receiver reject a
+//! truncated or padded message before decoding it. Text messages are a
+//! protocol error and end the connection.
+//!
+//! # TLS
+//!
+//! `wss://` URLs use the same certificate settings as QUIC
+//! ([`TransportConfig::tls_mode`] and
+//! [`TransportConfig::expected_cert_fingerprint`]), with SNI taken from the
+//! URL's host. No ALPN is offered, since the upgrade is plain HTTP/1.1. The
+//! accepting side ([`WsTransport::bind`]) speaks `ws://` only; terminate TLS
+//! in front of it.
+
+use std::{io, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::{
+    SinkExt, StreamExt,
+    stream::{SplitSink, SplitStream},
+};
+use lockframe_core::transport::{Transport, TransportConnection};
+use lockframe_proto::Frame;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadHalf, WriteHalf},
+    net::{TcpListener, TcpStream},
+    sync::{Mutex, watch},
+    task::AbortHandle,
+};
+use tokio_tungstenite::{
+    Connector, WebSocketStream, client_async_tls_with_config,
+    tungstenite::{
+        Message,
+        client::IntoClientRequest,
+        protocol::{CloseFrame, frame::coding::CloseCode},
+    },
+};
+
+use super::{TransportConfig, rustls_config};
+use crate::connection::{
+    ConnectedClient, FrameTransport, StreamFrames, TransportError, read_frame_bytes,
+};
+
+/// Bytes buffered between a stream half and the WebSocket.
+const STREAM_BUFFER: usize = 64 * 1024;
+
+/// Size of the frame length prefix in each message.
+const LENGTH_PREFIX: usize = 4;
+
+/// WebSocket implementation of [`Transport`].
+pub struct WsTransport {
+    listener: Option<TcpListener>,
+}
+
+impl WsTransport {
+    /// Listen for WebSocket connections on `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound.
+    pub async fn bind(address: &str) -> io::Result<Self> {
+        Ok(Self { listener: Some(TcpListener::bind(address).await?) })
+    }
+
+    /// Transport that only makes outgoing connections.
+    pub fn client() -> Self {
+        Self { listener: None }
+    }
+
+    /// Local address the transport is listening on.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotConnected` for a client-only transport.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener()?.local_addr()
+    }
+
+    fn listener(&self) -> io::Result<&TcpListener> {
+        self.listener
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "client-only transport"))
+    }
+}
+
+#[async_trait]
+impl Transport for WsTransport {
+    type Connection = WsConnection;
+
+    async fn accept(&self) -> io::Result<WsConnection> {
+        let (tcp, _) = self.listener()?.accept().await?;
+        let ws = tokio_tungstenite::accept_async(tcp).await.map_err(io::Error::other)?;
+        Ok(WsConnection::new(ws))
+    }
+
+    async fn connect(&self, remote: SocketAddr) -> io::Result<WsConnection> {
+        let tcp = TcpStream::connect(remote).await?;
+        let (ws, _) = tokio_tungstenite::client_async(format!("ws://{remote}/"), tcp)
+            .await
+            .map_err(io::Error::other)?;
+        Ok(WsConnection::new(ws))
+    }
+}
+
+/// Stream halves of a [`WsConnection`].
+type StreamHalves = (WriteHalf<DuplexStream>, ReadHalf<DuplexStream>);
+
+/// A WebSocket connection carrying one bidirectional frame stream.
+///
+/// Background tasks move frames between the stream and the socket. Dropping
+/// the connection closes the WebSocket.
+pub struct WsConnection {
+    stream: Mutex<Option<StreamHalves>>,
+    close: watch::Sender<Option<CloseFrame>>,
+    closed: watch::Receiver<bool>,
+    inbound: AbortHandle,
+}
+
+impl WsConnection {
+    fn new<S>(ws: WebSocketStream<S>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (sink, messages) = ws.split();
+        let (app, pump) = tokio::io::duplex(STREAM_BUFFER);
+        let (from_app, to_app) = tokio::io::split(pump);
+        let (app_recv, app_send) = tokio::io::split(app);
+        let (close_tx, close_rx) = watch::channel(None);
+        let (closed_tx, closed_rx) = watch::channel(false);
+
+        tokio::spawn(pump_outbound(from_app, sink, close_rx));
+        let inbound = tokio::spawn(pump_inbound(messages, to_app, closed_tx));
+
+        Self {
+            stream: Mutex::new(Some((app_send, app_recv))),
+            close: close_tx,
+            closed: closed_rx,
+            inbound: inbound.abort_handle(),
+        }
+    }
+}
+
+impl Drop for WsConnection {
+    fn drop(&mut self) {
+        self.close.send_if_modified(|close| {
+            close.get_or_insert_with(|| CloseFrame { code: CloseCode::Normal, reason: "".into() });
+            true
+        });
+        self.inbound.abort();
+    }
+}
+
+#[async_trait]
+impl TransportConnection for WsConnection {
+    type SendStream = WriteHalf<DuplexStream>;
+    type RecvStream = ReadHalf<DuplexStream>;
+
+    /// Take the connection's only stream.
+    ///
+    /// Fails with `Unsupported` once the stream has been taken.
+    async fn open_bi(&self) -> io::Result<(Self::SendStream, Self::RecvStream)> {
+        self.stream.lock().await.take().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Unsupported, "a WebSocket carries a single stream")
+        })
+    }
+
+    /// Take the connection's only stream, or wait for the connection to close
+    /// once it has been taken.
+    async fn accept_bi(&self) -> io::Result<Option<(Self::SendStream, Self::RecvStream)>> {
+        if let Some(stream) = self.stream.lock().await.take() {
+            return Ok(Some(stream));
+        }
+        let mut closed = self.closed.clone();
+        let _ = closed.wait_for(|closed| *closed).await;
+        Ok(None)
+    }
+
+    /// Send a close frame. Error code 0 closes normally; any other code is
+    /// sent as an internal error with the code in the reason.
+    fn close(&self, error_code: u64, reason: &str) {
+        let frame = if error_code == 0 {
+            CloseFrame { code: CloseCode::Normal, reason: reason.to_string().into() }
+        } else {
+            CloseFrame { code: CloseCode::Error, reason: format!("{error_code}: {reason}").into() }
+        };
+        self.close.send_replace(Some(frame));
+    }
+}
+
+/// Connect to a Lockframe server at a `ws://` or `wss://` URL.
+pub(super) async fn connect(
+    url: &str,
+    config: &TransportConfig,
+) -> Result<ConnectedClient, TransportError> {
+    let request = url
+        .into_client_request()
+        .map_err(|e| TransportError::Connection(format!("invalid URL: {e}")))?;
+    let secure = request.uri().scheme_str() == Some("wss");
+    let host = request
+        .uri()
+        .host()
+        .ok_or_else(|| TransportError::Connection(format!("URL has no host: {url}")))?
+        .trim_matches(['[', ']'])
+        .to_string();
+    let port = request.uri().port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+    let connector = if secure {
+        let mut tls = rustls_config(config);
+        tls.alpn_protocols.clear();
+        tls.enable_early_data = false;
+        Connector::Rustls(Arc::new(tls))
+    } else {
+        Connector::Plain
+    };
+
+    let handshake = async {
+        let tcp = TcpStream::connect((host.as_str(), port))
+            .await
+            .map_err(|e| TransportError::Connection(format!("connect failed: {e}")))?;
+        client_async_tls_with_config(request, tcp, None, Some(connector))
+            .await
+            .map_err(|e| TransportError::Connection(format!("WebSocket handshake failed: {e}")))
+    };
+    let (ws, _) =
+        tokio::time::timeout(config.connect_timeout, handshake).await.map_err(|_| {
+            TransportError::Connection(format!(
+                "connection timed out after {:?}",
+                config.connect_timeout
+            ))
+        })??;
+
+    let connection = WsConnection::new(ws);
+    let (send, recv) = connection
+        .open_bi()
+        .await
+        .map_err(|e| TransportError::Connection(format!("failed to open stream: {e}")))?;
+    let frames = WsFrames { frames: StreamFrames::new(send, recv), connection };
+    Ok(ConnectedClient::new(Box::new(frames)))
+}
+
+/// Frames over a [`WsConnection`]'s stream.
+pub struct WsFrames {
+    frames: StreamFrames<WriteHalf<DuplexStream>, ReadHalf<DuplexStream>>,
+    connection: WsConnection,
+}
+
+#[async_trait]
+impl FrameTransport for WsFrames {
+    async fn send_frame(&self, frame: &Frame) -> Result<(), TransportError> {
+        self.frames.send_frame(frame).await
+    }
+
+    async fn recv_frame(&self) -> Result<Option<Frame>, TransportError> {
+        self.frames.recv_frame().await
+    }
+
+    fn close(&self) {
+        self.frames.close();
+        self.connection.close(0, "client closed");
+    }
+}
+
+/// Forward frames written to the stream as length-prefixed messages.
+async fn pump_outbound<S>(
+    mut from_app: ReadHalf<DuplexStream>,
+    mut sink: SplitSink<WebSocketStream<S>, Message>,
+    mut close: watch::Receiver<Option<CloseFrame>>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buf = BytesMut::with_capacity(STREAM_BUFFER);
+    loop {
+        tokio::select! {
+            read = read_frame_bytes(&mut from_app, &mut buf) => {
+                let Ok(true) = read else { break };
+                let Ok(message) = encode_message(&buf) else { break };
+                if sink.send(Message::Binary(message)).await.is_err() {
+                    return;
+                }
+            },
+            _ = close.changed() => break,
+        }
+    }
+
+    let frame =
+        close.borrow().clone().unwrap_or(CloseFrame { code: CloseCode::Normal, reason: "".into() });
+    let _ = sink.send(Message::Close(Some(frame))).await;
+    let _ = sink.close().await;
+}
+
+/// Write the frames from incoming messages to the stream.
+async fn pump_inbound<S>(
+    mut messages: SplitStream<WebSocketStream<S>>,
+    mut to_app: WriteHalf<DuplexStream>,
+    closed: watch::Sender<bool>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    while let Some(Ok(message)) = messages.next().await {
+        match message {
+            Message::Binary(data) => {
+                let Some(frame) = decode_message(&data) else { break };
+                if to_app.write_all(frame).await.is_err() {
+                    break;
+                }
+            },
+            Message::Text(_) | Message::Close(_) => break,
+            // Pings are answered by tungstenite itself
+            Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {},
+        }
+    }
+
+    let _ = to_app.shutdown().await;
+    closed.send_replace(true);
+}
+
+/// Prefix an encoded frame with its length.
+fn encode_message(frame: &[u8]) -> io::Result<Bytes> {
+    let len = u32::try_from(frame.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "frame too large"))?;
+    let mut message = BytesMut::with_capacity(LENGTH_PREFIX + frame.len());
+    message.put_u32(len);
+    message.put_slice(frame);
+    Ok(message.freeze())
+}
+
+/// Strip and check a message's length prefix.
+fn decode_message(message: &[u8]) -> Option<&[u8]> {
+    let (prefix, frame) = message.split_first_chunk::<LENGTH_PREFIX>()?;
+    (usize::try_from(u32::from_be_bytes(*prefix)).ok() == Some(frame.len())).then_some(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_length_prefix_must_match_frame() {
+        let message = encode_message(b"frame").unwrap();
+        assert_eq!(&message[..LENGTH_PREFIX], &5u32.to_be_bytes());
+        assert_eq!(decode_message(&message), Some(&b"frame"[..]));
+
+        assert_eq!(decode_message(&message[..message.len() - 1]), None);
+        let mut padded = message.to_vec();
+        padded.push(0);
+        assert_eq!(decode_message(&padded), None);
+        assert_eq!(decode_message(&[0, 0]), None);
+    }
+}
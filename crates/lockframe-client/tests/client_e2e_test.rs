@@ -5,10 +5,23 @@
 //! - Empty message handling (edge case)
 //! - Malformed payload rejection (garbage bytes)
 //! - Encryption determinism (same seed → same output, critical for DST)
-
-use lockframe_client::{Client, ClientAction, ClientEvent, ClientIdentity};
-use lockframe_harness::SimEnv;
-use lockframe_proto::Frame;
+//! - Message edits decrypt for other members
+//! - Typing indicators reach other members
+//! - Retried sends are delivered once
+//! - Server-assigned log indices keep signatures valid
+//! - Frames stamped too far in the future are rejected
+//! - Sent messages keep one ID from queueing to sequencing
+//! - Exported room keys let a non-member decrypt the room
+//! - Commits delivered out of order are applied once the gap is filled
+
+use std::time::Duration;
+
+use lockframe_client::{
+    Client, ClientAction, ClientConfig, ClientError, ClientEvent, ClientIdentity, Environment,
+};
+use lockframe_harness::{SimEnv, TestCluster};
+use lockframe_proto::{Frame, Opcode};
+use lockframe_server::{MemoryStorage, RoomAction, RoomManager, Storage};
 use turmoil::Builder;
 
 /// Test room ID
@@ -44,7 +57,7 @@ fn client_large_message_handling() {
     sim.host("test", || async {
         let env = SimEnv::new();
         let alice = ClientIdentity::new(1);
-        let mut alice_client = Client::new(env, alice);
+        let mut alice_client = Client::new(env, alice, ClientConfig::default());
 
         alice_client.handle(ClientEvent::CreateRoom { room_id: ROOM_ID }).expect("create room");
 
@@ -97,7 +110,7 @@ fn client_empty_message_handling() {
     sim.host("test", || async {
         let env = SimEnv::new();
         let alice = ClientIdentity::new(1);
-        let mut alice_client = Client::new(env, alice);
+        let mut alice_client = Client::new(env, alice, ClientConfig::default());
 
         alice_client.handle(ClientEvent::CreateRoom { room_id: ROOM_ID }).expect("create room");
 
@@ -141,7 +154,7 @@ fn client_malformed_payload_handling() {
     sim.host("test", || async {
         let env = SimEnv::new();
         let alice_identity = ClientIdentity::new(1);
-        let mut alice = Client::new(env, alice_identity);
+        let mut alice = Client::new(env, alice_identity, ClientConfig::default());
 
         alice.handle(ClientEvent::CreateRoom { room_id: ROOM_ID }).expect("create room");
 
@@ -196,7 +209,7 @@ fn client_encryption_determinism() {
         // First run with seed 12345
         let env1 = SimEnv::with_seed(12345);
         let alice1 = ClientIdentity::new(1);
-        let mut alice_client1 = Client::new(env1, alice1);
+        let mut alice_client1 = Client::new(env1, alice1, ClientConfig::default());
 
         alice_client1.handle(ClientEvent::CreateRoom { room_id: ROOM_ID }).expect("create room 1");
 
@@ -218,7 +231,7 @@ fn client_encryption_determinism() {
         let room_id_2 = ROOM_ID.wrapping_add(1);
         let env2 = SimEnv::with_seed(12345);
         let alice2 = ClientIdentity::new(1);
-        let mut alice_client2 = Client::new(env2, alice2);
+        let mut alice_client2 = Client::new(env2, alice2, ClientConfig::default());
 
         alice_client2
             .handle(ClientEvent::CreateRoom { room_id: room_id_2 })
@@ -241,7 +254,7 @@ fn client_encryption_determinism() {
         let room_id_3 = ROOM_ID.wrapping_add(2);
         let env3 = SimEnv::with_seed(54321);
         let alice3 = ClientIdentity::new(1);
-        let mut alice_client3 = Client::new(env3, alice3);
+        let mut alice_client3 = Client::new(env3, alice3, ClientConfig::default());
 
         alice_client3
             .handle(ClientEvent::CreateRoom { room_id: room_id_3 })
@@ -271,3 +284,570 @@ fn client_encryption_determinism() {
 
     sim.run().unwrap();
 }
+
+/// Test that an edit from one member decrypts for the others.
+///
+/// WHY THIS TEST IS NEEDED:
+/// Edits reuse the sender key ratchet but travel in their own `AppEdit`
+/// payload. The receiver must decrypt the nested ciphertext and report which
+/// message it replaces; the sender must ignore its own echo.
+#[test]
+fn client_edit_propagates_to_members() {
+    let mut cluster = TestCluster::new(7, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+    cluster.send_and_verify(ROOM_ID, 0, b"helo").expect("original");
+
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::EditMessage {
+            room_id: ROOM_ID,
+            target_log_index: 0,
+            plaintext: b"hello".to_vec(),
+        })
+        .expect("edit");
+    let frames = extract_send_frames(&actions);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].header.opcode_enum(), Some(Opcode::AppEdit));
+
+    let actions = cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(frames[0].clone()))
+        .expect("bob receives edit");
+    assert!(actions.iter().any(|a| matches!(
+        a,
+        ClientAction::MessageEdited { sender_id: 1, target_log_index: 0, plaintext, .. }
+            if plaintext == b"hello"
+    )));
+
+    // Sender ignores its own echo
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::FrameReceived(frames[0].clone()))
+        .expect("alice receives own edit");
+    assert!(actions.is_empty());
+}
+
+/// Test that a redaction reaches every member, including the sender.
+#[test]
+fn client_redaction_propagates_to_members() {
+    let mut cluster = TestCluster::new(9, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+    cluster.send_and_verify(ROOM_ID, 1, b"spam").expect("original");
+
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::RedactMessage {
+            room_id: ROOM_ID,
+            target_log_index: 0,
+            reason: "spam".to_string(),
+        })
+        .expect("redact");
+    let frames = extract_send_frames(&actions);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].header.opcode_enum(), Some(Opcode::Redact));
+
+    for client in &mut cluster.clients {
+        let actions =
+            client.handle(ClientEvent::FrameReceived(frames[0].clone())).expect("receive redact");
+        assert!(actions.iter().any(|a| matches!(
+            a,
+            ClientAction::MessageRedacted { moderator_id: 1, target_log_index: 0, reason, .. }
+                if reason == "spam"
+        )));
+    }
+}
+
+/// Test that an expiring message is delivered, then reported expired on the
+/// first tick after its expiry, for the sender and the recipient alike.
+#[test]
+fn client_expiring_message_is_removed_after_expiry() {
+    let mut cluster = TestCluster::new(12, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let expires_at = cluster.env().wall_clock() + 60_000;
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::SendExpiringMessage {
+            room_id: ROOM_ID,
+            plaintext: b"gone soon".to_vec(),
+            expires_at,
+        })
+        .expect("send");
+    let frame = extract_send_frames(&actions).remove(0);
+
+    cluster.clients[0].handle(ClientEvent::FrameReceived(frame.clone())).expect("own echo");
+    let actions = cluster.clients[1].handle(ClientEvent::FrameReceived(frame)).expect("receive");
+    assert!(actions.iter().any(|a| matches!(a, ClientAction::DeliverMessage { .. })));
+
+    let expired = |cluster: &mut TestCluster, index: usize| {
+        let now = cluster.env().now();
+        cluster.clients[index]
+            .handle(ClientEvent::Tick { now })
+            .expect("tick")
+            .into_iter()
+            .filter(|a| matches!(a, ClientAction::MessageExpired { room_id: ROOM_ID, .. }))
+            .count()
+    };
+
+    assert_eq!(expired(&mut cluster, 0), 0);
+    assert_eq!(expired(&mut cluster, 1), 0);
+
+    cluster.env().advance_wall_clock(Duration::from_mins(1));
+    assert_eq!(expired(&mut cluster, 0), 1);
+    assert_eq!(expired(&mut cluster, 1), 1);
+    assert_eq!(expired(&mut cluster, 1), 0);
+}
+
+/// Test that a message received after its expiry is never delivered.
+#[test]
+fn client_drops_message_received_after_expiry() {
+    let mut cluster = TestCluster::new(13, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let expires_at = cluster.env().wall_clock() + 1_000;
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::SendExpiringMessage {
+            room_id: ROOM_ID,
+            plaintext: b"too late".to_vec(),
+            expires_at,
+        })
+        .expect("send");
+    let frame = extract_send_frames(&actions).remove(0);
+
+    cluster.env().advance_wall_clock(Duration::from_secs(1));
+    let actions = cluster.clients[1].handle(ClientEvent::FrameReceived(frame)).expect("receive");
+    assert!(!actions.iter().any(|a| matches!(a, ClientAction::DeliverMessage { .. })));
+}
+
+/// Test that a room name and topic update reaches every member.
+#[test]
+fn client_room_meta_propagates_to_members() {
+    let mut cluster = TestCluster::new(10, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::SetRoomMeta {
+            room_id: ROOM_ID,
+            name: None,
+            topic: Some("release planning".to_string()),
+        })
+        .expect("set topic");
+    let frames = extract_send_frames(&actions);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].header.opcode_enum(), Some(Opcode::RoomMeta));
+
+    for client in &mut cluster.clients {
+        let actions =
+            client.handle(ClientEvent::FrameReceived(frames[0].clone())).expect("receive meta");
+        assert!(actions.iter().any(|a| matches!(
+            a,
+            ClientAction::RoomMetaChanged { sender_id: 1, name: None, topic: Some(topic), .. }
+                if topic == "release planning"
+        )));
+    }
+}
+
+/// Test that typing indicators reach other members and are not echoed.
+#[test]
+fn client_typing_indicator_propagates() {
+    let mut cluster = TestCluster::new(11, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::SetTyping { room_id: ROOM_ID, is_typing: true })
+        .expect("typing");
+    let frames = extract_send_frames(&actions);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].header.opcode_enum(), Some(Opcode::Typing));
+
+    let actions = cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(frames[0].clone()))
+        .expect("bob receives typing");
+    assert!(actions.iter().any(|a| matches!(a, ClientAction::TypingChanged {
+        room_id: ROOM_ID,
+        sender_id: 1,
+        is_typing: true
+    })));
+
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::FrameReceived(frames[0].clone()))
+        .expect("alice receives own typing");
+    assert!(actions.is_empty());
+}
+
+/// Test that a retried send is delivered once and distinct messages are not.
+///
+/// WHY THIS TEST IS NEEDED:
+/// A sender that times out resends the same frame, which the server sequences
+/// twice. The retry reuses the message's generation, so the receiver must
+/// drop it by `message_id` instead of failing to decrypt or delivering twice.
+#[test]
+fn client_duplicate_message_id_delivered_once() {
+    let mut cluster = TestCluster::new(13, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let mut send = |plaintext: &[u8]| {
+        let actions = cluster.clients[0]
+            .handle(ClientEvent::SendMessage { room_id: ROOM_ID, plaintext: plaintext.to_vec() })
+            .expect("send");
+        extract_send_frames(&actions).remove(0)
+    };
+    let first = send(b"same text");
+    let second = send(b"same text");
+
+    let delivered = |actions: &[ClientAction]| {
+        actions.iter().filter(|a| matches!(a, ClientAction::DeliverMessage { .. })).count()
+    };
+
+    // The original and its retry: only the first is delivered
+    for (frame, expected) in [(&first, 1), (&first, 0)] {
+        let actions =
+            cluster.clients[1].handle(ClientEvent::FrameReceived(frame.clone())).expect("receive");
+        assert_eq!(delivered(&actions), expected);
+    }
+
+    // Identical content under a new message ID is a distinct message
+    let actions = cluster.clients[1].handle(ClientEvent::FrameReceived(second)).expect("receive");
+    assert_eq!(delivered(&actions), 1);
+}
+
+/// Test that a frame sequenced by the real server still verifies.
+///
+/// WHY THIS TEST IS NEEDED:
+/// The sender signs before the server knows the frame's position, and the
+/// server writes `log_index` into the header afterwards. If `signing_data()`
+/// ever covered `log_index`, every sequenced frame would fail verification
+/// at the receiver even though unit tests on either side still pass.
+#[test]
+fn client_verifies_frame_after_server_assigns_log_index() {
+    let mut cluster = TestCluster::new(17, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let env = SimEnv::new();
+    let storage = MemoryStorage::new();
+    let mut server = RoomManager::new();
+    server.create_room(ROOM_ID, 1, None, &env, &storage).expect("server room");
+
+    for (expected_index, plaintext) in [(0, b"first"), (1, b"again")] {
+        let actions = cluster.clients[0]
+            .handle(ClientEvent::SendMessage { room_id: ROOM_ID, plaintext: plaintext.to_vec() })
+            .expect("send");
+        let signed = extract_send_frames(&actions).remove(0);
+        assert_eq!(signed.header.log_index(), 0, "client leaves log_index unset");
+
+        let sequenced = server
+            .process_frame(signed.clone(), (), &storage)
+            .expect("sequence")
+            .into_iter()
+            .find_map(|a| match a {
+                RoomAction::Broadcast { frame, .. } => Some(frame),
+                _ => None,
+            })
+            .expect("broadcast");
+        assert_eq!(sequenced.header.log_index(), expected_index);
+        assert_eq!(sequenced.header.signature(), signed.header.signature());
+
+        let actions = cluster.clients[1]
+            .handle(ClientEvent::FrameReceived(sequenced))
+            .expect("bob verifies sequenced frame");
+        assert!(actions.iter().any(|a| matches!(
+            a,
+            ClientAction::DeliverMessage { sender_id: 1, plaintext: p, log_index, .. }
+                if p == plaintext && *log_index == expected_index
+        )));
+    }
+}
+
+/// Test that clock skew is tolerated up to `max_skew` and no further.
+///
+/// WHY THIS TEST IS NEEDED:
+/// A sender whose clock runs ahead stamps frames with future HLCs. Within
+/// the tolerance that's ordinary skew; past it, accepting the frame would let
+/// one bad clock pin the room's ordering in the future.
+#[test]
+fn client_rejects_frames_past_clock_skew() {
+    let mut cluster = TestCluster::new(19, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let local_time = cluster.env().wall_clock();
+    let max_skew = ClientConfig::default().max_skew;
+    let max_skew_ms = u64::try_from(max_skew.as_millis()).unwrap();
+
+    // Alice's clock runs ahead while she sends, then we're back to Bob's
+    let send_at = |cluster: &mut TestCluster, ahead: Duration| {
+        cluster.env().advance_wall_clock(ahead);
+        let actions = cluster.clients[0]
+            .handle(ClientEvent::SendMessage { room_id: ROOM_ID, plaintext: b"hi".to_vec() })
+            .expect("send");
+        cluster.env().set_wall_clock(local_time);
+        extract_send_frames(&actions).remove(0)
+    };
+
+    let at_skew = send_at(&mut cluster, max_skew);
+    assert_eq!(at_skew.header.hlc_timestamp(), local_time + max_skew_ms);
+    let actions =
+        cluster.clients[1].handle(ClientEvent::FrameReceived(at_skew)).expect("at skew accepted");
+    assert!(actions.iter().any(|a| matches!(a, ClientAction::DeliverMessage { .. })));
+
+    let past_skew = send_at(&mut cluster, max_skew + Duration::from_millis(1));
+    let result = cluster.clients[1].handle(ClientEvent::FrameReceived(past_skew));
+    assert!(matches!(
+        result,
+        Err(ClientError::ClockSkew { sender_id: 1, timestamp, .. })
+            if timestamp == local_time + max_skew_ms + 1
+    ));
+}
+
+/// Test that a sent message's ID links its queued and sequenced reports.
+///
+/// WHY THIS TEST IS NEEDED:
+/// The sender only learns the log index from the server's echo, and edits
+/// and redactions target log indices. Without a stable ID the application
+/// can't tell which of several in-flight messages an echo belongs to.
+#[test]
+fn client_sent_message_id_resolves_to_log_index() {
+    let mut cluster = TestCluster::new(23, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let env = SimEnv::new();
+    let storage = MemoryStorage::new();
+    let mut server = RoomManager::new();
+    server.create_room(ROOM_ID, 1, None, &env, &storage).expect("server room");
+
+    // Sequence and persist, so the server can check edit targets
+    let mut sequence = |frame: Frame| {
+        let mut broadcast = None;
+        for action in server.process_frame(frame, (), &storage).expect("sequence") {
+            match action {
+                RoomAction::PersistFrame { room_id, log_index, frame, .. } => {
+                    storage.store_frame(room_id, log_index, &frame).expect("persist");
+                },
+                RoomAction::Broadcast { frame, .. } => broadcast = Some(frame),
+                _ => {},
+            }
+        }
+        broadcast.expect("broadcast")
+    };
+    let queued = |actions: &[ClientAction]| {
+        actions.iter().find_map(|a| match a {
+            ClientAction::MessageQueued { message_id, log_index, .. } => {
+                Some((*message_id, *log_index))
+            },
+            _ => None,
+        })
+    };
+
+    let mut echoes = Vec::new();
+    let mut ids = Vec::new();
+    for plaintext in [b"first", b"typo!"] {
+        let actions = cluster.clients[0]
+            .handle(ClientEvent::SendMessage { room_id: ROOM_ID, plaintext: plaintext.to_vec() })
+            .expect("send");
+        let (message_id, log_index) = queued(&actions).expect("queued on send");
+        assert_eq!(log_index, None);
+        ids.push(message_id);
+        echoes.push(sequence(extract_send_frames(&actions).remove(0)));
+    }
+    assert_ne!(ids[0], ids[1]);
+
+    // The echo reports the same ID with the index the server assigned
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::FrameReceived(echoes[1].clone()))
+        .expect("alice receives echo");
+    assert_eq!(queued(&actions), Some((ids[1], Some(1))));
+
+    for echo in &echoes {
+        cluster.clients[1].handle(ClientEvent::FrameReceived(echo.clone())).expect("bob receives");
+    }
+
+    let actions = cluster.clients[0]
+        .handle(ClientEvent::EditMessage {
+            room_id: ROOM_ID,
+            target_log_index: 1,
+            plaintext: b"fixed".to_vec(),
+        })
+        .expect("edit");
+    let edit = sequence(extract_send_frames(&actions).remove(0));
+    let actions =
+        cluster.clients[1].handle(ClientEvent::FrameReceived(edit)).expect("bob receives edit");
+    assert!(actions.iter().any(|a| matches!(
+        a,
+        ClientAction::MessageEdited { target_log_index: 1, plaintext, .. } if plaintext == b"fixed"
+    )));
+}
+
+/// Test that a commit overtaking the one before it is held, not lost.
+///
+/// WHY THIS TEST IS NEEDED:
+/// Commits only merge in epoch order. If the commit for epoch N+2 arrives
+/// before N+1's, the client must keep it and apply both once the gap is
+/// filled, or the member stays an epoch behind and can't read the room.
+#[test]
+fn client_buffers_out_of_order_commits() {
+    let mut cluster = TestCluster::new(19, 4);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    // Alice adds Carol, then Dave; Bob sees neither commit yet
+    let mut commits = Vec::new();
+    for joiner in [2, 3] {
+        let (key_package, _) = cluster.clients[joiner].generate_key_package().expect("keygen");
+        let actions = cluster.clients[0]
+            .handle(ClientEvent::AddMembers { room_id: ROOM_ID, key_packages: vec![key_package] })
+            .expect("add");
+        let commit = extract_send_frames(&actions)
+            .into_iter()
+            .find(|f| f.header.opcode_enum() == Some(Opcode::Commit))
+            .expect("commit frame");
+        cluster.clients[0]
+            .handle(ClientEvent::FrameReceived(commit.clone()))
+            .expect("alice merges");
+        commits.push(commit);
+    }
+    assert_eq!(cluster.clients[0].epoch(ROOM_ID), Some(3));
+
+    // The second commit arrives first: held, with a sync for the gap
+    let actions = cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(commits[1].clone()))
+        .expect("later commit");
+    assert!(actions.iter().any(|a| matches!(a, ClientAction::CommitBuffered {
+        commit_epoch: 2,
+        room_epoch: 1,
+        ..
+    })));
+    assert!(actions.iter().any(|a| matches!(a, ClientAction::RequestSync {
+        from_epoch: 1,
+        to_epoch: 2,
+        ..
+    })));
+    assert_eq!(cluster.clients[1].epoch(ROOM_ID), Some(1));
+
+    // The missing commit applies both, and Bob reads Alice's new epoch
+    cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(commits[0].clone()))
+        .expect("missing commit");
+    assert_eq!(cluster.clients[1].epoch(ROOM_ID), Some(3));
+    cluster.send_and_verify(ROOM_ID, 0, b"converged").expect("bob decrypts");
+}
+
+/// Test that every new epoch gap is synced and stale buffered commits expire.
+///
+/// WHY THIS TEST IS NEEDED:
+/// A sync only covers the range it asked for. If a commit further ahead
+/// arrives while one gap is outstanding, that stretch must be requested too.
+/// And if the gap never fills, the held commits must not sit forever.
+#[test]
+fn client_syncs_each_commit_gap_and_expires_buffer() {
+    let mut cluster = TestCluster::new(23, 5);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    // Alice adds three members; Bob sees none of the commits yet
+    let mut commits = Vec::new();
+    for joiner in [2, 3, 4] {
+        let (key_package, _) = cluster.clients[joiner].generate_key_package().expect("keygen");
+        let actions = cluster.clients[0]
+            .handle(ClientEvent::AddMembers { room_id: ROOM_ID, key_packages: vec![key_package] })
+            .expect("add");
+        let commit = extract_send_frames(&actions)
+            .into_iter()
+            .find(|f| f.header.opcode_enum() == Some(Opcode::Commit))
+            .expect("commit frame");
+        cluster.clients[0]
+            .handle(ClientEvent::FrameReceived(commit.clone()))
+            .expect("alice merges");
+        commits.push(commit);
+    }
+
+    let syncs = |actions: &[ClientAction]| {
+        actions
+            .iter()
+            .filter_map(|a| match a {
+                ClientAction::RequestSync { from_epoch, to_epoch, .. } => {
+                    Some((*from_epoch, *to_epoch))
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Each commit further ahead requests the stretch since the last one
+    let actions = cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(commits[1].clone()))
+        .expect("epoch 2 commit");
+    assert_eq!(syncs(&actions), vec![(1, 2)]);
+    let actions = cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(commits[2].clone()))
+        .expect("epoch 3 commit");
+    assert_eq!(syncs(&actions), vec![(2, 3)]);
+
+    // A repeat inside the requested range waits on the same sync
+    let actions = cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(commits[1].clone()))
+        .expect("repeat commit");
+    assert!(syncs(&actions).is_empty());
+
+    // The gap never fills: the held commits expire and the range is resynced
+    let now = cluster.env().now();
+    let actions = cluster.clients[1].handle(ClientEvent::Tick { now }).expect("tick");
+    assert!(syncs(&actions).is_empty());
+    let later = now + ClientConfig::default().buffered_commit_ttl + Duration::from_secs(1);
+    let actions = cluster.clients[1].handle(ClientEvent::Tick { now: later }).expect("tick");
+    assert_eq!(syncs(&actions), vec![(1, 3)]);
+
+    // Once dropped, the missing commit only advances Bob by one epoch
+    cluster.clients[1]
+        .handle(ClientEvent::FrameReceived(commits[0].clone()))
+        .expect("missing commit");
+    assert_eq!(cluster.clients[1].epoch(ROOM_ID), Some(2));
+}
+
+/// Test that exported room keys decrypt messages on a client outside the room.
+///
+/// WHY THIS TEST IS NEEDED:
+/// A bridge never joins the MLS group, so it must re-derive every member's
+/// sender ratchet from the exported secret and leaf mapping alone. If the
+/// bundle missed a member or the derivation drifted from the one members
+/// use, the bridge would silently fail to decrypt.
+#[cfg(feature = "key-export")]
+#[test]
+fn client_imported_room_keys_decrypt_messages() {
+    let mut cluster = TestCluster::new(17, 2);
+    cluster.create_room(ROOM_ID).expect("create");
+    cluster.join_via_welcome(ROOM_ID, 1).expect("bob joins");
+
+    let actions = cluster.clients[1]
+        .handle(ClientEvent::SendMessage { room_id: ROOM_ID, plaintext: b"bridged".to_vec() })
+        .expect("send");
+    let message = extract_send_frames(&actions).remove(0);
+
+    let bundle = cluster.clients[0].export_room_keys(ROOM_ID).expect("export");
+    assert_eq!(bundle.members.len(), 2);
+    assert!(!format!("{bundle:?}").contains(&format!("{:?}", bundle.sender_key_secret)));
+
+    let mut bridge =
+        Client::new(cluster.env().clone(), ClientIdentity::new(99), ClientConfig::default());
+    assert!(matches!(
+        bridge.handle(ClientEvent::FrameReceived(message.clone())),
+        Err(ClientError::RoomNotFound { .. })
+    ));
+
+    bridge.import_room_keys(bundle.clone()).expect("import");
+    let actions = bridge.handle(ClientEvent::FrameReceived(message)).expect("bridge decrypts");
+    assert!(actions.iter().any(|a| matches!(
+        a,
+        ClientAction::DeliverMessage { sender_id: 2, plaintext, .. } if plaintext == b"bridged"
+    )));
+    assert!(!bridge.is_member(ROOM_ID));
+
+    // Members can't import over their own state
+    assert!(matches!(
+        cluster.clients[0].import_room_keys(bundle),
+        Err(ClientError::RoomAlreadyExists { .. })
+    ));
+}
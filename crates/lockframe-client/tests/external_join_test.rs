@@ -6,7 +6,7 @@
 //! - Client state machine transitions
 //! - Determinism requirements for DST
 
-use lockframe_client::{Client, ClientAction, ClientEvent, ClientIdentity};
+use lockframe_client::{Client, ClientAction, ClientConfig, ClientEvent, ClientIdentity};
 use lockframe_core::mls::{MlsGroup, RoomId};
 use lockframe_harness::SimEnv;
 use lockframe_proto::{FrameHeader, Opcode, Payload, payloads::mls::GroupInfoPayload};
@@ -59,7 +59,7 @@ fn client_external_join_existing_room_fails() {
     sim.host("test", || async {
         let env = SimEnv::new();
         let alice = ClientIdentity::new(1);
-        let mut alice_client = Client::new(env, alice);
+        let mut alice_client = Client::new(env, alice, ClientConfig::default());
 
         // Alice creates room
         alice_client.handle(ClientEvent::CreateRoom { room_id: ROOM_ID }).expect("create room");
@@ -88,7 +88,7 @@ fn client_external_join_requests_group_info() {
     sim.host("test", || async {
         let env = SimEnv::new();
         let bob = ClientIdentity::new(2);
-        let mut bob_client = Client::new(env, bob);
+        let mut bob_client = Client::new(env, bob, ClientConfig::default());
 
         let actions = bob_client
             .handle(ClientEvent::ExternalJoin { room_id: ROOM_ID })
@@ -135,7 +135,7 @@ fn client_processes_group_info_response() {
 
         // Bob initiates external join
         let bob = ClientIdentity::new(2);
-        let mut bob_client = Client::new(env, bob);
+        let mut bob_client = Client::new(env, bob, ClientConfig::default());
         bob_client.handle(ClientEvent::ExternalJoin { room_id: ROOM_ID }).expect("initiate");
 
         // Simulate server responding with GroupInfo
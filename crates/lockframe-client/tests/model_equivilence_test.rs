@@ -3,9 +3,9 @@
 //! These tests generate random operation sequences and verify that the real
 //! implementation behaves identically to the reference model.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use lockframe_client::{Client, ClientAction, ClientEvent, ClientIdentity};
+use lockframe_client::{Client, ClientAction, ClientConfig, ClientEvent, ClientIdentity};
 use lockframe_harness::{
     ClientId, ModelMessage, ModelRoomId, ModelWorld, ObservableState, Operation, OperationError,
     OperationResult, SimEnv, SmallMessage,
@@ -18,6 +18,8 @@ struct PendingFrame {
     room_id: ModelRoomId,
     frame: Frame,
     recipients: Vec<ClientId>,
+    /// Sender's epoch when the message was sent
+    epoch: u64,
 }
 
 /// Delivered message for observable state.
@@ -27,6 +29,7 @@ struct DeliveredMessage {
     content: Vec<u8>,
     log_index: u64,
     epoch: u64,
+    redacted: bool,
 }
 
 /// Real system wrapper that mirrors `ModelWorld`'s interface.
@@ -42,6 +45,9 @@ struct RealWorld {
     disconnected: HashMap<ClientId, bool>,
     group_info: HashMap<ModelRoomId, Vec<u8>>,
     server_rooms: HashMap<ModelRoomId, bool>,
+    redacted: HashSet<(ModelRoomId, u64)>,
+    room_creators: HashMap<ModelRoomId, ClientId>,
+    message_authors: HashMap<(ModelRoomId, u64), ClientId>,
 }
 
 const KEY_PACKAGES_PER_CLIENT: usize = 10;
@@ -52,7 +58,7 @@ impl RealWorld {
         let mut clients: Vec<Client<SimEnv>> = (0..num_clients)
             .map(|i| {
                 let identity = ClientIdentity::new(i as u64 + 1);
-                Client::new(env.clone(), identity)
+                Client::new(env.clone(), identity, ClientConfig::default())
             })
             .collect();
 
@@ -80,6 +86,9 @@ impl RealWorld {
             disconnected: HashMap::new(),
             group_info: HashMap::new(),
             server_rooms: HashMap::new(),
+            redacted: HashSet::new(),
+            room_creators: HashMap::new(),
+            message_authors: HashMap::new(),
         }
     }
 
@@ -103,6 +112,12 @@ impl RealWorld {
             Operation::RemoveMember { remover_id, target_id, room_id } => {
                 self.apply_remove_member(*remover_id, *target_id, *room_id)
             },
+            Operation::Redact { client_id, room_id, target_log_index } => {
+                self.apply_redact(*client_id, *room_id, *target_log_index)
+            },
+            Operation::SelfUpdate { client_id, room_id } => {
+                self.apply_self_update(*client_id, *room_id)
+            },
             Operation::AdvanceTime { .. } => OperationResult::Ok,
             Operation::DeliverPending => {
                 self.apply_deliver_pending();
@@ -139,18 +154,16 @@ impl RealWorld {
                             ..
                         } = action
                         {
-                            // Use tracked epoch, not frame epoch (MLS epoch lags until commit)
-                            let recipient_epoch = self
-                                .room_epochs
-                                .get(&(recipient_id, pf.room_id))
-                                .copied()
-                                .unwrap_or(0);
+                            // Redacted before delivery: only the tombstone arrives
+                            let redacted = self.redacted.contains(&(pf.room_id, log_index));
                             self.delivered_messages.push((recipient_id, DeliveredMessage {
                                 room_id: pf.room_id,
-                                sender_id,
-                                content: plaintext,
+                                // Real sender IDs are `ClientId + 1`
+                                sender_id: sender_id.saturating_sub(1),
+                                content: if redacted { Vec::new() } else { plaintext },
                                 log_index,
-                                epoch: recipient_epoch,
+                                epoch: pf.epoch,
+                                redacted,
                             }));
                         }
                     }
@@ -192,6 +205,7 @@ impl RealWorld {
                 content: dm.content.clone(),
                 log_index: dm.log_index,
                 epoch: dm.epoch,
+                redacted: dm.redacted,
             });
         }
 
@@ -487,6 +501,131 @@ impl RealWorld {
         OperationResult::Ok
     }
 
+    fn apply_redact(
+        &mut self,
+        client_id: ClientId,
+        room_id: ModelRoomId,
+        target_log_index: u64,
+    ) -> OperationResult {
+        if client_id as usize >= self.clients.len() {
+            return OperationResult::Error(OperationError::InvalidClient);
+        }
+
+        if self.partitioned.get(&client_id).copied().unwrap_or(false) {
+            return OperationResult::Error(OperationError::Partitioned);
+        }
+
+        if !self.room_membership.get(&(client_id, room_id)).copied().unwrap_or(false) {
+            return OperationResult::Error(OperationError::NotMember);
+        }
+
+        let Some(&author) = self.message_authors.get(&(room_id, target_log_index)) else {
+            return OperationResult::Error(OperationError::MessageNotFound);
+        };
+
+        // The server only sequences redactions from the author or an admin
+        if author != client_id && self.room_creators.get(&room_id) != Some(&client_id) {
+            return OperationResult::Error(OperationError::Forbidden);
+        }
+
+        let real_room_id = u128::from(room_id) + 1;
+        let Ok(actions) = self.clients[client_id as usize].handle(ClientEvent::RedactMessage {
+            room_id: real_room_id,
+            target_log_index,
+            reason: String::new(),
+        }) else {
+            return OperationResult::Error(OperationError::NotMember);
+        };
+
+        self.redacted.insert((room_id, target_log_index));
+
+        let connected_members: Vec<ClientId> = self
+            .room_membership
+            .iter()
+            .filter(|&(&(cid, rid), &m)| {
+                m && rid == room_id && !self.partitioned.get(&cid).copied().unwrap_or(false)
+            })
+            .map(|(&(cid, _), _)| cid)
+            .collect();
+
+        for action in &actions {
+            let ClientAction::Send(frame) = action else { continue };
+            for &member_id in &connected_members {
+                let Some(client) = self.clients.get_mut(member_id as usize) else { continue };
+                let Ok(received) = client.handle(ClientEvent::FrameReceived(frame.clone())) else {
+                    continue;
+                };
+
+                for action in received {
+                    if let ClientAction::MessageRedacted { target_log_index, .. } = action {
+                        for (cid, dm) in &mut self.delivered_messages {
+                            if *cid == member_id
+                                && dm.room_id == room_id
+                                && dm.log_index == target_log_index
+                            {
+                                dm.content.clear();
+                                dm.redacted = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        OperationResult::Ok
+    }
+
+    fn apply_self_update(&mut self, client_id: ClientId, room_id: ModelRoomId) -> OperationResult {
+        if client_id as usize >= self.clients.len() {
+            return OperationResult::Error(OperationError::InvalidClient);
+        }
+
+        if !self.room_membership.get(&(client_id, room_id)).copied().unwrap_or(false) {
+            return OperationResult::Error(OperationError::NotMember);
+        }
+
+        let real_room_id = u128::from(room_id) + 1;
+
+        let Ok(actions) = self.clients[client_id as usize]
+            .handle(ClientEvent::SelfUpdate { room_id: real_room_id })
+        else {
+            return OperationResult::Error(OperationError::NotMember);
+        };
+
+        let members: Vec<ClientId> = self
+            .room_membership
+            .iter()
+            .filter(|&(&(_, rid), &m)| m && rid == room_id)
+            .map(|(&(cid, _), _)| cid)
+            .collect();
+
+        // Deliver the commit immediately so every member, including the
+        // updater, processes the epoch transition
+        for action in &actions {
+            if let ClientAction::Send(frame) = action
+                && frame.header.opcode_enum() == Some(Opcode::Commit)
+            {
+                for &member_id in &members {
+                    if let Some(client) = self.clients.get_mut(member_id as usize) {
+                        let _ = client.handle(ClientEvent::FrameReceived(frame.clone()));
+                    }
+                }
+            }
+        }
+
+        for cid in &members {
+            let epoch = self.room_epochs.entry((*cid, room_id)).or_insert(0);
+            *epoch += 1;
+        }
+
+        OperationResult::Ok
+    }
+
+    /// Epoch the real client's MLS group is at. `None` if not a member.
+    fn client_epoch(&self, client_id: ClientId, room_id: ModelRoomId) -> Option<u64> {
+        self.clients.get(client_id as usize)?.epoch(u128::from(room_id) + 1)
+    }
+
     fn apply_create_room(&mut self, client_id: ClientId, room_id: ModelRoomId) -> OperationResult {
         if client_id as usize >= self.clients.len() {
             return OperationResult::Error(OperationError::InvalidClient);
@@ -514,6 +653,7 @@ impl RealWorld {
         match result {
             Ok(actions) => {
                 self.server_rooms.insert(room_id, true);
+                self.room_creators.insert(room_id, client_id);
                 self.room_membership.insert((client_id, room_id), true);
                 self.room_epochs.insert((client_id, room_id), 0);
 
@@ -572,6 +712,7 @@ impl RealWorld {
                         let log_index_ref = self.next_log_index.entry(room_id).or_insert(0);
                         let log_index_val = *log_index_ref;
                         *log_index_ref += 1;
+                        self.message_authors.insert((room_id, log_index_val), client_id);
 
                         let mut sequenced_frame = frame;
                         sequenced_frame.header.set_log_index(log_index_val);
@@ -585,6 +726,7 @@ impl RealWorld {
                             content: plaintext.clone(),
                             log_index: log_index_val,
                             epoch: sender_epoch,
+                            redacted: false,
                         }));
 
                         if !other_recipients.is_empty() {
@@ -592,6 +734,7 @@ impl RealWorld {
                                 room_id,
                                 frame: sequenced_frame,
                                 recipients: other_recipients,
+                                epoch: sender_epoch,
                             });
                         }
                     }
@@ -749,6 +892,15 @@ fn operation_strategy(num_clients: usize) -> impl Strategy<Value = Operation> {
         1 => (client_id.clone(), client_id.clone(), room_id).prop_map(|(r, t, room)| {
             Operation::RemoveMember { remover_id: r, target_id: t, room_id: room }
         }),
+        1 => (client_id.clone(), room_id, 0..8u64).prop_map(|(c, r, t)| Operation::Redact {
+            client_id: c,
+            room_id: r,
+            target_log_index: t
+        }),
+        1 => (client_id.clone(), room_id).prop_map(|(c, r)| Operation::SelfUpdate {
+            client_id: c,
+            room_id: r
+        }),
         1 => millis.prop_map(|m| Operation::AdvanceTime { millis: m }),
         1 => Just(Operation::DeliverPending),
         1 => client_id.clone().prop_map(|c| Operation::Partition { client_id: c }),
@@ -966,6 +1118,118 @@ proptest! {
         prop_assert!(result.is_err(), "Removed member should not be able to send");
     }
 
+    /// Verify a self-update advances the epoch identically in model and real,
+    /// for every member, without changing membership.
+    #[test]
+    fn prop_self_update_matches_real(
+        seed in any::<u64>(),
+        creator in 0..3u8,
+        member in 0..3u8,
+        updater_is_creator in any::<bool>(),
+        room_id in any::<ModelRoomId>()
+    ) {
+        prop_assume!(creator != member);
+
+        let mut model = ModelWorld::new(3);
+        let mut real = RealWorld::new(3, seed);
+
+        let setup = [
+            Operation::CreateRoom { client_id: creator, room_id },
+            Operation::AddMember { inviter_id: creator, invitee_id: member, room_id },
+        ];
+        for op in &setup {
+            prop_assert!(model.apply(op).is_ok());
+            prop_assert!(real.apply(op).is_ok());
+        }
+        let rooms_before = model.observable_state().client_rooms;
+
+        let updater = if updater_is_creator { creator } else { member };
+        let op = Operation::SelfUpdate { client_id: updater, room_id };
+        prop_assert!(model.apply(&op).is_ok());
+        prop_assert!(real.apply(&op).is_ok());
+
+        let model_state = model.observable_state();
+        let real_state = real.observable_state();
+        prop_assert_eq!(&model_state.client_rooms, &rooms_before, "Self-update changed membership");
+        prop_assert_eq!(&model_state.client_rooms, &real_state.client_rooms);
+        prop_assert_eq!(&model_state.client_epochs, &real_state.client_epochs);
+
+        for client_id in [creator, member] {
+            let model_epoch = model.client(client_id).and_then(|c| c.epoch(room_id));
+            prop_assert_eq!(model_epoch, Some(2));
+            prop_assert_eq!(real.client_epoch(client_id, room_id), model_epoch);
+        }
+
+        // Non-members cannot self-update
+        let outsider = 3 - creator - member;
+        let op = Operation::SelfUpdate { client_id: outsider, room_id };
+        prop_assert_eq!(model.apply(&op), OperationResult::Error(OperationError::NotMember));
+        prop_assert!(real.apply(&op).is_err());
+    }
+
+    /// Verify a redaction leaves the same tombstone in model and real, both
+    /// for members that already received the message and for deliveries
+    /// still pending when the redaction happened.
+    #[test]
+    fn prop_redact_matches_real(
+        seed in any::<u64>(),
+        creator in 0..3u8,
+        member in 0..3u8,
+        deliver_first in any::<bool>(),
+        room_id in any::<ModelRoomId>()
+    ) {
+        prop_assume!(creator != member);
+
+        let mut model = ModelWorld::new(3);
+        let mut real = RealWorld::new(3, seed);
+
+        let mut ops = vec![
+            Operation::CreateRoom { client_id: creator, room_id },
+            Operation::AddMember { inviter_id: creator, invitee_id: member, room_id },
+            Operation::SendMessage {
+                client_id: member,
+                room_id,
+                content: SmallMessage { seed: 1, size_class: 1 },
+            },
+        ];
+        if deliver_first {
+            ops.push(Operation::DeliverPending);
+        }
+        ops.push(Operation::Redact { client_id: creator, room_id, target_log_index: 0 });
+        ops.push(Operation::DeliverPending);
+
+        for op in &ops {
+            prop_assert!(model.apply(op).is_ok(), "model rejected {:?}", op);
+            prop_assert!(real.apply(op).is_ok(), "real rejected {:?}", op);
+        }
+
+        let model_state = model.observable_state();
+        let real_state = real.observable_state();
+        prop_assert_eq!(&model_state.client_messages, &real_state.client_messages);
+
+        for client_id in [creator, member] {
+            let messages = model.client_messages(client_id, room_id).unwrap_or_default();
+            prop_assert_eq!(messages.len(), 1);
+            prop_assert!(messages[0].redacted && messages[0].content.is_empty());
+        }
+
+        // Redacting a log index that was never assigned fails in both
+        let op = Operation::Redact { client_id: creator, room_id, target_log_index: 1 };
+        prop_assert_eq!(model.apply(&op), OperationResult::Error(OperationError::MessageNotFound));
+        prop_assert_eq!(real.apply(&op), OperationResult::Error(OperationError::MessageNotFound));
+
+        // Only the author or the creator may redact
+        let op = Operation::SendMessage {
+            client_id: creator,
+            room_id,
+            content: SmallMessage { seed: 2, size_class: 1 },
+        };
+        prop_assert!(model.apply(&op).is_ok() && real.apply(&op).is_ok());
+        let op = Operation::Redact { client_id: member, room_id, target_log_index: 1 };
+        prop_assert_eq!(model.apply(&op), OperationResult::Error(OperationError::Forbidden));
+        prop_assert_eq!(real.apply(&op), OperationResult::Error(OperationError::Forbidden));
+    }
+
     /// Verify cannot remove self.
     #[test]
     fn prop_cannot_remove_self(
@@ -1325,6 +1589,12 @@ fn clamp_client_id(op: Operation, num_clients: usize) -> Operation {
             target_id: clamp(target_id),
             room_id,
         },
+        Operation::Redact { client_id, room_id, target_log_index } => {
+            Operation::Redact { client_id: clamp(client_id), room_id, target_log_index }
+        },
+        Operation::SelfUpdate { client_id, room_id } => {
+            Operation::SelfUpdate { client_id: clamp(client_id), room_id }
+        },
         other => other,
     }
 }
@@ -0,0 +1,90 @@
+//! Simulation test for resending rate-limited frames.
+//!
+//! A fake server rate-limits the first copy of a message and sequences the
+//! second. The client must wait out `retry_after` in virtual time before
+//! resending, then see its frame echoed back.
+
+use std::time::Duration;
+
+use lockframe_client::{
+    Client, ClientAction, ClientConfig, ClientEvent, ClientIdentity, Environment, SendRetry,
+};
+use lockframe_harness::{SimEnv, read_frame};
+use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::ErrorPayload};
+use tokio::io::AsyncWriteExt;
+use turmoil::{
+    Builder,
+    net::{TcpListener, TcpStream},
+};
+
+const ROOM_ID: u128 = 0x1234_5678_9abc_def0_1234_5678_9abc_def0;
+const RETRY_AFTER_SECS: u64 = 2;
+
+#[allow(clippy::expect_used)]
+fn encode(frame: &Frame) -> Vec<u8> {
+    let mut buf = Vec::new();
+    frame.encode(&mut buf).expect("frame encodes");
+    buf
+}
+
+#[test]
+fn rate_limited_send_is_retried_after_advised_delay() {
+    let mut sim = Builder::new().simulation_duration(Duration::from_secs(30)).build();
+
+    sim.host("server", || async {
+        let listener = TcpListener::bind("0.0.0.0:443").await?;
+        let (mut stream, _) = listener.accept().await?;
+
+        let first = read_frame(&mut stream).await?;
+        let first_at = tokio::time::Instant::now();
+        let mut error = Payload::Error(ErrorPayload::rate_limited(RETRY_AFTER_SECS))
+            .into_frame(FrameHeader::new(Opcode::Error))
+            .unwrap();
+        error.header.set_room_id(first.header.room_id());
+        error.header.set_request_id(first.header.request_id());
+        stream.write_all(&encode(&error)).await?;
+
+        let mut second = read_frame(&mut stream).await?;
+        assert!(first_at.elapsed() >= Duration::from_secs(RETRY_AFTER_SECS));
+        assert_eq!(second.header.signature(), first.header.signature());
+
+        second.header.set_log_index(0);
+        stream.write_all(&encode(&second)).await?;
+        Ok(())
+    });
+
+    sim.client("client", async {
+        let env = SimEnv::new();
+        let mut client = Client::new(env.clone(), ClientIdentity::new(1), ClientConfig::default());
+        client.handle(ClientEvent::CreateRoom { room_id: ROOM_ID }).unwrap();
+        let actions = client
+            .handle(ClientEvent::SendMessage { room_id: ROOM_ID, plaintext: b"hi".to_vec() })
+            .unwrap();
+        let Some(ClientAction::Send(frame)) = actions.into_iter().next() else {
+            panic!("expected Send");
+        };
+
+        let mut stream = TcpStream::connect("server:443").await?;
+        let mut retry = SendRetry::default();
+        stream.write_all(&encode(&frame)).await?;
+        retry.sent(&frame);
+
+        let error = read_frame(&mut stream).await?;
+        let resend = retry.on_frame(&error).expect("rate-limited frame is resent");
+        assert_eq!(resend.delay, Duration::from_secs(RETRY_AFTER_SECS));
+        assert_eq!(resend.attempt, 2);
+
+        let start = env.now();
+        resend.wait(&env).await;
+        assert_eq!(env.now() - start, Duration::from_secs(RETRY_AFTER_SECS));
+        stream.write_all(&encode(&resend.frame)).await?;
+        retry.resent(resend);
+
+        let echo = read_frame(&mut stream).await?;
+        assert!(retry.on_frame(&echo).is_none());
+        assert_eq!(retry.in_flight(), 0);
+        Ok(())
+    });
+
+    sim.run().unwrap();
+}
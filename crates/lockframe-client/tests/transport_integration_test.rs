@@ -5,10 +5,16 @@
 
 use std::time::Duration;
 
-use lockframe_client::transport::{self, ConnectedClient, TransportConfig};
+use lockframe_client::transport::{
+    self, ConnectedClient, Connector, TransportConfig, ws::WsTransport,
+};
+use lockframe_core::transport::{Transport, TransportConnection};
 use lockframe_proto::{
     Frame, FrameHeader, Opcode,
-    payloads::{Payload, session::Hello},
+    payloads::{
+        ErrorPayload, Payload,
+        session::{Hello, SyncRequest},
+    },
 };
 use lockframe_server::{DriverConfig, Server, ServerRuntimeConfig};
 use tokio::time::timeout;
@@ -22,30 +28,47 @@ fn make_hello_frame() -> Frame {
 }
 
 /// Start a real server, spawn its run loop, and return the address.
-#[allow(clippy::expect_used)]
 fn start_server() -> String {
+    start_server_with_fingerprint().0
+}
+
+/// Start a real server and return its address and certificate fingerprint.
+#[allow(clippy::expect_used)]
+fn start_server_with_fingerprint() -> (String, [u8; 32]) {
+    start_server_with_config(false)
+}
+
+/// Start a real server with 0-RTT toggled and return its address and
+/// certificate fingerprint.
+#[allow(clippy::expect_used)]
+fn start_server_with_config(enable_0rtt: bool) -> (String, [u8; 32]) {
     let config = ServerRuntimeConfig {
         bind_address: "127.0.0.1:0".to_string(),
         cert_path: None,
         key_path: None,
         driver: DriverConfig::default(),
+        enable_0rtt,
+        ws_bind_address: None,
     };
     let server = Server::bind(config).expect("valid server config");
     let addr = server.local_addr().expect("underlying socket").to_string();
+    let fingerprint = server.cert_fingerprint();
 
     tokio::spawn(async move {
         let _ = server.run().await;
     });
 
-    addr
+    (addr, fingerprint)
 }
 
 /// Connect to server with retry. Avoids timing-dependent sleeps.
 async fn connect_with_retry(addr: &str) -> ConnectedClient {
-    let config = TransportConfig {
-        connect_timeout: Duration::from_millis(100),
-        ..TransportConfig::development()
-    };
+    connect_with_retry_config(addr, TransportConfig::development()).await
+}
+
+/// Connect with `config` and retry.
+async fn connect_with_retry_config(addr: &str, config: TransportConfig) -> ConnectedClient {
+    let config = TransportConfig { connect_timeout: Duration::from_millis(100), ..config };
 
     for attempt in 0..20 {
         match transport::connect_with_config(addr, config.clone()).await {
@@ -77,6 +100,32 @@ async fn client_connect_fails_for_invalid_address() {
     assert!(result.is_err(), "should fail to connect to invalid address");
 }
 
+#[tokio::test]
+async fn client_connects_with_matching_cert_fingerprint() {
+    let (addr, fingerprint) = start_server_with_fingerprint();
+    let mut client = connect_with_retry_config(&addr, TransportConfig::pinned(fingerprint)).await;
+
+    client.to_server.send(make_hello_frame()).await.unwrap();
+    let response = timeout(Duration::from_secs(5), client.from_server.recv()).await.unwrap();
+    assert_eq!(response.unwrap().header.opcode(), Opcode::HelloReply as u16);
+}
+
+#[tokio::test]
+async fn client_rejects_mismatched_cert_fingerprint() {
+    let (addr, mut fingerprint) = start_server_with_fingerprint();
+    let _ = connect_with_retry(&addr).await;
+
+    fingerprint[0] ^= 0xFF;
+    let config = TransportConfig {
+        connect_timeout: Duration::from_secs(5),
+        ..TransportConfig::pinned(fingerprint)
+    };
+    let Err(err) = transport::connect_with_config(&addr, config).await else {
+        panic!("mismatched fingerprint must fail the handshake");
+    };
+    assert!(err.to_string().contains("certificate"), "unexpected error: {err}");
+}
+
 #[tokio::test]
 async fn client_can_send_frame_to_server() {
     let addr = start_server();
@@ -141,3 +190,197 @@ async fn client_stops_cleanly() {
     // Stop should not panic
     client.stop();
 }
+
+#[tokio::test]
+async fn reconnect_sends_sync_as_early_data_and_defers_commit() {
+    let (addr, _) = start_server_with_config(true);
+    let config = TransportConfig { enable_0rtt: true, ..TransportConfig::development() };
+    let connector = Connector::new(config).unwrap();
+
+    // First connection completes a full handshake and receives session tickets
+    let mut first = connector.connect(&addr).await.unwrap();
+    assert!(!first.early_data.borrow().attempted);
+    first.to_server.send(make_hello_frame()).await.unwrap();
+    let reply = timeout(Duration::from_secs(5), first.from_server.recv()).await.unwrap().unwrap();
+    assert_eq!(reply.header.opcode(), Opcode::HelloReply as u16);
+    first.stop();
+
+    let mut client = connector.connect(&addr).await.unwrap();
+    assert!(client.early_data.borrow().attempted);
+
+    let room_id = 0x0e_a21f;
+    let sync = SyncRequest { from_log_index: 0, limit: 10, from_timestamp: None };
+    let mut sync =
+        Payload::SyncRequest(sync).into_frame(FrameHeader::new(Opcode::SyncRequest)).unwrap();
+    sync.header.set_room_id(room_id);
+    let mut commit = FrameHeader::new(Opcode::Commit);
+    commit.set_room_id(room_id);
+    client.to_server.send(sync).await.unwrap();
+    client.to_server.send(Frame::new(commit, Vec::new())).await.unwrap();
+
+    // The commit's echo may overtake the sync's error on the outbound stream
+    let error = loop {
+        let frame =
+            timeout(Duration::from_secs(5), client.from_server.recv()).await.unwrap().unwrap();
+        if frame.header.opcode_enum() == Some(Opcode::Error) {
+            break frame;
+        }
+    };
+    let Payload::Error(error) = Payload::from_frame(&error).unwrap() else {
+        panic!("expected error frame for unknown room");
+    };
+    assert_eq!(error.code, ErrorPayload::ROOM_NOT_FOUND);
+
+    let status = client.early_data.borrow().clone();
+    assert_eq!(status.accepted, Some(true));
+    assert_eq!(status.sent, vec![Opcode::SyncRequest as u16]);
+    assert_eq!(status.deferred, vec![Opcode::Commit as u16]);
+}
+
+/// Send Hello as `sender_id` and wait for the `HelloReply`.
+#[allow(clippy::expect_used)]
+async fn authenticate(client: &mut ConnectedClient, sender_id: u64) {
+    let hello =
+        Hello { version: 1, capabilities: vec![], sender_id: Some(sender_id), auth_token: None };
+    let frame = Payload::Hello(hello)
+        .into_frame(FrameHeader::new(Opcode::Hello))
+        .expect("frame conversion should work");
+    client.to_server.send(frame).await.expect("connection open");
+    recv_opcode(client, Opcode::HelloReply).await;
+}
+
+/// Receive frames until one with `opcode` arrives.
+#[allow(clippy::expect_used)]
+async fn recv_opcode(client: &mut ConnectedClient, opcode: Opcode) -> Frame {
+    loop {
+        let frame = timeout(Duration::from_secs(5), client.from_server.recv())
+            .await
+            .expect("frame within timeout")
+            .expect("connection open");
+        if frame.header.opcode() == opcode as u16 {
+            return frame;
+        }
+    }
+}
+
+#[tokio::test]
+async fn shutdown_delivers_goodbye_before_closing() {
+    let config = ServerRuntimeConfig {
+        bind_address: "127.0.0.1:0".to_string(),
+        cert_path: None,
+        key_path: None,
+        driver: DriverConfig::default(),
+        enable_0rtt: false,
+        ws_bind_address: None,
+    };
+    let server = Server::bind(config).expect("valid server config");
+    let addr = server.local_addr().expect("underlying socket").to_string();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(server.run_until(async {
+        let _ = stopped.await;
+    }));
+
+    let mut client = connect_with_retry(&addr).await;
+    authenticate(&mut client, 7).await;
+
+    stop.send(()).unwrap();
+    let goodbye = recv_opcode(&mut client, Opcode::Goodbye).await;
+    let Ok(Payload::Goodbye(goodbye)) = Payload::from_frame(&goodbye) else {
+        panic!("expected Goodbye payload");
+    };
+    assert_eq!(goodbye.reason, "server shutting down");
+
+    timeout(Duration::from_secs(15), server).await.unwrap().unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn migrated_connection_keeps_receiving_broadcasts() {
+    let addr = start_server();
+    let room_id = 0x0d1e_5e1f;
+
+    let mut alice = connect_with_retry(&addr).await;
+    authenticate(&mut alice, 1).await;
+    let bob_connector = Connector::new(TransportConfig::development()).unwrap();
+    let mut bob = bob_connector.connect(&addr).await.unwrap();
+    authenticate(&mut bob, 2).await;
+
+    // Alice's commit creates the room; her Welcome subscribes Bob
+    let mut commit = FrameHeader::new(Opcode::Commit);
+    commit.set_room_id(room_id);
+    commit.set_sender_id(1);
+    alice.to_server.send(Frame::new(commit, Vec::new())).await.unwrap();
+    recv_opcode(&mut alice, Opcode::Commit).await;
+    let mut welcome = FrameHeader::new(Opcode::Welcome);
+    welcome.set_room_id(room_id);
+    welcome.set_sender_id(1);
+    welcome.set_recipient_id(2);
+    alice.to_server.send(Frame::new(welcome, Vec::new())).await.unwrap();
+    recv_opcode(&mut bob, Opcode::Welcome).await;
+
+    let before = bob_connector.local_addr().unwrap();
+    bob_connector.rebind().unwrap();
+    assert_ne!(bob_connector.local_addr().unwrap().port(), before.port());
+
+    // A round trip from the new socket moves the server onto the new path
+    bob.to_server.send(Frame::new(FrameHeader::new(Opcode::Ping), Vec::new())).await.unwrap();
+    recv_opcode(&mut bob, Opcode::Pong).await;
+
+    let mut message = FrameHeader::new(Opcode::AppMessage);
+    message.set_room_id(room_id);
+    message.set_sender_id(1);
+    message.set_epoch(1);
+    alice.to_server.send(Frame::new(message, &b"after migration"[..])).await.unwrap();
+
+    let received = recv_opcode(&mut bob, Opcode::AppMessage).await;
+    assert_eq!(received.header.room_id(), room_id);
+    assert_eq!(&received.payload[..], b"after migration");
+}
+
+#[tokio::test]
+async fn frame_round_trips_over_websocket() {
+    let server = WsTransport::bind("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(async move {
+        let connection = server.accept().await.unwrap();
+        let (mut send, mut recv) = connection.accept_bi().await.unwrap().unwrap();
+        let _ = tokio::io::copy(&mut recv, &mut send).await;
+    });
+
+    let mut client =
+        transport::connect_with_config(&format!("ws://{addr}"), TransportConfig::development())
+            .await
+            .unwrap();
+    let mut frame = make_hello_frame();
+    frame.header.set_room_id(0x00ec_4000);
+    client.to_server.send(frame.clone()).await.unwrap();
+
+    let echoed = timeout(Duration::from_secs(5), client.from_server.recv()).await.unwrap().unwrap();
+    assert_eq!(echoed.header.room_id(), frame.header.room_id());
+    assert_eq!(echoed.payload, frame.payload);
+}
+
+#[tokio::test]
+async fn server_accepts_websocket_clients() {
+    let config = ServerRuntimeConfig {
+        bind_address: "127.0.0.1:0".to_string(),
+        ws_bind_address: Some("127.0.0.1:0".to_string()),
+        ..ServerRuntimeConfig::default()
+    };
+    let server = Server::bind(config).expect("valid server config");
+    let ws_addr = server.ws_addr().expect("ws socket").expect("ws listener");
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(server.run_until(async {
+        let _ = stopped.await;
+    }));
+
+    let mut client = connect_with_retry(&format!("ws://{ws_addr}")).await;
+    authenticate(&mut client, 7).await;
+
+    // Closing flushes the Goodbye over the socket before it closes
+    stop.send(()).unwrap();
+    recv_opcode(&mut client, Opcode::Goodbye).await;
+    let closed = timeout(Duration::from_secs(5), client.from_server.recv()).await.unwrap();
+    assert!(closed.is_none(), "socket should close after Goodbye");
+
+    timeout(Duration::from_secs(15), server).await.unwrap().unwrap().unwrap();
+}
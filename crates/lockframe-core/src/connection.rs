@@ -26,7 +26,7 @@ use std::{
 
 use lockframe_proto::{
     Frame, FrameHeader, Opcode, Payload,
-    payloads::session::{Goodbye, Hello, HelloReply},
+    payloads::session::{Goodbye, Hello, HelloReply, RoomUnread},
 };
 
 use crate::error::ConnectionError;
@@ -117,6 +117,8 @@ where
     session_id: Option<u64>,
     /// Client's sender ID (from Hello frame, used for `KeyPackage` registry)
     client_sender_id: Option<u64>,
+    /// Unread counts for the `HelloReply` (set by server before Hello)
+    unread: Vec<RoomUnread>,
 }
 
 impl<I> Connection<I>
@@ -132,6 +134,7 @@ where
             last_heartbeat: None,
             session_id: None,
             client_sender_id: None,
+            unread: Vec::new(),
         }
     }
 
@@ -171,6 +174,13 @@ where
         self.session_id = Some(session_id);
     }
 
+    /// Set the unread counts to report (server use, before handling Hello).
+    ///
+    /// The state machine sends them in the `HelloReply` it constructs.
+    pub fn set_unread(&mut self, unread: Vec<RoomUnread>) {
+        self.unread = unread;
+    }
+
     /// Initiate handshake (client use).
     ///
     /// Transitions to Pending state and returns SendFrame(Hello) action.
@@ -232,8 +242,12 @@ where
         self.state = ConnectionState::Authenticated;
         self.last_activity = now;
 
-        let reply =
-            Payload::HelloReply(HelloReply { session_id, capabilities: vec![], challenge: None });
+        let reply = Payload::HelloReply(HelloReply {
+            session_id,
+            capabilities: vec![],
+            challenge: None,
+            unread: std::mem::take(&mut self.unread),
+        });
 
         let frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply))?;
 
@@ -245,6 +259,27 @@ where
         self.state = ConnectionState::Closed;
     }
 
+    /// Close the connection, telling the peer why.
+    ///
+    /// Returns a `Goodbye` frame carrying `reason` followed by `Close`, or
+    /// nothing if the connection is already closed.
+    pub fn initiate_goodbye(&mut self, reason: impl Into<String>) -> Vec<ConnectionAction> {
+        if self.state == ConnectionState::Closed {
+            return Vec::new();
+        }
+        self.close();
+
+        let reason = reason.into();
+        let mut actions = Vec::new();
+        let goodbye = Payload::Goodbye(Goodbye { reason: reason.clone() });
+        // Close even if the Goodbye can't be encoded
+        if let Ok(frame) = goodbye.into_frame(FrameHeader::new(Opcode::Goodbye)) {
+            actions.push(ConnectionAction::SendFrame(frame));
+        }
+        actions.push(ConnectionAction::Close { reason });
+        actions
+    }
+
     /// Mark connection as active (call when receiving frames).
     pub fn update_activity(&mut self, now: I) {
         self.last_activity = now;
@@ -279,9 +314,7 @@ where
                 _ => "timeout".to_string(),
             };
 
-            self.close();
-            actions.push(ConnectionAction::Close { reason });
-            return actions;
+            return self.initiate_goodbye(reason);
         }
 
         if self.state == ConnectionState::Authenticated {
@@ -354,6 +387,7 @@ where
                             session_id,
                             capabilities: vec![],
                             challenge: None,
+                            unread: std::mem::take(&mut self.unread),
                         });
 
                         let frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply))?;
@@ -463,6 +497,7 @@ mod tests {
             session_id: 12345,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let reply_frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         let actions = conn.handle_frame(&reply_frame, t0).unwrap();
@@ -487,6 +522,7 @@ mod tests {
             session_id: 12345,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let reply_frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         conn.handle_frame(&reply_frame, t0).unwrap();
@@ -522,6 +558,7 @@ mod tests {
             session_id: 12345,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let reply_frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         conn.handle_frame(&reply_frame, t0).unwrap();
@@ -703,6 +740,7 @@ mod tests {
             session_id: 12345,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let reply_frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         conn.handle_frame(&reply_frame, t0).unwrap();
@@ -727,6 +765,7 @@ mod tests {
             session_id: 12345,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let reply_frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         conn.handle_frame(&reply_frame, t0).unwrap();
@@ -762,6 +801,40 @@ mod tests {
         assert_eq!(actions.len(), 2);
     }
 
+    #[test]
+    fn idle_timeout_sends_goodbye_before_close() {
+        let env = MockEnv::new();
+        let t0 = env.now();
+        let config = ConnectionConfig::default();
+        let idle_timeout = config.idle_timeout;
+        let mut conn = Connection::new(t0, config);
+
+        conn.send_hello(t0).unwrap();
+        let reply = Payload::HelloReply(HelloReply {
+            session_id: 12345,
+            capabilities: vec![],
+            challenge: None,
+            unread: Vec::new(),
+        });
+        let reply_frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
+        conn.handle_frame(&reply_frame, t0).unwrap();
+
+        env.advance_time(idle_timeout + Duration::from_secs(1));
+        let actions = conn.tick(env.now());
+
+        assert_eq!(conn.state(), ConnectionState::Closed);
+        let [ConnectionAction::SendFrame(goodbye), ConnectionAction::Close { reason }] =
+            actions.as_slice()
+        else {
+            panic!("expected Goodbye then Close, got {actions:?}");
+        };
+        let Payload::Goodbye(payload) = Payload::from_frame(goodbye).unwrap() else {
+            panic!("expected Goodbye payload");
+        };
+        assert_eq!(&payload.reason, reason);
+        assert!(conn.initiate_goodbye("again").is_empty());
+    }
+
     #[test]
     fn handle_error_frame() {
         let env = MockEnv::new();
@@ -774,6 +847,7 @@ mod tests {
             session_id: 12345,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let reply_frame = reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         conn.handle_frame(&reply_frame, t0).unwrap();
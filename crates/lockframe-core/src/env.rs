@@ -25,6 +25,9 @@ pub trait Environment: Clone + Send + Sync + 'static {
 
     /// Current time (monotonic).
     ///
+    /// Use for timeouts and elapsed-time math only. Instants carry no
+    /// absolute meaning; use `wall_clock()` for timestamps.
+    ///
     /// # Invariants
     ///
     /// - This method MUST return values that never decrease within a single
@@ -46,16 +49,21 @@ pub trait Environment: Clone + Send + Sync + 'static {
     /// - Uses cryptographically secure RNG
     fn random_bytes(&self, buffer: &mut [u8]);
 
+    /// Wall-clock time as Unix timestamp in milliseconds.
+    ///
+    /// Used for timestamps that leave the process: HLC stamps, audit logs,
+    /// and persisted metadata. Unlike `now()`, this may jump in either
+    /// direction (NTP adjustments, clock skew), so never use it for timeouts.
+    fn wall_clock(&self) -> u64;
+
     /// Wall-clock time as Unix timestamp (seconds since 1970-01-01 00:00:00
     /// UTC).
     ///
-    /// Used for audit logging and persistent metadata (e.g., room creation
-    /// time). Unlike `now()`, this returns absolute time suitable for
-    /// storage.
-    ///
-    /// Use `now()` for timeouts and elapsed time. Use `wall_clock_secs()` for
-    /// timestamps that need to be persisted or compared across restarts.
-    fn wall_clock_secs(&self) -> u64;
+    /// Convenience over `wall_clock()` for second-granularity metadata (e.g.,
+    /// room creation time).
+    fn wall_clock_secs(&self) -> u64 {
+        self.wall_clock() / 1000
+    }
 
     /// Generates a random `u64`.
     ///
@@ -213,10 +221,11 @@ pub mod test_utils {
             self.rng.lock().expect("MockEnv RNG mutex poisoned").fill_bytes(buffer);
         }
 
-        fn wall_clock_secs(&self) -> u64 {
-            // For testing, return a fixed timestamp (2024-01-01 00:00:00 UTC)
-            // Tests that need specific timestamps can override this behavior
-            1_704_067_200
+        fn wall_clock(&self) -> u64 {
+            // Starts at a fixed timestamp (2024-01-01 00:00:00 UTC) and moves
+            // with `advance_time`
+            let millis = self.offset_nanos.load(Ordering::SeqCst) / 1_000_000;
+            1_704_067_200_000 + millis
         }
     }
 
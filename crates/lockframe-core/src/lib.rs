@@ -23,11 +23,15 @@
 //! - [`connection`]: Connection state machine (handshake, heartbeat, timeout)
 //! - [`mls`]: MLS group state machine (proposals, commits, messages)
 //! - [`mod@env`]: Environment abstraction (time, RNG)
+//! - [`system_env`]: Production environment (real time, crypto RNG)
+//! - [`message_ids`]: Bounded message ID history for duplicate suppression
 //! - [`transport`]: Transport abstraction (streams)
 //! - [`error`]: Connection error types
 
 pub mod connection;
 pub mod env;
 pub mod error;
+pub mod message_ids;
 pub mod mls;
+pub mod system_env;
 pub mod transport;
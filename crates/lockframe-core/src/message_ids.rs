@@ -0,0 +1,70 @@
+//! Bounded message ID history for duplicate suppression.
+//!
+//! Both the server (before sequencing) and the client (before delivery) drop
+//! application messages whose ID they have recently seen. The history is
+//! bounded so memory stays flat on long-lived rooms; IDs older than the
+//! window are forgotten and would be accepted again.
+
+use std::collections::{HashSet, VecDeque};
+
+/// Bounded set of recently seen message IDs, oldest evicted first.
+#[derive(Debug, Clone)]
+pub struct RecentMessageIds {
+    ids: HashSet<u128>,
+    order: VecDeque<u128>,
+    capacity: usize,
+}
+
+impl RecentMessageIds {
+    /// Empty history that remembers at most `capacity` IDs.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { ids: HashSet::new(), order: VecDeque::new(), capacity }
+    }
+
+    /// Whether `message_id` is still in the history.
+    pub fn contains(&self, message_id: u128) -> bool {
+        self.ids.contains(&message_id)
+    }
+
+    /// Record `message_id`, evicting the oldest ID once over capacity.
+    pub fn insert(&mut self, message_id: u128) {
+        if !self.ids.insert(message_id) {
+            return;
+        }
+
+        self.order.push_back(message_id);
+        if self.order.len() > self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgets_oldest() {
+        let mut ids = RecentMessageIds::with_capacity(2);
+        ids.insert(1);
+        ids.insert(2);
+        ids.insert(3);
+
+        assert!(!ids.contains(1));
+        assert!(ids.contains(2));
+        assert!(ids.contains(3));
+    }
+
+    #[test]
+    fn reinserting_does_not_refresh_or_duplicate() {
+        let mut ids = RecentMessageIds::with_capacity(2);
+        ids.insert(1);
+        ids.insert(1);
+        ids.insert(2);
+
+        assert!(ids.contains(1));
+        assert!(ids.contains(2));
+    }
+}
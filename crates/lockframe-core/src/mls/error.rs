@@ -33,6 +33,13 @@ pub enum MlsError {
         received: u64,
     },
 
+    /// Group is at `MAX_EPOCH` and cannot commit again
+    #[error("epoch {epoch} is the last allowed epoch")]
+    EpochExhausted {
+        /// Current epoch
+        epoch: u64,
+    },
+
     /// Member not found in group
     #[error("member not found: {member_id}")]
     MemberNotFound {
@@ -132,5 +139,8 @@ mod tests {
 
         let protocol_err = MlsError::Protocol("invalid tree hash".to_string());
         assert!(!protocol_err.is_transient());
+
+        let exhausted_err = MlsError::EpochExhausted { epoch: 1_000_000 };
+        assert!(!exhausted_err.is_transient());
     }
 }
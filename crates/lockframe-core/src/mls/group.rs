@@ -2,33 +2,46 @@
 
 use std::{collections::HashMap, time::Duration};
 
-use lockframe_proto::{Frame, FrameHeader, Opcode};
+use lockframe_proto::{Frame, FrameHeader, HeaderSigner, Opcode};
 use openmls::{
     key_packages::KeyPackageIn,
     prelude::{
         BasicCredential, Ciphersuite, Credential, CredentialWithKey, GroupId, KeyPackage,
-        LeafNodeIndex, MlsGroupCreateConfig, MlsGroupJoinConfig, MlsMessageBodyIn, MlsMessageIn,
-        OpenMlsProvider, ProcessedMessageContent, ProtocolMessage, ProtocolVersion, StagedWelcome,
+        LeafNodeIndex, LeafNodeParameters, Member, MlsGroupCreateConfig, MlsGroupJoinConfig,
+        MlsMessageBodyIn, MlsMessageIn, OpenMlsProvider, ProcessedMessageContent, ProposalStore,
+        ProtocolMessage, ProtocolVersion, PublicGroup, Sender, StagedCommit, StagedWelcome,
     },
 };
 use openmls_basic_credential::SignatureKeyPair;
+use openmls_memory_storage::MemoryStorage;
 use openmls_traits::signatures::Signer;
 use tls_codec::{Deserialize, Serialize};
 
 use super::{
     MlsGroupState,
+    constants::MAX_EPOCH,
     error::MlsError,
+    membership::CommitMembership,
     provider::MlsProvider,
-    validator::{MlsValidator, ValidationResult},
+    validator::{MlsValidator, ValidationResult, VerifyingKeyCache},
 };
 use crate::env::Environment;
 
 /// Room identifier (128-bit UUID).
+///
+/// Raw alias kept while call sites move to the typed
+/// [`lockframe_proto::ids::RoomId`].
 pub type RoomId = u128;
 
 /// Member identifier within a group.
+///
+/// Raw alias kept while call sites move to the typed
+/// [`lockframe_proto::ids::MemberId`].
 pub type MemberId = u64;
 
+/// Ciphersuite every Lockframe group and `KeyPackage` uses.
+pub const CIPHERSUITE: Ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+
 /// Opaque state needed to process a Welcome message.
 ///
 /// This is returned by [`MlsGroup::generate_key_package`] and must be passed
@@ -39,10 +52,162 @@ pub struct PendingJoinState<E: Environment> {
     signer: SignatureKeyPair,
 }
 
+impl<E: Environment> std::fmt::Debug for PendingJoinState<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PendingJoinState")
+            .field("provider", &"<redacted>")
+            .field("signer", &"<redacted>")
+            .finish()
+    }
+}
+
 /// Result of generating a key package: (`key_package_bytes`, `hash_ref`,
 /// `pending_state`).
 pub type KeyPackageResult<E> = Result<(Vec<u8>, Vec<u8>, PendingJoinState<E>), MlsError>;
 
+/// What a validated `KeyPackage` says about its owner.
+///
+/// Returned by [`validate_key_package`] and
+/// [`MlsGroup::validate_key_package_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPackageInfo {
+    /// Member the `KeyPackage`'s credential names
+    pub member_id: MemberId,
+    /// Ciphersuite the `KeyPackage` was built for
+    pub ciphersuite: Ciphersuite,
+    /// Unix time (seconds) after which the `KeyPackage` is no longer valid
+    pub expires_at_secs: u64,
+}
+
+/// Validate a serialized `KeyPackage` without a group.
+///
+/// Checks the leaf and `KeyPackage` signatures with `provider`'s crypto, that
+/// the package hasn't expired by `provider`'s wall clock, and that the
+/// ciphersuite is [`CIPHERSUITE`]. Used by the server to refuse bad packages
+/// when they are published.
+///
+/// `OpenMLS` also checks the lifetime against the system clock, which can't
+/// be swapped out.
+///
+/// # Errors
+///
+/// - `MlsError::Serialization` if `bytes` is not a `KeyPackage`
+/// - `MlsError::Crypto` if a signature or the lifetime is invalid, the package
+///   has expired, the ciphersuite is not [`CIPHERSUITE`], or the credential has
+///   no member ID
+pub fn validate_key_package<E: Environment>(
+    provider: &MlsProvider<E>,
+    bytes: &[u8],
+) -> Result<KeyPackageInfo, MlsError> {
+    parse_key_package(provider, bytes, CIPHERSUITE).map(|(_, info)| info)
+}
+
+/// Deserialize and verify a `KeyPackage` built for `ciphersuite`.
+fn parse_key_package<E: Environment>(
+    provider: &MlsProvider<E>,
+    mut bytes: &[u8],
+    ciphersuite: Ciphersuite,
+) -> Result<(KeyPackage, KeyPackageInfo), MlsError> {
+    let kp_in = KeyPackageIn::tls_deserialize(&mut bytes)
+        .map_err(|e| MlsError::Serialization(format!("Invalid KeyPackage: {e}")))?;
+    let key_package = kp_in
+        .validate(provider.crypto(), ProtocolVersion::Mls10)
+        .map_err(|e| MlsError::Crypto(format!("Invalid KeyPackage signature: {e:?}")))?;
+
+    if key_package.ciphersuite() != ciphersuite {
+        return Err(MlsError::Crypto(format!(
+            "KeyPackage ciphersuite {:?} does not match {ciphersuite:?}",
+            key_package.ciphersuite()
+        )));
+    }
+
+    let info = KeyPackageInfo {
+        member_id: extract_member_id_from_credential(key_package.leaf_node().credential())?,
+        ciphersuite,
+        expires_at_secs: key_package.life_time().not_after(),
+    };
+    if provider.wall_clock_secs() >= info.expires_at_secs {
+        return Err(MlsError::Crypto(format!("KeyPackage expired at {}", info.expires_at_secs)));
+    }
+    Ok((key_package, info))
+}
+
+/// Read the public group state from a serialized `GroupInfo`.
+///
+/// The `GroupInfo` must carry its ratchet tree. The tree is checked and the
+/// `GroupInfo` signature verified against its signer's leaf, then each
+/// member's ID and signature key are read from the tree's leaves. Used by
+/// the server to learn the keys of members added since its stored state was
+/// written.
+///
+/// # Errors
+///
+/// - `MlsError::Serialization` if `bytes` is not a `GroupInfo` message
+/// - `MlsError::Crypto` if it carries no ratchet tree, or the tree or the
+///   signature is invalid
+pub fn group_state_from_group_info<E: Environment>(
+    provider: &MlsProvider<E>,
+    room_id: RoomId,
+    mut bytes: &[u8],
+) -> Result<MlsGroupState, MlsError> {
+    let group_info = MlsMessageIn::tls_deserialize(&mut bytes)
+        .map_err(|e| MlsError::Serialization(format!("Invalid GroupInfo message: {e}")))?
+        .into_verifiable_group_info()
+        .ok_or_else(|| MlsError::Serialization("Message is not a GroupInfo".to_string()))?;
+    let ratchet_tree = group_info
+        .extensions()
+        .ratchet_tree()
+        .ok_or_else(|| MlsError::Crypto("GroupInfo carries no ratchet tree".to_string()))?
+        .ratchet_tree()
+        .clone();
+
+    // The group is only read, so it is stored in a throwaway storage
+    let (public_group, _) = PublicGroup::from_external(
+        provider.crypto(),
+        &MemoryStorage::default(),
+        ratchet_tree,
+        group_info,
+        ProposalStore::new(),
+    )
+    .map_err(|e| MlsError::Crypto(format!("Invalid GroupInfo: {e}")))?;
+
+    group_state(
+        room_id,
+        public_group.group_context().epoch().as_u64(),
+        public_group.group_context().tree_hash(),
+        public_group.members(),
+    )
+}
+
+/// Group state for `members`, skipping members without a member ID in their
+/// credential.
+fn group_state(
+    room_id: RoomId,
+    epoch: u64,
+    tree_hash: &[u8],
+    members: impl Iterator<Item = Member>,
+) -> Result<MlsGroupState, MlsError> {
+    let mut member_ids = Vec::new();
+    let mut member_keys = HashMap::new();
+
+    for member in members {
+        let Ok(member_id) = extract_member_id_from_credential(&member.credential) else {
+            continue;
+        };
+        member_ids.push(member_id);
+
+        if let Some(key_bytes) = member.signature_key.get(..32).and_then(|b| b.try_into().ok()) {
+            member_keys.insert(member_id, key_bytes);
+        }
+    }
+
+    let tree_hash: [u8; 32] = tree_hash
+        .try_into()
+        .map_err(|_| MlsError::Crypto("tree hash has unexpected length".to_string()))?;
+
+    Ok(MlsGroupState::with_keys(room_id, epoch, tree_hash, member_ids, member_keys))
+}
+
 /// Actions that MLS group operations can produce.
 ///
 /// The application layer is responsible for executing these actions.
@@ -96,6 +261,24 @@ pub enum MlsAction {
     },
 }
 
+/// Decode a frame payload as an MLS protocol message.
+fn protocol_message(frame: &Frame) -> Result<ProtocolMessage, MlsError> {
+    let mls_message = MlsMessageIn::tls_deserialize_exact(&frame.payload)
+        .map_err(|e| MlsError::Serialization(format!("Failed to deserialize MLS message: {e}")))?;
+
+    mls_message
+        .try_into()
+        .map_err(|e| MlsError::Serialization(format!("Invalid MLS message type: {e:?}")))
+}
+
+/// Epoch a commit made at `epoch` moves the group to.
+///
+/// Groups stop at [`MAX_EPOCH`], the same limit the server enforces on
+/// incoming frames, so a commit that would pass it is refused up front.
+fn next_epoch(epoch: u64) -> Result<u64, MlsError> {
+    epoch.checked_add(1).filter(|&next| next <= MAX_EPOCH).ok_or(MlsError::EpochExhausted { epoch })
+}
+
 /// Extract `member_id` from an MLS credential.
 ///
 /// Our credentials store the `member_id` as little-endian u64 bytes.
@@ -145,6 +328,13 @@ pub struct MlsGroup<E: Environment> {
 
     /// Pending commit that we sent (waiting for sequencer acceptance)
     pending_commit: Option<PendingCommit<E::Instant>>,
+
+    /// Member IDs and keys exported for the current epoch, reused for
+    /// frame validation until the next commit
+    validation_state: Option<MlsGroupState>,
+
+    /// Parsed member keys for frame validation in the current epoch
+    key_cache: VerifyingKeyCache,
 }
 
 /// Tracks a commit we sent that's waiting for sequencer acceptance.
@@ -172,7 +362,7 @@ impl<E: Environment> MlsGroup<E> {
         member_id: MemberId,
     ) -> Result<(Self, Vec<MlsAction>), MlsError> {
         let provider = MlsProvider::new(env);
-        let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+        let ciphersuite = CIPHERSUITE;
 
         let signer = SignatureKeyPair::new(ciphersuite.signature_algorithm())
             .map_err(|e| MlsError::Crypto(format!("Failed to generate keypair: {e}")))?;
@@ -192,8 +382,16 @@ impl<E: Environment> MlsGroup<E> {
             openmls::group::MlsGroup::new(&provider, &signer, &group_config, credential_with_key)
                 .map_err(|e| MlsError::Crypto(format!("Failed to create MLS group: {e}")))?;
 
-        let group =
-            Self { room_id, member_id, inner_group, signer, provider, pending_commit: None };
+        let group = Self {
+            room_id,
+            member_id,
+            inner_group,
+            signer,
+            provider,
+            pending_commit: None,
+            validation_state: None,
+            key_cache: VerifyingKeyCache::new(),
+        };
 
         // Export GroupInfo so external joiners can join immediately
         let group_info_bytes = group.export_group_info()?;
@@ -241,20 +439,26 @@ impl<E: Environment> MlsGroup<E> {
     /// assigns `log_index` during sequencing, so it must be excluded from the
     /// signature.
     pub fn sign_frame_header(&self, header: &mut FrameHeader) {
-        let signed_data = header.signing_data();
-
-        if let Ok(signature) = self.signer.sign(&signed_data)
-            && signature.len() == 64
-        {
-            let mut sig_bytes = [0u8; 64];
-            sig_bytes.copy_from_slice(&signature);
-            header.set_signature(sig_bytes);
+        if let Some(signature) = self.sign_header_data(&header.signing_data()) {
+            header.set_signature(signature);
         }
     }
 
-    /// All member positions in the ratchet tree (for sender key derivation).
+    /// All member positions in the ratchet tree (for sender key derivation),
+    /// in ascending order.
+    ///
+    /// Sorted rather than left in `OpenMLS` iteration order, which is not
+    /// part of its API, so every member derives sender keys from the same
+    /// list.
     pub fn member_leaf_indices(&self) -> Vec<u32> {
-        self.inner_group.members().map(|m| m.index.u32()).collect()
+        let mut indices: Vec<u32> = self.inner_group.members().map(|m| m.index.u32()).collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Number of members in the group, without collecting their indices.
+    pub fn member_count(&self) -> usize {
+        self.inner_group.members().count()
     }
 
     /// Member ID at given leaf index. `None` if position is empty.
@@ -318,7 +522,10 @@ impl<E: Environment> MlsGroup<E> {
     ///
     /// This is called when we created a commit (e.g., via `add_members`) and
     /// the sequencer has confirmed it. This advances the group's epoch.
-    pub fn merge_pending_commit(&mut self) -> Result<Vec<MlsAction>, MlsError> {
+    ///
+    /// No `GroupInfo` is exported for the new epoch; callers that publish one
+    /// export it with [`Self::export_group_info`] when needed.
+    pub fn merge_pending_commit(&mut self) -> Result<(), MlsError> {
         let expected_epoch = self
             .pending_commit
             .as_ref()
@@ -336,14 +543,7 @@ impl<E: Environment> MlsGroup<E> {
         );
 
         self.pending_commit = None;
-
-        let group_info_bytes = self.export_group_info()?;
-
-        Ok(vec![MlsAction::PublishGroupInfo {
-            room_id: self.room_id,
-            epoch: actual_epoch,
-            group_info_bytes,
-        }])
+        Ok(())
     }
 
     /// Check if the `OpenMLS` group has a pending commit.
@@ -356,15 +556,18 @@ impl<E: Environment> MlsGroup<E> {
     /// Checks:
     /// - Frame epoch matches group epoch
     /// - Sender is a member of the group
-    pub fn validate_frame(
-        &self,
-        frame: &Frame,
-        mls_state: Option<&MlsGroupState>,
-    ) -> Result<(), MlsError> {
-        let validation_result = if let Some(state) = mls_state {
-            MlsValidator::validate_frame(frame, self.epoch(), state)
-        } else {
-            MlsValidator::validate_frame_no_state(frame)
+    /// - Signature verifies against the sender's key
+    ///
+    /// The group state is exported once per epoch and reused for every
+    /// frame validated in it.
+    pub fn validate_frame(&mut self, frame: &Frame) -> Result<(), MlsError> {
+        let epoch = self.epoch();
+        if self.validation_state.as_ref().is_none_or(|state| state.epoch != epoch) {
+            self.validation_state = Some(self.export_validation_state());
+        }
+        let validation_result = match &self.validation_state {
+            Some(state) => MlsValidator::validate_frame(frame, epoch, state, &mut self.key_cache),
+            None => MlsValidator::validate_frame_no_state(frame),
         };
 
         match validation_result {
@@ -373,18 +576,22 @@ impl<E: Environment> MlsGroup<E> {
         }
     }
 
+    /// Epoch an incoming MLS message (Commit, Proposal, or Application) was
+    /// created in.
+    ///
+    /// Read from the MLS framing without processing the message, so callers
+    /// can tell whether it applies to the current epoch before touching group
+    /// state. Commit frame headers don't carry the epoch.
+    pub fn message_epoch(frame: &Frame) -> Result<u64, MlsError> {
+        Ok(protocol_message(frame)?.epoch().as_u64())
+    }
+
     /// Process an incoming MLS message (Commit, Proposal, or Application).
     ///
     /// Processes an MLS protocol message, updates the group state, and returns
     /// any actions that need to be taken as a result.
     pub fn process_message(&mut self, frame: &Frame) -> Result<Vec<MlsAction>, MlsError> {
-        let mls_message = MlsMessageIn::tls_deserialize_exact(&frame.payload).map_err(|e| {
-            MlsError::Serialization(format!("Failed to deserialize MLS message: {e}"))
-        })?;
-
-        let protocol_message: ProtocolMessage = mls_message
-            .try_into()
-            .map_err(|e| MlsError::Serialization(format!("Invalid MLS message type: {e:?}")))?;
+        let protocol_message = protocol_message(frame)?;
 
         let processed = self
             .inner_group
@@ -392,6 +599,7 @@ impl<E: Environment> MlsGroup<E> {
             .map_err(|e| MlsError::Crypto(format!("Failed to process message: {e}")))?;
 
         let sender_id = extract_member_id_from_credential(processed.credential())?;
+        let declared = CommitMembership::from_aad(processed.aad());
 
         let mut actions = Vec::new();
 
@@ -417,6 +625,7 @@ impl<E: Environment> MlsGroup<E> {
                 });
             },
             ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+                self.check_declared_membership(&staged_commit, &declared?)?;
                 let old_epoch = self.epoch();
 
                 self.inner_group
@@ -430,14 +639,7 @@ impl<E: Environment> MlsGroup<E> {
                     message: format!("Advanced to epoch {}", self.epoch()),
                 });
 
-                if self.inner_group.is_active() {
-                    let group_info_bytes = self.export_group_info()?;
-                    actions.push(MlsAction::PublishGroupInfo {
-                        room_id: self.room_id,
-                        epoch: new_epoch,
-                        group_info_bytes,
-                    });
-                } else {
+                if !self.inner_group.is_active() {
                     actions.push(MlsAction::RemoveGroup {
                         reason: "Removed from group by commit".to_string(),
                     });
@@ -479,20 +681,30 @@ impl<E: Environment> MlsGroup<E> {
         &mut self,
         key_packages_bytes: &[Vec<u8>],
     ) -> Result<Vec<MlsAction>, MlsError> {
+        let ciphersuite = self.inner_group.ciphersuite();
         let key_packages: Vec<KeyPackage> = key_packages_bytes
             .iter()
-            .map(|bytes| {
-                let kp_in = KeyPackageIn::tls_deserialize(&mut bytes.as_slice())
-                    .map_err(|e| MlsError::Serialization(format!("Invalid KeyPackage: {e}")))?;
-                kp_in
-                    .validate(self.provider.crypto(), ProtocolVersion::Mls10)
-                    .map_err(|e| MlsError::Crypto(format!("Invalid KeyPackage signature: {e:?}")))
-            })
+            .map(|bytes| parse_key_package(&self.provider, bytes, ciphersuite).map(|(kp, _)| kp))
             .collect::<Result<Vec<_>, MlsError>>()?;
 
         self.add_members(&key_packages)
     }
 
+    /// Validate a serialized `KeyPackage` as [`Self::add_members_from_bytes`]
+    /// would, without adding anyone.
+    ///
+    /// Checks the signatures, the lifetime, and that the ciphersuite matches
+    /// this group's. Group state is untouched.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`validate_key_package`], with the group's ciphersuite in place
+    /// of [`CIPHERSUITE`].
+    pub fn validate_key_package_bytes(&self, bytes: &[u8]) -> Result<KeyPackageInfo, MlsError> {
+        parse_key_package(&self.provider, bytes, self.inner_group.ciphersuite())
+            .map(|(_, info)| info)
+    }
+
     /// Export the current group state for storage.
     ///
     /// Returns the serialized `OpenMLS` group state that can be stored
@@ -525,33 +737,12 @@ impl<E: Environment> MlsGroup<E> {
     /// epoch, tree hash, member IDs, and member public keys. The server
     /// uses this to validate incoming frames without MLS cryptographic state.
     pub fn export_group_state(&self) -> Result<MlsGroupState, MlsError> {
-        let mut members = Vec::new();
-        let mut member_keys = HashMap::new();
-
-        for member in self.inner_group.members() {
-            let identity = member.credential.serialized_content();
-            if identity.len() >= 8
-                && let Some(id_bytes) = identity.get(..8).and_then(|b| b.try_into().ok())
-            {
-                let member_id = u64::from_le_bytes(id_bytes);
-                members.push(member_id);
-
-                if let Some(key_bytes) =
-                    member.signature_key.get(..32).and_then(|b| b.try_into().ok())
-                {
-                    member_keys.insert(member_id, key_bytes);
-                }
-            }
-        }
-
-        let tree_hash: [u8; 32] = self
-            .inner_group
-            .export_group_context()
-            .tree_hash()
-            .try_into()
-            .map_err(|_| MlsError::Crypto("tree hash has unexpected length".to_string()))?;
-
-        Ok(MlsGroupState::with_keys(self.room_id, self.epoch(), tree_hash, members, member_keys))
+        group_state(
+            self.room_id,
+            self.epoch(),
+            self.inner_group.export_group_context().tree_hash(),
+            self.inner_group.members(),
+        )
     }
 
     /// Export `GroupInfo` for external joiners.
@@ -587,7 +778,7 @@ impl<E: Environment> MlsGroup<E> {
     /// [`Self::join_from_welcome`] when the Welcome message is received.
     pub fn generate_key_package(env: E, member_id: MemberId) -> KeyPackageResult<E> {
         let provider = MlsProvider::new(env);
-        let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+        let ciphersuite = CIPHERSUITE;
 
         let signer = SignatureKeyPair::new(ciphersuite.signature_algorithm())
             .map_err(|e| MlsError::Crypto(format!("Failed to generate keypair: {e}")))?;
@@ -655,6 +846,8 @@ impl<E: Environment> MlsGroup<E> {
             signer,
             provider,
             pending_commit: None,
+            validation_state: None,
+            key_cache: VerifyingKeyCache::new(),
         };
 
         let actions = vec![MlsAction::Log {
@@ -685,7 +878,7 @@ impl<E: Environment> MlsGroup<E> {
         mut group_info_bytes: &[u8],
     ) -> Result<(Self, Vec<MlsAction>), MlsError> {
         let provider = MlsProvider::new(env);
-        let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+        let ciphersuite = CIPHERSUITE;
 
         let signer = SignatureKeyPair::new(ciphersuite.signature_algorithm())
             .map_err(|e| MlsError::Crypto(format!("Failed to generate keypair: {e}")))?;
@@ -703,6 +896,7 @@ impl<E: Environment> MlsGroup<E> {
         let verifiable_group_info = mls_message_in
             .into_verifiable_group_info()
             .ok_or_else(|| MlsError::Serialization("Message is not a GroupInfo".to_string()))?;
+        let group_info_epoch = verifiable_group_info.epoch().as_u64();
 
         let (mls_group, commit_bundle) = openmls::group::MlsGroup::external_commit_builder()
             .build_group(&provider, verifiable_group_info, credential_with_key)
@@ -721,9 +915,11 @@ impl<E: Environment> MlsGroup<E> {
             .tls_serialize_detached()
             .map_err(|e| MlsError::Serialization(format!("Failed to serialize commit: {e}")))?;
 
+        // The server checks the epoch against its latest GroupInfo
         let mut commit_header = FrameHeader::new(Opcode::ExternalCommit);
         commit_header.set_room_id(room_id);
         commit_header.set_sender_id(member_id);
+        commit_header.set_epoch(group_info_epoch);
 
         let commit_frame = Frame::new(commit_header, commit_payload);
         let group = Self {
@@ -733,6 +929,8 @@ impl<E: Environment> MlsGroup<E> {
             signer,
             provider,
             pending_commit: None,
+            validation_state: None,
+            key_cache: VerifyingKeyCache::new(),
         };
         let group_info_bytes = group.export_group_info()?;
 
@@ -755,31 +953,31 @@ impl<E: Environment> MlsGroup<E> {
     /// commit must be sent to the sequencer and will advance the epoch when
     /// accepted.
     fn add_members(&mut self, key_packages: &[KeyPackage]) -> Result<Vec<MlsAction>, MlsError> {
-        let target_epoch = self
-            .epoch()
-            .checked_add(1)
-            .ok_or_else(|| MlsError::Crypto("Epoch overflow".to_string()))?;
+        let target_epoch = next_epoch(self.epoch())?;
         let now = self.provider.now();
 
-        let (mls_message_out, welcome, group_info) = self
+        let added = key_packages
+            .iter()
+            .map(|kp| extract_member_id_from_credential(kp.leaf_node().credential()))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.inner_group
+            .set_aad(CommitMembership { added: added.clone(), removed: Vec::new() }.to_aad()?);
+
+        let (mls_message_out, welcome, _group_info) = self
             .inner_group
             .add_members(&self.provider, &self.signer, key_packages)
-            .map_err(|e| MlsError::Crypto(format!("Failed to add members: {e}")))?;
+            .map_err(|e| {
+                self.inner_group.set_aad(Vec::new());
+                MlsError::Crypto(format!("Failed to add members: {e}"))
+            })?;
 
         self.pending_commit = Some(PendingCommit { target_epoch, sent_at: now });
 
+        // The new epoch's GroupInfo is published once the commit is
+        // sequenced; the server refuses one for an epoch the room hasn't
+        // reached
         let mut actions = Vec::new();
 
-        let group_info_bytes = group_info
-            .tls_serialize_detached()
-            .map_err(|e| MlsError::Serialization(format!("Failed to serialize GroupInfo: {e}")))?;
-
-        actions.push(MlsAction::PublishGroupInfo {
-            room_id: self.room_id,
-            epoch: target_epoch,
-            group_info_bytes,
-        });
-
         let commit_payload = mls_message_out
             .tls_serialize_detached()
             .map_err(|e| MlsError::Serialization(format!("Failed to serialize commit: {e}")))?;
@@ -787,7 +985,9 @@ impl<E: Environment> MlsGroup<E> {
         let mut commit_header = FrameHeader::new(Opcode::Commit);
         commit_header.set_room_id(self.room_id);
         commit_header.set_sender_id(self.member_id);
-        let commit_frame = Frame::new(commit_header, commit_payload);
+        commit_header.set_epoch(target_epoch);
+        let mut commit_frame = Frame::new(commit_header, commit_payload);
+        self.sign_frame_header(&mut commit_frame.header);
 
         actions.push(MlsAction::SendCommit(commit_frame));
 
@@ -795,8 +995,7 @@ impl<E: Environment> MlsGroup<E> {
             .tls_serialize_detached()
             .map_err(|e| MlsError::Serialization(format!("Failed to serialize welcome: {e}")))?;
 
-        for kp in key_packages {
-            let recipient = extract_member_id_from_credential(kp.leaf_node().credential())?;
+        for recipient in added {
             let mut header = FrameHeader::new(Opcode::Welcome);
             header.set_recipient_id(recipient);
             header.set_room_id(self.room_id);
@@ -828,33 +1027,29 @@ impl<E: Environment> MlsGroup<E> {
             ));
         }
 
-        let target_epoch = self
-            .epoch()
-            .checked_add(1)
-            .ok_or_else(|| MlsError::Crypto("Epoch overflow".to_string()))?;
+        let target_epoch = next_epoch(self.epoch())?;
         let now = self.provider.now();
 
         let leaf_indices = self.member_ids_to_leaf_indices(member_ids)?;
+        self.inner_group.set_aad(
+            CommitMembership { added: Vec::new(), removed: member_ids.to_vec() }.to_aad()?,
+        );
 
-        let (mls_message_out, _welcome_option, group_info) = self
+        let (mls_message_out, _welcome_option, _group_info) = self
             .inner_group
             .remove_members(&self.provider, &self.signer, &leaf_indices)
-            .map_err(|e| MlsError::Crypto(format!("Failed to remove members: {e}")))?;
+            .map_err(|e| {
+                self.inner_group.set_aad(Vec::new());
+                MlsError::Crypto(format!("Failed to remove members: {e}"))
+            })?;
 
         self.pending_commit = Some(PendingCommit { target_epoch, sent_at: now });
 
+        // The new epoch's GroupInfo is published once the commit is
+        // sequenced; the server refuses one for an epoch the room hasn't
+        // reached
         let mut actions = Vec::new();
 
-        let group_info_bytes = group_info
-            .tls_serialize_detached()
-            .map_err(|e| MlsError::Serialization(format!("Failed to serialize GroupInfo: {e}")))?;
-
-        actions.push(MlsAction::PublishGroupInfo {
-            room_id: self.room_id,
-            epoch: target_epoch,
-            group_info_bytes,
-        });
-
         let commit_payload = mls_message_out
             .tls_serialize_detached()
             .map_err(|e| MlsError::Serialization(format!("Failed to serialize commit: {e}")))?;
@@ -862,7 +1057,9 @@ impl<E: Environment> MlsGroup<E> {
         let mut commit_header = FrameHeader::new(Opcode::Commit);
         commit_header.set_room_id(self.room_id);
         commit_header.set_sender_id(self.member_id);
-        let commit_frame = Frame::new(commit_header, commit_payload);
+        commit_header.set_epoch(target_epoch);
+        let mut commit_frame = Frame::new(commit_header, commit_payload);
+        self.sign_frame_header(&mut commit_frame.header);
 
         actions.push(MlsAction::SendCommit(commit_frame));
 
@@ -873,6 +1070,39 @@ impl<E: Environment> MlsGroup<E> {
         Ok(actions)
     }
 
+    /// Rotate our leaf key material without changing membership.
+    ///
+    /// Creates a commit that replaces our leaf node with fresh keys, giving
+    /// post-compromise security for our position in the tree. The commit must
+    /// be sent to the sequencer and will advance the epoch when accepted.
+    pub fn self_update(&mut self) -> Result<Vec<MlsAction>, MlsError> {
+        let target_epoch = next_epoch(self.epoch())?;
+        let now = self.provider.now();
+
+        let bundle = self
+            .inner_group
+            .self_update(&self.provider, &self.signer, LeafNodeParameters::default())
+            .map_err(|e| MlsError::Crypto(format!("Failed to create self update: {e}")))?;
+
+        self.pending_commit = Some(PendingCommit { target_epoch, sent_at: now });
+
+        let commit_payload = bundle
+            .commit()
+            .tls_serialize_detached()
+            .map_err(|e| MlsError::Serialization(format!("Failed to serialize commit: {e}")))?;
+
+        let mut commit_header = FrameHeader::new(Opcode::Commit);
+        commit_header.set_room_id(self.room_id);
+        commit_header.set_sender_id(self.member_id);
+        commit_header.set_epoch(target_epoch);
+        let mut commit_frame = Frame::new(commit_header, commit_payload);
+        self.sign_frame_header(&mut commit_frame.header);
+
+        Ok(vec![MlsAction::SendCommit(commit_frame), MlsAction::Log {
+            message: format!("Updating own leaf (member_id={})", self.member_id),
+        }])
+    }
+
     /// Leave the group voluntarily.
     ///
     /// Creates a Remove proposal for this member. The proposal must be sent
@@ -911,6 +1141,58 @@ impl<E: Environment> MlsGroup<E> {
         Ok(actions)
     }
 
+    /// Check that a received commit adds and removes exactly the members its
+    /// authenticated data declares.
+    ///
+    /// The server authorizes commits and keeps its roster from the
+    /// declaration alone, so a commit that changes membership in undeclared
+    /// ways is refused. Removals proposed by the removed member (leaving)
+    /// need not be declared.
+    fn check_declared_membership(
+        &self,
+        staged_commit: &StagedCommit,
+        declared: &CommitMembership,
+    ) -> Result<(), MlsError> {
+        let mut added = staged_commit
+            .add_proposals()
+            .map(|p| {
+                extract_member_id_from_credential(
+                    p.add_proposal().key_package().leaf_node().credential(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut removed = Vec::new();
+        for proposal in staged_commit.remove_proposals() {
+            let leaf_index = proposal.remove_proposal().removed();
+            if *proposal.sender() == Sender::Member(leaf_index) {
+                continue;
+            }
+            let credential =
+                self.inner_group.member(leaf_index).ok_or_else(|| MlsError::InvalidCommit {
+                    reason: format!("commit removes unknown leaf {leaf_index}"),
+                })?;
+            removed.push(extract_member_id_from_credential(credential)?);
+        }
+
+        let mut declared_added = declared.added.clone();
+        let mut declared_removed = declared.removed.clone();
+        for ids in [&mut added, &mut removed, &mut declared_added, &mut declared_removed] {
+            ids.sort_unstable();
+        }
+
+        if added == declared_added && removed == declared_removed {
+            Ok(())
+        } else {
+            Err(MlsError::InvalidCommit {
+                reason: format!(
+                    "commit declares adds {declared_added:?} and removes {declared_removed:?} \
+                     but adds {added:?} and removes {removed:?}"
+                ),
+            })
+        }
+    }
+
     /// Map member IDs to their corresponding leaf node indices.
     fn member_ids_to_leaf_indices(
         &self,
@@ -943,6 +1225,14 @@ impl<E: Environment> MlsGroup<E> {
     }
 }
 
+/// Signs with the group's MLS signature key.
+impl<E: Environment> HeaderSigner for MlsGroup<E> {
+    fn sign_header_data(&self, signing_data: &[u8]) -> Option<[u8; 64]> {
+        let signature = self.signer.sign(signing_data).ok()?;
+        signature.try_into().ok()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -1103,6 +1393,134 @@ mod tests {
         assert_eq!(welcome_frame.header.recipient_id(), bob_id);
         assert_eq!(welcome_frame.header.room_id(), room_id);
         assert_eq!(welcome_frame.header.sender_id(), alice_id);
+
+        // The epoch Bob joins isn't reached until the commit is sequenced
+        assert!(!add_actions.iter().any(|a| matches!(a, MlsAction::PublishGroupInfo { .. })));
+    }
+
+    #[test]
+    fn member_count_tracks_adds_and_removes() {
+        let env = MockEnv::with_crypto_rng();
+        let room_id = 0x1234_5678_9abc_def0_1234_5678_9abc_def0;
+
+        let (mut alice_group, _) =
+            MlsGroup::new(env.clone(), room_id, 42).expect("alice create group");
+        assert_eq!(alice_group.member_count(), 1);
+
+        let (bob_kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env.clone(), 100).expect("bob key package");
+        let (carol_kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env, 101).expect("carol key package");
+        alice_group.add_members_from_bytes(&[bob_kp_bytes, carol_kp_bytes]).expect("add");
+
+        // Pending commits don't change membership until merged
+        assert_eq!(alice_group.member_count(), 1);
+        alice_group.merge_pending_commit().expect("merge add commit");
+        assert_eq!(alice_group.member_count(), 3);
+        assert_eq!(alice_group.member_count(), alice_group.member_leaf_indices().len());
+
+        alice_group.remove_members(&[100]).expect("remove bob");
+        alice_group.merge_pending_commit().expect("merge remove commit");
+        assert_eq!(alice_group.member_count(), 2);
+    }
+
+    /// Test that members with the same view of the group list the same leaf
+    /// indices in the same order, including around a blank leaf left by a
+    /// removal.
+    #[test]
+    fn member_leaf_indices_are_sorted_and_agree_across_members() {
+        let env = MockEnv::with_crypto_rng();
+        let room_id = 0x1234_5678_9abc_def0_1234_5678_9abc_def0;
+
+        let (mut alice_group, _) =
+            MlsGroup::new(env.clone(), room_id, 42).expect("alice create group");
+        let (bob_kp_bytes, _, bob_pending) =
+            MlsGroup::generate_key_package(env.clone(), 100).expect("bob key package");
+        let (carol_kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env.clone(), 101).expect("carol key package");
+        let (dave_kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env, 102).expect("dave key package");
+        let add_actions = alice_group
+            .add_members_from_bytes(&[bob_kp_bytes, carol_kp_bytes, dave_kp_bytes])
+            .expect("add");
+        alice_group.merge_pending_commit().expect("merge add commit");
+        let welcome_frame = add_actions
+            .iter()
+            .find_map(|a| match a {
+                MlsAction::SendWelcome { recipient: 100, frame } => Some(frame.clone()),
+                _ => None,
+            })
+            .expect("should have welcome for bob");
+        let (mut bob_group, _) =
+            MlsGroup::join_from_welcome(room_id, 100, &welcome_frame.payload, bob_pending)
+                .expect("bob join via welcome");
+
+        let remove_actions = alice_group.remove_members(&[101]).expect("remove carol");
+        let commit_frame = remove_actions
+            .iter()
+            .find_map(|a| match a {
+                MlsAction::SendCommit(frame) => Some(frame.clone()),
+                _ => None,
+            })
+            .expect("remove should produce a commit");
+        alice_group.merge_pending_commit().expect("merge remove commit");
+        bob_group.process_message(&commit_frame).expect("bob process remove");
+
+        let indices = alice_group.member_leaf_indices();
+        assert_eq!(indices, vec![0, 1, 3]);
+        assert_eq!(bob_group.member_leaf_indices(), indices);
+
+        // Same indices and same exported secret: same sender keys
+        let alice_secret = alice_group.export_secret("sender keys", b"", 32).expect("export");
+        let bob_secret = bob_group.export_secret("sender keys", b"", 32).expect("export");
+        assert_eq!(alice_secret, bob_secret);
+    }
+
+    #[test]
+    fn next_epoch_stops_at_max_epoch() {
+        assert_eq!(next_epoch(0), Ok(1));
+        assert_eq!(next_epoch(MAX_EPOCH - 1), Ok(MAX_EPOCH));
+        assert_eq!(next_epoch(MAX_EPOCH), Err(MlsError::EpochExhausted { epoch: MAX_EPOCH }));
+        assert_eq!(next_epoch(u64::MAX), Err(MlsError::EpochExhausted { epoch: u64::MAX }));
+    }
+
+    #[test]
+    fn validate_key_package_reports_owner_without_adding() {
+        let env = MockEnv::with_crypto_rng();
+        let (group, _) = MlsGroup::new(env.clone(), 0x100, 1).expect("create group");
+        let (kp_bytes, _, _) = MlsGroup::generate_key_package(env.clone(), 2).expect("generate");
+
+        let info = group.validate_key_package_bytes(&kp_bytes).expect("valid package");
+        assert_eq!(info.member_id, 2);
+        assert_eq!(info.ciphersuite, CIPHERSUITE);
+        // Lifetimes come from the system clock, so only check it's a real date
+        assert!(info.expires_at_secs > 1_700_000_000);
+        let provider = MlsProvider::new(env.clone());
+        assert_eq!(validate_key_package(&provider, &kp_bytes), Ok(info.clone()));
+        assert_eq!(group.member_count(), 1);
+
+        // Expiry is judged by the environment's clock
+        env.advance_time(Duration::from_secs(info.expires_at_secs - env.wall_clock_secs()));
+        assert!(matches!(validate_key_package(&provider, &kp_bytes), Err(MlsError::Crypto(_))));
+    }
+
+    #[test]
+    fn validate_key_package_rejects_tampered_signature() {
+        let env = MockEnv::with_crypto_rng();
+        let (group, _) = MlsGroup::new(env.clone(), 0x100, 1).expect("create group");
+        let (mut kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env.clone(), 2).expect("generate");
+
+        // The KeyPackage signature is the last field
+        *kp_bytes.last_mut().expect("non-empty package") ^= 0x01;
+
+        assert!(matches!(group.validate_key_package_bytes(&kp_bytes), Err(MlsError::Crypto(_))));
+        let provider = MlsProvider::new(env);
+        assert!(matches!(validate_key_package(&provider, &kp_bytes), Err(MlsError::Crypto(_))));
+        assert!(matches!(
+            validate_key_package(&provider, &[1, 2, 3]),
+            Err(MlsError::Serialization(_))
+        ));
     }
 
     /// Test that `remove_members` produces a Commit and removes the correct
@@ -1158,6 +1576,162 @@ mod tests {
         assert!(has_log, "remove_members should log the removed member ID");
     }
 
+    /// Test that `self_update` advances the epoch for every member without
+    /// changing membership.
+    #[test]
+    fn self_update_advances_epoch_for_all_members() {
+        let env = MockEnv::with_crypto_rng();
+        let room_id = 0x1234_5678_9abc_def0_1234_5678_9abc_def0;
+
+        let alice_id = 42u64;
+        let (mut alice_group, _) =
+            MlsGroup::new(env.clone(), room_id, alice_id).expect("alice create group");
+
+        let bob_id = 100u64;
+        let (bob_kp_bytes, _, bob_pending) =
+            MlsGroup::generate_key_package(env, bob_id).expect("bob generate key package");
+        let add_actions =
+            alice_group.add_members_from_bytes(&[bob_kp_bytes]).expect("alice add bob");
+        alice_group.merge_pending_commit().expect("merge add commit");
+        let welcome_frame = add_actions
+            .iter()
+            .find_map(|a| match a {
+                MlsAction::SendWelcome { frame, .. } => Some(frame.clone()),
+                _ => None,
+            })
+            .expect("should have welcome");
+        let (mut bob_group, _) =
+            MlsGroup::join_from_welcome(room_id, bob_id, &welcome_frame.payload, bob_pending)
+                .expect("bob join via welcome");
+
+        let tree_hash_before = alice_group.export_group_state().expect("export").tree_hash;
+
+        let update_actions = bob_group.self_update().expect("bob self update");
+        let commit_frame = update_actions
+            .iter()
+            .find_map(|a| match a {
+                MlsAction::SendCommit(frame) => Some(frame.clone()),
+                _ => None,
+            })
+            .expect("self_update should produce a SendCommit action");
+
+        bob_group.merge_pending_commit().expect("bob merge self update");
+        alice_group.process_message(&commit_frame).expect("alice process self update");
+
+        assert_eq!(alice_group.epoch(), 2);
+        assert_eq!(bob_group.epoch(), 2);
+        assert_eq!(alice_group.member_leaf_indices(), bob_group.member_leaf_indices());
+
+        let alice_state = alice_group.export_group_state().expect("export");
+        let bob_state = bob_group.export_group_state().expect("export");
+        assert_eq!(alice_state.tree_hash, bob_state.tree_hash);
+        assert_ne!(alice_state.tree_hash, tree_hash_before);
+        assert_eq!(alice_state.members, bob_state.members);
+    }
+
+    #[test]
+    fn validate_frame_reuses_state_until_epoch_changes() {
+        let env = MockEnv::with_crypto_rng();
+        let room_id = 0x1234_5678_9abc_def0_1234_5678_9abc_def0;
+        let (mut group, _) = MlsGroup::new(env, room_id, 42).expect("create group");
+        let signed_frame = |group: &MlsGroup<MockEnv>| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(42);
+            header.set_epoch(group.epoch());
+            group.sign_frame_header(&mut header);
+            Frame::new(header, Vec::new())
+        };
+
+        let frame = signed_frame(&group);
+        group.validate_frame(&frame).expect("first frame");
+        group.validate_frame(&frame).expect("second frame");
+        assert_eq!(group.key_cache.parses(), 1);
+
+        group.self_update().expect("self update");
+        group.merge_pending_commit().expect("merge");
+        assert!(group.validate_frame(&frame).is_err(), "old epoch accepted");
+        group.validate_frame(&signed_frame(&group)).expect("new epoch frame");
+        assert_eq!(group.validation_state.as_ref().map(|s| s.epoch), Some(1));
+        assert_eq!(group.key_cache.parses(), 2);
+    }
+
+    /// Test that membership commits declare their adds and removes, and that
+    /// receivers refuse commits that change membership undeclared.
+    #[test]
+    fn commits_declare_membership_changes() {
+        let env = MockEnv::with_crypto_rng();
+        let room_id = 0x1234_5678_9abc_def0_1234_5678_9abc_def0;
+        let commit_of = |actions: &[MlsAction]| {
+            actions
+                .iter()
+                .find_map(|a| match a {
+                    MlsAction::SendCommit(frame) => Some(frame.clone()),
+                    _ => None,
+                })
+                .expect("should have commit")
+        };
+
+        let (mut alice_group, _) =
+            MlsGroup::new(env.clone(), room_id, 42).expect("alice create group");
+        let (bob_kp_bytes, _, bob_pending) =
+            MlsGroup::generate_key_package(env.clone(), 100).expect("bob key package");
+        let add_actions = alice_group.add_members_from_bytes(&[bob_kp_bytes]).expect("add bob");
+        alice_group.merge_pending_commit().expect("merge add bob");
+        let welcome_frame = add_actions
+            .iter()
+            .find_map(|a| match a {
+                MlsAction::SendWelcome { frame, .. } => Some(frame.clone()),
+                _ => None,
+            })
+            .expect("should have welcome");
+        let (mut bob_group, _) =
+            MlsGroup::join_from_welcome(room_id, 100, &welcome_frame.payload, bob_pending)
+                .expect("bob join via welcome");
+
+        let (carol_kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env.clone(), 200).expect("carol key package");
+        let add_commit =
+            commit_of(&alice_group.add_members_from_bytes(&[carol_kp_bytes]).expect("add carol"));
+        assert_eq!(CommitMembership::from_frame(&add_commit), CommitMembership {
+            added: vec![200],
+            removed: vec![]
+        });
+        alice_group.merge_pending_commit().expect("merge add carol");
+        let actions = bob_group.process_message(&add_commit).expect("bob process add carol");
+        // Receivers leave publishing the new epoch's GroupInfo to the committer
+        assert!(!actions.iter().any(|a| matches!(a, MlsAction::PublishGroupInfo { .. })));
+
+        let remove_commit = commit_of(&alice_group.remove_members(&[200]).expect("remove carol"));
+        assert_eq!(CommitMembership::from_frame(&remove_commit), CommitMembership {
+            added: vec![],
+            removed: vec![200]
+        });
+        alice_group.merge_pending_commit().expect("merge remove carol");
+        bob_group.process_message(&remove_commit).expect("bob process remove carol");
+
+        // An add committed without a declaration looks like no change to the
+        // server, so members must refuse it
+        let (dave_kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env, 300).expect("dave key package");
+        let (dave_kp, _) = parse_key_package(&alice_group.provider, &dave_kp_bytes, CIPHERSUITE)
+            .expect("parse dave key package");
+        let (undeclared, _, _) = alice_group
+            .inner_group
+            .add_members(&alice_group.provider, &alice_group.signer, &[dave_kp])
+            .expect("add dave");
+        let mut header = FrameHeader::new(Opcode::Commit);
+        header.set_room_id(room_id);
+        header.set_sender_id(42);
+        let undeclared =
+            Frame::new(header, undeclared.tls_serialize_detached().expect("serialize commit"));
+        assert_eq!(CommitMembership::from_frame(&undeclared), CommitMembership::default());
+
+        let result = bob_group.process_message(&undeclared);
+        assert!(matches!(result, Err(MlsError::InvalidCommit { .. })), "got {result:?}");
+        assert_eq!(bob_group.epoch(), 3);
+    }
+
     /// Test that `remove_members` rejects removing self.
     #[test]
     fn remove_members_rejects_self_removal() {
@@ -1302,4 +1876,38 @@ mod tests {
 
         assert!(result.is_err(), "should reject invalid GroupInfo");
     }
+
+    #[test]
+    fn group_info_yields_keys_that_verify_commits() {
+        let env = MockEnv::with_crypto_rng();
+        let room_id = 0x1234_5678_9abc_def0_1234_5678_9abc_def0;
+        let (mut alice_group, _) = MlsGroup::new(env.clone(), room_id, 42).expect("create group");
+        let (bob_kp_bytes, _, _) =
+            MlsGroup::generate_key_package(env.clone(), 100).expect("bob generate key package");
+        let actions = alice_group.add_members_from_bytes(&[bob_kp_bytes]).expect("add bob");
+        let commit = actions
+            .iter()
+            .find_map(|a| match a {
+                MlsAction::SendCommit(frame) => Some(frame.clone()),
+                _ => None,
+            })
+            .expect("should have commit");
+
+        // Commits are signed with the committer's key in the current epoch
+        let before = alice_group.export_group_state().expect("export");
+        assert_eq!(MlsValidator::validate_signature(&commit, &before), ValidationResult::Accept);
+
+        alice_group.merge_pending_commit().expect("merge add commit");
+        let group_info = alice_group.export_group_info().expect("export group info");
+        let provider = MlsProvider::new(env);
+        let state = group_state_from_group_info(&provider, room_id, &group_info).expect("valid");
+        assert_eq!(state, alice_group.export_group_state().expect("export"));
+        assert_eq!(state.epoch, 1);
+        assert!(state.member_keys.contains_key(&100));
+
+        assert!(matches!(
+            group_state_from_group_info(&provider, room_id, &[1, 2, 3]),
+            Err(MlsError::Serialization(_))
+        ));
+    }
 }
@@ -0,0 +1,125 @@
+//! Membership changes declared by a commit.
+//!
+//! Commits are encrypted, so the server can't see which members they add or
+//! remove. The committer declares those changes in the MLS authenticated data,
+//! which travels in the clear next to the ciphertext and is covered by the
+//! message's authentication. The server authorizes commits and maintains its
+//! roster from the declaration; receivers refuse to merge a commit whose
+//! declaration doesn't match what it actually does.
+
+use lockframe_proto::Frame;
+use openmls::prelude::Sender;
+use serde::{Deserialize, Serialize};
+use tls_codec::{Deserialize as _, VLBytes};
+
+use super::{MemberId, error::MlsError};
+
+/// `WireFormat` of a `PublicMessage`
+const WIRE_FORMAT_PUBLIC: u16 = 1;
+
+/// `WireFormat` of a `PrivateMessage`
+const WIRE_FORMAT_PRIVATE: u16 = 2;
+
+/// Members a commit adds and removes.
+///
+/// Removals proposed by the removed member themselves (leaving) need not be
+/// declared, since they are not the committer's decision.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitMembership {
+    /// Members the commit adds
+    pub added: Vec<MemberId>,
+    /// Members the commit removes
+    pub removed: Vec<MemberId>,
+}
+
+impl CommitMembership {
+    /// Whether the commit leaves membership unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Encode as MLS authenticated data.
+    ///
+    /// An unchanged membership encodes as empty data, which is what commits
+    /// without a declaration carry.
+    pub fn to_aad(&self) -> Result<Vec<u8>, MlsError> {
+        if self.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut aad = Vec::new();
+        ciborium::ser::into_writer(self, &mut aad).map_err(|e| {
+            MlsError::Serialization(format!("Failed to encode commit membership: {e}"))
+        })?;
+        Ok(aad)
+    }
+
+    /// Decode from MLS authenticated data. Empty data declares no change.
+    pub fn from_aad(aad: &[u8]) -> Result<Self, MlsError> {
+        if aad.is_empty() {
+            return Ok(Self::default());
+        }
+
+        ciborium::de::from_reader(aad).map_err(|e| {
+            MlsError::Serialization(format!("Failed to decode commit membership: {e}"))
+        })
+    }
+
+    /// Declaration carried by a commit frame, read without decrypting it.
+    ///
+    /// Frames whose MLS framing or declaration can't be decoded declare no
+    /// change. Receivers refuse to merge such a commit if it does change
+    /// membership, so an undecodable declaration can't hide an add or remove.
+    pub fn from_frame(frame: &Frame) -> Self {
+        authenticated_data(&frame.payload)
+            .and_then(|aad| Self::from_aad(&aad).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Authenticated data of a serialized `MLSMessage`, if it is a public or
+/// private message.
+///
+/// Both framings put the authenticated data right after the group ID, epoch,
+/// and a sender (public) or content type (private) field (RFC 9420 §6).
+fn authenticated_data(mut bytes: &[u8]) -> Option<Vec<u8>> {
+    let _version = u16::tls_deserialize(&mut bytes).ok()?;
+    let wire_format = u16::tls_deserialize(&mut bytes).ok()?;
+    let _group_id = VLBytes::tls_deserialize(&mut bytes).ok()?;
+    let _epoch = u64::tls_deserialize(&mut bytes).ok()?;
+    match wire_format {
+        WIRE_FORMAT_PUBLIC => {
+            Sender::tls_deserialize(&mut bytes).ok()?;
+        },
+        WIRE_FORMAT_PRIVATE => {
+            let _content_type = u8::tls_deserialize(&mut bytes).ok()?;
+        },
+        _ => return None,
+    }
+    let aad = VLBytes::tls_deserialize(&mut bytes).ok()?;
+    Some(aad.as_slice().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use lockframe_proto::{FrameHeader, Opcode};
+
+    use super::*;
+
+    #[test]
+    fn aad_round_trip() {
+        let membership = CommitMembership { added: vec![1, 2], removed: vec![3] };
+        let aad = membership.to_aad().unwrap();
+        assert_eq!(CommitMembership::from_aad(&aad).unwrap(), membership);
+
+        assert!(CommitMembership::default().to_aad().unwrap().is_empty());
+        assert_eq!(CommitMembership::from_aad(&[]).unwrap(), CommitMembership::default());
+        assert!(CommitMembership::from_aad(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn non_mls_payload_declares_no_change() {
+        let frame = Frame::new(FrameHeader::new(Opcode::Commit), b"commit".to_vec());
+        assert_eq!(CommitMembership::from_frame(&frame), CommitMembership::default());
+    }
+}
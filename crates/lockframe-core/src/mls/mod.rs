@@ -8,6 +8,7 @@
 //!
 //! - [`group`]: Client-side MLS group state machine
 //! - [`state`]: MLS group state for storage and validation
+//! - [`membership`]: Membership changes declared by commits
 //! - [`provider`]: `OpenMLS` provider integration
 //! - [`validator`]: Frame validation for server sequencing
 //! - [`error`]: MLS-specific error types
@@ -16,13 +17,18 @@
 pub mod constants;
 pub mod error;
 pub mod group;
+pub mod membership;
 pub mod provider;
 pub mod state;
 pub mod validator;
 
 pub use constants::MAX_EPOCH;
 pub use error::MlsError;
-pub use group::{MemberId, MlsAction, MlsGroup, PendingJoinState, RoomId};
+pub use group::{
+    CIPHERSUITE, KeyPackageInfo, MemberId, MlsAction, MlsGroup, PendingJoinState, RoomId,
+    group_state_from_group_info, validate_key_package,
+};
+pub use membership::CommitMembership;
 pub use provider::MlsProvider;
 pub use state::MlsGroupState;
-pub use validator::{MlsValidator, ValidationResult};
+pub use validator::{MlsValidator, ValidationResult, VerifyingKeyCache};
@@ -39,6 +39,13 @@ impl<E: Environment> MlsProvider<E> {
     pub fn now(&self) -> E::Instant {
         self.rand.env.now()
     }
+
+    /// Wall-clock time (Unix seconds) from the environment.
+    ///
+    /// Used to check `KeyPackage` lifetimes.
+    pub fn wall_clock_secs(&self) -> u64 {
+        self.rand.env.wall_clock_secs()
+    }
 }
 
 /// RNG adapter that delegates to our Environment trait.
@@ -4,7 +4,9 @@
 //! current MLS state (epoch, membership, and signature) without performing full
 //! MLS operations.
 
-use ed25519_dalek::Verifier;
+use std::collections::HashMap;
+
+use ed25519_dalek::{Verifier, VerifyingKey};
 use lockframe_proto::Frame;
 
 use super::{MlsGroupState, constants::MAX_EPOCH};
@@ -22,6 +24,65 @@ pub enum ValidationResult {
     },
 }
 
+/// Parsed verifying keys for one room epoch.
+///
+/// Parsing an Ed25519 public key decompresses a curve point, which is a
+/// significant share of per-frame verification cost. The cache keeps parsed
+/// keys keyed by sender and drops them whenever the room or epoch changes,
+/// since member keys can only change through a commit.
+#[derive(Debug, Default, Clone)]
+pub struct VerifyingKeyCache {
+    /// Room and epoch the cached keys belong to
+    scope: Option<(u128, u64)>,
+    /// Parsed key per sender ID
+    keys: HashMap<u64, VerifyingKey>,
+    /// Number of keys parsed from group state (cache misses)
+    parses: u64,
+}
+
+impl VerifyingKeyCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verifying key for `sender_id`, parsing it from `group_state` on a miss.
+    ///
+    /// Clears the cache first if `group_state` is for a different room or
+    /// epoch than the cached keys.
+    pub fn key_for(&mut self, group_state: &MlsGroupState, sender_id: u64) -> Option<VerifyingKey> {
+        let scope = (group_state.room_id, group_state.epoch);
+        if self.scope != Some(scope) {
+            self.keys.clear();
+            self.scope = Some(scope);
+        }
+
+        if let Some(key) = self.keys.get(&sender_id) {
+            return Some(*key);
+        }
+
+        let key = group_state.member_key(sender_id)?;
+        self.parses += 1;
+        self.keys.insert(sender_id, key);
+        Some(key)
+    }
+
+    /// Number of keys parsed from group state since creation.
+    pub fn parses(&self) -> u64 {
+        self.parses
+    }
+
+    /// Number of keys currently cached.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// `true` if no keys are cached.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
 /// MLS frame validator
 ///
 /// This validator performs lightweight checks needed by the sequencer:
@@ -37,12 +98,16 @@ pub struct MlsValidator;
 impl MlsValidator {
     /// Validate a frame against current MLS group state
     ///
+    /// The sender's verifying key is taken from `cache`, parsing it from
+    /// `group_state` only on the first frame from that sender in the epoch.
+    ///
     /// Note: Validation failures return `Ok(ValidationResult::Reject)`, not
     /// errors.
     pub fn validate_frame(
         frame: &Frame,
         current_epoch: u64,
         group_state: &MlsGroupState,
+        cache: &mut VerifyingKeyCache,
     ) -> ValidationResult {
         debug_assert!(current_epoch < MAX_EPOCH);
 
@@ -62,7 +127,7 @@ impl MlsValidator {
 
         debug_assert!(group_state.is_member(sender_id));
 
-        Self::validate_signature(frame, group_state)
+        Self::verify_with_key(frame, cache.key_for(group_state, sender_id))
     }
 
     /// Validate only the signature of a frame.
@@ -70,9 +135,14 @@ impl MlsValidator {
     /// Use this when epoch and membership have already been validated
     /// (e.g., after sequencing when the frame has been modified).
     pub fn validate_signature(frame: &Frame, group_state: &MlsGroupState) -> ValidationResult {
+        Self::verify_with_key(frame, group_state.member_key(frame.header.sender_id()))
+    }
+
+    /// Verify the frame signature against the sender's key, if known.
+    fn verify_with_key(frame: &Frame, verifying_key: Option<VerifyingKey>) -> ValidationResult {
         let sender_id = frame.header.sender_id();
 
-        let Some(verifying_key) = group_state.member_key(sender_id) else {
+        let Some(verifying_key) = verifying_key else {
             return ValidationResult::Reject {
                 reason: format!(
                     "member {sender_id} has no signature key (group state inconsistency)"
@@ -140,6 +210,11 @@ mod tests {
         Frame::new(header, Bytes::new())
     }
 
+    /// Validate with a fresh key cache.
+    fn validate(frame: &Frame, epoch: u64, state: &MlsGroupState) -> ValidationResult {
+        MlsValidator::validate_frame(frame, epoch, state, &mut VerifyingKeyCache::new())
+    }
+
     fn create_test_state(epoch: u64, members: Vec<u64>) -> MlsGroupState {
         MlsGroupState::new(100, epoch, [0u8; 32], members)
     }
@@ -180,7 +255,7 @@ mod tests {
     fn test_valid_frame_accepted() {
         let (frame, state) = create_signed_frame_and_state(100, 5, vec![100, 200, 300]);
 
-        let result = MlsValidator::validate_frame(&frame, 5, &state);
+        let result = validate(&frame, 5, &state);
 
         assert_eq!(result, ValidationResult::Accept);
     }
@@ -190,7 +265,7 @@ mod tests {
         let frame = create_test_frame(100, 3);
         let state = create_test_state(5, vec![100, 200]);
 
-        let result = MlsValidator::validate_frame(&frame, 5, &state);
+        let result = validate(&frame, 5, &state);
 
         match result {
             ValidationResult::Reject { reason } => {
@@ -207,7 +282,7 @@ mod tests {
         let frame = create_test_frame(100, 7);
         let state = create_test_state(5, vec![100, 200]);
 
-        let result = MlsValidator::validate_frame(&frame, 5, &state);
+        let result = validate(&frame, 5, &state);
 
         match result {
             ValidationResult::Reject { reason } => {
@@ -224,7 +299,7 @@ mod tests {
         let frame = create_test_frame(999, 5); // sender 999 not in group
         let state = create_test_state(5, vec![100, 200, 300]);
 
-        let result = MlsValidator::validate_frame(&frame, 5, &state);
+        let result = validate(&frame, 5, &state);
 
         match result {
             ValidationResult::Reject { reason } => {
@@ -266,7 +341,7 @@ mod tests {
             header.set_signature(signature.to_bytes());
             let frame = Frame::new(header, Bytes::new());
 
-            let result = MlsValidator::validate_frame(&frame, epoch, &state);
+            let result = validate(&frame, epoch, &state);
             assert_eq!(result, ValidationResult::Accept);
         }
     }
@@ -318,7 +393,7 @@ mod tests {
         member_keys.insert(100, verifying_key.to_bytes());
         let state = MlsGroupState::with_keys(100, 5, [0u8; 32], vec![100], member_keys);
 
-        let result = MlsValidator::validate_frame(&frame, 5, &state);
+        let result = validate(&frame, 5, &state);
         assert_eq!(result, ValidationResult::Accept);
     }
 
@@ -348,7 +423,7 @@ mod tests {
         member_keys.insert(100, wrong_verifying_key.to_bytes());
         let state = MlsGroupState::with_keys(100, 5, [0u8; 32], vec![100], member_keys);
 
-        let result = MlsValidator::validate_frame(&frame, 5, &state);
+        let result = validate(&frame, 5, &state);
 
         match result {
             ValidationResult::Reject { reason } => {
@@ -364,7 +439,7 @@ mod tests {
         let frame = create_test_frame(100, 5);
         let state = create_test_state(5, vec![100, 200, 300]);
 
-        let result = MlsValidator::validate_frame(&frame, 5, &state);
+        let result = validate(&frame, 5, &state);
 
         match result {
             ValidationResult::Reject { reason } => {
@@ -387,6 +462,60 @@ mod tests {
         assert_eq!(result, ValidationResult::Accept);
     }
 
+    #[test]
+    fn test_cached_validation_parses_each_key_once() {
+        let (frame, state) = create_signed_frame_and_state(100, 5, vec![100, 200]);
+        let mut cache = VerifyingKeyCache::new();
+
+        for _ in 0..100 {
+            let result = MlsValidator::validate_frame(&frame, 5, &state, &mut cache);
+            assert_eq!(result, ValidationResult::Accept);
+        }
+
+        // 100 verifications, one key parse
+        assert_eq!(cache.parses(), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cached_validation_invalidates_on_epoch_change() {
+        let old_key = SigningKey::generate(&mut rand::thread_rng());
+        let new_key = SigningKey::generate(&mut rand::thread_rng());
+        let state_with = |epoch, key: &SigningKey| {
+            let member_keys = HashMap::from([(100, key.verifying_key().to_bytes())]);
+            MlsGroupState::with_keys(100, epoch, [0u8; 32], vec![100], member_keys)
+        };
+        let frame_signed_by = |epoch, key: &SigningKey| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_sender_id(100);
+            header.set_epoch(epoch);
+            header.set_room_id(100);
+            header.set_signature(key.sign(&header.signing_data()).to_bytes());
+            Frame::new(header, Bytes::new())
+        };
+        let mut cache = VerifyingKeyCache::new();
+
+        let result = MlsValidator::validate_frame(
+            &frame_signed_by(5, &old_key),
+            5,
+            &state_with(5, &old_key),
+            &mut cache,
+        );
+        assert_eq!(result, ValidationResult::Accept);
+
+        // The sender rotated its key in epoch 6; the cached epoch-5 key must
+        // not verify frames signed with the retired key
+        let new_state = state_with(6, &new_key);
+        let result =
+            MlsValidator::validate_frame(&frame_signed_by(6, &old_key), 6, &new_state, &mut cache);
+        assert!(matches!(result, ValidationResult::Reject { .. }));
+
+        let result =
+            MlsValidator::validate_frame(&frame_signed_by(6, &new_key), 6, &new_state, &mut cache);
+        assert_eq!(result, ValidationResult::Accept);
+        assert_eq!(cache.parses(), 2);
+    }
+
     #[test]
     fn test_validate_signature_rejects_invalid() {
         // Generate two different key pairs
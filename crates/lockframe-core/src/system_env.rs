@@ -14,7 +14,7 @@
 
 use std::time::Duration;
 
-use lockframe_core::env::Environment;
+use crate::env::Environment;
 
 /// Production environment using system time and cryptographic RNG.
 ///
@@ -30,9 +30,9 @@ use lockframe_core::env::Environment;
 ///
 /// # Panics
 ///
-/// Panics if the OS RNG fails. This is intentional - a server without
-/// functioning cryptographic randomness cannot operate securely. RNG failure
-/// is extremely rare (indicates OS-level issues) and continuing would
+/// Panics if the OS RNG fails. This is intentional - a server or client
+/// without functioning cryptographic randomness cannot operate securely. RNG
+/// failure is extremely rare (indicates OS-level issues) and continuing would
 /// compromise session IDs, nonces, and all cryptographic operations.
 #[derive(Clone, Default)]
 pub struct SystemEnv;
@@ -60,16 +60,16 @@ impl Environment for SystemEnv {
     #[allow(clippy::expect_used)]
     fn random_bytes(&self, buffer: &mut [u8]) {
         getrandom::fill(buffer)
-            .expect("invariant: OS RNG failure is unrecoverable - server cannot operate securely");
+            .expect("invariant: OS RNG failure is unrecoverable - cannot operate securely");
     }
 
     #[allow(clippy::disallowed_methods)]
     #[allow(clippy::expect_used)]
-    fn wall_clock_secs(&self) -> u64 {
-        std::time::SystemTime::now()
+    fn wall_clock(&self) -> u64 {
+        let since_epoch = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
-            .expect("invariant: system clock is after Unix epoch (1970-01-01)")
-            .as_secs()
+            .expect("invariant: system clock is after Unix epoch (1970-01-01)");
+        u64::try_from(since_epoch.as_millis()).unwrap_or(u64::MAX)
     }
 }
 
@@ -89,6 +89,15 @@ mod tests {
         assert!(t2 > t1, "Time should advance");
     }
 
+    #[test]
+    fn system_env_wall_clock_is_unix_millis() {
+        let env = SystemEnv::new();
+
+        let millis = env.wall_clock();
+        assert!(millis > 1_704_067_200_000, "wall clock should be after 2024-01-01");
+        assert!(env.wall_clock_secs().abs_diff(millis / 1000) <= 1);
+    }
+
     #[test]
     fn system_env_random_bytes_are_random() {
         let env = SystemEnv::new();
@@ -72,6 +72,7 @@ fn prop_state_never_goes_backward() {
             session_id,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let frame = hello_reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         let _ = conn.handle_frame(&frame, now);
@@ -155,6 +156,7 @@ fn prop_idle_timeout_only_authenticated() {
             session_id,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let frame = hello_reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         let _ = conn.handle_frame(&frame, now);
@@ -212,6 +214,7 @@ fn prop_heartbeats_only_authenticated() {
             session_id,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let frame = hello_reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         let _ = conn.handle_frame(&frame, now);
@@ -249,6 +252,7 @@ fn prop_session_id_immutable() {
             session_id: session_id1,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let frame1 = hello_reply1.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         let _ = conn.handle_frame(&frame1, now);
@@ -260,6 +264,7 @@ fn prop_session_id_immutable() {
             session_id: session_id2,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let frame2 = hello_reply2.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
 
@@ -290,6 +295,7 @@ fn prop_activity_resets_timeout() {
             session_id,
             capabilities: vec![],
             challenge: None,
+            unread: Vec::new(),
         });
         let frame = hello_reply.into_frame(FrameHeader::new(Opcode::HelloReply)).unwrap();
         let _ = conn.handle_frame(&frame, now);
@@ -50,10 +50,20 @@
 //! - MLS commit advances epoch -> new epoch secret
 //! - New epoch secret -> all sender keys re-derived from scratch
 //! - Previous compromise doesn't affect new epoch's messages
+//!
+//! # Test Vectors
+//!
+//! `vectors/sender_keys.json` holds known-answer vectors for seed derivation,
+//! ratchet message keys, and message encryption, checked byte for byte by
+//! this crate's tests. Other implementations should check against the same
+//! file.
 
 pub mod sender_keys;
 
+#[cfg(test)]
+mod vectors;
+
 pub use sender_keys::{
-    EncryptedMessage, MessageKey, NONCE_RANDOM_SIZE, SenderKeyError, SymmetricRatchet,
-    decrypt_message, derive_sender_key_seed, encrypt_message,
+    EncryptedMessage, MAX_GENERATION, MessageKey, NONCE_RANDOM_SIZE, SenderKeyError,
+    SymmetricRatchet, decrypt_message, derive_sender_key_seed, encrypt_message,
 };
@@ -17,7 +17,7 @@ pub const NONCE_RANDOM_SIZE: usize = 8;
 const POLY1305_TAG_SIZE: usize = 16;
 
 /// An encrypted message with metadata for decryption.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct EncryptedMessage {
     /// The MLS epoch this message was encrypted under
     pub epoch: u64,
@@ -38,6 +38,18 @@ impl EncryptedMessage {
     }
 }
 
+impl std::fmt::Debug for EncryptedMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedMessage")
+            .field("epoch", &self.epoch)
+            .field("sender_index", &self.sender_index)
+            .field("generation", &self.generation)
+            .field("nonce", &self.nonce)
+            .field("ciphertext_len", &self.ciphertext.len())
+            .finish()
+    }
+}
+
 /// Encrypt a message using `XChaCha20-Poly1305`.
 ///
 /// Returns `EncryptedMessage` containing the ciphertext and metadata.
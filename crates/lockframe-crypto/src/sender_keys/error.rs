@@ -53,6 +53,13 @@ pub enum SenderKeyError {
         /// Current generation when overflow was detected
         current: u32,
     },
+
+    /// An epoch was initialized again with a different member set
+    #[error("epoch {epoch} re-initialized with a different member set")]
+    MemberSetChanged {
+        /// Epoch that was re-initialized
+        epoch: u64,
+    },
 }
 
 impl SenderKeyError {
@@ -65,7 +72,8 @@ impl SenderKeyError {
             // Protocol violations - fatal
             Self::DecryptionFailed { .. }
             | Self::InvalidKeyLength { .. }
-            | Self::GenerationOverflow { .. } => true,
+            | Self::GenerationOverflow { .. }
+            | Self::MemberSetChanged { .. } => true,
 
             // Potentially recoverable - need state sync
             Self::UnknownSender { .. }
@@ -97,6 +105,12 @@ mod tests {
         assert!(!err.is_fatal());
     }
 
+    #[test]
+    fn member_set_changed_is_fatal() {
+        let err = SenderKeyError::MemberSetChanged { epoch: 3 };
+        assert!(err.is_fatal());
+    }
+
     #[test]
     fn error_display() {
         let err = SenderKeyError::RatchetTooFarBehind { current: 10, requested: 100 };
@@ -23,4 +23,4 @@ pub mod ratchet;
 pub use derivation::derive_sender_key_seed;
 pub use encryption::{EncryptedMessage, NONCE_RANDOM_SIZE, decrypt_message, encrypt_message};
 pub use error::SenderKeyError;
-pub use ratchet::{MessageKey, SymmetricRatchet};
+pub use ratchet::{MAX_GENERATION, MessageKey, SymmetricRatchet};
@@ -24,6 +24,11 @@ const MESSAGE_LABEL: &[u8] = b"message";
 /// This limits the work done when receiving out-of-order messages.
 const MAX_SKIP: u32 = 1000;
 
+/// Highest generation a ratchet derives a message key for.
+///
+/// One below `u32::MAX`, so the generation after the last key still fits.
+pub const MAX_GENERATION: u32 = u32::MAX - 1;
+
 /// A message key derived from the ratchet.
 ///
 /// This key is used for a single message encryption/decryption.
@@ -48,6 +53,15 @@ impl MessageKey {
     }
 }
 
+impl std::fmt::Debug for MessageKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageKey")
+            .field("key", &"<redacted>")
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
 // Implement Drop to zeroize key material
 impl Drop for MessageKey {
     fn drop(&mut self) {
@@ -100,7 +114,7 @@ impl SymmetricRatchet {
     /// 3. Overwrites the old chain key
     /// 4. Increments the generation counter
     pub fn advance(&mut self) -> Result<MessageKey, SenderKeyError> {
-        if self.generation == u32::MAX {
+        if self.generation > MAX_GENERATION {
             return Err(SenderKeyError::GenerationOverflow { current: self.generation });
         }
 
@@ -111,8 +125,9 @@ impl SymmetricRatchet {
         self.chain_key.zeroize();
         self.chain_key = next_chain_key;
 
+        // Cannot overflow: generation <= MAX_GENERATION < u32::MAX
         let current_gen = self.generation;
-        self.generation = self.generation.wrapping_add(1);
+        self.generation += 1;
 
         Ok(MessageKey { key: message_key, generation: current_gen })
     }
@@ -122,6 +137,10 @@ impl SymmetricRatchet {
     /// Used for decrypting out-of-order messages. If the target generation
     /// is ahead of our current position, we skip forward.
     pub fn advance_to(&mut self, target: u32) -> Result<MessageKey, SenderKeyError> {
+        if target > MAX_GENERATION {
+            return Err(SenderKeyError::GenerationOverflow { current: self.generation });
+        }
+
         if target < self.generation {
             return Err(SenderKeyError::RatchetTooFarBehind {
                 current: self.generation,
@@ -180,6 +199,15 @@ impl SymmetricRatchet {
     }
 }
 
+impl std::fmt::Debug for SymmetricRatchet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SymmetricRatchet")
+            .field("chain_key", &"<redacted>")
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
 impl Drop for SymmetricRatchet {
     fn drop(&mut self) {
         self.chain_key.zeroize();
@@ -343,4 +371,45 @@ mod tests {
         let key = ratchet.advance().unwrap();
         assert_eq!(key.key().len(), 32);
     }
+
+    #[test]
+    fn advance_stops_cleanly_at_max_generation() {
+        let mut ratchet = SymmetricRatchet { chain_key: test_seed(), generation: MAX_GENERATION };
+
+        let key = ratchet.advance().unwrap();
+        assert_eq!(key.generation(), MAX_GENERATION);
+        assert_eq!(ratchet.generation(), u32::MAX);
+
+        // No key past the cap, and the generation does not wrap
+        assert!(matches!(
+            ratchet.advance(),
+            Err(SenderKeyError::GenerationOverflow { current: u32::MAX })
+        ));
+        assert_eq!(ratchet.generation(), u32::MAX);
+    }
+
+    #[test]
+    fn advance_to_rejects_generation_past_cap() {
+        let mut ratchet =
+            SymmetricRatchet { chain_key: test_seed(), generation: MAX_GENERATION - 1 };
+
+        assert!(matches!(
+            ratchet.advance_to(u32::MAX),
+            Err(SenderKeyError::GenerationOverflow { current })
+                if current == MAX_GENERATION - 1
+        ));
+        assert_eq!(ratchet.advance_to(MAX_GENERATION).unwrap().generation(), MAX_GENERATION);
+    }
+
+    #[test]
+    fn debug_redacts_key_material() {
+        let mut ratchet = SymmetricRatchet::new(&[0xAB; 32]);
+        let key = ratchet.advance().unwrap();
+
+        for debug in [format!("{ratchet:?}"), format!("{key:?}")] {
+            assert!(debug.contains("<redacted>"));
+            assert!(!debug.contains("171"), "raw key bytes leaked: {debug}");
+        }
+        assert!(format!("{key:?}").contains("generation: 0"));
+    }
 }
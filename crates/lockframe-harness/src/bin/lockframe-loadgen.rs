@@ -0,0 +1,58 @@
+//! Load generator entry point.
+//!
+//! Runs a [`LoadConfig`] workload against a simulated server and prints the
+//! [`lockframe_harness::LoadReport`].
+
+use std::{io::Write, time::Duration};
+
+use clap::Parser;
+use lockframe_harness::{LoadConfig, loadgen};
+
+/// Simulated load against a Lockframe server
+#[derive(Parser, Debug)]
+#[command(name = "lockframe-loadgen")]
+#[command(
+    about = "Run simulated clients against a Lockframe server and report throughput and latency"
+)]
+#[command(version)]
+struct Args {
+    /// Number of simulated clients
+    #[arg(short, long, default_value_t = 16)]
+    clients: usize,
+
+    /// Number of rooms the clients are spread across
+    #[arg(short, long, default_value_t = 4)]
+    rooms: usize,
+
+    /// Messages each client sends
+    #[arg(short, long, default_value_t = 50)]
+    messages: usize,
+
+    /// Milliseconds between a client's sends
+    #[arg(short, long, default_value_t = 10)]
+    interval_ms: u64,
+
+    /// Payload bytes per message
+    #[arg(short, long, default_value_t = 256)]
+    payload_size: usize,
+
+    /// Simulation seed
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let config = LoadConfig {
+        clients: args.clients,
+        rooms: args.rooms,
+        messages_per_client: args.messages,
+        send_interval: Duration::from_millis(args.interval_ms),
+        payload_size: args.payload_size,
+        seed: args.seed,
+    };
+
+    let report = loadgen::run(&config)?;
+    writeln!(std::io::stdout(), "{report}")?;
+    Ok(())
+}
@@ -6,7 +6,7 @@
 
 use std::collections::HashMap;
 
-use lockframe_client::{Client, ClientAction, ClientEvent, ClientIdentity};
+use lockframe_client::{Client, ClientAction, ClientConfig, ClientEvent, ClientIdentity};
 use lockframe_core::mls::RoomId;
 use lockframe_proto::{FrameHeader, Opcode, Payload, payloads::mls::GroupInfoPayload};
 
@@ -16,6 +16,8 @@ use crate::SimEnv;
 pub struct TestCluster {
     /// List of simulated clients.
     pub clients: Vec<Client<SimEnv>>,
+    /// Environment shared by every client (one wall clock for all).
+    env: SimEnv,
     /// Simulated server storage: `room_id` -> (epoch, `group_info_bytes`)
     group_info_storage: HashMap<RoomId, (u64, Vec<u8>)>,
 }
@@ -28,11 +30,16 @@ impl TestCluster {
             .map(|i| {
                 let sender_id = (i + 1) as u64;
                 let identity = ClientIdentity::new(sender_id);
-                Client::new(env.clone(), identity)
+                Client::new(env.clone(), identity, ClientConfig::default())
             })
             .collect();
 
-        Self { clients, group_info_storage: HashMap::new() }
+        Self { clients, env, group_info_storage: HashMap::new() }
+    }
+
+    /// Environment shared by every client, e.g. to move their wall clock.
+    pub fn env(&self) -> &SimEnv {
+        &self.env
     }
 
     /// Store `GroupInfo` for a room (simulates server's Storage trait).
@@ -149,27 +156,32 @@ impl TestCluster {
             .map_err(|e| format!("process GroupInfo failed: {e}"))?;
 
         let mut ext_commit = None;
+        let mut joiner_group_info = None;
 
         for action in &join_actions {
-            if let ClientAction::Send(frame) = action
-                && matches!(
-                    frame.header.opcode_enum(),
-                    Some(Opcode::Commit | Opcode::ExternalCommit)
-                )
-            {
-                ext_commit = Some(frame);
+            if let ClientAction::Send(frame) = action {
+                match frame.header.opcode_enum() {
+                    Some(Opcode::Commit | Opcode::ExternalCommit) => ext_commit = Some(frame),
+                    // The joiner publishes GroupInfo for the epoch its commit
+                    // creates
+                    Some(Opcode::GroupInfo) => {
+                        if let Ok(Payload::GroupInfo(payload)) = Payload::from_frame(frame) {
+                            joiner_group_info = Some((payload.epoch, payload.group_info_bytes));
+                        }
+                    },
+                    _ => {},
+                }
             }
         }
 
         let commit = ext_commit.ok_or("no external commit frame")?;
-        let mut joiner_group_info = None;
 
         for (i, client) in self.clients.iter_mut().enumerate() {
             if client.is_member(room_id) || i == joiner_idx {
                 let commit_actions =
                     client.handle(ClientEvent::FrameReceived(commit.clone())).unwrap_or_default();
 
-                // Capture GroupInfo published AFTER commit merge
+                // Capture GroupInfo published after the commit merges
                 for action in &commit_actions {
                     if let ClientAction::Send(frame) = action
                         && frame.header.opcode_enum() == Some(Opcode::GroupInfo)
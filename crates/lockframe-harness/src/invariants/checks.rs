@@ -3,7 +3,7 @@
 //! These invariants capture behavioral properties that must always hold.
 //! They verify WHAT must be true, not specific test scenarios.
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use super::{InvariantKind, InvariantResult, SystemSnapshot, Violation};
 use crate::invariants::Invariant;
@@ -11,8 +11,8 @@ use crate::invariants::Invariant;
 /// Type alias for room-epoch key
 type RoomEpochKey = (u128, u64);
 
-/// Type alias for client member data
-type ClientMemberData = (u64, BTreeSet<u64>);
+/// Type alias for client member data (client, members, member leaf indices)
+type ClientMemberData = (u64, BTreeSet<u64>, BTreeMap<u64, u32>);
 
 /// Type alias for room-epoch members mapping
 type RoomEpochMembers = HashMap<RoomEpochKey, Vec<ClientMemberData>>;
@@ -87,8 +87,10 @@ impl Invariant for EpochMonotonicity {
 
 /// Members of the same room at the same epoch must agree on membership.
 ///
-/// All clients in a room at the same epoch should see the same member set.
-/// Divergent membership views indicate a synchronization bug.
+/// All clients in a room at the same epoch should see the same member set,
+/// and agree on the leaf index of every member they both know. Divergent
+/// membership views indicate a synchronization bug; divergent leaf indices
+/// mean sender keys are derived for the wrong leaf and decryption fails.
 pub struct MembershipConsistency;
 
 impl Invariant for MembershipConsistency {
@@ -102,10 +104,11 @@ impl Invariant for MembershipConsistency {
 
         for client in &state.clients {
             for (room_id, room) in &client.rooms {
-                room_epoch_members
-                    .entry((*room_id, room.epoch))
-                    .or_default()
-                    .push((client.id, room.members.clone()));
+                room_epoch_members.entry((*room_id, room.epoch)).or_default().push((
+                    client.id,
+                    room.members.clone(),
+                    room.leaf_indices.clone(),
+                ));
             }
         }
 
@@ -114,17 +117,32 @@ impl Invariant for MembershipConsistency {
                 continue; // Need at least 2 clients to compare
             }
 
-            let first_members = &clients[0].1;
-            for (client_id, members) in &clients[1..] {
+            let (first_id, first_members, first_leaves) = &clients[0];
+            for (client_id, members, leaves) in &clients[1..] {
                 if members != first_members {
                     return Err(Violation {
                         invariant: self.kind(),
                         message: format!(
-                            "room {} epoch {}: client {} sees members {:?}, client {} sees {:?}",
-                            room_id, epoch, clients[0].0, first_members, client_id, members
+                            "room {room_id} epoch {epoch}: client {first_id} sees members \
+                             {first_members:?}, client {client_id} sees {members:?}"
                         ),
                     });
                 }
+
+                for (member_id, leaf) in leaves {
+                    if let Some(first_leaf) = first_leaves.get(member_id)
+                        && first_leaf != leaf
+                    {
+                        return Err(Violation {
+                            invariant: self.kind(),
+                            message: format!(
+                                "room {room_id} epoch {epoch}: member {member_id} is at leaf \
+                                 {first_leaf} for client {first_id}, leaf {leaf} for client \
+                                 {client_id}"
+                            ),
+                        });
+                    }
+                }
             }
         }
         Ok(())
@@ -158,14 +176,21 @@ impl Invariant for TreeHashConvergence {
         for ((room_id, epoch), clients) in room_epoch_hashes {
             let unique_hashes: HashSet<_> = clients.iter().map(|(_, h)| h).collect();
             if unique_hashes.len() > 1 {
+                // Name one diverging pair so the failure points at real clients
+                let (first_id, first_hash) = clients[0];
+                let diverging =
+                    clients.iter().find(|(_, h)| *h != first_hash).map_or(first_id, |(id, _)| *id);
                 return Err(Violation {
                     invariant: self.kind(),
                     message: format!(
-                        "room {} epoch {}: {} distinct tree hashes among {} clients",
+                        "room {} epoch {}: {} distinct tree hashes among {} clients \
+                         (client {} differs from client {})",
                         room_id,
                         epoch,
                         unique_hashes.len(),
-                        clients.len()
+                        clients.len(),
+                        diverging,
+                        first_id
                     ),
                 });
             }
@@ -287,7 +312,7 @@ impl Invariant for TotalOrdering {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::invariants::{ClientSnapshot, RoomSnapshot};
+    use crate::invariants::{ClientSnapshot, InvariantRegistry, RoomSnapshot};
 
     #[test]
     fn active_room_in_rooms_passes_when_valid() {
@@ -364,6 +389,37 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn membership_consistency_fails_when_leaf_indices_differ() {
+        let room1 = RoomSnapshot::with_epoch(1)
+            .with_members([10, 20])
+            .with_leaf_index(10, 0)
+            .with_leaf_index(20, 1);
+        let room2 = RoomSnapshot::with_epoch(1)
+            .with_members([10, 20])
+            .with_leaf_index(10, 0)
+            .with_leaf_index(20, 2); // Different
+
+        let client1 = ClientSnapshot::new(10).with_room(100, room1);
+        let client2 = ClientSnapshot::new(20).with_room(100, room2);
+
+        let snapshot = SystemSnapshot::from_clients(vec![client1, client2]);
+        let result = MembershipConsistency.check(&snapshot);
+        assert!(result.unwrap_err().message.contains("member 20 is at leaf 1"));
+    }
+
+    #[test]
+    fn membership_consistency_passes_when_leaf_indices_partially_known() {
+        let room1 = RoomSnapshot::with_epoch(1).with_members([10, 20]).with_leaf_index(10, 0);
+        let room2 = RoomSnapshot::with_epoch(1).with_members([10, 20]).with_leaf_index(20, 1);
+
+        let client1 = ClientSnapshot::new(10).with_room(100, room1);
+        let client2 = ClientSnapshot::new(20).with_room(100, room2);
+
+        let snapshot = SystemSnapshot::from_clients(vec![client1, client2]);
+        assert!(MembershipConsistency.check(&snapshot).is_ok());
+    }
+
     #[test]
     fn tree_hash_convergence_passes_when_same() {
         let hash = [42u8; 32];
@@ -391,6 +447,37 @@ mod tests {
         assert!(result.unwrap_err().message.contains("2 distinct"));
     }
 
+    #[test]
+    fn tree_hash_convergence_ignores_different_epochs() {
+        let room1 = RoomSnapshot::with_epoch(1).with_tree_hash([1u8; 32]);
+        let room2 = RoomSnapshot::with_epoch(2).with_tree_hash([2u8; 32]);
+
+        let client1 = ClientSnapshot::new(10).with_room(100, room1);
+        let client2 = ClientSnapshot::new(20).with_room(100, room2);
+
+        let snapshot = SystemSnapshot::from_clients(vec![client1, client2]);
+        assert!(TreeHashConvergence.check(&snapshot).is_ok());
+    }
+
+    #[test]
+    fn standard_registry_reports_divergent_tree_hash() {
+        let hash = [7u8; 32];
+        let client1 = ClientSnapshot::new(10)
+            .with_room(100, RoomSnapshot::with_epoch(3).with_tree_hash(hash));
+        let client2 = ClientSnapshot::new(20)
+            .with_room(100, RoomSnapshot::with_epoch(3).with_tree_hash(hash));
+        let client3 = ClientSnapshot::new(30)
+            .with_room(100, RoomSnapshot::with_epoch(3).with_tree_hash([8u8; 32]));
+
+        let snapshot = SystemSnapshot::from_clients(vec![client1, client2, client3]);
+        let violations = InvariantRegistry::standard().check_all(&snapshot).unwrap_err();
+
+        let violation =
+            violations.iter().find(|v| v.invariant == InvariantKind::TreeHashConvergence).unwrap();
+        assert!(violation.message.contains("room 100 epoch 3"));
+        assert!(violation.message.contains("client 30"));
+    }
+
     #[test]
     fn no_log_gaps_passes_when_sequential() {
         let room = RoomSnapshot::with_epoch(1).with_log_indices([0, 1, 2, 3, 4]);
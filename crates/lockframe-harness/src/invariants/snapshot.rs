@@ -4,7 +4,7 @@
 //! Invariants operate on snapshots rather than live state to ensure
 //! consistent, atomic checks.
 
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use lockframe_core::mls::RoomId;
 use serde::Serialize;
@@ -92,6 +92,8 @@ pub struct RoomSnapshot {
     pub tree_hash: [u8; 32],
     /// Member IDs in this room (`BTreeSet` for deterministic ordering).
     pub members: BTreeSet<u64>,
+    /// Leaf index of each member as seen by this client, keyed by member ID.
+    pub leaf_indices: BTreeMap<u64, u32>,
     /// Number of messages received.
     pub message_count: usize,
     /// Log indices of received messages (for ordering invariants).
@@ -119,6 +121,13 @@ impl RoomSnapshot {
         self
     }
 
+    /// Record the leaf index this client sees for `member_id`.
+    #[must_use]
+    pub fn with_leaf_index(mut self, member_id: u64, leaf_index: u32) -> Self {
+        self.leaf_indices.insert(member_id, leaf_index);
+        self
+    }
+
     /// Set message count.
     #[must_use]
     pub fn with_message_count(mut self, count: usize) -> Self {
@@ -21,6 +21,7 @@
 
 pub mod cluster;
 pub mod invariants;
+pub mod loadgen;
 pub mod model;
 pub mod scenario;
 pub mod sim_driver;
@@ -34,6 +35,7 @@ pub use invariants::{
     InvariantRegistry, InvariantResult, MembershipConsistency, RoomSnapshot, SystemSnapshot,
     TreeHashConvergence, Violation,
 };
+pub use loadgen::{LoadConfig, LoadReport, Percentiles};
 pub use model::{
     ClientId, ErrorProperties, ModelClient, ModelMessage, ModelRoomId, ModelServer, ModelWorld,
     ObservableState, Operation, OperationError, OperationResult, PendingMessage, SmallMessage,
@@ -41,4 +43,4 @@ pub use model::{
 pub use sim_driver::{SimDriver, SimDriverError};
 pub use sim_env::SimEnv;
 pub use sim_server::{SharedSimServer, SimServer, create_shared_server};
-pub use sim_transport::SimTransport;
+pub use sim_transport::{SimFrames, SimTransport, read_frame};
@@ -0,0 +1,492 @@
+//! Load generation against a simulated server.
+//!
+//! [`run`] starts a [`ServerDriver`] on a turmoil host and connects
+//! [`LoadConfig::clients`] simulated clients over [`SimTransport`], spread
+//! round-robin across [`LoadConfig::rooms`]. The first client in each room
+//! creates it and welcomes the rest; once every room is populated, each
+//! client sends its messages at a fixed interval and reads the room's traffic
+//! until every message (including its own echoes) has arrived.
+//!
+//! Frames are sent raw, without MLS, so the numbers measure sequencing and
+//! fan-out in the server rather than client crypto. Every time is simulated,
+//! so a given [`LoadConfig`] always produces the same [`LoadReport`].
+//!
+//! Each message payload starts with its simulated send time, which the
+//! receiving client subtracts from the arrival time to get the latency.
+
+use std::{
+    collections::HashMap,
+    fmt, io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::session::Hello};
+use lockframe_server::{
+    DriverConfig, LogLevel, MemoryStorage, ServerAction, ServerDriver, ServerEvent,
+};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf},
+    sync::{Barrier, mpsc},
+};
+use turmoil::net::{TcpListener, TcpStream};
+
+use crate::{SimEnv, SimTransport, read_frame};
+
+/// Address the load server listens on.
+const SERVER_ADDRESS: &str = "0.0.0.0:443";
+
+/// Address clients connect to.
+const SERVER_HOST: &str = "loadgen-server:443";
+
+/// Bytes at the start of each payload that carry the send time.
+const TIMESTAMP_LEN: usize = 8;
+
+/// Room IDs are offset so none is zero.
+const ROOM_ID_BASE: u128 = 0x1000;
+
+/// Shape of a load run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadConfig {
+    /// Number of simulated clients
+    pub clients: usize,
+    /// Number of rooms, each with at least one client
+    pub rooms: usize,
+    /// Messages each client sends
+    pub messages_per_client: usize,
+    /// Simulated time between a client's sends
+    pub send_interval: Duration,
+    /// Payload bytes per message (at least 8, for the send time)
+    pub payload_size: usize,
+    /// Seed for turmoil's network jitter and the server environment
+    pub seed: u64,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            clients: 16,
+            rooms: 4,
+            messages_per_client: 50,
+            send_interval: Duration::from_millis(10),
+            payload_size: 256,
+            seed: 0,
+        }
+    }
+}
+
+impl LoadConfig {
+    /// Room the client at `index` joins.
+    fn room_of(&self, index: usize) -> u128 {
+        ROOM_ID_BASE + (index % self.rooms) as u128
+    }
+
+    /// Indices of the clients in the same room as `index`, creator first.
+    fn room_members(&self, index: usize) -> impl Iterator<Item = usize> {
+        (index % self.rooms..self.clients).step_by(self.rooms)
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        if self.rooms == 0 || self.clients < self.rooms {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} clients cannot fill {} rooms", self.clients, self.rooms),
+            ));
+        }
+        if self.payload_size < TIMESTAMP_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("payload size must be at least {TIMESTAMP_LEN} bytes"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Latency distribution of delivered messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Percentiles {
+    /// Median
+    pub p50: Duration,
+    /// 90th percentile
+    pub p90: Duration,
+    /// 99th percentile
+    pub p99: Duration,
+    /// Slowest delivery
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles of `samples`, which are sorted in place.
+    fn from_samples(samples: &mut [Duration]) -> Self {
+        samples.sort_unstable();
+        let rank = |percent: usize| {
+            if samples.is_empty() {
+                return Duration::ZERO;
+            }
+            let index = (samples.len() * percent).div_ceil(100).saturating_sub(1);
+            samples[index]
+        };
+        Self { p50: rank(50), p90: rank(90), p99: rank(99), max: rank(100) }
+    }
+}
+
+/// Outcome of a load run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadReport {
+    /// Messages sent by all clients
+    pub sent: usize,
+    /// Messages received by all clients, echoes included
+    pub delivered: usize,
+    /// Simulated time from the first send to the last delivery
+    pub elapsed: Duration,
+    /// Send-to-delivery latency
+    pub latency: Percentiles,
+}
+
+impl LoadReport {
+    /// Deliveries per simulated second.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 { 0.0 } else { self.delivered as f64 / secs }
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "sent:       {}", self.sent)?;
+        writeln!(f, "delivered:  {}", self.delivered)?;
+        writeln!(f, "elapsed:    {:?}", self.elapsed)?;
+        writeln!(f, "throughput: {:.1} msg/s", self.throughput())?;
+        write!(
+            f,
+            "latency:    p50 {:?}  p90 {:?}  p99 {:?}  max {:?}",
+            self.latency.p50, self.latency.p90, self.latency.p99, self.latency.max
+        )
+    }
+}
+
+/// Measurements shared by every client host.
+#[derive(Debug, Default)]
+struct Stats {
+    sent: usize,
+    latencies: Vec<Duration>,
+    first_send: Option<Duration>,
+    last_delivery: Duration,
+}
+
+type SharedStats = Arc<Mutex<Stats>>;
+
+/// Run the workload described by `config` to completion.
+///
+/// # Errors
+///
+/// Returns an error if the config is invalid, or if a client fails or does
+/// not receive all of its room's traffic before the simulation times out.
+pub fn run(config: &LoadConfig) -> Result<LoadReport, Box<dyn std::error::Error>> {
+    config.validate()?;
+
+    // Turmoil panics when a socket buffer fills, so size every buffer to
+    // hold the whole run's traffic
+    let segments = 2 * config.clients * (config.messages_per_client + 1) + 64;
+    let mut sim = turmoil::Builder::new()
+        .rng_seed(config.seed)
+        .simulation_duration(Duration::from_hours(1))
+        .tcp_capacity(segments)
+        .build();
+
+    let seed = config.seed;
+    sim.host("loadgen-server", move || serve(seed));
+
+    let stats = SharedStats::default();
+    let ready = Arc::new(Barrier::new(config.clients));
+    let populated = Arc::new(Barrier::new(config.clients));
+    for index in 0..config.clients {
+        let client = LoadClient {
+            index,
+            config: config.clone(),
+            stats: Arc::clone(&stats),
+            ready: Arc::clone(&ready),
+            populated: Arc::clone(&populated),
+        };
+        sim.client(format!("loadgen-client-{index}"), async move { Ok(client.run().await?) });
+    }
+    sim.run()?;
+
+    let mut stats = stats.lock().map_err(|e| e.to_string())?;
+    let delivered = stats.latencies.len();
+    let elapsed = stats.last_delivery.saturating_sub(stats.first_send.unwrap_or_default());
+    Ok(LoadReport {
+        sent: stats.sent,
+        delivered,
+        elapsed,
+        latency: Percentiles::from_samples(&mut stats.latencies),
+    })
+}
+
+/// Something for the server loop to handle.
+enum Inbound {
+    Frame(u64, Frame),
+    Closed(u64),
+}
+
+/// Server host: accept connections and drive a [`ServerDriver`] with every
+/// frame they send, in arrival order.
+async fn serve(seed: u64) -> turmoil::Result {
+    let listener = TcpListener::bind(SERVER_ADDRESS).await?;
+    let mut driver =
+        ServerDriver::new(SimEnv::with_seed(seed), MemoryStorage::new(), DriverConfig::default());
+    let mut writers: HashMap<u64, WriteHalf<TcpStream>> = HashMap::new();
+    let (inbound_tx, mut inbound_rx) = mpsc::unbounded_channel();
+    let mut next_session_id = 1;
+
+    loop {
+        let event = tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                let session_id = next_session_id;
+                next_session_id += 1;
+
+                let (reader, writer) = tokio::io::split(stream);
+                writers.insert(session_id, writer);
+                tokio::spawn(read_session(session_id, reader, inbound_tx.clone()));
+                ServerEvent::ConnectionAccepted { session_id }
+            },
+            Some(inbound) = inbound_rx.recv() => match inbound {
+                Inbound::Frame(session_id, frame) => ServerEvent::FrameReceived { session_id, frame },
+                Inbound::Closed(session_id) => {
+                    writers.remove(&session_id);
+                    ServerEvent::ConnectionClosed { session_id, reason: "disconnected".into() }
+                },
+            },
+        };
+
+        let actions = driver.process_event(event).map_err(|e| e.to_string())?;
+        for action in actions {
+            match action {
+                ServerAction::SendToSession { session_id, frame } => {
+                    if let Some(writer) = writers.get_mut(&session_id) {
+                        write_frame(writer, &frame).await?;
+                    }
+                },
+                ServerAction::Broadcast { session_ids, frame } => {
+                    for session_id in session_ids {
+                        if let Some(writer) = writers.get_mut(&session_id) {
+                            write_frame(writer, &frame).await?;
+                        }
+                    }
+                },
+                ServerAction::CloseConnection { session_id, .. } => {
+                    writers.remove(&session_id);
+                },
+                ServerAction::Log { level, message, .. } => match level {
+                    LogLevel::Warn | LogLevel::Error => tracing::warn!("{message}"),
+                    LogLevel::Debug | LogLevel::Info => tracing::debug!("{message}"),
+                },
+            }
+        }
+    }
+}
+
+/// Forward frames from one connection to the server loop until it closes.
+async fn read_session(
+    session_id: u64,
+    mut reader: ReadHalf<TcpStream>,
+    inbound: mpsc::UnboundedSender<Inbound>,
+) {
+    while let Ok(frame) = read_frame(&mut reader).await {
+        if inbound.send(Inbound::Frame(session_id, frame)).is_err() {
+            return;
+        }
+    }
+    let _ = inbound.send(Inbound::Closed(session_id));
+}
+
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &Frame) -> io::Result<()> {
+    let mut buf = Vec::new();
+    frame.encode(&mut buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    writer.write_all(&buf).await?;
+    writer.flush().await
+}
+
+/// Simulated time since the run started.
+fn now() -> Duration {
+    turmoil::sim_elapsed().unwrap_or_default()
+}
+
+/// One simulated client.
+struct LoadClient {
+    index: usize,
+    config: LoadConfig,
+    stats: SharedStats,
+    /// Every client has authenticated
+    ready: Arc<Barrier>,
+    /// Every client is subscribed to its room
+    populated: Arc<Barrier>,
+}
+
+impl LoadClient {
+    fn sender_id(index: usize) -> u64 {
+        index as u64 + 1
+    }
+
+    async fn run(self) -> io::Result<()> {
+        let connection = SimTransport::client().connect_to_host(SERVER_HOST).await?;
+        let (mut writer, mut reader) = connection.into_split();
+        let sender_id = Self::sender_id(self.index);
+        let room_id = self.config.room_of(self.index);
+
+        let hello = Hello {
+            version: 1,
+            capabilities: Vec::new(),
+            sender_id: Some(sender_id),
+            auth_token: None,
+        };
+        let hello = Payload::Hello(hello)
+            .into_frame(FrameHeader::new(Opcode::Hello))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        write_frame(&mut writer, &hello).await?;
+        skip_until(&mut reader, Opcode::HelloReply).await?;
+        self.ready.wait().await;
+
+        let mut members = self.config.room_members(self.index);
+        let creator = members.next() == Some(self.index);
+        if creator {
+            // The commit creates the room; the pong proves every welcome
+            // was routed, so all members are subscribed
+            write_frame(
+                &mut writer,
+                &room_frame(Opcode::Commit, room_id, sender_id, 0, Vec::new()),
+            )
+            .await?;
+            for member in members {
+                let mut welcome = room_frame(Opcode::Welcome, room_id, sender_id, 0, Vec::new());
+                welcome.header.set_recipient_id(Self::sender_id(member));
+                write_frame(&mut writer, &welcome).await?;
+            }
+            write_frame(&mut writer, &Frame::new(FrameHeader::new(Opcode::Ping), Vec::new()))
+                .await?;
+            skip_until(&mut reader, Opcode::Pong).await?;
+        } else {
+            skip_until(&mut reader, Opcode::Welcome).await?;
+        }
+        self.populated.wait().await;
+
+        let expected =
+            self.config.room_members(self.index).count() * self.config.messages_per_client;
+        let receiver = tokio::spawn(receive(reader, expected, Arc::clone(&self.stats)));
+
+        let mut ticks = tokio::time::interval(self.config.send_interval);
+        for _ in 0..self.config.messages_per_client {
+            ticks.tick().await;
+            let sent_at = now();
+            let mut payload = vec![0u8; self.config.payload_size];
+            let nanos = u64::try_from(sent_at.as_nanos()).unwrap_or(u64::MAX);
+            payload[..TIMESTAMP_LEN].copy_from_slice(&nanos.to_le_bytes());
+
+            let message = room_frame(Opcode::AppMessage, room_id, sender_id, 1, payload);
+            write_frame(&mut writer, &message).await?;
+
+            let mut stats = self.stats.lock().map_err(|e| io::Error::other(e.to_string()))?;
+            stats.sent += 1;
+            stats.first_send.get_or_insert(sent_at);
+        }
+
+        receiver.await.map_err(io::Error::other)?
+    }
+}
+
+/// Read frames until one with `opcode` arrives.
+async fn skip_until(reader: &mut ReadHalf<TcpStream>, opcode: Opcode) -> io::Result<Frame> {
+    loop {
+        let frame = read_frame(reader).await?;
+        if frame.header.opcode_enum() == Some(opcode) {
+            return Ok(frame);
+        }
+    }
+}
+
+/// Record the latency of `expected` app messages.
+async fn receive(
+    mut reader: ReadHalf<TcpStream>,
+    expected: usize,
+    stats: SharedStats,
+) -> io::Result<()> {
+    let mut received = 0;
+    while received < expected {
+        let frame = skip_until(&mut reader, Opcode::AppMessage).await?;
+        let arrived = now();
+        let sent_at = frame
+            .payload
+            .get(..TIMESTAMP_LEN)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "payload too short"))?;
+
+        let mut stats = stats.lock().map_err(|e| io::Error::other(e.to_string()))?;
+        stats.latencies.push(arrived.saturating_sub(Duration::from_nanos(sent_at)));
+        stats.last_delivery = stats.last_delivery.max(arrived);
+        received += 1;
+    }
+    Ok(())
+}
+
+fn room_frame(
+    opcode: Opcode,
+    room_id: u128,
+    sender_id: u64,
+    epoch: u64,
+    payload: Vec<u8>,
+) -> Frame {
+    let mut header = FrameHeader::new(opcode);
+    header.set_room_id(room_id);
+    header.set_sender_id(sender_id);
+    header.set_epoch(epoch);
+    Frame::new(header, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small() -> LoadConfig {
+        LoadConfig {
+            clients: 6,
+            rooms: 2,
+            messages_per_client: 5,
+            send_interval: Duration::from_millis(20),
+            payload_size: 64,
+            seed: 7,
+        }
+    }
+
+    #[test]
+    fn fixed_workload_is_deterministic() {
+        let config = small();
+        let first = run(&config).unwrap();
+        let second = run(&config).unwrap();
+
+        // Two rooms of three clients, each message delivered to all three
+        assert_eq!(first.sent, 30);
+        assert_eq!(first.delivered, 90);
+        assert!(first.latency.p50 <= first.latency.p99);
+        assert!(first.latency.p99 <= first.latency.max);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn rejects_more_rooms_than_clients() {
+        let config = LoadConfig { clients: 2, rooms: 3, ..small() };
+        assert!(run(&config).is_err());
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let mut samples: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let percentiles = Percentiles::from_samples(&mut samples);
+        assert_eq!(percentiles.p50, Duration::from_millis(50));
+        assert_eq!(percentiles.p90, Duration::from_millis(90));
+        assert_eq!(percentiles.p99, Duration::from_millis(99));
+        assert_eq!(percentiles.max, Duration::from_millis(100));
+    }
+}
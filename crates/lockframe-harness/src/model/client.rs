@@ -18,6 +18,16 @@ pub struct ModelMessage {
     pub log_index: u64,
     /// Epoch when message was sent.
     pub epoch: u64,
+    /// Content was removed by a redaction (tombstone).
+    pub redacted: bool,
+}
+
+impl ModelMessage {
+    /// Replace this message with a tombstone.
+    pub fn redact(&mut self) {
+        self.content.clear();
+        self.redacted = true;
+    }
 }
 
 /// Per-room state in the model client.
@@ -123,6 +133,15 @@ impl ModelClient {
         }
     }
 
+    /// Tombstone the received message at `log_index`, if any.
+    pub fn redact_message(&mut self, room_id: ModelRoomId, log_index: u64) {
+        if let Some(room) = self.rooms.get_mut(&room_id)
+            && let Some(message) = room.messages.iter_mut().find(|m| m.log_index == log_index)
+        {
+            message.redact();
+        }
+    }
+
     /// Join a room (invited by another member).
     pub fn join_room(&mut self, room_id: ModelRoomId) -> OperationResult {
         if self.rooms.contains_key(&room_id) {
@@ -75,6 +75,24 @@ pub enum Operation {
         room_id: ModelRoomId,
     },
 
+    /// Redact a sequenced message, leaving a tombstone in its place.
+    Redact {
+        /// Client performing the redaction (must be member).
+        client_id: ClientId,
+        /// Target room.
+        room_id: ModelRoomId,
+        /// Log index of the message to redact.
+        target_log_index: u64,
+    },
+
+    /// Rotate a member's key material (advances epoch, membership unchanged).
+    SelfUpdate {
+        /// Client updating its own leaf (must be member).
+        client_id: ClientId,
+        /// Target room.
+        room_id: ModelRoomId,
+    },
+
     /// Advance simulation time.
     ///
     /// Triggers timeout processing in both model and real system.
@@ -182,6 +200,12 @@ pub enum OperationError {
         actual: u64,
     },
 
+    /// No message at the target log index.
+    MessageNotFound,
+
+    /// Client lacks permission (e.g. redacting another member's message).
+    Forbidden,
+
     /// Client is partitioned from the server.
     Partitioned,
 
@@ -213,7 +237,9 @@ impl OperationError {
             Self::RoomNotFound
             | Self::RoomAlreadyExists
             | Self::AlreadyMember
-            | Self::NoGroupInfo => ErrorProperties { is_fatal: false, is_retryable: false },
+            | Self::NoGroupInfo
+            | Self::MessageNotFound
+            | Self::Forbidden => ErrorProperties { is_fatal: false, is_retryable: false },
 
             // Retryable errors: sync can fix, or wait for partition heal
             Self::EpochMismatch { .. } | Self::Partitioned => {
@@ -14,7 +14,6 @@ use super::{
 #[derive(Debug, Clone)]
 struct ServerRoomState {
     /// Room creator (for authorization in future).
-    #[allow(dead_code)]
     creator: ClientId,
     /// Members of the room.
     members: HashSet<ClientId>,
@@ -135,7 +134,7 @@ impl ModelServer {
         room.next_log_index += 1;
 
         let epoch = room.epoch;
-        let message = ModelMessage { sender_id, content, log_index, epoch };
+        let message = ModelMessage { sender_id, content, log_index, epoch, redacted: false };
 
         room.messages.push(message.clone());
 
@@ -154,6 +153,38 @@ impl ModelServer {
         Ok(message)
     }
 
+    /// Redact the message at `log_index` on behalf of `redactor`.
+    ///
+    /// Only the message's author or the room's creator may redact it.
+    /// Tombstones the stored message and any copies still awaiting delivery,
+    /// so recipients that have not received it yet only see the tombstone.
+    pub fn redact_message(
+        &mut self,
+        room_id: ModelRoomId,
+        redactor: ClientId,
+        log_index: u64,
+    ) -> Result<(), OperationError> {
+        let room = self.rooms.get_mut(&room_id).ok_or(OperationError::RoomNotFound)?;
+        let creator = room.creator;
+        let message = room
+            .messages
+            .iter_mut()
+            .find(|m| m.log_index == log_index)
+            .ok_or(OperationError::MessageNotFound)?;
+        if message.sender_id != redactor && creator != redactor {
+            return Err(OperationError::Forbidden);
+        }
+        message.redact();
+
+        for pending in &mut self.pending_deliveries {
+            if pending.room_id == room_id && pending.message.log_index == log_index {
+                pending.message.redact();
+            }
+        }
+
+        Ok(())
+    }
+
     /// Remove a member from a room.
     ///
     /// Rooms persist even when all members leave.
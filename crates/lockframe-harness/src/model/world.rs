@@ -85,6 +85,12 @@ impl ModelWorld {
             Operation::RemoveMember { remover_id, target_id, room_id } => {
                 self.apply_remove_member(*remover_id, *target_id, *room_id)
             },
+            Operation::Redact { client_id, room_id, target_log_index } => {
+                self.apply_redact(*client_id, *room_id, *target_log_index)
+            },
+            Operation::SelfUpdate { client_id, room_id } => {
+                self.apply_self_update(*client_id, *room_id)
+            },
             Operation::AdvanceTime { .. } => {
                 // Model doesn't track time
                 OperationResult::Ok
@@ -367,6 +373,65 @@ impl ModelWorld {
         OperationResult::Ok
     }
 
+    /// Apply redact operation.
+    ///
+    /// Any member may redact. Connected members tombstone their copy
+    /// immediately; partitioned members miss the redaction.
+    fn apply_redact(
+        &mut self,
+        client_id: ClientId,
+        room_id: ModelRoomId,
+        target_log_index: u64,
+    ) -> OperationResult {
+        if client_id as usize >= self.clients.len() {
+            return OperationResult::Error(OperationError::InvalidClient);
+        }
+
+        let client = &self.clients[client_id as usize];
+        if client.is_partitioned() {
+            return OperationResult::Error(OperationError::Partitioned);
+        }
+
+        if !client.is_member(room_id) {
+            return OperationResult::Error(OperationError::NotMember);
+        }
+
+        if let Err(e) = self.server.redact_message(room_id, client_id, target_log_index) {
+            return OperationResult::Error(e);
+        }
+
+        for client in &mut self.clients {
+            if client.is_member(room_id) && !client.is_partitioned() {
+                client.redact_message(room_id, target_log_index);
+            }
+        }
+
+        OperationResult::Ok
+    }
+
+    /// Apply self-update operation.
+    ///
+    /// Member rotates its own keys. Advances epoch without changing membership.
+    fn apply_self_update(&mut self, client_id: ClientId, room_id: ModelRoomId) -> OperationResult {
+        if client_id as usize >= self.clients.len() {
+            return OperationResult::Error(OperationError::InvalidClient);
+        }
+
+        if !self.clients[client_id as usize].is_member(room_id) {
+            return OperationResult::Error(OperationError::NotMember);
+        }
+
+        self.server.advance_epoch(room_id);
+
+        for client in &mut self.clients {
+            if client.is_member(room_id) {
+                client.advance_epoch(room_id);
+            }
+        }
+
+        OperationResult::Ok
+    }
+
     /// Messages visible to a client in a room.
     pub fn client_messages(
         &self,
@@ -3,7 +3,10 @@
 #![allow(clippy::disallowed_types, reason = "Synchronous in-memory operations only")]
 
 use std::{
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
     time::Duration,
 };
 
@@ -17,6 +20,10 @@ use rand_chacha::ChaCha20Rng;
 /// via `turmoil::sleep()`. `random_bytes()` uses `ChaCha20Rng` seeded with a
 /// fixed value (0 by default), ensuring reproducible test runs and easier
 /// debugging.
+///
+/// `wall_clock()` is a separate clock that starts at 2024-01-01 00:00:00 UTC
+/// and only moves when a test sets or advances it, so tests can skew it
+/// without disturbing timeouts driven by `now()`.
 #[derive(Clone)]
 pub struct SimEnv {
     /// Seeded RNG for deterministic random bytes
@@ -25,8 +32,13 @@ pub struct SimEnv {
     /// across clones (important for proper RNG sequence).
     /// Note: Turmoil is single-threaded, so this Mutex will never block.
     rng: Arc<Mutex<ChaCha20Rng>>,
+    /// Wall clock in Unix milliseconds, shared across clones
+    wall_clock_millis: Arc<AtomicU64>,
 }
 
+/// Initial simulated wall clock (2024-01-01 00:00:00 UTC) in milliseconds.
+const INITIAL_WALL_CLOCK_MILLIS: u64 = 1_704_067_200_000;
+
 impl SimEnv {
     /// Create a new `SimEnv` with default seed (0)
     ///
@@ -41,7 +53,23 @@ impl SimEnv {
     /// Use this when you want to test different random scenarios while
     /// maintaining reproducibility.
     pub fn with_seed(seed: u64) -> Self {
-        Self { rng: Arc::new(Mutex::new(ChaCha20Rng::seed_from_u64(seed))) }
+        Self {
+            rng: Arc::new(Mutex::new(ChaCha20Rng::seed_from_u64(seed))),
+            wall_clock_millis: Arc::new(AtomicU64::new(INITIAL_WALL_CLOCK_MILLIS)),
+        }
+    }
+
+    /// Set the wall clock to `millis` since the Unix epoch.
+    ///
+    /// May move the clock backwards; `now()` is unaffected.
+    pub fn set_wall_clock(&self, millis: u64) {
+        self.wall_clock_millis.store(millis, Ordering::SeqCst);
+    }
+
+    /// Advance the wall clock by `duration`. `now()` is unaffected.
+    pub fn advance_wall_clock(&self, duration: Duration) {
+        let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        self.wall_clock_millis.fetch_add(millis, Ordering::SeqCst);
     }
 }
 
@@ -73,10 +101,10 @@ impl Environment for SimEnv {
             .fill_bytes(dest);
     }
 
-    fn wall_clock_secs(&self) -> u64 {
-        // For simulation, return a fixed timestamp (2024-01-01 00:00:00 UTC)
-        // Virtual time in turmoil is relative, not absolute
-        1_704_067_200
+    fn wall_clock(&self) -> u64 {
+        // Virtual time in turmoil is relative, not absolute, so the wall
+        // clock is tracked separately
+        self.wall_clock_millis.load(Ordering::SeqCst)
     }
 }
 
@@ -103,6 +131,36 @@ mod tests {
         sim.run().expect("simulation failed");
     }
 
+    #[test]
+    fn sim_env_wall_clock_is_controllable_and_separate() {
+        let mut sim = turmoil::Builder::new().build();
+
+        sim.client("test", async {
+            let env = SimEnv::new();
+            assert_eq!(env.wall_clock(), SimEnv::new().wall_clock(), "start is deterministic");
+            assert_eq!(env.wall_clock_secs(), 1_704_067_200);
+
+            // Monotonic time passing leaves the wall clock alone
+            let start = env.now();
+            let wall_start = env.wall_clock();
+            env.sleep(Duration::from_secs(5)).await;
+            assert_eq!(env.wall_clock(), wall_start);
+
+            // Skewing the wall clock, even backwards, leaves `now()` alone
+            let before_skew = env.now();
+            env.advance_wall_clock(Duration::from_millis(1_500));
+            assert_eq!(env.wall_clock(), wall_start + 1_500);
+            env.set_wall_clock(1_000);
+            assert_eq!(env.clone().wall_clock(), 1_000, "clones share the wall clock");
+            assert_eq!(env.now(), before_skew);
+            assert_eq!(env.now() - start, Duration::from_secs(5));
+
+            Ok(())
+        });
+
+        sim.run().expect("simulation failed");
+    }
+
     #[test]
     fn sim_env_rng_is_deterministic() {
         // Run the same test twice with same seed, verify same output
@@ -3,7 +3,9 @@
 //! `SimServer` wraps `ServerDriver` for integration with turmoil's
 //! deterministic simulation. It uses `SimEnv` with `MemoryStorage` for the
 //! action-based core, turmoil TCP for networking, and tracks connection state
-//! in a `HashMap`.
+//! in a `HashMap`. Inbound frames are only read when the test asks for them via
+//! [`SimServer::receive_frame`], so tests control exactly when the server
+//! makes progress.
 
 use std::{
     collections::HashMap,
@@ -16,15 +18,17 @@ use lockframe_server::{
     DriverConfig, LogLevel, MemoryStorage, ServerAction, ServerDriver, ServerEvent,
 };
 use tokio::{
-    io::{AsyncWriteExt, WriteHalf},
+    io::{AsyncWriteExt, ReadHalf, WriteHalf},
     sync::Mutex,
 };
 use turmoil::net::{TcpListener, TcpStream};
 
-use crate::SimEnv;
+use crate::{SimEnv, sim_transport::read_frame};
 
 /// Connection state for a simulated connection.
 struct SimConnectionState {
+    /// Read half for receiving frames
+    reader: ReadHalf<TcpStream>,
     /// Write half for sending frames
     writer: WriteHalf<TcpStream>,
 }
@@ -77,8 +81,8 @@ impl SimServer {
             .process_event(ServerEvent::ConnectionAccepted { session_id })
             .map_err(|e| io::Error::other(e.to_string()))?;
 
-        let (_reader, writer) = tokio::io::split(stream);
-        self.connections.insert(session_id, SimConnectionState { writer });
+        let (reader, writer) = tokio::io::split(stream);
+        self.connections.insert(session_id, SimConnectionState { reader, writer });
 
         // Execute actions
         self.execute_actions(actions).await?;
@@ -169,6 +173,18 @@ impl SimServer {
         self.execute_actions(actions).await
     }
 
+    /// Read the next frame from a connection and process it.
+    ///
+    /// Waits until a full frame has arrived on `session_id`.
+    pub async fn receive_frame(&mut self, session_id: u64) -> io::Result<()> {
+        let conn = self.connections.get_mut(&session_id).ok_or_else(|| {
+            io::Error::new(ErrorKind::NotConnected, format!("unknown session {session_id}"))
+        })?;
+        let frame = read_frame(&mut conn.reader).await?;
+
+        self.process_frame(session_id, frame).await
+    }
+
     /// Create a room (for testing convenience).
     ///
     /// The creator connection must already exist.
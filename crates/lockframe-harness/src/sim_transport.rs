@@ -3,8 +3,10 @@
 use std::{io, net::SocketAddr};
 
 use async_trait::async_trait;
+use lockframe_client::connection::StreamFrames;
 use lockframe_core::transport::{Transport, TransportConnection};
-use tokio::io::{ReadHalf, WriteHalf};
+use lockframe_proto::{Frame, FrameHeader};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadHalf, WriteHalf};
 use turmoil::net::{TcpListener, TcpStream};
 
 /// Simulation transport using Turmoil's deterministic TCP streams.
@@ -46,6 +48,19 @@ impl SimConnection {
     }
 }
 
+/// Frame transport over a [`SimConnection`], for driving a real
+/// [`ConnectedClient`](lockframe_client::connection::ConnectedClient).
+pub type SimFrames = StreamFrames<WriteHalf<TcpStream>, ReadHalf<TcpStream>>;
+
+impl SimConnection {
+    /// Turn the connection into a frame transport.
+    #[must_use]
+    pub fn into_frames(self) -> SimFrames {
+        let (send, recv) = self.into_split();
+        StreamFrames::new(send, recv)
+    }
+}
+
 impl SimTransport {
     /// Creates a server endpoint bound to the specified address.
     ///
@@ -77,6 +92,22 @@ impl SimTransport {
     }
 }
 
+/// Read one length-delimited frame from a stream.
+///
+/// Reads the fixed-size header first, then the body it announces.
+pub async fn read_frame<R: AsyncRead + Unpin>(recv: &mut R) -> io::Result<Frame> {
+    let mut buf = vec![0u8; FrameHeader::SIZE];
+    recv.read_exact(&mut buf).await?;
+
+    let body_size = FrameHeader::from_bytes(&buf)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?
+        .body_size();
+    buf.resize(FrameHeader::SIZE + body_size, 0);
+    recv.read_exact(&mut buf[FrameHeader::SIZE..]).await?;
+
+    Frame::decode(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
 #[async_trait]
 impl Transport for SimTransport {
     type Connection = SimConnection;
@@ -139,9 +170,15 @@ impl TransportConnection for SimConnection {
 
 #[cfg(test)]
 mod tests {
+    use lockframe_client::connection::ConnectedClient;
+    use lockframe_proto::{
+        Opcode,
+        payloads::{Payload, session::Hello},
+    };
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
     use super::*;
+    use crate::SimServer;
 
     #[test]
     fn sim_transport_echo() {
@@ -217,4 +254,35 @@ mod tests {
 
         sim.run().expect("simulation failed");
     }
+
+    #[test]
+    fn connected_client_runs_over_sim_transport() {
+        let mut sim = turmoil::Builder::new().build();
+
+        sim.host("server", || async {
+            let mut server = SimServer::bind("0.0.0.0:443").await?;
+            let session_id = server.accept_connection().await?;
+            server.receive_frame(session_id).await?;
+            Ok(())
+        });
+
+        sim.client("client", async {
+            let transport = SimTransport::client();
+            let conn = transport.connect_to_host("server:443").await?;
+            let mut client = ConnectedClient::new(conn.into_frames());
+
+            let hello =
+                Hello { version: 1, capabilities: vec![], sender_id: Some(7), auth_token: None };
+            let frame = Payload::Hello(hello).into_frame(FrameHeader::new(Opcode::Hello))?;
+            client.to_server.send(frame).await?;
+
+            let reply = client.from_server.recv().await.expect("server replies");
+            assert_eq!(reply.header.opcode_enum(), Some(Opcode::HelloReply));
+
+            client.stop();
+            Ok(())
+        });
+
+        sim.run().expect("simulation failed");
+    }
 }
@@ -1,15 +1,19 @@
 //! Cluster convergence tests using Deterministic Simulation Testing.
 //!
 //! Uses the `TestCluster` fake to verify that Client convergence logic works
-//! correctly under various join scenarios (Welcome, External, mixed).
+//! correctly under various join scenarios (Welcome, External, mixed), and that
+//! concurrent senders converge on the sequencer's total order.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, VecDeque};
 
 use insta::assert_json_snapshot;
+use lockframe_client::{ClientAction, ClientEvent};
 use lockframe_core::mls::RoomId;
 use lockframe_harness::{
     ClientSnapshot, InvariantRegistry, RoomSnapshot, SystemSnapshot, TestCluster,
 };
+use lockframe_proto::Frame;
+use lockframe_server::{MemoryStorage, Sequencer, SequencerAction};
 use proptest::prelude::*;
 
 const ROOM_ID: RoomId = 0x0001_0001_0001_0001_0001_0001_0001_0001;
@@ -30,8 +34,9 @@ fn to_snapshot(cluster: &TestCluster) -> SystemSnapshot {
         let members: BTreeSet<u64> =
             client.member_ids(ROOM_ID).unwrap_or_default().into_iter().collect();
 
-        let room_snapshot =
+        let mut room_snapshot =
             RoomSnapshot::with_epoch(epoch).with_tree_hash(tree_hash).with_members(members);
+        room_snapshot.leaf_indices = client.member_leaf_indices(ROOM_ID).unwrap_or_default();
 
         let mut client_snapshot = ClientSnapshot::new(client_id);
         client_snapshot.rooms.insert(ROOM_ID, room_snapshot);
@@ -160,6 +165,101 @@ proptest! {
     }
 }
 
+/// Merge two per-sender queues, taking from `first` when the next pick is
+/// `true`. Each sender's own order is preserved; leftovers are appended.
+fn merge_by<T>(mut first: VecDeque<T>, mut second: VecDeque<T>, picks: &[bool]) -> Vec<T> {
+    let mut merged = Vec::with_capacity(first.len() + second.len());
+    for &pick_first in picks {
+        let next = if pick_first { first.pop_front() } else { second.pop_front() };
+        merged.extend(next);
+    }
+    merged.extend(first);
+    merged.extend(second);
+    merged
+}
+
+/// `(log_index, sender_id, plaintext)` for every message `receiver` delivers
+/// from `frames`, in the order it delivered them.
+fn deliver_all(
+    cluster: &mut TestCluster,
+    receiver: usize,
+    frames: Vec<Frame>,
+) -> Result<Vec<(u64, u64, Vec<u8>)>, String> {
+    let mut delivered = Vec::new();
+    for frame in frames {
+        let actions = cluster.clients[receiver]
+            .handle(ClientEvent::FrameReceived(frame))
+            .map_err(|e| format!("client {receiver} receive failed: {e}"))?;
+        for action in actions {
+            if let ClientAction::DeliverMessage { log_index, sender_id, plaintext, .. } = action {
+                delivered.push((log_index, sender_id, plaintext));
+            }
+        }
+    }
+    Ok(delivered)
+}
+
+proptest! {
+    /// Two members send in the same tick and their frames reach the real
+    /// sequencer interleaved. Every receiver must deliver the messages in
+    /// exactly the order the sequencer accepted them.
+    #[test]
+    fn prop_concurrent_senders_share_total_order(
+        seed in 1u64..10000,
+        sequencer_picks in prop::collection::vec(any::<bool>(), 2..16),
+    ) {
+        let mut cluster = TestCluster::new(seed, 4);
+        cluster.create_room(ROOM_ID).expect("create");
+        for joiner in 1..4 {
+            cluster.join_via_welcome(ROOM_ID, joiner).expect("join");
+        }
+
+        // Both senders encrypt their whole batch before anything is sequenced
+        let mut outboxes = [VecDeque::new(), VecDeque::new()];
+        for (i, &from_first) in sequencer_picks.iter().enumerate() {
+            let sender = usize::from(!from_first);
+            let plaintext = format!("{sender}:{i}").into_bytes();
+            let actions = cluster.clients[sender]
+                .handle(ClientEvent::SendMessage { room_id: ROOM_ID, plaintext: plaintext.clone() })
+                .expect("send");
+            outboxes[sender].extend(actions.into_iter().filter_map(|a| match a {
+                ClientAction::Send(frame) => Some((frame, plaintext.clone())),
+                _ => None,
+            }));
+        }
+        let [first, second] = outboxes;
+        let submitted = merge_by(first, second, &sequencer_picks);
+
+        // The order the sequencer accepts frames in is the total order
+        let total_order: Vec<(u64, u64, Vec<u8>)> = submitted
+            .iter()
+            .zip(0..)
+            .map(|((frame, plaintext), log_index)| {
+                (log_index, frame.header.sender_id(), plaintext.clone())
+            })
+            .collect();
+
+        let storage = MemoryStorage::new();
+        let mut sequencer = Sequencer::new();
+        let mut broadcast = Vec::new();
+        for (frame, _) in submitted {
+            for action in sequencer.process_frame(frame, &storage).expect("sequence") {
+                if let SequencerAction::BroadcastToRoom { frame, .. } = action {
+                    broadcast.push(frame);
+                }
+            }
+        }
+        let indices: Vec<u64> = broadcast.iter().map(|f| f.header.log_index()).collect();
+        let expected: Vec<u64> = (0..broadcast.len() as u64).collect();
+        prop_assert_eq!(&indices, &expected, "sequencer must assign contiguous indices");
+
+        for receiver in 2..4 {
+            let seen = deliver_all(&mut cluster, receiver, broadcast.clone()).expect("receive");
+            prop_assert_eq!(&seen, &total_order, "receiver {} diverged from sequencer order", receiver);
+        }
+    }
+}
+
 /// Snapshot of converged state after Welcome-based joins.
 ///
 /// This test uses a fixed seed to ensure deterministic state, allowing us to
@@ -0,0 +1,546 @@
+//! Model comparison against the real server.
+//!
+//! The model equivalence tests in `lockframe-client` stand in for the server
+//! with test bookkeeping, so bugs in `ServerDriver` routing or `Sequencer`
+//! ordering go unnoticed there. These tests wire real `Client`s to a real
+//! `ServerDriver` (through `SimServer`) over `SimTransport` connections, apply
+//! the same operations to `ModelWorld`, and compare the results. Every frame,
+//! including `KeyPackage` exchange and Welcome routing, takes the production
+//! path.
+//!
+//! Covers create, add, and send. The real sequencer assigns log indices to
+//! commits as well as messages, so message indices are compared by their rank
+//! among the room's application messages.
+
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use lockframe_client::{Client, ClientAction, ClientConfig, ClientEvent, ClientIdentity};
+use lockframe_core::transport::Transport;
+use lockframe_harness::{
+    ClientId, ClientSnapshot, InvariantRegistry, ModelMessage, ModelRoomId, ModelWorld,
+    ObservableState, Operation, OperationError, OperationResult, RoomSnapshot, SimEnv, SimServer,
+    SimTransport, SmallMessage, SystemSnapshot, read_frame,
+};
+use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::session::Hello};
+use lockframe_server::Storage;
+use proptest::prelude::*;
+use tokio::io::{AsyncWriteExt, ReadHalf, WriteHalf};
+use turmoil::net::TcpStream;
+
+const SERVER_PORT: u16 = 443;
+
+/// Rooms the generated operations draw from (small so operations collide).
+const NUM_ROOMS: ModelRoomId = 2;
+
+/// Simulated time allowed for in-flight frames to arrive.
+const SETTLE: Duration = Duration::from_millis(10);
+
+/// Message as observed by a real client.
+struct DeliveredMessage {
+    sender_id: ClientId,
+    content: Vec<u8>,
+    log_index: u64,
+    epoch: u64,
+}
+
+/// Real client with its connection to the server.
+struct ConnectedClient {
+    client: Client<SimEnv>,
+    session_id: u64,
+    reader: ReadHalf<TcpStream>,
+    writer: WriteHalf<TcpStream>,
+    /// Own plaintexts awaiting their sequenced echo, per room.
+    unsequenced: HashMap<ModelRoomId, VecDeque<Vec<u8>>>,
+    delivered: HashMap<ModelRoomId, Vec<DeliveredMessage>>,
+}
+
+/// Real clients connected to a real server.
+struct ServerWorld {
+    server: SimServer,
+    clients: Vec<ConnectedClient>,
+}
+
+/// Real room ID for a model room (avoids room 0).
+fn real_room_id(room_id: ModelRoomId) -> u128 {
+    u128::from(room_id) + 1
+}
+
+/// Real sender ID for a model client (avoids sender 0).
+fn real_sender_id(client_id: ClientId) -> u64 {
+    u64::from(client_id) + 1
+}
+
+impl ServerWorld {
+    /// Bind a server and connect `num_clients` clients that have sent Hello.
+    async fn connect(num_clients: usize, seed: u64) -> io::Result<Self> {
+        let mut server = SimServer::bind(&format!("0.0.0.0:{SERVER_PORT}")).await?;
+        let env = SimEnv::with_seed(seed);
+        let transport = SimTransport::client();
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, SERVER_PORT));
+
+        let mut clients = Vec::with_capacity(num_clients);
+        for i in 0..num_clients {
+            let (connection, session_id) =
+                tokio::join!(transport.connect(address), server.accept_connection());
+            let (writer, reader) = connection?.into_split();
+            let client = Client::new(
+                env.clone(),
+                ClientIdentity::new(real_sender_id(i as u8)),
+                ClientConfig::default(),
+            );
+
+            clients.push(ConnectedClient {
+                client,
+                session_id: session_id?,
+                reader,
+                writer,
+                unsequenced: HashMap::new(),
+                delivered: HashMap::new(),
+            });
+        }
+
+        let mut world = Self { server, clients };
+        for i in 0..num_clients {
+            let hello = Payload::Hello(Hello {
+                version: 1,
+                capabilities: vec![],
+                sender_id: Some(real_sender_id(i as u8)),
+                auth_token: None,
+            })
+            .into_frame(FrameHeader::new(Opcode::Hello))
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+            world.send(i, &hello).await?;
+        }
+        world.settle().await?;
+
+        Ok(world)
+    }
+
+    async fn apply(&mut self, op: &Operation) -> io::Result<OperationResult> {
+        let result = match op {
+            Operation::CreateRoom { client_id, room_id } => {
+                self.apply_create_room(*client_id as usize, *room_id).await?
+            },
+            Operation::AddMember { inviter_id, invitee_id, room_id } => {
+                self.apply_add_member(*inviter_id as usize, *invitee_id as usize, *room_id).await?
+            },
+            Operation::SendMessage { client_id, room_id, content } => {
+                self.apply_send_message(*client_id as usize, *room_id, content).await?
+            },
+            Operation::DeliverPending => OperationResult::Ok,
+            other => {
+                return Err(io::Error::other(format!("operation not supported: {other:?}")));
+            },
+        };
+
+        self.settle().await?;
+        Ok(result)
+    }
+
+    async fn apply_create_room(
+        &mut self,
+        client_idx: usize,
+        room_id: ModelRoomId,
+    ) -> io::Result<OperationResult> {
+        let real_room_id = real_room_id(room_id);
+        if self.server.has_room(real_room_id) {
+            return Ok(OperationResult::Error(OperationError::RoomAlreadyExists));
+        }
+
+        let Ok(actions) = self.clients[client_idx]
+            .client
+            .handle(ClientEvent::CreateRoom { room_id: real_room_id })
+        else {
+            return Ok(OperationResult::Error(OperationError::RoomAlreadyExists));
+        };
+
+        self.server.create_room(real_room_id, self.clients[client_idx].session_id)?;
+        self.execute(client_idx, actions, 0).await?;
+
+        Ok(OperationResult::Ok)
+    }
+
+    async fn apply_add_member(
+        &mut self,
+        inviter_idx: usize,
+        invitee_idx: usize,
+        room_id: ModelRoomId,
+    ) -> io::Result<OperationResult> {
+        let real_room_id = real_room_id(room_id);
+        if !self.clients[inviter_idx].client.is_member(real_room_id) {
+            return Ok(OperationResult::Error(OperationError::NotMember));
+        }
+        if self.clients[invitee_idx].client.is_member(real_room_id) {
+            return Ok(OperationResult::Error(OperationError::AlreadyMember));
+        }
+
+        // Invitee publishes a KeyPackage; inviter fetches it from the registry
+        self.handle(invitee_idx, ClientEvent::PublishKeyPackage).await?;
+        self.settle().await?;
+        self.handle(inviter_idx, ClientEvent::FetchAndAddMember {
+            room_id: real_room_id,
+            user_id: real_sender_id(invitee_idx as u8),
+        })
+        .await?;
+        self.settle().await?;
+
+        if self.clients[invitee_idx].client.is_member(real_room_id) {
+            Ok(OperationResult::Ok)
+        } else {
+            Ok(OperationResult::Error(OperationError::NotMember))
+        }
+    }
+
+    async fn apply_send_message(
+        &mut self,
+        client_idx: usize,
+        room_id: ModelRoomId,
+        content: &SmallMessage,
+    ) -> io::Result<OperationResult> {
+        let plaintext = content.to_bytes();
+        let Ok(actions) = self.clients[client_idx].client.handle(ClientEvent::SendMessage {
+            room_id: real_room_id(room_id),
+            plaintext: plaintext.clone(),
+        }) else {
+            return Ok(OperationResult::Error(OperationError::NotMember));
+        };
+
+        self.clients[client_idx].unsequenced.entry(room_id).or_default().push_back(plaintext);
+        self.execute(client_idx, actions, 0).await?;
+
+        Ok(OperationResult::Ok)
+    }
+
+    /// Feed an event to a client and execute its actions.
+    ///
+    /// Errors from the client itself are ignored; only I/O errors surface.
+    async fn handle(
+        &mut self,
+        client_idx: usize,
+        event: ClientEvent<tokio::time::Instant>,
+    ) -> io::Result<()> {
+        if let Ok(actions) = self.clients[client_idx].client.handle(event) {
+            self.execute(client_idx, actions, 0).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute client actions. `epoch` is the epoch of the frame that
+    /// produced them, recorded on delivered messages.
+    async fn execute(
+        &mut self,
+        client_idx: usize,
+        actions: Vec<ClientAction>,
+        epoch: u64,
+    ) -> io::Result<()> {
+        for action in actions {
+            match action {
+                ClientAction::Send(frame) => self.send(client_idx, &frame).await?,
+                ClientAction::DeliverMessage {
+                    room_id, sender_id, plaintext, log_index, ..
+                } => {
+                    let room_id = (room_id - 1) as ModelRoomId;
+                    self.clients[client_idx].delivered.entry(room_id).or_default().push(
+                        DeliveredMessage {
+                            sender_id: sender_id.saturating_sub(1) as ClientId,
+                            content: plaintext,
+                            log_index,
+                            epoch,
+                        },
+                    );
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a frame to the server and have the server process it.
+    async fn send(&mut self, client_idx: usize, frame: &Frame) -> io::Result<()> {
+        let conn = &mut self.clients[client_idx];
+        let mut buf = Vec::new();
+        frame.encode(&mut buf).map_err(|e| io::Error::other(e.to_string()))?;
+        conn.writer.write_all(&buf).await?;
+        conn.writer.flush().await?;
+
+        self.server.receive_frame(conn.session_id).await
+    }
+
+    /// Deliver server frames to clients until no more arrive.
+    async fn settle(&mut self) -> io::Result<()> {
+        loop {
+            tokio::time::sleep(SETTLE).await;
+
+            let mut delivered_any = false;
+            for client_idx in 0..self.clients.len() {
+                // Frames already in the receive buffer are read without waiting
+                while let Ok(frame) = tokio::time::timeout(
+                    Duration::ZERO,
+                    read_frame(&mut self.clients[client_idx].reader),
+                )
+                .await
+                {
+                    delivered_any = true;
+                    self.receive(client_idx, frame?).await?;
+                }
+            }
+
+            if !delivered_any {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn receive(&mut self, client_idx: usize, frame: Frame) -> io::Result<()> {
+        let conn = &mut self.clients[client_idx];
+        let room_id = frame.header.room_id().saturating_sub(1) as ModelRoomId;
+        let epoch = frame.header.epoch();
+
+        // The client skips its own echo, but the echo carries the log index
+        if frame.header.opcode_enum() == Some(Opcode::AppMessage)
+            && frame.header.sender_id() == conn.client.sender_id()
+            && let Some(content) = conn.unsequenced.get_mut(&room_id).and_then(VecDeque::pop_front)
+        {
+            conn.delivered.entry(room_id).or_default().push(DeliveredMessage {
+                sender_id: client_idx as ClientId,
+                content,
+                log_index: frame.header.log_index(),
+                epoch,
+            });
+        }
+
+        if let Ok(actions) = conn.client.handle(ClientEvent::FrameReceived(frame)) {
+            self.execute(client_idx, actions, epoch).await?;
+        }
+        Ok(())
+    }
+
+    fn observable_state(&self) -> ObservableState {
+        // Rank of each application message in its room's sequenced log
+        let mut ranks: HashMap<ModelRoomId, HashMap<u64, u64>> = HashMap::new();
+        for room_id in 0..NUM_ROOMS {
+            let indices: BTreeSet<u64> = self
+                .clients
+                .iter()
+                .filter_map(|c| c.delivered.get(&room_id))
+                .flatten()
+                .map(|m| m.log_index)
+                .collect();
+            ranks.insert(room_id, indices.into_iter().zip(0..).collect());
+        }
+
+        let mut state = ObservableState {
+            client_rooms: Vec::new(),
+            client_messages: Vec::new(),
+            client_epochs: Vec::new(),
+            server_messages: Vec::new(),
+        };
+
+        for conn in &self.clients {
+            let rooms: Vec<ModelRoomId> =
+                (0..NUM_ROOMS).filter(|&r| conn.client.is_member(real_room_id(r))).collect();
+
+            let epochs = rooms
+                .iter()
+                .filter_map(|&r| conn.client.epoch(real_room_id(r)).map(|e| (r, e)))
+                .collect();
+
+            let messages = rooms
+                .iter()
+                .map(|&r| {
+                    let mut msgs: Vec<ModelMessage> = conn
+                        .delivered
+                        .get(&r)
+                        .into_iter()
+                        .flatten()
+                        .map(|m| ModelMessage {
+                            sender_id: m.sender_id,
+                            content: m.content.clone(),
+                            log_index: ranks[&r][&m.log_index],
+                            epoch: m.epoch,
+                            redacted: false,
+                        })
+                        .collect();
+                    msgs.sort_by_key(|m| m.log_index);
+                    (r, msgs)
+                })
+                .collect();
+
+            state.client_rooms.push(rooms);
+            state.client_epochs.push(epochs);
+            state.client_messages.push(messages);
+        }
+
+        state
+    }
+
+    /// Snapshot of every room's MLS state for invariant checking.
+    fn snapshot(&self) -> SystemSnapshot {
+        let clients = self
+            .clients
+            .iter()
+            .map(|conn| {
+                let client = &conn.client;
+                let mut snapshot = ClientSnapshot::new(client.sender_id());
+                for room_id in (0..NUM_ROOMS).map(real_room_id) {
+                    let Some(epoch) = client.epoch(room_id) else { continue };
+                    let mut room = RoomSnapshot::with_epoch(epoch)
+                        .with_tree_hash(client.tree_hash(room_id).unwrap_or_default())
+                        .with_members(client.member_ids(room_id).unwrap_or_default());
+                    room.leaf_indices = client.member_leaf_indices(room_id).unwrap_or_default();
+                    snapshot.rooms.insert(room_id, room);
+                    snapshot.record_epoch(room_id, epoch);
+                }
+                snapshot
+            })
+            .collect();
+
+        SystemSnapshot::from_clients(clients)
+    }
+
+    /// Every member's view of a room matches the server's stored membership.
+    fn check_server_members(&self) -> Result<(), String> {
+        for room_id in (0..NUM_ROOMS).map(real_room_id) {
+            let stored: BTreeSet<u64> = self
+                .server
+                .driver()
+                .storage()
+                .members(room_id)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .collect();
+            for conn in &self.clients {
+                let Some(members) = conn.client.member_ids(room_id) else { continue };
+                let members: BTreeSet<u64> = members.into_iter().collect();
+                if members != stored {
+                    return Err(format!(
+                        "client {} sees members {members:?} in room {room_id}, server stores {stored:?}",
+                        conn.client.sender_id()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Apply `ops` to the model and to real clients on a real server, then
+/// compare observable state and check convergence.
+fn run_against_model(seed: u64, num_clients: usize, ops: Vec<Operation>) -> Result<(), String> {
+    let mut sim = turmoil::Builder::new().simulation_duration(Duration::from_mins(1)).build();
+
+    sim.client("world", async move {
+        let mut model = ModelWorld::new(num_clients);
+        let mut real = ServerWorld::connect(num_clients, seed).await?;
+
+        for (i, op) in ops.iter().enumerate() {
+            let model_result = model.apply(op);
+            let real_result = real.apply(op).await?;
+
+            if model_result.is_ok() != real_result.is_ok() {
+                return Err(format!(
+                    "divergence at operation {i}: {op:?}\nmodel: {model_result:?}\nreal: {real_result:?}"
+                )
+                .into());
+            }
+        }
+        model.apply(&Operation::DeliverPending);
+
+        // The model keeps messages in arrival order; compare in log order
+        let mut model_state = model.observable_state();
+        for (_, messages) in model_state.client_messages.iter_mut().flatten() {
+            messages.sort_by_key(|m| m.log_index);
+        }
+        let real_state = real.observable_state();
+        if model_state.client_rooms != real_state.client_rooms {
+            return Err(format!(
+                "room membership divergence\nmodel: {:?}\nreal: {:?}",
+                model_state.client_rooms, real_state.client_rooms
+            )
+            .into());
+        }
+        if model_state.client_epochs != real_state.client_epochs {
+            return Err(format!(
+                "epoch divergence\nmodel: {:?}\nreal: {:?}",
+                model_state.client_epochs, real_state.client_epochs
+            )
+            .into());
+        }
+        if model_state.client_messages != real_state.client_messages {
+            return Err(format!(
+                "message divergence\nmodel: {:?}\nreal: {:?}",
+                model_state.client_messages, real_state.client_messages
+            )
+            .into());
+        }
+
+        InvariantRegistry::standard().check_all(&real.snapshot()).map_err(|violations| {
+            let messages: Vec<_> = violations.iter().map(ToString::to_string).collect();
+            format!("invariant violations:\n  {}", messages.join("\n  "))
+        })?;
+        real.check_server_members()?;
+
+        Ok(())
+    });
+
+    sim.run().map_err(|e| e.to_string())
+}
+
+/// Strategy for create/add/send operations over a few rooms.
+fn operation_strategy(num_clients: u8) -> impl Strategy<Value = Operation> {
+    let client_id = 0..num_clients;
+    let room_id = 0..NUM_ROOMS;
+    let content =
+        (any::<u8>(), any::<u8>()).prop_map(|(seed, size_class)| SmallMessage { seed, size_class });
+
+    prop_oneof![
+        2 => (client_id.clone(), room_id.clone())
+            .prop_map(|(c, r)| Operation::CreateRoom { client_id: c, room_id: r }),
+        3 => (client_id.clone(), client_id.clone(), room_id.clone()).prop_map(|(i, e, r)| {
+            Operation::AddMember { inviter_id: i, invitee_id: e, room_id: r }
+        }),
+        4 => (client_id, room_id, content).prop_map(|(c, r, content)| {
+            Operation::SendMessage { client_id: c, room_id: r, content }
+        }),
+        1 => Just(Operation::DeliverPending),
+    ]
+}
+
+#[test]
+fn create_add_send_matches_model() {
+    let content = |seed| SmallMessage { seed, size_class: 1 };
+    let ops = vec![
+        Operation::CreateRoom { client_id: 0, room_id: 0 },
+        Operation::SendMessage { client_id: 0, room_id: 0, content: content(1) },
+        Operation::AddMember { inviter_id: 0, invitee_id: 1, room_id: 0 },
+        Operation::SendMessage { client_id: 1, room_id: 0, content: content(2) },
+        Operation::AddMember { inviter_id: 1, invitee_id: 2, room_id: 0 },
+        Operation::SendMessage { client_id: 2, room_id: 0, content: content(3) },
+        Operation::SendMessage { client_id: 0, room_id: 0, content: content(4) },
+        Operation::CreateRoom { client_id: 1, room_id: 0 },
+        Operation::AddMember { inviter_id: 2, invitee_id: 0, room_id: 0 },
+    ];
+
+    run_against_model(7, 3, ops).unwrap();
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Verify that real clients on a real server match the model for random
+    /// create/add/send sequences.
+    #[test]
+    fn prop_real_server_matches_model(
+        seed in any::<u64>(),
+        ops in prop::collection::vec(operation_strategy(3), 0..20),
+    ) {
+        let result = run_against_model(seed, 3, ops);
+        prop_assert!(result.is_ok(), "{:?}", result);
+    }
+}
@@ -22,8 +22,13 @@ pub enum ProtocolError {
     InvalidMagic,
 
     /// Unsupported protocol version
-    #[error("unsupported protocol version: {0}")]
-    UnsupportedVersion(u8),
+    #[error("unsupported protocol version: {got} (supported: {supported})")]
+    UnsupportedVersion {
+        /// Version byte from the frame header
+        got: u8,
+        /// Version this build speaks
+        supported: u8,
+    },
 
     /// Payload exceeds maximum allowed size
     #[error("payload too large: {size} bytes exceeds maximum {max}")]
@@ -66,6 +71,15 @@ pub enum ProtocolError {
         actual: usize,
     },
 
+    /// Checksum trailer doesn't match the header and payload
+    #[error("checksum mismatch: trailer {expected:#010x}, computed {actual:#010x}")]
+    ChecksumMismatch {
+        /// Checksum carried in the frame trailer
+        expected: u32,
+        /// Checksum computed over the received header and payload
+        actual: u32,
+    },
+
     /// Invalid flag combination
     #[error("invalid flags: {0:#04x}")]
     InvalidFlags(u8),
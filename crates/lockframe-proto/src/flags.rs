@@ -32,8 +32,8 @@ bitflags! {
         /// Can be redacted by moderators
         const REDACTABLE = 0b0100_0000;
 
-        /// Reserved for future use
-        const RESERVED = 0b1000_0000;
+        /// CRC32C trailer follows the payload (see [`crate::Frame::encode`])
+        const CHECKSUM = 0b1000_0000;
     }
 }
 
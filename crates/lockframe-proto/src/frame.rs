@@ -7,10 +7,12 @@
 //! This is a pure data holder (header + bytes). For high-level logic,
 //! see `Payload::into_frame()` and `Payload::from_frame()`.
 
+use std::fmt;
+
 use bytes::{BufMut, Bytes};
 
 use crate::{
-    FrameHeader,
+    FrameFlags, FrameHeader, checksum,
     errors::{ProtocolError, Result},
 };
 
@@ -41,7 +43,7 @@ use crate::{
 ///
 /// For authenticated content, the signature in `header.signature()` must be
 /// verified against the MLS epoch before trusting the payload.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Frame {
     /// Frame header (128 bytes)
     pub header: FrameHeader,
@@ -51,6 +53,10 @@ pub struct Frame {
 }
 
 impl Frame {
+    /// Size of the CRC32C trailer carried by frames flagged with
+    /// [`FrameFlags::CHECKSUM`].
+    pub const CHECKSUM_SIZE: usize = 4;
+
     /// Create a new frame with automatic `payload_size` calculation
     ///
     /// The header's `payload_size` field is automatically set to match
@@ -91,7 +97,9 @@ impl Frame {
 
     /// Encode frame into buffer (simple copy, no magic)
     ///
-    /// Writes: `[header (128 bytes)] + [payload (variable)]`
+    /// Writes: `[header (128 bytes)] + [payload (variable)]`, followed by a
+    /// big-endian CRC32C of header and payload when the header carries
+    /// [`FrameFlags::CHECKSUM`].
     ///
     /// # Errors
     ///
@@ -117,9 +125,14 @@ impl Frame {
             });
         }
 
-        dst.put_slice(&self.header.to_bytes());
+        let header_bytes = self.header.to_bytes();
+        dst.put_slice(&header_bytes);
         dst.put_slice(&self.payload);
 
+        if self.header.flags().contains(FrameFlags::CHECKSUM) {
+            dst.put_u32(checksum::crc32c(&[&header_bytes, &self.payload]));
+        }
+
         Ok(())
     }
 
@@ -134,6 +147,9 @@ impl Frame {
     ///   size limits)
     /// - `ProtocolError::FrameTooShort` if payload is truncated (fewer bytes
     ///   than header claims)
+    /// - `ProtocolError::ChecksumMismatch` if the header carries
+    ///   [`FrameFlags::CHECKSUM`] and the trailer doesn't match. Unflagged
+    ///   frames skip the check.
     ///
     /// # Security
     ///
@@ -150,7 +166,8 @@ impl Frame {
         let header = FrameHeader::from_bytes(bytes)?;
 
         let payload_size = header.payload_size() as usize;
-        let total_size = FrameHeader::SIZE.checked_add(payload_size).ok_or({
+        let payload_end = FrameHeader::SIZE + payload_size;
+        let total_size = FrameHeader::SIZE.checked_add(header.body_size()).ok_or({
             ProtocolError::PayloadTooLarge {
                 size: payload_size,
                 max: FrameHeader::MAX_PAYLOAD_SIZE as usize,
@@ -167,7 +184,7 @@ impl Frame {
             }
 
             return Err(ProtocolError::FrameTruncated {
-                expected: payload_size,
+                expected: header.body_size(),
                 actual: bytes.len().saturating_sub(FrameHeader::SIZE),
             });
         }
@@ -179,9 +196,23 @@ impl Frame {
         }
 
         #[allow(clippy::expect_used)]
-        let payload = Bytes::copy_from_slice(
-            bytes.get(FrameHeader::SIZE..total_size).expect("invariant: bounds checked above"),
-        );
+        let payload_bytes =
+            bytes.get(FrameHeader::SIZE..payload_end).expect("invariant: bounds checked above");
+
+        if header.flags().contains(FrameFlags::CHECKSUM) {
+            #[allow(clippy::expect_used)]
+            let trailer: [u8; Self::CHECKSUM_SIZE] = bytes
+                .get(payload_end..total_size)
+                .and_then(|t| t.try_into().ok())
+                .expect("invariant: bounds checked above");
+            let expected = u32::from_be_bytes(trailer);
+            let actual = checksum::crc32c(&[&header.to_bytes(), payload_bytes]);
+            if expected != actual {
+                return Err(ProtocolError::ChecksumMismatch { expected, actual });
+            }
+        }
+
+        let payload = Bytes::copy_from_slice(payload_bytes);
 
         debug_assert_eq!(payload.len(), payload_size);
 
@@ -189,6 +220,17 @@ impl Frame {
     }
 }
 
+/// Shows the payload length only; payloads are usually ciphertext or MLS
+/// material and don't belong in logs.
+impl fmt::Debug for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frame")
+            .field("header", &self.header)
+            .field("payload_len", &self.payload.len())
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use proptest::prelude::*;
@@ -258,4 +300,80 @@ mod tests {
         let result = Frame::decode(&header_bytes);
         assert!(matches!(result, Err(ProtocolError::FrameTruncated { .. })));
     }
+
+    fn ping_frame(flags: FrameFlags) -> Frame {
+        let mut header = FrameHeader::new(Opcode::Ping);
+        header.set_flags(flags);
+        Frame::new(header, vec![1, 2, 3, 4])
+    }
+
+    #[test]
+    fn debug_shows_payload_length_not_bytes() {
+        let frame = Frame::new(FrameHeader::new(Opcode::AppMessage), vec![0xAB; 16]);
+        let debug = format!("{frame:?}");
+        assert!(debug.contains("payload_len: 16"));
+        assert!(!debug.contains("171"));
+    }
+
+    #[test]
+    fn decode_accepts_current_version() {
+        let frame = ping_frame(FrameFlags::empty());
+        let mut wire = Vec::new();
+        frame.encode(&mut wire).expect("should encode");
+        assert_eq!(wire[4], FrameHeader::VERSION);
+        assert_eq!(Frame::decode(&wire).expect("should decode"), frame);
+    }
+
+    #[test]
+    fn decode_rejects_future_version() {
+        let frame = ping_frame(FrameFlags::empty());
+        let mut wire = Vec::new();
+        frame.encode(&mut wire).expect("should encode");
+        wire[4] = FrameHeader::VERSION + 1;
+
+        let result = Frame::decode(&wire);
+        assert_eq!(
+            result,
+            Err(ProtocolError::UnsupportedVersion {
+                got: FrameHeader::VERSION + 1,
+                supported: FrameHeader::VERSION,
+            })
+        );
+    }
+
+    #[test]
+    fn checksum_detects_flipped_payload_byte() {
+        let frame = ping_frame(FrameFlags::CHECKSUM);
+        let mut wire = Vec::new();
+        frame.encode(&mut wire).expect("should encode");
+        assert_eq!(wire.len(), FrameHeader::SIZE + 4 + Frame::CHECKSUM_SIZE);
+        assert_eq!(Frame::decode(&wire).expect("should decode"), frame);
+
+        wire[FrameHeader::SIZE] ^= 0xFF;
+        let result = Frame::decode(&wire);
+        assert!(matches!(result, Err(ProtocolError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn checksum_requires_trailer() {
+        let frame = ping_frame(FrameFlags::CHECKSUM);
+        let mut wire = Vec::new();
+        frame.encode(&mut wire).expect("should encode");
+        wire.truncate(wire.len() - 1);
+
+        let result = Frame::decode(&wire);
+        assert!(matches!(result, Err(ProtocolError::FrameTruncated { .. })));
+    }
+
+    #[test]
+    fn unflagged_frame_skips_checksum() {
+        let frame = ping_frame(FrameFlags::empty());
+        let mut wire = Vec::new();
+        frame.encode(&mut wire).expect("should encode");
+        assert_eq!(wire.len(), FrameHeader::SIZE + 4);
+
+        wire[FrameHeader::SIZE] ^= 0xFF;
+        let parsed = Frame::decode(&wire).expect("should decode");
+        assert_eq!(parsed.payload[0], 1 ^ 0xFF);
+    }
 }
@@ -9,6 +9,7 @@ use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 use crate::{
     FrameFlags, Opcode,
     errors::{ProtocolError, Result},
+    ids::{MemberId, RoomId},
 };
 
 /// Fixed 128-byte frame header (Big Endian network byte order)
@@ -69,11 +70,24 @@ pub struct FrameHeader {
     signature: [u8; 64], // Ed25519 signature
 }
 
+// Pin the layout `signing_data` depends on: everything before `context_id`,
+// then `hlc_timestamp` and `epoch` up to the signature.
+const _: () = {
+    assert!(std::mem::offset_of!(FrameHeader, context_id) == 40);
+    assert!(std::mem::offset_of!(FrameHeader, hlc_timestamp) == 48);
+    assert!(std::mem::offset_of!(FrameHeader, epoch) == 56);
+    assert!(std::mem::offset_of!(FrameHeader, signature) == 64);
+    assert!(FrameHeader::SIGNING_DATA_SIZE == 40 + 16);
+};
+
 impl FrameHeader {
     /// Size of the serialized header (128 bytes)
     /// Fits exactly into two 64-byte CPU cache lines
     pub const SIZE: usize = 128;
 
+    /// Length of [`Self::signing_data`] (56 bytes)
+    pub const SIGNING_DATA_SIZE: usize = 56;
+
     /// Magic number: "LOFR" in ASCII (0x4C4F4652)
     pub const MAGIC: u32 = 0x4C4F_4652;
 
@@ -139,7 +153,10 @@ impl FrameHeader {
         }
 
         if header.version != Self::VERSION {
-            return Err(ProtocolError::UnsupportedVersion(header.version));
+            return Err(ProtocolError::UnsupportedVersion {
+                got: header.version,
+                supported: Self::VERSION,
+            });
         }
 
         let payload_size = u32::from_be_bytes(header.payload_size);
@@ -180,6 +197,18 @@ impl FrameHeader {
         FrameFlags::from_byte(self.flags)
     }
 
+    /// Bytes following the header on the wire: the payload plus the
+    /// checksum trailer if [`FrameFlags::CHECKSUM`] is set.
+    #[must_use]
+    pub fn body_size(&self) -> usize {
+        let trailer = if self.flags().contains(FrameFlags::CHECKSUM) {
+            crate::Frame::CHECKSUM_SIZE
+        } else {
+            0
+        };
+        self.payload_size() as usize + trailer
+    }
+
     /// Operation code as raw u16.
     #[must_use]
     pub fn opcode(&self) -> u16 {
@@ -204,6 +233,12 @@ impl FrameHeader {
         u128::from_be_bytes(self.room_id)
     }
 
+    /// Room UUID as a typed [`RoomId`].
+    #[must_use]
+    pub fn room(&self) -> RoomId {
+        RoomId::new(self.room_id())
+    }
+
     /// Room UUID as raw big-endian bytes.
     #[must_use]
     pub fn room_id_bytes(&self) -> &[u8; 16] {
@@ -216,6 +251,12 @@ impl FrameHeader {
         u64::from_be_bytes(self.sender_id)
     }
 
+    /// Sender identifier as a typed [`MemberId`].
+    #[must_use]
+    pub fn sender(&self) -> MemberId {
+        MemberId::new(self.sender_id())
+    }
+
     /// Monotonic sequence number within this room's log.
     ///
     /// Only meaningful for sequenced opcodes (`AppMessage`, Commit, Proposal).
@@ -268,21 +309,50 @@ impl FrameHeader {
 
     /// Bytes to sign (excludes mutable `context_id` and signature itself).
     ///
-    /// Returns bytes 0-39 + 48-63 (56 bytes total).
+    /// Returns bytes 0-39 + 48-63 (56 bytes total). `context_id` is excluded
+    /// so the server can assign `log_index` after the sender signs.
+    ///
+    /// Built field by field rather than by slicing the serialized header, so
+    /// it cannot panic and touches the same bytes whatever their values. The
+    /// offsets are pinned by compile-time assertions below.
     #[must_use]
-    pub fn signing_data(&self) -> [u8; 56] {
-        let bytes = self.to_bytes();
-        let mut data = [0u8; 56];
-        data[..40].copy_from_slice(&bytes[..40]); // Bytes 0-39
-        data[40..56].copy_from_slice(&bytes[48..64]); // Bytes 48-63
+    pub fn signing_data(&self) -> [u8; Self::SIGNING_DATA_SIZE] {
+        let fields: [&[u8]; 10] = [
+            &self.magic,
+            &[self.version],
+            &[self.flags],
+            &self.opcode,
+            &self.request_id,
+            &self.payload_size,
+            &self.room_id,
+            &self.sender_id,
+            // context_id (bytes 40-47) is skipped
+            &self.hlc_timestamp,
+            &self.epoch,
+        ];
+
+        let mut data = [0u8; Self::SIGNING_DATA_SIZE];
+        for (dst, src) in data.iter_mut().zip(fields.into_iter().flatten()) {
+            *dst = *src;
+        }
         data
     }
 
+    /// Replace the operation code.
+    pub fn set_opcode(&mut self, opcode: Opcode) {
+        self.opcode = opcode.to_u16().to_be_bytes();
+    }
+
     /// Update room UUID.
     pub fn set_room_id(&mut self, room_id: u128) {
         self.room_id = room_id.to_be_bytes();
     }
 
+    /// Update room UUID from a typed [`RoomId`].
+    pub fn set_room(&mut self, room: RoomId) {
+        self.set_room_id(room.get());
+    }
+
     /// Assign log index (sequencer use only).
     ///
     /// Only valid for sequenced opcodes. For Welcome, use
@@ -312,6 +382,11 @@ impl FrameHeader {
         self.sender_id = sender_id.to_be_bytes();
     }
 
+    /// Update sender identifier from a typed [`MemberId`].
+    pub fn set_sender(&mut self, sender: MemberId) {
+        self.set_sender_id(sender.get());
+    }
+
     /// Update MLS epoch.
     pub fn set_epoch(&mut self, epoch: u64) {
         self.epoch = epoch.to_be_bytes();
@@ -341,6 +416,97 @@ impl FrameHeader {
     pub fn set_payload_size(&mut self, size: u32) {
         self.payload_size = size.to_be_bytes();
     }
+
+    /// Start building a header with the specified opcode.
+    pub fn builder(opcode: Opcode) -> FrameHeaderBuilder {
+        FrameHeaderBuilder { header: Self::new(opcode) }
+    }
+}
+
+/// Produces the Ed25519 signature over [`FrameHeader::signing_data`].
+pub trait HeaderSigner {
+    /// Sign `signing_data`, or `None` if the key can't produce a signature.
+    fn sign_header_data(&self, signing_data: &[u8]) -> Option<[u8; 64]>;
+}
+
+/// Builder for [`FrameHeader`].
+///
+/// Sets every signed field before [`Self::sign_with`] computes the signature,
+/// so a header can't be signed and then changed. Fields the server assigns
+/// (`log_index`, `recipient_id`) are not signed and are left to the setters.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct FrameHeaderBuilder {
+    header: FrameHeader,
+}
+
+impl FrameHeaderBuilder {
+    /// Replace the opcode.
+    pub fn opcode(mut self, opcode: Opcode) -> Self {
+        self.header.set_opcode(opcode);
+        self
+    }
+
+    /// Set the frame flags.
+    pub fn flags(mut self, flags: FrameFlags) -> Self {
+        self.header.set_flags(flags);
+        self
+    }
+
+    /// Set the client request nonce.
+    pub fn request_id(mut self, request_id: u32) -> Self {
+        self.header.set_request_id(request_id);
+        self
+    }
+
+    /// Set the room ID.
+    pub fn room_id(mut self, room_id: u128) -> Self {
+        self.header.set_room_id(room_id);
+        self
+    }
+
+    /// Set the sender ID.
+    pub fn sender_id(mut self, sender_id: u64) -> Self {
+        self.header.set_sender_id(sender_id);
+        self
+    }
+
+    /// Set the MLS epoch.
+    pub fn epoch(mut self, epoch: u64) -> Self {
+        self.header.set_epoch(epoch);
+        self
+    }
+
+    /// Set the HLC timestamp.
+    pub fn hlc_timestamp(mut self, timestamp: u64) -> Self {
+        self.header.set_hlc_timestamp(timestamp);
+        self
+    }
+
+    /// Set the payload size.
+    pub fn payload_size(mut self, size: u32) -> Self {
+        self.header.set_payload_size(size);
+        self
+    }
+
+    /// Finish without a signature, for frames that are not verified.
+    #[must_use]
+    pub fn build(self) -> FrameHeader {
+        self.header
+    }
+
+    /// Finish by signing the header with `signer`.
+    ///
+    /// If `signer` fails the signature stays zeroed, and receivers reject the
+    /// frame when they verify it.
+    #[must_use]
+    pub fn sign_with(self, signer: &impl HeaderSigner) -> FrameHeader {
+        let mut header = self.header;
+        if let Some(signature) = signer.sign_header_data(&header.signing_data()) {
+            header.set_signature(signature);
+        }
+        header
+    }
 }
 
 // Manual Debug implementation (can't derive due to packed repr)
@@ -381,6 +547,19 @@ mod tests {
 
     use super::*;
 
+    /// Deterministic stand-in: the "signature" is the signing data repeated.
+    struct EchoSigner;
+
+    impl HeaderSigner for EchoSigner {
+        fn sign_header_data(&self, signing_data: &[u8]) -> Option<[u8; 64]> {
+            let mut signature = [0u8; 64];
+            for (i, byte) in signature.iter_mut().enumerate() {
+                *byte = signing_data[i % signing_data.len()];
+            }
+            Some(signature)
+        }
+    }
+
     fn arbitrary_bytes<const N: usize>() -> impl Strategy<Value = [u8; N]> {
         prop::collection::vec(any::<u8>(), N).prop_map(|v| {
             let mut arr = [0u8; N];
@@ -439,6 +618,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn builder_sign_with_signs_final_fields() {
+        let header = FrameHeader::builder(Opcode::Commit)
+            .opcode(Opcode::AppMessage)
+            .room_id(7)
+            .sender_id(3)
+            .sign_with(&EchoSigner);
+
+        assert_eq!(header.opcode_enum(), Some(Opcode::AppMessage));
+        assert_eq!(
+            header.signature(),
+            &EchoSigner.sign_header_data(&header.signing_data()).unwrap()
+        );
+    }
+
+    #[test]
+    fn signing_data_covers_bytes_0_to_39_and_48_to_63() {
+        // Distinct value per byte, then make magic/version/size valid
+        let mut bytes: [u8; FrameHeader::SIZE] = std::array::from_fn(|i| i as u8);
+        bytes[0..4].copy_from_slice(&FrameHeader::MAGIC.to_be_bytes());
+        bytes[4] = FrameHeader::VERSION;
+        bytes[12..16].copy_from_slice(&0u32.to_be_bytes());
+        let header = FrameHeader::from_bytes(&bytes).unwrap();
+
+        let expected: Vec<u8> = bytes[..40].iter().chain(&bytes[48..64]).copied().collect();
+        assert_eq!(header.signing_data().as_slice(), expected.as_slice());
+        assert_eq!(header.signing_data().len(), FrameHeader::SIGNING_DATA_SIZE);
+    }
+
     #[test]
     fn header_size() {
         assert_eq!(std::mem::size_of::<FrameHeader>(), FrameHeader::SIZE);
@@ -446,6 +654,41 @@ mod tests {
     }
 
     proptest! {
+        #[test]
+        fn builder_matches_manual_construction(
+            room_id in any::<u128>(),
+            sender_id in any::<u64>(),
+            epoch in any::<u64>(),
+            hlc_timestamp in any::<u64>(),
+            payload_size in 0..=FrameHeader::MAX_PAYLOAD_SIZE,
+            flags in any::<u8>(),
+            request_id in any::<u32>(),
+        ) {
+            let flags = FrameFlags::from_byte(flags);
+            let mut manual = FrameHeader::new(Opcode::AppMessage);
+            manual.set_flags(flags);
+            manual.set_request_id(request_id);
+            manual.set_room_id(room_id);
+            manual.set_sender_id(sender_id);
+            manual.set_epoch(epoch);
+            manual.set_hlc_timestamp(hlc_timestamp);
+            manual.set_payload_size(payload_size);
+
+            let builder = FrameHeader::builder(Opcode::AppMessage)
+                .flags(flags)
+                .request_id(request_id)
+                .room_id(room_id)
+                .sender_id(sender_id)
+                .epoch(epoch)
+                .hlc_timestamp(hlc_timestamp)
+                .payload_size(payload_size);
+            prop_assert_eq!(builder.build(), manual);
+
+            let signature = EchoSigner.sign_header_data(&manual.signing_data()).unwrap();
+            manual.set_signature(signature);
+            prop_assert_eq!(builder.sign_with(&EchoSigner), manual);
+        }
+
         #[test]
         fn header_round_trip(header in any::<FrameHeader>()) {
             let bytes = header.to_bytes();
@@ -453,6 +696,38 @@ mod tests {
             prop_assert_eq!(&header, parsed);
         }
 
+        #[test]
+        fn signing_data_ignores_context_id_and_signature(
+            header in any::<FrameHeader>(),
+            log_index in any::<u64>(),
+            signature in arbitrary_bytes::<64>(),
+        ) {
+            let mut mutated = header;
+            mutated.set_log_index(log_index);
+            mutated.set_signature(signature);
+            prop_assert_eq!(header.signing_data(), mutated.signing_data());
+        }
+
+        #[test]
+        fn signing_data_covers_routing_fields(
+            header in any::<FrameHeader>(),
+            field in 0usize..7,
+            delta in 1u64..,
+        ) {
+            let mut mutated = header;
+            match field {
+                0 => mutated.set_room_id(header.room_id() ^ u128::from(delta)),
+                1 => mutated.set_sender_id(header.sender_id() ^ delta),
+                2 => mutated.set_epoch(header.epoch() ^ delta),
+                3 => mutated.set_hlc_timestamp(header.hlc_timestamp() ^ delta),
+                #[allow(clippy::cast_possible_truncation)]
+                4 => mutated.set_request_id(header.request_id() ^ (delta as u32).max(1)),
+                5 => mutated.opcode = (header.opcode() ^ 1).to_be_bytes(),
+                _ => mutated.flags ^= 1,
+            }
+            prop_assert_ne!(header.signing_data(), mutated.signing_data());
+        }
+
         #[test]
         fn header_accessors(header in any::<FrameHeader>()) {
             // Verify accessors return correct values
@@ -486,7 +761,10 @@ mod tests {
         buf[4] = 0xFF; // invalid version
 
         let result = FrameHeader::from_bytes(&buf);
-        assert_eq!(result, Err(ProtocolError::UnsupportedVersion(0xFF)));
+        assert_eq!(
+            result,
+            Err(ProtocolError::UnsupportedVersion { got: 0xFF, supported: FrameHeader::VERSION })
+        );
     }
 
     #[test]
@@ -0,0 +1,253 @@
+//! Typed room and member identifiers.
+//!
+//! Headers and most APIs still pass raw `u128` room IDs and `u64` member
+//! IDs, which makes it easy to hand a member ID to something expecting a room.
+//! [`RoomId`] and [`MemberId`] wrap them so the compiler catches the mix-up.
+//! They convert losslessly to and from the raw integers, so call sites can
+//! move over one at a time.
+//!
+//! Both parse from decimal or `0x`-prefixed hex, since users copy IDs out of
+//! logs (hex) as often as they type them. A [`RoomId`] also parses from its
+//! [`std::fmt::Display`] form, 32 hex digits without a prefix, so parsing what
+//! was printed gives back the same room.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Error parsing a [`RoomId`] or [`MemberId`] from text.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid {kind} ID {input:?}: expected {expected}")]
+pub struct ParseIdError {
+    kind: &'static str,
+    expected: &'static str,
+    input: String,
+}
+
+/// Parse decimal, or hex after a `0x`/`0X` prefix.
+fn parse_radix<T>(
+    input: &str,
+    from_str_radix: fn(&str, u32) -> Result<T, std::num::ParseIntError>,
+) -> Result<T, std::num::ParseIntError> {
+    match input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+        Some(hex) => from_str_radix(hex, 16),
+        None => from_str_radix(input, 10),
+    }
+}
+
+/// Room identifier (128-bit UUID).
+///
+/// Displays as 32 lowercase hex digits, the same as `{:032x}` on the raw
+/// value, and parses back from that form.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RoomId(u128);
+
+impl RoomId {
+    /// Length of the [`std::fmt::Display`] form.
+    const DISPLAY_LEN: usize = 32;
+
+    /// Wrap a raw room ID.
+    #[must_use]
+    pub const fn new(raw: u128) -> Self {
+        Self(raw)
+    }
+
+    /// The raw room ID.
+    #[must_use]
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+
+    /// Parse hex digits, with or without a `0x` prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseIdError`] if `hex` is empty, not hex, or too long.
+    pub fn from_hex(hex: &str) -> Result<Self, ParseIdError> {
+        let digits = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")).unwrap_or(hex);
+        u128::from_str_radix(digits, 16).map(Self).map_err(|_| ParseIdError {
+            kind: "room",
+            expected: "hex digits",
+            input: hex.to_string(),
+        })
+    }
+
+    /// 32 lowercase hex digits, no prefix (the [`std::fmt::Display`] form).
+    #[must_use]
+    pub fn to_hex(self) -> String {
+        self.to_string()
+    }
+}
+
+impl FromStr for RoomId {
+    type Err = ParseIdError;
+
+    /// Parse the 32-digit [`std::fmt::Display`] form as hex, and anything
+    /// else as decimal or `0x`-prefixed hex.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = if s.len() == Self::DISPLAY_LEN {
+            u128::from_str_radix(s, 16)
+        } else {
+            parse_radix(s, u128::from_str_radix)
+        };
+        parsed.map(Self).map_err(|_| ParseIdError {
+            kind: "room",
+            expected: "decimal, 0x-prefixed hex, or 32 hex digits",
+            input: s.to_string(),
+        })
+    }
+}
+
+impl From<u128> for RoomId {
+    fn from(raw: u128) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<RoomId> for u128 {
+    fn from(id: RoomId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for RoomId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+impl std::fmt::LowerHex for RoomId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl std::fmt::Debug for RoomId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RoomId({self})")
+    }
+}
+
+/// Member identifier (a client's stable sender ID).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct MemberId(u64);
+
+impl MemberId {
+    /// Wrap a raw member ID.
+    #[must_use]
+    pub const fn new(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    /// The raw member ID.
+    #[must_use]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for MemberId {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_radix(s, u64::from_str_radix).map(Self).map_err(|_| ParseIdError {
+            kind: "member",
+            expected: "decimal or 0x-prefixed hex",
+            input: s.to_string(),
+        })
+    }
+}
+
+impl From<u64> for MemberId {
+    fn from(raw: u64) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<MemberId> for u64 {
+    fn from(id: MemberId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for MemberId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Frame, FrameHeader, Opcode};
+
+    #[test]
+    fn room_id_formats_like_raw_hex() {
+        for raw in [0, 0x1234, 0x1234_5678_9abc_def0_1234_5678_9abc_def0, u128::MAX] {
+            let id = RoomId::new(raw);
+            assert_eq!(id.to_string(), format!("{raw:032x}"));
+            assert_eq!(format!("{id:x}"), format!("{raw:x}"));
+            assert_eq!(format!("{id:032x}"), format!("{raw:032x}"));
+        }
+    }
+
+    #[test]
+    fn room_id_hex_round_trips() {
+        let id = RoomId::new(0x1234);
+        assert_eq!(id.to_hex(), "00000000000000000000000000001234");
+        assert_eq!(RoomId::from_hex(&id.to_hex()), Ok(id));
+        assert_eq!(RoomId::from_hex("0x1234"), Ok(id));
+        assert!(RoomId::from_hex("").is_err());
+        assert!(RoomId::from_hex(&"f".repeat(33)).is_err());
+    }
+
+    #[test]
+    fn ids_parse_back_from_display() {
+        for raw in [0, 0x1234, 1_000_000, 0x1234_5678_9abc_def0_1234_5678_9abc_def0, u128::MAX] {
+            let id = RoomId::new(raw);
+            assert_eq!(id.to_string().parse::<RoomId>(), Ok(id));
+        }
+        for raw in [0, 42, u64::MAX] {
+            let id = MemberId::new(raw);
+            assert_eq!(id.to_string().parse::<MemberId>(), Ok(id));
+        }
+    }
+
+    #[test]
+    fn ids_parse_decimal_and_prefixed_hex() {
+        assert_eq!("4660".parse::<RoomId>(), Ok(RoomId::new(0x1234)));
+        assert_eq!("0x1234".parse::<RoomId>(), Ok(RoomId::new(4660)));
+        assert_eq!("0X2a".parse::<MemberId>(), Ok(MemberId::new(42)));
+
+        let err = "12ab".parse::<RoomId>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid room ID \"12ab\": expected decimal, 0x-prefixed hex, or 32 hex digits"
+        );
+        assert!("0x".parse::<MemberId>().is_err());
+        assert!("-1".parse::<MemberId>().is_err());
+    }
+
+    #[test]
+    fn ids_round_trip_through_frame_header() {
+        let room = RoomId::new(0xdead_beef_0000_0000_0000_0000_cafe_f00d);
+        let member = MemberId::new(42);
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room(room);
+        header.set_sender(member);
+        assert_eq!(header.room_id(), room.get());
+        assert_eq!(header.sender_id(), u64::from(member));
+
+        let mut buf = Vec::new();
+        Frame::new(header, Vec::new()).encode(&mut buf).unwrap();
+        let decoded = Frame::decode(&buf).unwrap();
+        assert_eq!(decoded.header.room(), room);
+        assert_eq!(decoded.header.sender(), member);
+    }
+}
@@ -15,17 +15,19 @@
 //! 16 MB payload limit to prevent memory exhaustion attacks. No "fast paths"
 //! that skip validation.
 
+mod checksum;
 pub mod errors;
 pub mod flags;
 pub mod frame;
 pub mod header;
+pub mod ids;
 pub mod opcodes;
 pub mod payloads;
 
 pub use errors::{ProtocolError, Result};
 pub use flags::FrameFlags;
 pub use frame::Frame;
-pub use header::FrameHeader;
+pub use header::{FrameHeader, FrameHeaderBuilder, HeaderSigner};
 pub use opcodes::Opcode;
 pub use payloads::Payload;
 
@@ -46,6 +46,22 @@ pub enum Opcode {
     SyncRequest = 0x0006,
     /// Sync response with frames (server → client)
     SyncResponse = 0x0007,
+    /// Request the user's joined rooms (client → server)
+    RoomListRequest = 0x0008,
+    /// Joined rooms response (server → client)
+    RoomListResponse = 0x0009,
+    /// Operator request or response (admin sessions only)
+    Admin = 0x000A,
+    /// Subscribe to public room announcements (client → server)
+    DirectorySubscribe = 0x000B,
+    /// Public room was created (server → client)
+    RoomAnnouncement = 0x000C,
+    /// Search public rooms by name or topic (client → server)
+    RoomSearch = 0x000D,
+    /// Matching public rooms (server → client)
+    RoomSearchResponse = 0x000E,
+    /// Create a room with metadata and policy (client → server)
+    CreateRoom = 0x000F,
     /// Error frame
     Error = 0x00FF,
 
@@ -104,6 +120,8 @@ pub enum Opcode {
     Pin = 0x3005,
     /// Report content
     Report = 0x3006,
+    /// Change room name or topic
+    RoomMeta = 0x3007,
 
     // Federation (0x4000-0x4FFF)
     /// Federated log append
@@ -158,6 +176,14 @@ impl Opcode {
             0x0005 => Some(Self::Pong),
             0x0006 => Some(Self::SyncRequest),
             0x0007 => Some(Self::SyncResponse),
+            0x0008 => Some(Self::RoomListRequest),
+            0x0009 => Some(Self::RoomListResponse),
+            0x000A => Some(Self::Admin),
+            0x000B => Some(Self::DirectorySubscribe),
+            0x000C => Some(Self::RoomAnnouncement),
+            0x000D => Some(Self::RoomSearch),
+            0x000E => Some(Self::RoomSearchResponse),
+            0x000F => Some(Self::CreateRoom),
             0x00FF => Some(Self::Error),
 
             0x1000 => Some(Self::KeyPackage),
@@ -187,6 +213,7 @@ impl Opcode {
             0x3004 => Some(Self::Mute),
             0x3005 => Some(Self::Pin),
             0x3006 => Some(Self::Report),
+            0x3007 => Some(Self::RoomMeta),
 
             0x4000 => Some(Self::FedAppend),
             0x4001 => Some(Self::FedSync),
@@ -219,6 +246,14 @@ mod tests {
             Opcode::Pong,
             Opcode::SyncRequest,
             Opcode::SyncResponse,
+            Opcode::RoomListRequest,
+            Opcode::RoomListResponse,
+            Opcode::Admin,
+            Opcode::DirectorySubscribe,
+            Opcode::RoomAnnouncement,
+            Opcode::RoomSearch,
+            Opcode::RoomSearchResponse,
+            Opcode::CreateRoom,
             Opcode::Error,
             // MLS Operations
             Opcode::KeyPackage,
@@ -248,6 +283,7 @@ mod tests {
             Opcode::Mute,
             Opcode::Pin,
             Opcode::Report,
+            Opcode::RoomMeta,
             // Federation
             Opcode::FedAppend,
             Opcode::FedSync,
@@ -0,0 +1,102 @@
+//! Server administration payload types.
+//!
+//! Operators drive a running server over an ordinary session: they send an
+//! [`AdminRequest`] and get an [`AdminResponse`] back, both under
+//! `Opcode::Admin`. The server only honors requests from sessions its
+//! authenticator marked as admin.
+
+use serde::{Deserialize, Serialize};
+
+/// Body of an `Opcode::Admin` frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdminMessage {
+    /// Operator request (client → server)
+    Request(AdminRequest),
+    /// Result of a request (server → client)
+    Response(AdminResponse),
+}
+
+/// Admin operation to run on the server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdminRequest {
+    /// List every room the server has persisted.
+    ListRooms,
+    /// Unsubscribe every session from a room and drop its in-memory state.
+    ///
+    /// Stored frames are kept; the room reloads on its next frame.
+    EvictRoom {
+        /// Room to evict
+        room_id: u128,
+    },
+    /// Close a session's connection.
+    DropSession {
+        /// Session to close
+        session_id: u64,
+    },
+    /// Replace the set of members allowed to change a room's membership.
+    ///
+    /// An empty list lifts the restriction.
+    SetRoomAdmins {
+        /// Room to update
+        room_id: u128,
+        /// Member IDs allowed to add and remove members
+        admins: Vec<u64>,
+    },
+}
+
+/// Outcome of an [`AdminRequest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdminResponse {
+    /// Answer to [`AdminRequest::ListRooms`].
+    Rooms {
+        /// Room IDs in ascending order
+        room_ids: Vec<u128>,
+    },
+    /// Answer to [`AdminRequest::EvictRoom`].
+    RoomEvicted {
+        /// Room that was evicted
+        room_id: u128,
+        /// False if the room was not loaded
+        evicted: bool,
+    },
+    /// Answer to [`AdminRequest::DropSession`].
+    SessionDropped {
+        /// Session that was closed
+        session_id: u64,
+        /// False if no such session was connected
+        dropped: bool,
+    },
+    /// Answer to [`AdminRequest::SetRoomAdmins`].
+    RoomAdminsSet {
+        /// Room that was updated
+        room_id: u128,
+        /// Room admins in ascending order
+        admins: Vec<u64>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admin_message_serde() {
+        let messages = [
+            AdminMessage::Request(AdminRequest::ListRooms),
+            AdminMessage::Request(AdminRequest::EvictRoom { room_id: u128::MAX }),
+            AdminMessage::Request(AdminRequest::DropSession { session_id: 7 }),
+            AdminMessage::Request(AdminRequest::SetRoomAdmins { room_id: 1, admins: vec![2, 3] }),
+            AdminMessage::Response(AdminResponse::Rooms { room_ids: vec![1, 2] }),
+            AdminMessage::Response(AdminResponse::RoomEvicted { room_id: 1, evicted: true }),
+            AdminMessage::Response(AdminResponse::SessionDropped { session_id: 7, dropped: false }),
+            AdminMessage::Response(AdminResponse::RoomAdminsSet { room_id: 1, admins: vec![2] }),
+        ];
+
+        for message in messages {
+            let mut bytes = Vec::new();
+            ciborium::ser::into_writer(&message, &mut bytes).expect("encode");
+            let decoded: AdminMessage = ciborium::de::from_reader(&bytes[..]).expect("decode");
+            assert_eq!(decoded, message);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 //! Application message payload types.
 //!
-//! These payloads handle user-visible messages: encrypted content, delivery
-//! receipts, and reactions.
+//! These payloads handle user-visible messages: encrypted content, edits,
+//! delivery receipts, reactions, and typing indicators.
 
 use serde::{Deserialize, Serialize};
 
@@ -43,6 +43,22 @@ pub struct EncryptedMessage {
     /// Only included for high-priority messages (DMs, mentions).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub push_keys: Option<Vec<PushKey>>,
+
+    /// Client-generated message ID (random u128)
+    ///
+    /// Identifies the message across send retries: a sender that resends the
+    /// same frame after a timeout reuses the ID, and receivers deliver each
+    /// ID once. `None` for edits and senders that predate message IDs.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub message_id: Option<u128>,
+
+    /// HLC timestamp (Unix milliseconds) after which the message expires
+    ///
+    /// Left unencrypted so the server can drop the message from sync and
+    /// prune it from storage once it has expired; clients remove it from
+    /// history at the same time. `None` keeps the message forever.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub expires_at: Option<u64>,
 }
 
 /// Push-Carried Ephemeral Key for a specific recipient
@@ -68,6 +84,20 @@ pub struct PushKey {
     pub encrypted_key: Vec<u8>,
 }
 
+/// Edit of a previously sent message
+///
+/// Sequenced and persisted as its own frame rather than rewriting the
+/// original, so sync replays the original followed by its edits. The server
+/// only accepts edits from the sender of the target message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edit {
+    /// Log index of the message being edited
+    pub target_log_index: u64,
+
+    /// Replacement content, encrypted exactly like an [`EncryptedMessage`]
+    pub new_ciphertext: EncryptedMessage,
+}
+
 /// Delivery receipt
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Receipt {
@@ -103,6 +133,16 @@ pub struct Reaction {
     pub add: bool,
 }
 
+/// Typing indicator
+///
+/// Ephemeral: the server broadcasts it to the room but never sequences or
+/// persists it, so sync never replays typing state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Typing {
+    /// True when the sender started typing, false when they stopped
+    pub is_typing: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,6 +156,8 @@ mod tests {
             nonce: [0; 24],
             ciphertext: vec![1, 2, 3, 4],
             push_keys: None,
+            message_id: None,
+            expires_at: None,
         };
 
         let cbor = ciborium::ser::into_writer(&msg, Vec::new());
@@ -131,6 +173,8 @@ mod tests {
             nonce: [0xAB; 24],
             ciphertext: vec![1, 2, 3, 4, 5, 6, 7, 8],
             push_keys: None,
+            message_id: None,
+            expires_at: None,
         };
 
         // Encode to CBOR
@@ -143,6 +187,51 @@ mod tests {
         assert_eq!(original, decoded);
     }
 
+    #[test]
+    fn encrypted_message_id_round_trip() {
+        let original = EncryptedMessage {
+            epoch: 1,
+            sender_index: 2,
+            generation: 3,
+            nonce: [0; 24],
+            ciphertext: vec![1],
+            push_keys: None,
+            message_id: Some(u128::MAX - 1),
+            expires_at: None,
+        };
+
+        let mut encoded = Vec::new();
+        ciborium::ser::into_writer(&original, &mut encoded).unwrap();
+        let decoded: EncryptedMessage = ciborium::de::from_reader(&encoded[..]).unwrap();
+        assert_eq!(original, decoded);
+
+        // Payloads without the field decode with no ID
+        let legacy = EncryptedMessage { message_id: None, ..original };
+        let mut encoded = Vec::new();
+        ciborium::ser::into_writer(&legacy, &mut encoded).unwrap();
+        let decoded: EncryptedMessage = ciborium::de::from_reader(&encoded[..]).unwrap();
+        assert_eq!(decoded.message_id, None);
+    }
+
+    #[test]
+    fn encrypted_message_expiry_round_trip() {
+        let original = EncryptedMessage {
+            epoch: 1,
+            sender_index: 2,
+            generation: 3,
+            nonce: [0; 24],
+            ciphertext: vec![1],
+            push_keys: None,
+            message_id: None,
+            expires_at: Some(1_704_067_260_000),
+        };
+
+        let mut encoded = Vec::new();
+        ciborium::ser::into_writer(&original, &mut encoded).unwrap();
+        let decoded: EncryptedMessage = ciborium::de::from_reader(&encoded[..]).unwrap();
+        assert_eq!(original, decoded);
+    }
+
     #[test]
     fn receipt_serde() {
         let receipt =
@@ -151,4 +240,27 @@ mod tests {
         let cbor = ciborium::ser::into_writer(&receipt, Vec::new());
         assert!(cbor.is_ok());
     }
+
+    #[test]
+    fn edit_round_trip() {
+        let original = Edit {
+            target_log_index: 7,
+            new_ciphertext: EncryptedMessage {
+                epoch: 3,
+                sender_index: 1,
+                generation: 5,
+                nonce: [0xCD; 24],
+                ciphertext: vec![9, 8, 7],
+                push_keys: None,
+                message_id: None,
+                expires_at: None,
+            },
+        };
+
+        let mut encoded = Vec::new();
+        ciborium::ser::into_writer(&original, &mut encoded).unwrap();
+        let decoded: Edit = ciborium::de::from_reader(&encoded[..]).unwrap();
+
+        assert_eq!(original, decoded);
+    }
 }
@@ -14,6 +14,7 @@
 //! Each payload variant maps to exactly one opcode (enforced by match
 //! exhaustiveness). Round-trip encoding must produce identical values.
 
+pub mod admin;
 pub mod app;
 pub mod mls;
 pub mod moderation;
@@ -68,6 +69,22 @@ pub enum Payload {
     SyncRequest(session::SyncRequest),
     /// Server sync response
     SyncResponse(session::SyncResponse),
+    /// Client request for its joined rooms
+    RoomListRequest(session::RoomListRequest),
+    /// Server response listing joined rooms
+    RoomListResponse(session::RoomListResponse),
+    /// Operator request or response
+    Admin(admin::AdminMessage),
+    /// Client subscription to public room announcements
+    DirectorySubscribe(session::DirectorySubscribe),
+    /// Server announcement of a new public room
+    RoomAnnouncement(session::RoomAnnouncement),
+    /// Client search of public rooms
+    RoomSearch(session::RoomSearch),
+    /// Server response listing matching public rooms
+    RoomSearchResponse(session::RoomSearchResponse),
+    /// Client request to create a room
+    CreateRoom(session::CreateRoom),
 
     // MLS Operations
     /// Key package upload
@@ -90,10 +107,14 @@ pub enum Payload {
     // Application Messages
     /// Encrypted application message
     AppMessage(app::EncryptedMessage),
+    /// Edit of a previously sent message
+    AppEdit(app::Edit),
     /// Delivery receipt
     AppReceipt(app::Receipt),
     /// Message reaction
     AppReaction(app::Reaction),
+    /// Typing indicator (ephemeral)
+    Typing(app::Typing),
 
     // Moderation
     /// Redact message content
@@ -102,6 +123,8 @@ pub enum Payload {
     Ban(moderation::Ban),
     /// Kick user
     Kick(moderation::Kick),
+    /// Change room name or topic
+    RoomMeta(moderation::RoomMeta),
 
     // Error frame
     /// Error response
@@ -135,6 +158,18 @@ impl ErrorPayload {
     pub const SEQUENCER_ERROR: u16 = 0x0006;
     /// `KeyPackage` not found in registry.
     pub const KEYPACKAGE_NOT_FOUND: u16 = 0x0007;
+    /// Request requires an authenticated session.
+    pub const UNAUTHENTICATED: u16 = 0x0008;
+    /// Frame header carries a protocol version this server doesn't speak.
+    pub const UNSUPPORTED_VERSION: u16 = 0x0009;
+    /// Room is at its member cap.
+    pub const ROOM_FULL: u16 = 0x000A;
+    /// Sender is over its rate limit; retry after `retry_after` seconds.
+    pub const RATE_LIMITED: u16 = 0x000B;
+    /// Session is authenticated but not allowed to do this.
+    pub const FORBIDDEN: u16 = 0x000C;
+    /// Frame was built against an epoch the room has moved past.
+    pub const STALE_EPOCH: u16 = 0x000D;
 
     /// Create a frame rejection error.
     pub fn frame_rejected(reason: impl Into<String>) -> Self {
@@ -170,6 +205,56 @@ impl ErrorPayload {
         Self { code: Self::SEQUENCER_ERROR, message: msg.into(), retry_after: None }
     }
 
+    /// Create an unauthenticated session error.
+    pub fn unauthenticated(msg: impl Into<String>) -> Self {
+        Self { code: Self::UNAUTHENTICATED, message: msg.into(), retry_after: None }
+    }
+
+    /// Create a permission denied error.
+    pub fn forbidden(msg: impl Into<String>) -> Self {
+        Self { code: Self::FORBIDDEN, message: msg.into(), retry_after: None }
+    }
+
+    /// Create an unsupported protocol version error.
+    pub fn unsupported_version(got: u8, supported: u8) -> Self {
+        Self {
+            code: Self::UNSUPPORTED_VERSION,
+            message: format!("unsupported protocol version {got}, server speaks {supported}"),
+            retry_after: None,
+        }
+    }
+
+    /// Create a room full error.
+    pub fn room_full(room_id: u128, max_members: usize) -> Self {
+        Self {
+            code: Self::ROOM_FULL,
+            message: format!("room {room_id:032x} is full ({max_members} members)"),
+            retry_after: None,
+        }
+    }
+
+    /// Create a stale epoch error for a frame built at `epoch` while the
+    /// room is at `current`.
+    pub fn stale_epoch(room_id: u128, epoch: u64, current: u64) -> Self {
+        Self {
+            code: Self::STALE_EPOCH,
+            message: format!(
+                "room {room_id:032x} is at epoch {current}, frame is for epoch {epoch}"
+            ),
+            retry_after: None,
+        }
+    }
+
+    /// Create a rate limit error asking the sender to wait `retry_after`
+    /// seconds.
+    pub fn rate_limited(retry_after: u64) -> Self {
+        Self {
+            code: Self::RATE_LIMITED,
+            message: format!("rate limited, retry after {retry_after}s"),
+            retry_after: Some(retry_after),
+        }
+    }
+
     /// Create a `KeyPackage` not found error.
     pub fn keypackage_not_found(user_id: u64) -> Self {
         Self {
@@ -192,6 +277,14 @@ impl Payload {
             Self::Pong => Opcode::Pong,
             Self::SyncRequest(_) => Opcode::SyncRequest,
             Self::SyncResponse(_) => Opcode::SyncResponse,
+            Self::RoomListRequest(_) => Opcode::RoomListRequest,
+            Self::RoomListResponse(_) => Opcode::RoomListResponse,
+            Self::Admin(_) => Opcode::Admin,
+            Self::DirectorySubscribe(_) => Opcode::DirectorySubscribe,
+            Self::RoomAnnouncement(_) => Opcode::RoomAnnouncement,
+            Self::RoomSearch(_) => Opcode::RoomSearch,
+            Self::RoomSearchResponse(_) => Opcode::RoomSearchResponse,
+            Self::CreateRoom(_) => Opcode::CreateRoom,
             Self::KeyPackage(_) => Opcode::KeyPackage,
             Self::Proposal(_) => Opcode::Proposal,
             Self::Commit(_) => Opcode::Commit,
@@ -201,11 +294,14 @@ impl Payload {
             Self::GroupInfoRequest(_) => Opcode::GroupInfoRequest,
             Self::GroupInfo(_) => Opcode::GroupInfo,
             Self::AppMessage(_) => Opcode::AppMessage,
+            Self::AppEdit(_) => Opcode::AppEdit,
             Self::AppReceipt(_) => Opcode::AppReceipt,
             Self::AppReaction(_) => Opcode::AppReaction,
+            Self::Typing(_) => Opcode::Typing,
             Self::Redact(_) => Opcode::Redact,
             Self::Ban(_) => Opcode::Ban,
             Self::Kick(_) => Opcode::Kick,
+            Self::RoomMeta(_) => Opcode::RoomMeta,
             Self::Error(_) => Opcode::Error,
         }
     }
@@ -239,6 +335,14 @@ impl Payload {
             Self::Ping | Self::Pong => Ok(()), // Zero-byte payloads
             Self::SyncRequest(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::SyncResponse(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::RoomListRequest(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::RoomListResponse(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::Admin(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::DirectorySubscribe(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::RoomAnnouncement(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::RoomSearch(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::RoomSearchResponse(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::CreateRoom(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::KeyPackage(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::Proposal(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::Commit(inner) => ciborium::ser::into_writer(inner, &mut writer),
@@ -248,11 +352,14 @@ impl Payload {
             Self::GroupInfoRequest(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::GroupInfo(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::AppMessage(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::AppEdit(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::AppReceipt(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::AppReaction(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::Typing(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::Redact(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::Ban(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::Kick(inner) => ciborium::ser::into_writer(inner, &mut writer),
+            Self::RoomMeta(inner) => ciborium::ser::into_writer(inner, &mut writer),
             Self::Error(inner) => ciborium::ser::into_writer(inner, &mut writer),
         }
         .map_err(|e| ProtocolError::CborEncode(e.to_string()))
@@ -278,6 +385,7 @@ impl Payload {
     ///   (16 MB)
     /// - `ProtocolError::CborDecode` if CBOR deserialization fails
     /// - `ProtocolError::CborDecode` if opcode is not recognized
+    #[allow(clippy::too_many_lines)]
     pub fn decode(opcode: Opcode, bytes: &[u8]) -> Result<Self> {
         if bytes.len() > FrameHeader::MAX_PAYLOAD_SIZE as usize {
             return Err(ProtocolError::PayloadTooLarge {
@@ -309,6 +417,38 @@ impl Payload {
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
             ),
+            Opcode::RoomListRequest => Self::RoomListRequest(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
+            Opcode::RoomListResponse => Self::RoomListResponse(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
+            Opcode::Admin => Self::Admin(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
+            Opcode::DirectorySubscribe => Self::DirectorySubscribe(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
+            Opcode::RoomAnnouncement => Self::RoomAnnouncement(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
+            Opcode::RoomSearch => Self::RoomSearch(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
+            Opcode::RoomSearchResponse => Self::RoomSearchResponse(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
+            Opcode::CreateRoom => Self::CreateRoom(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
             Opcode::KeyPackage => Self::KeyPackage(
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
@@ -345,6 +485,10 @@ impl Payload {
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
             ),
+            Opcode::AppEdit => Self::AppEdit(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
             Opcode::AppReceipt => Self::AppReceipt(
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
@@ -353,6 +497,10 @@ impl Payload {
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
             ),
+            Opcode::Typing => Self::Typing(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
             Opcode::Redact => Self::Redact(
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
@@ -365,6 +513,10 @@ impl Payload {
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
             ),
+            Opcode::RoomMeta => Self::RoomMeta(
+                ciborium::de::from_reader(bytes)
+                    .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
+            ),
             Opcode::Error => Self::Error(
                 ciborium::de::from_reader(bytes)
                     .map_err(|e| ProtocolError::CborDecode(e.to_string()))?,
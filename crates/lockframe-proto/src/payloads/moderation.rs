@@ -55,6 +55,21 @@ pub struct Kick {
     pub moderator_id: u64,
 }
 
+/// Change a room's name or topic
+///
+/// Only room admins may send this. The room's creator counts as its admin
+/// until admins are assigned. Fields left `None` keep their current value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomMeta {
+    /// New display name
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub name: Option<String>,
+
+    /// New topic
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub topic: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,4 +94,15 @@ mod tests {
         let cbor = ciborium::ser::into_writer(&ban, Vec::new());
         assert!(cbor.is_ok());
     }
+
+    #[test]
+    fn room_meta_serde() {
+        let meta = RoomMeta { name: None, topic: Some("release planning".to_string()) };
+
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&meta, &mut bytes).expect("encode");
+
+        let decoded: RoomMeta = ciborium::de::from_reader(&bytes[..]).expect("decode");
+        assert_eq!(decoded, meta);
+    }
 }
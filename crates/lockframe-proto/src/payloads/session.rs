@@ -75,6 +75,21 @@ pub struct HelloReply {
     /// Authentication challenge (if needed)
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub challenge: Option<Vec<u8>>,
+    /// Rooms with messages sequenced while the user had no session in them
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub unread: Vec<RoomUnread>,
+}
+
+/// Messages a user missed in one room while offline.
+///
+/// Listed in `HelloReply` so a reconnecting client can sync the busiest
+/// rooms first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomUnread {
+    /// Room the messages were sent to.
+    pub room_id: u128,
+    /// Application messages sequenced since the user was last in the room.
+    pub count: u64,
 }
 
 impl std::fmt::Debug for HelloReply {
@@ -86,6 +101,7 @@ impl std::fmt::Debug for HelloReply {
                 "challenge",
                 &self.challenge.as_ref().map(|ch| format!("<redacted {} bytes>", ch.len())),
             )
+            .field("unread", &self.unread)
             .finish()
     }
 }
@@ -123,6 +139,14 @@ pub struct SyncRequest {
     /// Default: 100 frames per batch.
     #[serde(default = "default_limit")]
     pub limit: u64,
+
+    /// Start replaying from the first frame with an HLC timestamp at or after
+    /// this value, instead of `from_log_index`.
+    ///
+    /// For clients that only know the wall-clock time of their last activity.
+    /// When set, `from_log_index` is ignored.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub from_timestamp: Option<u64>,
 }
 
 fn default_limit() -> u64 {
@@ -151,6 +175,120 @@ pub struct SyncResponse {
     ///
     /// After processing all frames, client epoch should match this.
     pub server_epoch: u64,
+
+    /// Stand-in for frames removed by the room's retention policy.
+    ///
+    /// Set when the request started before the first retained frame;
+    /// `frames` then start at `snapshot.first_log_index`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub snapshot: Option<SyncSnapshot>,
+}
+
+/// Room state replacing frames the server no longer keeps
+///
+/// A client that missed pruned commits can't replay them, so it rejoins from
+/// the `GroupInfo` (e.g. by external commit) and continues syncing at
+/// `first_log_index`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncSnapshot {
+    /// Log index of the oldest frame still stored.
+    pub first_log_index: u64,
+
+    /// MLS epoch of `group_info`.
+    pub epoch: u64,
+
+    /// Serialized MLS `GroupInfo` at the time frames were pruned.
+    pub group_info: Vec<u8>,
+}
+
+/// Request the authenticated user's joined rooms
+///
+/// Lets a reconnecting client rediscover its rooms without local state. The
+/// server rejects the request if the session has not authenticated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomListRequest {}
+
+/// Server response listing the user's joined rooms
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomListResponse {
+    /// Joined room IDs in ascending order.
+    pub room_ids: Vec<u128>,
+}
+
+/// Subscribe to the server's room directory
+///
+/// After this, the session receives a [`RoomAnnouncement`] for every public
+/// room created on the server. Rooms created before subscribing are not
+/// announced.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectorySubscribe {}
+
+/// A public room was created
+///
+/// Sent to every directory subscriber. Private rooms are never announced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomAnnouncement {
+    /// Room that was created.
+    pub room_id: u128,
+    /// User who created it.
+    pub creator: u64,
+    /// Unix timestamp (seconds) when it was created.
+    pub created_at_secs: u64,
+    /// Display name.
+    #[serde(default)]
+    pub name: String,
+    /// Topic.
+    #[serde(default)]
+    pub topic: String,
+}
+
+/// Search the server's public rooms
+///
+/// Matches rooms whose name or topic contains `query`, ignoring case. An
+/// empty query matches every public room. The server answers with a
+/// [`RoomSearchResponse`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomSearch {
+    /// Substring to look for in room names and topics.
+    pub query: String,
+}
+
+/// Public rooms matching a [`RoomSearch`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomSearchResponse {
+    /// Matching rooms in ascending room ID order.
+    ///
+    /// The server caps the number of results; refine the query to find rooms
+    /// past the cap.
+    pub rooms: Vec<RoomAnnouncement>,
+}
+
+/// Create a room with its initial metadata and policy
+///
+/// The room ID is taken from the frame header. The server creates the room
+/// with the sender as its creator and subscribes the session to it, provided
+/// the session may create rooms and is under its creation rate limit.
+/// Failures are answered with an `Error` frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateRoom {
+    /// List the room in the room directory.
+    #[serde(default)]
+    pub public: bool,
+    /// Display name.
+    #[serde(default)]
+    pub name: String,
+    /// Topic.
+    #[serde(default)]
+    pub topic: String,
+    /// Members allowed to add and remove members. Empty means anyone may.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub admins: Vec<u64>,
+    /// Prune frames older than this many seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u64>,
+    /// Keep at most this many of the newest frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_frames: Option<u64>,
 }
 
 #[cfg(test)]
@@ -172,7 +310,7 @@ mod tests {
 
     #[test]
     fn sync_request_serde() {
-        let request = SyncRequest { from_log_index: 42, limit: 50 };
+        let request = SyncRequest { from_log_index: 42, limit: 50, from_timestamp: None };
 
         let mut bytes = Vec::new();
         ciborium::ser::into_writer(&request, &mut bytes).expect("encode");
@@ -184,7 +322,8 @@ mod tests {
     #[test]
     fn sync_request_default_limit() {
         // Encode without limit field
-        let request_no_limit = SyncRequest { from_log_index: 10, limit: default_limit() };
+        let request_no_limit =
+            SyncRequest { from_log_index: 10, limit: default_limit(), from_timestamp: None };
 
         let mut bytes = Vec::new();
         ciborium::ser::into_writer(&request_no_limit, &mut bytes).expect("encode");
@@ -193,12 +332,24 @@ mod tests {
         assert_eq!(decoded.limit, 100); // default
     }
 
+    #[test]
+    fn sync_request_from_timestamp_serde() {
+        let request = SyncRequest { from_log_index: 0, limit: 50, from_timestamp: Some(1_000) };
+
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&request, &mut bytes).expect("encode");
+
+        let decoded: SyncRequest = ciborium::de::from_reader(&bytes[..]).expect("decode");
+        assert_eq!(decoded.from_timestamp, Some(1_000));
+    }
+
     #[test]
     fn sync_response_serde() {
         let response = SyncResponse {
             frames: vec![vec![1, 2, 3], vec![4, 5, 6]],
             has_more: true,
             server_epoch: 5,
+            snapshot: Some(SyncSnapshot { first_log_index: 40, epoch: 3, group_info: vec![7] }),
         };
 
         let mut bytes = Vec::new();
@@ -207,4 +358,38 @@ mod tests {
         let decoded: SyncResponse = ciborium::de::from_reader(&bytes[..]).expect("decode");
         assert_eq!(response, decoded);
     }
+
+    #[test]
+    fn room_announcement_serde() {
+        let announcement = RoomAnnouncement {
+            room_id: u128::MAX,
+            creator: 7,
+            created_at_secs: 1_000,
+            name: "lobby".into(),
+            topic: "general chat".into(),
+        };
+
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&announcement, &mut bytes).expect("encode");
+
+        let decoded: RoomAnnouncement = ciborium::de::from_reader(&bytes[..]).expect("decode");
+        assert_eq!(announcement, decoded);
+    }
+
+    #[test]
+    fn create_room_serde() {
+        let create = CreateRoom {
+            public: true,
+            name: "lobby".into(),
+            admins: vec![7],
+            max_frames: Some(100),
+            ..CreateRoom::default()
+        };
+
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&create, &mut bytes).expect("encode");
+
+        let decoded: CreateRoom = ciborium::de::from_reader(&bytes[..]).expect("decode");
+        assert_eq!(create, decoded);
+    }
 }
@@ -5,7 +5,7 @@
 //! verify round-trip properties.
 
 use bytes::Bytes;
-use lockframe_proto::{Frame, FrameHeader, Opcode};
+use lockframe_proto::{Frame, FrameFlags, FrameHeader, Opcode};
 use proptest::prelude::*;
 
 /// Strategy for generating arbitrary opcodes
@@ -223,3 +223,61 @@ fn prop_frame_encoded_size_correct() {
         );
     });
 }
+
+#[test]
+fn prop_frame_setters_roundtrip() {
+    proptest!(|(
+        opcode in arbitrary_opcode(),
+        flags in any::<u8>(),
+        request_id in any::<u32>(),
+        room_id in any::<u128>(),
+        sender_id in any::<u64>(),
+        epoch in any::<u64>(),
+        context_id in any::<u64>(),
+        hlc_timestamp in any::<u64>(),
+        signature in prop::collection::vec(any::<u8>(), 64),
+        payload in prop::collection::vec(any::<u8>(), 0..256),
+    )| {
+        // Start from a different opcode so set_opcode has something to change
+        let mut header = FrameHeader::new(Opcode::Ping);
+        header.set_opcode(opcode);
+        header.set_flags(FrameFlags::from_byte(flags));
+        header.set_request_id(request_id);
+        header.set_room_id(room_id);
+        header.set_sender_id(sender_id);
+        header.set_epoch(epoch);
+        header.set_hlc_timestamp(hlc_timestamp);
+        if opcode == Opcode::Welcome {
+            header.set_recipient_id(context_id);
+        } else {
+            header.set_log_index(context_id);
+        }
+        let signature: [u8; 64] = signature.try_into().expect("strategy yields 64 bytes");
+        header.set_signature(signature);
+
+        let frame = Frame::new(header, Bytes::from(payload.clone()));
+
+        let mut buf = Vec::new();
+        frame.encode(&mut buf).expect("encode should succeed");
+        let decoded = Frame::decode(&buf).expect("decode should succeed");
+
+        // PROPERTY: every field reads back what was set, before and after the wire
+        for header in [&frame.header, &decoded.header] {
+            prop_assert_eq!(header.opcode_enum(), Some(opcode), "Opcode mismatch");
+            prop_assert_eq!(header.flags(), FrameFlags::from_byte(flags), "Flags mismatch");
+            prop_assert_eq!(header.request_id(), request_id, "Request ID mismatch");
+            prop_assert_eq!(header.room_id(), room_id, "Room ID mismatch");
+            prop_assert_eq!(header.sender_id(), sender_id, "Sender ID mismatch");
+            prop_assert_eq!(header.epoch(), epoch, "Epoch mismatch");
+            prop_assert_eq!(header.hlc_timestamp(), hlc_timestamp, "HLC timestamp mismatch");
+            if opcode == Opcode::Welcome {
+                prop_assert_eq!(header.recipient_id(), context_id, "Recipient ID mismatch");
+            } else {
+                prop_assert_eq!(header.log_index(), context_id, "Log index mismatch");
+            }
+            prop_assert_eq!(header.signature(), &signature, "Signature mismatch");
+            prop_assert_eq!(header.payload_size() as usize, payload.len(), "Payload size mismatch");
+        }
+        prop_assert_eq!(&decoded.payload[..], &payload[..], "Payload content mismatch");
+    });
+}
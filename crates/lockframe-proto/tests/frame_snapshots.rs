@@ -75,6 +75,7 @@ fn snapshot_hello_reply_frame() {
         session_id: 0x1000_0000_0000_0000,
         capabilities: vec![],
         challenge: None,
+        unread: Vec::new(),
     });
 
     let frame = reply
@@ -90,6 +91,7 @@ fn snapshot_hello_reply_frame_with_challenge() {
         session_id: 0x1000_0000_0000_0000,
         capabilities: vec!["mls".to_string()],
         challenge: Some(vec![0x01, 0x02, 0x03, 0x04]),
+        unread: Vec::new(),
     });
 
     let frame = reply
@@ -135,6 +137,8 @@ fn snapshot_encrypted_message_frame() {
         nonce: [0x02; 24],
         ciphertext: vec![0xca, 0xfe, 0xba, 0xbe],
         push_keys: None,
+        message_id: None,
+        expires_at: None,
     });
 
     let frame = msg
@@ -0,0 +1,169 @@
+//! Session authentication policy.
+//!
+//! The connection state machine completes the Hello handshake; the
+//! [`Authenticator`] then decides what the new session is allowed to do,
+//! as a set of [`Capabilities`].
+
+use std::{fmt, ops};
+
+use lockframe_proto::payloads::session::Hello;
+use subtle::ConstantTimeEq;
+
+/// What an authenticated session is allowed to do.
+///
+/// A bitset: combine flags with `|` and test them with
+/// [`contains`](Self::contains). The empty set is the default, which is what
+/// a session has before its Hello.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// No capabilities.
+    pub const NONE: Self = Self(0);
+
+    /// Session may send `Opcode::Admin` requests.
+    pub const ADMIN: Self = Self(1);
+
+    /// Session may create rooms, either with an `Opcode::CreateRoom` request
+    /// or by committing to a room the server does not know yet.
+    pub const CREATE_ROOM: Self = Self(1 << 1);
+
+    /// What a regular user gets.
+    pub const USER: Self = Self::CREATE_ROOM;
+
+    const NAMES: [(Self, &'static str); 2] =
+        [(Self::ADMIN, "ADMIN"), (Self::CREATE_ROOM, "CREATE_ROOM")];
+
+    /// Raw bits.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether every flag in `other` is set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether the session may send admin requests.
+    pub const fn is_admin(self) -> bool {
+        self.contains(Self::ADMIN)
+    }
+
+    /// Whether the session may create rooms.
+    pub const fn can_create_room(self) -> bool {
+        self.contains(Self::CREATE_ROOM)
+    }
+}
+
+impl ops::BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitOrAssign for Capabilities {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl fmt::Debug for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = Self::NAMES.iter().filter(|(flag, _)| self.contains(*flag)).map(|(_, n)| n);
+        f.debug_set().entries(names).finish()
+    }
+}
+
+/// Decides the [`Capabilities`] of each session from its Hello.
+pub trait Authenticator: Send + Sync + 'static {
+    /// Capabilities for the session that sent `hello`.
+    fn authenticate(&self, hello: &Hello) -> Capabilities;
+}
+
+/// Accepts every session as a regular, non-admin user.
+///
+/// The default, so admin requests are refused until an operator configures
+/// an authenticator that grants them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UsersOnly;
+
+impl Authenticator for UsersOnly {
+    fn authenticate(&self, _hello: &Hello) -> Capabilities {
+        Capabilities::USER
+    }
+}
+
+/// Grants admin to sessions whose Hello carries a shared secret token.
+///
+/// Everyone else is a regular user.
+pub struct AdminToken {
+    token: Vec<u8>,
+}
+
+impl AdminToken {
+    /// Grant admin to Hellos whose `auth_token` equals `token`.
+    pub fn new(token: impl Into<Vec<u8>>) -> Self {
+        Self { token: token.into() }
+    }
+}
+
+impl std::fmt::Debug for AdminToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminToken")
+            .field("token", &format!("<redacted {} bytes>", self.token.len()))
+            .finish()
+    }
+}
+
+impl Authenticator for AdminToken {
+    fn authenticate(&self, hello: &Hello) -> Capabilities {
+        // Constant time, so response timing doesn't leak how much of a guess
+        // matched
+        let matches =
+            hello.auth_token.as_deref().is_some_and(|token| bool::from(token.ct_eq(&self.token)));
+        if matches { Capabilities::USER | Capabilities::ADMIN } else { Capabilities::USER }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(auth_token: Option<&[u8]>) -> Hello {
+        Hello {
+            version: 1,
+            capabilities: Vec::new(),
+            sender_id: Some(1),
+            auth_token: auth_token.map(<[u8]>::to_vec),
+        }
+    }
+
+    #[test]
+    fn admin_token_grants_admin_only_on_match() {
+        let auth = AdminToken::new(*b"secret");
+        assert!(auth.authenticate(&hello(Some(b"secret"))).is_admin());
+        assert!(!auth.authenticate(&hello(Some(b"guess"))).is_admin());
+        assert!(!auth.authenticate(&hello(None)).is_admin());
+        assert!(!auth.authenticate(&hello(Some(b"secrets"))).is_admin());
+        assert!(!UsersOnly.authenticate(&hello(Some(b"secret"))).is_admin());
+        assert!(UsersOnly.authenticate(&hello(None)).can_create_room());
+    }
+
+    #[test]
+    fn capabilities_combine_and_debug_by_name() {
+        let mut caps = Capabilities::NONE;
+        assert!(!caps.can_create_room());
+        caps |= Capabilities::CREATE_ROOM;
+        assert!(caps.can_create_room() && !caps.is_admin());
+        assert!((caps | Capabilities::ADMIN).contains(Capabilities::USER));
+        assert_eq!(format!("{:?}", caps | Capabilities::ADMIN), "{\"ADMIN\", \"CREATE_ROOM\"}");
+    }
+
+    #[test]
+    fn admin_token_debug_redacts_token() {
+        let debug = format!("{:?}", AdminToken::new(*b"secret"));
+        assert!(!debug.contains("secret"));
+    }
+}
@@ -2,31 +2,84 @@
 //!
 //! Ties together connection state machines, `RoomManager` (MLS validation +
 //! sequencing), `ConnectionRegistry` (session-to-room mapping), and storage.
-
-use std::collections::HashMap;
+//!
+//! Room state lives in [`RoomShards`], so runtimes can split frame handling
+//! into three phases: [`ServerDriver::route_frame`] under the driver lock,
+//! which queues room frames on their shard, [`RoomShards::process_next`]
+//! under only the room's shard lock, and [`ServerDriver::finish_room_frame`]
+//! back under the driver lock. [`ServerDriver::process_event`] runs all three
+//! inline, so every room frame goes through its room's queue.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
 
 use lockframe_core::{
-    connection::{Connection, ConnectionAction, ConnectionConfig},
+    connection::{Connection, ConnectionAction, ConnectionConfig, ConnectionState},
     env::Environment,
+    mls::{MlsGroupState, MlsProvider, group_state_from_group_info, validate_key_package},
 };
 use lockframe_proto::{
-    Frame, FrameHeader, Opcode, Payload,
+    Frame, FrameHeader, Opcode, Payload, ProtocolError,
     payloads::{
         ErrorPayload,
+        admin::{AdminMessage, AdminRequest, AdminResponse},
         mls::{GroupInfoPayload, KeyPackageFetchPayload},
-        session::SyncResponse,
+        session::{
+            RoomAnnouncement, RoomListResponse, RoomSearchResponse, SyncResponse, SyncSnapshot,
+        },
     },
 };
 
 use crate::{
     RoomError,
+    auth::{Authenticator, Capabilities, UsersOnly},
     key_package_registry::{KeyPackageEntry, KeyPackageRegistry, StoreResult},
+    rate_limit::RateLimiter,
     registry::{ConnectionRegistry, SessionInfo},
-    room_manager::{RoomAction, RoomManager},
+    room_manager::{MembershipChange, ProcessedFrame, RoomAction, RoomListing, RoomSettings},
+    room_shards::{DEFAULT_ROOM_SHARDS, RoomShards},
+    sequencer::RoomCheckpoint,
     server_error::ServerError,
-    storage::{Storage, StorageError},
+    storage::{
+        AuditEntry, RetentionPolicy, RoomSnapshot, Storage, StorageError, StoredRoomMetadata,
+    },
+    unread::UnreadTracker,
 };
 
+/// Most rooms one `RoomSearchResponse` lists.
+pub const MAX_ROOM_SEARCH_RESULTS: usize = 100;
+
+/// Longest `RoomSearch` query, in bytes, the server accepts.
+pub const MAX_ROOM_SEARCH_QUERY_LEN: usize = 128;
+
+/// Most audit entries buffered between flushes.
+///
+/// Entries past the cap are dropped and counted, so a client provoking
+/// errors in a loop can't grow the buffer or the write it turns into.
+pub const MAX_PENDING_AUDIT: usize = 1024;
+
+/// Full persisted state of one room.
+///
+/// Produced by [`ServerDriver::export_room`] and consumed by
+/// [`ServerDriver::import_room`] to move a room between server instances.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomExport {
+    /// Room being exported.
+    pub room_id: u128,
+    /// Creator and creation time.
+    pub metadata: StoredRoomMetadata,
+    /// Every frame in the room's log, in log order starting at index 0.
+    pub frames: Vec<Frame>,
+    /// MLS group state, if any was stored.
+    pub mls_state: Option<MlsGroupState>,
+    /// Latest `GroupInfo` as `(epoch, bytes)`, if any was published.
+    pub group_info: Option<(u64, Vec<u8>)>,
+    /// Persisted members in ascending order.
+    pub members: Vec<u64>,
+}
+
 /// Server configuration
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
@@ -34,11 +87,77 @@ pub struct ServerConfig {
     pub connection: ConnectionConfig,
     /// Maximum concurrent connections
     pub max_connections: usize,
+    /// Number of independently locked room shards
+    pub room_shards: usize,
+    /// Byte budget for the frames in one `SyncResponse`
+    ///
+    /// Responses stop early with `has_more` set once the budget is reached,
+    /// whatever count the client requested. The first frame is always sent so
+    /// a single oversized frame can't stall sync.
+    pub max_sync_bytes: usize,
+    /// Frame cap for one `SyncResponse`
+    ///
+    /// Requests for more frames are served a page at a time: the response
+    /// stops with `has_more` set and the client asks again for the rest. Each
+    /// follow-up queues behind other sessions' requests, so a long backfill
+    /// is interleaved with short syncs instead of holding them up. Values
+    /// below 1 are treated as 1 so every page makes progress.
+    pub max_sync_frames: usize,
+    /// Maximum members per room (`None` = unlimited)
+    ///
+    /// Welcomes to new members and external joins are rejected with a
+    /// `ROOM_FULL` error once a room's persisted membership reaches the cap.
+    pub max_members: Option<usize>,
+    /// How often rooms are checked against their [`RetentionPolicy`]
+    ///
+    /// The same sweep drops expired `KeyPackage`s. It runs on `Tick`, so the
+    /// effective interval is rounded up to the next tick.
+    pub retention_sweep_interval: Duration,
+    /// Most frames queued for one offline user
+    ///
+    /// Welcomes to users without a session are held in storage and sent
+    /// after their next Hello. Once a user's queue is full, further frames
+    /// for them are dropped with a warning.
+    pub max_pending_deliveries: usize,
+    /// Most offline users whose unread counts are kept for their next Hello
+    ///
+    /// Once full, the user tracked longest is forgotten and gets no unread
+    /// hint, as after a restart.
+    pub max_unread_users: usize,
+    /// Most `CreateRoom` requests one user may make per
+    /// `room_creation_window`
+    ///
+    /// Requests past the limit are refused with a `RATE_LIMITED` error
+    /// carrying the seconds until the user may try again.
+    pub room_creation_limit: usize,
+    /// Sliding window for `room_creation_limit`
+    pub room_creation_window: Duration,
+    /// Most `RoomSearch` requests one user may make per
+    /// `room_search_window`
+    ///
+    /// Searches past the limit are refused with a `RATE_LIMITED` error.
+    pub room_search_limit: usize,
+    /// Sliding window for `room_search_limit`
+    pub room_search_window: Duration,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
-        Self { connection: ConnectionConfig::default(), max_connections: 10_000 }
+        Self {
+            connection: ConnectionConfig::default(),
+            max_connections: 10_000,
+            room_shards: DEFAULT_ROOM_SHARDS,
+            max_sync_bytes: 4 * 1024 * 1024,
+            max_sync_frames: 256,
+            max_members: None,
+            retention_sweep_interval: Duration::from_mins(1),
+            max_pending_deliveries: 64,
+            max_unread_users: 100_000,
+            room_creation_limit: 10,
+            room_creation_window: Duration::from_mins(1),
+            room_search_limit: 30,
+            room_search_window: Duration::from_mins(1),
+        }
     }
 }
 
@@ -61,6 +180,17 @@ pub enum ServerEvent {
         frame: Frame,
     },
 
+    /// Bytes from a connection failed to decode as a frame
+    ///
+    /// The runtime reports this instead of dropping the input silently, so
+    /// the peer learns why (e.g. it speaks a newer protocol version).
+    FrameDecodeFailed {
+        /// Connection that sent the bytes
+        session_id: u64,
+        /// Why decoding failed
+        error: ProtocolError,
+    },
+
     /// A connection was closed (by peer or error)
     ConnectionClosed {
         /// Connection that was closed
@@ -115,6 +245,38 @@ pub enum ServerAction<I = std::time::Instant> {
     },
 }
 
+/// Where a received frame goes after [`ServerDriver::route_frame`].
+#[derive(Debug, Clone)]
+pub enum FrameRoute<I = std::time::Instant> {
+    /// Frame was fully handled by session state.
+    Handled(Vec<ServerAction<I>>),
+
+    /// Frame was queued on its room's shard.
+    ///
+    /// Call [`RoomShards::process_next`] for `room_id` once, then pass the
+    /// result to [`ServerDriver::finish_room_frame`]. The frame processed may
+    /// be one another session queued earlier.
+    Room {
+        /// Room the frame was queued for
+        room_id: u128,
+        /// Actions already produced while routing (room creation, subscribe)
+        actions: Vec<ServerAction<I>>,
+    },
+}
+
+/// [`FrameRoute`] of a frame before a room frame is queued.
+enum Dispatch<I> {
+    /// Frame was fully handled by session state.
+    Handled(Vec<ServerAction<I>>),
+    /// Frame goes to its room's queue.
+    Room {
+        /// Frame to queue
+        frame: Frame,
+        /// Actions already produced while routing
+        actions: Vec<ServerAction<I>>,
+    },
+}
+
 /// Log levels for server actions
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LogLevel {
@@ -140,16 +302,36 @@ where
     connections: HashMap<u64, Connection<E::Instant>>,
     /// Session/room registry
     pub(crate) registry: ConnectionRegistry,
-    /// Room manager (routing + sequencing)
-    room_manager: RoomManager,
+    /// Room managers sharded by room ID (routing + sequencing)
+    rooms: RoomShards,
     /// `KeyPackage` registry for publish/fetch operations
     key_package_registry: KeyPackageRegistry,
+    /// Crypto and clock for validating published `KeyPackage`s
+    mls_provider: MlsProvider<E>,
     /// Storage backend
     storage: S,
     /// Environment (time, RNG)
     env: E,
     /// Server configuration
     config: ServerConfig,
+    /// Decides each session's rights after Hello
+    authenticator: Box<dyn Authenticator>,
+    /// When the last retention sweep ran (`None` until the first tick)
+    last_retention_sweep: Option<E::Instant>,
+    /// Messages each member missed while offline, reported in `HelloReply`
+    unread: UnreadTracker,
+    /// Recent `CreateRoom` requests per user
+    room_creations: RateLimiter<E::Instant>,
+    /// Recent `RoomSearch` requests per user
+    room_searches: RateLimiter<E::Instant>,
+    /// Directory entries of public rooms, searched by `RoomSearch`
+    public_rooms: BTreeMap<u128, RoomAnnouncement>,
+    /// Audit entries waiting for the next flush, at most
+    /// [`MAX_PENDING_AUDIT`]
+    pending_audit: Vec<AuditEntry>,
+    /// Audit entries dropped since the last flush because the buffer was
+    /// full
+    dropped_audit: usize,
 }
 
 impl<E, S> ServerDriver<E, S>
@@ -162,14 +344,35 @@ where
         Self {
             connections: HashMap::new(),
             registry: ConnectionRegistry::new(),
-            room_manager: RoomManager::new(),
+            rooms: RoomShards::with_max_members(config.room_shards, config.max_members),
             key_package_registry: KeyPackageRegistry::new(),
+            mls_provider: MlsProvider::new(env.clone()),
             storage,
             env,
+            room_creations: RateLimiter::new(
+                config.room_creation_limit,
+                config.room_creation_window,
+            ),
+            room_searches: RateLimiter::new(config.room_search_limit, config.room_search_window),
+            public_rooms: BTreeMap::new(),
+            pending_audit: Vec::new(),
+            dropped_audit: 0,
+            unread: UnreadTracker::new(config.max_unread_users),
             config,
+            authenticator: Box::new(UsersOnly),
+            last_retention_sweep: None,
         }
     }
 
+    /// Use `authenticator` to grant rights to new sessions.
+    ///
+    /// Defaults to [`UsersOnly`], which never grants admin.
+    #[must_use]
+    pub fn with_authenticator(mut self, authenticator: impl Authenticator) -> Self {
+        self.authenticator = Box::new(authenticator);
+        self
+    }
+
     /// Process a server event and return actions to execute.
     ///
     /// This is the main entry point for the server driver.
@@ -177,20 +380,205 @@ where
         &mut self,
         event: ServerEvent,
     ) -> Result<Vec<ServerAction<E::Instant>>, ServerError> {
+        let actions = match event {
+            // Audited by `route_frame` and `finish_room_frame`
+            ServerEvent::FrameReceived { session_id, frame } => {
+                return self.handle_frame_received(session_id, frame);
+            },
+            ServerEvent::ConnectionAccepted { session_id } => {
+                self.handle_connection_accepted(session_id)
+            },
+            ServerEvent::FrameDecodeFailed { session_id, error } => {
+                self.handle_frame_decode_failed(session_id, &error)
+            },
+            ServerEvent::ConnectionClosed { session_id, reason } => {
+                self.handle_connection_closed(session_id, &reason)
+            },
+            ServerEvent::Tick => self.handle_tick(),
+        };
+        Ok(self.audited(actions))
+    }
+
+    /// Check whether an event would be accepted, without mutating state.
+    ///
+    /// Runs the same session, payload, and sequencer validation as
+    /// [`Self::process_event`] but never persists, broadcasts, or updates
+    /// connections. Useful for linting frames and debugging rejections.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that processing would report for a rejected event:
+    /// - `ServerError::SessionNotFound` / `SessionAlreadyExists` for unknown or
+    ///   duplicate sessions
+    /// - `ServerError::ConnectionFailed` if the connection limit is reached
+    /// - `ServerError::Protocol` if the payload does not decode
+    /// - `ServerError::Room` if sequencer validation fails, the room is
+    ///   missing, or the room would reject the frame (`RoomError::Rejected`)
+    pub fn validate_event(&self, event: &ServerEvent) -> Result<(), ServerError> {
         match event {
             ServerEvent::ConnectionAccepted { session_id } => {
-                Ok(self.handle_connection_accepted(session_id))
+                if self.connections.contains_key(session_id) {
+                    return Err(ServerError::SessionAlreadyExists(*session_id));
+                }
+                if self.connections.len() >= self.config.max_connections {
+                    return Err(ServerError::ConnectionFailed {
+                        session_id: *session_id,
+                        reason: "max connections exceeded".to_string(),
+                    });
+                }
+                Ok(())
             },
             ServerEvent::FrameReceived { session_id, frame } => {
-                self.handle_frame_received(session_id, frame)
+                self.validate_frame(*session_id, frame)
             },
-            ServerEvent::ConnectionClosed { session_id, reason } => {
-                Ok(self.handle_connection_closed(session_id, &reason))
+            ServerEvent::FrameDecodeFailed { .. }
+            | ServerEvent::ConnectionClosed { .. }
+            | ServerEvent::Tick => Ok(()),
+        }
+    }
+
+    /// Validate a received frame for [`Self::validate_event`].
+    fn validate_frame(&self, session_id: u64, frame: &Frame) -> Result<(), ServerError> {
+        if !self.connections.contains_key(&session_id) {
+            return Err(ServerError::SessionNotFound(session_id));
+        }
+
+        let opcode = frame.header.opcode_enum();
+        match opcode {
+            Some(
+                Opcode::Hello
+                | Opcode::Ping
+                | Opcode::Pong
+                | Opcode::Goodbye
+                | Opcode::KeyPackagePublish
+                | Opcode::KeyPackageFetch
+                | Opcode::GroupInfoRequest
+                | Opcode::RoomListRequest
+                | Opcode::DirectorySubscribe
+                | Opcode::RoomSearch
+                | Opcode::CreateRoom,
+            ) => {
+                Payload::from_frame(frame)?;
+                Ok(())
+            },
+
+            // Admin requests aren't scoped to a room, so room 0 is fine
+            Some(Opcode::Admin) => {
+                Payload::from_frame(frame)?;
+                if !self.registry.sessions(session_id).is_some_and(|i| i.capabilities.is_admin()) {
+                    let error = ErrorPayload::forbidden("admin requests require an admin session");
+                    return Err(RoomError::Rejected(error).into());
+                }
+                Ok(())
+            },
+
+            // Processing reloads an evicted room before syncing it
+            Some(Opcode::SyncRequest) => {
+                Payload::from_frame(frame)?;
+                let room_id = frame.header.room_id();
+                if !self.rooms.has_room(room_id)
+                    && self.storage.load_room_metadata(room_id)?.is_none()
+                {
+                    return Err(RoomError::RoomNotFound(room_id).into());
+                }
+                Ok(())
+            },
+
+            // Stored without sequencing, after the same checks dispatch runs
+            Some(Opcode::GroupInfo) => {
+                let Payload::GroupInfo(payload) = Payload::from_frame(frame)? else {
+                    return Err(ServerError::Protocol("expected GroupInfo payload".to_string()));
+                };
+                self.check_group_info(session_id, frame, &payload).map(drop)
+            },
+
+            // Routed directly to the recipient without sequencing, after the
+            // same checks dispatch runs
+            Some(Opcode::Welcome) => {
+                let room_id = frame.header.room_id();
+                let sender_id = self.session_user_id(session_id);
+                let recipient_id = frame.header.recipient_id();
+                self.rooms.with_room(room_id, |rooms| {
+                    rooms.check_welcome(room_id, sender_id, recipient_id, &self.storage)
+                })?;
+                Ok(())
+            },
+
+            _ => {
+                if let Some(error) = self.sender_rejection(session_id, frame) {
+                    return Err(RoomError::Rejected(error).into());
+                }
+
+                let is_commit =
+                    opcode == Some(Opcode::Commit) || opcode == Some(Opcode::ExternalCommit);
+                match self.rooms.with_room(frame.header.room_id(), |rooms| {
+                    rooms.validate_frame(frame, &self.storage)
+                }) {
+                    // Commits to unknown rooms create the room on processing
+                    Err(RoomError::RoomNotFound(_)) if is_commit => Ok(()),
+                    result => result.map_err(ServerError::from),
+                }
             },
-            ServerEvent::Tick => Ok(self.handle_tick()),
         }
     }
 
+    /// Hold `frame` in storage until `user_id` next connects.
+    fn queue_for_offline(&self, user_id: u64, frame: &Frame) -> ServerAction<E::Instant> {
+        let room_id = frame.header.room_id();
+        let (level, message) = match self.storage.queue_delivery(
+            user_id,
+            frame,
+            self.config.max_pending_deliveries,
+        ) {
+            Ok(true) => (
+                LogLevel::Debug,
+                format!(
+                    "user {user_id} not connected, queued frame for room {room_id:032x} until their next session"
+                ),
+            ),
+            Ok(false) => (
+                LogLevel::Warn,
+                format!(
+                    "user {user_id} not connected and their delivery queue is full, dropped frame for room {room_id:032x}"
+                ),
+            ),
+            Err(e) => (
+                LogLevel::Warn,
+                format!(
+                    "user {user_id} not connected, failed to queue frame for room {room_id:032x}: {e}"
+                ),
+            ),
+        };
+        ServerAction::Log { level, message, timestamp: self.env.now() }
+    }
+
+    /// Send `user_id` the frames queued while they were offline.
+    ///
+    /// Queued Welcomes subscribe the session to their room, as a Welcome
+    /// delivered live does.
+    fn deliver_pending(&mut self, session_id: u64, user_id: u64) -> Vec<ServerAction<E::Instant>> {
+        let frames = match self.storage.take_deliveries(user_id) {
+            Ok(frames) => frames,
+            Err(e) => {
+                return vec![ServerAction::Log {
+                    level: LogLevel::Warn,
+                    message: format!("failed to load queued frames for user {user_id}: {e}"),
+                    timestamp: self.env.now(),
+                }];
+            },
+        };
+
+        frames
+            .into_iter()
+            .map(|frame| {
+                if frame.header.opcode_enum() == Some(Opcode::Welcome) {
+                    self.registry.subscribe(session_id, frame.header.room_id());
+                }
+                ServerAction::SendToSession { session_id, frame }
+            })
+            .collect()
+    }
+
     /// Handle a new connection being accepted.
     fn handle_connection_accepted(&mut self, session_id: u64) -> Vec<ServerAction<E::Instant>> {
         let now = self.env.now();
@@ -216,12 +604,72 @@ where
     }
 
     /// Handle a frame received from a connection.
-    #[allow(clippy::too_many_lines)]
     fn handle_frame_received(
         &mut self,
         session_id: u64,
         frame: Frame,
     ) -> Result<Vec<ServerAction<E::Instant>>, ServerError> {
+        match self.route_frame(session_id, frame)? {
+            FrameRoute::Handled(actions) => Ok(actions),
+            FrameRoute::Room { room_id, mut actions } => {
+                let processed = self.rooms.process_next(room_id, self.env.now(), &self.storage);
+                if let Some(processed) = processed {
+                    actions.extend(self.finish_room_frame(processed));
+                }
+                Ok(actions)
+            },
+        }
+    }
+
+    /// Route a received frame using session state only.
+    ///
+    /// Session, key package, and sync frames are handled immediately. Room
+    /// frames are queued on their room's shard and come back as
+    /// [`FrameRoute::Room`], so the runtime can sequence them without holding
+    /// the driver. A frame for a room whose queue is full is refused with an
+    /// `Error` to the sender.
+    ///
+    /// # Errors
+    ///
+    /// - `ServerError::SessionNotFound` if the session is unknown
+    /// - `ServerError::ConnectionFailed` if the connection rejects the frame
+    /// - `ServerError::Room` / `Storage` if room reload or creation fails
+    pub fn route_frame(
+        &mut self,
+        session_id: u64,
+        frame: Frame,
+    ) -> Result<FrameRoute<E::Instant>, ServerError> {
+        let (room_id, request_id) = (frame.header.room_id(), frame.header.request_id());
+        let route = match self.dispatch_frame(session_id, frame)? {
+            Dispatch::Handled(actions) => FrameRoute::Handled(actions),
+            Dispatch::Room { frame, mut actions } => {
+                if let Err(e) = self.rooms.enqueue(session_id, frame) {
+                    actions.extend(self.make_error_response(session_id, room_id, &e.into()));
+                    FrameRoute::Handled(actions)
+                } else {
+                    FrameRoute::Room { room_id, actions }
+                }
+            },
+        };
+        Ok(match route {
+            FrameRoute::Handled(actions) => FrameRoute::Handled(
+                self.audited(answering(session_id, room_id, request_id, actions)),
+            ),
+            FrameRoute::Room { room_id, actions } => FrameRoute::Room {
+                room_id,
+                actions: self.audited(answering(session_id, room_id, request_id, actions)),
+            },
+        })
+    }
+
+    /// Body of [`Self::route_frame`], before room frames are queued and error
+    /// frames are audited.
+    #[allow(clippy::too_many_lines)]
+    fn dispatch_frame(
+        &mut self,
+        session_id: u64,
+        frame: Frame,
+    ) -> Result<Dispatch<E::Instant>, ServerError> {
         let now = self.env.now();
         let mut actions = Vec::new();
 
@@ -233,7 +681,22 @@ where
 
         match opcode {
             Some(Opcode::Hello | Opcode::Ping | Opcode::Pong | Opcode::Goodbye) => {
-                // Session-layer frames
+                // Session-layer frames. A Hello is answered with the sender's
+                // unread counts, which are cleared once the reply is built
+                let hello = if opcode == Some(Opcode::Hello)
+                    && let Ok(Payload::Hello(hello)) = Payload::from_frame(&frame)
+                {
+                    Some(hello)
+                } else {
+                    None
+                };
+                if let Some(hello) = &hello
+                    && conn.state() == ConnectionState::Init
+                    && let Some(user_id) = hello.sender_id.or_else(|| conn.session_id())
+                {
+                    conn.set_unread(self.unread.counts(user_id));
+                }
+
                 let conn_actions = conn.handle_frame(&frame, now).map_err(|e| {
                     ServerError::ConnectionFailed { session_id, reason: e.to_string() }
                 })?;
@@ -253,8 +716,13 @@ where
                     // Update session with authenticated user_id for reverse lookup
                     let user_id = conn.client_sender_id().or_else(|| conn.session_id());
                     if let Some(user_id) = user_id {
-                        let new_info = SessionInfo::authenticated(user_id);
+                        let capabilities = hello.as_ref().map_or(Capabilities::NONE, |hello| {
+                            self.authenticator.authenticate(hello)
+                        });
+                        let new_info = SessionInfo::authenticated_with(user_id, capabilities);
                         self.registry.update_session_info(session_id, new_info);
+                        self.unread.clear(user_id);
+                        actions.extend(self.deliver_pending(session_id, user_id));
                     }
                 }
             },
@@ -264,6 +732,36 @@ where
                 actions.extend(sync_actions);
             },
 
+            Some(Opcode::RoomListRequest) => {
+                conn.update_activity(now);
+                actions.extend(self.handle_room_list_request(session_id));
+            },
+
+            Some(Opcode::Admin) => {
+                conn.update_activity(now);
+                actions.extend(self.handle_admin_request(session_id, &frame));
+            },
+
+            Some(Opcode::RoomSearch) => {
+                conn.update_activity(now);
+                actions.extend(self.handle_room_search(session_id, &frame));
+            },
+
+            Some(Opcode::CreateRoom) => {
+                conn.update_activity(now);
+                actions.extend(self.handle_create_room(session_id, &frame));
+            },
+
+            Some(Opcode::DirectorySubscribe) => {
+                conn.update_activity(now);
+                self.registry.subscribe_directory(session_id);
+                actions.push(ServerAction::Log {
+                    level: LogLevel::Debug,
+                    message: format!("session {session_id} subscribed to the room directory"),
+                    timestamp: now,
+                });
+            },
+
             Some(Opcode::KeyPackagePublish) => {
                 conn.update_activity(now);
                 let publish_actions = self.handle_key_package_publish(session_id, &frame);
@@ -293,6 +791,31 @@ where
                 let recipient_id = frame.header.recipient_id();
                 conn.update_activity(now);
 
+                // Only members of an existing room may welcome someone into
+                // it, held to the same cap and admin policy as the commit
+                self.reload_room(room_id)?;
+                let sender_id = self.session_user_id(session_id);
+                match self.rooms.with_room(room_id, |rooms| {
+                    rooms.check_welcome(room_id, sender_id, recipient_id, &self.storage)
+                }) {
+                    Ok(()) => {},
+                    Err(
+                        e @ (RoomError::RoomNotFound(_)
+                        | RoomError::RoomFull { .. }
+                        | RoomError::Forbidden { .. }),
+                    ) => {
+                        let error = e.into();
+                        return Ok(Dispatch::Handled(
+                            self.make_error_response(session_id, room_id, &error),
+                        ));
+                    },
+                    Err(e) => return Err(e.into()),
+                }
+
+                // Membership is not written here: the recipient only joins
+                // the group if the paired commit is sequenced, and
+                // persisting that commit stages the add
+
                 if let Some(recipient_session_id) = self.registry.session_id_for_user(recipient_id)
                 {
                     self.registry.subscribe(recipient_session_id, room_id);
@@ -311,33 +834,39 @@ where
                         frame,
                     });
                 } else {
-                    actions.push(ServerAction::Log {
-                        level: LogLevel::Warn,
-                        message: format!(
-                            "Welcome recipient {recipient_id} not connected, cannot subscribe to room {room_id:032x}"
-                        ),
-                        timestamp: now,
-                    });
+                    actions.push(self.queue_for_offline(recipient_id, &frame));
                 }
             },
 
             Some(Opcode::AppMessage) => {
                 conn.update_activity(now);
-                let room_actions = self.room_manager.process_frame(frame, now, &self.storage)?;
-
-                for room_action in room_actions {
-                    actions.extend(self.process_room_action(room_action, session_id));
+                if let Some(error) = self.sender_rejection(session_id, &frame) {
+                    let room_id = frame.header.room_id();
+                    actions.extend(self.error_response(session_id, room_id, error));
+                    return Ok(Dispatch::Handled(actions));
                 }
+                return Ok(Dispatch::Room { frame, actions });
             },
 
             _ => {
                 // Room-level frames
                 conn.update_activity(now);
                 let room_id = frame.header.room_id();
+                if let Some(error) = self.sender_rejection(session_id, &frame) {
+                    actions.extend(self.error_response(session_id, room_id, error));
+                    return Ok(Dispatch::Handled(actions));
+                }
+
+                self.reload_room(room_id)?;
 
                 let is_commit =
                     opcode == Some(Opcode::Commit) || opcode == Some(Opcode::ExternalCommit);
-                if is_commit && !self.room_manager.has_room(room_id) {
+                if is_commit && !self.rooms.has_room(room_id) {
+                    if let Some(error) = self.room_creation_rejection(session_id) {
+                        actions.extend(self.error_response(session_id, room_id, error));
+                        return Ok(Dispatch::Handled(actions));
+                    }
+
                     // GroupInfo publish should create the room, but this is a fallback
                     let create_actions = self.create_room(room_id, session_id)?;
                     actions.extend(create_actions);
@@ -354,15 +883,157 @@ where
                     });
                 }
 
-                let room_actions = self.room_manager.process_frame(frame, now, &self.storage)?;
+                return Ok(Dispatch::Room { frame, actions });
+            },
+        }
 
-                for room_action in room_actions {
-                    actions.extend(self.process_room_action(room_action, session_id));
-                }
+        Ok(Dispatch::Handled(actions))
+    }
+
+    /// Convert the outcome of sequencing a queued frame into server actions
+    /// (broadcast recipients, rejections, persistence).
+    ///
+    /// A frame that failed to sequence is answered with an `Error` to the
+    /// session that submitted it.
+    pub fn finish_room_frame(
+        &mut self,
+        processed: ProcessedFrame<E::Instant>,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let ProcessedFrame { room_id, session_id, request_id, result } = processed;
+        let actions = match result {
+            Ok(room_actions) => self.finish_room_actions(session_id, room_actions),
+            Err(e) => self.make_error_response(session_id, room_id, &e.into()),
+        };
+        self.audited(answering(session_id, room_id, request_id, actions))
+    }
+
+    /// Body of [`Self::finish_room_frame`] for a frame that sequenced.
+    fn finish_room_actions(
+        &mut self,
+        session_id: u64,
+        room_actions: Vec<RoomAction<E::Instant>>,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let mut renamed_rooms = Vec::new();
+        for room_action in &room_actions {
+            if let RoomAction::PersistFrame { room_id, frame, .. } = room_action
+                && frame.header.opcode_enum() == Some(Opcode::RoomMeta)
+            {
+                renamed_rooms.push(*room_id);
+            }
+        }
+
+        let mut actions = Vec::new();
+        for room_action in room_actions {
+            let RoomAction::PersistFrame { room_id, log_index, frame, checkpoint, .. } =
+                room_action
+            else {
+                actions.extend(self.process_room_action(room_action, session_id));
+                continue;
+            };
+            match self.persist_frame(room_id, log_index, &frame, checkpoint) {
+                Ok(persist_actions) => {
+                    if frame.header.opcode_enum() == Some(Opcode::AppMessage) {
+                        self.record_unread(room_id);
+                    }
+                    actions.extend(persist_actions);
+                },
+                // A frame that was never stored must not be broadcast either
+                Err(e) => {
+                    actions.push(self.persist_failure(&e));
+                    break;
+                },
+            }
+        }
+        for room_id in renamed_rooms {
+            self.reindex_public_room(room_id);
+        }
+        actions
+    }
+
+    /// Count a stored message in `room_id` as unread for every member without
+    /// a session in the room.
+    ///
+    /// Members come from the room's in-memory roster, so counting costs no
+    /// storage read.
+    fn record_unread(&mut self, room_id: u128) {
+        let present: HashSet<u64> = self
+            .registry
+            .sessions_in_room(room_id)
+            .filter_map(|session_id| self.registry.sessions(session_id)?.user_id)
+            .collect();
+        let absent: Vec<u64> = self.rooms.with_room(room_id, |rooms| {
+            rooms.members(room_id).map_or_else(Vec::new, |members| {
+                members.iter().copied().filter(|m| !present.contains(m)).collect()
+            })
+        });
+        for member in absent {
+            self.unread.record(member, room_id);
+        }
+    }
+
+    /// Buffer an audit entry for every `Error` frame in `actions`.
+    ///
+    /// Nothing is written here: [`Self::flush_audit`] writes the buffer on
+    /// the next tick, so provoking errors never costs a storage write per
+    /// request.
+    fn audited(&mut self, actions: Vec<ServerAction<E::Instant>>) -> Vec<ServerAction<E::Instant>> {
+        let at_millis = self.env.wall_clock();
+        let entries = actions.iter().filter_map(|action| match action {
+            ServerAction::SendToSession { session_id, frame }
+                if frame.header.opcode_enum() == Some(Opcode::Error) =>
+            {
+                let Ok(Payload::Error(error)) = Payload::from_frame(frame) else {
+                    return None;
+                };
+                Some(AuditEntry {
+                    code: error.code,
+                    session_id: *session_id,
+                    room_id: frame.header.room_id(),
+                    reason: error.message,
+                    at_millis,
+                })
             },
+            _ => None,
+        });
+
+        for entry in entries {
+            if self.pending_audit.len() < MAX_PENDING_AUDIT {
+                self.pending_audit.push(entry);
+            } else {
+                self.dropped_audit += 1;
+            }
         }
 
-        Ok(actions)
+        actions
+    }
+
+    /// Write buffered audit entries to storage in one append.
+    ///
+    /// Auditing is best effort: a failed append or a full buffer is reported
+    /// as a log action and the entries are lost.
+    fn flush_audit(&mut self) -> Vec<ServerAction<E::Instant>> {
+        let mut actions = Vec::new();
+        let dropped = std::mem::take(&mut self.dropped_audit);
+        if dropped > 0 {
+            actions.push(ServerAction::Log {
+                level: LogLevel::Warn,
+                message: format!("audit buffer full, dropped {dropped} entries"),
+                timestamp: self.env.now(),
+            });
+        }
+
+        let entries = std::mem::take(&mut self.pending_audit);
+        if entries.is_empty() {
+            return actions;
+        }
+        if let Err(e) = self.storage.append_audit(&entries) {
+            actions.push(ServerAction::Log {
+                level: LogLevel::Error,
+                message: format!("failed to audit {} errors: {e}", entries.len()),
+                timestamp: self.env.now(),
+            });
+        }
+        actions
     }
 
     /// Handle a sync request from a client.
@@ -376,21 +1047,42 @@ where
 
         let result = (|| -> Result<Vec<ServerAction<E::Instant>>, ServerError> {
             let payload = Payload::from_frame(&frame.clone())?;
-            let (from_log_index, limit) = match payload {
-                Payload::SyncRequest(req) => (req.from_log_index, req.limit as usize),
-                _ => {
-                    return Err(ServerError::Protocol("expected SyncRequest payload".to_string()));
+            let Payload::SyncRequest(req) = payload else {
+                return Err(ServerError::Protocol("expected SyncRequest payload".to_string()));
+            };
+            // An empty page with `has_more` would have the client page forever
+            let limit = usize::try_from(req.limit)
+                .unwrap_or(usize::MAX)
+                .min(self.config.max_sync_frames)
+                .max(1);
+
+            // Timestamp requests resolve to the first matching frame, or past
+            // the end of the log if every frame is older
+            let from_log_index = match req.from_timestamp {
+                Some(timestamp) => match self.storage.index_at_or_after(room_id, timestamp)? {
+                    Some(index) => index,
+                    None => self.storage.latest_log_index(room_id)?.map_or(0, |i| i + 1),
                 },
+                None => req.from_log_index,
             };
 
-            let room_action = self.room_manager.handle_sync_request(
-                room_id,
-                session_id,
-                from_log_index,
-                limit,
-                now,
-                &self.storage,
-            )?;
+            self.reload_room(room_id)?;
+
+            let mut room_action = self.rooms.with_room(room_id, |rooms| {
+                rooms.handle_sync_request(
+                    room_id,
+                    session_id,
+                    from_log_index,
+                    limit,
+                    now,
+                    self.env.wall_clock(),
+                    &self.storage,
+                )
+            })?;
+
+            if let RoomAction::SendSyncResponse { frames, has_more, .. } = &mut room_action {
+                cap_sync_frames(frames, has_more, self.config.max_sync_bytes);
+            }
 
             Ok(self.process_room_action(room_action, session_id))
         })();
@@ -401,23 +1093,318 @@ where
         }
     }
 
-    fn make_error_response(
-        &self,
-        session_id: u64,
-        room_id: u128,
-        error: &ServerError,
-    ) -> Vec<ServerAction<E::Instant>> {
-        let error_payload = match error {
-            ServerError::Room(room_err) => match room_err {
-                RoomError::RoomNotFound(_) => ErrorPayload::room_not_found(room_id),
-                RoomError::Storage(e) => ErrorPayload::storage_error(e.to_string()),
+    /// Rooms where `user_id` is a persisted member.
+    ///
+    /// Storage errors are treated as no rooms; live subscriptions still
+    /// answer the request.
+    fn persisted_rooms_for_user(&self, user_id: u64) -> Vec<u128> {
+        self.storage.member_rooms(user_id).unwrap_or_default()
+    }
+
+    /// User `session_id` acts as: its authenticated user ID, or the session
+    /// ID itself before Hello.
+    fn session_user_id(&self, session_id: u64) -> u64 {
+        self.registry.sessions(session_id).and_then(|info| info.user_id).unwrap_or(session_id)
+    }
+
+    /// Error for a room frame whose header names a sender other than the
+    /// user `session_id` acts as, or `None`.
+    ///
+    /// Room checks (admin rights, kicks, commit membership) trust the header
+    /// `sender_id`, so it has to be pinned to the session before routing.
+    fn sender_rejection(&self, session_id: u64, frame: &Frame) -> Option<ErrorPayload> {
+        let user_id = self.session_user_id(session_id);
+        let sender_id = frame.header.sender_id();
+        (sender_id != user_id).then(|| {
+            ErrorPayload::forbidden(format!(
+                "session {session_id} may not send as user {sender_id}"
+            ))
+        })
+    }
+
+    /// Handle a request for the authenticated user's joined rooms.
+    fn handle_room_list_request(&self, session_id: u64) -> Vec<ServerAction<E::Instant>> {
+        let now = self.env.now();
+
+        let user_id = self
+            .registry
+            .sessions(session_id)
+            .filter(|info| info.authenticated)
+            .and_then(|info| info.user_id);
+
+        let (payload, log) = match user_id {
+            Some(user_id) => {
+                let mut rooms = self.registry.rooms_for_user(user_id);
+                rooms.extend(self.persisted_rooms_for_user(user_id));
+                let room_ids: Vec<u128> = rooms.into_iter().collect();
+                let message = format!("room list for user {user_id}: {} rooms", room_ids.len());
+                (
+                    Payload::RoomListResponse(RoomListResponse { room_ids }),
+                    (LogLevel::Debug, message),
+                )
+            },
+            None => (
+                Payload::Error(ErrorPayload::unauthenticated("room list requires authentication")),
+                (LogLevel::Warn, format!("unauthenticated room list request from {session_id}")),
+            ),
+        };
+
+        let header = FrameHeader::new(payload.opcode());
+        match payload.into_frame(header) {
+            Ok(frame) => {
+                vec![ServerAction::SendToSession { session_id, frame }, ServerAction::Log {
+                    level: log.0,
+                    message: log.1,
+                    timestamp: now,
+                }]
+            },
+            Err(e) => vec![ServerAction::Log {
+                level: LogLevel::Error,
+                message: format!("failed to encode room list response: {e}"),
+                timestamp: now,
+            }],
+        }
+    }
+
+    /// Handle a search of the room directory.
+    ///
+    /// Matches against the in-memory index of public rooms, so searches never
+    /// touch storage. Queries longer than [`MAX_ROOM_SEARCH_QUERY_LEN`] are
+    /// refused, and each user may search `room_search_limit` times per
+    /// `room_search_window`.
+    fn handle_room_search(
+        &mut self,
+        session_id: u64,
+        frame: &Frame,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let now = self.env.now();
+
+        let query = match Payload::from_frame(frame) {
+            Ok(Payload::RoomSearch(search)) => search.query,
+            Ok(_) | Err(_) => {
+                return vec![ServerAction::Log {
+                    level: LogLevel::Warn,
+                    message: format!("invalid RoomSearch from session {session_id}"),
+                    timestamp: now,
+                }];
+            },
+        };
+        if query.len() > MAX_ROOM_SEARCH_QUERY_LEN {
+            let error = ErrorPayload::invalid_payload(format!(
+                "search query longer than {MAX_ROOM_SEARCH_QUERY_LEN} bytes"
+            ));
+            return self.error_response(session_id, 0, error);
+        }
+
+        let user_id = self.session_user_id(session_id);
+        if let Err(retry_after) = self.room_searches.check(user_id, now) {
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            return self.error_response(session_id, 0, ErrorPayload::rate_limited(secs));
+        }
+
+        let query = query.to_lowercase();
+        let rooms: Vec<RoomAnnouncement> = self
+            .public_rooms
+            .values()
+            .filter(|room| {
+                room.name.to_lowercase().contains(&query)
+                    || room.topic.to_lowercase().contains(&query)
+            })
+            .take(MAX_ROOM_SEARCH_RESULTS)
+            .cloned()
+            .collect();
+
+        let message = format!("room search from session {session_id}: {} matches", rooms.len());
+        match Payload::RoomSearchResponse(RoomSearchResponse { rooms })
+            .into_frame(FrameHeader::new(Opcode::RoomSearchResponse))
+        {
+            Ok(frame) => {
+                vec![ServerAction::SendToSession { session_id, frame }, ServerAction::Log {
+                    level: LogLevel::Debug,
+                    message,
+                    timestamp: now,
+                }]
+            },
+            Err(e) => vec![ServerAction::Log {
+                level: LogLevel::Error,
+                message: format!("failed to encode room search response: {e}"),
+                timestamp: now,
+            }],
+        }
+    }
+
+    /// Handle a client's request to create a room.
+    ///
+    /// The session must pass [`Self::room_creation_rejection`]. The room is
+    /// stored with the requested name, topic, admins and retention, and the
+    /// session is subscribed to it.
+    fn handle_create_room(
+        &mut self,
+        session_id: u64,
+        frame: &Frame,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let room_id = frame.header.room_id();
+
+        let request = match Payload::from_frame(frame) {
+            Ok(Payload::CreateRoom(request)) => request,
+            Ok(_) => {
+                let error = ServerError::Protocol("expected CreateRoom payload".to_string());
+                return self.make_error_response(session_id, room_id, &error);
+            },
+            Err(e) => return self.make_error_response(session_id, room_id, &e.into()),
+        };
+
+        if let Some(error) = self.room_creation_rejection(session_id) {
+            return self.error_response(session_id, room_id, error);
+        }
+
+        let settings = RoomSettings {
+            public: request.public,
+            listing: RoomListing { name: request.name, topic: request.topic },
+            admins: request.admins.into_iter().collect(),
+            retention: RetentionPolicy {
+                max_age: request.max_age_secs.map(Duration::from_secs),
+                max_frames: request.max_frames,
+            },
+        };
+        let created = self
+            .reload_room(room_id)
+            .and_then(|()| self.create_room_with_settings(room_id, session_id, settings));
+        match created {
+            Ok(actions) => actions,
+            Err(e) => self.make_error_response(session_id, room_id, &e),
+        }
+    }
+
+    /// Error for a session that may not create a room now, or `None`.
+    ///
+    /// Creating a room needs [`Capabilities::CREATE_ROOM`] and counts against
+    /// the user's `room_creation_limit`, whether the room comes from a
+    /// `CreateRoom` request or a commit to an unknown room.
+    fn room_creation_rejection(&mut self, session_id: u64) -> Option<ErrorPayload> {
+        let user_id = match self.registry.sessions(session_id) {
+            Some(info) if info.capabilities.can_create_room() => info.user_id.unwrap_or(session_id),
+            _ => return Some(ErrorPayload::forbidden("session may not create rooms")),
+        };
+
+        let retry_after = self.room_creations.check(user_id, self.env.now()).err()?;
+        let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        Some(ErrorPayload::rate_limited(secs))
+    }
+
+    /// Handle an operator request.
+    ///
+    /// Only sessions the authenticator granted admin may use this; anyone
+    /// else gets a `FORBIDDEN` error.
+    fn handle_admin_request(
+        &mut self,
+        session_id: u64,
+        frame: &Frame,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let now = self.env.now();
+
+        if !self.registry.sessions(session_id).is_some_and(|info| info.capabilities.is_admin()) {
+            let error = ErrorPayload::forbidden("admin requests require an admin session");
+            return self.error_response(session_id, 0, error);
+        }
+
+        let request = match Payload::from_frame(frame) {
+            Ok(Payload::Admin(AdminMessage::Request(request))) => request,
+            Ok(_) => {
+                let error = ServerError::Protocol("expected AdminRequest payload".to_string());
+                return self.make_error_response(session_id, 0, &error);
+            },
+            Err(e) => return self.make_error_response(session_id, 0, &e.into()),
+        };
+
+        let mut actions = Vec::new();
+        let response = match request {
+            AdminRequest::ListRooms => match self.storage.list_rooms() {
+                Ok(mut room_ids) => {
+                    room_ids.sort_unstable();
+                    AdminResponse::Rooms { room_ids }
+                },
+                Err(e) => return self.make_error_response(session_id, 0, &e.into()),
+            },
+            AdminRequest::EvictRoom { room_id } => {
+                let subscribers: Vec<u64> = self.registry.sessions_in_room(room_id).collect();
+                for subscriber in subscribers {
+                    self.registry.unsubscribe(subscriber, room_id);
+                }
+                let evicted = self.rooms.with_room(room_id, |rooms| rooms.evict_room(room_id));
+                AdminResponse::RoomEvicted { room_id, evicted }
+            },
+            AdminRequest::DropSession { session_id: target } => {
+                let dropped = self.connections.contains_key(&target);
+                if dropped {
+                    let reason = format!("dropped by admin session {session_id}");
+                    actions.extend(self.handle_connection_closed(target, &reason));
+                    actions.push(ServerAction::CloseConnection { session_id: target, reason });
+                }
+                AdminResponse::SessionDropped { session_id: target, dropped }
+            },
+            AdminRequest::SetRoomAdmins { room_id, admins } => {
+                match self
+                    .rooms
+                    .with_room(room_id, |rooms| rooms.set_admins(room_id, admins, &self.storage))
+                {
+                    Ok(admins) => AdminResponse::RoomAdminsSet { room_id, admins },
+                    Err(e) => return self.make_error_response(session_id, room_id, &e.into()),
+                }
+            },
+        };
+
+        let message = format!("admin session {session_id}: {response:?}");
+        match Payload::Admin(AdminMessage::Response(response))
+            .into_frame(FrameHeader::new(Opcode::Admin))
+        {
+            Ok(frame) => {
+                actions.push(ServerAction::SendToSession { session_id, frame });
+                actions.push(ServerAction::Log { level: LogLevel::Info, message, timestamp: now });
+            },
+            Err(e) => actions.push(ServerAction::Log {
+                level: LogLevel::Error,
+                message: format!("failed to encode admin response: {e}"),
+                timestamp: now,
+            }),
+        }
+        actions
+    }
+
+    fn make_error_response(
+        &self,
+        session_id: u64,
+        room_id: u128,
+        error: &ServerError,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let error_payload = match error {
+            ServerError::Room(room_err) => match room_err {
+                RoomError::RoomNotFound(_) => ErrorPayload::room_not_found(room_id),
+                RoomError::Storage(e) => ErrorPayload::storage_error(e.to_string()),
                 RoomError::Sequencing(e) => ErrorPayload::sequencer_error(e.to_string()),
                 RoomError::RoomAlreadyExists(e) => ErrorPayload::frame_rejected(e.to_string()),
+                RoomError::QueueFull(_) => ErrorPayload::frame_rejected(room_err.to_string()),
+                RoomError::RoomFull { room_id, max_members } => {
+                    ErrorPayload::room_full(*room_id, *max_members)
+                },
+                RoomError::Forbidden { .. } => ErrorPayload::forbidden(room_err.to_string()),
+                RoomError::Rejected(error) => error.clone(),
             },
             ServerError::Protocol(msg) => ErrorPayload::invalid_payload(msg),
             _ => ErrorPayload::frame_rejected(error.to_string()),
         };
 
+        self.error_response(session_id, room_id, error_payload)
+    }
+
+    /// Send `error_payload` to `session_id` as an `Error` frame for
+    /// `room_id`.
+    fn error_response(
+        &self,
+        session_id: u64,
+        room_id: u128,
+        error_payload: ErrorPayload,
+    ) -> Vec<ServerAction<E::Instant>> {
         let error_msg = error_payload.message.clone();
         let error = Payload::Error(error_payload);
         match error.into_frame(FrameHeader::new(Opcode::Error)) {
@@ -425,7 +1412,7 @@ where
                 frame.header.set_room_id(room_id);
                 vec![ServerAction::SendToSession { session_id, frame }, ServerAction::Log {
                     level: LogLevel::Warn,
-                    message: format!("sync request failed for {session_id}: {error_msg}"),
+                    message: format!("request from {session_id} failed: {error_msg}"),
                     timestamp: self.env.now(),
                 }]
             },
@@ -437,7 +1424,40 @@ where
         }
     }
 
+    /// Answer undecodable input with an `Error` frame.
+    ///
+    /// Version mismatches get a dedicated code so clients can tell the user
+    /// to upgrade instead of treating it as a corrupt frame.
+    fn handle_frame_decode_failed(
+        &self,
+        session_id: u64,
+        error: &ProtocolError,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let now = self.env.now();
+        let payload = match error {
+            ProtocolError::UnsupportedVersion { got, supported } => {
+                ErrorPayload::unsupported_version(*got, *supported)
+            },
+            _ => ErrorPayload::invalid_payload(error.to_string()),
+        };
+
+        let log = ServerAction::Log {
+            level: LogLevel::Warn,
+            message: format!("undecodable frame from session {session_id}: {error}"),
+            timestamp: now,
+        };
+        match Payload::Error(payload).into_frame(FrameHeader::new(Opcode::Error)) {
+            Ok(frame) => vec![ServerAction::SendToSession { session_id, frame }, log],
+            Err(e) => vec![log, ServerAction::Log {
+                level: LogLevel::Error,
+                message: format!("failed to encode error response: {e}"),
+                timestamp: now,
+            }],
+        }
+    }
+
     /// Handle `KeyPackage` publish request.
+    #[allow(clippy::too_many_lines)]
     fn handle_key_package_publish(
         &self,
         session_id: u64,
@@ -530,9 +1550,15 @@ where
             },
         };
 
-        let store_result = self
-            .key_package_registry
-            .store(user_id, KeyPackageEntry::new(payload.key_package_bytes, payload.hash_ref));
+        let expires_at_secs =
+            match check_key_package(&self.mls_provider, user_id, &payload.key_package_bytes) {
+                Ok(expires_at_secs) => expires_at_secs,
+                Err(error) => return self.error_response(session_id, 0, error),
+            };
+
+        let entry = KeyPackageEntry::new(payload.key_package_bytes, payload.hash_ref)
+            .with_expiry(expires_at_secs);
+        let store_result = self.key_package_registry.store(user_id, entry);
 
         let mut actions = vec![ServerAction::Log {
             level: LogLevel::Info,
@@ -610,7 +1636,9 @@ where
         };
 
         // Fetch (and consume) from registry
-        if let Some(entry) = self.key_package_registry.take(request.user_id) {
+        if let Some(entry) =
+            self.key_package_registry.take(request.user_id, self.env.wall_clock_secs())
+        {
             let response = Payload::KeyPackageFetch(KeyPackageFetchPayload {
                 user_id: request.user_id,
                 key_package_bytes: entry.key_package_bytes,
@@ -689,9 +1717,19 @@ where
             },
         };
 
-        if let Err(e) =
-            self.storage.store_group_info(payload.room_id, payload.epoch, &payload.group_info_bytes)
-        {
+        let group_state = match self.check_group_info(session_id, frame, &payload) {
+            Ok(group_state) => group_state,
+            Err(e) => return self.make_error_response(session_id, frame.header.room_id(), &e),
+        };
+
+        let result = self.storage.batch(|batch| {
+            batch.store_group_info(payload.room_id, payload.epoch, &payload.group_info_bytes)?;
+            match &group_state {
+                Some(state) => batch.store_mls_state(payload.room_id, state),
+                None => Ok(()),
+            }
+        });
+        if let Err(e) = result {
             return vec![ServerAction::Log {
                 level: LogLevel::Error,
                 message: format!(
@@ -701,6 +1739,10 @@ where
                 timestamp: now,
             }];
         }
+        if let Some(state) = group_state {
+            self.rooms
+                .with_room(payload.room_id, |rooms| rooms.set_group_state(payload.room_id, state));
+        }
 
         actions.push(ServerAction::Log {
             level: LogLevel::Debug,
@@ -714,6 +1756,50 @@ where
         actions
     }
 
+    /// Check that `session_id` may publish `payload`, carried by `frame`, and
+    /// return the group state it describes if the room has MLS group state.
+    ///
+    /// The frame must be addressed to the room the `GroupInfo` describes; see
+    /// [`RoomManager::check_group_info`](crate::RoomManager::check_group_info)
+    /// for the rest. In rooms with MLS group state the `GroupInfo` must also
+    /// verify and be for the epoch it claims, since the keys of members added
+    /// since the last commit are taken from it.
+    fn check_group_info(
+        &self,
+        session_id: u64,
+        frame: &Frame,
+        payload: &GroupInfoPayload,
+    ) -> Result<Option<MlsGroupState>, ServerError> {
+        let room_id = frame.header.room_id();
+        if payload.room_id != room_id {
+            let error = ErrorPayload::frame_rejected(format!(
+                "GroupInfo for room {:032x} sent in a frame for room {room_id:032x}",
+                payload.room_id
+            ));
+            return Err(RoomError::Rejected(error).into());
+        }
+
+        let sender_id = self.session_user_id(session_id);
+        self.rooms.with_room(room_id, |rooms| {
+            rooms.check_group_info(room_id, sender_id, payload.epoch, &self.storage)
+        })?;
+
+        if self.storage.load_mls_state(room_id)?.is_none() {
+            return Ok(None);
+        }
+        let state =
+            group_state_from_group_info(&self.mls_provider, room_id, &payload.group_info_bytes)
+                .map_err(|e| RoomError::Rejected(ErrorPayload::mls_error(e.to_string())))?;
+        if state.epoch != payload.epoch {
+            let error = ErrorPayload::mls_error(format!(
+                "GroupInfo for epoch {} published as epoch {}",
+                state.epoch, payload.epoch
+            ));
+            return Err(RoomError::Rejected(error).into());
+        }
+        Ok(Some(state))
+    }
+
     /// Handle `GroupInfo` request (fetch `GroupInfo` for external joiners).
     #[allow(clippy::too_many_lines)] // TODO: we should refactor this
     fn handle_group_info_request(
@@ -803,7 +1889,9 @@ where
             Ok(None) => {
                 let error = Payload::Error(ErrorPayload::room_not_found(request.room_id));
                 match error.into_frame(FrameHeader::new(Opcode::Error)) {
-                    Ok(frame) => {
+                    Ok(mut frame) => {
+                        // Lets the client match the error to its pending join
+                        frame.header.set_room_id(request.room_id);
                         vec![ServerAction::SendToSession { session_id, frame }, ServerAction::Log {
                             level: LogLevel::Debug,
                             message: format!(
@@ -857,8 +1945,20 @@ where
                 ),
                 timestamp: now,
             });
+
+            for room_id in rooms {
+                if self.evict_room_if_empty(room_id) {
+                    actions.push(ServerAction::Log {
+                        level: LogLevel::Debug,
+                        message: format!("evicted empty room {room_id:032x}"),
+                        timestamp: now,
+                    });
+                }
+            }
         }
 
+        debug_assert_eq!(self.registry.verify_consistency(), Ok(()));
+
         actions
     }
 
@@ -886,63 +1986,205 @@ where
             }
         }
 
+        // Expired messages are already withheld from sync; drop their
+        // ciphertext from storage too
+        for (room_id, log_index) in self.rooms.take_expired(self.env.wall_clock()) {
+            match self.storage.prune_frame(room_id, log_index) {
+                // Already deleted by the retention sweep
+                Ok(()) | Err(StorageError::NotFound { .. }) => {},
+                Err(e) => actions.push(ServerAction::Log {
+                    level: LogLevel::Warn,
+                    message: format!(
+                        "failed to prune expired frame {log_index} of room {room_id:032x}: {e}"
+                    ),
+                    timestamp: now,
+                }),
+            }
+        }
+
+        let sweep_due = self
+            .last_retention_sweep
+            .is_none_or(|last| now - last >= self.config.retention_sweep_interval);
+        if sweep_due {
+            self.last_retention_sweep = Some(now);
+            actions.extend(self.sweep_retention(now));
+            self.key_package_registry.prune_expired(self.env.wall_clock_secs());
+        }
+        self.room_creations.prune(now);
+        self.room_searches.prune(now);
+        actions.extend(self.flush_audit());
+
         actions
     }
 
+    /// Prune every room's frames that fall outside its [`RetentionPolicy`].
+    fn sweep_retention(&self, now: E::Instant) -> Vec<ServerAction<E::Instant>> {
+        let room_ids = match self.storage.list_rooms() {
+            Ok(room_ids) => room_ids,
+            Err(e) => {
+                return vec![ServerAction::Log {
+                    level: LogLevel::Warn,
+                    message: format!("retention sweep failed to list rooms: {e}"),
+                    timestamp: now,
+                }];
+            },
+        };
+
+        room_ids
+            .into_iter()
+            .filter_map(|room_id| {
+                let e = self.apply_retention(room_id).err()?;
+                Some(ServerAction::Log {
+                    level: LogLevel::Warn,
+                    message: format!("retention sweep failed for room {room_id:032x}: {e}"),
+                    timestamp: now,
+                })
+            })
+            .collect()
+    }
+
+    /// Prune the frames of `room_id` that fall outside its retention policy.
+    ///
+    /// Age is measured from when the server stored each frame, not from the
+    /// client-set HLC, so the cut always falls after every expired frame.
+    /// The room's current `GroupInfo` is stored as the snapshot that replaces
+    /// them, so clients syncing from before the pruned range can still join.
+    /// Rooms without `GroupInfo` are left alone. The newest frame is always
+    /// kept, so `latest_log_index` and sequencing are unaffected.
+    fn apply_retention(&self, room_id: u128) -> Result<(), StorageError> {
+        let Some(metadata) = self.storage.load_room_metadata(room_id)? else {
+            return Ok(());
+        };
+        let policy = metadata.retention;
+        if policy.is_unbounded() {
+            return Ok(());
+        }
+        let Some(latest) = self.storage.latest_log_index(room_id)? else {
+            return Ok(());
+        };
+
+        let by_count = policy.max_frames.map_or(0, |max| (latest + 1).saturating_sub(max));
+        let by_age = match policy.max_age {
+            Some(max_age) => {
+                let max_age_ms = u64::try_from(max_age.as_millis()).unwrap_or(u64::MAX);
+                let cutoff = self.env.wall_clock().saturating_sub(max_age_ms);
+                self.storage.last_index_stored_before(room_id, cutoff)?.map_or(0, |i| i + 1)
+            },
+            None => 0,
+        };
+        let first_log_index = by_count.max(by_age).min(latest);
+
+        let pruned_before = self.storage.load_snapshot(room_id)?.map_or(0, |s| s.first_log_index);
+        if first_log_index <= pruned_before {
+            return Ok(());
+        }
+        let Some((epoch, group_info)) = self.storage.load_group_info(room_id)? else {
+            return Ok(());
+        };
+
+        self.storage.prune_frames(room_id, &RoomSnapshot { first_log_index, epoch, group_info })
+    }
+
+    /// Persist a sequenced frame.
+    ///
+    /// The frame, its store time, the sequencer state after it, the
+    /// membership changes it carries and the room's updated MLS group state
+    /// are written atomically, so a failure can't leave one without the
+    /// others.
+    ///
+    /// # Errors
+    ///
+    /// On any storage error the room's sequencer, which already counted the
+    /// frame, is reset so the next frame re-initializes it from storage and
+    /// leaves no gap in the log.
+    fn persist_frame(
+        &self,
+        room_id: u128,
+        log_index: u64,
+        frame: &Frame,
+        checkpoint: RoomCheckpoint,
+    ) -> Result<Vec<ServerAction<E::Instant>>, StorageError> {
+        let membership_changes = MembershipChange::from_frame(frame);
+        let group_state = self.rooms.with_room(room_id, |rooms| {
+            rooms.group_state_after(frame, checkpoint.epoch, &membership_changes)
+        });
+        let stored_at = self.env.wall_clock();
+        let result = self.storage.batch(|batch| {
+            batch.store_frame(room_id, log_index, frame)?;
+            batch.store_frame_time(room_id, log_index, stored_at)?;
+            batch.store_room_checkpoint(room_id, checkpoint)?;
+            if let Some(state) = &group_state {
+                batch.store_mls_state(room_id, state)?;
+            }
+            membership_changes.iter().try_for_each(|change| change.stage(room_id, batch))
+        });
+        if let Err(e) = result {
+            self.clear_room_sequencer(room_id);
+            return Err(e);
+        }
+        if !membership_changes.is_empty() || group_state.is_some() {
+            self.rooms.with_room(room_id, |rooms| {
+                rooms.apply_membership(room_id, &membership_changes);
+                if let Some(state) = group_state {
+                    rooms.set_group_state(room_id, state);
+                }
+            });
+        }
+
+        Ok(match self.apply_room_meta(room_id, frame) {
+            Ok(()) => vec![],
+            Err(e) => vec![ServerAction::Log {
+                level: LogLevel::Error,
+                message: format!("failed to update metadata of room {room_id:032x}: {e}"),
+                timestamp: self.env.now(),
+            }],
+        })
+    }
+
+    /// Log entry for a frame [`Self::persist_frame`] failed to store.
+    fn persist_failure(&self, e: &StorageError) -> ServerAction<E::Instant> {
+        ServerAction::Log {
+            level: LogLevel::Error,
+            message: format!("Failed to persist frame: {e}"),
+            timestamp: self.env.now(),
+        }
+    }
+
     /// Convert a `RoomAction` to `ServerActions`.
     fn process_room_action(
-        &mut self,
+        &self,
         room_action: RoomAction<E::Instant>,
         sender_session_id: u64,
     ) -> Vec<ServerAction<E::Instant>> {
         match room_action {
-            RoomAction::Broadcast { room_id, frame, exclude_sender, .. } => {
+            RoomAction::Broadcast { room_id, frame, policy, .. } => {
                 if frame.header.opcode_enum() == Some(Opcode::Welcome) {
                     let recipient_id = frame.header.recipient_id();
                     if let Some(session_id) = self.registry.session_id_for_user(recipient_id) {
                         return vec![ServerAction::SendToSession { session_id, frame }];
                     }
-                    return vec![ServerAction::Log {
-                        level: LogLevel::Warn,
-                        message: format!(
-                            "Welcome recipient {recipient_id} not connected (room {room_id})"
-                        ),
-                        timestamp: self.env.now(),
-                    }];
+                    return vec![self.queue_for_offline(recipient_id, &frame)];
                 }
 
-                let mut session_ids: Vec<u64> = self.sessions_in_room(room_id).collect();
-                if exclude_sender
-                    && let Some(pos) = session_ids.iter().position(|&id| id == sender_session_id)
-                {
-                    session_ids.remove(pos);
-                }
+                let session_ids =
+                    policy.recipients(self.sessions_in_room(room_id), sender_session_id);
 
                 vec![ServerAction::Broadcast { session_ids, frame }]
             },
 
-            RoomAction::PersistFrame { room_id, log_index, frame, .. } => {
-                if let Err(e) = self.storage.store_frame(room_id, log_index, &frame) {
-                    // Sequencer state drifted from storage. Re-initialize
-                    // room state from storage on next frame to sync
-                    if let StorageError::Conflict { .. } = e {
-                        self.clear_room_sequencer(room_id);
-                    }
-
-                    return vec![ServerAction::Log {
-                        level: LogLevel::Error,
-                        message: format!("Failed to persist frame: {e}"),
-                        timestamp: self.env.now(),
-                    }];
-                }
-                vec![]
-            },
+            RoomAction::PersistFrame { room_id, log_index, frame, checkpoint, .. } => self
+                .persist_frame(room_id, log_index, &frame, checkpoint)
+                .unwrap_or_else(|e| vec![self.persist_failure(&e)]),
 
-            RoomAction::Reject { sender_id, reason, processed_at } => {
-                let error = Payload::Error(ErrorPayload::frame_rejected(&reason));
+            RoomAction::Reject { sender_id, reason, code, processed_at } => {
+                let error = Payload::Error(ErrorPayload {
+                    code,
+                    message: reason.clone(),
+                    retry_after: None,
+                });
                 match error.into_frame(FrameHeader::new(Opcode::Error)) {
                     Ok(frame) => vec![
-                        ServerAction::SendToSession { session_id: sender_id, frame },
+                        ServerAction::SendToSession { session_id: sender_session_id, frame },
                         ServerAction::Log {
                             level: LogLevel::Warn,
                             message: format!("rejected frame from {sender_id}: {reason}"),
@@ -957,10 +2199,21 @@ where
                 }
             },
 
-            RoomAction::SendSyncResponse { sender_id, room_id, frames, has_more, .. } => {
+            RoomAction::SendSyncResponse {
+                sender_id, room_id, frames, has_more, snapshot, ..
+            } => {
+                let snapshot = snapshot.map(|s| SyncSnapshot {
+                    first_log_index: s.first_log_index,
+                    epoch: s.epoch,
+                    group_info: s.group_info,
+                });
                 // Server doesn't track epoch - set to 0, clients determine epoch from frames
-                let response =
-                    Payload::SyncResponse(SyncResponse { frames, has_more, server_epoch: 0 });
+                let response = Payload::SyncResponse(SyncResponse {
+                    frames,
+                    has_more,
+                    server_epoch: 0,
+                    snapshot,
+                });
 
                 match response.into_frame(FrameHeader::new(Opcode::SyncResponse)) {
                     Ok(mut frame) => {
@@ -981,13 +2234,39 @@ where
 
     /// Create a new room.
     ///
-    /// The creator is automatically subscribed to the room.
+    /// The creator is automatically subscribed to the room. The room is
+    /// private: it is not announced in the room directory.
     pub fn create_room(
         &mut self,
         room_id: u128,
         creator_session_id: u64,
     ) -> Result<Vec<ServerAction<E::Instant>>, ServerError> {
-        let now = self.env.now();
+        self.create_room_with_settings(room_id, creator_session_id, RoomSettings::default())
+    }
+
+    /// Create a new room and announce it in the room directory.
+    ///
+    /// Like [`Self::create_room`], but every session subscribed to the
+    /// directory is sent a `RoomAnnouncement`, and the room shows up in
+    /// `RoomSearch` results by its name and topic.
+    pub fn create_public_room(
+        &mut self,
+        room_id: u128,
+        creator_session_id: u64,
+        listing: &RoomListing,
+    ) -> Result<Vec<ServerAction<E::Instant>>, ServerError> {
+        let settings =
+            RoomSettings { public: true, listing: listing.clone(), ..RoomSettings::default() };
+        self.create_room_with_settings(room_id, creator_session_id, settings)
+    }
+
+    fn create_room_with_settings(
+        &mut self,
+        room_id: u128,
+        creator_session_id: u64,
+        settings: RoomSettings,
+    ) -> Result<Vec<ServerAction<E::Instant>>, ServerError> {
+        let now = self.env.now();
 
         let info = self
             .registry
@@ -996,14 +2275,92 @@ where
 
         let user_id = info.user_id.unwrap_or(creator_session_id);
 
-        self.room_manager.create_room(room_id, user_id, &self.env, &self.storage)?;
+        let metadata = self.rooms.with_room(room_id, |rooms| {
+            rooms.create_room_with_settings(room_id, user_id, settings, &self.env, &self.storage)
+        })?;
         self.registry.subscribe(creator_session_id, room_id);
 
-        Ok(vec![ServerAction::Log {
+        let mut actions = vec![ServerAction::Log {
             level: LogLevel::Info,
             message: format!("room {room_id:032x} created by session {creator_session_id}"),
             timestamp: now,
-        }])
+        }];
+        if metadata.public {
+            self.index_public_room(room_id, &metadata);
+            actions.extend(self.announce_room(room_id, &metadata));
+        }
+        Ok(actions)
+    }
+
+    /// Apply a sequenced `RoomMeta` frame to the room's stored name and
+    /// topic, so directory searches see them. Other frames are ignored.
+    ///
+    /// The frame itself stays in the log, so members that sync learn the
+    /// change from it rather than from stored metadata.
+    fn apply_room_meta(&self, room_id: u128, frame: &Frame) -> Result<(), StorageError> {
+        if frame.header.opcode_enum() != Some(Opcode::RoomMeta) {
+            return Ok(());
+        }
+        let Ok(Payload::RoomMeta(meta)) = Payload::from_frame(frame) else {
+            return Ok(());
+        };
+        let Some(mut metadata) = self.storage.load_room_metadata(room_id)? else {
+            return Ok(());
+        };
+
+        if let Some(name) = meta.name {
+            metadata.name = name;
+        }
+        if let Some(topic) = meta.topic {
+            metadata.topic = topic;
+        }
+        self.storage.update_room_metadata(room_id, &metadata)
+    }
+
+    /// Add a public room to the search index, or refresh its entry.
+    fn index_public_room(&mut self, room_id: u128, metadata: &StoredRoomMetadata) {
+        if metadata.public {
+            self.public_rooms.insert(room_id, room_announcement(room_id, metadata));
+        }
+    }
+
+    /// Refresh a room's search index entry from its stored metadata.
+    ///
+    /// Best effort: on a storage error the entry keeps its old name and topic
+    /// until the next change.
+    fn reindex_public_room(&mut self, room_id: u128) {
+        if let Ok(Some(metadata)) = self.storage.load_room_metadata(room_id) {
+            self.index_public_room(room_id, &metadata);
+        }
+    }
+
+    /// Send a `RoomAnnouncement` for a new public room to every directory
+    /// subscriber.
+    fn announce_room(
+        &self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Vec<ServerAction<E::Instant>> {
+        let frame = match Payload::RoomAnnouncement(room_announcement(room_id, metadata))
+            .into_frame(FrameHeader::new(Opcode::RoomAnnouncement))
+        {
+            Ok(mut frame) => {
+                frame.header.set_room_id(room_id);
+                frame
+            },
+            Err(e) => {
+                return vec![ServerAction::Log {
+                    level: LogLevel::Error,
+                    message: format!("failed to encode RoomAnnouncement: {e}"),
+                    timestamp: self.env.now(),
+                }];
+            },
+        };
+
+        self.registry
+            .directory_subscribers()
+            .map(|session_id| ServerAction::SendToSession { session_id, frame: frame.clone() })
+            .collect()
     }
 
     /// Subscribe a session to a room.
@@ -1012,8 +2369,43 @@ where
     }
 
     /// Unsubscribe a session from a room.
+    ///
+    /// Evicts the room from memory if this was its last subscriber.
     pub fn unsubscribe_from_room(&mut self, session_id: u64, room_id: u128) -> bool {
-        self.registry.unsubscribe(session_id, room_id)
+        let unsubscribed = self.registry.unsubscribe(session_id, room_id);
+        if unsubscribed {
+            self.evict_room_if_empty(room_id);
+        }
+        unsubscribed
+    }
+
+    /// Evict a room's in-memory state once no sessions are subscribed.
+    ///
+    /// Storage is left intact; the room is reloaded on its next frame.
+    /// Returns `true` if the room was evicted.
+    fn evict_room_if_empty(&mut self, room_id: u128) -> bool {
+        if self.registry.sessions_in_room(room_id).next().is_some() {
+            return false;
+        }
+        self.rooms.with_room(room_id, |rooms| rooms.evict_room(room_id))
+    }
+
+    /// Reload an evicted room from storage if it isn't in memory.
+    ///
+    /// [`RoomManager::process_frame`] reloads on its own; this is for paths
+    /// that check room existence first (sync, commit auto-creation). Rooms
+    /// that were never persisted are left absent.
+    fn reload_room(&mut self, room_id: u128) -> Result<(), ServerError> {
+        let result = self.rooms.with_room(room_id, |rooms| {
+            if rooms.has_room(room_id) {
+                return Ok(());
+            }
+            rooms.recover_room(room_id, &self.storage)
+        });
+        match result {
+            Ok(()) | Err(RoomError::RoomNotFound(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
     }
 
     /// All sessions subscribed to a room.
@@ -1021,6 +2413,35 @@ where
         self.registry.sessions_in_room(room_id)
     }
 
+    /// Number of sessions subscribed to a room.
+    pub fn session_count(&self, room_id: u128) -> usize {
+        self.registry.room_session_count(room_id)
+    }
+
+    /// Say `Goodbye` to every connected session before the server stops.
+    ///
+    /// Returns a `Goodbye` send followed by `CloseConnection` for each
+    /// session. Connections stay registered until the runtime reports them
+    /// closed. Buffered audit entries are flushed; nothing else needs
+    /// persisting, since each room's sequencer checkpoint is written with
+    /// every frame it appends.
+    pub fn shutdown(&mut self, reason: &str) -> Vec<ServerAction<E::Instant>> {
+        let mut actions = self.flush_audit();
+        for (&session_id, conn) in &mut self.connections {
+            for action in conn.initiate_goodbye(reason) {
+                actions.push(match action {
+                    ConnectionAction::SendFrame(frame) => {
+                        ServerAction::SendToSession { session_id, frame }
+                    },
+                    ConnectionAction::Close { reason } => {
+                        ServerAction::CloseConnection { session_id, reason }
+                    },
+                });
+            }
+        }
+        actions
+    }
+
     /// Number of active connections.
     pub fn connection_count(&self) -> usize {
         self.connections.len()
@@ -1028,7 +2449,7 @@ where
 
     /// Room exists and is initialized.
     pub fn has_room(&self, room_id: u128) -> bool {
-        self.room_manager.has_room(room_id)
+        self.rooms.has_room(room_id)
     }
 
     /// Current MLS epoch for a room.
@@ -1039,6 +2460,28 @@ where
         None
     }
 
+    /// Replace the retention policy of an existing room.
+    ///
+    /// Takes effect at the next retention sweep. Rooms that were never
+    /// created are left alone.
+    ///
+    /// # Errors
+    ///
+    /// - `ServerError::Storage` if the room's metadata cannot be read or
+    ///   written
+    pub fn set_retention_policy(
+        &self,
+        room_id: u128,
+        retention: RetentionPolicy,
+    ) -> Result<(), ServerError> {
+        let Some(mut metadata) = self.storage.load_room_metadata(room_id)? else {
+            return Ok(());
+        };
+        metadata.retention = retention;
+        self.storage.update_room_metadata(room_id, &metadata)?;
+        Ok(())
+    }
+
     /// Storage backend for frame/state persistence.
     pub fn storage(&self) -> &S {
         &self.storage
@@ -1048,8 +2491,8 @@ where
     ///
     /// This is useful when we detect a log index conflict and need to
     /// re-initialize the sequencer from storage.
-    pub fn clear_room_sequencer(&mut self, room_id: u128) -> bool {
-        self.room_manager.clear_room_sequencer(room_id)
+    pub fn clear_room_sequencer(&self, room_id: u128) -> bool {
+        self.rooms.with_room(room_id, |rooms| rooms.clear_room_sequencer(room_id))
     }
 
     /// Recover all room state from storage.
@@ -1058,30 +2501,108 @@ where
     /// before a restart. After this call, the server is ready to accept
     /// connections for all previously active rooms.
     ///
+    /// Each room's sequencer resumes from its latest stored index, with the
+    /// epoch from the checkpoint persisted alongside that frame (see
+    /// [`Storage::load_room_checkpoint`]), so recovery never reuses or skips
+    /// an index. Public rooms are added to the `RoomSearch` index.
+    ///
     /// # Errors
     ///
     /// - `ServerError::Storage` if storage enumeration fails
     /// - `ServerError::Room` if room recovery fails
     pub fn recover_from_storage(&mut self) -> Result<usize, ServerError> {
         let room_ids = self.storage.list_rooms()?;
-
         let room_count = room_ids.len();
 
-        tracing::info!(room_count, "Recovering rooms from storage");
-
         for room_id in room_ids {
-            self.room_manager.recover_room(room_id, &self.storage)?;
+            self.rooms.with_room(room_id, |rooms| rooms.recover_room(room_id, &self.storage))?;
+            if let Some(metadata) = self.storage.load_room_metadata(room_id)? {
+                self.index_public_room(room_id, &metadata);
+            }
         }
 
-        tracing::info!(room_count, "Room recovery complete");
-
         Ok(room_count)
     }
 
-    /// Access the room manager (for testing).
-    #[cfg(test)]
-    pub fn room_manager(&self) -> &RoomManager {
-        &self.room_manager
+    /// Export a room's full persisted state.
+    ///
+    /// Reads only from storage, so the room does not need to be in memory.
+    /// The room is left in place; operators remove it once the import on the
+    /// target server succeeds.
+    ///
+    /// # Errors
+    ///
+    /// - `ServerError::Room` if the room has no stored metadata
+    /// - `ServerError::Storage` if a storage read fails
+    pub fn export_room(&self, room_id: u128) -> Result<RoomExport, ServerError> {
+        let metadata =
+            self.storage.load_room_metadata(room_id)?.ok_or(RoomError::RoomNotFound(room_id))?;
+
+        let frames = match self.storage.latest_log_index(room_id)? {
+            Some(latest) => {
+                let count = usize::try_from(latest.saturating_add(1)).unwrap_or(usize::MAX);
+                self.storage.load_frames(room_id, 0, count)?
+            },
+            None => Vec::new(),
+        };
+
+        Ok(RoomExport {
+            room_id,
+            metadata,
+            frames,
+            mls_state: self.storage.load_mls_state(room_id)?,
+            group_info: self.storage.load_group_info(room_id)?,
+            members: self.storage.members(room_id)?,
+        })
+    }
+
+    /// Import a room exported from another server.
+    ///
+    /// The room, its frames, MLS state, `GroupInfo` and membership are written
+    /// in one batch, so a failed import leaves nothing behind. The room is
+    /// then loaded into memory and ready to serve.
+    ///
+    /// # Errors
+    ///
+    /// - `ServerError::Room` if the room already exists on this server
+    /// - `ServerError::Storage` if a storage write fails
+    pub fn import_room(&mut self, export: &RoomExport) -> Result<(), ServerError> {
+        let room_id = export.room_id;
+        if self.storage.load_room_metadata(room_id)?.is_some() {
+            return Err(RoomError::RoomAlreadyExists(room_id).into());
+        }
+
+        // Exports carry no store times, so retention counts from the import
+        let stored_at = self.env.wall_clock();
+        self.storage.batch(|batch| {
+            batch.create_room(room_id, &export.metadata)?;
+            for (log_index, frame) in (0u64..).zip(&export.frames) {
+                batch.store_frame(room_id, log_index, frame)?;
+                batch.store_frame_time(room_id, log_index, stored_at)?;
+            }
+            if let Some(state) = &export.mls_state {
+                batch.store_mls_state(room_id, state)?;
+            }
+            if let Some((epoch, group_info)) = &export.group_info {
+                batch.store_group_info(room_id, *epoch, group_info)?;
+            }
+            for &user_id in &export.members {
+                batch.add_member(room_id, user_id)?;
+            }
+            Ok(())
+        })?;
+
+        self.rooms.with_room(room_id, |rooms| rooms.recover_room(room_id, &self.storage))?;
+        self.index_public_room(room_id, &export.metadata);
+        Ok(())
+    }
+
+    /// Shared handle to the room shards.
+    ///
+    /// Runtimes use this to sequence [`FrameRoute::Room`] frames without
+    /// holding the driver.
+    pub fn rooms(&self) -> RoomShards {
+        self.rooms.clone()
     }
 }
 
@@ -1095,18 +2616,115 @@ where
         f.debug_struct("ServerDriver")
             .field("connection_count", &self.connections.len())
             .field("session_count", &self.registry.session_count())
+            .field("unread_users", &self.unread.user_count())
+            .field("room_creators", &self.room_creations.user_count())
             .finish()
     }
 }
 
+/// Tie the `Error` frames `actions` send back to `session_id` to the request
+/// that caused them.
+///
+/// Each gets the request's `request_id`, and its room unless it already names
+/// one, so a client can tell which of its frames was refused.
+fn answering<I>(
+    session_id: u64,
+    room_id: u128,
+    request_id: u32,
+    mut actions: Vec<ServerAction<I>>,
+) -> Vec<ServerAction<I>> {
+    for action in &mut actions {
+        if let ServerAction::SendToSession { session_id: to, frame } = action
+            && *to == session_id
+            && frame.header.opcode_enum() == Some(Opcode::Error)
+        {
+            frame.header.set_request_id(request_id);
+            if frame.header.room_id() == 0 {
+                frame.header.set_room_id(room_id);
+            }
+        }
+    }
+    actions
+}
+
+/// Drop frames past `max_bytes`, setting `has_more` if any were dropped.
+///
+/// The first frame is always kept so sync makes progress even when one frame
+/// alone exceeds the budget.
+fn cap_sync_frames(frames: &mut Vec<Vec<u8>>, has_more: &mut bool, max_bytes: usize) {
+    let mut total = 0usize;
+    let keep = frames
+        .iter()
+        .take_while(|frame| {
+            total = total.saturating_add(frame.len());
+            total <= max_bytes
+        })
+        .count()
+        .max(1);
+
+    if keep < frames.len() {
+        frames.truncate(keep);
+        *has_more = true;
+    }
+}
+
+/// Directory entry for a public room.
+fn room_announcement(room_id: u128, metadata: &StoredRoomMetadata) -> RoomAnnouncement {
+    RoomAnnouncement {
+        room_id,
+        creator: metadata.creator,
+        created_at_secs: metadata.created_at_secs,
+        name: metadata.name.clone(),
+        topic: metadata.topic.clone(),
+    }
+}
+
+/// Expiry (Unix seconds) of a published `KeyPackage` the server will hand
+/// out.
+///
+/// Forged or expired packages, and packages for someone other than the
+/// publisher, could never be added to a group, so they are refused up front.
+/// Expiry is checked against `provider`'s wall clock.
+///
+/// # Errors
+///
+/// Returns the `Error` payload to send the publisher.
+fn check_key_package<E: Environment>(
+    provider: &MlsProvider<E>,
+    user_id: u64,
+    key_package_bytes: &[u8],
+) -> Result<u64, ErrorPayload> {
+    let info = validate_key_package(provider, key_package_bytes)
+        .map_err(|e| ErrorPayload::mls_error(format!("invalid KeyPackage: {e}")))?;
+    if info.member_id != user_id {
+        return Err(ErrorPayload::forbidden(format!(
+            "KeyPackage is for user {}, not {user_id}",
+            info.member_id
+        )));
+    }
+    Ok(info.expires_at_secs)
+}
+
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
-    use lockframe_core::env::test_utils::MockEnv;
-    use lockframe_proto::FrameHeader;
+    use lockframe_core::{
+        env::test_utils::MockEnv,
+        mls::{MlsAction, MlsGroup},
+    };
+    use lockframe_proto::{
+        FrameHeader,
+        payloads::{
+            moderation::RoomMeta,
+            session::{CreateRoom, DirectorySubscribe, RoomSearch, RoomUnread},
+        },
+    };
 
     use super::*;
-    use crate::storage::MemoryStorage;
+    use crate::{
+        room_manager::{BroadcastPolicy, ROOM_QUEUE_CAPACITY},
+        storage::{ChaoticStorage, MemoryStorage},
+    };
 
     #[test]
     fn server_accepts_connection() {
@@ -1180,6 +2798,130 @@ mod tests {
         assert_eq!(sessions, vec![1]);
     }
 
+    #[test]
+    fn public_room_creation_notifies_directory_subscribers() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        for session_id in 1..=3 {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+        }
+
+        let subscribe = Payload::DirectorySubscribe(DirectorySubscribe {})
+            .into_frame(FrameHeader::new(Opcode::DirectorySubscribe))
+            .unwrap();
+        server
+            .process_event(ServerEvent::FrameReceived { session_id: 2, frame: subscribe })
+            .unwrap();
+
+        let announcements = |actions: &[ServerAction<_>]| -> Vec<(u64, Payload)> {
+            actions
+                .iter()
+                .filter_map(|action| match action {
+                    ServerAction::SendToSession { session_id, frame } => {
+                        Some((*session_id, Payload::from_frame(frame).unwrap()))
+                    },
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let actions = server.create_room(0x100, 1).unwrap();
+        assert!(announcements(&actions).is_empty());
+
+        let actions = server.create_public_room(0x200, 1, &RoomListing::default()).unwrap();
+        let sent = announcements(&actions);
+        assert_eq!(sent.len(), 1);
+        let (session_id, Payload::RoomAnnouncement(announcement)) = &sent[0] else {
+            panic!("expected RoomAnnouncement, got {sent:?}");
+        };
+        assert_eq!(*session_id, 2);
+        assert_eq!(announcement.room_id, 0x200);
+        assert_eq!(announcement.creator, 1);
+    }
+
+    #[test]
+    fn room_search_matches_public_rooms_by_substring() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        // Private rooms never match, whatever their metadata says
+        let hidden =
+            StoredRoomMetadata { creator: 9, name: "Rust internals".into(), ..Default::default() };
+        storage.create_room(0x400, &hidden).unwrap();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+
+        let listing =
+            |name: &str, topic: &str| RoomListing { name: name.into(), topic: topic.into() };
+        server.create_public_room(0x300, 1, &listing("Cooking", "rust-proof pans")).unwrap();
+        server.create_public_room(0x100, 1, &listing("Rustaceans", "systems talk")).unwrap();
+        server.create_public_room(0x200, 1, &listing("Gardening", "plants")).unwrap();
+        server.create_room(0x500, 1).unwrap();
+
+        let mut search = |query: &str| -> Vec<u128> {
+            let frame = Payload::RoomSearch(RoomSearch { query: query.into() })
+                .into_frame(FrameHeader::new(Opcode::RoomSearch))
+                .unwrap();
+            let actions =
+                server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+            let Payload::RoomSearchResponse(response) = sent_payload(&actions) else {
+                panic!("expected RoomSearchResponse");
+            };
+            response.rooms.iter().map(|room| room.room_id).collect()
+        };
+
+        assert_eq!(search("RUST"), vec![0x100, 0x300]);
+        assert_eq!(search("plant"), vec![0x200]);
+        assert_eq!(search(""), vec![0x100, 0x200, 0x300]);
+        assert!(search("internals").is_empty());
+    }
+
+    #[test]
+    fn room_search_indexes_recovered_rooms_and_limits_queries() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let lobby = StoredRoomMetadata {
+            creator: 9,
+            public: true,
+            name: "Lobby".into(),
+            ..Default::default()
+        };
+        storage.create_room(0x100, &lobby).unwrap();
+        let config = ServerConfig { room_search_limit: 2, ..ServerConfig::default() };
+        let window = config.room_search_window;
+        let mut server = ServerDriver::new(env, storage, config);
+        server.recover_from_storage().unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+
+        let mut search = |query: String| {
+            let frame = Payload::RoomSearch(RoomSearch { query })
+                .into_frame(FrameHeader::new(Opcode::RoomSearch))
+                .unwrap();
+            let actions =
+                server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+            sent_payload(&actions)
+        };
+
+        let Payload::RoomSearchResponse(response) = search("lob".into()) else {
+            panic!("expected RoomSearchResponse");
+        };
+        assert_eq!(response.rooms.len(), 1);
+        assert_eq!(response.rooms[0].room_id, 0x100);
+
+        let Payload::Error(error) = search("x".repeat(MAX_ROOM_SEARCH_QUERY_LEN + 1)) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::INVALID_PAYLOAD);
+
+        // Refused queries don't count against the limit
+        assert!(matches!(search("lob".into()), Payload::RoomSearchResponse(_)));
+        let Payload::Error(error) = search("lob".into()) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::RATE_LIMITED);
+        assert_eq!(error.retry_after, Some(window.as_secs()));
+    }
+
     #[test]
     fn server_create_room_fails_for_unknown_session() {
         let env = MockEnv::with_crypto_rng();
@@ -1220,6 +2962,61 @@ mod tests {
         assert_eq!(sessions.len(), 1);
     }
 
+    #[test]
+    fn closing_every_connection_leaves_no_sessions() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, MemoryStorage::new(), ServerConfig::default());
+
+        for session_id in 1..=5 {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            let frame = hello_frame(100 + session_id, None);
+            server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+        }
+        server.create_room(0x100, 1).unwrap();
+        server.create_room(0x200, 2).unwrap();
+        for session_id in 3..=5 {
+            assert!(server.subscribe_to_room(session_id, 0x100));
+        }
+        server.registry.subscribe_directory(5);
+        assert_eq!(server.registry.subscription_count(), 5);
+
+        for session_id in 1..=5 {
+            let reason = "test".to_string();
+            server.process_event(ServerEvent::ConnectionClosed { session_id, reason }).unwrap();
+        }
+
+        assert_eq!(server.connection_count(), 0);
+        assert_eq!(server.registry.session_count(), 0);
+        assert_eq!(server.registry.subscription_count(), 0);
+        assert_eq!(server.registry.verify_consistency(), Ok(()));
+    }
+
+    #[test]
+    fn session_count_follows_subscriptions() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, MemoryStorage::new(), ServerConfig::default());
+        let room_id = 0x100;
+
+        assert_eq!(server.session_count(room_id), 0);
+        for session_id in [1, 2, 3] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+        }
+        server.create_room(room_id, 1).unwrap();
+        assert!(server.subscribe_to_room(2, room_id));
+        assert!(server.subscribe_to_room(3, room_id));
+
+        assert_eq!(server.session_count(room_id), 3);
+        let mut sessions: Vec<u64> = server.sessions_in_room(room_id).collect();
+        sessions.sort_unstable();
+        assert_eq!(sessions, vec![1, 2, 3]);
+
+        for session_id in [1, 2, 3] {
+            assert!(server.unsubscribe_from_room(session_id, room_id));
+        }
+        assert_eq!(server.session_count(room_id), 0);
+        assert_eq!(server.sessions_in_room(room_id).count(), 0);
+    }
+
     #[test]
     fn welcome_frame_subscribes_receiver_to_room() {
         use bytes::Bytes;
@@ -1283,7 +3080,11 @@ mod tests {
         // Pre-populate storage with rooms (explicit ROOMS table + frames)
         for room_id in [100u128, 200, 300] {
             // Create room in ROOMS table
-            let metadata = StoredRoomMetadata { creator: room_id as u64, created_at_secs: 0 };
+            let metadata = StoredRoomMetadata {
+                creator: room_id as u64,
+                created_at_secs: 0,
+                ..Default::default()
+            };
             storage.create_room(room_id, &metadata).unwrap();
 
             // Add frames
@@ -1305,9 +3106,9 @@ mod tests {
         let count = driver.recover_from_storage().unwrap();
 
         assert_eq!(count, 3);
-        assert!(driver.room_manager().has_room(100));
-        assert!(driver.room_manager().has_room(200));
-        assert!(driver.room_manager().has_room(300));
+        assert!(driver.has_room(100));
+        assert!(driver.has_room(200));
+        assert!(driver.has_room(300));
     }
 
     #[test]
@@ -1331,7 +3132,8 @@ mod tests {
         let sender_id = 42u64;
 
         // Create room in ROOMS table
-        let metadata = StoredRoomMetadata { creator: sender_id, created_at_secs: 0 };
+        let metadata =
+            StoredRoomMetadata { creator: sender_id, created_at_secs: 0, ..Default::default() };
         storage.create_room(room_id, &metadata).unwrap();
 
         // Pre-populate storage with 3 frames
@@ -1370,4 +3172,2216 @@ mod tests {
         assert_eq!(stored_frames.len(), 1);
         assert_eq!(stored_frames[0], frame);
     }
+
+    #[test]
+    fn server_driver_recovery_resumes_from_appended_checkpoint() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+        let sender_id = 42u64;
+        let app_frame = |log_index| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(sender_id);
+            header.set_log_index(log_index);
+            Frame::new(header, Bytes::from("msg"))
+        };
+        let connect = |driver: &mut ServerDriver<MockEnv, MemoryStorage>| {
+            driver.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+            driver.registry.update_session_info(1, SessionInfo::authenticated(sender_id));
+        };
+        let send = |driver: &mut ServerDriver<MockEnv, MemoryStorage>, log_index| {
+            let frame = app_frame(log_index);
+            driver.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        };
+
+        let env = MockEnv::with_crypto_rng();
+        let mut driver = ServerDriver::new(env, storage.clone(), ServerConfig::default());
+        connect(&mut driver);
+        driver.create_room(room_id, 1).unwrap();
+        send(&mut driver, 0);
+        send(&mut driver, 1);
+        // Crash without shutdown: the checkpoint went out with the frames
+        drop(driver);
+        let checkpoint = storage.load_room_checkpoint(room_id).unwrap();
+        assert_eq!(checkpoint.map(|c| c.next_log_index), Some(2));
+
+        let env = MockEnv::with_crypto_rng();
+        let mut driver = ServerDriver::new(env, storage.clone(), ServerConfig::default());
+        driver.recover_from_storage().unwrap();
+        connect(&mut driver);
+        driver.subscribe_to_room(1, room_id);
+        send(&mut driver, 2);
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn server_driver_recovery_discards_stale_checkpoint() {
+        use crate::storage::StoredRoomMetadata;
+
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+        let sender_id = 42u64;
+        let metadata =
+            StoredRoomMetadata { creator: sender_id, created_at_secs: 0, ..Default::default() };
+        storage.create_room(room_id, &metadata).unwrap();
+
+        // Checkpoint taken after one frame, then two more stored before the crash
+        let checkpoint = RoomCheckpoint { next_log_index: 1, epoch: None };
+        storage.batch(|batch| batch.store_room_checkpoint(room_id, checkpoint)).unwrap();
+        for i in 0..3 {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(sender_id);
+            header.set_log_index(i);
+            storage.store_frame(room_id, i, &Frame::new(header, Bytes::new())).unwrap();
+        }
+
+        let env = MockEnv::with_crypto_rng();
+        let mut driver = ServerDriver::new(env, storage, ServerConfig::default());
+        driver.recover_from_storage().unwrap();
+        assert_eq!(
+            driver.rooms.with_room(room_id, |rooms| rooms.next_log_index(room_id)),
+            Some(3),
+            "stale checkpoint must not rewind the sequencer"
+        );
+    }
+
+    #[test]
+    fn validate_event_rejects_malformed_commit_without_mutation() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+
+        // Header claims a payload size that doesn't match the actual payload
+        let mut header = FrameHeader::new(Opcode::Commit);
+        header.set_room_id(room_id);
+        header.set_sender_id(1);
+        let mut frame = Frame::new(header, Bytes::from("commit"));
+        frame.header.set_payload_size(999);
+
+        let event = ServerEvent::FrameReceived { session_id: 1, frame };
+        let result = server.validate_event(&event);
+        assert!(
+            matches!(result, Err(ServerError::Room(RoomError::Sequencing(_)))),
+            "expected sequencer validation error, got {result:?}"
+        );
+
+        // Nothing was sequenced or persisted
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), None);
+        assert!(server.has_room(room_id));
+        assert_eq!(server.connection_count(), 1);
+    }
+
+    #[test]
+    fn validate_event_accepts_valid_frame_without_sequencing() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(1);
+        let frame = Frame::new(header, Bytes::from("hello"));
+
+        server.validate_event(&ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), None);
+
+        // Unknown sessions and rooms are reported
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id + 1);
+        header.set_sender_id(1);
+        let frame = Frame::new(header, Bytes::new());
+        assert!(matches!(
+            server.validate_event(&ServerEvent::FrameReceived {
+                session_id: 1,
+                frame: frame.clone()
+            }),
+            Err(ServerError::Room(RoomError::RoomNotFound(_)))
+        ));
+        assert!(matches!(
+            server.validate_event(&ServerEvent::FrameReceived { session_id: 9, frame }),
+            Err(ServerError::SessionNotFound(9))
+        ));
+    }
+
+    #[test]
+    fn validate_event_reloads_evicted_room_and_reports_rejections() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(10));
+        server.registry.update_session_info(2, SessionInfo::authenticated(20));
+        server.create_room(room_id, 1).unwrap();
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(10);
+        let frame = Frame::new(header, Bytes::from("original"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        server.rooms.with_room(room_id, |rooms| rooms.evict_room(room_id));
+        assert!(!server.has_room(room_id));
+
+        let validate = |session_id, frame| {
+            server.validate_event(&ServerEvent::FrameReceived { session_id, frame })
+        };
+        validate(1, edit_frame(room_id, 10, 0)).unwrap();
+        assert!(matches!(
+            validate(2, edit_frame(room_id, 20, 0)),
+            Err(ServerError::Room(RoomError::Rejected(_)))
+        ));
+        assert!(matches!(
+            validate(2, edit_frame(room_id, 10, 0)),
+            Err(ServerError::Room(RoomError::Rejected(ErrorPayload {
+                code: ErrorPayload::FORBIDDEN,
+                ..
+            })))
+        ));
+
+        // Still evicted, and nothing was sequenced
+        assert!(!server.has_room(room_id));
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn last_member_leaving_evicts_room_and_frame_reloads_it() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(42));
+        server.create_room(room_id, 1).unwrap();
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(42);
+        let frame = Frame::new(header, Bytes::from("first"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+
+        server
+            .process_event(ServerEvent::ConnectionClosed {
+                session_id: 1,
+                reason: "client disconnect".to_string(),
+            })
+            .unwrap();
+
+        // Evicted from memory, but still persisted
+        assert!(!server.has_room(room_id));
+        assert!(server.storage().load_room_metadata(room_id).unwrap().is_some());
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), Some(0));
+
+        // A later frame reloads the room and continues the log
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(2, SessionInfo::authenticated(42));
+        server.subscribe_to_room(2, room_id);
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(42);
+        let frame = Frame::new(header, Bytes::from("second"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+
+        assert!(server.has_room(room_id));
+        let stored = server.storage().load_frames(room_id, 1, 1).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].header.log_index(), 1);
+    }
+
+    #[test]
+    fn unsubscribe_evicts_only_when_room_empty() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+        server.subscribe_to_room(2, room_id);
+
+        assert!(server.unsubscribe_from_room(1, room_id));
+        assert!(server.has_room(room_id));
+
+        assert!(server.unsubscribe_from_room(2, room_id));
+        assert!(!server.has_room(room_id));
+    }
+
+    fn broadcast_recipients<I>(actions: &[ServerAction<I>]) -> Vec<u64> {
+        let mut session_ids = actions
+            .iter()
+            .find_map(|action| match action {
+                ServerAction::Broadcast { session_ids, .. } => Some(session_ids.clone()),
+                _ => None,
+            })
+            .unwrap();
+        session_ids.sort_unstable();
+        session_ids
+    }
+
+    #[test]
+    fn room_frames_sequence_outside_driver_in_any_room_order() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let config = ServerConfig { room_shards: 2, ..Default::default() };
+        let mut server = ServerDriver::new(env.clone(), storage.clone(), config);
+        let (room_a, room_b) = (0x10, 0x11);
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.create_room(room_a, 1).unwrap();
+        server.create_room(room_b, 2).unwrap();
+
+        let mut route = |session_id, room_id| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(session_id);
+            let frame = Frame::new(header, Bytes::from("msg"));
+            match server.route_frame(session_id, frame).unwrap() {
+                FrameRoute::Room { room_id: routed, actions } => {
+                    assert_eq!(routed, room_id);
+                    assert!(actions.is_empty());
+                },
+                FrameRoute::Handled(_) => panic!("expected room route"),
+            }
+        };
+        route(1, room_a);
+        route(2, room_b);
+
+        // Sequence both rooms concurrently without touching the driver
+        let rooms = server.rooms();
+        let now = env.now();
+        let (processed_a, processed_b) = std::thread::scope(|scope| {
+            let a = scope.spawn(|| rooms.process_next(room_a, now, &storage).unwrap());
+            let b = scope.spawn(|| rooms.process_next(room_b, now, &storage).unwrap());
+            (a.join().unwrap(), b.join().unwrap())
+        });
+
+        let actions_b = server.finish_room_frame(processed_b);
+        let actions_a = server.finish_room_frame(processed_a);
+        assert_eq!(broadcast_recipients(&actions_a), vec![1]);
+        assert_eq!(broadcast_recipients(&actions_b), vec![2]);
+        assert_eq!(server.storage().latest_log_index(room_a).unwrap(), Some(0));
+        assert_eq!(server.storage().latest_log_index(room_b).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn room_frame_failures_are_reported_to_the_sender() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, MemoryStorage::new(), ServerConfig::default());
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        let room_id = 0x10;
+        server.create_room(room_id, 1).unwrap();
+
+        let app_message = |room_id| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            Frame::new(header, Bytes::from("msg"))
+        };
+
+        // Sequencing errors come back as an Error, not a driver error
+        let frame = app_message(0x11);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::ROOM_NOT_FOUND);
+
+        // Fill the room's queue without draining it
+        for _ in 0..ROOM_QUEUE_CAPACITY {
+            let route = server.route_frame(1, app_message(room_id)).unwrap();
+            assert!(matches!(route, FrameRoute::Room { .. }));
+        }
+        let FrameRoute::Handled(actions) = server.route_frame(1, app_message(room_id)).unwrap()
+        else {
+            panic!("expected overflow to be handled");
+        };
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FRAME_REJECTED);
+    }
+
+    #[test]
+    fn broadcast_policy_selects_recipients_by_opcode() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        for session_id in 1..=3 {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+        }
+        server.create_room(room_id, 1).unwrap();
+        server.subscribe_to_room(2, room_id);
+        server.subscribe_to_room(3, room_id);
+
+        let frame_for = |opcode| {
+            let mut header = FrameHeader::new(opcode);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            Frame::new(header, Bytes::new())
+        };
+
+        // IncludeAll: messages echo back to the sender
+        let actions = server
+            .process_event(ServerEvent::FrameReceived {
+                session_id: 1,
+                frame: frame_for(Opcode::AppMessage),
+            })
+            .unwrap();
+        assert_eq!(broadcast_recipients(&actions), vec![1, 2, 3]);
+
+        // ExcludeSender: receipts go to the sender's peers only
+        let actions = server
+            .process_event(ServerEvent::FrameReceived {
+                session_id: 1,
+                frame: frame_for(Opcode::AppReceipt),
+            })
+            .unwrap();
+        assert_eq!(broadcast_recipients(&actions), vec![2, 3]);
+
+        // SenderOnly
+        let action = RoomAction::Broadcast {
+            room_id,
+            frame: frame_for(Opcode::AppMessage),
+            policy: BroadcastPolicy::SenderOnly,
+            processed_at: server.env.now(),
+        };
+        let actions = server.process_room_action(action, 1);
+        assert_eq!(broadcast_recipients(&actions), vec![1]);
+    }
+
+    fn edit_frame(room_id: u128, sender_id: u64, target_log_index: u64) -> Frame {
+        let edit = lockframe_proto::payloads::app::Edit {
+            target_log_index,
+            new_ciphertext: lockframe_proto::payloads::app::EncryptedMessage {
+                epoch: 0,
+                sender_index: 0,
+                generation: 1,
+                nonce: [0; 24],
+                ciphertext: vec![1, 2, 3],
+                push_keys: None,
+                message_id: None,
+                expires_at: None,
+            },
+        };
+        let mut header = FrameHeader::new(Opcode::AppEdit);
+        header.set_room_id(room_id);
+        header.set_sender_id(sender_id);
+        Payload::AppEdit(edit).into_frame(header).unwrap()
+    }
+
+    #[test]
+    fn edit_from_original_sender_is_sequenced_and_broadcast() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(10));
+        server.registry.update_session_info(2, SessionInfo::authenticated(20));
+        server.create_room(room_id, 1).unwrap();
+        server.subscribe_to_room(2, room_id);
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(10);
+        let frame = Frame::new(header, Bytes::from("original"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+
+        let actions = server
+            .process_event(ServerEvent::FrameReceived {
+                session_id: 1,
+                frame: edit_frame(room_id, 10, 0),
+            })
+            .unwrap();
+
+        assert_eq!(broadcast_recipients(&actions), vec![1, 2]);
+        let stored = server.storage().load_frames(room_id, 1, 1).unwrap();
+        assert_eq!(stored[0].header.opcode_enum(), Some(Opcode::AppEdit));
+        assert_eq!(stored[0].header.log_index(), 1);
+    }
+
+    #[test]
+    fn room_frames_must_carry_the_session_user_as_sender() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(10));
+        server.registry.update_session_info(2, SessionInfo::authenticated(20));
+        server.create_room(room_id, 1).unwrap();
+
+        // Session 2 claims to be the room's creator and admin
+        let meta = lockframe_proto::payloads::moderation::RoomMeta {
+            name: Some("hijacked".to_string()),
+            topic: None,
+        };
+        let mut header = FrameHeader::new(Opcode::RoomMeta);
+        header.set_room_id(room_id);
+        header.set_sender_id(10);
+        let frame = Payload::RoomMeta(meta).into_frame(header).unwrap();
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(10);
+        let frame = Frame::new(header, Bytes::from("forged"));
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), None);
+    }
+
+    #[test]
+    fn edit_from_other_sender_is_rejected() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(10));
+        server.registry.update_session_info(2, SessionInfo::authenticated(20));
+        server.create_room(room_id, 1).unwrap();
+        server.subscribe_to_room(2, room_id);
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(10);
+        let frame = Frame::new(header, Bytes::from("original"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+
+        for target in [0, 5] {
+            let actions = server
+                .process_event(ServerEvent::FrameReceived {
+                    session_id: 2,
+                    frame: edit_frame(room_id, 20, target),
+                })
+                .unwrap();
+
+            assert!(!actions.iter().any(|a| matches!(a, ServerAction::Broadcast { .. })));
+            assert!(actions.iter().any(|a| matches!(
+                a,
+                ServerAction::SendToSession { frame, .. }
+                    if frame.header.opcode_enum() == Some(Opcode::Error)
+            )));
+        }
+
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn typing_frames_broadcast_but_are_not_stored_or_synced() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x1234_5678_90ab_cdef_1234_5678_90ab_cdef;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(10));
+        server.registry.update_session_info(2, SessionInfo::authenticated(20));
+        server.create_room(room_id, 1).unwrap();
+        server.subscribe_to_room(2, room_id);
+
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(10);
+        let frame = Frame::new(header, Bytes::from("message"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+
+        let mut header = FrameHeader::new(Opcode::Typing);
+        header.set_room_id(room_id);
+        header.set_sender_id(10);
+        let typing = lockframe_proto::payloads::app::Typing { is_typing: true };
+        let frame = Payload::Typing(typing).into_frame(header).unwrap();
+
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(broadcast_recipients(&actions), vec![2]);
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), Some(0));
+
+        // Sync replays only the message
+        let request = lockframe_proto::payloads::session::SyncRequest {
+            from_log_index: 0,
+            limit: 100,
+            from_timestamp: None,
+        };
+        let mut frame = Payload::SyncRequest(request)
+            .into_frame(FrameHeader::new(Opcode::SyncRequest))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+
+        let response = actions
+            .iter()
+            .find_map(|action| match action {
+                ServerAction::SendToSession { frame, .. } => {
+                    Some(Payload::from_frame(frame).unwrap())
+                },
+                _ => None,
+            })
+            .unwrap();
+        let Payload::SyncResponse(response) = response else {
+            panic!("expected SyncResponse, got {response:?}");
+        };
+        assert_eq!(response.frames.len(), 1);
+        let replayed = Frame::decode(&response.frames[0]).unwrap();
+        assert_eq!(replayed.header.opcode_enum(), Some(Opcode::AppMessage));
+    }
+
+    fn room_list_request() -> Frame {
+        Payload::RoomListRequest(lockframe_proto::payloads::session::RoomListRequest {})
+            .into_frame(FrameHeader::new(Opcode::RoomListRequest))
+            .unwrap()
+    }
+
+    fn sent_payload<I>(actions: &[ServerAction<I>]) -> Payload {
+        actions
+            .iter()
+            .find_map(|action| match action {
+                ServerAction::SendToSession { frame, .. } => {
+                    Some(Payload::from_frame(frame).unwrap())
+                },
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn room_list_returns_authenticated_users_rooms() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let (room_a, room_b, room_c) = (0x200, 0x100, 0x300);
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(42));
+        server.registry.update_session_info(2, SessionInfo::authenticated(43));
+        server.create_room(room_a, 1).unwrap();
+        server.create_room(room_b, 1).unwrap();
+        server.create_room(room_c, 2).unwrap();
+
+        let actions = server
+            .process_event(ServerEvent::FrameReceived { session_id: 1, frame: room_list_request() })
+            .unwrap();
+
+        let Payload::RoomListResponse(response) = sent_payload(&actions) else {
+            panic!("expected RoomListResponse");
+        };
+        assert_eq!(response.room_ids, vec![room_b, room_a]);
+    }
+
+    #[test]
+    fn room_list_rejects_unauthenticated_session() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(0x100, 1).unwrap();
+
+        let actions = server
+            .process_event(ServerEvent::FrameReceived { session_id: 1, frame: room_list_request() })
+            .unwrap();
+
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::UNAUTHENTICATED);
+    }
+
+    #[test]
+    fn sync_from_timestamp_returns_frames_at_or_after_it() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+
+        for hlc in [100u64, 200, 300, 400] {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            header.set_hlc_timestamp(hlc);
+            let frame = Frame::new(header, Bytes::from("msg"));
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        }
+
+        let sync_since = |server: &mut ServerDriver<_, _>, timestamp| {
+            let request = lockframe_proto::payloads::session::SyncRequest {
+                from_log_index: 0,
+                limit: 100,
+                from_timestamp: Some(timestamp),
+            };
+            let mut frame = Payload::SyncRequest(request)
+                .into_frame(FrameHeader::new(Opcode::SyncRequest))
+                .unwrap();
+            frame.header.set_room_id(room_id);
+            let actions =
+                server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+            let Payload::SyncResponse(response) = sent_payload(&actions) else {
+                panic!("expected SyncResponse");
+            };
+            response
+                .frames
+                .iter()
+                .map(|bytes| Frame::decode(bytes).unwrap().header.hlc_timestamp())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sync_since(&mut server, 200), vec![200, 300, 400]);
+        assert_eq!(sync_since(&mut server, 250), vec![300, 400]);
+        assert_eq!(sync_since(&mut server, 0), vec![100, 200, 300, 400]);
+        assert!(sync_since(&mut server, 500).is_empty());
+    }
+
+    #[test]
+    fn expired_messages_are_left_out_of_sync_and_pruned_on_tick() {
+        let env = MockEnv::with_crypto_rng();
+        let now = env.wall_clock();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+
+        for expires_at in [None, Some(now - 1), Some(now + 60_000)] {
+            let message = lockframe_proto::payloads::app::EncryptedMessage {
+                epoch: 0,
+                sender_index: 0,
+                generation: 0,
+                nonce: [0; 24],
+                ciphertext: vec![1, 2, 3],
+                push_keys: None,
+                message_id: None,
+                expires_at,
+            };
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            let frame = Payload::AppMessage(message).into_frame(header).unwrap();
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        }
+
+        let request = lockframe_proto::payloads::session::SyncRequest {
+            from_log_index: 0,
+            limit: 100,
+            from_timestamp: None,
+        };
+        let mut frame = Payload::SyncRequest(request)
+            .into_frame(FrameHeader::new(Opcode::SyncRequest))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::SyncResponse(response) = sent_payload(&actions) else {
+            panic!("expected SyncResponse");
+        };
+        let synced: Vec<u64> = response
+            .frames
+            .iter()
+            .map(|bytes| Frame::decode(bytes).unwrap().header.log_index())
+            .collect();
+        assert_eq!(synced, vec![0, 2]);
+
+        server.process_event(ServerEvent::Tick).unwrap();
+
+        let stored = server.storage().load_frames(room_id, 0, 3).unwrap();
+        let pruned: Vec<bool> = stored.iter().map(|f| f.payload.is_empty()).collect();
+        assert_eq!(pruned, vec![false, true, false]);
+    }
+
+    #[test]
+    fn expiries_survive_restart_and_expired_pages_are_skipped() {
+        let storage = MemoryStorage::new();
+        let room_id = 0x100;
+        let env = MockEnv::with_crypto_rng();
+        let now = env.wall_clock();
+
+        {
+            let mut server =
+                ServerDriver::new(env.clone(), storage.clone(), ServerConfig::default());
+            server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+            server.create_room(room_id, 1).unwrap();
+
+            for expires_at in [Some(now + 60_000), Some(now + 60_000), None] {
+                let message = lockframe_proto::payloads::app::EncryptedMessage {
+                    epoch: 0,
+                    sender_index: 0,
+                    generation: 0,
+                    nonce: [0; 24],
+                    ciphertext: vec![1, 2, 3],
+                    push_keys: None,
+                    message_id: None,
+                    expires_at,
+                };
+                let mut header = FrameHeader::new(Opcode::AppMessage);
+                header.set_room_id(room_id);
+                header.set_sender_id(1);
+                let frame = Payload::AppMessage(message).into_frame(header).unwrap();
+                server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+            }
+        }
+
+        env.advance_time(Duration::from_mins(2));
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+
+        // The first page holds only expired messages, so the response moves
+        // on to the next rather than coming back empty
+        let request = lockframe_proto::payloads::session::SyncRequest {
+            from_log_index: 0,
+            limit: 2,
+            from_timestamp: None,
+        };
+        let mut frame = Payload::SyncRequest(request)
+            .into_frame(FrameHeader::new(Opcode::SyncRequest))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::SyncResponse(response) = sent_payload(&actions) else {
+            panic!("expected SyncResponse");
+        };
+        let synced: Vec<u64> = response
+            .frames
+            .iter()
+            .map(|bytes| Frame::decode(bytes).unwrap().header.log_index())
+            .collect();
+        assert_eq!(synced, vec![2]);
+        assert!(!response.has_more);
+
+        // Recovering the room rescheduled the expiries from storage
+        server.process_event(ServerEvent::Tick).unwrap();
+        let stored = server.storage().load_frames(room_id, 0, 3).unwrap();
+        let pruned: Vec<bool> = stored.iter().map(|f| f.payload.is_empty()).collect();
+        assert_eq!(pruned, vec![true, true, false]);
+    }
+
+    #[test]
+    fn retention_prunes_old_frames_and_sync_starts_from_snapshot() {
+        let env = MockEnv::with_crypto_rng();
+        let now = env.wall_clock();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env.clone(), storage, ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+        server.storage().store_group_info(room_id, 3, b"group info").unwrap();
+        let retention =
+            RetentionPolicy { max_age: Some(Duration::from_hours(1)), max_frames: None };
+        server.set_retention_policy(room_id, retention).unwrap();
+
+        // Client HLCs are out of log order (0 on non-app frames), so age has
+        // to come from when the server stored each frame
+        let send = |server: &mut ServerDriver<MockEnv, MemoryStorage>, hlc: u64| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            header.set_hlc_timestamp(hlc);
+            let frame = Frame::new(header, Bytes::from_static(b"msg"));
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        };
+        send(&mut server, now);
+        send(&mut server, 0);
+        env.advance_time(Duration::from_hours(2));
+        send(&mut server, 0);
+        send(&mut server, now);
+
+        server.process_event(ServerEvent::Tick).unwrap();
+
+        let stored = server.storage().load_frames(room_id, 0, 10).unwrap();
+        let indices: Vec<u64> = stored.iter().map(|f| f.header.log_index()).collect();
+        assert_eq!(indices, vec![2, 3]);
+        assert_eq!(server.storage().latest_log_index(room_id).unwrap(), Some(3));
+
+        let request = lockframe_proto::payloads::session::SyncRequest {
+            from_log_index: 0,
+            limit: 100,
+            from_timestamp: None,
+        };
+        let mut frame = Payload::SyncRequest(request)
+            .into_frame(FrameHeader::new(Opcode::SyncRequest))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::SyncResponse(response) = sent_payload(&actions) else {
+            panic!("expected SyncResponse");
+        };
+        assert_eq!(
+            response.snapshot,
+            Some(SyncSnapshot { first_log_index: 2, epoch: 3, group_info: b"group info".to_vec() })
+        );
+        let synced: Vec<u64> = response
+            .frames
+            .iter()
+            .map(|bytes| Frame::decode(bytes).unwrap().header.log_index())
+            .collect();
+        assert_eq!(synced, vec![2, 3]);
+    }
+
+    #[test]
+    fn sync_response_respects_byte_cap() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let config = ServerConfig { max_sync_bytes: 2_500, ..Default::default() };
+        let mut server = ServerDriver::new(env, storage, config);
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+
+        // Five frames of ~1 KiB each; the count limit alone would return all
+        for _ in 0..5 {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            let frame = Frame::new(header, Bytes::from(vec![0u8; 1_000]));
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        }
+
+        let mut sync = |from_log_index| {
+            let request = lockframe_proto::payloads::session::SyncRequest {
+                from_log_index,
+                limit: 100,
+                from_timestamp: None,
+            };
+            let mut frame = Payload::SyncRequest(request)
+                .into_frame(FrameHeader::new(Opcode::SyncRequest))
+                .unwrap();
+            frame.header.set_room_id(room_id);
+            let actions =
+                server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+            let Payload::SyncResponse(response) = sent_payload(&actions) else {
+                panic!("expected SyncResponse");
+            };
+            response
+        };
+
+        let response = sync(0);
+        assert_eq!(response.frames.len(), 2);
+        assert!(response.frames.iter().map(Vec::len).sum::<usize>() <= 2_500);
+        assert!(response.has_more);
+
+        // The tail fits under the budget, so the final page is complete
+        let response = sync(4);
+        assert_eq!(response.frames.len(), 1);
+        assert!(!response.has_more);
+    }
+
+    fn send_app_message(
+        server: &mut ServerDriver<MockEnv, MemoryStorage>,
+        session_id: u64,
+        room_id: u128,
+    ) {
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(session_id);
+        let frame = Frame::new(header, Bytes::from_static(b"hi"));
+        server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+    }
+
+    fn sync_page(
+        server: &mut ServerDriver<MockEnv, MemoryStorage>,
+        session_id: u64,
+        room_id: u128,
+        from_log_index: u64,
+        limit: u64,
+    ) -> lockframe_proto::payloads::session::SyncResponse {
+        let request = lockframe_proto::payloads::session::SyncRequest {
+            from_log_index,
+            limit,
+            from_timestamp: None,
+        };
+        let mut frame = Payload::SyncRequest(request)
+            .into_frame(FrameHeader::new(Opcode::SyncRequest))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+        let Payload::SyncResponse(response) = sent_payload(&actions) else {
+            panic!("expected SyncResponse");
+        };
+        response
+    }
+
+    #[test]
+    fn large_sync_is_paged_so_small_sync_is_not_held_up() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let config = ServerConfig { max_sync_frames: 8, ..Default::default() };
+        let mut server = ServerDriver::new(env, storage, config);
+        let (large_room, small_room) = (0x100, 0x200);
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.create_room(large_room, 1).unwrap();
+        server.create_room(small_room, 2).unwrap();
+
+        for _ in 0..30 {
+            send_app_message(&mut server, 1, large_room);
+        }
+        for _ in 0..3 {
+            send_app_message(&mut server, 2, small_room);
+        }
+
+        // The backfill pages through its room while the other session keeps
+        // syncing between pages: every small sync completes in one response
+        // while the backfill still has more to fetch
+        let mut large_from = 0;
+        let mut large_pages = 0;
+        let mut small_from = 0;
+        loop {
+            let page = sync_page(&mut server, 1, large_room, large_from, 1_000);
+            assert!(page.frames.len() <= 8);
+            large_from += page.frames.len() as u64;
+            large_pages += 1;
+            if !page.has_more {
+                break;
+            }
+
+            let small = sync_page(&mut server, 2, small_room, small_from, 1_000);
+            assert!(!small.frames.is_empty());
+            assert!(!small.has_more, "small sync held up behind the backfill");
+            small_from += small.frames.len() as u64;
+
+            send_app_message(&mut server, 2, small_room);
+        }
+
+        assert_eq!(large_from, 30);
+        assert_eq!(large_pages, 4);
+        // The 3 initial frames plus one sent after each of the first two
+        // pages; the one sent after the third is still unsynced
+        assert_eq!(small_from, 5);
+    }
+
+    #[test]
+    fn zero_sync_frame_cap_still_makes_progress() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let config = ServerConfig { max_sync_frames: 0, ..Default::default() };
+        let mut server = ServerDriver::new(env, storage, config);
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+        for _ in 0..3 {
+            send_app_message(&mut server, 1, room_id);
+        }
+
+        // Neither the config nor a zero request limit yields an empty page
+        for limit in [1_000, 0] {
+            let mut from = 0;
+            loop {
+                let page = sync_page(&mut server, 1, room_id, from, limit);
+                assert_eq!(page.frames.len(), 1);
+                from += 1;
+                if !page.has_more {
+                    break;
+                }
+            }
+            assert_eq!(from, 3);
+        }
+    }
+
+    #[test]
+    fn sync_byte_cap_always_sends_one_frame() {
+        let mut frames = vec![vec![0u8; 100], vec![0u8; 10]];
+        let mut has_more = false;
+        cap_sync_frames(&mut frames, &mut has_more, 50);
+        assert_eq!(frames.len(), 1);
+        assert!(has_more);
+
+        let mut frames = Vec::new();
+        let mut has_more = false;
+        cap_sync_frames(&mut frames, &mut has_more, 50);
+        assert!(frames.is_empty());
+        assert!(!has_more);
+    }
+
+    fn group_info_frame(frame_room_id: u128, room_id: u128, epoch: u64) -> Frame {
+        let payload = GroupInfoPayload { room_id, epoch, group_info_bytes: vec![1, 2, 3] };
+        let mut header = FrameHeader::new(Opcode::GroupInfo);
+        header.set_room_id(frame_room_id);
+        Payload::GroupInfo(payload).into_frame(header).unwrap()
+    }
+
+    #[test]
+    fn group_info_is_only_stored_from_members_for_the_current_epoch() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server =
+            ServerDriver::new(env.clone(), MemoryStorage::new(), ServerConfig::default());
+        let room_id = 0x100;
+        let other_room_id = 0x200;
+        for (session_id, user_id) in [(1, 42), (2, 43)] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            server.registry.update_session_info(session_id, SessionInfo::authenticated(user_id));
+        }
+        server.create_room(room_id, 1).unwrap();
+        server.create_room(other_room_id, 2).unwrap();
+
+        let rejected =
+            |server: &mut ServerDriver<MockEnv, MemoryStorage>, session_id, frame: Frame| {
+                let event = ServerEvent::FrameReceived { session_id, frame };
+                assert!(server.validate_event(&event).is_err());
+                let actions = server.process_event(event).unwrap();
+                let Payload::Error(error) = sent_payload(&actions) else {
+                    panic!("expected Error, got {actions:?}");
+                };
+                error.code
+            };
+
+        // User 43 is not a member of the room
+        let frame = group_info_frame(room_id, room_id, 0);
+        assert_eq!(rejected(&mut server, 2, frame), ErrorPayload::FORBIDDEN);
+
+        // A member of the frame's room publishing for another room
+        let frame = group_info_frame(room_id, other_room_id, 0);
+        assert_eq!(rejected(&mut server, 1, frame), ErrorPayload::FRAME_REJECTED);
+        assert_eq!(server.storage.load_group_info(room_id).unwrap(), None);
+        assert_eq!(server.storage.load_group_info(other_room_id).unwrap(), None);
+
+        // Once a commit adds 43 and moves the room to epoch 1, only epoch 1
+        // is current
+        let frame = add_commit_frame(&env, room_id, 42, 43);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let frame = group_info_frame(room_id, room_id, 0);
+        assert_eq!(rejected(&mut server, 2, frame), ErrorPayload::STALE_EPOCH);
+
+        let frame = group_info_frame(room_id, room_id, 1);
+        let event = ServerEvent::FrameReceived { session_id: 2, frame };
+        server.validate_event(&event).unwrap();
+        server.process_event(event).unwrap();
+        assert_eq!(server.storage.load_group_info(room_id).unwrap(), Some((1, vec![1, 2, 3])));
+    }
+
+    #[test]
+    fn commit_keys_follow_commits_and_published_group_info() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server =
+            ServerDriver::new(env.clone(), MemoryStorage::new(), ServerConfig::default());
+        let room_id = 0x100;
+        for (session_id, user_id) in [(1, 42), (2, 43)] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            server.registry.update_session_info(session_id, SessionInfo::authenticated(user_id));
+        }
+        server.create_room(room_id, 1).unwrap();
+
+        // A room with MLS group state, reloaded so its keys are checked
+        let (mut alice, _) = MlsGroup::new(env.clone(), room_id, 42).unwrap();
+        server.storage.store_mls_state(room_id, &alice.export_group_state().unwrap()).unwrap();
+        server.rooms.with_room(room_id, |rooms| rooms.evict_room(room_id));
+
+        let (key_package, _, bob_pending) = MlsGroup::generate_key_package(env, 43).unwrap();
+        let actions = alice.add_members_from_bytes(&[key_package]).unwrap();
+        let sent = |opcode| {
+            actions.iter().find_map(|action| match action {
+                MlsAction::SendCommit(frame) | MlsAction::SendWelcome { frame, .. }
+                    if frame.header.opcode_enum() == Some(opcode) =>
+                {
+                    Some(frame.clone())
+                },
+                _ => None,
+            })
+        };
+        let frame = sent(Opcode::Commit).unwrap();
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        alice.merge_pending_commit().unwrap();
+        let welcome = sent(Opcode::Welcome).unwrap();
+        let (mut bob, _) =
+            MlsGroup::join_from_welcome(room_id, 43, &welcome.payload, bob_pending).unwrap();
+
+        // The commit moved the state on, but Bob's key is not known yet
+        let state = server.storage.load_mls_state(room_id).unwrap().unwrap();
+        assert_eq!((state.epoch, state.members.clone()), (1, vec![42, 43]));
+        assert!(!state.member_keys.contains_key(&43));
+        let commit = bob
+            .self_update()
+            .unwrap()
+            .into_iter()
+            .find_map(|action| match action {
+                MlsAction::SendCommit(frame) => Some(frame),
+                _ => None,
+            })
+            .unwrap();
+        let actions = server
+            .process_event(ServerEvent::FrameReceived { session_id: 2, frame: commit.clone() })
+            .unwrap();
+        assert!(
+            matches!(sent_payload(&actions), Payload::Error(e) if e.code == ErrorPayload::MLS_ERROR)
+        );
+
+        // Alice publishes the new epoch's GroupInfo, which carries Bob's key
+        let payload = GroupInfoPayload {
+            room_id,
+            epoch: 1,
+            group_info_bytes: alice.export_group_info().unwrap(),
+        };
+        let mut header = FrameHeader::new(Opcode::GroupInfo);
+        header.set_room_id(room_id);
+        let frame = Payload::GroupInfo(payload).into_frame(header).unwrap();
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let state = server.storage.load_mls_state(room_id).unwrap().unwrap();
+        assert_eq!(state, alice.export_group_state().unwrap());
+
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame: commit }).unwrap();
+        assert_eq!(server.storage.latest_log_index(room_id).unwrap(), Some(1));
+        assert_eq!(server.storage.load_mls_state(room_id).unwrap().unwrap().epoch, 2);
+    }
+
+    fn welcome_frame(room_id: u128, sender_id: u64, recipient_id: u64) -> Frame {
+        let mut header = FrameHeader::new(Opcode::Welcome);
+        header.set_room_id(room_id);
+        header.set_sender_id(sender_id);
+        header.set_recipient_id(recipient_id);
+        Frame::new(header, Bytes::from("welcome"))
+    }
+
+    /// MLS commit from `sender_id`, in a fresh group for `room_id`, that
+    /// declares `user_id` as added.
+    fn add_commit_frame(env: &MockEnv, room_id: u128, sender_id: u64, user_id: u64) -> Frame {
+        let (mut group, _) = MlsGroup::new(env.clone(), room_id, sender_id).unwrap();
+        let (key_package, _, _) = MlsGroup::generate_key_package(env.clone(), user_id).unwrap();
+        group
+            .add_members_from_bytes(&[key_package])
+            .unwrap()
+            .into_iter()
+            .find_map(|action| match action {
+                MlsAction::SendCommit(frame) => Some(frame),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn membership_survives_driver_restart() {
+        let storage = MemoryStorage::new();
+        let room_id = 0x100;
+
+        {
+            let env = MockEnv::with_crypto_rng();
+            let mut server =
+                ServerDriver::new(env.clone(), storage.clone(), ServerConfig::default());
+            server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+            server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+            server.registry.update_session_info(1, SessionInfo::authenticated(42));
+            server.registry.update_session_info(2, SessionInfo::authenticated(43));
+            server.create_room(room_id, 1).unwrap();
+
+            let frame = add_commit_frame(&env, room_id, 42, 43);
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        }
+
+        assert_eq!(storage.members(room_id).unwrap(), vec![42, 43]);
+
+        // Fresh driver with no live subscriptions still reports the room
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 7 }).unwrap();
+        server.registry.update_session_info(7, SessionInfo::authenticated(43));
+
+        let actions = server
+            .process_event(ServerEvent::FrameReceived { session_id: 7, frame: room_list_request() })
+            .unwrap();
+
+        let Payload::RoomListResponse(response) = sent_payload(&actions) else {
+            panic!("expected RoomListResponse");
+        };
+        assert_eq!(response.room_ids, vec![room_id]);
+    }
+
+    #[test]
+    fn welcome_requires_existing_room_and_member_sender() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage.clone(), ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(42));
+        server.registry.update_session_info(2, SessionInfo::authenticated(43));
+
+        let frame = welcome_frame(room_id, 42, 44);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::ROOM_NOT_FOUND);
+
+        server.create_room(room_id, 1).unwrap();
+
+        // A non-member can't add anyone, whatever sender the header claims
+        let frame = welcome_frame(room_id, 42, 44);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+        assert_eq!(storage.members(room_id).unwrap(), vec![42]);
+        assert!(storage.member_rooms(44).unwrap().is_empty());
+
+        // A member's Welcome is delivered, but membership waits for the
+        // commit that adds the recipient
+        let frame = welcome_frame(room_id, 42, 44);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(storage.members(room_id).unwrap(), vec![42]);
+        assert!(storage.member_rooms(44).unwrap().is_empty());
+    }
+
+    #[test]
+    fn welcome_whose_commit_is_rejected_leaves_roster_unchanged() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env.clone(), storage.clone(), ServerConfig::default());
+        let room_id = 0x100;
+
+        for (session_id, user_id) in [(1, 42), (2, 43)] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            server.registry.update_session_info(session_id, SessionInfo::authenticated(user_id));
+        }
+        server.create_room(room_id, 1).unwrap();
+
+        // A sequenced add is the only thing that grows the roster
+        let frame = add_commit_frame(&env, room_id, 42, 44);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(storage.members(room_id).unwrap(), vec![42, 44]);
+
+        let frame = welcome_frame(room_id, 42, 43);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert!(
+            actions
+                .iter()
+                .any(|action| matches!(action, ServerAction::SendToSession { session_id: 2, .. }))
+        );
+        assert_eq!(storage.members(room_id).unwrap(), vec![42, 44]);
+
+        // The paired commit targets an epoch the room has already reached
+        let frame = add_commit_frame(&env, room_id, 42, 43);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert!(matches!(sent_payload(&actions), Payload::Error(_)));
+        assert_eq!(storage.members(room_id).unwrap(), vec![42, 44]);
+        assert!(storage.member_rooms(43).unwrap().is_empty());
+    }
+
+    #[test]
+    fn exported_room_imports_into_fresh_driver_and_serves_sync() {
+        let room_id = 0x100;
+        let env = MockEnv::with_crypto_rng();
+        let source_storage = MemoryStorage::new();
+        let mut source =
+            ServerDriver::new(env.clone(), source_storage.clone(), ServerConfig::default());
+        source.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        source.registry.update_session_info(1, SessionInfo::authenticated(42));
+        source.create_room(room_id, 1).unwrap();
+
+        let frame = add_commit_frame(&env, room_id, 42, 43);
+        source.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        for text in ["one", "two", "three"] {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(42);
+            let frame = Frame::new(header, Bytes::from(text));
+            source.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        }
+        source_storage.store_group_info(room_id, 3, b"group-info").unwrap();
+
+        let export = source.export_room(room_id).unwrap();
+        assert_eq!(export.frames.len(), 4);
+        assert_eq!(export.members, vec![42, 43]);
+        assert_eq!(export.group_info, Some((3, b"group-info".to_vec())));
+
+        let mut target = ServerDriver::new(
+            MockEnv::with_crypto_rng(),
+            MemoryStorage::new(),
+            ServerConfig::default(),
+        );
+        target.import_room(&export).unwrap();
+        assert!(target.has_room(room_id));
+        assert_eq!(target.export_room(room_id).unwrap(), export);
+        assert!(matches!(
+            target.import_room(&export),
+            Err(ServerError::Room(RoomError::RoomAlreadyExists(_)))
+        ));
+
+        target.process_event(ServerEvent::ConnectionAccepted { session_id: 9 }).unwrap();
+        target.registry.update_session_info(9, SessionInfo::authenticated(43));
+        target.subscribe_to_room(9, room_id);
+        let request = lockframe_proto::payloads::session::SyncRequest {
+            from_log_index: 1,
+            limit: 100,
+            from_timestamp: None,
+        };
+        let mut frame = Payload::SyncRequest(request)
+            .into_frame(FrameHeader::new(Opcode::SyncRequest))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        let actions =
+            target.process_event(ServerEvent::FrameReceived { session_id: 9, frame }).unwrap();
+
+        let Payload::SyncResponse(response) = sent_payload(&actions) else {
+            panic!("expected SyncResponse");
+        };
+        let frames: Vec<_> =
+            response.frames.iter().map(|bytes| Frame::decode(bytes).unwrap()).collect();
+        assert_eq!(frames, export.frames[1..]);
+        assert!(!response.has_more);
+    }
+
+    #[test]
+    fn export_missing_room_fails() {
+        let server = ServerDriver::new(
+            MockEnv::with_crypto_rng(),
+            MemoryStorage::new(),
+            ServerConfig::default(),
+        );
+        assert!(matches!(
+            server.export_room(0x100),
+            Err(ServerError::Room(RoomError::RoomNotFound(0x100)))
+        ));
+    }
+
+    #[test]
+    fn unsupported_version_gets_error_frame() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, MemoryStorage::new(), ServerConfig::default());
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+
+        let error = ProtocolError::UnsupportedVersion { got: 2, supported: FrameHeader::VERSION };
+        let actions =
+            server.process_event(ServerEvent::FrameDecodeFailed { session_id: 1, error }).unwrap();
+
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::UNSUPPORTED_VERSION);
+    }
+
+    #[test]
+    fn sync_failure_is_recorded_in_audit_log() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env.clone(), storage.clone(), ServerConfig::default());
+        let room_id = 0x100;
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+
+        let request = lockframe_proto::payloads::session::SyncRequest {
+            from_log_index: 0,
+            limit: 100,
+            from_timestamp: None,
+        };
+        let mut frame = Payload::SyncRequest(request)
+            .into_frame(FrameHeader::new(Opcode::SyncRequest))
+            .unwrap();
+        frame.header.set_room_id(room_id);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+
+        // Written on the next tick, not in the request path
+        assert!(storage.load_audit(0, 10).unwrap().is_empty());
+        server.process_event(ServerEvent::Tick).unwrap();
+
+        let audit = storage.load_audit(0, 10).unwrap();
+        assert_eq!(audit, vec![AuditEntry {
+            code: ErrorPayload::ROOM_NOT_FOUND,
+            session_id: 1,
+            room_id,
+            reason: error.message,
+            at_millis: env.wall_clock(),
+        }]);
+    }
+
+    #[test]
+    fn audit_buffer_is_bounded_between_flushes() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage.clone(), ServerConfig::default());
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+
+        for _ in 0..=MAX_PENDING_AUDIT {
+            let mut header = FrameHeader::new(Opcode::SyncRequest);
+            header.set_room_id(0x100);
+            let frame = Frame::new(header, Bytes::from("not cbor"));
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        }
+
+        let actions = server.process_event(ServerEvent::Tick).unwrap();
+        assert_eq!(storage.load_audit(0, usize::MAX).unwrap().len(), MAX_PENDING_AUDIT);
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            ServerAction::Log { level: LogLevel::Warn, message, .. } if message.contains("dropped 1")
+        )));
+    }
+
+    fn hello_frame(sender_id: u64, auth_token: Option<&[u8]>) -> Frame {
+        Payload::Hello(lockframe_proto::payloads::session::Hello {
+            version: 1,
+            capabilities: Vec::new(),
+            sender_id: Some(sender_id),
+            auth_token: auth_token.map(<[u8]>::to_vec),
+        })
+        .into_frame(FrameHeader::new(Opcode::Hello))
+        .unwrap()
+    }
+
+    /// Position of the first `Goodbye` sent to and the `CloseConnection` of
+    /// `session_id` in `actions`.
+    fn goodbye_and_close<I>(actions: &[ServerAction<I>], session_id: u64) -> (usize, usize) {
+        let goodbye = actions.iter().position(|a| {
+            matches!(a, ServerAction::SendToSession { session_id: s, frame }
+                if *s == session_id && frame.header.opcode_enum() == Some(Opcode::Goodbye))
+        });
+        let close = actions.iter().position(
+            |a| matches!(a, ServerAction::CloseConnection { session_id: s, .. } if *s == session_id),
+        );
+        (goodbye.expect("no Goodbye sent"), close.expect("connection not closed"))
+    }
+
+    #[test]
+    fn idle_eviction_sends_goodbye_before_close() {
+        let env = MockEnv::with_crypto_rng();
+        let config = ServerConfig::default();
+        let idle_timeout = config.connection.idle_timeout;
+        let mut server = ServerDriver::new(env.clone(), MemoryStorage::new(), config);
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        let frame = hello_frame(42, None);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+
+        env.advance_time(idle_timeout + Duration::from_secs(1));
+        let actions = server.process_event(ServerEvent::Tick).unwrap();
+
+        let (goodbye, close) = goodbye_and_close(&actions, 1);
+        assert!(goodbye < close);
+    }
+
+    #[test]
+    fn shutdown_says_goodbye_to_every_session() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, MemoryStorage::new(), ServerConfig::default());
+        for session_id in [1, 2] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+        }
+
+        let actions = server.shutdown("server shutting down");
+
+        for session_id in [1, 2] {
+            let (goodbye, close) = goodbye_and_close(&actions, session_id);
+            assert!(goodbye < close);
+        }
+        assert!(server.shutdown("again").is_empty());
+    }
+
+    #[test]
+    fn hello_reply_lists_messages_missed_while_offline() {
+        let env = MockEnv::with_crypto_rng();
+        let mut server =
+            ServerDriver::new(env.clone(), MemoryStorage::new(), ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        let frame = hello_frame(1, None);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+        let frame = add_commit_frame(&env, room_id, 1, 2);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(server.storage().members(room_id).unwrap(), vec![1, 2]);
+
+        // Member 2 is offline for all three messages
+        for _ in 0..3 {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            let frame = Frame::new(header, Bytes::from_static(b"msg"));
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        }
+
+        let unread_on_hello =
+            |server: &mut ServerDriver<MockEnv, MemoryStorage>, session_id, user| {
+                server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+                let frame = hello_frame(user, None);
+                let actions =
+                    server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+                let Payload::HelloReply(reply) = sent_payload(&actions) else {
+                    panic!("expected HelloReply");
+                };
+                reply.unread
+            };
+
+        assert_eq!(unread_on_hello(&mut server, 2, 2), vec![RoomUnread { room_id, count: 3 }]);
+        // The sender was in the room, and counts are only reported once
+        assert!(unread_on_hello(&mut server, 3, 1).is_empty());
+        assert!(unread_on_hello(&mut server, 4, 2).is_empty());
+    }
+
+    fn admin_request(request: AdminRequest) -> Frame {
+        Payload::Admin(AdminMessage::Request(request))
+            .into_frame(FrameHeader::new(Opcode::Admin))
+            .unwrap()
+    }
+
+    fn admin_response<I>(actions: &[ServerAction<I>]) -> AdminResponse {
+        let Payload::Admin(AdminMessage::Response(response)) = sent_payload(actions) else {
+            panic!("expected AdminResponse");
+        };
+        response
+    }
+
+    fn admin_server() -> ServerDriver<MockEnv, MemoryStorage> {
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, MemoryStorage::new(), ServerConfig::default())
+            .with_authenticator(crate::AdminToken::new(*b"ops"));
+        for (session_id, token) in [(1, Some(&b"ops"[..])), (2, Some(&b"guess"[..])), (3, None)] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            let frame = hello_frame(100 + session_id, token);
+            server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+        }
+        server
+    }
+
+    #[test]
+    fn admin_request_from_non_admin_session_is_forbidden() {
+        let mut server = admin_server();
+        server.create_room(0x100, 1).unwrap();
+
+        for session_id in [2, 3] {
+            let frame = admin_request(AdminRequest::DropSession { session_id: 1 });
+            let actions =
+                server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+
+            let Payload::Error(error) = sent_payload(&actions) else {
+                panic!("expected Error");
+            };
+            assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+            assert!(!actions.iter().any(|a| matches!(a, ServerAction::CloseConnection { .. })));
+        }
+        assert_eq!(server.connection_count(), 3);
+    }
+
+    #[test]
+    fn session_without_create_room_cannot_create_rooms() {
+        struct ReadOnly;
+        impl crate::Authenticator for ReadOnly {
+            fn authenticate(
+                &self,
+                hello: &lockframe_proto::payloads::session::Hello,
+            ) -> Capabilities {
+                if hello.sender_id == Some(101) { Capabilities::USER } else { Capabilities::NONE }
+            }
+        }
+
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env, MemoryStorage::new(), ServerConfig::default())
+            .with_authenticator(ReadOnly);
+        for session_id in [1, 2] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            let frame = hello_frame(100 + session_id, None);
+            server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+        }
+        let commit = |room_id, sender_id| {
+            let mut header = FrameHeader::new(Opcode::Commit);
+            header.set_room_id(room_id);
+            header.set_sender_id(sender_id);
+            Frame::new(header, Bytes::from("commit"))
+        };
+
+        let frame = commit(0x100, 102);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+        assert!(!server.has_room(0x100));
+
+        let frame = commit(0x200, 101);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert!(server.has_room(0x200));
+    }
+
+    fn create_room_frame(room_id: u128, request: CreateRoom) -> Frame {
+        let mut header = FrameHeader::new(Opcode::CreateRoom);
+        header.set_room_id(room_id);
+        Payload::CreateRoom(request).into_frame(header).unwrap()
+    }
+
+    fn create_room_server(config: ServerConfig) -> (MockEnv, ServerDriver<MockEnv, MemoryStorage>) {
+        let env = MockEnv::with_crypto_rng();
+        let mut server = ServerDriver::new(env.clone(), MemoryStorage::new(), config);
+        for session_id in [1, 2] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            let frame = hello_frame(100 + session_id, None);
+            server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+        }
+        (env, server)
+    }
+
+    #[test]
+    fn create_room_request_persists_metadata_and_policy() {
+        let (_, mut server) = create_room_server(ServerConfig::default());
+        let room_id = 0x100;
+
+        let request = CreateRoom {
+            public: true,
+            name: "Lobby".into(),
+            topic: "general chat".into(),
+            admins: vec![101, 102],
+            max_age_secs: Some(3600),
+            max_frames: Some(500),
+        };
+        let frame = create_room_frame(room_id, request);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert!(!actions.iter().any(|a| matches!(a, ServerAction::SendToSession { .. })));
+
+        let metadata = server.storage.load_room_metadata(room_id).unwrap().unwrap();
+        assert_eq!(metadata.creator, 101);
+        assert!(metadata.public);
+        assert_eq!((metadata.name.as_str(), metadata.topic.as_str()), ("Lobby", "general chat"));
+        assert_eq!(metadata.retention, RetentionPolicy {
+            max_age: Some(Duration::from_hours(1)),
+            max_frames: Some(500),
+        });
+        let policy = server.storage.load_room_policy(room_id).unwrap().unwrap();
+        assert_eq!(policy.admins, [101, 102].into());
+        assert!(server.sessions_in_room(room_id).any(|session_id| session_id == 1));
+
+        // The room exists now, so a second create is rejected
+        let frame = create_room_frame(room_id, CreateRoom::default());
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FRAME_REJECTED);
+        assert_eq!(server.storage.load_room_metadata(room_id).unwrap().unwrap().creator, 101);
+    }
+
+    #[test]
+    fn create_room_request_without_capability_is_forbidden() {
+        let (_, mut server) = create_room_server(ServerConfig::default());
+        server
+            .registry
+            .update_session_info(2, SessionInfo::authenticated_with(102, Capabilities::NONE));
+
+        let frame =
+            create_room_frame(0x100, CreateRoom { name: "Mine".into(), ..CreateRoom::default() });
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+        assert!(!server.has_room(0x100));
+        assert_eq!(server.storage.load_room_metadata(0x100).unwrap(), None);
+    }
+
+    #[test]
+    fn create_room_requests_are_rate_limited_per_user() {
+        let config = ServerConfig { room_creation_limit: 2, ..ServerConfig::default() };
+        let window = config.room_creation_window;
+        let (env, mut server) = create_room_server(config);
+        let create = |server: &mut ServerDriver<MockEnv, MemoryStorage>, session_id, room_id| {
+            let frame = create_room_frame(room_id, CreateRoom::default());
+            server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap()
+        };
+
+        create(&mut server, 1, 0x100);
+        create(&mut server, 1, 0x101);
+        let actions = create(&mut server, 1, 0x102);
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::RATE_LIMITED);
+        assert_eq!(error.retry_after, Some(window.as_secs()));
+        assert!(!server.has_room(0x102));
+
+        // Committing to an unknown room would create it, so it counts too
+        let mut header = FrameHeader::new(Opcode::Commit);
+        header.set_room_id(0x103);
+        header.set_sender_id(101);
+        header.set_request_id(7);
+        let frame = Frame::new(header, Bytes::from("commit"));
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::RATE_LIMITED);
+        // The error names the refused request
+        let error_frame = actions
+            .iter()
+            .find_map(|action| match action {
+                ServerAction::SendToSession { frame, .. } => Some(frame),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(error_frame.header.request_id(), 7);
+        assert_eq!(error_frame.header.room_id(), 0x103);
+        assert!(!server.has_room(0x103));
+
+        // Other users have their own budget
+        create(&mut server, 2, 0x200);
+        assert!(server.has_room(0x200));
+
+        env.advance_time(window);
+        create(&mut server, 1, 0x102);
+        assert!(server.has_room(0x102));
+    }
+
+    #[test]
+    fn admin_list_rooms_returns_every_persisted_room() {
+        let mut server = admin_server();
+        for room_id in [0x300, 0x100, 0x200] {
+            server.create_room(room_id, 2).unwrap();
+        }
+
+        let frame = admin_request(AdminRequest::ListRooms);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+
+        assert_eq!(admin_response(&actions), AdminResponse::Rooms {
+            room_ids: vec![0x100, 0x200, 0x300]
+        });
+    }
+
+    #[test]
+    fn admin_evicts_room_and_drops_session() {
+        let mut server = admin_server();
+        server.create_room(0x100, 2).unwrap();
+        server.subscribe_to_room(3, 0x100);
+
+        let frame = admin_request(AdminRequest::EvictRoom { room_id: 0x100 });
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(admin_response(&actions), AdminResponse::RoomEvicted {
+            room_id: 0x100,
+            evicted: true
+        });
+        assert!(!server.has_room(0x100));
+        assert_eq!(server.sessions_in_room(0x100).count(), 0);
+
+        let frame = admin_request(AdminRequest::DropSession { session_id: 2 });
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(admin_response(&actions), AdminResponse::SessionDropped {
+            session_id: 2,
+            dropped: true
+        });
+        assert!(
+            actions
+                .iter()
+                .any(|a| matches!(a, ServerAction::CloseConnection { session_id: 2, .. }))
+        );
+        assert_eq!(server.connection_count(), 2);
+    }
+
+    #[test]
+    fn validate_event_agrees_with_dispatch_for_each_opcode() {
+        let env = MockEnv::with_crypto_rng();
+        let config = ServerConfig { max_members: Some(2), ..ServerConfig::default() };
+        let mut server = ServerDriver::new(env.clone(), MemoryStorage::new(), config)
+            .with_authenticator(crate::AdminToken::new(*b"ops"));
+        for session_id in 1..=4 {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            let token = (session_id == 1).then_some(&b"ops"[..]);
+            let frame = hello_frame(100 + session_id, token);
+            server.process_event(ServerEvent::FrameReceived { session_id, frame }).unwrap();
+        }
+        let (room_id, evicted_room, missing_room) = (0x100, 0x200, 0x999);
+        server.create_room(room_id, 2).unwrap();
+        server.create_room(evicted_room, 2).unwrap();
+        let frame = add_commit_frame(&env, room_id, 102, 103);
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        let app_message = |room_id| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(102);
+            Frame::new(header, Bytes::from("hi"))
+        };
+        let frame = app_message(evicted_room);
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        server.rooms.with_room(evicted_room, |rooms| rooms.evict_room(evicted_room));
+
+        let sync = |room_id| {
+            let request = lockframe_proto::payloads::session::SyncRequest {
+                from_log_index: 0,
+                limit: 10,
+                from_timestamp: None,
+            };
+            let mut frame = Payload::SyncRequest(request)
+                .into_frame(FrameHeader::new(Opcode::SyncRequest))
+                .unwrap();
+            frame.header.set_room_id(room_id);
+            frame
+        };
+
+        let cases = [
+            (2, room_list_request(), true),
+            (1, admin_request(AdminRequest::ListRooms), true),
+            (2, admin_request(AdminRequest::ListRooms), false),
+            (2, sync(room_id), true),
+            (2, sync(evicted_room), true),
+            (2, sync(missing_room), false),
+            (2, welcome_frame(missing_room, 102, 104), false),
+            // The recipient of someone else's Welcome isn't a member yet
+            (4, welcome_frame(room_id, 104, 101), false),
+            // The room is at its cap of two
+            (2, welcome_frame(room_id, 102, 104), false),
+            (2, welcome_frame(room_id, 102, 103), true),
+            (2, welcome_frame(evicted_room, 102, 104), true),
+            (2, app_message(room_id), true),
+            (2, app_message(missing_room), false),
+        ];
+        for (session_id, frame, expect_ok) in cases {
+            let opcode = frame.header.opcode_enum();
+            let event = ServerEvent::FrameReceived { session_id, frame };
+            let validated = server.validate_event(&event);
+            let actions = server.process_event(event).unwrap();
+            let rejected = actions.iter().any(|action| {
+                matches!(action, ServerAction::SendToSession { session_id: s, frame }
+                    if *s == session_id && frame.header.opcode_enum() == Some(Opcode::Error))
+            });
+
+            assert_eq!(validated.is_ok(), expect_ok, "{opcode:?}: validate gave {validated:?}");
+            assert_eq!(!rejected, expect_ok, "{opcode:?}: dispatch gave {actions:?}");
+        }
+    }
+
+    #[test]
+    fn room_admins_restrict_who_can_add_members() {
+        let mut server = admin_server();
+        let room_id = 0x100;
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 4 }).unwrap();
+        let frame = hello_frame(104, None);
+        server.process_event(ServerEvent::FrameReceived { session_id: 4, frame }).unwrap();
+        server.create_room(room_id, 2).unwrap();
+
+        // Only the operator can name the room's admins
+        let frame = admin_request(AdminRequest::SetRoomAdmins { room_id, admins: vec![102] });
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(admin_response(&actions), AdminResponse::RoomAdminsSet {
+            room_id,
+            admins: vec![102]
+        });
+
+        // A non-admin's add is refused and the invitee never sees the Welcome
+        let frame = welcome_frame(room_id, 103, 104);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 3, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+        assert!(
+            !actions
+                .iter()
+                .any(|action| matches!(action, ServerAction::SendToSession { session_id: 4, .. }))
+        );
+        assert!(!server.storage.members(room_id).unwrap().contains(&104));
+
+        // The admin's add goes through, and its commit makes the invitee a
+        // member
+        let frame = welcome_frame(room_id, 102, 104);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        assert!(
+            actions
+                .iter()
+                .any(|action| matches!(action, ServerAction::SendToSession { session_id: 4, .. }))
+        );
+        assert!(server.sessions_in_room(room_id).any(|session_id| session_id == 4));
+        let frame = add_commit_frame(&server.env, room_id, 102, 104);
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        assert!(server.storage.members(room_id).unwrap().contains(&104));
+    }
+
+    #[test]
+    fn room_meta_updates_stored_name_and_topic() {
+        let mut server = admin_server();
+        let room_id = 0x100;
+        server.create_public_room(room_id, 2, &RoomListing::default()).unwrap();
+
+        let room_meta = |sender_id, name: Option<&str>, topic: &str| {
+            let meta = RoomMeta { name: name.map(Into::into), topic: Some(topic.into()) };
+            let mut frame =
+                Payload::RoomMeta(meta).into_frame(FrameHeader::new(Opcode::RoomMeta)).unwrap();
+            frame.header.set_room_id(room_id);
+            frame.header.set_sender_id(sender_id);
+            frame
+        };
+        let stored = |server: &ServerDriver<MockEnv, MemoryStorage>| {
+            let metadata = server.storage.load_room_metadata(room_id).unwrap().unwrap();
+            (metadata.name, metadata.topic)
+        };
+
+        let frame = room_meta(102, Some("Plans"), "Q3");
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        assert_eq!(stored(&server), ("Plans".into(), "Q3".into()));
+        // The search index follows
+        assert_eq!(server.public_rooms[&room_id].name, "Plans");
+
+        // Fields left out keep their value
+        let frame = room_meta(102, None, "Q4");
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        assert_eq!(stored(&server), ("Plans".into(), "Q4".into()));
+
+        // Someone who isn't the room's admin is refused
+        let frame = room_meta(103, Some("Mine"), "hijacked");
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 3, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::FORBIDDEN);
+        assert_eq!(stored(&server), ("Plans".into(), "Q4".into()));
+    }
+
+    #[test]
+    fn room_at_member_cap_rejects_adds_and_external_joins() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let config = ServerConfig { max_members: Some(2), ..ServerConfig::default() };
+        let mut server = ServerDriver::new(env.clone(), storage.clone(), config);
+        let room_id = 0x100;
+
+        for (session_id, user_id) in [(1, 42), (2, 43), (3, 44), (45, 45)] {
+            server.process_event(ServerEvent::ConnectionAccepted { session_id }).unwrap();
+            server.registry.update_session_info(session_id, SessionInfo::authenticated(user_id));
+        }
+        server.create_room(room_id, 1).unwrap();
+
+        // Filling the room to the cap is allowed
+        let frame = add_commit_frame(&env, room_id, 42, 43);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(storage.members(room_id).unwrap(), vec![42, 43]);
+
+        // The next add is refused and the invitee never sees the Welcome
+        let frame = welcome_frame(room_id, 42, 44);
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::ROOM_FULL);
+        assert!(
+            !actions
+                .iter()
+                .any(|action| matches!(action, ServerAction::SendToSession { session_id: 3, .. }))
+        );
+        assert_eq!(storage.members(room_id).unwrap(), vec![42, 43]);
+        assert!(server.sessions_in_room(room_id).all(|session_id| session_id != 3));
+
+        // External joins count against the same cap
+        let mut header = FrameHeader::new(Opcode::ExternalCommit);
+        header.set_room_id(room_id);
+        header.set_sender_id(45);
+        let frame = Frame::new(header, Bytes::from("external commit"));
+        let actions =
+            server.process_event(ServerEvent::FrameReceived { session_id: 45, frame }).unwrap();
+        let Payload::Error(error) = sent_payload(&actions) else {
+            panic!("expected Error");
+        };
+        assert_eq!(error.code, ErrorPayload::ROOM_FULL);
+        // Only the commit that filled the room is stored
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(0));
+        assert_eq!(storage.members(room_id).unwrap(), vec![42, 43]);
+
+        // Existing members can still commit
+        let mut header = FrameHeader::new(Opcode::Commit);
+        header.set_room_id(room_id);
+        header.set_sender_id(43);
+        header.set_epoch(2);
+        let frame = Frame::new(header, Bytes::from("commit"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 2, frame }).unwrap();
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn failed_persist_is_not_broadcast_and_leaves_no_log_gap() {
+        let env = MockEnv::with_crypto_rng();
+        let inner = MemoryStorage::new();
+        let storage = ChaoticStorage::new(inner.clone(), 0.0);
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+        server.subscribe_to_room(2, room_id);
+
+        let send = |server: &mut ServerDriver<MockEnv, ChaoticStorage<MemoryStorage>>, text| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            let frame = Frame::new(header, Bytes::from_static(text));
+            server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap()
+        };
+        let broadcast = |actions: &[ServerAction<_>]| {
+            actions.iter().any(|action| matches!(action, ServerAction::Broadcast { .. }))
+        };
+
+        assert!(broadcast(&send(&mut server, b"stored")));
+        assert_eq!(inner.latest_log_index(room_id).unwrap(), Some(0));
+        // The creator has no authenticated session, so the message is unread
+        let unread = vec![RoomUnread { room_id, count: 1 }];
+        assert_eq!(server.unread.counts(1), unread);
+
+        // Every write fails with an I/O error while the next frame persists
+        server.storage = ChaoticStorage::new(inner.clone(), 1.0);
+        let actions = send(&mut server, b"lost");
+        assert!(!broadcast(&actions), "unpersisted frame was broadcast: {actions:?}");
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            ServerAction::Log { level: LogLevel::Error, message, .. } if message.contains("chaotic")
+        )));
+        assert_eq!(inner.latest_log_index(room_id).unwrap(), Some(0));
+        assert_eq!(server.unread.counts(1), unread);
+
+        // Once storage recovers, the log continues where storage left off
+        server.storage = ChaoticStorage::new(inner.clone(), 0.0);
+        assert!(broadcast(&send(&mut server, b"after")));
+        let frames = inner.load_frames(room_id, 0, 10).unwrap();
+        assert_eq!(frames.iter().map(|f| f.header.log_index()).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(frames[1].payload.as_ref(), b"after");
+    }
+
+    #[test]
+    fn frame_persists_with_checkpoint_and_membership_change() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage.clone(), ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.create_room(room_id, 1).unwrap();
+        storage.add_member(room_id, 7).unwrap();
+        storage.add_member(room_id, 8).unwrap();
+
+        let mut header = FrameHeader::new(Opcode::Commit);
+        header.set_room_id(room_id);
+        header.set_sender_id(1);
+        header.set_epoch(1);
+        let frame = Frame::new(header, Bytes::from("commit"));
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        let checkpoint = RoomCheckpoint { next_log_index: 1, epoch: Some(1) };
+        assert_eq!(storage.load_room_checkpoint(room_id).unwrap(), Some(checkpoint));
+
+        let kick = |user_id| {
+            let mut header = FrameHeader::new(Opcode::Kick);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            Payload::Kick(lockframe_proto::payloads::moderation::Kick {
+                user_id,
+                reason: String::new(),
+                moderator_id: 1,
+            })
+            .into_frame(header)
+            .unwrap()
+        };
+        let frame = kick(7);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(1));
+        assert_eq!(storage.members(room_id).unwrap(), vec![1, 8]);
+
+        // Another writer takes the next index, so persisting the kick
+        // conflicts and neither its membership change nor its checkpoint may
+        // be applied
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_log_index(2);
+        storage.store_frame(room_id, 2, &Frame::new(header, Bytes::new())).unwrap();
+
+        let frame = kick(8);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(2));
+        assert_eq!(storage.members(room_id).unwrap(), vec![1, 8]);
+        let checkpoint = RoomCheckpoint { next_log_index: 2, epoch: Some(1) };
+        assert_eq!(storage.load_room_checkpoint(room_id).unwrap(), Some(checkpoint));
+    }
+
+    #[test]
+    fn kick_removes_persisted_member() {
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut server = ServerDriver::new(env, storage, ServerConfig::default());
+        let room_id = 0x100;
+
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 1 }).unwrap();
+        server.process_event(ServerEvent::ConnectionAccepted { session_id: 2 }).unwrap();
+        server.registry.update_session_info(1, SessionInfo::authenticated(42));
+        server.registry.update_session_info(2, SessionInfo::authenticated(43));
+        server.create_room(room_id, 1).unwrap();
+        let frame = welcome_frame(room_id, 42, 43);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+
+        let kick = |user_id, sender_id| {
+            let mut header = FrameHeader::new(Opcode::Kick);
+            header.set_room_id(room_id);
+            header.set_sender_id(sender_id);
+            Payload::Kick(lockframe_proto::payloads::moderation::Kick {
+                user_id,
+                reason: String::new(),
+                moderator_id: sender_id,
+            })
+            .into_frame(header)
+            .unwrap()
+        };
+
+        // Moderator kicks a member
+        let frame = kick(43, 42);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert_eq!(server.storage().members(room_id).unwrap(), vec![42]);
+
+        // Self-kick is a leave
+        let frame = kick(42, 42);
+        server.process_event(ServerEvent::FrameReceived { session_id: 1, frame }).unwrap();
+        assert!(server.storage().members(room_id).unwrap().is_empty());
+    }
 }
@@ -2,7 +2,10 @@
 //!
 //! Provides in-memory storage for `KeyPackages` indexed by `user_id`.
 //! `KeyPackages` are consumed (deleted) after fetch to enforce one-time use.
-//! Enforces capacity limits with LRU eviction to prevent unbounded growth.
+//! Expired `KeyPackages` are never handed out;
+//! [`KeyPackageRegistry::prune_expired`] drops the rest, called periodically by
+//! the server. Enforces capacity limits with LRU eviction to prevent unbounded
+//! growth.
 
 #![allow(clippy::disallowed_types, reason = "Synchronous in-memory operations only")]
 #![allow(clippy::expect_used, reason = "Mutex poisoning should cause a panic")]
@@ -33,19 +36,34 @@ pub struct KeyPackageEntry {
     pub key_package_bytes: Vec<u8>,
     /// `KeyPackage` hash reference.
     pub hash_ref: Vec<u8>,
+    /// Unix time (seconds) from which the `KeyPackage` is no longer valid.
+    pub expires_at_secs: u64,
     /// Insertion timestamp for LRU tracking (simplified - using counter).
     timestamp: u64,
 }
 
 impl KeyPackageEntry {
-    /// Create a new `KeyPackageEntry`.
+    /// Create a new `KeyPackageEntry` that never expires.
     pub fn new(key_package_bytes: Vec<u8>, hash_ref: Vec<u8>) -> Self {
         Self {
             key_package_bytes,
             hash_ref,
+            expires_at_secs: u64::MAX,
             timestamp: 0, // Will be set by registry
         }
     }
+
+    /// Expire the entry at `expires_at_secs` (Unix seconds).
+    #[must_use]
+    pub fn with_expiry(mut self, expires_at_secs: u64) -> Self {
+        self.expires_at_secs = expires_at_secs;
+        self
+    }
+
+    /// `KeyPackage` is past its lifetime at `now_secs` (Unix seconds).
+    pub fn is_expired(&self, now_secs: u64) -> bool {
+        now_secs >= self.expires_at_secs
+    }
 }
 
 /// In-memory registry for `KeyPackages` with LRU eviction.
@@ -134,14 +152,14 @@ impl KeyPackageRegistry {
 
     /// Fetch and remove a `KeyPackage` for a user.
     ///
-    /// Returns `None` if no `KeyPackage` exists for this user.
-    /// Removes the `KeyPackage` after fetching (one-time use).
-    /// Also removes from LRU tracking.
+    /// Returns `None` if no unexpired `KeyPackage` exists for this user at
+    /// `now_secs` (Unix seconds). Removes the `KeyPackage` after fetching
+    /// (one-time use), and drops it unreturned if it has expired.
     ///
     /// # Panics
     ///
     /// Panics if the internal mutex is poisoned.
-    pub fn take(&self, user_id: u64) -> Option<KeyPackageEntry> {
+    pub fn take(&self, user_id: u64, now_secs: u64) -> Option<KeyPackageEntry> {
         let mut inner = self.inner.lock().expect("KeyPackageRegistry mutex poisoned");
 
         let entry = inner.entries.remove(&user_id);
@@ -150,7 +168,27 @@ impl KeyPackageRegistry {
             inner.lru_order.retain(|&id| id != user_id);
         }
 
-        entry
+        entry.filter(|entry| !entry.is_expired(now_secs))
+    }
+
+    /// Drop every `KeyPackage` expired at `now_secs` (Unix seconds), so stale
+    /// packages don't hold capacity until they are evicted.
+    ///
+    /// Returns the number of packages dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn prune_expired(&self, now_secs: u64) -> usize {
+        let mut inner = self.inner.lock().expect("KeyPackageRegistry mutex poisoned");
+
+        let before = inner.entries.len();
+        inner.entries.retain(|_, entry| !entry.is_expired(now_secs));
+        let inner = &mut *inner;
+        let entries = &inner.entries;
+        inner.lru_order.retain(|id| entries.contains_key(id));
+
+        before - inner.entries.len()
     }
 
     /// Check if a `KeyPackage` exists for a user (without consuming it).
@@ -205,7 +243,7 @@ mod tests {
         assert!(registry.has(42));
         assert_eq!(registry.count(), 1);
 
-        let entry = registry.take(42).expect("should have entry");
+        let entry = registry.take(42, 0).expect("should have entry");
         assert_eq!(entry.key_package_bytes, vec![1, 2, 3]);
         assert_eq!(entry.hash_ref, vec![4, 5, 6]);
 
@@ -217,7 +255,7 @@ mod tests {
     #[test]
     fn take_nonexistent_returns_none() {
         let registry = KeyPackageRegistry::new();
-        assert!(registry.take(999).is_none());
+        assert!(registry.take(999, 0).is_none());
     }
 
     #[test]
@@ -227,7 +265,7 @@ mod tests {
         registry.store(42, KeyPackageEntry::new(vec![1], vec![2]));
         registry.store(42, KeyPackageEntry::new(vec![3], vec![4]));
 
-        let entry = registry.take(42).expect("should have entry");
+        let entry = registry.take(42, 0).expect("should have entry");
         assert_eq!(entry.key_package_bytes, vec![3]);
     }
 
@@ -239,7 +277,7 @@ mod tests {
         registry1.store(42, KeyPackageEntry::new(vec![1], vec![2]));
 
         assert!(registry2.has(42));
-        let entry = registry2.take(42).expect("should have entry");
+        let entry = registry2.take(42, 0).expect("should have entry");
         assert_eq!(entry.key_package_bytes, vec![1]);
 
         assert!(!registry1.has(42));
@@ -291,7 +329,7 @@ mod tests {
         assert!(registry.has(1));
         assert!(registry.has(2));
 
-        let entry = registry.take(1).expect("should have entry");
+        let entry = registry.take(1, 0).expect("should have entry");
         assert_eq!(entry.key_package_bytes, vec![10]); // Updated entry
     }
 
@@ -315,4 +353,27 @@ mod tests {
         assert!(registry.has(3));
         assert!(registry.has(4));
     }
+
+    #[test]
+    fn expired_entries_are_withheld_and_pruned() {
+        let registry = KeyPackageRegistry::new();
+        let entry =
+            |expires_at_secs| KeyPackageEntry::new(vec![1], vec![2]).with_expiry(expires_at_secs);
+
+        registry.store(1, entry(100));
+        registry.store(2, entry(200));
+        registry.store(3, KeyPackageEntry::new(vec![3], vec![3]));
+        assert!(entry(100).is_expired(100));
+        assert!(!entry(100).is_expired(99));
+
+        // User 1's package expired and is dropped; the others are untouched
+        assert!(registry.take(1, 200).is_none());
+        assert!(!registry.has(1));
+        assert!(registry.has(2));
+
+        assert_eq!(registry.prune_expired(200), 1);
+        assert!(!registry.has(2));
+        assert!(registry.take(3, 200).is_some());
+        assert_eq!(registry.count(), 0);
+    }
 }
@@ -1,7 +1,8 @@
 //! Lockframe production server.
 //!
-//! Production server implementation using Quinn for QUIC transport, Tokio for
-//! async runtime, and system time with cryptographic RNG.
+//! Production server implementation using Quinn for QUIC transport (with an
+//! optional WebSocket listener), Tokio for async runtime, and system time with
+//! cryptographic RNG.
 //!
 //! # Architecture
 //!
@@ -13,49 +14,114 @@
 //! # Components
 //!
 //! - [`ServerDriver`]: Action-based orchestrator (pure logic, no I/O)
+//! - [`RoomShards`]: Per-room state, locked per shard so rooms process
+//!   concurrently
 //! - [`Server`]: Production runtime that executes `ServerDriver` actions
 //! - [`QuinnTransport`]: QUIC transport via Quinn library
+//! - [`WsListener`]: WebSocket listener for clients that can't use QUIC
 //! - [`SystemEnv`]: Production environment (real time, crypto RNG)
 
+mod auth;
 mod driver;
 mod error;
 mod key_package_registry;
+mod outbound;
+mod rate_limit;
 mod registry;
 mod room_manager;
+mod room_shards;
 pub mod sequencer;
 mod server_error;
 pub mod storage;
-mod system_env;
 mod transport;
+mod unread;
+mod ws;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
 
+pub use auth::{AdminToken, Authenticator, Capabilities, UsersOnly};
 use bytes::BytesMut;
-pub use driver::{LogLevel, ServerAction, ServerConfig as DriverConfig, ServerDriver, ServerEvent};
+pub use driver::{
+    FrameRoute, LogLevel, MAX_ROOM_SEARCH_QUERY_LEN, MAX_ROOM_SEARCH_RESULTS, RoomExport,
+    ServerAction, ServerConfig as DriverConfig, ServerDriver, ServerEvent,
+};
 pub use error::ServerError;
 pub use key_package_registry::{KeyPackageEntry, KeyPackageRegistry};
 use lockframe_core::env::Environment;
+pub use lockframe_core::system_env::SystemEnv;
 use lockframe_proto::{Frame, FrameHeader};
+pub use outbound::{OutboundQueue, Priority, drain};
+pub use rate_limit::RateLimiter;
 pub use registry::{ConnectionRegistry, SessionInfo};
-pub use room_manager::{RoomAction, RoomError, RoomManager, RoomMetadata};
-pub use sequencer::{Sequencer, SequencerAction, SequencerError};
+pub use room_manager::{
+    BroadcastPolicy, MESSAGE_ID_WINDOW, ProcessedFrame, ROOM_QUEUE_CAPACITY, RoomAction, RoomError,
+    RoomListing, RoomManager, RoomMetadata, RoomSettings,
+};
+pub use room_shards::{DEFAULT_ROOM_SHARDS, RoomShards};
+pub use sequencer::{Sequencer, SequencerAction, SequencerError, SequencerState};
 pub use server_error::{ExecutorError, ServerError as DriverError};
-pub use storage::{ChaoticStorage, MemoryStorage, Storage, StorageError};
-pub use system_env::SystemEnv;
-use tokio::sync::RwLock;
-pub use transport::{QuinnConnection, QuinnTransport};
+pub use storage::{
+    ChaoticStorage, MemoryStorage, RetentionPolicy, RoomPolicy, RoomSnapshot, Storage,
+    StorageBatch, StorageError,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::RwLock,
+    task::JoinHandle,
+};
+pub use transport::{QuinnConnection, QuinnTransport, TransportOptions};
+pub use unread::UnreadTracker;
+pub use ws::WsListener;
+use ws::{WsCloser, WsConnection};
 use zerocopy::FromBytes;
 
+/// How long a closing connection may take to flush its queued frames.
+///
+/// A peer that stops reading would otherwise keep the connection open.
+const CLOSE_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the driver is sent [`ServerEvent::Tick`].
+///
+/// Ticks drive heartbeats, idle timeouts, retention and audit flushes.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Shared state for all connections.
 ///
 /// This holds connection and stream maps for message routing.
 struct SharedState {
-    /// Map of session ID to QUIC connection (for closing)
-    connections: RwLock<HashMap<u64, QuinnConnection>>,
-    /// Map of session ID to persistent outbound stream
-    /// All messages to a client go through this single stream, ensuring
-    /// ordering.
-    outbound_streams: RwLock<HashMap<u64, tokio::sync::Mutex<quinn::SendStream>>>,
+    /// Map of session ID to its connection (for closing)
+    connections: RwLock<HashMap<u64, SessionConnection>>,
+    /// Map of session ID to its persistent outbound stream
+    /// All messages to a client go through this single stream, in order
+    /// within each priority.
+    outbound: RwLock<HashMap<u64, Outbound>>,
+}
+
+/// Connection a session arrived on.
+#[derive(Clone)]
+enum SessionConnection {
+    /// QUIC connection
+    Quic(QuinnConnection),
+    /// WebSocket connection
+    Ws(WsCloser),
+}
+
+impl SessionConnection {
+    /// Close the connection, giving `reason` where the transport carries one.
+    fn close(&self, reason: &str) {
+        match self {
+            Self::Quic(conn) => conn.close(0u32.into(), reason.as_bytes()),
+            Self::Ws(closer) => closer.close(),
+        }
+    }
+}
+
+/// Outbound side of one session.
+struct Outbound {
+    /// Queue feeding the session's outbound stream
+    queue: Arc<OutboundQueue>,
+    /// Task draining `queue`, done once it is closed and delivered
+    drain: JoinHandle<()>,
 }
 
 /// Server configuration for the production runtime.
@@ -69,6 +135,15 @@ pub struct ServerRuntimeConfig {
     pub key_path: Option<String>,
     /// Driver configuration (timeouts, limits)
     pub driver: DriverConfig,
+    /// Accept 0-RTT early data from resuming clients
+    ///
+    /// See [`TransportOptions::enable_0rtt`] for the replay risks.
+    pub enable_0rtt: bool,
+    /// Address to also accept WebSocket connections on (e.g.,
+    /// "0.0.0.0:8080"), or `None` for QUIC only
+    ///
+    /// The listener speaks plain `ws://`; see [`WsListener`].
+    pub ws_bind_address: Option<String>,
 }
 
 impl Default for ServerRuntimeConfig {
@@ -78,18 +153,23 @@ impl Default for ServerRuntimeConfig {
             cert_path: None,
             key_path: None,
             driver: DriverConfig::default(),
+            enable_0rtt: false,
+            ws_bind_address: None,
         }
     }
 }
 
 /// Production Lockframe server.
 ///
-/// Wraps `ServerDriver` with Quinn QUIC transport and system environment.
+/// Wraps `ServerDriver` with Quinn QUIC transport, an optional WebSocket
+/// listener, and system environment.
 pub struct Server {
     /// The action-based server driver
     driver: ServerDriver<SystemEnv, MemoryStorage>,
     /// QUIC endpoint
     transport: QuinnTransport,
+    /// WebSocket listener, if configured
+    ws: Option<WsListener>,
     /// Environment
     env: SystemEnv,
 }
@@ -101,49 +181,167 @@ impl Server {
         let storage = MemoryStorage::new();
         let driver = ServerDriver::new(env.clone(), storage, config.driver);
 
-        let transport =
-            QuinnTransport::bind(&config.bind_address, config.cert_path, config.key_path)?;
+        let options =
+            TransportOptions { enable_0rtt: config.enable_0rtt, ..TransportOptions::default() };
+        let transport = QuinnTransport::bind_with_options(
+            &config.bind_address,
+            config.cert_path,
+            config.key_path,
+            options,
+        )?;
+        let ws = config
+            .ws_bind_address
+            .map(|address| {
+                std::net::TcpListener::bind(&address).and_then(WsListener::from_std).map_err(|e| {
+                    ServerError::Transport(format!(
+                        "failed to bind WebSocket listener {address}: {e}"
+                    ))
+                })
+            })
+            .transpose()?;
+
+        Ok(Self { driver, transport, ws, env })
+    }
 
-        Ok(Self { driver, transport, env })
+    /// Use `authenticator` to grant rights to new sessions.
+    ///
+    /// See [`ServerDriver::with_authenticator`]. Defaults to [`UsersOnly`],
+    /// so no session is admin unless one is configured here.
+    #[must_use]
+    pub fn with_authenticator(self, authenticator: impl Authenticator) -> Self {
+        Self { driver: self.driver.with_authenticator(authenticator), ..self }
     }
 
     /// Run the server, accepting connections and processing frames.
     ///
-    /// This method runs until the server is shut down or an error occurs.
+    /// Runs until Ctrl-C, then shuts down as [`Self::run_until`] does.
     pub async fn run(self) -> Result<(), ServerError> {
+        self.run_until(async {
+            if let Err(e) = tokio::signal::ctrl_c().await {
+                tracing::error!("Failed to listen for Ctrl-C: {}", e);
+                std::future::pending::<()>().await;
+            }
+        })
+        .await
+    }
+
+    /// Run the server until `shutdown` completes.
+    ///
+    /// On shutdown the server stops accepting connections, says `Goodbye` to
+    /// every session (see [`ServerDriver::shutdown`]), and waits for the
+    /// connections to flush and close.
+    pub async fn run_until(self, shutdown: impl Future<Output = ()>) -> Result<(), ServerError> {
         tracing::info!("Server starting on {}", self.transport.local_addr()?);
+        if let Some(ws) = &self.ws {
+            tracing::info!("Accepting WebSocket connections on {}", ws_local_addr(ws)?);
+        }
 
         let env = self.env;
         let driver = Arc::new(tokio::sync::Mutex::new(self.driver));
         let shared = Arc::new(SharedState {
             connections: RwLock::new(HashMap::new()),
-            outbound_streams: RwLock::new(HashMap::new()),
+            outbound: RwLock::new(HashMap::new()),
         });
 
+        let ticker = tokio::spawn({
+            let driver = Arc::clone(&driver);
+            let shared = Arc::clone(&shared);
+            async move {
+                let mut interval = tokio::time::interval(TICK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let actions = driver.lock().await.process_event(ServerEvent::Tick);
+                    let result = match actions {
+                        Ok(actions) => execute_actions(actions, &shared).await,
+                        Err(e) => Err(e.into()),
+                    };
+                    if let Err(e) = result {
+                        tracing::error!("Tick failed: {}", e);
+                    }
+                }
+            }
+        });
+
+        let mut shutdown = std::pin::pin!(shutdown);
         loop {
-            match self.transport.accept().await {
-                Ok(conn) => {
-                    let driver = Arc::clone(&driver);
-                    let shared = Arc::clone(&shared);
-                    let env = env.clone();
-
-                    tokio::spawn(async move {
-                        if let Err(e) = handle_connection(conn, driver, shared, env).await {
-                            tracing::error!("Connection error: {}", e);
-                        }
-                    });
+            let ws_accept = async {
+                match &self.ws {
+                    Some(ws) => ws.accept().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                accepted = self.transport.accept() => match accepted {
+                    Ok(conn) => {
+                        let driver = Arc::clone(&driver);
+                        let shared = Arc::clone(&shared);
+                        let env = env.clone();
+
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_connection(conn, driver, shared, env).await {
+                                tracing::error!("Connection error: {}", e);
+                            }
+                        });
+                    },
+                    Err(e) => {
+                        tracing::error!("Accept error: {}", e);
+                    },
                 },
-                Err(e) => {
-                    tracing::error!("Accept error: {}", e);
+                accepted = ws_accept => match accepted {
+                    Ok(tcp) => {
+                        let driver = Arc::clone(&driver);
+                        let shared = Arc::clone(&shared);
+                        let env = env.clone();
+
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_ws_connection(tcp, driver, shared, env).await {
+                                tracing::error!("WebSocket connection error: {}", e);
+                            }
+                        });
+                    },
+                    Err(e) => {
+                        tracing::error!("WebSocket accept error: {}", e);
+                    },
                 },
+                () = &mut shutdown => break,
             }
         }
+
+        tracing::info!("Server shutting down");
+        ticker.abort();
+        let actions = driver.lock().await.shutdown("server shutting down");
+        execute_actions(actions, &shared).await?;
+
+        // Closing connections flush first, so allow for that plus QUIC's
+        // own close handshake
+        let idle = self.transport.wait_idle();
+        if tokio::time::timeout(CLOSE_FLUSH_TIMEOUT * 2, idle).await.is_err() {
+            tracing::warn!("Connections still open after shutdown");
+        }
+        Ok(())
     }
 
     /// Local address the server is bound to.
     pub fn local_addr(&self) -> Result<std::net::SocketAddr, ServerError> {
         self.transport.local_addr()
     }
+
+    /// Local address of the WebSocket listener, if one is configured.
+    pub fn ws_addr(&self) -> Result<Option<std::net::SocketAddr>, ServerError> {
+        self.ws.as_ref().map(ws_local_addr).transpose()
+    }
+
+    /// SHA-256 fingerprint of the server's TLS certificate.
+    ///
+    /// See [`QuinnTransport::cert_fingerprint`].
+    pub fn cert_fingerprint(&self) -> [u8; 32] {
+        self.transport.cert_fingerprint()
+    }
+}
+
+/// Local address of `ws`.
+fn ws_local_addr(ws: &WsListener) -> Result<std::net::SocketAddr, ServerError> {
+    ws.local_addr().map_err(|e| ServerError::Transport(e.to_string()))
 }
 
 /// Handle a single QUIC connection.
@@ -153,11 +351,7 @@ async fn handle_connection(
     shared: Arc<SharedState>,
     env: SystemEnv,
 ) -> Result<(), ServerError> {
-    let session_id = {
-        let mut buf = [0u8; 8];
-        env.random_bytes(&mut buf);
-        u64::from_le_bytes(buf)
-    };
+    let session_id = new_session_id(&env);
 
     tracing::debug!("New connection: {}", session_id);
 
@@ -166,31 +360,38 @@ async fn handle_connection(
         .await
         .map_err(|e| ServerError::Internal(format!("Failed to open outbound stream: {e}")))?;
 
-    {
-        let mut connections = shared.connections.write().await;
-        connections.insert(session_id, conn.clone());
-    }
-
-    {
-        let mut streams = shared.outbound_streams.write().await;
-        streams.insert(session_id, tokio::sync::Mutex::new(outbound_stream));
-    }
+    let queue = Arc::new(OutboundQueue::new());
+    let drain = tokio::spawn({
+        let queue = Arc::clone(&queue);
+        let mut outbound_stream = outbound_stream;
+        async move {
+            if let Err(e) = drain(&queue, &mut outbound_stream).await {
+                tracing::debug!("Outbound stream for {} failed: {}", session_id, e);
+                return;
+            }
+            // Closing the connection discards unacknowledged data, so wait
+            // until the peer has everything
+            if let Err(e) = outbound_stream.stopped().await {
+                tracing::debug!("Outbound stream for {} not delivered: {}", session_id, e);
+            }
+        }
+    });
 
-    let actions = {
-        let mut driver = driver.lock().await;
-        driver.process_event(ServerEvent::ConnectionAccepted { session_id })?
-    };
-    execute_actions(actions, &shared).await?;
+    let connection = SessionConnection::Quic(conn.clone());
+    start_session(session_id, connection, Outbound { queue, drain }, &driver, &shared).await?;
 
     loop {
         match conn.accept_bi().await {
             Ok((send, recv)) => {
+                drop(send); // not used for now
                 let driver = Arc::clone(&driver);
                 let shared = Arc::clone(&shared);
 
+                let env = env.clone();
+
                 tokio::spawn(async move {
-                    if let Err(e) = handle_stream(session_id, send, recv, driver, &shared).await {
-                        tracing::debug!("Stream error: {}", e);
+                    if let Err(e) = handle_stream(session_id, recv, driver, &shared, env).await {
+                        close_on_error(session_id, &e, &shared).await;
                     }
                 });
             },
@@ -201,37 +402,118 @@ async fn handle_connection(
         }
     }
 
-    {
-        let mut connections = shared.connections.write().await;
-        connections.remove(&session_id);
+    end_session(session_id, &driver, &shared).await
+}
+
+/// Handle a single WebSocket connection.
+///
+/// The socket carries one inbound frame stream, and the session's outbound
+/// frames go back over it.
+async fn handle_ws_connection(
+    tcp: tokio::net::TcpStream,
+    driver: Arc<tokio::sync::Mutex<ServerDriver<SystemEnv, MemoryStorage>>>,
+    shared: Arc<SharedState>,
+    env: SystemEnv,
+) -> Result<(), ServerError> {
+    let conn = WsConnection::accept(tcp)
+        .await
+        .map_err(|e| ServerError::Transport(format!("WebSocket upgrade failed: {e}")))?;
+    let session_id = new_session_id(&env);
+
+    tracing::debug!("New WebSocket connection: {}", session_id);
+
+    let connection = SessionConnection::Ws(conn.closer());
+    let (inbound, sink) = conn.into_parts();
+    let queue = Arc::new(OutboundQueue::new());
+    let drain = tokio::spawn({
+        let queue = Arc::clone(&queue);
+        async move {
+            if let Err(e) = sink.drain(&queue).await {
+                tracing::debug!("WebSocket for {} failed: {}", session_id, e);
+            }
+        }
+    });
+
+    start_session(session_id, connection, Outbound { queue, drain }, &driver, &shared).await?;
+
+    if let Err(e) = handle_stream(session_id, inbound, Arc::clone(&driver), &shared, env).await {
+        close_on_error(session_id, &e, &shared).await;
     }
 
-    {
-        let mut streams = shared.outbound_streams.write().await;
-        streams.remove(&session_id);
+    end_session(session_id, &driver, &shared).await
+}
+
+/// Random ID for a new session.
+fn new_session_id(env: &SystemEnv) -> u64 {
+    let mut buf = [0u8; 8];
+    env.random_bytes(&mut buf);
+    u64::from_le_bytes(buf)
+}
+
+/// Register a new session's connection and outbound queue, and tell the
+/// driver it connected.
+async fn start_session(
+    session_id: u64,
+    connection: SessionConnection,
+    outbound: Outbound,
+    driver: &tokio::sync::Mutex<ServerDriver<SystemEnv, MemoryStorage>>,
+    shared: &SharedState,
+) -> Result<(), ServerError> {
+    shared.connections.write().await.insert(session_id, connection);
+    shared.outbound.write().await.insert(session_id, outbound);
+
+    let actions =
+        driver.lock().await.process_event(ServerEvent::ConnectionAccepted { session_id })?;
+    execute_actions(actions, shared).await
+}
+
+/// Drop a closed session's connection and outbound queue, and tell the
+/// driver it disconnected.
+async fn end_session(
+    session_id: u64,
+    driver: &tokio::sync::Mutex<ServerDriver<SystemEnv, MemoryStorage>>,
+    shared: &SharedState,
+) -> Result<(), ServerError> {
+    shared.connections.write().await.remove(&session_id);
+    if let Some(outbound) = shared.outbound.write().await.remove(&session_id) {
+        outbound.queue.close();
     }
 
-    let actions = {
-        let mut driver = driver.lock().await;
-        driver.process_event(ServerEvent::ConnectionClosed {
-            session_id,
-            reason: "connection closed".to_string(),
-        })?
-    };
-    execute_actions(actions, &shared).await?;
+    let actions = driver.lock().await.process_event(ServerEvent::ConnectionClosed {
+        session_id,
+        reason: "connection closed".to_string(),
+    })?;
+    execute_actions(actions, shared).await
+}
 
-    Ok(())
+/// Close a session whose inbound stream failed with `error`.
+async fn close_on_error(session_id: u64, error: &ServerError, shared: &SharedState) {
+    tracing::warn!("Closing session {} after stream error: {}", session_id, error);
+    let close = ServerAction::CloseConnection { session_id, reason: error.to_string() };
+    if let Err(e) = execute_actions(vec![close], shared).await {
+        tracing::debug!("Failed to close session {}: {}", session_id, e);
+    }
 }
 
-/// Handle a single bidirectional stream.
-async fn handle_stream(
+/// Handle a single inbound frame stream.
+///
+/// Frames the driver refuses are answered with an `Error` frame. A frame the
+/// driver can't process at all ends the stream with the error, and the caller
+/// closes the session.
+async fn handle_stream<R>(
     session_id: u64,
-    send: quinn::SendStream,
-    mut recv: quinn::RecvStream,
+    mut recv: R,
     driver: Arc<tokio::sync::Mutex<ServerDriver<SystemEnv, MemoryStorage>>>,
     shared: &Arc<SharedState>,
-) -> Result<(), ServerError> {
-    drop(send); // not used for now
+    env: SystemEnv,
+) -> Result<(), ServerError>
+where
+    R: AsyncRead + Unpin,
+{
+    let (rooms, storage) = {
+        let driver = driver.lock().await;
+        (driver.rooms(), driver.storage().clone())
+    };
 
     let mut buf = BytesMut::with_capacity(65536);
 
@@ -240,7 +522,7 @@ async fn handle_stream(
         buf.resize(128, 0);
 
         match recv.read_exact(&mut buf[..128]).await {
-            Ok(()) => {},
+            Ok(_) => {},
             Err(e) => {
                 tracing::debug!("Read error: {}", e);
                 break;
@@ -254,10 +536,10 @@ async fn handle_stream(
             break;
         };
 
-        let payload_size = header.payload_size() as usize;
+        let body_size = header.body_size();
 
-        if payload_size > 0 {
-            buf.resize(128 + payload_size, 0);
+        if body_size > 0 {
+            buf.resize(128 + body_size, 0);
             if let Err(e) = recv.read_exact(&mut buf[128..]).await {
                 tracing::debug!("Payload read error: {}", e);
                 break;
@@ -266,21 +548,37 @@ async fn handle_stream(
 
         let frame = match Frame::decode(&buf) {
             Ok(f) => f,
-            Err(e) => {
-                tracing::warn!("Frame decode error: {}", e);
+            Err(error) => {
+                tracing::warn!("Frame decode error: {}", error);
+                let actions = driver
+                    .lock()
+                    .await
+                    .process_event(ServerEvent::FrameDecodeFailed { session_id, error })?;
+                execute_actions(actions, shared).await?;
                 break;
             },
         };
 
-        let actions = {
-            let mut driver = driver.lock().await;
-            match driver.process_event(ServerEvent::FrameReceived { session_id, frame }) {
-                Ok(actions) => actions,
-                Err(e) => {
-                    tracing::warn!("Frame processing error: {}", e);
-                    continue;
-                },
-            }
+        // Route and enqueue under the driver lock, so a room's queue holds its
+        // frames in receive order across connections. Sequencing then runs
+        // under only the room's shard lock so busy rooms don't block others.
+        let route = driver.lock().await.route_frame(session_id, frame)?;
+        let actions = match route {
+            FrameRoute::Handled(actions) => actions,
+            FrameRoute::Room { room_id, mut actions } => {
+                // Each enqueue is paired with one dequeue; the frame processed
+                // here may have been received by another connection. The shard
+                // lock is held across storage I/O, so keep it off the runtime
+                let (rooms, storage, now) = (rooms.clone(), storage.clone(), env.now());
+                let processed =
+                    tokio::task::spawn_blocking(move || rooms.process_next(room_id, now, &storage))
+                        .await
+                        .map_err(|e| ServerError::Internal(format!("room task failed: {e}")))?;
+                if let Some(processed) = processed {
+                    actions.extend(driver.lock().await.finish_room_frame(processed));
+                }
+                actions
+            },
         };
 
         execute_actions(actions, shared).await?;
@@ -300,12 +598,9 @@ async fn execute_actions(
                 let mut buf = Vec::new();
                 frame.encode(&mut buf).map_err(|e| ServerError::Protocol(e.to_string()))?;
 
-                let streams = shared.outbound_streams.read().await;
-                if let Some(stream_mutex) = streams.get(&session_id) {
-                    let mut stream = stream_mutex.lock().await;
-                    if let Err(e) = stream.write_all(&buf).await {
-                        tracing::warn!("SendToSession write failed for {}: {}", session_id, e);
-                    }
+                let outbound = shared.outbound.read().await;
+                if let Some(outbound) = outbound.get(&session_id) {
+                    outbound.queue.push(Priority::of(&frame), frame.header.room_id(), buf.into());
                 } else {
                     tracing::warn!("SendToSession: session {} not found", session_id);
                 }
@@ -314,23 +609,24 @@ async fn execute_actions(
             ServerAction::Broadcast { session_ids, frame } => {
                 let mut buf = Vec::new();
                 frame.encode(&mut buf).map_err(|e| ServerError::Protocol(e.to_string()))?;
+                let priority = Priority::of(&frame);
+                let room_id = frame.header.room_id();
+                let buf = bytes::Bytes::from(buf);
 
-                let streams = shared.outbound_streams.read().await;
+                let outbound = shared.outbound.read().await;
                 for session_id in session_ids {
-                    if let Some(stream_mutex) = streams.get(&session_id) {
-                        let mut stream = stream_mutex.lock().await;
-                        if let Err(e) = stream.write_all(&buf).await {
-                            tracing::warn!("Broadcast write failed for {}: {}", session_id, e);
-                        }
+                    if let Some(outbound) = outbound.get(&session_id) {
+                        outbound.queue.push(priority, room_id, buf.clone());
                     }
                 }
             },
 
             ServerAction::CloseConnection { session_id, reason } => {
                 tracing::info!("Closing connection {}: {}", session_id, reason);
-                let mut connections = shared.connections.write().await;
-                if let Some(conn) = connections.remove(&session_id) {
-                    conn.close(0u32.into(), reason.as_bytes());
+                let conn = shared.connections.write().await.remove(&session_id);
+                let outbound = shared.outbound.write().await.remove(&session_id);
+                if let Some(conn) = conn {
+                    tokio::spawn(close_after_flush(conn, outbound, reason));
                 }
             },
 
@@ -345,3 +641,15 @@ async fn execute_actions(
 
     Ok(())
 }
+
+/// Close `conn` once the frames already queued for it, such as a `Goodbye`,
+/// are delivered, or after [`CLOSE_FLUSH_TIMEOUT`].
+async fn close_after_flush(conn: SessionConnection, outbound: Option<Outbound>, reason: String) {
+    if let Some(Outbound { queue, drain }) = outbound {
+        queue.close();
+        if tokio::time::timeout(CLOSE_FLUSH_TIMEOUT, drain).await.is_err() {
+            tracing::debug!("Outbound stream not flushed before close: {}", reason);
+        }
+    }
+    conn.close(&reason);
+}
@@ -8,12 +8,24 @@
 //!
 //! # Start with TLS certificate (production)
 //! lockframe-server --bind 0.0.0.0:4433 --cert cert.pem --key key.pem
+//!
+//! # Also accept WebSocket clients (terminate TLS in front for wss://)
+//! lockframe-server --bind 0.0.0.0:4433 --ws-bind 0.0.0.0:8080
 //! ```
+//!
+//! Sessions whose Hello carries the token in `LOCKFRAME_ADMIN_TOKEN` are
+//! granted admin. Without it, no session is.
 
 use clap::Parser;
-use lockframe_server::{DriverConfig, Server, ServerRuntimeConfig};
+use lockframe_server::{AdminToken, DriverConfig, Server, ServerRuntimeConfig};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
+/// Environment variable holding the admin token.
+///
+/// Read from the environment rather than the command line, where other
+/// users could see it in the process list.
+const ADMIN_TOKEN_VAR: &str = "LOCKFRAME_ADMIN_TOKEN";
+
 /// Lockframe protocol server
 #[derive(Parser, Debug)]
 #[command(name = "lockframe-server")]
@@ -32,10 +44,18 @@ struct Args {
     #[arg(short, long)]
     key: Option<String>,
 
+    /// Address to accept WebSocket connections on (plain ws://)
+    #[arg(long)]
+    ws_bind: Option<String>,
+
     /// Maximum concurrent connections
     #[arg(long, default_value = "10000")]
     max_connections: usize,
 
+    /// Accept 0-RTT early data from resuming clients (replayable)
+    #[arg(long)]
+    enable_0rtt: bool,
+
     /// Log level (trace, debug, info, warn, error)
     #[arg(long, default_value = "info")]
     log_level: String,
@@ -62,9 +82,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         cert_path: args.cert,
         key_path: args.key,
         driver: DriverConfig { max_connections: args.max_connections, ..Default::default() },
+        enable_0rtt: args.enable_0rtt,
+        ws_bind_address: args.ws_bind,
     };
 
-    let server = Server::bind(config)?;
+    let mut server = Server::bind(config)?;
+    match std::env::var(ADMIN_TOKEN_VAR) {
+        Ok(token) if !token.is_empty() => {
+            server = server.with_authenticator(AdminToken::new(token));
+        },
+        _ => tracing::info!("{} not set - admin requests are disabled", ADMIN_TOKEN_VAR),
+    }
 
     tracing::info!("Server listening on {}", server.local_addr()?);
 
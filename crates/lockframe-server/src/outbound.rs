@@ -0,0 +1,231 @@
+//! Per-session outbound queues with control-frame priority.
+//!
+//! Every frame for a session goes through one [`OutboundQueue`], drained into
+//! the session's stream by [`drain`]. When the stream is backed up (a slow
+//! reader, QUIC flow control), frames wait in the queue instead of blocking
+//! the executor, and MLS control frames (commits, welcomes, group info) jump
+//! ahead of queued application traffic for other rooms so members don't fall
+//! behind on epochs while a bulk backlog drains.
+//!
+//! Frames keep their order within a [`Priority`], and frames for the same room
+//! are never reordered: a room's commit can't overtake that room's earlier
+//! messages, which were encrypted for the epoch it ends. A frame already being
+//! written is never interrupted.
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+};
+
+use bytes::Bytes;
+use lockframe_proto::{Frame, Opcode};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::Notify,
+};
+
+/// Which lane of an [`OutboundQueue`] a frame takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// MLS group operations, sent before queued data for other rooms.
+    Control,
+    /// Everything else, in order.
+    Data,
+}
+
+impl Priority {
+    /// Classify a frame by opcode.
+    pub fn of(frame: &Frame) -> Self {
+        match frame.header.opcode_enum() {
+            Some(
+                Opcode::Proposal
+                | Opcode::Commit
+                | Opcode::Welcome
+                | Opcode::GroupInfo
+                | Opcode::PSKProposal
+                | Opcode::ReInit
+                | Opcode::ExternalCommit,
+            ) => Self::Control,
+            _ => Self::Data,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Queued {
+    priority: Priority,
+    room_id: u128,
+    frame: Bytes,
+}
+
+#[derive(Debug, Default)]
+struct Lanes {
+    /// Frames in send order
+    frames: VecDeque<Queued>,
+    closed: bool,
+}
+
+/// Encoded frames waiting to be written to one session.
+#[derive(Debug, Default)]
+pub struct OutboundQueue {
+    lanes: Mutex<Lanes>,
+    ready: Notify,
+}
+
+impl OutboundQueue {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue an encoded frame for `room_id`. Ignored once the queue is closed.
+    ///
+    /// A control frame goes after every queued control frame and every queued
+    /// frame for its own room, ahead of the rest.
+    pub fn push(&self, priority: Priority, room_id: u128, frame: Bytes) {
+        {
+            let mut lanes = self.lanes();
+            if lanes.closed {
+                return;
+            }
+            let position = match priority {
+                Priority::Control => lanes
+                    .frames
+                    .iter()
+                    .rposition(|q| q.priority == Priority::Control || q.room_id == room_id)
+                    .map_or(0, |i| i + 1),
+                Priority::Data => lanes.frames.len(),
+            };
+            lanes.frames.insert(position, Queued { priority, room_id, frame });
+        }
+        self.ready.notify_one();
+    }
+
+    /// Stop accepting frames. [`drain`] finishes once the queue is empty.
+    pub fn close(&self) {
+        self.lanes().closed = true;
+        self.ready.notify_one();
+    }
+
+    /// Number of frames waiting.
+    pub fn len(&self) -> usize {
+        self.lanes().frames.len()
+    }
+
+    /// Whether no frames are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wait for the next frame in send order.
+    ///
+    /// Returns `None` once the queue is closed and empty.
+    pub(crate) async fn next(&self) -> Option<Bytes> {
+        loop {
+            {
+                let mut lanes = self.lanes();
+                if let Some(queued) = lanes.frames.pop_front() {
+                    return Some(queued.frame);
+                }
+                if lanes.closed {
+                    return None;
+                }
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    fn lanes(&self) -> std::sync::MutexGuard<'_, Lanes> {
+        self.lanes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Write queued frames to `stream` until the queue is closed and empty.
+///
+/// # Errors
+///
+/// Returns the first write error; frames still queued are dropped.
+pub async fn drain<W>(queue: &OutboundQueue, mut stream: W) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(frame) = queue.next().await {
+        stream.write_all(&frame).await?;
+    }
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lockframe_proto::FrameHeader;
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    fn encoded(opcode: Opcode, room_id: u128, payload_len: usize) -> (Priority, u128, Bytes) {
+        let mut frame = Frame::new(FrameHeader::new(opcode), vec![0u8; payload_len]);
+        frame.header.set_room_id(room_id);
+        let mut buf = Vec::new();
+        frame.encode(&mut buf).unwrap();
+        (Priority::of(&frame), room_id, Bytes::from(buf))
+    }
+
+    #[test]
+    fn control_plane_opcodes_are_prioritized() {
+        for opcode in [Opcode::Commit, Opcode::Welcome, Opcode::GroupInfo, Opcode::ExternalCommit] {
+            assert_eq!(encoded(opcode, 1, 0).0, Priority::Control, "{opcode:?}");
+        }
+        for opcode in [Opcode::AppMessage, Opcode::Error, Opcode::HelloReply, Opcode::Typing] {
+            assert_eq!(encoded(opcode, 1, 0).0, Priority::Data, "{opcode:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn commit_overtakes_only_other_rooms_behind_saturated_stream() {
+        const APP_LEN: usize = 1024;
+        let queue = Arc::new(OutboundQueue::new());
+        let (writer, mut reader) = tokio::io::duplex(APP_LEN);
+
+        for room_id in [1, 1, 2, 1] {
+            let (priority, room_id, frame) = encoded(Opcode::AppMessage, room_id, APP_LEN);
+            queue.push(priority, room_id, frame);
+        }
+        let drainer = tokio::spawn({
+            let queue = Arc::clone(&queue);
+            async move { drain(&queue, writer).await }
+        });
+
+        // The first app message fills the duplex and stalls the writer
+        while queue.len() > 3 {
+            tokio::task::yield_now().await;
+        }
+        for room_id in [2, 3] {
+            let (priority, room_id, commit) = encoded(Opcode::Commit, room_id, 0);
+            queue.push(priority, room_id, commit);
+        }
+        queue.close();
+
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).await.unwrap();
+        drainer.await.unwrap().unwrap();
+
+        let mut sent = Vec::new();
+        let mut rest = &received[..];
+        while !rest.is_empty() {
+            let frame = Frame::decode(rest).unwrap();
+            rest = &rest[FrameHeader::SIZE + frame.payload.len()..];
+            sent.push((frame.header.opcode_enum().unwrap(), frame.header.room_id()));
+        }
+        // Room 2's commit waits for room 2's message; room 3's follows it
+        assert_eq!(sent, [
+            (Opcode::AppMessage, 1),
+            (Opcode::AppMessage, 1),
+            (Opcode::AppMessage, 2),
+            (Opcode::Commit, 2),
+            (Opcode::Commit, 3),
+            (Opcode::AppMessage, 1),
+        ]);
+    }
+}
@@ -0,0 +1,88 @@
+//! Per-user rate limiting.
+//!
+//! A sliding window: each user may act at most `limit` times in any
+//! `window`. The server uses it for room creation, where every request writes
+//! to storage and public rooms are announced to every directory subscriber.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Sub,
+    time::Duration,
+};
+
+/// Sliding-window rate limiter keyed by user ID.
+#[derive(Debug, Clone)]
+pub struct RateLimiter<I> {
+    /// Most actions allowed per window
+    limit: usize,
+    /// Length of the window
+    window: Duration,
+    /// `user_id` → times of the actions still inside the window, oldest first
+    actions: HashMap<u64, VecDeque<I>>,
+}
+
+impl<I> RateLimiter<I>
+where
+    I: Copy + Ord + Sub<Output = Duration>,
+{
+    /// Allow `limit` actions per user in any `window`.
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self { limit, window, actions: HashMap::new() }
+    }
+
+    /// Record an action by `user_id` at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns how long until the user may act again if they are over the
+    /// limit. Refused actions are not recorded.
+    pub fn check(&mut self, user_id: u64, now: I) -> Result<(), Duration> {
+        let window = self.window;
+        let actions = self.actions.entry(user_id).or_default();
+        while actions.front().is_some_and(|&at| now - at >= window) {
+            actions.pop_front();
+        }
+
+        if actions.len() >= self.limit {
+            let retry_after =
+                actions.front().map_or(window, |&oldest| window.saturating_sub(now - oldest));
+            return Err(retry_after);
+        }
+        actions.push_back(now);
+        Ok(())
+    }
+
+    /// Forget users whose actions have all left the window.
+    pub fn prune(&mut self, now: I) {
+        let window = self.window;
+        self.actions.retain(|_, actions| actions.back().is_some_and(|&at| now - at < window));
+    }
+
+    /// Number of users with actions inside the window.
+    pub fn user_count(&self) -> usize {
+        self.actions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_past_limit_until_window_slides() {
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(10));
+        let at = Duration::from_secs;
+
+        assert_eq!(limiter.check(1, at(0)), Ok(()));
+        assert_eq!(limiter.check(1, at(4)), Ok(()));
+        assert_eq!(limiter.check(1, at(6)), Err(at(4)));
+        assert_eq!(limiter.check(2, at(6)), Ok(()));
+        assert_eq!(limiter.check(1, at(10)), Ok(()));
+        assert_eq!(limiter.check(1, at(11)), Err(at(3)));
+
+        limiter.prune(at(16));
+        assert_eq!(limiter.user_count(), 1);
+        limiter.prune(at(20));
+        assert_eq!(limiter.user_count(), 0);
+    }
+}
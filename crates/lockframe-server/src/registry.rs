@@ -7,7 +7,9 @@
 //! Sessions must explicitly subscribe to rooms - no lazy room creation. When
 //! you unregister a session, we automatically remove all its subscriptions.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::auth::Capabilities;
 
 /// Information about a registered session.
 #[derive(Debug, Clone)]
@@ -16,6 +18,8 @@ pub struct SessionInfo {
     pub user_id: Option<u64>,
     /// Whether the session has completed handshake
     pub authenticated: bool,
+    /// What the authenticator allows this session to do
+    pub capabilities: Capabilities,
 }
 
 impl Default for SessionInfo {
@@ -27,12 +31,23 @@ impl Default for SessionInfo {
 impl SessionInfo {
     /// Create a new unauthenticated session info.
     pub fn new() -> Self {
-        Self { user_id: None, authenticated: false }
+        Self { user_id: None, authenticated: false, capabilities: Capabilities::NONE }
     }
 
-    /// Create an authenticated session info with user ID.
+    /// Create an authenticated session info with user ID and regular user
+    /// capabilities.
     pub fn authenticated(user_id: u64) -> Self {
-        Self { user_id: Some(user_id), authenticated: true }
+        Self::authenticated_with(user_id, Capabilities::USER)
+    }
+
+    /// Create an authenticated session info with user ID and `capabilities`.
+    pub fn authenticated_with(user_id: u64, capabilities: Capabilities) -> Self {
+        Self { user_id: Some(user_id), authenticated: true, capabilities }
+    }
+
+    /// Create an authenticated session info with admin rights.
+    pub fn admin(user_id: u64) -> Self {
+        Self::authenticated_with(user_id, Capabilities::USER | Capabilities::ADMIN)
     }
 }
 
@@ -53,6 +68,8 @@ pub struct ConnectionRegistry {
     session_rooms: HashMap<u64, HashSet<u128>>,
     /// User ID → session ID (reverse index). Enforces one session per user
     user_sessions: HashMap<u64, u64>,
+    /// Sessions subscribed to public room announcements
+    directory_subscribers: BTreeSet<u64>,
 }
 
 impl ConnectionRegistry {
@@ -96,6 +113,7 @@ impl ConnectionRegistry {
         if let Some(user_id) = info.user_id {
             self.user_sessions.remove(&user_id);
         }
+        self.directory_subscribers.remove(&session_id);
 
         for room_id in &rooms {
             if let Some(subscribers) = self.room_subscriptions.get_mut(room_id) {
@@ -201,6 +219,31 @@ impl ConnectionRegistry {
         self.session_rooms.get(&session_id).into_iter().flat_map(|r| r.iter().copied())
     }
 
+    /// All rooms subscribed by any session authenticated as `user_id`.
+    pub fn rooms_for_user(&self, user_id: u64) -> BTreeSet<u128> {
+        self.sessions
+            .iter()
+            .filter(|(_, info)| info.user_id == Some(user_id))
+            .flat_map(|(session_id, _)| self.rooms_for_session(*session_id))
+            .collect()
+    }
+
+    /// Subscribe a session to public room announcements.
+    ///
+    /// Returns `false` if the session is not registered.
+    pub fn subscribe_directory(&mut self, session_id: u64) -> bool {
+        if !self.sessions.contains_key(&session_id) {
+            return false;
+        }
+        self.directory_subscribers.insert(session_id);
+        true
+    }
+
+    /// Sessions subscribed to public room announcements, in ascending order.
+    pub fn directory_subscribers(&self) -> impl Iterator<Item = u64> + '_ {
+        self.directory_subscribers.iter().copied()
+    }
+
     /// Find session ID for a given user ID.
     ///
     /// Used for routing Welcome frames to specific recipients.
@@ -219,6 +262,65 @@ impl ConnectionRegistry {
     pub fn room_session_count(&self, room_id: u128) -> usize {
         self.room_subscriptions.get(&room_id).map_or(0, HashSet::len)
     }
+
+    /// Total number of (session, room) subscriptions.
+    pub fn subscription_count(&self) -> usize {
+        self.room_subscriptions.values().map(HashSet::len).sum()
+    }
+
+    /// Check that every index references only registered sessions and that
+    /// the room → sessions and session → rooms maps mirror each other.
+    ///
+    /// Returns a description of the first inconsistency found. Meant for
+    /// debug assertions and tests; it walks every subscription.
+    pub fn verify_consistency(&self) -> Result<(), String> {
+        for (room_id, sessions) in &self.room_subscriptions {
+            if sessions.is_empty() {
+                return Err(format!("room {room_id:032x} has an empty subscriber set"));
+            }
+            for session_id in sessions {
+                if !self.sessions.contains_key(session_id) {
+                    return Err(format!(
+                        "room {room_id:032x} subscribed by unregistered session {session_id}"
+                    ));
+                }
+                if !self.session_rooms.get(session_id).is_some_and(|r| r.contains(room_id)) {
+                    return Err(format!(
+                        "session {session_id} missing room {room_id:032x} in its room set"
+                    ));
+                }
+            }
+        }
+
+        for (session_id, rooms) in &self.session_rooms {
+            if !self.sessions.contains_key(session_id) {
+                return Err(format!("room set kept for unregistered session {session_id}"));
+            }
+            for room_id in rooms {
+                if !self.is_subscribed(*session_id, *room_id) {
+                    return Err(format!(
+                        "room {room_id:032x} missing session {session_id} in its subscribers"
+                    ));
+                }
+            }
+        }
+
+        for (user_id, session_id) in &self.user_sessions {
+            if self.sessions.get(session_id).and_then(|info| info.user_id) != Some(*user_id) {
+                return Err(format!(
+                    "user {user_id} mapped to session {session_id} of another user"
+                ));
+            }
+        }
+
+        if let Some(session_id) =
+            self.directory_subscribers.iter().find(|s| !self.sessions.contains_key(s))
+        {
+            return Err(format!("directory subscribed by unregistered session {session_id}"));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -238,6 +340,39 @@ mod tests {
         assert!(info.user_id.is_none());
     }
 
+    #[test]
+    fn counts_and_consistency_follow_subscriptions() {
+        let mut registry = ConnectionRegistry::new();
+        registry.register_session(1, SessionInfo::authenticated(42));
+        registry.register_session(2, SessionInfo::new());
+        registry.subscribe(1, 100);
+        registry.subscribe(1, 200);
+        registry.subscribe(2, 100);
+        registry.subscribe_directory(2);
+
+        assert_eq!(registry.session_count(), 2);
+        assert_eq!(registry.subscription_count(), 3);
+        assert_eq!(registry.verify_consistency(), Ok(()));
+
+        registry.unsubscribe(1, 200);
+        registry.unregister_session(2);
+        assert_eq!(registry.subscription_count(), 1);
+        assert_eq!(registry.verify_consistency(), Ok(()));
+    }
+
+    #[test]
+    fn verify_consistency_detects_leaked_subscription() {
+        let mut registry = ConnectionRegistry::new();
+        registry.register_session(1, SessionInfo::new());
+        registry.subscribe(1, 100);
+
+        // A cleanup path that forgets the room index
+        registry.sessions.remove(&1);
+        registry.session_rooms.remove(&1);
+
+        assert!(registry.verify_consistency().unwrap_err().contains("unregistered session 1"));
+    }
+
     #[test]
     fn register_duplicate_session_fails() {
         let mut registry = ConnectionRegistry::new();
@@ -443,4 +578,18 @@ mod tests {
         assert_eq!(registry.session_id_for_user(42), None); // Reverse index cleaned up
         assert!(rooms.is_empty());
     }
+
+    #[test]
+    fn unregister_session_drops_directory_subscription() {
+        let mut registry = ConnectionRegistry::new();
+
+        assert!(!registry.subscribe_directory(1));
+        registry.register_session(1, SessionInfo::authenticated(42));
+        registry.register_session(2, SessionInfo::authenticated(99));
+        assert!(registry.subscribe_directory(1));
+        assert!(registry.subscribe_directory(2));
+
+        registry.unregister_session(1);
+        assert_eq!(registry.directory_subscribers().collect::<Vec<_>>(), vec![2]);
+    }
 }
@@ -5,17 +5,40 @@
 //! Clients own the MLS group state; the server just sequences and broadcasts.
 //!
 //! Rooms must be explicitly created (no lazy creation) to prevent accidental
-//! rooms and enable future auth. `RoomMetadata` is an extension point for
-//! permissions/roles.
+//! rooms and enable future auth. Each room carries a [`RoomPolicy`]; when it
+//! names admins, only they may add or remove other members.
+//!
+//! Rooms that carry stored MLS group state (imported rooms) have the
+//! signatures of member frames checked against it while frames are still in
+//! that state's epoch, and commits must be signed by their author's key. The
+//! state follows the room as commits are sequenced, and picks up the keys of
+//! new members from each `GroupInfo` published for the current epoch. Parsed
+//! member keys are cached per room, so each key is decompressed once per
+//! epoch rather than once per frame.
+//!
+//! Frames can be submitted through a bounded per-room queue
+//! ([`RoomManager::enqueue`] / [`RoomManager::process_next`]) so a room's
+//! frames are sequenced in receive order even when the task that drains the
+//! queue is not the one that received the frame.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
-use lockframe_core::env::Environment;
-use lockframe_proto::Frame;
+use lockframe_core::{
+    env::Environment,
+    message_ids::RecentMessageIds,
+    mls::{CommitMembership, MlsGroupState, MlsValidator, ValidationResult, VerifyingKeyCache},
+};
+use lockframe_proto::{
+    Frame, Opcode, Payload,
+    payloads::{ErrorPayload, app::EncryptedMessage},
+};
 
 use crate::{
-    sequencer::{Sequencer, SequencerAction, SequencerError},
-    storage::{Storage, StorageError, StoredRoomMetadata},
+    sequencer::{RoomCheckpoint, Sequencer, SequencerAction, SequencerError},
+    storage::{
+        RetentionPolicy, RoomPolicy, RoomSnapshot, Storage, StorageBatch, StorageError,
+        StoredRoomMetadata,
+    },
 };
 
 /// Metadata about a room (extension point for future authorization)
@@ -25,15 +48,213 @@ pub struct RoomMetadata {
     pub creator: u64, // UserId
     /// Unix timestamp (seconds since epoch) when room was created.
     pub created_at_secs: u64,
-    // Future: admins, members, permissions
+    /// Whether the room is listed in the room directory
+    pub public: bool,
+    /// Who may change the room's membership
+    pub policy: RoomPolicy,
+}
+
+impl RoomMetadata {
+    /// Whether `user_id` administers the room: one of its admins, or its
+    /// creator while no admins are assigned.
+    ///
+    /// Stricter than [`RoomPolicy::may_change_membership`], which lets anyone
+    /// add and remove members while no admins are assigned.
+    pub fn is_admin(&self, user_id: u64) -> bool {
+        if self.policy.admins.is_empty() {
+            user_id == self.creator
+        } else {
+            self.policy.admins.contains(&user_id)
+        }
+    }
+}
+
+/// How a public room is listed in the room directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoomListing {
+    /// Display name
+    pub name: String,
+    /// Short description of what the room is about
+    pub topic: String,
+}
+
+/// Everything a room is created with besides its creator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoomSettings {
+    /// Whether the room is listed in the room directory
+    pub public: bool,
+    /// Name and topic, stored whether or not the room is public
+    pub listing: RoomListing,
+    /// Members allowed to add and remove members. Empty means anyone may
+    /// change membership, while admin-only actions fall to the creator (see
+    /// [`RoomMetadata::is_admin`]).
+    pub admins: HashSet<u64>,
+    /// How long the room's frames are kept
+    pub retention: RetentionPolicy,
 }
 
+/// Maximum frames waiting in one room's processing queue.
+pub const ROOM_QUEUE_CAPACITY: usize = 1024;
+
+/// `AppMessage` IDs remembered per room for duplicate suppression.
+pub const MESSAGE_ID_WINDOW: usize = 4096;
+
+/// Frames read per page when rebuilding a recovered room's expiry schedule.
+const EXPIRY_SCAN_PAGE: usize = 256;
+
 /// Routes frames between clients, assigns log indices.
 pub struct RoomManager {
     /// Frame sequencer (assigns log indices)
     sequencer: Sequencer,
     /// Room metadata (for future authorization)
     room_metadata: HashMap<u128, RoomMetadata>,
+    /// Frames waiting to be sequenced, per room, in receive order
+    queues: HashMap<u128, VecDeque<(u64, Frame)>>,
+    /// Recently sequenced `AppMessage` IDs, per room
+    message_ids: HashMap<u128, RecentMessageIds>,
+    /// Sequenced `AppMessage` frames awaiting pruning, as
+    /// `(expires_at, room_id, log_index)`
+    expiring: BTreeSet<(u64, u128, u64)>,
+    /// Stored MLS state and parsed member keys, per room that has state
+    signers: HashMap<u128, RoomSigners>,
+    /// Persisted members of each loaded room, kept in step with storage
+    members: HashMap<u128, BTreeSet<u64>>,
+    /// Member cap per room (`None` = unlimited)
+    max_members: Option<usize>,
+}
+
+/// Member keys for checking frame signatures in one room.
+#[derive(Debug)]
+struct RoomSigners {
+    /// Group state as of the room's latest sequenced frame
+    state: MlsGroupState,
+    /// Keys parsed from `state` so far
+    keys: VerifyingKeyCache,
+}
+
+impl RoomSigners {
+    /// Reason `frame` fails signature checks, or `None`.
+    ///
+    /// Only frames members sign and stamp with the stored state's epoch are
+    /// checked; the server can't follow the group into later epochs.
+    fn rejection(&mut self, frame: &Frame) -> Option<String> {
+        let signed = matches!(
+            frame.header.opcode_enum(),
+            Some(
+                Opcode::AppMessage
+                    | Opcode::AppEdit
+                    | Opcode::Redact
+                    | Opcode::RoomMeta
+                    | Opcode::Typing
+            )
+        );
+        if !signed || frame.header.epoch() != self.state.epoch {
+            return None;
+        }
+        match MlsValidator::validate_frame(frame, self.state.epoch, &self.state, &mut self.keys) {
+            ValidationResult::Accept => None,
+            ValidationResult::Reject { reason } => Some(reason),
+        }
+    }
+
+    /// Reason a `Commit` fails author checks, or `None`.
+    ///
+    /// The commit itself is opaque, but its author must be a member with a
+    /// signature key in the group state, and the frame header must carry a
+    /// valid signature from that key.
+    fn commit_rejection(&self, frame: &Frame) -> Option<String> {
+        let room_id = frame.header.room_id();
+        let sender_id = frame.header.sender_id();
+        if !self.state.member_keys.contains_key(&sender_id) {
+            return Some(format!(
+                "commit sender {sender_id} is not a member of room {room_id:032x}"
+            ));
+        }
+
+        match MlsValidator::validate_signature(frame, &self.state) {
+            ValidationResult::Accept => None,
+            ValidationResult::Reject { reason } => Some(reason),
+        }
+    }
+
+    /// Group state after `frame` was sequenced, or `None` if it is unchanged.
+    ///
+    /// Commits move the state to the room's new `epoch`, and removed members
+    /// lose their keys. Added members have no key until a `GroupInfo` for
+    /// the new epoch is published (see [`RoomManager::set_group_state`]).
+    fn state_after(
+        &self,
+        frame: &Frame,
+        epoch: Option<u64>,
+        changes: &[MembershipChange],
+    ) -> Option<MlsGroupState> {
+        let commit =
+            matches!(frame.header.opcode_enum(), Some(Opcode::Commit | Opcode::ExternalCommit));
+        if !commit && changes.is_empty() {
+            return None;
+        }
+
+        let mut state = self.state.clone();
+        if commit && let Some(epoch) = epoch {
+            state.epoch = epoch;
+        }
+        for change in changes {
+            match *change {
+                MembershipChange::Add(user_id) => {
+                    if !state.is_member(user_id) {
+                        state.members.push(user_id);
+                    }
+                },
+                MembershipChange::Remove(user_id) => {
+                    state.members.retain(|&member| member != user_id);
+                    state.member_keys.remove(&user_id);
+                },
+            }
+        }
+        Some(state)
+    }
+}
+
+/// Which room members receive a broadcast frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BroadcastPolicy {
+    /// Every subscribed session, including the sender (echo confirms
+    /// sequencing).
+    IncludeAll,
+    /// Every subscribed session except the sender.
+    ExcludeSender,
+    /// Only the sender's session.
+    SenderOnly,
+}
+
+impl BroadcastPolicy {
+    /// Default policy for a frame with the given opcode.
+    ///
+    /// Ephemeral signals (receipts, typing, presence) go to the sender's
+    /// peers only; everything else is echoed back so the sender learns its
+    /// assigned log index.
+    pub fn for_opcode(opcode: Option<Opcode>) -> Self {
+        match opcode {
+            Some(Opcode::AppReceipt | Opcode::Typing | Opcode::Presence) => Self::ExcludeSender,
+            _ => Self::IncludeAll,
+        }
+    }
+
+    /// Filter room members down to the sessions this policy delivers to.
+    pub fn recipients(
+        self,
+        room_sessions: impl IntoIterator<Item = u64>,
+        sender_session_id: u64,
+    ) -> Vec<u64> {
+        room_sessions
+            .into_iter()
+            .filter(|&session_id| match self {
+                Self::IncludeAll => true,
+                Self::ExcludeSender => session_id != sender_session_id,
+                Self::SenderOnly => session_id == sender_session_id,
+            })
+            .collect()
+    }
 }
 
 /// Actions returned by `RoomManager` for driver to execute.
@@ -47,8 +268,8 @@ pub enum RoomAction<I = std::time::Instant> {
         room_id: u128,
         /// Frame to broadcast
         frame: Frame,
-        /// Whether to exclude the original sender
-        exclude_sender: bool,
+        /// Which room members receive the frame
+        policy: BroadcastPolicy,
         /// When the frame was processed by the server
         processed_at: I,
     },
@@ -61,6 +282,8 @@ pub enum RoomAction<I = std::time::Instant> {
         log_index: u64,
         /// Frame to persist
         frame: Frame,
+        /// Sequencer state after this frame, persisted with it
+        checkpoint: RoomCheckpoint,
         /// When the frame was processed by the server
         processed_at: I,
     },
@@ -71,6 +294,8 @@ pub enum RoomAction<I = std::time::Instant> {
         sender_id: u64,
         /// Reason for rejection
         reason: String,
+        /// `ErrorPayload` code sent back to the sender
+        code: u16,
         /// When the rejection occurred
         processed_at: I,
     },
@@ -85,11 +310,26 @@ pub enum RoomAction<I = std::time::Instant> {
         frames: Vec<Vec<u8>>,
         /// Whether more frames are available
         has_more: bool,
+        /// Pruned history, if the request started before the retained frames
+        snapshot: Option<RoomSnapshot>,
         /// When the response was prepared
         processed_at: I,
     },
 }
 
+/// Outcome of sequencing a queued frame with [`RoomManager::process_next`].
+#[derive(Debug)]
+pub struct ProcessedFrame<I = std::time::Instant> {
+    /// Room the frame was queued for
+    pub room_id: u128,
+    /// Session that submitted the frame
+    pub session_id: u64,
+    /// `request_id` the sender stamped on the frame
+    pub request_id: u32,
+    /// Result of [`RoomManager::process_frame`] for the frame
+    pub result: Result<Vec<RoomAction<I>>, RoomError>,
+}
+
 /// Errors from `RoomManager` operations
 #[derive(Debug, thiserror::Error)]
 pub enum RoomError {
@@ -108,12 +348,398 @@ pub enum RoomError {
     /// Room already exists
     #[error("Room already exists: {0:032x}")]
     RoomAlreadyExists(u128),
+
+    /// Room's processing queue is at capacity
+    #[error("Room queue full: {0:032x}")]
+    QueueFull(u128),
+
+    /// Room already has its maximum number of members
+    #[error("Room full: {room_id:032x} ({max_members} members)")]
+    RoomFull {
+        /// Room that is full
+        room_id: u128,
+        /// Configured member cap
+        max_members: usize,
+    },
+
+    /// Processing would reject the frame with this error
+    #[error("Frame rejected: {}", .0.message)]
+    Rejected(ErrorPayload),
+
+    /// Room restricts membership changes to its admins
+    #[error("User {user_id} may not change membership of room {room_id:032x}")]
+    Forbidden {
+        /// Room whose membership was to change
+        room_id: u128,
+        /// Non-admin who tried to change it
+        user_id: u64,
+    },
 }
 
 impl RoomManager {
     /// Create a new `RoomManager`
     pub fn new() -> Self {
-        Self { sequencer: Sequencer::new(), room_metadata: HashMap::new() }
+        Self::with_max_members(None)
+    }
+
+    /// Create a `RoomManager` that caps each room at `max_members` members.
+    ///
+    /// `None` leaves rooms unbounded.
+    pub fn with_max_members(max_members: Option<usize>) -> Self {
+        Self {
+            sequencer: Sequencer::new(),
+            room_metadata: HashMap::new(),
+            queues: HashMap::new(),
+            message_ids: HashMap::new(),
+            expiring: BTreeSet::new(),
+            signers: HashMap::new(),
+            members: HashMap::new(),
+            max_members,
+        }
+    }
+
+    /// Check that `user_id` may become a member of `room_id`.
+    ///
+    /// Existing members always pass. Membership is the persisted member set,
+    /// kept from the adds and removes commits declare, external joins, and
+    /// kicks.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::RoomFull` if the room is at its member cap
+    /// - `RoomError::Storage` if membership can't be loaded
+    pub fn check_member_capacity(
+        &self,
+        room_id: u128,
+        user_id: u64,
+        storage: &impl Storage,
+    ) -> Result<(), RoomError> {
+        let Some(max_members) = self.max_members else {
+            return Ok(());
+        };
+
+        let members = storage.members(room_id)?;
+        if members.len() >= max_members && !members.contains(&user_id) {
+            return Err(RoomError::RoomFull { room_id, max_members });
+        }
+        Ok(())
+    }
+
+    /// Check that `sender_id` may deliver a Welcome adding `recipient_id` to
+    /// `room_id`.
+    ///
+    /// Adds are capped and authorized on their commit, but the Welcome can
+    /// arrive before that commit is sequenced, so it is held to the same
+    /// checks: the room exists, the sender is a persisted member, the room
+    /// has space for the recipient, and the sender may change membership. A
+    /// room that isn't loaded is checked against storage.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::RoomNotFound` if the room is neither loaded nor stored
+    /// - `RoomError::Forbidden` if the sender isn't a member or may not add
+    /// - `RoomError::RoomFull` if the room is at its member cap
+    /// - `RoomError::Storage` if the room, membership, or policy can't be
+    ///   loaded
+    pub fn check_welcome(
+        &self,
+        room_id: u128,
+        sender_id: u64,
+        recipient_id: u64,
+        storage: &impl Storage,
+    ) -> Result<(), RoomError> {
+        if !self.has_room(room_id) && storage.load_room_metadata(room_id)?.is_none() {
+            return Err(RoomError::RoomNotFound(room_id));
+        }
+        if !storage.members(room_id)?.contains(&sender_id) {
+            return Err(RoomError::Forbidden { room_id, user_id: sender_id });
+        }
+        self.check_member_capacity(room_id, recipient_id, storage)?;
+        self.check_membership_change(room_id, sender_id, storage)
+    }
+
+    /// Check that `sender_id` may publish a `GroupInfo` for `room_id` at
+    /// `epoch`.
+    ///
+    /// External joiners build their commit on the stored `GroupInfo`, so it
+    /// has to come from a persisted member and describe the room's current
+    /// epoch: the one its last sequenced commit created, or 0 before any.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::RoomNotFound` if the room is neither loaded nor stored
+    /// - `RoomError::Forbidden` if the sender isn't a member
+    /// - `RoomError::Rejected` with a `STALE_EPOCH` error if `epoch` isn't the
+    ///   room's current epoch
+    /// - `RoomError::Storage` or `RoomError::Sequencing` if the room's
+    ///   membership or epoch can't be loaded
+    pub fn check_group_info(
+        &self,
+        room_id: u128,
+        sender_id: u64,
+        epoch: u64,
+        storage: &impl Storage,
+    ) -> Result<(), RoomError> {
+        if !self.has_room(room_id) && storage.load_room_metadata(room_id)?.is_none() {
+            return Err(RoomError::RoomNotFound(room_id));
+        }
+        if !storage.members(room_id)?.contains(&sender_id) {
+            return Err(RoomError::Forbidden { room_id, user_id: sender_id });
+        }
+
+        let current = self.sequencer.epoch(room_id, storage)?.unwrap_or(0);
+        if epoch != current {
+            return Err(RoomError::Rejected(ErrorPayload::stale_epoch(room_id, epoch, current)));
+        }
+        Ok(())
+    }
+
+    /// Check that `user_id` may add or remove members of `room_id`.
+    ///
+    /// Enforced on the adds and removes a commit declares (see
+    /// [`CommitMembership`]), on the Welcome that delivers an add, and on the
+    /// Kick that removes someone else. Leaving and joining externally are
+    /// always allowed.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::Forbidden` if the room has admins and `user_id` is not one
+    /// - `RoomError::Storage` if the policy of an unloaded room can't be loaded
+    pub fn check_membership_change(
+        &self,
+        room_id: u128,
+        user_id: u64,
+        storage: &impl Storage,
+    ) -> Result<(), RoomError> {
+        let allowed = match self.room_metadata.get(&room_id) {
+            Some(metadata) => metadata.policy.may_change_membership(user_id),
+            None => storage
+                .load_room_policy(room_id)?
+                .is_none_or(|policy| policy.may_change_membership(user_id)),
+        };
+        if allowed { Ok(()) } else { Err(RoomError::Forbidden { room_id, user_id }) }
+    }
+
+    /// Replace the admins of `room_id`, returning them in ascending order.
+    ///
+    /// An empty set lets every member change membership again. The policy is
+    /// persisted before the in-memory copy is updated.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::RoomNotFound` if the room doesn't exist
+    /// - `RoomError::Storage` if the policy can't be persisted
+    pub fn set_admins(
+        &mut self,
+        room_id: u128,
+        admins: impl IntoIterator<Item = u64>,
+        storage: &impl Storage,
+    ) -> Result<Vec<u64>, RoomError> {
+        self.recover_room(room_id, storage)?;
+
+        let policy = RoomPolicy { admins: admins.into_iter().collect() };
+        storage.store_room_policy(room_id, &policy)?;
+
+        let mut admins: Vec<u64> = policy.admins.iter().copied().collect();
+        admins.sort_unstable();
+        if let Some(metadata) = self.room_metadata.get_mut(&room_id) {
+            metadata.policy = policy;
+        }
+        Ok(admins)
+    }
+
+    /// Error for a `RoomMeta` frame that may not be sequenced, or `None`.
+    ///
+    /// The payload must decode and the sender must be a room admin (see
+    /// [`RoomMetadata::is_admin`]). The room must already be loaded. The
+    /// driver has already pinned the header `sender_id` to the session's user.
+    fn room_meta_rejection(&self, frame: &Frame) -> Option<ErrorPayload> {
+        if !matches!(Payload::from_frame(frame), Ok(Payload::RoomMeta(_))) {
+            return Some(ErrorPayload::invalid_payload("undecodable RoomMeta payload"));
+        }
+
+        let room_id = frame.header.room_id();
+        let sender_id = frame.header.sender_id();
+        let is_admin = self.room_metadata.get(&room_id).is_some_and(|m| m.is_admin(sender_id));
+        (!is_admin).then(|| {
+            ErrorPayload::forbidden(format!(
+                "only room admins may change the name or topic of room {room_id:032x}"
+            ))
+        })
+    }
+
+    /// Reason to reject a `Redact` frame, or `None` if it may be sequenced.
+    ///
+    /// Only a message's author or a room admin (see
+    /// [`RoomMetadata::is_admin`]) may redact it, and the payload must name
+    /// the sender as moderator. The driver has already pinned the header
+    /// `sender_id` to the session's user.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::Storage` if the target message can't be loaded
+    fn redact_rejection(
+        &self,
+        frame: &Frame,
+        storage: &impl Storage,
+    ) -> Result<Option<ErrorPayload>, RoomError> {
+        let Ok(Payload::Redact(redact)) = Payload::from_frame(frame) else {
+            return Ok(Some(ErrorPayload::invalid_payload("undecodable Redact payload")));
+        };
+
+        let room_id = frame.header.room_id();
+        let sender_id = frame.header.sender_id();
+        if redact.moderator_id != sender_id {
+            return Ok(Some(ErrorPayload::forbidden(format!(
+                "redaction from {sender_id} claims moderator {}",
+                redact.moderator_id
+            ))));
+        }
+
+        let target_log_index = redact.message_log_index;
+        let Some(target) = storage
+            .load_frames(room_id, target_log_index, 1)?
+            .into_iter()
+            .find(|f| f.header.log_index() == target_log_index)
+        else {
+            return Ok(Some(ErrorPayload::frame_rejected(format!(
+                "redaction target {target_log_index} not found"
+            ))));
+        };
+
+        let author_id = target.header.sender_id();
+        let is_admin = self.room_metadata.get(&room_id).is_some_and(|m| m.is_admin(sender_id));
+        Ok((author_id != sender_id && !is_admin).then(|| {
+            ErrorPayload::forbidden(format!(
+                "sender {sender_id} may not redact message {target_log_index} from {author_id}"
+            ))
+        }))
+    }
+
+    /// Reason `sender_id` may not kick `target` from `room_id`, or `None`.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::Storage` if membership or the room policy can't be loaded
+    fn kick_rejection(
+        &self,
+        room_id: u128,
+        sender_id: u64,
+        target: u64,
+        storage: &impl Storage,
+    ) -> Result<Option<String>, RoomError> {
+        if !storage.members(room_id)?.contains(&sender_id) {
+            return Ok(Some(format!("user {sender_id} is not a member of room {room_id:032x}")));
+        }
+        if target == sender_id {
+            return Ok(None);
+        }
+        Ok(match self.check_membership_change(room_id, sender_id, storage) {
+            Ok(()) => None,
+            Err(RoomError::Forbidden { .. }) => {
+                Some(format!("only room admins may kick members of room {room_id:032x}"))
+            },
+            Err(e) => return Err(e),
+        })
+    }
+
+    /// Error for a `Commit` that may not be sequenced, or `None`.
+    ///
+    /// Commits move the room's epoch, so the sender must be a persisted
+    /// member; otherwise anyone could wedge the room on a forged epoch. In
+    /// rooms with MLS group state the commit must also be signed by the
+    /// sender's key there. The commit itself is encrypted, so adds and removes
+    /// of others are authorized from its [`CommitMembership`] declaration,
+    /// which members refuse to merge if it doesn't match. Declared adds are
+    /// held to the member cap here, before any Welcome for them is
+    /// delivered. The driver has already pinned the header `sender_id` to
+    /// the session's user.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::Storage` if membership or the room policy can't be loaded
+    fn commit_rejection(
+        &self,
+        frame: &Frame,
+        storage: &impl Storage,
+    ) -> Result<Option<ErrorPayload>, RoomError> {
+        let room_id = frame.header.room_id();
+        let sender_id = frame.header.sender_id();
+        let members = storage.members(room_id)?;
+        if !members.contains(&sender_id) {
+            return Ok(Some(ErrorPayload::forbidden(format!(
+                "commit sender {sender_id} is not a member of room {room_id:032x}"
+            ))));
+        }
+        if let Some(reason) =
+            self.signers.get(&room_id).and_then(|signers| signers.commit_rejection(frame))
+        {
+            return Ok(Some(ErrorPayload::mls_error(reason)));
+        }
+
+        let membership = CommitMembership::from_frame(frame);
+        let changes_others =
+            !membership.added.is_empty() || membership.removed.iter().any(|&id| id != sender_id);
+        if !changes_others {
+            return Ok(None);
+        }
+        match self.check_membership_change(room_id, sender_id, storage) {
+            Ok(()) => {},
+            Err(RoomError::Forbidden { .. }) => {
+                return Ok(Some(ErrorPayload::forbidden(format!(
+                    "only room admins may add or remove members of room {room_id:032x}"
+                ))));
+            },
+            Err(e) => return Err(e),
+        }
+
+        let Some(max_members) = self.max_members else {
+            return Ok(None);
+        };
+        // Only growth is refused, so a room over a lowered cap can still
+        // shrink or re-add existing members
+        let before = members.len();
+        let mut after: HashSet<u64> = members.into_iter().collect();
+        after.extend(&membership.added);
+        for removed in &membership.removed {
+            after.remove(removed);
+        }
+        Ok((after.len() > before && after.len() > max_members)
+            .then(|| ErrorPayload::room_full(room_id, max_members)))
+    }
+
+    /// Error for an `ExternalCommit` that may not be sequenced, or `None`.
+    ///
+    /// The joiner needs no prior membership, but joining adds them, so the
+    /// member cap applies. The commit must also be built on the latest
+    /// published `GroupInfo`: one from an older epoch conflicts with the
+    /// commits sequenced since, and members could not merge it.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::Storage` if membership or `GroupInfo` can't be loaded
+    fn external_commit_rejection(
+        &self,
+        frame: &Frame,
+        storage: &impl Storage,
+    ) -> Result<Option<ErrorPayload>, RoomError> {
+        let room_id = frame.header.room_id();
+        match self.check_member_capacity(room_id, frame.header.sender_id(), storage) {
+            Ok(()) => {},
+            Err(RoomError::RoomFull { max_members, .. }) => {
+                return Ok(Some(ErrorPayload::room_full(room_id, max_members)));
+            },
+            Err(e) => return Err(e),
+        }
+
+        let epoch = frame.header.epoch();
+        Ok(match storage.load_group_info(room_id)? {
+            Some((current, _)) if current == epoch => None,
+            Some((current, _)) => Some(ErrorPayload::stale_epoch(room_id, epoch, current)),
+            None => Some(ErrorPayload::frame_rejected(format!(
+                "no GroupInfo published for room {room_id:032x}"
+            ))),
+        })
     }
 
     /// Check if a room exists
@@ -121,36 +747,125 @@ impl RoomManager {
         self.room_metadata.contains_key(&room_id)
     }
 
+    /// Persisted members of a loaded room, or `None` if it isn't loaded.
+    pub fn members(&self, room_id: u128) -> Option<&BTreeSet<u64>> {
+        self.members.get(&room_id)
+    }
+
+    /// Apply membership changes the driver persisted for a loaded room.
+    pub(crate) fn apply_membership(&mut self, room_id: u128, changes: &[MembershipChange]) {
+        let Some(members) = self.members.get_mut(&room_id) else {
+            return;
+        };
+        for change in changes {
+            match *change {
+                MembershipChange::Add(user_id) => members.insert(user_id),
+                MembershipChange::Remove(user_id) => members.remove(&user_id),
+            };
+        }
+    }
+
+    /// MLS group state of a loaded room once `frame` is persisted, or `None`
+    /// if the room has none or `frame` leaves it unchanged.
+    ///
+    /// `epoch` is the room's epoch after `frame`. The driver stages the
+    /// result in the frame's storage batch, then hands it to
+    /// [`Self::set_group_state`].
+    pub(crate) fn group_state_after(
+        &self,
+        frame: &Frame,
+        epoch: Option<u64>,
+        changes: &[MembershipChange],
+    ) -> Option<MlsGroupState> {
+        let signers = self.signers.get(&frame.header.room_id())?;
+        signers.state_after(frame, epoch, changes)
+    }
+
+    /// Replace a loaded room's MLS group state once it is persisted.
+    ///
+    /// Called after each frame that changes it, and when a member publishes
+    /// a `GroupInfo` for the room's current epoch, which carries the keys of
+    /// members added since.
+    pub(crate) fn set_group_state(&mut self, room_id: u128, state: MlsGroupState) {
+        if let Some(signers) = self.signers.get_mut(&room_id) {
+            *signers = RoomSigners { state, keys: VerifyingKeyCache::new() };
+        }
+    }
+
     /// Creates a room with the specified ID and records the creator for
     /// future authorization checks. Prevents duplicate room creation.
     ///
     /// Persists room metadata to storage first, then updates in-memory state.
     /// The storage persistence is idempotent (won't overwrite existing rooms).
+    /// Rooms with a `listing` are public. Returns the metadata that was stored.
     pub fn create_room(
         &mut self,
         room_id: u128,
         creator: u64,
+        listing: Option<&RoomListing>,
         env: &impl Environment,
         storage: &impl Storage,
-    ) -> Result<(), RoomError> {
+    ) -> Result<StoredRoomMetadata, RoomError> {
+        let settings = RoomSettings {
+            public: listing.is_some(),
+            listing: listing.cloned().unwrap_or_default(),
+            ..RoomSettings::default()
+        };
+        self.create_room_with_settings(room_id, creator, settings, env, storage)
+    }
+
+    /// Like [`Self::create_room`], with every setting a `CreateRoom` request
+    /// can carry.
+    ///
+    /// The metadata, the creator's membership and any admins are written in
+    /// one storage batch, so a failure leaves no half-created room.
+    pub fn create_room_with_settings(
+        &mut self,
+        room_id: u128,
+        creator: u64,
+        settings: RoomSettings,
+        env: &impl Environment,
+        storage: &impl Storage,
+    ) -> Result<StoredRoomMetadata, RoomError> {
         if self.has_room(room_id) {
             return Err(RoomError::RoomAlreadyExists(room_id));
         }
 
         let created_at_secs = env.wall_clock_secs();
-        let stored_metadata = StoredRoomMetadata { creator, created_at_secs };
-        storage.create_room(room_id, &stored_metadata)?;
+        let public = settings.public;
+        let stored_metadata = StoredRoomMetadata {
+            creator,
+            created_at_secs,
+            public,
+            name: settings.listing.name,
+            topic: settings.listing.topic,
+            retention: settings.retention,
+        };
+        let admins = (!settings.admins.is_empty()).then(|| RoomPolicy { admins: settings.admins });
+        storage.batch(|batch| {
+            batch.create_room(room_id, &stored_metadata)?;
+            batch.add_member(room_id, creator)?;
+            admins.as_ref().map_or(Ok(()), |policy| batch.store_room_policy(room_id, policy))
+        })?;
 
-        let metadata = RoomMetadata { creator, created_at_secs };
+        let policy = match admins {
+            Some(policy) => policy,
+            None => storage.load_room_policy(room_id)?.unwrap_or_default(),
+        };
+        let metadata = RoomMetadata { creator, created_at_secs, public, policy };
         self.room_metadata.insert(room_id, metadata);
+        self.members.insert(room_id, BTreeSet::from([creator]));
 
-        Ok(())
+        Ok(stored_metadata)
     }
 
     /// Handle a sync request from a client.
     ///
     /// Loads frames from storage starting at `from_log_index` and returns
     /// a `SendSyncResponse` action for the driver to send back to the client.
+    /// Messages that expired at or before `wall_clock` (Unix milliseconds) are
+    /// left out. A request starting before frames pruned by the retention
+    /// policy is answered with the room's snapshot and the retained frames.
     pub fn handle_sync_request<I: Copy>(
         &self,
         room_id: u128,
@@ -158,37 +873,48 @@ impl RoomManager {
         from_log_index: u64,
         limit: usize,
         now: I,
+        wall_clock: u64,
         storage: &impl Storage,
     ) -> Result<RoomAction<I>, RoomError> {
         if !self.has_room(room_id) {
             return Err(RoomError::RoomNotFound(room_id));
         }
 
-        let frames = storage.load_frames(room_id, from_log_index, limit)?;
+        let snapshot =
+            storage.load_snapshot(room_id)?.filter(|s| from_log_index < s.first_log_index);
+        let mut from_log_index = snapshot.as_ref().map_or(from_log_index, |s| s.first_log_index);
 
-        let frame_bytes: Vec<Vec<u8>> = frames
-            .iter()
-            .map(|f| {
+        // Expired messages are withheld, so a page holding nothing else is
+        // skipped rather than sent empty with `has_more`, which clients read
+        // as a stalled sync
+        let latest_index = storage.latest_log_index(room_id)?;
+        let mut frame_bytes = Vec::new();
+        let has_more = loop {
+            let frames = storage.load_frames(room_id, from_log_index, limit)?;
+            let Some(last_loaded_index) = frames.last().map(|f| f.header.log_index()) else {
+                break false;
+            };
+
+            frame_bytes.extend(frames.iter().filter(|f| !is_expired(f, wall_clock)).map(|f| {
                 let mut buf = Vec::new();
                 #[allow(clippy::expect_used)]
                 f.encode(&mut buf).expect("invariant: Vec write never fails");
                 buf
-            })
-            .collect();
+            }));
 
-        let latest_index = storage.latest_log_index(room_id)?;
-        let last_loaded_index = if frames.is_empty() {
-            from_log_index.saturating_sub(1)
-        } else {
-            from_log_index + frames.len() as u64 - 1
+            let has_more = latest_index.is_some_and(|latest| last_loaded_index < latest);
+            if !has_more || !frame_bytes.is_empty() {
+                break has_more;
+            }
+            from_log_index = last_loaded_index + 1;
         };
-        let has_more = latest_index.is_some_and(|latest| last_loaded_index < latest);
 
         Ok(RoomAction::SendSyncResponse {
             sender_id,
             room_id,
             frames: frame_bytes,
             has_more,
+            snapshot,
             processed_at: now,
         })
     }
@@ -199,10 +925,49 @@ impl RoomManager {
         self.sequencer.clear_room(room_id)
     }
 
-    /// Recover a room from storage during server startup.
+    /// Log index the next frame sequenced in `room_id` will get, or `None` if
+    /// the room's sequencer isn't loaded.
+    #[cfg(test)]
+    pub(crate) fn next_log_index(&self, room_id: u128) -> Option<u64> {
+        self.sequencer.next_log_index(room_id)
+    }
+
+    /// Evict a room's in-memory state.
+    ///
+    /// Drops metadata and sequencer state so idle rooms don't accumulate in
+    /// memory. Persisted frames and metadata are left intact; the room can be
+    /// restored later with [`Self::recover_room`].
+    ///
+    /// Returns `true` if the room was in memory.
+    pub fn evict_room(&mut self, room_id: u128) -> bool {
+        self.sequencer.clear_room(room_id);
+        self.message_ids.remove(&room_id);
+        self.signers.remove(&room_id);
+        self.members.remove(&room_id);
+        self.room_metadata.remove(&room_id).is_some()
+    }
+
+    /// Remove and return the `(room_id, log_index)` of every sequenced
+    /// message that expired at or before `wall_clock` (Unix milliseconds).
+    ///
+    /// Messages stored before a restart are tracked once their room is
+    /// recovered with [`Self::recover_room`].
+    pub fn take_expired(&mut self, wall_clock: u64) -> Vec<(u128, u64)> {
+        let pending = self.expiring.split_off(&(wall_clock.saturating_add(1), 0, 0));
+        std::mem::replace(&mut self.expiring, pending)
+            .into_iter()
+            .map(|(_, room_id, log_index)| (room_id, log_index))
+            .collect()
+    }
+
+    /// Recover a room from storage.
+    ///
+    /// Used during server startup and to lazily reload evicted rooms.
+    /// Idempotent: a room already in memory is left untouched.
     ///
-    /// Loads room metadata from the ROOMS table, then initializes
-    /// the sequencer with the correct `next_log_index` from frames.
+    /// Loads room metadata from the ROOMS table, initializes the sequencer
+    /// with the correct `next_log_index` from frames, and schedules pruning for
+    /// stored messages that have an expiry.
     ///
     /// # Errors
     ///
@@ -214,72 +979,431 @@ impl RoomManager {
             return Ok(());
         }
 
+        self.load_room(room_id, storage)?;
+        self.schedule_stored_expiries(room_id, storage)
+    }
+
+    /// Load a room's metadata, members, group state and sequencer from
+    /// storage, without scanning its log for expiries.
+    fn load_room(&mut self, room_id: u128, storage: &impl Storage) -> Result<(), RoomError> {
         let stored =
             storage.load_room_metadata(room_id)?.ok_or(RoomError::RoomNotFound(room_id))?;
 
-        let metadata =
-            RoomMetadata { creator: stored.creator, created_at_secs: stored.created_at_secs };
+        let policy = storage.load_room_policy(room_id)?.unwrap_or_default();
+        let members = storage.members(room_id)?.into_iter().collect();
+        let metadata = RoomMetadata {
+            creator: stored.creator,
+            created_at_secs: stored.created_at_secs,
+            public: stored.public,
+            policy,
+        };
         self.room_metadata.insert(room_id, metadata);
+        self.members.insert(room_id, members);
+        if let Some(state) = storage.load_mls_state(room_id)? {
+            self.signers.insert(room_id, RoomSigners { state, keys: VerifyingKeyCache::new() });
+        }
 
         self.sequencer.initialize_room(room_id, storage)?;
 
         Ok(())
     }
 
-    /// Process a frame through sequencing and routing.
+    /// Schedule pruning for every stored message of `room_id` that has an
+    /// expiry and still carries its ciphertext.
     ///
-    /// The server is a routing-only node - it does NOT participate in MLS.
-    /// Clients own the MLS group state; the server just:
-    /// 1. Verifies room exists (metadata check)
-    /// 2. Sequences frames (assigns log index)
-    /// 3. Routes frames to room subscribers
-    pub fn process_frame<I: Copy>(
+    /// The scan starts at the retention snapshot, as earlier frames are gone.
+    fn schedule_stored_expiries(
         &mut self,
-        frame: Frame,
-        now: I,
+        room_id: u128,
+        storage: &impl Storage,
+    ) -> Result<(), RoomError> {
+        if storage.latest_log_index(room_id)?.is_none() {
+            return Ok(());
+        }
+
+        let mut from = storage.load_snapshot(room_id)?.map_or(0, |s| s.first_log_index);
+        loop {
+            let frames = storage.load_frames(room_id, from, EXPIRY_SCAN_PAGE)?;
+            let Some(last) = frames.last() else {
+                return Ok(());
+            };
+            from = last.header.log_index() + 1;
+
+            for frame in frames.iter().filter(|f| !f.payload.is_empty()) {
+                if let Some(expires_at) = app_message(frame).and_then(|m| m.expires_at) {
+                    self.expiring.insert((expires_at, room_id, frame.header.log_index()));
+                }
+            }
+        }
+    }
+
+    /// Validate a frame as [`Self::process_frame`] would, without sequencing,
+    /// persisting, or broadcasting it.
+    ///
+    /// Structural checks run first, so a malformed frame is reported as
+    /// `RoomError::Sequencing` even when the room does not exist. A room that
+    /// was evicted is checked against a copy reloaded from storage, so it
+    /// stays evicted. The copy skips the expiry scan [`Self::recover_room`]
+    /// does, since it is dropped straight after.
+    ///
+    /// # Errors
+    ///
+    /// - `RoomError::Sequencing` if the frame is malformed
+    /// - `RoomError::RoomNotFound` if the room is neither loaded nor stored
+    /// - `RoomError::Rejected` if processing would reject the frame
+    /// - `RoomError::Storage` if the room or a referenced frame can't be loaded
+    pub fn validate_frame(
+        &mut self,
+        frame: &Frame,
+        storage: &impl Storage,
+    ) -> Result<(), RoomError> {
+        self.sequencer.validate_frame(frame)?;
+
+        let room_id = frame.header.room_id();
+        let rejection = if self.has_room(room_id) {
+            self.rejection(frame, storage)?
+        } else {
+            let mut stored = Self::with_max_members(self.max_members);
+            stored.load_room(room_id, storage)?;
+            stored.rejection(frame, storage)?
+        };
+
+        match rejection {
+            Some(error) => Err(RoomError::Rejected(error)),
+            None => Ok(()),
+        }
+    }
+
+    /// Process a frame through sequencing and routing.
+    ///
+    /// The server is a routing-only node - it does NOT participate in MLS.
+    /// Clients own the MLS group state; the server just:
+    /// 1. Verifies room exists (metadata check), reloading it from storage if
+    ///    it was evicted or the server restarted and, when the room has stored
+    ///    MLS state, that member frames in its epoch are signed by their sender
+    /// 2. Sequences frames (assigns log index), except ephemeral `Typing`
+    ///    frames which are only broadcast and `AppMessage` frames whose message
+    ///    ID was sequenced recently (a retried send), which are rejected
+    /// 3. Routes frames to room subscribers
+    pub fn process_frame<I: Copy>(
+        &mut self,
+        frame: Frame,
+        now: I,
         storage: &impl Storage,
     ) -> Result<Vec<RoomAction<I>>, RoomError> {
-        // 1. Room must exist (check metadata)
+        // 1. Room must exist (in memory, or reloaded from storage)
         let room_id = frame.header.room_id();
         if !self.has_room(room_id) {
-            return Err(RoomError::RoomNotFound(room_id));
+            self.recover_room(room_id, storage)?;
         }
 
+        if let Some(error) = self.rejection(&frame, storage)? {
+            return Ok(vec![RoomAction::Reject {
+                sender_id: frame.header.sender_id(),
+                reason: error.message,
+                code: error.code,
+                processed_at: now,
+            }]);
+        }
+
+        // Typing indicators are ephemeral: broadcast without sequencing or
+        // persisting, so sync never replays them
+        if frame.header.opcode_enum() == Some(Opcode::Typing) {
+            return Ok(vec![RoomAction::Broadcast {
+                room_id,
+                policy: BroadcastPolicy::for_opcode(Some(Opcode::Typing)),
+                frame,
+                processed_at: now,
+            }]);
+        }
+
+        let message = app_message(&frame);
+
         // 2. Sequence the frame (assign log index)
         let sequencer_actions = self.sequencer.process_frame(frame, storage)?;
 
         // 3. Convert SequencerAction to RoomAction
-        let room_actions: Vec<RoomAction<I>> = sequencer_actions
-            .into_iter()
-            .filter_map(|action| match action {
-                SequencerAction::AcceptFrame { .. } => {
-                    // AcceptFrame is just validation, no storage needed
-                    // StoreFrame handles the actual persistence
-                    None
-                },
-                SequencerAction::StoreFrame { room_id, log_index, frame } => {
-                    Some(RoomAction::PersistFrame { room_id, log_index, frame, processed_at: now })
-                },
-                SequencerAction::BroadcastToRoom { room_id, frame } => {
-                    Some(RoomAction::Broadcast {
-                        room_id,
-                        frame,
-                        exclude_sender: false,
-                        processed_at: now,
-                    })
-                },
-                SequencerAction::RejectFrame { room_id: _, reason, original_frame } => {
-                    Some(RoomAction::Reject {
-                        sender_id: original_frame.header.sender_id(),
-                        reason,
-                        processed_at: now,
-                    })
-                },
-            })
-            .collect();
+        let room_actions = room_actions(sequencer_actions, now);
+
+        if let Some(message) = message {
+            self.record_app_message(room_id, &message, &room_actions);
+        }
 
         Ok(room_actions)
     }
+
+    /// Reason `frame` may not be sequenced in its (loaded) room, or `None`.
+    ///
+    /// Covers every check [`Self::process_frame`] makes before sequencing:
+    /// signatures against stored MLS state, edit and redaction authorship,
+    /// kick and admin rights, commit membership, and retried message IDs.
+    fn rejection(
+        &mut self,
+        frame: &Frame,
+        storage: &impl Storage,
+    ) -> Result<Option<ErrorPayload>, RoomError> {
+        let room_id = frame.header.room_id();
+        if let Some(reason) =
+            self.signers.get_mut(&room_id).and_then(|signers| signers.rejection(frame))
+        {
+            return Ok(Some(ErrorPayload::frame_rejected(reason)));
+        }
+
+        // Edits are only sequenced if they come from the original sender
+        if frame.header.opcode_enum() == Some(Opcode::AppEdit)
+            && let Some(reason) = edit_rejection(frame, storage)?
+        {
+            return Ok(Some(ErrorPayload::frame_rejected(reason)));
+        }
+
+        // Only members may kick, and removing someone else is a membership
+        // change the room may reserve for its admins
+        if let Some(target) = kick_target(frame)
+            && let Some(reason) =
+                self.kick_rejection(room_id, frame.header.sender_id(), target, storage)?
+        {
+            return Ok(Some(ErrorPayload::forbidden(reason)));
+        }
+
+        // Only admins may rename the room or change its topic, only authors
+        // and admins may redact, and only members may commit. External joins
+        // come from non-members, so they are checked against the member cap
+        // and the published GroupInfo instead
+        let rejection = match frame.header.opcode_enum() {
+            Some(Opcode::RoomMeta) => self.room_meta_rejection(frame),
+            Some(Opcode::Redact) => self.redact_rejection(frame, storage)?,
+            Some(Opcode::Commit) => self.commit_rejection(frame, storage)?,
+            Some(Opcode::ExternalCommit) => self.external_commit_rejection(frame, storage)?,
+            _ => None,
+        };
+        if rejection.is_some() {
+            return Ok(rejection);
+        }
+
+        // Retried sends reuse their message ID; sequence each ID only once
+        let message_id = app_message(frame).and_then(|m| m.message_id);
+        Ok(message_id
+            .filter(|id| self.message_ids.get(&room_id).is_some_and(|ids| ids.contains(*id)))
+            .map(|id| ErrorPayload::frame_rejected(format!("duplicate message id {id:x}"))))
+    }
+
+    /// Remember a sequenced `AppMessage`'s ID for duplicate suppression and
+    /// schedule it for pruning if it expires.
+    fn record_app_message<I>(
+        &mut self,
+        room_id: u128,
+        message: &EncryptedMessage,
+        room_actions: &[RoomAction<I>],
+    ) {
+        for action in room_actions {
+            let RoomAction::PersistFrame { log_index, .. } = action else { continue };
+            if let Some(id) = message.message_id {
+                self.message_ids
+                    .entry(room_id)
+                    .or_insert_with(|| RecentMessageIds::with_capacity(MESSAGE_ID_WINDOW))
+                    .insert(id);
+            }
+            if let Some(expires_at) = message.expires_at {
+                self.expiring.insert((expires_at, room_id, *log_index));
+            }
+        }
+    }
+
+    /// Append a frame received from `session_id` to its room's queue.
+    ///
+    /// Frames are later sequenced by [`Self::process_next`] in the order they
+    /// were enqueued. The room is not checked here; an unknown room is
+    /// reported when the frame is processed.
+    ///
+    /// # Errors
+    ///
+    /// `RoomError::QueueFull` if the room already has
+    /// [`ROOM_QUEUE_CAPACITY`] frames waiting. The frame is dropped.
+    pub fn enqueue(&mut self, session_id: u64, frame: Frame) -> Result<(), RoomError> {
+        let room_id = frame.header.room_id();
+        let queue = self.queues.entry(room_id).or_default();
+        if queue.len() >= ROOM_QUEUE_CAPACITY {
+            return Err(RoomError::QueueFull(room_id));
+        }
+
+        queue.push_back((session_id, frame));
+        Ok(())
+    }
+
+    /// Process the oldest queued frame for `room_id`.
+    ///
+    /// Returns `None` if the queue is empty. A frame that fails processing is
+    /// still removed from the queue.
+    pub fn process_next<I: Copy>(
+        &mut self,
+        room_id: u128,
+        now: I,
+        storage: &impl Storage,
+    ) -> Option<ProcessedFrame<I>> {
+        let queue = self.queues.get_mut(&room_id)?;
+        let (session_id, frame) = queue.pop_front()?;
+        if queue.is_empty() {
+            self.queues.remove(&room_id);
+        }
+
+        let request_id = frame.header.request_id();
+        let result = self.process_frame(frame, now, storage);
+        Some(ProcessedFrame { room_id, session_id, request_id, result })
+    }
+
+    /// Number of frames waiting in `room_id`'s queue.
+    pub fn queued_frames(&self, room_id: u128) -> usize {
+        self.queues.get(&room_id).map_or(0, VecDeque::len)
+    }
+}
+
+/// Persisted membership change implied by a sequenced frame.
+///
+/// MLS proposals are opaque to the server, so membership is inferred from
+/// frame metadata: a Commit applies the adds and removes it declares, an
+/// external joiner becomes a member of the room, and a Kick removes its target
+/// (a self-kick is a leave). All are authorized by
+/// [`RoomManager::process_frame`] before sequencing. The driver stages the
+/// changes in the same storage batch as the frame that carries them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MembershipChange {
+    Add(u64),
+    Remove(u64),
+}
+
+impl MembershipChange {
+    pub(crate) fn from_frame(frame: &Frame) -> Vec<Self> {
+        match frame.header.opcode_enum() {
+            Some(Opcode::Commit) => {
+                let membership = CommitMembership::from_frame(frame);
+                let added = membership.added.into_iter().map(Self::Add);
+                added.chain(membership.removed.into_iter().map(Self::Remove)).collect()
+            },
+            Some(Opcode::ExternalCommit) => vec![Self::Add(frame.header.sender_id())],
+            Some(Opcode::Kick) => kick_target(frame).map(Self::Remove).into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub(crate) fn stage(
+        self,
+        room_id: u128,
+        batch: &mut dyn StorageBatch,
+    ) -> Result<(), StorageError> {
+        match self {
+            Self::Add(user_id) => batch.add_member(room_id, user_id),
+            Self::Remove(user_id) => batch.remove_member(room_id, user_id),
+        }
+    }
+}
+
+/// Room actions for what the sequencer decided about a frame.
+fn room_actions<I: Copy>(sequencer_actions: Vec<SequencerAction>, now: I) -> Vec<RoomAction<I>> {
+    sequencer_actions
+        .into_iter()
+        .filter_map(|action| match action {
+            SequencerAction::AcceptFrame { .. } => {
+                // AcceptFrame is just validation, no storage needed
+                // StoreFrame handles the actual persistence
+                None
+            },
+            SequencerAction::StoreFrame { room_id, log_index, frame, checkpoint } => {
+                Some(RoomAction::PersistFrame {
+                    room_id,
+                    log_index,
+                    frame,
+                    checkpoint,
+                    processed_at: now,
+                })
+            },
+            SequencerAction::BroadcastToRoom { room_id, frame } => {
+                let policy = BroadcastPolicy::for_opcode(frame.header.opcode_enum());
+                Some(RoomAction::Broadcast { room_id, frame, policy, processed_at: now })
+            },
+            SequencerAction::RejectFrame { room_id: _, reason, original_frame } => {
+                Some(RoomAction::Reject {
+                    sender_id: original_frame.header.sender_id(),
+                    reason,
+                    code: ErrorPayload::FRAME_REJECTED,
+                    processed_at: now,
+                })
+            },
+        })
+        .collect()
+}
+
+/// User a Kick frame removes, if the frame is a decodable Kick.
+fn kick_target(frame: &Frame) -> Option<u64> {
+    if frame.header.opcode_enum() != Some(Opcode::Kick) {
+        return None;
+    }
+    match Payload::from_frame(frame) {
+        Ok(Payload::Kick(kick)) => Some(kick.user_id),
+        _ => None,
+    }
+}
+
+/// Decoded payload of an `AppMessage` frame, for its message ID and expiry.
+///
+/// Undecodable payloads yield `None`; the frame is still sequenced since the
+/// server can't judge encrypted content.
+fn app_message(frame: &Frame) -> Option<EncryptedMessage> {
+    if frame.header.opcode_enum() != Some(Opcode::AppMessage) {
+        return None;
+    }
+
+    match Payload::from_frame(frame) {
+        Ok(Payload::AppMessage(message)) => Some(message),
+        _ => None,
+    }
+}
+
+/// Whether `frame` is an `AppMessage` that expired at or before
+/// `wall_clock`, or whose payload was already pruned.
+fn is_expired(frame: &Frame, wall_clock: u64) -> bool {
+    if frame.header.opcode_enum() != Some(Opcode::AppMessage) {
+        return false;
+    }
+
+    frame.payload.is_empty()
+        || app_message(frame).and_then(|m| m.expires_at).is_some_and(|at| at <= wall_clock)
+}
+
+/// Reason to reject an `AppEdit` frame, or `None` if it may be sequenced.
+///
+/// The edit payload is plaintext CBOR around the encrypted content, so the
+/// target index can be checked without decrypting. The target must be a
+/// stored `AppMessage` from the same sender as the edit.
+fn edit_rejection(frame: &Frame, storage: &impl Storage) -> Result<Option<String>, RoomError> {
+    let edit = match Payload::from_frame(frame) {
+        Ok(Payload::AppEdit(edit)) => edit,
+        Ok(_) => return Ok(Some("expected AppEdit payload".to_string())),
+        Err(e) => return Ok(Some(format!("invalid edit payload: {e}"))),
+    };
+
+    let target_log_index = edit.target_log_index;
+    let room_id = frame.header.room_id();
+    let Some(original) = storage
+        .load_frames(room_id, target_log_index, 1)?
+        .into_iter()
+        .find(|f| f.header.log_index() == target_log_index)
+    else {
+        return Ok(Some(format!("edit target {target_log_index} not found")));
+    };
+
+    if original.header.opcode_enum() != Some(Opcode::AppMessage) {
+        return Ok(Some(format!("edit target {target_log_index} is not a message")));
+    }
+
+    if original.header.sender_id() != frame.header.sender_id() {
+        return Ok(Some(format!(
+            "sender {} may not edit message {target_log_index} from {}",
+            frame.header.sender_id(),
+            original.header.sender_id()
+        )));
+    }
+
+    Ok(None)
 }
 
 impl Default for RoomManager {
@@ -292,6 +1416,12 @@ impl std::fmt::Debug for RoomManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RoomManager")
             .field("room_count", &self.room_metadata.len())
+            .field("queued_rooms", &self.queues.len())
+            .field("message_id_rooms", &self.message_ids.len())
+            .field("expiring_messages", &self.expiring.len())
+            .field("signed_rooms", &self.signers.len())
+            .field("roster_rooms", &self.members.len())
+            .field("max_members", &self.max_members)
             .field("sequencer", &self.sequencer)
             .finish()
     }
@@ -300,7 +1430,8 @@ impl std::fmt::Debug for RoomManager {
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
-    use lockframe_proto::{FrameHeader, Opcode};
+    use lockframe_core::env::test_utils::MockEnv;
+    use lockframe_proto::FrameHeader;
 
     use super::*;
     use crate::storage::MemoryStorage;
@@ -321,7 +1452,7 @@ mod tests {
         let creator = 42u64;
 
         // Pre-populate storage with room metadata and frames
-        let metadata = StoredRoomMetadata { creator, created_at_secs: 0 };
+        let metadata = StoredRoomMetadata { creator, created_at_secs: 0, ..Default::default() };
         storage.create_room(room_id, &metadata).unwrap();
         for i in 0..5 {
             let frame = create_test_frame(room_id, creator, i);
@@ -343,7 +1474,7 @@ mod tests {
         let creator = 1u64;
 
         // Pre-populate storage with room metadata and frame
-        let metadata = StoredRoomMetadata { creator, created_at_secs: 0 };
+        let metadata = StoredRoomMetadata { creator, created_at_secs: 0, ..Default::default() };
         storage.create_room(room_id, &metadata).unwrap();
         let frame = create_test_frame(room_id, creator, 0);
         storage.store_frame(room_id, 0, &frame).unwrap();
@@ -376,7 +1507,7 @@ mod tests {
         let creator = 42u64;
 
         // Pre-populate storage with room metadata
-        let metadata = StoredRoomMetadata { creator, created_at_secs: 0 };
+        let metadata = StoredRoomMetadata { creator, created_at_secs: 0, ..Default::default() };
         storage.create_room(room_id, &metadata).unwrap();
 
         let mut room_manager = RoomManager::new();
@@ -385,4 +1516,739 @@ mod tests {
         // Verify room exists
         assert!(room_manager.has_room(room_id));
     }
+
+    #[test]
+    fn test_room_manager_evict_room_keeps_storage() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+        let creator = 42u64;
+        let env = MockEnv::new();
+
+        let mut room_manager = RoomManager::new();
+        room_manager.create_room(room_id, creator, None, &env, &storage).unwrap();
+        let frame = create_test_frame(room_id, creator, 0);
+        room_manager.process_frame(frame, (), &storage).unwrap();
+
+        assert!(room_manager.evict_room(room_id));
+        assert!(!room_manager.has_room(room_id));
+        assert!(!room_manager.evict_room(room_id));
+        assert!(storage.load_room_metadata(room_id).unwrap().is_some());
+
+        room_manager.recover_room(room_id, &storage).unwrap();
+        assert!(room_manager.has_room(room_id));
+    }
+
+    #[test]
+    fn test_room_manager_validating_evicted_room_skips_expiry_scan() {
+        use crate::storage::ChaoticStorage;
+
+        let storage = ChaoticStorage::new(MemoryStorage::new(), 0.0);
+        let room_id = 100u128;
+        let creator = 42u64;
+        let metadata = StoredRoomMetadata { creator, created_at_secs: 0, ..Default::default() };
+        storage.create_room(room_id, &metadata).unwrap();
+        let frames = 4 * EXPIRY_SCAN_PAGE as u64;
+        for i in 0..frames {
+            storage.store_frame(room_id, i, &create_test_frame(room_id, creator, i)).unwrap();
+        }
+
+        let ops = |f: &dyn Fn()| {
+            let before = storage.operation_count().unwrap();
+            f();
+            storage.operation_count().unwrap() - before
+        };
+        let frame = create_test_frame(room_id, creator, frames);
+        let validating = ops(&|| RoomManager::new().validate_frame(&frame, &storage).unwrap());
+        let recovering = ops(&|| RoomManager::new().recover_room(room_id, &storage).unwrap());
+
+        // Recovery pages through the log; validation reads only the room
+        assert!(recovering >= validating + 4, "{validating} vs {recovering}");
+    }
+
+    #[test]
+    fn test_room_manager_checks_signatures_against_stored_mls_state() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+        let creator = 42u64;
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let metadata = StoredRoomMetadata { creator, created_at_secs: 0, ..Default::default() };
+        storage.create_room(room_id, &metadata).unwrap();
+        let member_keys = HashMap::from([(creator, key.verifying_key().to_bytes())]);
+        let state = MlsGroupState::with_keys(room_id, 3, [0; 32], vec![creator], member_keys);
+        storage.store_mls_state(room_id, &state).unwrap();
+
+        let frame_at = |epoch, signer: &SigningKey| {
+            let mut frame = create_test_frame(room_id, creator, 0);
+            frame.header.set_epoch(epoch);
+            frame.header.set_signature(signer.sign(&frame.header.signing_data()).to_bytes());
+            frame
+        };
+        let rejected = |actions: &[RoomAction<()>]| {
+            actions.iter().any(|a| matches!(a, RoomAction::Reject { .. }))
+        };
+
+        let mut room_manager = RoomManager::new();
+        for _ in 0..2 {
+            let actions = room_manager.process_frame(frame_at(3, &key), (), &storage).unwrap();
+            assert!(!rejected(&actions));
+        }
+        assert_eq!(room_manager.signers[&room_id].keys.parses(), 1);
+
+        let forged = frame_at(3, &SigningKey::from_bytes(&[8; 32]));
+        let actions = room_manager.process_frame(forged, (), &storage).unwrap();
+        assert!(rejected(&actions));
+
+        // Later epochs are beyond what the stored state can vouch for
+        let later = frame_at(4, &SigningKey::from_bytes(&[8; 32]));
+        let actions = room_manager.process_frame(later, (), &storage).unwrap();
+        assert!(!rejected(&actions));
+    }
+
+    #[test]
+    fn test_room_manager_validates_evicted_room_against_storage() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+        let creator = 42u64;
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let metadata = StoredRoomMetadata { creator, created_at_secs: 0, ..Default::default() };
+        storage.create_room(room_id, &metadata).unwrap();
+        let member_keys = HashMap::from([(creator, key.verifying_key().to_bytes())]);
+        let state = MlsGroupState::with_keys(room_id, 3, [0; 32], vec![creator], member_keys);
+        storage.store_mls_state(room_id, &state).unwrap();
+
+        let signed_by = |signer: &SigningKey| {
+            let mut frame = create_test_frame(room_id, creator, 0);
+            frame.header.set_epoch(3);
+            frame.header.set_signature(signer.sign(&frame.header.signing_data()).to_bytes());
+            frame
+        };
+
+        // Never loaded (as after a restart or eviction)
+        let mut room_manager = RoomManager::new();
+        room_manager.validate_frame(&signed_by(&key), &storage).unwrap();
+        let forged = signed_by(&SigningKey::from_bytes(&[8; 32]));
+        assert!(matches!(
+            room_manager.validate_frame(&forged, &storage),
+            Err(RoomError::Rejected(ErrorPayload { code: ErrorPayload::FRAME_REJECTED, .. }))
+        ));
+
+        // Validation leaves the room evicted
+        assert!(!room_manager.has_room(room_id));
+
+        let mut unknown = create_test_frame(room_id + 1, creator, 0);
+        unknown.header.set_room_id(room_id + 1);
+        assert!(matches!(
+            room_manager.validate_frame(&unknown, &storage),
+            Err(RoomError::RoomNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_room_manager_process_frame_reloads_evicted_room() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+        let creator = 42u64;
+
+        let metadata = StoredRoomMetadata { creator, created_at_secs: 0, ..Default::default() };
+        storage.create_room(room_id, &metadata).unwrap();
+        for i in 0..3 {
+            let frame = create_test_frame(room_id, creator, i);
+            storage.store_frame(room_id, i, &frame).unwrap();
+        }
+
+        // Never loaded (as after a restart or eviction)
+        let mut room_manager = RoomManager::new();
+        assert!(!room_manager.has_room(room_id));
+
+        let actions = room_manager
+            .process_frame(create_test_frame(room_id, creator, 0), (), &storage)
+            .unwrap();
+        assert!(room_manager.has_room(room_id));
+
+        let persisted = actions.iter().find_map(|action| match action {
+            RoomAction::PersistFrame { log_index, frame, .. } => Some((*log_index, frame.clone())),
+            _ => None,
+        });
+        let (log_index, frame) = persisted.unwrap();
+        assert_eq!(log_index, 3);
+        storage.store_frame(room_id, log_index, &frame).unwrap();
+
+        // Reloading again after another eviction picks up where storage left off
+        room_manager.evict_room(room_id);
+        let actions = room_manager
+            .process_frame(create_test_frame(room_id, creator, 0), (), &storage)
+            .unwrap();
+        assert!(actions.iter().any(|a| matches!(a, RoomAction::PersistFrame { log_index: 4, .. })));
+    }
+
+    #[test]
+    fn test_room_manager_process_frame_unknown_room_fails() {
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+
+        let result = room_manager.process_frame(create_test_frame(100, 1, 0), (), &storage);
+        assert!(matches!(result, Err(RoomError::RoomNotFound(100))));
+        assert!(!room_manager.has_room(100));
+    }
+
+    #[test]
+    fn test_room_manager_suppresses_duplicate_message_id() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+
+        let message = |message_id| {
+            let mut header = FrameHeader::new(Opcode::AppMessage);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            Payload::AppMessage(lockframe_proto::payloads::app::EncryptedMessage {
+                epoch: 0,
+                sender_index: 0,
+                generation: 0,
+                nonce: [0; 24],
+                ciphertext: vec![1, 2, 3],
+                push_keys: None,
+                message_id,
+                expires_at: None,
+            })
+            .into_frame(header)
+            .unwrap()
+        };
+        let count = |actions: &[RoomAction<()>]| {
+            let persisted =
+                actions.iter().filter(|a| matches!(a, RoomAction::PersistFrame { .. })).count();
+            let broadcast =
+                actions.iter().filter(|a| matches!(a, RoomAction::Broadcast { .. })).count();
+            (persisted, broadcast)
+        };
+
+        let actions = room_manager.process_frame(message(Some(7)), (), &storage).unwrap();
+        assert_eq!(count(&actions), (1, 1));
+
+        // The same message again is rejected without persisting or broadcasting
+        let actions = room_manager.process_frame(message(Some(7)), (), &storage).unwrap();
+        assert_eq!(count(&actions), (0, 0));
+        assert!(matches!(&actions[..], [RoomAction::Reject { sender_id: 1, .. }]));
+
+        // Other IDs and messages without an ID are sequenced normally
+        for message_id in [Some(8), None, None] {
+            let actions = room_manager.process_frame(message(message_id), (), &storage).unwrap();
+            assert_eq!(count(&actions), (1, 1));
+        }
+    }
+
+    #[test]
+    fn test_room_manager_rejects_stale_external_commit() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+
+        let external_commit = |sender_id, epoch| {
+            let mut header = FrameHeader::new(Opcode::ExternalCommit);
+            header.set_room_id(room_id);
+            header.set_sender_id(sender_id);
+            header.set_epoch(epoch);
+            Frame::new(header, Bytes::from("external commit"))
+        };
+
+        // Nothing to join from until GroupInfo is published
+        let actions = room_manager.process_frame(external_commit(7, 0), (), &storage).unwrap();
+        assert!(matches!(&actions[..], [RoomAction::Reject {
+            sender_id: 7,
+            code: ErrorPayload::FRAME_REJECTED,
+            ..
+        }]));
+
+        storage.store_group_info(room_id, 3, b"group info").unwrap();
+
+        // Built on the latest GroupInfo: sequenced like any commit
+        let actions = room_manager.process_frame(external_commit(7, 3), (), &storage).unwrap();
+        assert!(actions.iter().any(|a| matches!(a, RoomAction::PersistFrame { log_index: 0, .. })));
+        assert!(actions.iter().any(|a| matches!(a, RoomAction::Broadcast { .. })));
+
+        // Built on an older epoch: rejected, naming both epochs
+        let actions = room_manager.process_frame(external_commit(8, 2), (), &storage).unwrap();
+        let [RoomAction::Reject { sender_id: 8, code, reason, .. }] = &actions[..] else {
+            panic!("expected Reject, got {actions:?}");
+        };
+        assert_eq!(*code, ErrorPayload::STALE_EPOCH);
+        assert!(reason.contains("epoch 3") && reason.contains("epoch 2"), "{reason}");
+    }
+
+    #[test]
+    fn test_room_manager_rejects_commit_from_non_member() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+
+        let member_key = SigningKey::from_bytes(&[1; 32]);
+        let outsider_key = SigningKey::from_bytes(&[2; 32]);
+        let mut state = MlsGroupState::new(room_id, 0, [0; 32], vec![1]);
+        state.member_keys.insert(1, member_key.verifying_key().to_bytes());
+        storage.store_mls_state(room_id, &state).unwrap();
+        room_manager.evict_room(room_id);
+
+        let commit = |sender_id, key: &SigningKey| {
+            let mut header = FrameHeader::new(Opcode::Commit);
+            header.set_room_id(room_id);
+            header.set_sender_id(sender_id);
+            let mut frame = Frame::new(header, Bytes::from("commit"));
+            let signature = key.sign(&frame.header.signing_data());
+            frame.header.set_signature(signature.to_bytes());
+            frame
+        };
+        let rejected = |actions: &[RoomAction<()>], sender, error_code| {
+            matches!(actions, [RoomAction::Reject { sender_id, code, .. }]
+                if *sender_id == sender && *code == error_code)
+        };
+
+        // Not a persisted member of the room
+        let actions = room_manager.process_frame(commit(2, &outsider_key), (), &storage).unwrap();
+        assert!(rejected(&actions, 2, ErrorPayload::FORBIDDEN), "{actions:?}");
+
+        // A member of the room, but unknown to the group
+        storage.add_member(room_id, 2).unwrap();
+        let actions = room_manager.process_frame(commit(2, &outsider_key), (), &storage).unwrap();
+        assert!(rejected(&actions, 2, ErrorPayload::MLS_ERROR), "{actions:?}");
+
+        // Claiming a member's ID without that member's key
+        let actions = room_manager.process_frame(commit(1, &outsider_key), (), &storage).unwrap();
+        assert!(rejected(&actions, 1, ErrorPayload::MLS_ERROR), "{actions:?}");
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), None);
+
+        // Signed by the member
+        let actions = room_manager.process_frame(commit(1, &member_key), (), &storage).unwrap();
+        assert!(actions.iter().any(|a| matches!(a, RoomAction::PersistFrame { log_index: 0, .. })));
+    }
+
+    #[test]
+    fn test_room_manager_group_state_follows_sequenced_frames() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+        let member_keys = HashMap::from([(1, [1; 32]), (2, [2; 32])]);
+        let state = MlsGroupState::with_keys(room_id, 3, [0; 32], vec![1, 2], member_keys);
+        storage.store_mls_state(room_id, &state).unwrap();
+        room_manager.evict_room(room_id);
+        room_manager.recover_room(room_id, &storage).unwrap();
+
+        let frame = |opcode| {
+            let mut header = FrameHeader::new(opcode);
+            header.set_room_id(room_id);
+            header.set_sender_id(1);
+            Frame::new(header, Bytes::new())
+        };
+
+        // Frames that neither commit nor change membership leave it alone
+        let message = frame(Opcode::AppMessage);
+        assert_eq!(room_manager.group_state_after(&message, Some(3), &[]), None);
+
+        // A commit moves it to the room's new epoch; removed members lose
+        // their keys and added members have none yet
+        let changes = [MembershipChange::Remove(2), MembershipChange::Add(5)];
+        let after = room_manager.group_state_after(&frame(Opcode::Commit), Some(4), &changes);
+        let after = after.unwrap();
+        assert_eq!((after.epoch, after.members.clone()), (4, vec![1, 5]));
+        assert_eq!(after.member_keys, HashMap::from([(1, [1; 32])]));
+
+        room_manager.set_group_state(room_id, after.clone());
+        assert_eq!(room_manager.signers[&room_id].state, after);
+
+        // Rooms without group state have nothing to follow
+        let mut other = frame(Opcode::Commit);
+        other.header.set_room_id(room_id + 1);
+        assert_eq!(room_manager.group_state_after(&other, Some(4), &changes), None);
+    }
+
+    #[test]
+    fn test_room_manager_restricts_declared_membership_changes_to_admins() {
+        use lockframe_core::mls::{MlsAction, MlsGroup};
+
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+        room_manager.set_admins(room_id, [1], &storage).unwrap();
+        for member in 2..=3 {
+            storage.add_member(room_id, member).unwrap();
+        }
+
+        let commit_of = |actions: Vec<MlsAction>| {
+            actions
+                .into_iter()
+                .find_map(|a| match a {
+                    MlsAction::SendCommit(frame) => Some(frame),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let add_commit = |sender_id, user_id| {
+            let (mut group, _) = MlsGroup::new(env.clone(), room_id, sender_id).unwrap();
+            let (key_package, _, _) = MlsGroup::generate_key_package(env.clone(), user_id).unwrap();
+            commit_of(group.add_members_from_bytes(&[key_package]).unwrap())
+        };
+        let remove_commit = |sender_id, user_id| {
+            let (mut group, _) = MlsGroup::new(env.clone(), room_id, sender_id).unwrap();
+            let (key_package, _, _) = MlsGroup::generate_key_package(env.clone(), user_id).unwrap();
+            group.add_members_from_bytes(&[key_package]).unwrap();
+            group.merge_pending_commit().unwrap();
+            commit_of(group.remove_members(&[user_id]).unwrap())
+        };
+        let assert_forbidden = |actions: &[RoomAction<()>]| {
+            assert!(
+                matches!(actions, [RoomAction::Reject { code, .. }]
+                    if *code == ErrorPayload::FORBIDDEN),
+                "{actions:?}"
+            );
+        };
+
+        // A member that isn't an admin may neither add nor remove others
+        let actions = room_manager.process_frame(add_commit(2, 9), (), &storage).unwrap();
+        assert_forbidden(&actions);
+        let actions = room_manager.process_frame(remove_commit(2, 3), (), &storage).unwrap();
+        assert_forbidden(&actions);
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), None);
+
+        // The admin's add is sequenced and carries the new member
+        let actions = room_manager.process_frame(add_commit(1, 9), (), &storage).unwrap();
+        let Some(RoomAction::PersistFrame { frame, .. }) =
+            actions.iter().find(|a| matches!(a, RoomAction::PersistFrame { .. }))
+        else {
+            panic!("expected PersistFrame, got {actions:?}");
+        };
+        assert_eq!(MembershipChange::from_frame(frame), vec![MembershipChange::Add(9)]);
+    }
+
+    #[test]
+    fn test_room_manager_caps_declared_adds() {
+        use lockframe_core::mls::{MlsAction, MlsGroup};
+
+        let env = MockEnv::with_crypto_rng();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::with_max_members(Some(2));
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+        storage.add_member(room_id, 2).unwrap();
+
+        let add_commit = |user_id| {
+            let (mut group, _) = MlsGroup::new(env.clone(), room_id, 1).unwrap();
+            let (key_package, _, _) = MlsGroup::generate_key_package(env.clone(), user_id).unwrap();
+            group
+                .add_members_from_bytes(&[key_package])
+                .unwrap()
+                .into_iter()
+                .find_map(|a| match a {
+                    MlsAction::SendCommit(frame) => Some(frame),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        // The commit that would grow a full room is refused before any
+        // Welcome for it is delivered
+        let actions = room_manager.process_frame(add_commit(9), (), &storage).unwrap();
+        assert!(
+            matches!(&actions[..], [RoomAction::Reject { code, .. }]
+                if *code == ErrorPayload::ROOM_FULL),
+            "{actions:?}"
+        );
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), None);
+
+        // Adding someone already counted doesn't grow the room
+        let actions = room_manager.process_frame(add_commit(2), (), &storage).unwrap();
+        assert!(actions.iter().any(|a| matches!(a, RoomAction::PersistFrame { log_index: 0, .. })));
+    }
+
+    #[test]
+    fn test_room_manager_restricts_kicks_to_admins() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+        assert_eq!(room_manager.set_admins(room_id, [1], &storage).unwrap(), vec![1]);
+        for member in 2..=5 {
+            storage.add_member(room_id, member).unwrap();
+        }
+
+        let kick = |sender_id, user_id| {
+            let mut frame = Payload::Kick(lockframe_proto::payloads::moderation::Kick {
+                user_id,
+                reason: String::new(),
+                moderator_id: sender_id,
+            })
+            .into_frame(FrameHeader::new(Opcode::Kick))
+            .unwrap();
+            frame.header.set_room_id(room_id);
+            frame.header.set_sender_id(sender_id);
+            frame
+        };
+        let persisted = |actions: &[RoomAction<()>]| {
+            actions.iter().any(|a| matches!(a, RoomAction::PersistFrame { .. }))
+        };
+
+        // A non-admin may not remove someone else
+        let actions = room_manager.process_frame(kick(2, 3), (), &storage).unwrap();
+        assert!(matches!(&actions[..], [RoomAction::Reject {
+            sender_id: 2,
+            code: ErrorPayload::FORBIDDEN,
+            ..
+        }]));
+        assert!(matches!(
+            room_manager.check_membership_change(room_id, 2, &storage),
+            Err(RoomError::Forbidden { user_id: 2, .. })
+        ));
+
+        // but may still leave, and an admin may remove anyone
+        assert!(persisted(&room_manager.process_frame(kick(2, 2), (), &storage).unwrap()));
+        assert!(persisted(&room_manager.process_frame(kick(1, 3), (), &storage).unwrap()));
+
+        // The policy is persisted, so it survives eviction
+        room_manager.evict_room(room_id);
+        assert!(room_manager.check_membership_change(room_id, 4, &storage).is_err());
+        let actions = room_manager.process_frame(kick(4, 5), (), &storage).unwrap();
+        assert!(matches!(&actions[..], [RoomAction::Reject { code: ErrorPayload::FORBIDDEN, .. }]));
+
+        // Clearing the admins lifts the restriction
+        room_manager.set_admins(room_id, [], &storage).unwrap();
+        assert!(persisted(&room_manager.process_frame(kick(4, 5), (), &storage).unwrap()));
+
+        // but never for non-members
+        let actions = room_manager.process_frame(kick(9, 4), (), &storage).unwrap();
+        assert!(matches!(&actions[..], [RoomAction::Reject {
+            sender_id: 9,
+            code: ErrorPayload::FORBIDDEN,
+            ..
+        }]));
+    }
+
+    #[test]
+    fn test_room_manager_restricts_redactions_to_authors_and_admins() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+        for member in 2..=3 {
+            storage.add_member(room_id, member).unwrap();
+        }
+        storage.store_frame(room_id, 0, &create_test_frame(room_id, 2, 0)).unwrap();
+
+        let redact = |sender_id, moderator_id, message_log_index| {
+            let mut frame = Payload::Redact(lockframe_proto::payloads::moderation::Redact {
+                message_log_index,
+                reason: String::new(),
+                moderator_id,
+            })
+            .into_frame(FrameHeader::new(Opcode::Redact))
+            .unwrap();
+            frame.header.set_room_id(room_id);
+            frame.header.set_sender_id(sender_id);
+            frame
+        };
+        let rejection = |actions: &[RoomAction<()>]| match actions {
+            [RoomAction::Reject { code, .. }] => Some(*code),
+            _ => None,
+        };
+
+        // Another member may not redact the message, nor claim to be a moderator
+        let actions = room_manager.process_frame(redact(3, 3, 0), (), &storage).unwrap();
+        assert_eq!(rejection(&actions), Some(ErrorPayload::FORBIDDEN));
+        let actions = room_manager.process_frame(redact(3, 1, 0), (), &storage).unwrap();
+        assert_eq!(rejection(&actions), Some(ErrorPayload::FORBIDDEN));
+
+        // Missing targets are rejected rather than sequenced
+        let actions = room_manager.process_frame(redact(1, 1, 9), (), &storage).unwrap();
+        assert_eq!(rejection(&actions), Some(ErrorPayload::FRAME_REJECTED));
+
+        // The author and the creator (admin while none are assigned) may
+        assert_eq!(
+            rejection(&room_manager.process_frame(redact(2, 2, 0), (), &storage).unwrap()),
+            None
+        );
+        assert_eq!(
+            rejection(&room_manager.process_frame(redact(1, 1, 0), (), &storage).unwrap()),
+            None
+        );
+
+        // Once admins are assigned, the creator is no longer one
+        room_manager.set_admins(room_id, [3], &storage).unwrap();
+        let actions = room_manager.process_frame(redact(1, 1, 0), (), &storage).unwrap();
+        assert_eq!(rejection(&actions), Some(ErrorPayload::FORBIDDEN));
+        assert_eq!(
+            rejection(&room_manager.process_frame(redact(3, 3, 0), (), &storage).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_room_manager_restricts_room_meta_to_admins() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+
+        let room_meta = |sender_id, topic: &str| {
+            let mut frame = Payload::RoomMeta(lockframe_proto::payloads::moderation::RoomMeta {
+                name: None,
+                topic: Some(topic.to_string()),
+            })
+            .into_frame(FrameHeader::new(Opcode::RoomMeta))
+            .unwrap();
+            frame.header.set_room_id(room_id);
+            frame.header.set_sender_id(sender_id);
+            frame
+        };
+        let rejected = |actions: &[RoomAction<()>]| {
+            matches!(actions, [RoomAction::Reject { code: ErrorPayload::FORBIDDEN, .. }])
+        };
+        let persisted = |actions: &[RoomAction<()>]| {
+            actions.iter().any(|a| matches!(a, RoomAction::PersistFrame { .. }))
+        };
+
+        // Without admins, only the creator may change the topic
+        assert!(rejected(&room_manager.process_frame(room_meta(2, "spam"), (), &storage).unwrap()));
+        assert!(persisted(
+            &room_manager.process_frame(room_meta(1, "plans"), (), &storage).unwrap()
+        ));
+
+        // Once admins are assigned, they replace the creator
+        room_manager.set_admins(room_id, [2], &storage).unwrap();
+        assert!(persisted(
+            &room_manager.process_frame(room_meta(2, "news"), (), &storage).unwrap()
+        ));
+        assert!(rejected(&room_manager.process_frame(room_meta(1, "old"), (), &storage).unwrap()));
+
+        // Undecodable payloads are rejected outright
+        let mut garbage = Frame::new(FrameHeader::new(Opcode::RoomMeta), Bytes::from_static(b"x"));
+        garbage.header.set_room_id(room_id);
+        garbage.header.set_sender_id(2);
+        let actions = room_manager.process_frame(garbage, (), &storage).unwrap();
+        assert!(matches!(&actions[..], [RoomAction::Reject {
+            code: ErrorPayload::INVALID_PAYLOAD,
+            ..
+        }]));
+    }
+
+    #[test]
+    fn test_membership_change_from_frame() {
+        let room_id = 100u128;
+        let frame = |opcode, payload: Bytes| {
+            let mut header = FrameHeader::new(opcode);
+            header.set_room_id(room_id);
+            header.set_sender_id(7);
+            Frame::new(header, payload)
+        };
+
+        // Commits without a declaration change nothing
+        let commit = frame(Opcode::Commit, Bytes::new());
+        assert_eq!(MembershipChange::from_frame(&commit), vec![]);
+
+        let external = frame(Opcode::ExternalCommit, Bytes::new());
+        assert_eq!(MembershipChange::from_frame(&external), vec![MembershipChange::Add(7)]);
+
+        let kick = Payload::Kick(lockframe_proto::payloads::moderation::Kick {
+            user_id: 9,
+            reason: String::new(),
+            moderator_id: 7,
+        })
+        .into_frame(FrameHeader::new(Opcode::Kick))
+        .unwrap();
+        assert_eq!(MembershipChange::from_frame(&kick), vec![MembershipChange::Remove(9)]);
+
+        let message = frame(Opcode::AppMessage, Bytes::from("msg"));
+        assert_eq!(MembershipChange::from_frame(&message), vec![]);
+    }
+
+    #[test]
+    fn test_room_manager_queue_sequences_in_arrival_order() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+        room_manager.create_room(room_id, 1, None, &env, &storage).unwrap();
+
+        // Two sessions interleave submissions to the same room
+        let submissions = [(1, 10), (2, 20), (2, 21), (1, 11), (2, 22)];
+        for (session_id, sender_id) in submissions {
+            room_manager.enqueue(session_id, create_test_frame(room_id, sender_id, 0)).unwrap();
+        }
+        assert_eq!(room_manager.queued_frames(room_id), submissions.len());
+
+        let mut sequenced = Vec::new();
+        while let Some(processed) = room_manager.process_next(room_id, (), &storage) {
+            let session_id = processed.session_id;
+            for action in processed.result.unwrap() {
+                if let RoomAction::PersistFrame { log_index, frame, .. } = action {
+                    sequenced.push((session_id, frame.header.sender_id(), log_index));
+                }
+            }
+        }
+
+        assert_eq!(sequenced, vec![(1, 10, 0), (2, 20, 1), (2, 21, 2), (1, 11, 3), (2, 22, 4)]);
+        assert_eq!(room_manager.queued_frames(room_id), 0);
+    }
+
+    #[test]
+    fn test_room_manager_queue_is_bounded() {
+        let mut room_manager = RoomManager::new();
+        let room_id = 100u128;
+
+        for _ in 0..ROOM_QUEUE_CAPACITY {
+            room_manager.enqueue(1, create_test_frame(room_id, 1, 0)).unwrap();
+        }
+        let result = room_manager.enqueue(1, create_test_frame(room_id, 1, 0));
+        assert!(matches!(result, Err(RoomError::QueueFull(100))));
+
+        // Other rooms have their own queues
+        room_manager.enqueue(1, create_test_frame(200, 1, 0)).unwrap();
+        assert_eq!(room_manager.queued_frames(room_id), ROOM_QUEUE_CAPACITY);
+        assert_eq!(room_manager.queued_frames(200), 1);
+    }
+
+    #[test]
+    fn broadcast_policy_recipients() {
+        let members = [1u64, 2, 3];
+
+        assert_eq!(BroadcastPolicy::IncludeAll.recipients(members, 2), vec![1, 2, 3]);
+        assert_eq!(BroadcastPolicy::ExcludeSender.recipients(members, 2), vec![1, 3]);
+        assert_eq!(BroadcastPolicy::SenderOnly.recipients(members, 2), vec![2]);
+
+        // Sender not subscribed
+        assert_eq!(BroadcastPolicy::ExcludeSender.recipients(members, 9), vec![1, 2, 3]);
+        assert!(BroadcastPolicy::SenderOnly.recipients(members, 9).is_empty());
+    }
+
+    #[test]
+    fn broadcast_policy_for_opcode() {
+        assert_eq!(
+            BroadcastPolicy::for_opcode(Some(Opcode::AppMessage)),
+            BroadcastPolicy::IncludeAll
+        );
+        assert_eq!(BroadcastPolicy::for_opcode(Some(Opcode::Commit)), BroadcastPolicy::IncludeAll);
+        assert_eq!(
+            BroadcastPolicy::for_opcode(Some(Opcode::AppReceipt)),
+            BroadcastPolicy::ExcludeSender
+        );
+        assert_eq!(
+            BroadcastPolicy::for_opcode(Some(Opcode::Typing)),
+            BroadcastPolicy::ExcludeSender
+        );
+    }
 }
@@ -0,0 +1,236 @@
+//! Sharded room state for concurrent frame processing.
+//!
+//! Rooms are partitioned by room ID across independently locked
+//! [`RoomManager`] shards. Frames for rooms in different shards can be
+//! sequenced concurrently, while frames for one room always go through the
+//! same lock and stay serialized. Session and connection state lives in the
+//! [`crate::ServerDriver`] and is locked separately by the runtime.
+//!
+//! Shard locks are held across storage calls, so async runtimes should call
+//! into a shard from a blocking task (e.g. `tokio::task::spawn_blocking`).
+
+#![allow(clippy::disallowed_types, reason = "Synchronous locking operations only")]
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use lockframe_proto::Frame;
+
+use crate::{
+    room_manager::{ProcessedFrame, RoomAction, RoomError, RoomManager},
+    storage::Storage,
+};
+
+/// Default number of room shards.
+pub const DEFAULT_ROOM_SHARDS: usize = 16;
+
+/// Room managers partitioned by room ID.
+///
+/// Clone is cheap (Arc) and shares the same shards, so the runtime can process
+/// room frames without holding the driver lock.
+#[derive(Clone)]
+pub struct RoomShards {
+    shards: Arc<[Mutex<RoomManager>]>,
+    /// Member cap of every shard's rooms, kept to reset a poisoned shard
+    max_members: Option<usize>,
+}
+
+impl RoomShards {
+    /// Create `shard_count` empty shards (at least one).
+    pub fn new(shard_count: usize) -> Self {
+        Self::with_max_members(shard_count, None)
+    }
+
+    /// Create `shard_count` empty shards whose rooms are capped at
+    /// `max_members` members. See [`RoomManager::with_max_members`].
+    pub fn with_max_members(shard_count: usize, max_members: Option<usize>) -> Self {
+        let shards = (0..shard_count.max(1))
+            .map(|_| Mutex::new(RoomManager::with_max_members(max_members)))
+            .collect();
+        Self { shards, max_members }
+    }
+
+    /// Number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Index of the shard that owns `room_id`.
+    pub fn shard_for(&self, room_id: u128) -> usize {
+        // Shard count is small, so the remainder always fits in usize
+        (room_id % self.shards.len() as u128) as usize
+    }
+
+    /// Run `f` with exclusive access to the shard that owns `room_id`.
+    ///
+    /// Only that shard is locked; other rooms' shards stay available.
+    pub fn with_room<R>(&self, room_id: u128, f: impl FnOnce(&mut RoomManager) -> R) -> R {
+        f(&mut self.lock(&self.shards[self.shard_for(room_id)]))
+    }
+
+    /// Lock `shard`, resetting it if a panic poisoned it.
+    ///
+    /// A panic mid-frame may leave the shard's rooms half updated, so the
+    /// shard starts over empty. Its rooms reload from storage on their next
+    /// frame; frames still queued for them are lost.
+    fn lock<'a>(&self, shard: &'a Mutex<RoomManager>) -> MutexGuard<'a, RoomManager> {
+        shard.lock().unwrap_or_else(|poisoned| {
+            let mut manager = poisoned.into_inner();
+            *manager = RoomManager::with_max_members(self.max_members);
+            shard.clear_poison();
+            manager
+        })
+    }
+
+    /// Sequence a frame under its room's shard lock.
+    ///
+    /// See [`RoomManager::process_frame`].
+    pub fn process_frame<I: Copy>(
+        &self,
+        frame: Frame,
+        now: I,
+        storage: &impl Storage,
+    ) -> Result<Vec<RoomAction<I>>, RoomError> {
+        let room_id = frame.header.room_id();
+        self.with_room(room_id, |rooms| rooms.process_frame(frame, now, storage))
+    }
+
+    /// Queue a frame from `session_id` on its room's shard.
+    ///
+    /// See [`RoomManager::enqueue`].
+    pub fn enqueue(&self, session_id: u64, frame: Frame) -> Result<(), RoomError> {
+        let room_id = frame.header.room_id();
+        self.with_room(room_id, |rooms| rooms.enqueue(session_id, frame))
+    }
+
+    /// Sequence the oldest queued frame for `room_id` under its shard lock.
+    ///
+    /// See [`RoomManager::process_next`].
+    pub fn process_next<I: Copy>(
+        &self,
+        room_id: u128,
+        now: I,
+        storage: &impl Storage,
+    ) -> Option<ProcessedFrame<I>> {
+        self.with_room(room_id, |rooms| rooms.process_next(room_id, now, storage))
+    }
+
+    /// Room is in memory in its shard.
+    pub fn has_room(&self, room_id: u128) -> bool {
+        self.with_room(room_id, |rooms| rooms.has_room(room_id))
+    }
+
+    /// Expired messages across every shard, locking one shard at a time.
+    ///
+    /// See [`RoomManager::take_expired`].
+    pub fn take_expired(&self, wall_clock: u64) -> Vec<(u128, u64)> {
+        self.shards.iter().flat_map(|shard| self.lock(shard).take_expired(wall_clock)).collect()
+    }
+}
+
+impl Default for RoomShards {
+    fn default() -> Self {
+        Self::new(DEFAULT_ROOM_SHARDS)
+    }
+}
+
+impl std::fmt::Debug for RoomShards {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RoomShards")
+            .field("shard_count", &self.shards.len())
+            .field("max_members", &self.max_members)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use bytes::Bytes;
+    use lockframe_core::env::test_utils::MockEnv;
+    use lockframe_proto::{FrameHeader, Opcode};
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn app_frame(room_id: u128) -> Frame {
+        let mut header = FrameHeader::new(Opcode::AppMessage);
+        header.set_room_id(room_id);
+        header.set_sender_id(1);
+        Frame::new(header, Bytes::from("msg"))
+    }
+
+    #[test]
+    fn rooms_map_to_stable_shards() {
+        let shards = RoomShards::new(4);
+        assert_eq!(shards.shard_count(), 4);
+        assert_eq!(shards.shard_for(1), shards.shard_for(5));
+        assert_ne!(shards.shard_for(1), shards.shard_for(2));
+        assert_eq!(RoomShards::new(0).shard_count(), 1);
+    }
+
+    #[test]
+    fn busy_room_does_not_block_other_shard() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let shards = RoomShards::new(2);
+        let (shards, storage) = (&shards, &storage);
+        let (busy_room, idle_room) = (0x10, 0x11);
+        assert_ne!(shards.shard_for(busy_room), shards.shard_for(idle_room));
+
+        for room_id in [busy_room, idle_room] {
+            shards
+                .with_room(room_id, |rooms| rooms.create_room(room_id, 1, None, &env, storage))
+                .unwrap();
+        }
+
+        let (held_tx, held_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (done_tx, done_rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            // Hold the busy room's shard until released
+            scope.spawn(move || {
+                shards.with_room(busy_room, |_| {
+                    held_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                });
+            });
+            held_rx.recv().unwrap();
+
+            // The idle room is sequenced while the busy shard is still locked
+            scope.spawn(move || {
+                let result = shards.process_frame(app_frame(idle_room), (), storage);
+                done_tx.send(result.map(|actions| actions.len())).unwrap();
+            });
+            let processed = done_rx.recv_timeout(Duration::from_secs(5));
+
+            release_tx.send(()).unwrap();
+            assert!(matches!(processed, Ok(Ok(n)) if n > 0), "idle room was blocked");
+        });
+
+        // The busy room still processes once released
+        assert!(shards.process_frame(app_frame(busy_room), (), storage).is_ok());
+    }
+
+    #[test]
+    fn poisoned_shard_resets_and_reloads_from_storage() {
+        let env = MockEnv::new();
+        let storage = MemoryStorage::new();
+        let shards = RoomShards::new(1);
+        let room_id = 0x10;
+        shards
+            .with_room(room_id, |rooms| rooms.create_room(room_id, 1, None, &env, &storage))
+            .unwrap();
+
+        let panicked = thread::scope(|scope| {
+            scope.spawn(|| shards.with_room(room_id, |_| panic!("frame handler bug"))).join()
+        });
+        assert!(panicked.is_err());
+
+        // The shard is usable again, with its rooms dropped from memory
+        assert!(!shards.has_room(room_id));
+        shards.with_room(room_id, |rooms| rooms.recover_room(room_id, &storage)).unwrap();
+        assert!(shards.process_frame(app_frame(room_id), (), &storage).is_ok());
+    }
+}
@@ -6,11 +6,23 @@
 //!
 //! Flow: load state from storage, validate frame structure (magic, version,
 //! payload size), assign next `log_index`, return sequencing actions.
+//!
+//! Commits must advance the room's epoch by exactly one. The sequencer
+//! remembers the epoch each room's last commit created and refuses commits
+//! that target any other epoch than the next. Each stored frame carries the
+//! room's [`RoomCheckpoint`], which the driver writes in the same batch, so
+//! the epoch survives restarts and eviction. Until a room's epoch is known
+//! (a room whose log predates checkpoints) the first commit sets it.
+//!
+//! The cached state can be captured with [`Sequencer::checkpoint`] and put
+//! back with [`Sequencer::restore`], e.g. to roll back indices assigned to
+//! frames that never reached storage.
 
-use std::collections::{HashMap, hash_map};
+use std::collections::{BTreeMap, HashMap, hash_map};
 
 use lockframe_core::mls::MAX_EPOCH;
-use lockframe_proto::{Frame, FrameHeader};
+use lockframe_proto::{Frame, FrameHeader, Opcode};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::storage::{Storage, StorageError};
@@ -29,6 +41,24 @@ pub enum SequencerError {
     /// Frame was rejected by validator
     #[error("frame rejected: {0}")]
     Rejected(String),
+
+    /// Commit targets the room's current epoch or an earlier one
+    #[error("epoch regression: commit targets epoch {got}, expected {expected}")]
+    EpochRegression {
+        /// Epoch the commit would create
+        got: u64,
+        /// Only epoch a commit may create
+        expected: u64,
+    },
+
+    /// Commit targets an epoch past the one after the room's current one
+    #[error("epoch gap: commit targets epoch {got}, expected {expected}")]
+    EpochGap {
+        /// Epoch the commit would create
+        got: u64,
+        /// Only epoch a commit may create
+        expected: u64,
+    },
 }
 
 impl From<StorageError> for SequencerError {
@@ -68,6 +98,8 @@ pub enum SequencerAction {
         log_index: u64,
         /// Frame to store
         frame: Frame,
+        /// Room state after this frame, stored alongside it
+        checkpoint: RoomCheckpoint,
     },
 
     /// Broadcast frame to all room subscribers
@@ -79,11 +111,68 @@ pub enum SequencerAction {
     },
 }
 
-/// Per-room sequencer state (cached)
-#[derive(Debug, Clone)]
-struct RoomSequencer {
+/// Per-room sequencer state.
+///
+/// Cached by the [`Sequencer`] and persisted with every stored frame through
+/// [`StorageBatch::store_room_checkpoint`](crate::storage::StorageBatch::store_room_checkpoint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomCheckpoint {
     /// Next log index to assign
-    next_log_index: u64,
+    pub next_log_index: u64,
+    /// Epoch created by the last sequenced commit, if one has been seen
+    pub epoch: Option<u64>,
+}
+
+/// Snapshot of a [`Sequencer`]'s per-room state.
+///
+/// Produced by [`Sequencer::checkpoint`] and consumed by
+/// [`Sequencer::restore`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequencerState {
+    /// `room_id` → next log index and epoch
+    rooms: BTreeMap<u128, RoomCheckpoint>,
+}
+
+impl SequencerState {
+    /// Create an empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Next log index recorded for `room_id`.
+    pub fn next_log_index(&self, room_id: u128) -> Option<u64> {
+        self.rooms.get(&room_id).map(|room| room.next_log_index)
+    }
+
+    /// Checkpoint recorded for `room_id`.
+    pub fn room(&self, room_id: u128) -> Option<RoomCheckpoint> {
+        self.rooms.get(&room_id).copied()
+    }
+
+    /// Record `checkpoint` for `room_id`.
+    pub fn insert(&mut self, room_id: u128, checkpoint: RoomCheckpoint) {
+        self.rooms.insert(room_id, checkpoint);
+    }
+
+    /// Keep only the rooms for which `keep` returns `true`.
+    pub fn retain(&mut self, mut keep: impl FnMut(u128, RoomCheckpoint) -> bool) {
+        self.rooms.retain(|&room_id, &mut room| keep(room_id, room));
+    }
+
+    /// Rooms and their checkpoints, in room ID order.
+    pub fn rooms(&self) -> impl Iterator<Item = (u128, RoomCheckpoint)> + '_ {
+        self.rooms.iter().map(|(&room_id, &room)| (room_id, room))
+    }
+
+    /// Number of rooms recorded.
+    pub fn len(&self) -> usize {
+        self.rooms.len()
+    }
+
+    /// Whether no rooms are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty()
+    }
 }
 
 /// Server-side frame sequencer
@@ -93,7 +182,7 @@ struct RoomSequencer {
 #[derive(Debug)]
 pub struct Sequencer {
     /// Per-room state cache
-    rooms: HashMap<u128, RoomSequencer>,
+    rooms: HashMap<u128, RoomCheckpoint>,
 }
 
 /// Validate frame structure at API boundary (before processing)
@@ -144,12 +233,34 @@ fn validate_frame_structure(frame: &Frame) -> Result<(), SequencerError> {
     Ok(())
 }
 
+/// Epoch a commit frame would create, or `None` for other frames.
+///
+/// A `Commit` header carries the epoch it creates. An `ExternalCommit` header
+/// carries the epoch of the `GroupInfo` it was built on, one before.
+fn commit_target_epoch(frame: &Frame) -> Result<Option<u64>, SequencerError> {
+    let epoch = frame.header.epoch();
+    match frame.header.opcode_enum() {
+        Some(Opcode::Commit) => Ok(Some(epoch)),
+        Some(Opcode::ExternalCommit) => epoch.checked_add(1).map(Some).ok_or_else(|| {
+            SequencerError::Validation(format!("external commit epoch {epoch} overflows"))
+        }),
+        _ => Ok(None),
+    }
+}
+
 impl Sequencer {
     /// Create a new sequencer (empty state)
     pub fn new() -> Self {
         Self { rooms: HashMap::new() }
     }
 
+    /// Validate a frame without assigning a log index or touching state.
+    ///
+    /// Runs the same structural checks as [`Self::process_frame`].
+    pub fn validate_frame(&self, frame: &Frame) -> Result<(), SequencerError> {
+        validate_frame_structure(frame)
+    }
+
     /// Process an incoming frame and return actions
     ///
     /// # Invariants
@@ -171,45 +282,45 @@ impl Sequencer {
         // Welcome frames are NOT sequenced. They use recipient_id for point-to-point
         // delivery and are not stored in the log. The driver handles Welcome frames
         // specially (subscribes recipient to room).
-        if frame.header.opcode_enum() == Some(lockframe_proto::Opcode::Welcome) {
+        if frame.header.opcode_enum() == Some(Opcode::Welcome) {
             // broadcast to room, no sequencing or storage
             return Ok(vec![SequencerAction::BroadcastToRoom { room_id, frame }]);
         }
 
         let room = match self.rooms.entry(room_id) {
             hash_map::Entry::Vacant(e) => {
-                let latest_index = storage.latest_log_index(room_id).map_err(|e| {
+                let room = load_room(room_id, storage).map_err(|e| {
                     tracing::error!(
                         room_id = %room_id,
                         error = %e,
-                        "Failed to load latest_log_index during room initialization"
+                        "Failed to load room state during room initialization"
                     );
                     e
                 })?;
 
-                let next_log_index = latest_index.map_or(0, |i| i + 1);
-
                 tracing::info!(
                     room_id = %room_id,
-                    latest_storage_index = ?latest_index,
-                    next_log_index,
+                    next_log_index = room.next_log_index,
+                    epoch = ?room.epoch,
                     "Initializing sequencer for room"
                 );
 
-                debug_assert!(
-                    latest_index.map_or(next_log_index == 0, |i| next_log_index == i + 1)
-                );
-
-                tracing::debug!(
-                    room_id = %room_id,
-                    next_log_index,
-                    "Initialized room state from storage"
-                );
-
-                e.insert(RoomSequencer { next_log_index })
+                e.insert(room)
             },
             hash_map::Entry::Occupied(e) => e.into_mut(),
         };
+        let target_epoch = commit_target_epoch(&frame)?;
+        if let (Some(got), Some(current)) = (target_epoch, room.epoch) {
+            // Header epochs are bounded by MAX_EPOCH, so this can't overflow
+            let expected = current + 1;
+            if got < expected {
+                return Err(SequencerError::EpochRegression { got, expected });
+            }
+            if got > expected {
+                return Err(SequencerError::EpochGap { got, expected });
+            }
+        }
+
         let log_index = room.next_log_index;
 
         room.next_log_index = room.next_log_index.checked_add(1).ok_or_else(|| {
@@ -220,24 +331,69 @@ impl Sequencer {
 
         debug_assert!(room.next_log_index > log_index);
 
+        if target_epoch.is_some() {
+            room.epoch = target_epoch;
+        }
+
         let sequenced_frame = rebuild_frame_with_index(frame, log_index);
 
         debug_assert_eq!(sequenced_frame.header.log_index(), log_index);
 
+        let checkpoint = *room;
         let frame_for_actions = sequenced_frame;
         Ok(vec![
             SequencerAction::AcceptFrame { room_id, log_index, frame: frame_for_actions.clone() },
-            SequencerAction::StoreFrame { room_id, log_index, frame: frame_for_actions.clone() },
+            SequencerAction::StoreFrame {
+                room_id,
+                log_index,
+                frame: frame_for_actions.clone(),
+                checkpoint,
+            },
             SequencerAction::BroadcastToRoom { room_id, frame: frame_for_actions },
         ])
     }
 
+    /// Epoch created by the last commit sequenced in `room_id`, or `None`
+    /// if none has been seen.
+    ///
+    /// Rooms not cached are read from storage without being cached.
+    ///
+    /// # Errors
+    ///
+    /// `SequencerError::Storage` if an uncached room's state can't be loaded
+    pub fn epoch(
+        &self,
+        room_id: u128,
+        storage: &impl Storage,
+    ) -> Result<Option<u64>, SequencerError> {
+        match self.rooms.get(&room_id) {
+            Some(room) => Ok(room.epoch),
+            None => Ok(load_room(room_id, storage)?.epoch),
+        }
+    }
+
     /// Next log index that will be assigned (for testing/debugging).
     #[cfg(test)]
     pub fn next_log_index(&self, room_id: u128) -> Option<u64> {
         self.rooms.get(&room_id).map(|r| r.next_log_index)
     }
 
+    /// Capture the next log index and epoch of every initialized room.
+    pub fn checkpoint(&self) -> SequencerState {
+        let rooms = self.rooms.iter().map(|(&id, &room)| (id, room)).collect();
+        SequencerState { rooms }
+    }
+
+    /// Put back the rooms captured by [`Self::checkpoint`].
+    ///
+    /// Rooms in `state` resume from their recorded index without consulting
+    /// storage; rooms not in `state` are left as they are. The caller is
+    /// responsible for `state` matching storage: an index behind storage
+    /// causes a conflict on the next store, one ahead of it leaves a gap.
+    pub fn restore(&mut self, state: SequencerState) {
+        self.rooms.extend(state.rooms);
+    }
+
     /// Forces re-initialization from storage on next frame.
     ///
     /// Called when storage reports a log index conflict, indicating our
@@ -267,25 +423,37 @@ impl Sequencer {
             return Ok(());
         }
 
-        let latest_index = storage
-            .latest_log_index(room_id)
-            .map_err(|e| SequencerError::Storage(e.to_string()))?;
-
-        let next_log_index = latest_index.map_or(0, |i| i + 1);
+        let room = load_room(room_id, storage)?;
 
         tracing::info!(
             room_id = %room_id,
-            latest_storage_index = ?latest_index,
-            next_log_index,
+            next_log_index = room.next_log_index,
+            epoch = ?room.epoch,
             "Pre-initializing sequencer for room during recovery"
         );
 
-        self.rooms.insert(room_id, RoomSequencer { next_log_index });
+        self.rooms.insert(room_id, room);
 
         Ok(())
     }
 }
 
+/// Load a room's sequencer state from storage.
+///
+/// The next index always follows the latest stored frame. The epoch comes
+/// from the stored checkpoint, and only if that checkpoint was written with
+/// the latest frame: frames stored without one (imports, older logs) may
+/// have moved the epoch, so it is left unknown.
+fn load_room(room_id: u128, storage: &impl Storage) -> Result<RoomCheckpoint, SequencerError> {
+    let next_log_index = storage.latest_log_index(room_id)?.map_or(0, |i| i + 1);
+    let epoch = storage
+        .load_room_checkpoint(room_id)?
+        .filter(|checkpoint| checkpoint.next_log_index == next_log_index)
+        .and_then(|checkpoint| checkpoint.epoch);
+
+    Ok(RoomCheckpoint { next_log_index, epoch })
+}
+
 impl Default for Sequencer {
     fn default() -> Self {
         Self::new()
@@ -390,7 +558,7 @@ mod tests {
 
             // Execute StoreFrame action
             for action in actions {
-                if let SequencerAction::StoreFrame { room_id, log_index, frame } = action {
+                if let SequencerAction::StoreFrame { room_id, log_index, frame, .. } = action {
                     storage.store_frame(room_id, log_index, &frame).expect("store failed");
                     break;
                 }
@@ -427,6 +595,62 @@ mod tests {
         assert_eq!(sequencer.next_log_index(200), Some(5));
     }
 
+    #[test]
+    fn checkpoint_restore_resumes_identically() {
+        let storage = MemoryStorage::new();
+        let mut sequencer = Sequencer::new();
+        for room_id in [100, 200, 200] {
+            sequencer.process_frame(create_test_frame(room_id, 1, 0), &storage).unwrap();
+        }
+
+        let checkpoint = sequencer.checkpoint();
+        assert_eq!(checkpoint.next_log_index(100), Some(1));
+        assert_eq!(checkpoint.next_log_index(200), Some(2));
+
+        let mut restored = Sequencer::new();
+        restored.restore(checkpoint);
+        for room_id in [200, 100, 300] {
+            let frame = create_test_frame(room_id, 1, 0);
+            let expected = sequencer.process_frame(frame.clone(), &storage).unwrap();
+            assert_eq!(restored.process_frame(frame, &storage).unwrap(), expected);
+        }
+        assert_eq!(restored.checkpoint(), sequencer.checkpoint());
+    }
+
+    #[test]
+    fn restore_after_lost_store_prevents_conflict() {
+        let storage = MemoryStorage::new();
+        let mut sequencer = Sequencer::new();
+        let room_id = 100;
+        let store = |actions: Vec<SequencerAction>| {
+            for action in actions {
+                if let SequencerAction::StoreFrame { room_id, log_index, frame, .. } = action {
+                    return storage.store_frame(room_id, log_index, &frame);
+                }
+            }
+            panic!("no StoreFrame action");
+        };
+
+        store(sequencer.process_frame(create_test_frame(room_id, 1, 0), &storage).unwrap())
+            .unwrap();
+        let checkpoint = sequencer.checkpoint();
+
+        // Index 1 is assigned, then the write is lost in a crash
+        sequencer.process_frame(create_test_frame(room_id, 1, 0), &storage).unwrap();
+
+        // Without a restore the next frame is assigned index 2 and conflicts
+        let mut drifted = Sequencer::new();
+        drifted.restore(sequencer.checkpoint());
+        let result =
+            store(drifted.process_frame(create_test_frame(room_id, 1, 0), &storage).unwrap());
+        assert!(matches!(result, Err(StorageError::Conflict { expected: 1, got: 2 })));
+
+        sequencer.restore(checkpoint);
+        store(sequencer.process_frame(create_test_frame(room_id, 1, 0), &storage).unwrap())
+            .unwrap();
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(1));
+    }
+
     #[test]
     fn test_sequencer_initialize_room() {
         let mut sequencer = Sequencer::new();
@@ -521,4 +745,86 @@ mod tests {
             },
         }
     }
+
+    #[test]
+    fn commit_must_target_the_next_epoch() {
+        let mut sequencer = Sequencer::new();
+        let storage = MemoryStorage::new();
+        let frame = |opcode, epoch| {
+            let mut header = FrameHeader::new(opcode);
+            header.set_room_id(100);
+            header.set_sender_id(200);
+            header.set_epoch(epoch);
+            Frame::new(header, Bytes::from("commit"))
+        };
+
+        sequencer.process_frame(frame(Opcode::Commit, 3), &storage).unwrap();
+
+        // Same epoch again, and an older one, are both regressions
+        for epoch in [3, 2] {
+            let result = sequencer.process_frame(frame(Opcode::Commit, epoch), &storage);
+            assert_eq!(result, Err(SequencerError::EpochRegression { got: epoch, expected: 4 }));
+        }
+        // An external commit built on epoch 2 would create epoch 3
+        let result = sequencer.process_frame(frame(Opcode::ExternalCommit, 2), &storage);
+        assert_eq!(result, Err(SequencerError::EpochRegression { got: 3, expected: 4 }));
+        assert_eq!(sequencer.next_log_index(100), Some(1), "rejected commits take no index");
+
+        // Application messages don't move the epoch
+        sequencer.process_frame(create_test_frame(100, 200, 1), &storage).unwrap();
+        sequencer.process_frame(frame(Opcode::ExternalCommit, 3), &storage).unwrap();
+        sequencer.process_frame(frame(Opcode::Commit, 5), &storage).unwrap();
+
+        // Skipping ahead would wedge the room just the same
+        let result = sequencer.process_frame(frame(Opcode::Commit, MAX_EPOCH), &storage);
+        assert_eq!(result, Err(SequencerError::EpochGap { got: MAX_EPOCH, expected: 6 }));
+
+        let error = SequencerError::EpochRegression { got: 3, expected: 4 };
+        assert!(error.to_string().contains("epoch 3"), "{error}");
+        let error = SequencerError::EpochGap { got: 7, expected: 6 };
+        assert!(error.to_string().starts_with("epoch gap"), "{error}");
+    }
+
+    #[test]
+    fn epoch_survives_reload_from_storage() {
+        let storage = MemoryStorage::new();
+        let commit = |epoch| {
+            let mut header = FrameHeader::new(Opcode::Commit);
+            header.set_room_id(100);
+            header.set_sender_id(200);
+            header.set_epoch(epoch);
+            Frame::new(header, Bytes::from("commit"))
+        };
+        let store = |actions: Vec<SequencerAction>| {
+            for action in actions {
+                if let SequencerAction::StoreFrame { room_id, log_index, frame, checkpoint } =
+                    action
+                {
+                    return storage.batch(|batch| {
+                        batch.store_frame(room_id, log_index, &frame)?;
+                        batch.store_room_checkpoint(room_id, checkpoint)
+                    });
+                }
+            }
+            panic!("no StoreFrame action");
+        };
+
+        let mut sequencer = Sequencer::new();
+        store(sequencer.process_frame(commit(3), &storage).unwrap()).unwrap();
+        store(sequencer.process_frame(create_test_frame(100, 200, 3), &storage).unwrap()).unwrap();
+
+        // A fresh sequencer (restart or eviction) picks the epoch up again
+        let mut reloaded = Sequencer::new();
+        let result = reloaded.process_frame(commit(3), &storage);
+        assert_eq!(result, Err(SequencerError::EpochRegression { got: 3, expected: 4 }));
+
+        // Frames stored without a checkpoint leave the epoch unknown
+        let mut header = FrameHeader::new(Opcode::Commit);
+        header.set_room_id(100);
+        header.set_log_index(2);
+        storage.store_frame(100, 2, &Frame::new(header, Bytes::new())).unwrap();
+        let mut reloaded = Sequencer::new();
+        reloaded.initialize_room(100, &storage).unwrap();
+        reloaded.process_frame(commit(9), &storage).unwrap();
+    }
 }
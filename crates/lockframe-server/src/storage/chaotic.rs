@@ -11,7 +11,10 @@ use std::sync::{Arc, Mutex};
 use lockframe_core::mls::MlsGroupState;
 use lockframe_proto::Frame;
 
-use super::{Storage, StorageError, StoredRoomMetadata};
+use super::{
+    AuditEntry, RoomPolicy, RoomSnapshot, Storage, StorageBatch, StorageError, StoredRoomMetadata,
+};
+use crate::sequencer::RoomCheckpoint;
 
 /// Chaotic storage wrapper that randomly injects failures
 ///
@@ -99,22 +102,20 @@ impl<S: Storage> ChaoticStorage<S> {
     ///
     /// Used for performance oracles to verify O(n) complexity.
     /// Each call to any storage method increments this counter.
-    pub fn operation_count(&self) -> usize {
-        #[allow(clippy::expect_used)]
-        *self.operation_count.lock().expect("operation_count mutex poisoned")
+    pub fn operation_count(&self) -> Result<usize, StorageError> {
+        Ok(*self.operation_count.lock().map_err(|_| StorageError::Poisoned)?)
     }
 
     /// Increment operation counter
-    fn increment_operation_count(&self) {
-        #[allow(clippy::expect_used)]
-        let mut count = self.operation_count.lock().expect("operation_count mutex poisoned");
-        *count += 1;
+    fn increment_operation_count(&self) -> Result<(), StorageError> {
+        *self.operation_count.lock().map_err(|_| StorageError::Poisoned)? += 1;
+        Ok(())
     }
 
     /// Check if this operation should fail
-    fn should_fail(&self) -> bool {
-        #[allow(clippy::expect_used)]
-        self.rng.lock().expect("ChaoticRng mutex poisoned").should_fail(self.failure_rate)
+    fn should_fail(&self) -> Result<bool, StorageError> {
+        let mut rng = self.rng.lock().map_err(|_| StorageError::Poisoned)?;
+        Ok(rng.should_fail(self.failure_rate))
     }
 }
 
@@ -125,16 +126,16 @@ impl<S: Storage> Storage for ChaoticStorage<S> {
         log_index: u64,
         frame: &Frame,
     ) -> Result<(), StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.store_frame(room_id, log_index, frame)
     }
 
     fn latest_log_index(&self, room_id: u128) -> Result<Option<u64>, StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.latest_log_index(room_id)
@@ -146,24 +147,72 @@ impl<S: Storage> Storage for ChaoticStorage<S> {
         from: u64,
         limit: usize,
     ) -> Result<Vec<Frame>, StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.load_frames(room_id, from, limit)
     }
 
+    fn index_at_or_after(
+        &self,
+        room_id: u128,
+        hlc_timestamp: u64,
+    ) -> Result<Option<u64>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.index_at_or_after(room_id, hlc_timestamp)
+    }
+
+    fn last_index_stored_before(
+        &self,
+        room_id: u128,
+        wall_clock: u64,
+    ) -> Result<Option<u64>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.last_index_stored_before(room_id, wall_clock)
+    }
+
+    fn prune_frame(&self, room_id: u128, log_index: u64) -> Result<(), StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.prune_frame(room_id, log_index)
+    }
+
+    fn prune_frames(&self, room_id: u128, snapshot: &RoomSnapshot) -> Result<(), StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.prune_frames(room_id, snapshot)
+    }
+
+    fn load_snapshot(&self, room_id: u128) -> Result<Option<RoomSnapshot>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.load_snapshot(room_id)
+    }
+
     fn store_mls_state(&self, room_id: u128, state: &MlsGroupState) -> Result<(), StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.store_mls_state(room_id, state)
     }
 
     fn load_mls_state(&self, room_id: u128) -> Result<Option<MlsGroupState>, StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.load_mls_state(room_id)
@@ -175,24 +224,24 @@ impl<S: Storage> Storage for ChaoticStorage<S> {
         epoch: u64,
         group_info: &[u8],
     ) -> Result<(), StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.store_group_info(room_id, epoch, group_info)
     }
 
     fn load_group_info(&self, room_id: u128) -> Result<Option<(u64, Vec<u8>)>, StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.load_group_info(room_id)
     }
 
     fn list_rooms(&self) -> Result<Vec<u128>, StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.list_rooms()
@@ -203,8 +252,8 @@ impl<S: Storage> Storage for ChaoticStorage<S> {
         room_id: u128,
         metadata: &StoredRoomMetadata,
     ) -> Result<(), StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.create_room(room_id, metadata)
@@ -214,12 +263,220 @@ impl<S: Storage> Storage for ChaoticStorage<S> {
         &self,
         room_id: u128,
     ) -> Result<Option<StoredRoomMetadata>, StorageError> {
-        self.increment_operation_count();
-        if self.should_fail() {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
             return Err(StorageError::Io("chaotic failure injection".to_string()));
         }
         self.inner.load_room_metadata(room_id)
     }
+
+    fn update_room_metadata(
+        &self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.update_room_metadata(room_id, metadata)
+    }
+
+    fn store_room_policy(&self, room_id: u128, policy: &RoomPolicy) -> Result<(), StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.store_room_policy(room_id, policy)
+    }
+
+    fn load_room_policy(&self, room_id: u128) -> Result<Option<RoomPolicy>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.load_room_policy(room_id)
+    }
+
+    fn add_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.add_member(room_id, user_id)
+    }
+
+    fn remove_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.remove_member(room_id, user_id)
+    }
+
+    fn members(&self, room_id: u128) -> Result<Vec<u64>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.members(room_id)
+    }
+
+    fn member_rooms(&self, user_id: u64) -> Result<Vec<u128>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.member_rooms(user_id)
+    }
+
+    fn append_audit(&self, entries: &[AuditEntry]) -> Result<(), StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.append_audit(entries)
+    }
+
+    fn load_audit(&self, from: u64, limit: usize) -> Result<Vec<AuditEntry>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.load_audit(from, limit)
+    }
+
+    fn queue_delivery(
+        &self,
+        user_id: u64,
+        frame: &Frame,
+        limit: usize,
+    ) -> Result<bool, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.queue_delivery(user_id, frame, limit)
+    }
+
+    fn take_deliveries(&self, user_id: u64) -> Result<Vec<Frame>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.take_deliveries(user_id)
+    }
+
+    fn load_room_checkpoint(&self, room_id: u128) -> Result<Option<RoomCheckpoint>, StorageError> {
+        self.increment_operation_count()?;
+        if self.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        self.inner.load_room_checkpoint(room_id)
+    }
+
+    /// Each staged write counts as an operation and may fail, so failures
+    /// can land mid-batch after earlier writes were staged.
+    fn batch<F>(&self, f: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut dyn StorageBatch) -> Result<(), StorageError>,
+    {
+        self.inner.batch(|inner| f(&mut ChaoticBatch { storage: self, inner }))
+    }
+}
+
+/// Batch wrapper that injects failures into individual staged writes.
+struct ChaoticBatch<'a, S: Storage, B: StorageBatch + ?Sized> {
+    storage: &'a ChaoticStorage<S>,
+    inner: &'a mut B,
+}
+
+impl<S: Storage, B: StorageBatch + ?Sized> ChaoticBatch<'_, S, B> {
+    fn inject_failure(&self) -> Result<(), StorageError> {
+        self.storage.increment_operation_count()?;
+        if self.storage.should_fail()? {
+            return Err(StorageError::Io("chaotic failure injection".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl<S: Storage, B: StorageBatch + ?Sized> StorageBatch for ChaoticBatch<'_, S, B> {
+    fn store_frame(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        frame: &Frame,
+    ) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.store_frame(room_id, log_index, frame)
+    }
+
+    fn store_mls_state(
+        &mut self,
+        room_id: u128,
+        state: &MlsGroupState,
+    ) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.store_mls_state(room_id, state)
+    }
+
+    fn store_group_info(
+        &mut self,
+        room_id: u128,
+        epoch: u64,
+        group_info: &[u8],
+    ) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.store_group_info(room_id, epoch, group_info)
+    }
+
+    fn create_room(
+        &mut self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.create_room(room_id, metadata)
+    }
+
+    fn store_room_policy(
+        &mut self,
+        room_id: u128,
+        policy: &RoomPolicy,
+    ) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.store_room_policy(room_id, policy)
+    }
+
+    fn add_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.add_member(room_id, user_id)
+    }
+
+    fn remove_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.remove_member(room_id, user_id)
+    }
+
+    fn store_room_checkpoint(
+        &mut self,
+        room_id: u128,
+        checkpoint: RoomCheckpoint,
+    ) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.store_room_checkpoint(room_id, checkpoint)
+    }
+
+    fn store_frame_time(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        stored_at: u64,
+    ) -> Result<(), StorageError> {
+        self.inject_failure()?;
+        self.inner.store_frame_time(room_id, log_index, stored_at)
+    }
 }
 
 #[cfg(test)]
@@ -295,6 +552,52 @@ mod tests {
         assert_eq!(chaotic.inner().latest_log_index(100).expect("query failed"), Some(0));
     }
 
+    #[test]
+    fn test_chaotic_mid_batch_failure_leaves_no_partial_write() {
+        let storage = MemoryStorage::new();
+        // Seed 42 at rate 0.2: the first draw passes and the second fails
+        let chaotic = ChaoticStorage::with_seed(storage, 0.2, 42);
+        let room_id = 100u128;
+
+        let mut frame_staged = false;
+        let result = chaotic.batch(|batch| {
+            batch.store_frame(room_id, 0, &create_test_frame(room_id, 0))?;
+            frame_staged = true;
+            batch.add_member(room_id, 7)
+        });
+
+        assert!(frame_staged, "failure should land after the frame was staged");
+        assert!(matches!(result, Err(StorageError::Io(_))));
+        assert_eq!(chaotic.operation_count(), Ok(2));
+        assert_eq!(chaotic.inner().latest_log_index(room_id).unwrap(), None);
+        assert!(chaotic.inner().members(room_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_chaotic_poisoned_lock_returns_error() {
+        let chaotic = ChaoticStorage::new(MemoryStorage::new(), 0.0);
+        let room_id = 100u128;
+
+        // Panic while holding the RNG lock to poison it
+        let rng = Arc::clone(&chaotic.rng);
+        let result = std::thread::spawn(move || {
+            let _guard = rng.lock().unwrap();
+            panic!("poison the rng lock");
+        })
+        .join();
+        assert!(result.is_err());
+
+        assert_eq!(
+            chaotic.store_frame(room_id, 0, &create_test_frame(room_id, 0)),
+            Err(StorageError::Poisoned)
+        );
+        assert_eq!(
+            chaotic.batch(|batch| batch.add_member(room_id, 1)),
+            Err(StorageError::Poisoned)
+        );
+        assert_eq!(chaotic.inner().latest_log_index(room_id), Ok(None));
+    }
+
     #[test]
     #[should_panic(expected = "failure_rate must be between 0.0 and 1.0")]
     fn test_chaotic_rejects_invalid_failure_rate() {
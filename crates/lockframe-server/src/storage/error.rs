@@ -5,6 +5,7 @@
 //! - `Conflict`: Log index gap detected (sequencing violation)
 //! - `Serialization`: Failed to encode/decode data
 //! - `Io`: Underlying storage system errors
+//! - `Poisoned`: A thread panicked while holding the storage lock
 
 use thiserror::Error;
 
@@ -40,6 +41,13 @@ pub enum StorageError {
     /// I/O error (file system, database, etc.)
     #[error("I/O error: {0}")]
     Io(String),
+
+    /// Storage lock was poisoned by a thread that panicked while holding it
+    ///
+    /// The in-memory state may be partially updated, so the operation is
+    /// refused rather than risking a read of inconsistent data.
+    #[error("storage lock poisoned")]
+    Poisoned,
 }
 
 impl From<std::io::Error> for StorageError {
@@ -1,22 +1,30 @@
 #![allow(clippy::disallowed_types, reason = "Synchronous in-memory operations only")]
 
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
 };
 
+use bytes::Bytes;
 use lockframe_core::mls::MlsGroupState;
 use lockframe_proto::Frame;
 
-use super::{Storage, StorageError, StoredRoomMetadata};
+use super::{
+    AuditEntry, RoomPolicy, RoomSnapshot, Storage, StorageBatch, StorageError, StoredRoomMetadata,
+};
+use crate::sequencer::RoomCheckpoint;
 
 /// In-memory storage implementation for testing and simulation
 ///
 /// Uses `HashMap` for fast lookups and Vec for ordered frame storage. All state
 /// is wrapped in Arc<Mutex<>> to allow Clone and concurrent access. Thread-safe
-/// through Mutex, but uses `lock().expect()` which will panic if the mutex is
-/// poisoned - acceptable for test code. All operations are O(1) except
-/// `load_frames` which is O(limit).
+/// through Mutex; if a thread panics while holding it, later operations return
+/// `StorageError::Poisoned` rather than panicking. All operations are O(1)
+/// except `load_frames` which is O(limit).
+///
+/// A single mutex guards all state, so clones observe each other's writes as
+/// soon as the writing call returns, satisfying the [`Storage`] consistency
+/// model.
 #[derive(Clone)]
 pub struct MemoryStorage {
     inner: Arc<Mutex<MemoryStorageInner>>,
@@ -27,7 +35,10 @@ struct MemoryStorageInner {
     rooms: HashMap<u128, StoredRoomMetadata>,
 
     /// Frames organized by room, stored in `log_index` order
-    frames: HashMap<u128, Vec<Frame>>,
+    frames: HashMap<u128, FrameLog>,
+
+    /// Snapshot of each room's pruned frames
+    snapshots: HashMap<u128, RoomSnapshot>,
 
     /// MLS group state per room
     mls_states: HashMap<u128, MlsGroupState>,
@@ -35,6 +46,24 @@ struct MemoryStorageInner {
     /// `GroupInfo` for external joiners, maps `room_id` -> (epoch,
     /// `group_info_bytes`)
     group_infos: HashMap<u128, (u64, Vec<u8>)>,
+
+    /// Membership policy per room
+    policies: HashMap<u128, RoomPolicy>,
+
+    /// Persisted room membership, maps `room_id` -> member user IDs
+    members: HashMap<u128, BTreeSet<u64>>,
+
+    /// Reverse of `members`, maps `user_id` -> rooms they are a member of
+    member_rooms: HashMap<u64, BTreeSet<u128>>,
+
+    /// Error audit log in append order
+    audit: Vec<AuditEntry>,
+
+    /// Frames awaiting each offline user, oldest first
+    deliveries: HashMap<u64, Vec<Frame>>,
+
+    /// Last sequencer checkpoint per room
+    checkpoints: HashMap<u128, RoomCheckpoint>,
 }
 
 impl MemoryStorage {
@@ -44,37 +73,65 @@ impl MemoryStorage {
             inner: Arc::new(Mutex::new(MemoryStorageInner {
                 rooms: HashMap::new(),
                 frames: HashMap::new(),
+                snapshots: HashMap::new(),
                 mls_states: HashMap::new(),
                 group_infos: HashMap::new(),
+                policies: HashMap::new(),
+                members: HashMap::new(),
+                member_rooms: HashMap::new(),
+                audit: Vec::new(),
+                deliveries: HashMap::new(),
+                checkpoints: HashMap::new(),
             })),
         }
     }
 
+    /// Lock the shared state.
+    ///
+    /// A poisoned lock means a thread panicked mid-operation and may have
+    /// left the state half-updated, so it is reported as
+    /// `StorageError::Poisoned` instead of cascading the panic.
+    fn lock(&self) -> Result<MutexGuard<'_, MemoryStorageInner>, StorageError> {
+        self.inner.lock().map_err(|_| StorageError::Poisoned)
+    }
+
     /// Number of rooms with stored frames.
     ///
     /// Useful for debugging and testing.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned (a thread panicked while
-    /// holding the lock). This is acceptable for test/simulation code.
-    #[allow(clippy::expect_used)]
     pub fn room_count(&self) -> usize {
-        self.inner.lock().expect("Mutex poisoned").frames.len()
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner).frames.len()
     }
 
     /// Total number of frames across all rooms.
     ///
     /// Useful for debugging and testing.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned (a thread panicked while
-    /// holding the lock). This is acceptable for test/simulation code.
-    #[allow(clippy::expect_used)]
     pub fn total_frame_count(&self) -> usize {
-        let inner = self.inner.lock().expect("Mutex poisoned");
-        inner.frames.values().map(std::vec::Vec::len).sum()
+        let inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.frames.values().map(|log| log.frames.len()).sum()
+    }
+}
+
+/// One room's frames, minus any pruned from the front.
+#[derive(Default)]
+struct FrameLog {
+    /// Log index of `frames[0]`
+    first_index: u64,
+    /// Retained frames in `log_index` order
+    frames: Vec<Frame>,
+    /// Server time each retained frame was stored at, by `log_index`
+    stored_at: BTreeMap<u64, u64>,
+}
+
+impl FrameLog {
+    /// Index the next stored frame must have.
+    fn next_index(&self) -> u64 {
+        self.first_index + self.frames.len() as u64
+    }
+
+    /// Position of `log_index` in `frames`, if it is retained.
+    fn position(&self, log_index: u64) -> Option<usize> {
+        let position = usize::try_from(log_index.checked_sub(self.first_index)?).ok()?;
+        (position < self.frames.len()).then_some(position)
     }
 }
 
@@ -85,23 +142,17 @@ impl Default for MemoryStorage {
 }
 
 impl Storage for MemoryStorage {
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn store_frame(
         &self,
         room_id: u128,
         log_index: u64,
         frame: &Frame,
     ) -> Result<(), StorageError> {
-        let mut inner = self.inner.lock().expect("Mutex poisoned");
+        let mut inner = self.lock()?;
 
-        let frames = inner.frames.entry(room_id).or_default();
+        let log = inner.frames.entry(room_id).or_default();
 
-        let expected_index = frames.len() as u64;
-        debug_assert!(frames.len() < u64::MAX as usize);
+        let expected_index = log.next_index();
 
         if log_index != expected_index {
             return Err(StorageError::Conflict { expected: expected_index, got: log_index });
@@ -111,147 +162,407 @@ impl Storage for MemoryStorage {
         // Note: This clones the entire frame including payload bytes. Production
         // storage (redb) will avoid this by storing serialized bytes directly.
         // The payload clone is cheap (Arc increment via Bytes) but header is copied.
-        frames.push(frame.clone());
+        log.frames.push(frame.clone());
 
-        debug_assert_eq!(frames.len() as u64 - 1, log_index);
-        debug_assert_eq!(frames[log_index as usize].header.log_index(), log_index);
+        debug_assert_eq!(log.next_index() - 1, log_index);
 
         Ok(())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn latest_log_index(&self, room_id: u128) -> Result<Option<u64>, StorageError> {
-        let inner = self.inner.lock().expect("Mutex poisoned");
+        let inner = self.lock()?;
 
-        Ok(inner.frames.get(&room_id).and_then(|frames| {
-            if frames.is_empty() { None } else { Some(frames.len() as u64 - 1) }
-        }))
+        Ok(inner.frames.get(&room_id).and_then(|log| log.next_index().checked_sub(1)))
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn load_frames(
         &self,
         room_id: u128,
         from: u64,
         limit: usize,
     ) -> Result<Vec<Frame>, StorageError> {
-        let inner = self.inner.lock().expect("Mutex poisoned");
+        let inner = self.lock()?;
 
-        let frames = inner
+        let log = inner
             .frames
             .get(&room_id)
             .ok_or(StorageError::NotFound { room_id, log_index: from })?;
 
-        let start = from as usize;
-        let end = (start + limit).min(frames.len());
-
-        if start > frames.len() {
+        // Pruned frames are skipped: a start before them reads from the first
+        // retained frame
+        let start = usize::try_from(from.saturating_sub(log.first_index)).unwrap_or(usize::MAX);
+        if start > log.frames.len() {
             return Ok(Vec::new());
         }
+        let end = start.saturating_add(limit).min(log.frames.len());
 
-        Ok(frames[start..end].to_vec())
+        Ok(log.frames[start..end].to_vec())
+    }
+
+    fn index_at_or_after(
+        &self,
+        room_id: u128,
+        hlc_timestamp: u64,
+    ) -> Result<Option<u64>, StorageError> {
+        let inner = self.lock()?;
+
+        Ok(inner.frames.get(&room_id).and_then(|log| {
+            let index =
+                log.frames.iter().position(|f| f.header.hlc_timestamp() >= hlc_timestamp)?;
+            Some(log.first_index + index as u64)
+        }))
+    }
+
+    fn last_index_stored_before(
+        &self,
+        room_id: u128,
+        wall_clock: u64,
+    ) -> Result<Option<u64>, StorageError> {
+        let inner = self.lock()?;
+
+        Ok(inner.frames.get(&room_id).and_then(|log| {
+            log.stored_at
+                .iter()
+                .rev()
+                .find(|&(_, &stored_at)| stored_at < wall_clock)
+                .map(|(&log_index, _)| log_index)
+        }))
+    }
+
+    fn prune_frame(&self, room_id: u128, log_index: u64) -> Result<(), StorageError> {
+        let mut inner = self.lock()?;
+        let frame = inner
+            .frames
+            .get_mut(&room_id)
+            .and_then(|log| {
+                let position = log.position(log_index)?;
+                log.frames.get_mut(position)
+            })
+            .ok_or(StorageError::NotFound { room_id, log_index })?;
+        *frame = Frame::new(frame.header, Bytes::new());
+        Ok(())
+    }
+
+    fn prune_frames(&self, room_id: u128, snapshot: &RoomSnapshot) -> Result<(), StorageError> {
+        let mut inner = self.lock()?;
+        if let Some(log) = inner.frames.get_mut(&room_id) {
+            let count = snapshot.first_log_index.saturating_sub(log.first_index);
+            let count = usize::try_from(count).unwrap_or(usize::MAX).min(log.frames.len());
+            log.frames.drain(..count);
+            log.first_index += count as u64;
+            log.stored_at = log.stored_at.split_off(&log.first_index);
+        }
+        inner.snapshots.insert(room_id, snapshot.clone());
+        Ok(())
+    }
+
+    fn load_snapshot(&self, room_id: u128) -> Result<Option<RoomSnapshot>, StorageError> {
+        Ok(self.lock()?.snapshots.get(&room_id).cloned())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn store_mls_state(&self, room_id: u128, state: &MlsGroupState) -> Result<(), StorageError> {
-        self.inner.lock().expect("Mutex poisoned").mls_states.insert(room_id, state.clone());
+        self.lock()?.mls_states.insert(room_id, state.clone());
 
         Ok(())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn load_mls_state(&self, room_id: u128) -> Result<Option<MlsGroupState>, StorageError> {
-        let inner = self.inner.lock().expect("Mutex poisoned");
+        let inner = self.lock()?;
 
         Ok(inner.mls_states.get(&room_id).cloned())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn store_group_info(
         &self,
         room_id: u128,
         epoch: u64,
         group_info: &[u8],
     ) -> Result<(), StorageError> {
-        self.inner
-            .lock()
-            .expect("Mutex poisoned")
-            .group_infos
-            .insert(room_id, (epoch, group_info.to_vec()));
+        self.lock()?.group_infos.insert(room_id, (epoch, group_info.to_vec()));
 
         Ok(())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn load_group_info(&self, room_id: u128) -> Result<Option<(u64, Vec<u8>)>, StorageError> {
-        let inner = self.inner.lock().expect("Mutex poisoned");
+        let inner = self.lock()?;
 
         Ok(inner.group_infos.get(&room_id).cloned())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn list_rooms(&self) -> Result<Vec<u128>, StorageError> {
-        let inner = self.inner.lock().expect("Mutex poisoned");
+        let inner = self.lock()?;
         Ok(inner.rooms.keys().copied().collect())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn create_room(
         &self,
         room_id: u128,
         metadata: &StoredRoomMetadata,
     ) -> Result<(), StorageError> {
-        self.inner
-            .lock()
-            .expect("Mutex poisoned")
-            .rooms
-            .entry(room_id)
-            .or_insert_with(|| metadata.clone());
+        MemoryWrite::Room(room_id, metadata.clone()).apply(&mut *self.lock()?);
         Ok(())
     }
 
-    /// # Panics
-    ///
-    /// Panics if the internal mutex is poisoned. This is acceptable for test
-    /// code.
-    #[allow(clippy::expect_used)]
     fn load_room_metadata(
         &self,
         room_id: u128,
     ) -> Result<Option<StoredRoomMetadata>, StorageError> {
-        Ok(self.inner.lock().expect("Mutex poisoned").rooms.get(&room_id).cloned())
+        Ok(self.lock()?.rooms.get(&room_id).cloned())
+    }
+
+    fn update_room_metadata(
+        &self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError> {
+        if let Some(stored) = self.lock()?.rooms.get_mut(&room_id) {
+            *stored = metadata.clone();
+        }
+        Ok(())
+    }
+
+    fn store_room_policy(&self, room_id: u128, policy: &RoomPolicy) -> Result<(), StorageError> {
+        MemoryWrite::Policy(room_id, policy.clone()).apply(&mut *self.lock()?);
+        Ok(())
+    }
+
+    fn load_room_policy(&self, room_id: u128) -> Result<Option<RoomPolicy>, StorageError> {
+        Ok(self.lock()?.policies.get(&room_id).cloned())
+    }
+
+    fn add_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        MemoryWrite::AddMember(room_id, user_id).apply(&mut *self.lock()?);
+        Ok(())
+    }
+
+    fn remove_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        MemoryWrite::RemoveMember(room_id, user_id).apply(&mut *self.lock()?);
+        Ok(())
+    }
+
+    fn members(&self, room_id: u128) -> Result<Vec<u64>, StorageError> {
+        let inner = self.lock()?;
+        Ok(inner.members.get(&room_id).map(|m| m.iter().copied().collect()).unwrap_or_default())
+    }
+
+    fn member_rooms(&self, user_id: u64) -> Result<Vec<u128>, StorageError> {
+        let inner = self.lock()?;
+        Ok(inner
+            .member_rooms
+            .get(&user_id)
+            .map(|rooms| rooms.iter().copied().collect())
+            .unwrap_or_default())
+    }
+
+    fn append_audit(&self, entries: &[AuditEntry]) -> Result<(), StorageError> {
+        self.lock()?.audit.extend_from_slice(entries);
+        Ok(())
+    }
+
+    fn load_audit(&self, from: u64, limit: usize) -> Result<Vec<AuditEntry>, StorageError> {
+        let inner = self.lock()?;
+        let start = usize::try_from(from).unwrap_or(usize::MAX);
+        Ok(inner.audit.iter().skip(start).take(limit).cloned().collect())
+    }
+
+    fn queue_delivery(
+        &self,
+        user_id: u64,
+        frame: &Frame,
+        limit: usize,
+    ) -> Result<bool, StorageError> {
+        let mut inner = self.lock()?;
+        let queue = inner.deliveries.entry(user_id).or_default();
+        if queue.len() >= limit {
+            return Ok(false);
+        }
+        queue.push(frame.clone());
+        Ok(true)
+    }
+
+    fn take_deliveries(&self, user_id: u64) -> Result<Vec<Frame>, StorageError> {
+        Ok(self.lock()?.deliveries.remove(&user_id).unwrap_or_default())
+    }
+
+    fn load_room_checkpoint(&self, room_id: u128) -> Result<Option<RoomCheckpoint>, StorageError> {
+        Ok(self.lock()?.checkpoints.get(&room_id).copied())
+    }
+
+    /// Holds the lock for the whole batch and applies the staged writes only
+    /// once `f` succeeds.
+    fn batch<F>(&self, f: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut dyn StorageBatch) -> Result<(), StorageError>,
+    {
+        let mut inner = self.lock()?;
+
+        let mut batch = MemoryBatch { inner: &inner, writes: Vec::new() };
+        f(&mut batch)?;
+        let writes = batch.writes;
+
+        for write in writes {
+            write.apply(&mut inner);
+        }
+
+        Ok(())
+    }
+}
+
+/// Write staged by a [`MemoryBatch`].
+enum MemoryWrite {
+    Room(u128, StoredRoomMetadata),
+    Policy(u128, RoomPolicy),
+    Frame(u128, Frame),
+    MlsState(u128, MlsGroupState),
+    GroupInfo(u128, u64, Vec<u8>),
+    AddMember(u128, u64),
+    RemoveMember(u128, u64),
+    Checkpoint(u128, RoomCheckpoint),
+    FrameTime(u128, u64, u64),
+}
+
+impl MemoryWrite {
+    fn apply(self, inner: &mut MemoryStorageInner) {
+        match self {
+            Self::Room(room_id, metadata) => {
+                inner.rooms.entry(room_id).or_insert(metadata);
+            },
+            Self::Policy(room_id, policy) => {
+                inner.policies.insert(room_id, policy);
+            },
+            Self::Frame(room_id, frame) => {
+                inner.frames.entry(room_id).or_default().frames.push(frame);
+            },
+            Self::MlsState(room_id, state) => {
+                inner.mls_states.insert(room_id, state);
+            },
+            Self::GroupInfo(room_id, epoch, group_info) => {
+                inner.group_infos.insert(room_id, (epoch, group_info));
+            },
+            Self::AddMember(room_id, user_id) => {
+                inner.members.entry(room_id).or_default().insert(user_id);
+                inner.member_rooms.entry(user_id).or_default().insert(room_id);
+            },
+            Self::RemoveMember(room_id, user_id) => {
+                if let Some(members) = inner.members.get_mut(&room_id) {
+                    members.remove(&user_id);
+                    if members.is_empty() {
+                        inner.members.remove(&room_id);
+                    }
+                }
+                if let Some(rooms) = inner.member_rooms.get_mut(&user_id) {
+                    rooms.remove(&room_id);
+                    if rooms.is_empty() {
+                        inner.member_rooms.remove(&user_id);
+                    }
+                }
+            },
+            Self::Checkpoint(room_id, checkpoint) => {
+                inner.checkpoints.insert(room_id, checkpoint);
+            },
+            Self::FrameTime(room_id, log_index, stored_at) => {
+                inner.frames.entry(room_id).or_default().stored_at.insert(log_index, stored_at);
+            },
+        }
+    }
+}
+
+/// Staging area for [`MemoryStorage::batch`].
+///
+/// Reads committed state through the held lock and buffers writes until the
+/// batch closure returns.
+struct MemoryBatch<'a> {
+    inner: &'a MemoryStorageInner,
+    writes: Vec<MemoryWrite>,
+}
+
+impl StorageBatch for MemoryBatch<'_> {
+    fn store_frame(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        frame: &Frame,
+    ) -> Result<(), StorageError> {
+        let stored = self.inner.frames.get(&room_id).map_or(0, FrameLog::next_index);
+        let staged = self
+            .writes
+            .iter()
+            .filter(|w| matches!(w, MemoryWrite::Frame(id, _) if *id == room_id))
+            .count();
+
+        let expected_index = stored + staged as u64;
+        if log_index != expected_index {
+            return Err(StorageError::Conflict { expected: expected_index, got: log_index });
+        }
+
+        self.writes.push(MemoryWrite::Frame(room_id, frame.clone()));
+        Ok(())
+    }
+
+    fn store_mls_state(
+        &mut self,
+        room_id: u128,
+        state: &MlsGroupState,
+    ) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::MlsState(room_id, state.clone()));
+        Ok(())
+    }
+
+    fn store_group_info(
+        &mut self,
+        room_id: u128,
+        epoch: u64,
+        group_info: &[u8],
+    ) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::GroupInfo(room_id, epoch, group_info.to_vec()));
+        Ok(())
+    }
+
+    fn create_room(
+        &mut self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::Room(room_id, metadata.clone()));
+        Ok(())
+    }
+
+    fn store_room_policy(
+        &mut self,
+        room_id: u128,
+        policy: &RoomPolicy,
+    ) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::Policy(room_id, policy.clone()));
+        Ok(())
+    }
+
+    fn add_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::AddMember(room_id, user_id));
+        Ok(())
+    }
+
+    fn remove_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::RemoveMember(room_id, user_id));
+        Ok(())
+    }
+
+    fn store_room_checkpoint(
+        &mut self,
+        room_id: u128,
+        checkpoint: RoomCheckpoint,
+    ) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::Checkpoint(room_id, checkpoint));
+        Ok(())
+    }
+
+    fn store_frame_time(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        stored_at: u64,
+    ) -> Result<(), StorageError> {
+        self.writes.push(MemoryWrite::FrameTime(room_id, log_index, stored_at));
+        Ok(())
     }
 }
 
@@ -471,7 +782,11 @@ mod tests {
 
         // Create rooms explicitly
         for room_id in [100u128, 200, 300] {
-            let metadata = StoredRoomMetadata { creator: room_id as u64, created_at_secs: 0 };
+            let metadata = StoredRoomMetadata {
+                creator: room_id as u64,
+                created_at_secs: 0,
+                ..Default::default()
+            };
             storage.create_room(room_id, &metadata).unwrap();
         }
 
@@ -485,7 +800,11 @@ mod tests {
     fn test_create_room() {
         let storage = MemoryStorage::new();
         let room_id = 100u128;
-        let metadata = StoredRoomMetadata { creator: 42, created_at_secs: 1_234_567_890 };
+        let metadata = StoredRoomMetadata {
+            creator: 42,
+            created_at_secs: 1_234_567_890,
+            ..Default::default()
+        };
 
         storage.create_room(room_id, &metadata).unwrap();
 
@@ -498,8 +817,10 @@ mod tests {
     fn test_create_room_idempotent() {
         let storage = MemoryStorage::new();
         let room_id = 100u128;
-        let metadata1 = StoredRoomMetadata { creator: 42, created_at_secs: 100 };
-        let metadata2 = StoredRoomMetadata { creator: 99, created_at_secs: 200 };
+        let metadata1 =
+            StoredRoomMetadata { creator: 42, created_at_secs: 100, ..Default::default() };
+        let metadata2 =
+            StoredRoomMetadata { creator: 99, created_at_secs: 200, ..Default::default() };
 
         storage.create_room(room_id, &metadata1).unwrap();
         storage.create_room(room_id, &metadata2).unwrap(); // Should not overwrite
@@ -508,9 +829,237 @@ mod tests {
         assert_eq!(loaded.creator, 42); // Original creator preserved
     }
 
+    #[test]
+    fn test_update_room_metadata() {
+        let storage = MemoryStorage::new();
+        let metadata = StoredRoomMetadata { creator: 42, ..Default::default() };
+        let renamed = StoredRoomMetadata { name: "lobby".into(), ..metadata.clone() };
+
+        storage.update_room_metadata(100, &renamed).unwrap();
+        assert!(storage.load_room_metadata(100).unwrap().is_none());
+
+        storage.create_room(100, &metadata).unwrap();
+        storage.update_room_metadata(100, &renamed).unwrap();
+        assert_eq!(storage.load_room_metadata(100).unwrap(), Some(renamed));
+    }
+
     #[test]
     fn test_load_room_metadata_not_found() {
         let storage = MemoryStorage::new();
         assert!(storage.load_room_metadata(999).unwrap().is_none());
     }
+
+    #[test]
+    fn test_prune_frame_keeps_header() {
+        let storage = MemoryStorage::new();
+        let room_id = 100;
+        let frame = create_test_frame(room_id, 0);
+        let frame = Frame::new(frame.header, Bytes::from_static(b"secret"));
+        storage.store_frame(room_id, 0, &frame).unwrap();
+
+        storage.prune_frame(room_id, 0).unwrap();
+
+        let pruned = &storage.load_frames(room_id, 0, 1).unwrap()[0];
+        assert!(pruned.payload.is_empty());
+        assert_eq!(pruned.header.log_index(), 0);
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(0));
+        assert!(matches!(storage.prune_frame(room_id, 1), Err(StorageError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_prune_frames_keeps_latest_index() {
+        let storage = MemoryStorage::new();
+        let room_id = 100;
+        for i in 0..5u64 {
+            let mut frame = create_test_frame(room_id, i);
+            frame.header.set_hlc_timestamp(i * 10);
+            storage.store_frame(room_id, i, &frame).unwrap();
+        }
+        assert_eq!(storage.load_snapshot(room_id).unwrap(), None);
+
+        let snapshot = RoomSnapshot { first_log_index: 3, epoch: 2, group_info: b"info".to_vec() };
+        storage.prune_frames(room_id, &snapshot).unwrap();
+
+        let frames = storage.load_frames(room_id, 0, 10).unwrap();
+        let indices: Vec<u64> = frames.iter().map(|f| f.header.log_index()).collect();
+        assert_eq!(indices, vec![3, 4]);
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(4));
+        assert_eq!(storage.index_at_or_after(room_id, 0).unwrap(), Some(3));
+        assert_eq!(storage.load_snapshot(room_id).unwrap(), Some(snapshot));
+
+        storage.store_frame(room_id, 5, &create_test_frame(room_id, 5)).unwrap();
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(5));
+    }
+
+    #[test]
+    fn test_delivery_queue_is_bounded_and_drained() {
+        let storage = MemoryStorage::new();
+        for i in 0..3u64 {
+            let queued = storage.queue_delivery(42, &create_test_frame(7, i), 2).unwrap();
+            assert_eq!(queued, i < 2);
+        }
+        assert!(storage.queue_delivery(43, &create_test_frame(7, 9), 2).unwrap());
+
+        let frames = storage.take_deliveries(42).unwrap();
+        let indices: Vec<u64> = frames.iter().map(|f| f.header.log_index()).collect();
+        assert_eq!(indices, vec![0, 1]);
+        assert!(storage.take_deliveries(42).unwrap().is_empty());
+        assert_eq!(storage.take_deliveries(43).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_room_checkpoints_are_kept_per_room() {
+        let storage = MemoryStorage::new();
+        assert_eq!(storage.load_room_checkpoint(100).unwrap(), None);
+
+        let first = RoomCheckpoint { next_log_index: 3, epoch: None };
+        storage.batch(|batch| batch.store_room_checkpoint(100, first)).unwrap();
+        for checkpoint in [RoomCheckpoint { next_log_index: 7, epoch: Some(1) }, RoomCheckpoint {
+            next_log_index: 9,
+            epoch: Some(2),
+        }] {
+            storage.batch(|batch| batch.store_room_checkpoint(200, checkpoint)).unwrap();
+        }
+
+        assert_eq!(storage.load_room_checkpoint(100).unwrap(), Some(first));
+        assert_eq!(
+            storage.load_room_checkpoint(200).unwrap(),
+            Some(RoomCheckpoint { next_log_index: 9, epoch: Some(2) })
+        );
+    }
+
+    #[test]
+    fn test_index_at_or_after() {
+        let storage = MemoryStorage::new();
+        let room_id = 100;
+
+        // Non-application frames carry HLC 0, so the log isn't HLC-ordered
+        for (i, hlc) in [10u64, 20, 0, 0, 20, 0, 30].into_iter().enumerate() {
+            let mut frame = create_test_frame(room_id, i as u64);
+            frame.header.set_hlc_timestamp(hlc);
+            storage.store_frame(room_id, i as u64, &frame).unwrap();
+        }
+
+        assert_eq!(storage.index_at_or_after(room_id, 0).unwrap(), Some(0));
+        assert_eq!(storage.index_at_or_after(room_id, 20).unwrap(), Some(1));
+        assert_eq!(storage.index_at_or_after(room_id, 21).unwrap(), Some(6));
+        assert_eq!(storage.index_at_or_after(room_id, 31).unwrap(), None);
+        assert_eq!(storage.index_at_or_after(999, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_members_add_remove() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+
+        storage.add_member(room_id, 7).unwrap();
+        storage.add_member(room_id, 3).unwrap();
+        storage.add_member(room_id, 7).unwrap(); // Idempotent
+        assert_eq!(storage.members(room_id).unwrap(), vec![3, 7]);
+
+        storage.remove_member(room_id, 7).unwrap();
+        storage.remove_member(room_id, 99).unwrap(); // Non-member is a no-op
+        assert_eq!(storage.members(room_id).unwrap(), vec![3]);
+        assert!(storage.members(200).unwrap().is_empty());
+        assert_eq!(storage.member_rooms(3).unwrap(), vec![room_id]);
+        assert!(storage.member_rooms(7).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clones_read_writes_across_threads() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+
+        // Each writer thread stores through its own clone; after every write
+        // the main thread reads it back through a different clone
+        for log_index in 0..10 {
+            let writer = storage.clone();
+            std::thread::spawn(move || {
+                writer.store_frame(room_id, log_index, &create_test_frame(room_id, log_index))?;
+                writer.add_member(room_id, log_index)
+            })
+            .join()
+            .unwrap()
+            .unwrap();
+
+            let reader = storage.clone();
+            assert_eq!(reader.latest_log_index(room_id).unwrap(), Some(log_index));
+            assert!(reader.members(room_id).unwrap().contains(&log_index));
+        }
+
+        // Writes from the main thread are visible to a reader thread's clone
+        storage.store_group_info(room_id, 3, b"info").unwrap();
+        let reader = storage.clone();
+        let (frames, group_info) = std::thread::spawn(move || {
+            (reader.load_frames(room_id, 0, 100).unwrap(), reader.load_group_info(room_id).unwrap())
+        })
+        .join()
+        .unwrap();
+        assert_eq!(frames.len(), 10);
+        assert_eq!(group_info, Some((3, b"info".to_vec())));
+    }
+
+    #[test]
+    fn test_poisoned_lock_returns_error() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+        storage.store_frame(room_id, 0, &create_test_frame(room_id, 0)).unwrap();
+
+        // Panic while holding the lock to poison it
+        let poisoner = storage.clone();
+        let result = std::thread::spawn(move || {
+            let _guard = poisoner.inner.lock().unwrap();
+            panic!("poison the storage lock");
+        })
+        .join();
+        assert!(result.is_err());
+
+        assert_eq!(storage.latest_log_index(room_id), Err(StorageError::Poisoned));
+        assert_eq!(
+            storage.store_frame(room_id, 1, &create_test_frame(room_id, 1)),
+            Err(StorageError::Poisoned)
+        );
+        assert_eq!(storage.members(room_id), Err(StorageError::Poisoned));
+        assert_eq!(
+            storage.batch(|batch| batch.add_member(room_id, 1)),
+            Err(StorageError::Poisoned)
+        );
+        assert_eq!(storage.total_frame_count(), 1);
+    }
+
+    #[test]
+    fn test_batch_applies_all_writes() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+
+        storage
+            .batch(|batch| {
+                batch.store_frame(room_id, 0, &create_test_frame(room_id, 0))?;
+                batch.store_frame(room_id, 1, &create_test_frame(room_id, 1))?;
+                batch.add_member(room_id, 7)?;
+                batch.store_group_info(room_id, 1, b"info")
+            })
+            .unwrap();
+
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(1));
+        assert_eq!(storage.members(room_id).unwrap(), vec![7]);
+        assert_eq!(storage.load_group_info(room_id).unwrap(), Some((1, b"info".to_vec())));
+    }
+
+    #[test]
+    fn test_batch_failure_discards_staged_writes() {
+        let storage = MemoryStorage::new();
+        let room_id = 100u128;
+
+        // The second frame conflicts with the first, staged in the same batch
+        let result = storage.batch(|batch| {
+            batch.store_frame(room_id, 0, &create_test_frame(room_id, 0))?;
+            batch.add_member(room_id, 7)?;
+            batch.store_frame(room_id, 0, &create_test_frame(room_id, 0))
+        });
+
+        assert!(matches!(result, Err(StorageError::Conflict { expected: 1, got: 0 })));
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), None);
+        assert!(storage.members(room_id).unwrap().is_empty());
+    }
 }
@@ -8,6 +8,8 @@ mod error;
 mod memory;
 mod redb;
 
+use std::{collections::HashSet, time::Duration};
+
 pub use chaotic::ChaoticStorage;
 pub use error::StorageError;
 use lockframe_core::mls::MlsGroupState;
@@ -16,17 +18,104 @@ pub use memory::MemoryStorage;
 use serde::{Deserialize, Serialize};
 
 pub use self::redb::RedbStorage;
+use crate::sequencer::RoomCheckpoint;
 
 /// Metadata about a room stored in the ROOMS table.
 ///
 /// This is persisted separately from frames to survive frame deletion
 /// (e.g., retention policies) and enable O(rooms) enumeration.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StoredRoomMetadata {
     /// User ID who created the room.
     pub creator: u64,
     /// Unix timestamp (seconds) when room was created.
     pub created_at_secs: u64,
+    /// Whether the room's creation is announced in the room directory.
+    #[serde(default)]
+    pub public: bool,
+    /// Display name shown in the room directory.
+    #[serde(default)]
+    pub name: String,
+    /// Topic shown in the room directory.
+    #[serde(default)]
+    pub topic: String,
+    /// How long the room's frames are kept.
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// Which of a room's frames the server keeps.
+///
+/// Frames outside the policy are pruned by the server's retention sweep,
+/// which leaves a [`RoomSnapshot`] in their place. The newest frame is always
+/// kept. The default keeps everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Prune frames the server stored longer ago than this.
+    pub max_age: Option<Duration>,
+    /// Keep at most this many of the newest frames.
+    pub max_frames: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Whether the policy ever prunes anything.
+    pub fn is_unbounded(&self) -> bool {
+        self.max_age.is_none() && self.max_frames.is_none()
+    }
+}
+
+/// What remains of a room's pruned frames.
+///
+/// Sync requests that start before `first_log_index` are answered with the
+/// snapshot followed by the retained frames.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomSnapshot {
+    /// Log index of the oldest frame still stored.
+    pub first_log_index: u64,
+    /// MLS epoch of `group_info`.
+    pub epoch: u64,
+    /// `GroupInfo` stored for the room when its frames were pruned.
+    pub group_info: Vec<u8>,
+}
+
+/// Who may change a room's membership.
+///
+/// Stored separately from [`StoredRoomMetadata`] because, unlike the
+/// creator, it changes over the room's lifetime.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomPolicy {
+    /// Members allowed to add and remove members. Empty means anyone may.
+    ///
+    /// Membership is the only thing an empty set opens up. Other admin-only
+    /// actions, such as renaming the room or redacting others' messages, fall
+    /// back to the room's creator instead.
+    pub admins: HashSet<u64>,
+}
+
+impl RoomPolicy {
+    /// Whether `user_id` may add or remove members: any user while no admins
+    /// are assigned, otherwise only the admins.
+    pub fn may_change_membership(&self, user_id: u64) -> bool {
+        self.admins.is_empty() || self.admins.contains(&user_id)
+    }
+}
+
+/// One `Error` frame the server sent, kept in the audit log.
+///
+/// Operators read these back with [`Storage::load_audit`] to spot recurring
+/// failures without scraping logs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// `ErrorPayload` code.
+    pub code: u16,
+    /// Session the error was sent to.
+    pub session_id: u64,
+    /// Room the error concerns (0 if it isn't about a room).
+    pub room_id: u128,
+    /// Human-readable reason from the error payload.
+    pub reason: String,
+    /// Wall-clock time the error was sent, Unix milliseconds.
+    pub at_millis: u64,
 }
 
 /// Storage abstraction for frames and MLS group state
@@ -35,12 +124,20 @@ pub struct StoredRoomMetadata {
 /// (thread-safe), and synchronous (no async methods). Implementations typically
 /// share internal state via Arc, so clones access the same underlying storage.
 ///
-/// # Panics
+/// # Consistency
 ///
-/// Implementations may panic if internal synchronization primitives are
-/// poisoned (a thread panicked while holding a lock). Acceptable for
-/// test/simulation code, but production implementations should handle poisoned
-/// mutexes gracefully.
+/// All clones of a storage are one store. Once a write method returns `Ok`,
+/// the write is visible to every subsequent read through any clone, on any
+/// thread (read-your-writes across clones). Each method call is atomic on its
+/// own; use [`Storage::batch`] when several writes must become visible
+/// together. Readers never observe a partially applied batch.
+///
+/// # Poisoning
+///
+/// Implementations must not panic if internal synchronization primitives are
+/// poisoned (a thread panicked while holding a lock). Report it as
+/// `StorageError::Poisoned` so one panicked task doesn't take down every
+/// other user of the storage.
 pub trait Storage: Clone + Send + Sync + 'static {
     /// Store a frame in the room's log at the given index
     ///
@@ -67,6 +164,56 @@ pub trait Storage: Clone + Send + Sync + 'static {
         limit: usize,
     ) -> Result<Vec<Frame>, StorageError>;
 
+    /// First log index whose frame has an HLC timestamp at or after
+    /// `hlc_timestamp`.
+    ///
+    /// HLCs are set by clients and are 0 on non-application frames, so they
+    /// aren't ordered by log index; this scans the retained frames in order
+    /// rather than searching. Returns `None` if no stored frame is at or after
+    /// `hlc_timestamp` or the room has no frames.
+    fn index_at_or_after(
+        &self,
+        room_id: u128,
+        hlc_timestamp: u64,
+    ) -> Result<Option<u64>, StorageError>;
+
+    /// Newest log index whose frame was stored before `wall_clock` (Unix
+    /// milliseconds), going by the server times staged with
+    /// [`StorageBatch::store_frame_time`].
+    ///
+    /// Unlike frame HLCs, which clients set, these times are assigned in log
+    /// order, so retention can cut the log here. Frames stored without a time
+    /// are ignored. Returns `None` if no timed frame is that old.
+    fn last_index_stored_before(
+        &self,
+        room_id: u128,
+        wall_clock: u64,
+    ) -> Result<Option<u64>, StorageError>;
+
+    /// Drop the payload of a stored frame, keeping its header.
+    ///
+    /// Used to discard expired messages. The log keeps its length and
+    /// headers, so indices and [`Storage::index_at_or_after`] are unaffected.
+    /// Returns `StorageError::NotFound` if no frame is stored at `log_index`.
+    fn prune_frame(&self, room_id: u128, log_index: u64) -> Result<(), StorageError>;
+
+    /// Delete every frame before `snapshot.first_log_index`, along with their
+    /// stored times, and store `snapshot` in their place, atomically.
+    ///
+    /// Later frames keep their indices, so [`Storage::latest_log_index`] and
+    /// appends are unaffected. [`Storage::load_frames`] skips deleted frames.
+    ///
+    /// # Invariants
+    ///
+    /// - Pre: `snapshot.first_log_index` is at most the latest log index, so
+    ///   the newest frame is never deleted
+    fn prune_frames(&self, room_id: u128, snapshot: &RoomSnapshot) -> Result<(), StorageError>;
+
+    /// Load the snapshot left by the last [`Storage::prune_frames`].
+    ///
+    /// Returns `None` if the room's frames were never pruned.
+    fn load_snapshot(&self, room_id: u128) -> Result<Option<RoomSnapshot>, StorageError>;
+
     /// Store MLS group state for a room
     ///
     /// Overwrites any existing state for this room.
@@ -113,4 +260,145 @@ pub trait Storage: Clone + Send + Sync + 'static {
     /// Returns `None` if room doesn't exist in the ROOMS table.
     fn load_room_metadata(&self, room_id: u128)
     -> Result<Option<StoredRoomMetadata>, StorageError>;
+
+    /// Replace the metadata of an existing room.
+    ///
+    /// Unlike [`Storage::create_room`], this overwrites. Rooms that were never
+    /// created are left absent and `Ok` is returned.
+    fn update_room_metadata(
+        &self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError>;
+
+    /// Store a room's membership policy, replacing any earlier one.
+    fn store_room_policy(&self, room_id: u128, policy: &RoomPolicy) -> Result<(), StorageError>;
+
+    /// Load a room's membership policy.
+    ///
+    /// Returns `None` if no policy was ever stored for this room.
+    fn load_room_policy(&self, room_id: u128) -> Result<Option<RoomPolicy>, StorageError>;
+
+    /// Record a user as a member of a room.
+    ///
+    /// Membership is persisted independently of live session subscriptions,
+    /// so it survives disconnects and server restarts. Idempotent.
+    fn add_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError>;
+
+    /// Remove a user from a room's persisted membership.
+    ///
+    /// Idempotent - removing a non-member is a no-op.
+    fn remove_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError>;
+
+    /// Load the persisted members of a room.
+    ///
+    /// Returns user IDs in ascending order, or an empty list if the room has
+    /// no recorded members.
+    fn members(&self, room_id: u128) -> Result<Vec<u64>, StorageError>;
+
+    /// Load the rooms `user_id` is a persisted member of.
+    ///
+    /// Answered from an index kept alongside membership, so it does not scan
+    /// every room. Returns room IDs in ascending order.
+    fn member_rooms(&self, user_id: u64) -> Result<Vec<u128>, StorageError>;
+
+    /// Append entries to the error audit log in one write.
+    ///
+    /// The log is append-only; entries are numbered from 0 in append order.
+    fn append_audit(&self, entries: &[AuditEntry]) -> Result<(), StorageError>;
+
+    /// Load audit entries `[from, from+limit)` in append order.
+    ///
+    /// If fewer than `limit` entries exist, returns all available entries.
+    fn load_audit(&self, from: u64, limit: usize) -> Result<Vec<AuditEntry>, StorageError>;
+
+    /// Queue `frame` for delivery to `user_id` when they next connect.
+    ///
+    /// Holds at most `limit` frames per user. Returns `false` without
+    /// queueing if the user's queue is already full.
+    fn queue_delivery(
+        &self,
+        user_id: u64,
+        frame: &Frame,
+        limit: usize,
+    ) -> Result<bool, StorageError>;
+
+    /// Remove and return every frame queued for `user_id`, oldest first.
+    fn take_deliveries(&self, user_id: u64) -> Result<Vec<Frame>, StorageError>;
+
+    /// Load the last checkpoint stored for `room_id`, if any.
+    ///
+    /// Checkpoints are written with each appended frame through
+    /// [`StorageBatch::store_room_checkpoint`].
+    fn load_room_checkpoint(&self, room_id: u128) -> Result<Option<RoomCheckpoint>, StorageError>;
+
+    /// Apply several writes atomically.
+    ///
+    /// `f` stages writes on a [`StorageBatch`]. They become visible together
+    /// when `f` returns `Ok`; if `f` or any staged write fails, none of them
+    /// are applied and the error is returned.
+    fn batch<F>(&self, f: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut dyn StorageBatch) -> Result<(), StorageError>;
+}
+
+/// Writes staged inside [`Storage::batch`].
+///
+/// Each method mirrors the [`Storage`] method of the same name, including its
+/// validation: a `store_frame` conflict is reported when the write is staged,
+/// counting frames staged earlier in the same batch.
+pub trait StorageBatch {
+    /// Stage a frame. See [`Storage::store_frame`].
+    fn store_frame(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        frame: &Frame,
+    ) -> Result<(), StorageError>;
+
+    /// Stage the server wall-clock time (Unix milliseconds) at which the frame
+    /// at `log_index` was stored. See [`Storage::last_index_stored_before`].
+    fn store_frame_time(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        stored_at: u64,
+    ) -> Result<(), StorageError>;
+
+    /// Stage MLS group state. See [`Storage::store_mls_state`].
+    fn store_mls_state(&mut self, room_id: u128, state: &MlsGroupState)
+    -> Result<(), StorageError>;
+
+    /// Stage `GroupInfo`. See [`Storage::store_group_info`].
+    fn store_group_info(
+        &mut self,
+        room_id: u128,
+        epoch: u64,
+        group_info: &[u8],
+    ) -> Result<(), StorageError>;
+
+    /// Stage a room's creation. See [`Storage::create_room`].
+    fn create_room(
+        &mut self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError>;
+
+    /// Stage a room's membership policy. See [`Storage::store_room_policy`].
+    fn store_room_policy(&mut self, room_id: u128, policy: &RoomPolicy)
+    -> Result<(), StorageError>;
+
+    /// Stage a membership addition. See [`Storage::add_member`].
+    fn add_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError>;
+
+    /// Stage a membership removal. See [`Storage::remove_member`].
+    fn remove_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError>;
+
+    /// Stage one room's sequencer checkpoint, replacing its earlier one. See
+    /// [`Storage::load_room_checkpoint`].
+    fn store_room_checkpoint(
+        &mut self,
+        room_id: u128,
+        checkpoint: RoomCheckpoint,
+    ) -> Result<(), StorageError>;
 }
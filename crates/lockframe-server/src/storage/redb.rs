@@ -5,17 +5,26 @@
 
 use std::{path::Path, sync::Arc};
 
+use bytes::Bytes;
 use lockframe_core::mls::MlsGroupState;
 use lockframe_proto::Frame;
-use redb::{Database, ReadableTable, TableDefinition};
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition, WriteTransaction};
 
-use super::{Storage, StorageError, StoredRoomMetadata};
+use super::{
+    AuditEntry, RoomPolicy, RoomSnapshot, Storage, StorageBatch, StorageError, StoredRoomMetadata,
+};
+use crate::sequencer::RoomCheckpoint;
 
 /// Table: frames
 /// Key: (`room_id`: u128, `log_index`: u64) as big-endian bytes [24 bytes]
 /// Value: Frame bytes (header + payload concatenated)
 const FRAMES: TableDefinition<&[u8], &[u8]> = TableDefinition::new("frames");
 
+/// Table: `frame_times`
+/// Key: (`room_id`: u128, `log_index`: u64) as big-endian bytes [24 bytes]
+/// Value: server wall-clock time the frame was stored at (8 bytes BE)
+const FRAME_TIMES: TableDefinition<&[u8], &[u8]> = TableDefinition::new("frame_times");
+
 /// Table: `mls_state`
 /// Key: `room_id` as big-endian bytes [16 bytes]
 /// Value: CBOR-encoded `MlsGroupState`
@@ -31,6 +40,41 @@ const GROUP_INFO: TableDefinition<&[u8], &[u8]> = TableDefinition::new("group_in
 /// Value: CBOR-encoded `StoredRoomMetadata`
 const ROOMS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("rooms");
 
+/// Table: `room_policies`
+/// Key: `room_id` as big-endian bytes [16 bytes]
+/// Value: CBOR-encoded `RoomPolicy`
+const ROOM_POLICIES: TableDefinition<&[u8], &[u8]> = TableDefinition::new("room_policies");
+
+/// Table: snapshots
+/// Key: `room_id` as big-endian bytes [16 bytes]
+/// Value: CBOR-encoded `RoomSnapshot`
+const SNAPSHOTS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("snapshots");
+
+/// Table: members
+/// Key: (`room_id`: u128, `user_id`: u64) as big-endian bytes [24 bytes]
+/// Value: empty (presence of the key records membership)
+const MEMBERS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("members");
+
+/// Table: `member_rooms` (reverse index of MEMBERS)
+/// Key: (`user_id`: u64, `room_id`: u128) as big-endian bytes [24 bytes]
+/// Value: empty (presence of the key records membership)
+const MEMBER_ROOMS: TableDefinition<&[u8], &[u8]> = TableDefinition::new("member_rooms");
+
+/// Table: audit
+/// Key: sequence number as big-endian bytes [8 bytes]
+/// Value: CBOR-encoded `AuditEntry`
+const AUDIT: TableDefinition<&[u8], &[u8]> = TableDefinition::new("audit");
+
+/// Table: deliveries
+/// Key: (`user_id`: u64, sequence: u64) as big-endian bytes [16 bytes]
+/// Value: Serialized frame (header + payload)
+const DELIVERIES: TableDefinition<&[u8], &[u8]> = TableDefinition::new("deliveries");
+
+/// Table: sequencer
+/// Key: `room_id` as big-endian bytes [16 bytes]
+/// Value: CBOR-encoded `RoomCheckpoint`
+const SEQUENCER: TableDefinition<&[u8], &[u8]> = TableDefinition::new("sequencer");
+
 /// Durable storage backed by Redb.
 ///
 /// Thread-safe through Redb's internal locking. Clone is cheap (Arc).
@@ -42,8 +86,10 @@ pub struct RedbStorage {
 impl RedbStorage {
     /// Open or create a Redb database at the given path.
     ///
-    /// Creates tables if they don't exist (FRAMES, `MLS_STATE`, `GROUP_INFO`,
-    /// ROOMS).
+    /// Creates tables if they don't exist (FRAMES, `FRAME_TIMES`, `MLS_STATE`,
+    /// `GROUP_INFO`, ROOMS, `ROOM_POLICIES`, SNAPSHOTS, MEMBERS,
+    /// `MEMBER_ROOMS`, AUDIT, DELIVERIES, SEQUENCER). A database written
+    /// before `MEMBER_ROOMS` existed has the index rebuilt from MEMBERS.
     ///
     /// # Errors
     ///
@@ -54,9 +100,27 @@ impl RedbStorage {
         let txn = db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
         {
             let _ = txn.open_table(FRAMES).map_err(|e| StorageError::Io(e.to_string()))?;
+            let _ = txn.open_table(FRAME_TIMES).map_err(|e| StorageError::Io(e.to_string()))?;
             let _ = txn.open_table(MLS_STATE).map_err(|e| StorageError::Io(e.to_string()))?;
             let _ = txn.open_table(GROUP_INFO).map_err(|e| StorageError::Io(e.to_string()))?;
             let _ = txn.open_table(ROOMS).map_err(|e| StorageError::Io(e.to_string()))?;
+            let _ = txn.open_table(ROOM_POLICIES).map_err(|e| StorageError::Io(e.to_string()))?;
+            let _ = txn.open_table(SNAPSHOTS).map_err(|e| StorageError::Io(e.to_string()))?;
+            let members = txn.open_table(MEMBERS).map_err(|e| StorageError::Io(e.to_string()))?;
+            let mut member_rooms =
+                txn.open_table(MEMBER_ROOMS).map_err(|e| StorageError::Io(e.to_string()))?;
+            if member_rooms.is_empty().map_err(|e| StorageError::Io(e.to_string()))? {
+                for result in members.iter().map_err(|e| StorageError::Io(e.to_string()))? {
+                    let (key, _) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+                    let (room_id, user_id) = decode_frame_key(key.value());
+                    member_rooms
+                        .insert(encode_member_room_key(user_id, room_id).as_slice(), [].as_slice())
+                        .map_err(|e| StorageError::Io(e.to_string()))?;
+                }
+            }
+            let _ = txn.open_table(AUDIT).map_err(|e| StorageError::Io(e.to_string()))?;
+            let _ = txn.open_table(DELIVERIES).map_err(|e| StorageError::Io(e.to_string()))?;
+            let _ = txn.open_table(SEQUENCER).map_err(|e| StorageError::Io(e.to_string()))?;
         }
         txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
 
@@ -103,31 +167,7 @@ impl Storage for RedbStorage {
         log_index: u64,
         frame: &Frame,
     ) -> Result<(), StorageError> {
-        let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
-
-        {
-            let mut table = txn.open_table(FRAMES).map_err(|e| StorageError::Io(e.to_string()))?;
-
-            let expected_index = self.compute_next_log_index(&table, room_id)?;
-
-            if log_index != expected_index {
-                return Err(StorageError::Conflict { expected: expected_index, got: log_index });
-            }
-
-            let mut frame_bytes = Vec::with_capacity(128 + frame.payload.len());
-            frame
-                .encode(&mut frame_bytes)
-                .map_err(|e| StorageError::Serialization(e.to_string()))?;
-
-            let key = encode_frame_key(room_id, log_index);
-            table
-                .insert(key.as_slice(), frame_bytes.as_slice())
-                .map_err(|e| StorageError::Io(e.to_string()))?;
-        }
-
-        txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
-
-        Ok(())
+        self.batch(|batch| batch.store_frame(room_id, log_index, frame))
     }
 
     fn latest_log_index(&self, room_id: u128) -> Result<Option<u64>, StorageError> {
@@ -176,20 +216,123 @@ impl Storage for RedbStorage {
         Ok(frames)
     }
 
-    fn store_mls_state(&self, room_id: u128, state: &MlsGroupState) -> Result<(), StorageError> {
+    fn index_at_or_after(
+        &self,
+        room_id: u128,
+        hlc_timestamp: u64,
+    ) -> Result<Option<u64>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let table = txn.open_table(FRAMES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let start_key = encode_frame_key(room_id, 0);
+        let end_key = encode_frame_key(room_id, u64::MAX);
+        let range = table
+            .range(start_key.as_slice()..=end_key.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        for result in range {
+            let (key, value) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+            let frame = Frame::decode(value.value())
+                .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+            if frame.header.hlc_timestamp() >= hlc_timestamp {
+                let (_, log_index) = decode_frame_key(key.value());
+                return Ok(Some(log_index));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn last_index_stored_before(
+        &self,
+        room_id: u128,
+        wall_clock: u64,
+    ) -> Result<Option<u64>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let table = txn.open_table(FRAME_TIMES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let start_key = encode_frame_key(room_id, 0);
+        let end_key = encode_frame_key(room_id, u64::MAX);
+        let range = table
+            .range(start_key.as_slice()..=end_key.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        for result in range.rev() {
+            let (key, value) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+            let stored_at =
+                u64::from_be_bytes(value.value().try_into().map_err(|_| {
+                    StorageError::Serialization("frame time too short".to_string())
+                })?);
+            if stored_at < wall_clock {
+                let (_, log_index) = decode_frame_key(key.value());
+                return Ok(Some(log_index));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn prune_frame(&self, room_id: u128, log_index: u64) -> Result<(), StorageError> {
         let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
 
         {
-            let mut table =
-                txn.open_table(MLS_STATE).map_err(|e| StorageError::Io(e.to_string()))?;
+            let mut table = txn.open_table(FRAMES).map_err(|e| StorageError::Io(e.to_string()))?;
 
-            let mut bytes = Vec::new();
-            ciborium::into_writer(state, &mut bytes)
+            let key = encode_frame_key(room_id, log_index);
+            let header = match table.get(key.as_slice()) {
+                Ok(Some(value)) => {
+                    Frame::decode(value.value())
+                        .map_err(|e| StorageError::Serialization(e.to_string()))?
+                        .header
+                },
+                Ok(None) => return Err(StorageError::NotFound { room_id, log_index }),
+                Err(e) => return Err(StorageError::Io(e.to_string())),
+            };
+            let frame = Frame::new(header, Bytes::new());
+
+            let mut frame_bytes = Vec::new();
+            frame
+                .encode(&mut frame_bytes)
                 .map_err(|e| StorageError::Serialization(e.to_string()))?;
+            table
+                .insert(key.as_slice(), frame_bytes.as_slice())
+                .map_err(|e| StorageError::Io(e.to_string()))?;
+        }
 
-            let key = encode_room_key(room_id);
+        txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn prune_frames(&self, room_id: u128, snapshot: &RoomSnapshot) -> Result<(), StorageError> {
+        let mut snapshot_bytes = Vec::new();
+        ciborium::into_writer(snapshot, &mut snapshot_bytes)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        {
+            let mut table = txn.open_table(FRAMES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+            let start_key = encode_frame_key(room_id, 0);
+            let end_key = encode_frame_key(room_id, snapshot.first_log_index);
             table
-                .insert(key.as_slice(), bytes.as_slice())
+                .retain_in(start_key.as_slice()..end_key.as_slice(), |_, _| false)
+                .map_err(|e| StorageError::Io(e.to_string()))?;
+
+            let mut times =
+                txn.open_table(FRAME_TIMES).map_err(|e| StorageError::Io(e.to_string()))?;
+            times
+                .retain_in(start_key.as_slice()..end_key.as_slice(), |_, _| false)
+                .map_err(|e| StorageError::Io(e.to_string()))?;
+
+            let mut snapshots =
+                txn.open_table(SNAPSHOTS).map_err(|e| StorageError::Io(e.to_string()))?;
+            snapshots
+                .insert(encode_room_key(room_id).as_slice(), snapshot_bytes.as_slice())
                 .map_err(|e| StorageError::Io(e.to_string()))?;
         }
 
@@ -198,6 +341,27 @@ impl Storage for RedbStorage {
         Ok(())
     }
 
+    fn load_snapshot(&self, room_id: u128) -> Result<Option<RoomSnapshot>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+        let table = txn.open_table(SNAPSHOTS).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        match table
+            .get(encode_room_key(room_id).as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?
+        {
+            Some(value) => {
+                let snapshot: RoomSnapshot = ciborium::from_reader(value.value())
+                    .map_err(|e| StorageError::Serialization(e.to_string()))?;
+                Ok(Some(snapshot))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn store_mls_state(&self, room_id: u128, state: &MlsGroupState) -> Result<(), StorageError> {
+        self.batch(|batch| batch.store_mls_state(room_id, state))
+    }
+
     fn load_mls_state(&self, room_id: u128) -> Result<Option<MlsGroupState>, StorageError> {
         let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
 
@@ -221,26 +385,7 @@ impl Storage for RedbStorage {
         epoch: u64,
         group_info: &[u8],
     ) -> Result<(), StorageError> {
-        let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
-
-        {
-            let mut table =
-                txn.open_table(GROUP_INFO).map_err(|e| StorageError::Io(e.to_string()))?;
-
-            // Format: [epoch: 8 bytes BE][group_info bytes]
-            let mut value = Vec::with_capacity(8 + group_info.len());
-            value.extend_from_slice(&epoch.to_be_bytes());
-            value.extend_from_slice(group_info);
-
-            let key = encode_room_key(room_id);
-            table
-                .insert(key.as_slice(), value.as_slice())
-                .map_err(|e| StorageError::Io(e.to_string()))?;
-        }
-
-        txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
-
-        Ok(())
+        self.batch(|batch| batch.store_group_info(room_id, epoch, group_info))
     }
 
     fn load_group_info(&self, room_id: u128) -> Result<Option<(u64, Vec<u8>)>, StorageError> {
@@ -290,6 +435,14 @@ impl Storage for RedbStorage {
         &self,
         room_id: u128,
         metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError> {
+        self.batch(|batch| batch.create_room(room_id, metadata))
+    }
+
+    fn update_room_metadata(
+        &self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
     ) -> Result<(), StorageError> {
         let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
 
@@ -298,8 +451,8 @@ impl Storage for RedbStorage {
 
             let key = encode_room_key(room_id);
 
-            if table.get(key.as_slice()).map_err(|e| StorageError::Io(e.to_string()))?.is_some() {
-                return Ok(()); // Already exists, don't overwrite
+            if table.get(key.as_slice()).map_err(|e| StorageError::Io(e.to_string()))?.is_none() {
+                return Ok(());
             }
 
             let mut bytes = Vec::new();
@@ -335,6 +488,418 @@ impl Storage for RedbStorage {
             None => Ok(None),
         }
     }
+
+    fn store_room_policy(&self, room_id: u128, policy: &RoomPolicy) -> Result<(), StorageError> {
+        self.batch(|batch| batch.store_room_policy(room_id, policy))
+    }
+
+    fn load_room_policy(&self, room_id: u128) -> Result<Option<RoomPolicy>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+        let table = txn.open_table(ROOM_POLICIES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        match table
+            .get(encode_room_key(room_id).as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?
+        {
+            Some(value) => {
+                let policy: RoomPolicy = ciborium::from_reader(value.value())
+                    .map_err(|e| StorageError::Serialization(e.to_string()))?;
+                Ok(Some(policy))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn add_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.batch(|batch| batch.add_member(room_id, user_id))
+    }
+
+    fn remove_member(&self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        self.batch(|batch| batch.remove_member(room_id, user_id))
+    }
+
+    fn members(&self, room_id: u128) -> Result<Vec<u64>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let table = txn.open_table(MEMBERS).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let start_key = encode_member_key(room_id, 0);
+        let end_key = encode_member_key(room_id, u64::MAX);
+
+        let mut members = Vec::new();
+        for result in table
+            .range(start_key.as_slice()..=end_key.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?
+        {
+            let (key, _) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+            let (_, user_id) = decode_frame_key(key.value());
+            members.push(user_id);
+        }
+
+        Ok(members)
+    }
+
+    fn member_rooms(&self, user_id: u64) -> Result<Vec<u128>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let table = txn.open_table(MEMBER_ROOMS).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let start_key = encode_member_room_key(user_id, 0);
+        let end_key = encode_member_room_key(user_id, u128::MAX);
+
+        let mut rooms = Vec::new();
+        for result in table
+            .range(start_key.as_slice()..=end_key.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?
+        {
+            let (key, _) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+            rooms.push(decode_member_room_key(key.value()));
+        }
+
+        Ok(rooms)
+    }
+
+    fn append_audit(&self, entries: &[AuditEntry]) -> Result<(), StorageError> {
+        let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        {
+            let mut table = txn.open_table(AUDIT).map_err(|e| StorageError::Io(e.to_string()))?;
+
+            let first = match table.last().map_err(|e| StorageError::Io(e.to_string()))? {
+                Some((key, _)) => decode_audit_key(key.value()) + 1,
+                None => 0,
+            };
+
+            for (seq, entry) in (first..).zip(entries) {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(entry, &mut bytes)
+                    .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+                table
+                    .insert(seq.to_be_bytes().as_slice(), bytes.as_slice())
+                    .map_err(|e| StorageError::Io(e.to_string()))?;
+            }
+        }
+
+        txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn load_audit(&self, from: u64, limit: usize) -> Result<Vec<AuditEntry>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let table = txn.open_table(AUDIT).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let start_key = from.to_be_bytes();
+        let mut entries = Vec::new();
+        for result in table
+            .range(start_key.as_slice()..)
+            .map_err(|e| StorageError::Io(e.to_string()))?
+            .take(limit)
+        {
+            let (_, value) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+            let entry: AuditEntry = ciborium::from_reader(value.value())
+                .map_err(|e| StorageError::Serialization(e.to_string()))?;
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    fn queue_delivery(
+        &self,
+        user_id: u64,
+        frame: &Frame,
+        limit: usize,
+    ) -> Result<bool, StorageError> {
+        let mut frame_bytes = Vec::with_capacity(128 + frame.payload.len());
+        frame.encode(&mut frame_bytes).map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        {
+            let mut table =
+                txn.open_table(DELIVERIES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+            let start_key = encode_delivery_key(user_id, 0);
+            let end_key = encode_delivery_key(user_id, u64::MAX);
+            let mut count = 0;
+            let mut next = 0;
+            for result in table
+                .range(start_key.as_slice()..=end_key.as_slice())
+                .map_err(|e| StorageError::Io(e.to_string()))?
+            {
+                let (key, _) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+                next = decode_delivery_key(key.value()).1 + 1;
+                count += 1;
+            }
+            if count >= limit {
+                return Ok(false);
+            }
+
+            table
+                .insert(encode_delivery_key(user_id, next).as_slice(), frame_bytes.as_slice())
+                .map_err(|e| StorageError::Io(e.to_string()))?;
+        }
+
+        txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    fn take_deliveries(&self, user_id: u64) -> Result<Vec<Frame>, StorageError> {
+        let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let mut frames = Vec::new();
+        {
+            let mut table =
+                txn.open_table(DELIVERIES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+            let start_key = encode_delivery_key(user_id, 0);
+            let end_key = encode_delivery_key(user_id, u64::MAX);
+            for result in table
+                .extract_from_if(start_key.as_slice()..=end_key.as_slice(), |_, _| true)
+                .map_err(|e| StorageError::Io(e.to_string()))?
+            {
+                let (_, value) = result.map_err(|e| StorageError::Io(e.to_string()))?;
+                let frame = Frame::decode(value.value())
+                    .map_err(|e| StorageError::Serialization(e.to_string()))?;
+                frames.push(frame);
+            }
+        }
+
+        txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(frames)
+    }
+
+    fn load_room_checkpoint(&self, room_id: u128) -> Result<Option<RoomCheckpoint>, StorageError> {
+        let txn = self.db.begin_read().map_err(|e| StorageError::Io(e.to_string()))?;
+        let table = txn.open_table(SEQUENCER).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let key = encode_room_key(room_id);
+        table
+            .get(key.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?
+            .map(|value| decode_checkpoint(value.value()))
+            .transpose()
+    }
+
+    /// Stages every write in one Redb write transaction, which is committed
+    /// only if `f` succeeds and aborted otherwise.
+    fn batch<F>(&self, f: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut dyn StorageBatch) -> Result<(), StorageError>,
+    {
+        let txn = self.db.begin_write().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        if let Err(e) = f(&mut RedbBatch { storage: self, txn: &txn }) {
+            txn.abort().map_err(|e| StorageError::Io(e.to_string()))?;
+            return Err(e);
+        }
+
+        txn.commit().map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Writes staged in an open Redb write transaction.
+struct RedbBatch<'a> {
+    storage: &'a RedbStorage,
+    txn: &'a WriteTransaction,
+}
+
+impl StorageBatch for RedbBatch<'_> {
+    fn store_frame(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        frame: &Frame,
+    ) -> Result<(), StorageError> {
+        let mut table = self.txn.open_table(FRAMES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let expected_index = self.storage.compute_next_log_index(&table, room_id)?;
+
+        if log_index != expected_index {
+            return Err(StorageError::Conflict { expected: expected_index, got: log_index });
+        }
+
+        let mut frame_bytes = Vec::with_capacity(128 + frame.payload.len());
+        frame.encode(&mut frame_bytes).map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        let key = encode_frame_key(room_id, log_index);
+        table
+            .insert(key.as_slice(), frame_bytes.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn store_mls_state(
+        &mut self,
+        room_id: u128,
+        state: &MlsGroupState,
+    ) -> Result<(), StorageError> {
+        let mut table =
+            self.txn.open_table(MLS_STATE).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let mut bytes = Vec::new();
+        ciborium::into_writer(state, &mut bytes)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        let key = encode_room_key(room_id);
+        table
+            .insert(key.as_slice(), bytes.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn store_group_info(
+        &mut self,
+        room_id: u128,
+        epoch: u64,
+        group_info: &[u8],
+    ) -> Result<(), StorageError> {
+        let mut table =
+            self.txn.open_table(GROUP_INFO).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        // Format: [epoch: 8 bytes BE][group_info bytes]
+        let mut value = Vec::with_capacity(8 + group_info.len());
+        value.extend_from_slice(&epoch.to_be_bytes());
+        value.extend_from_slice(group_info);
+
+        let key = encode_room_key(room_id);
+        table
+            .insert(key.as_slice(), value.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn create_room(
+        &mut self,
+        room_id: u128,
+        metadata: &StoredRoomMetadata,
+    ) -> Result<(), StorageError> {
+        let mut table = self.txn.open_table(ROOMS).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let key = encode_room_key(room_id);
+
+        if table.get(key.as_slice()).map_err(|e| StorageError::Io(e.to_string()))?.is_some() {
+            return Ok(()); // Already exists, don't overwrite
+        }
+
+        let mut bytes = Vec::new();
+        ciborium::into_writer(metadata, &mut bytes)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        table
+            .insert(key.as_slice(), bytes.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn store_room_policy(
+        &mut self,
+        room_id: u128,
+        policy: &RoomPolicy,
+    ) -> Result<(), StorageError> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(policy, &mut bytes)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        let mut table =
+            self.txn.open_table(ROOM_POLICIES).map_err(|e| StorageError::Io(e.to_string()))?;
+        table
+            .insert(encode_room_key(room_id).as_slice(), bytes.as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn add_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        let mut table =
+            self.txn.open_table(MEMBERS).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let key = encode_member_key(room_id, user_id);
+        table.insert(key.as_slice(), [].as_slice()).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let mut index =
+            self.txn.open_table(MEMBER_ROOMS).map_err(|e| StorageError::Io(e.to_string()))?;
+        let key = encode_member_room_key(user_id, room_id);
+        index.insert(key.as_slice(), [].as_slice()).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn remove_member(&mut self, room_id: u128, user_id: u64) -> Result<(), StorageError> {
+        let mut table =
+            self.txn.open_table(MEMBERS).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let key = encode_member_key(room_id, user_id);
+        table.remove(key.as_slice()).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let mut index =
+            self.txn.open_table(MEMBER_ROOMS).map_err(|e| StorageError::Io(e.to_string()))?;
+        let key = encode_member_room_key(user_id, room_id);
+        index.remove(key.as_slice()).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn store_room_checkpoint(
+        &mut self,
+        room_id: u128,
+        checkpoint: RoomCheckpoint,
+    ) -> Result<(), StorageError> {
+        let mut table =
+            self.txn.open_table(SEQUENCER).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        insert_checkpoint(&mut table, room_id, checkpoint)
+    }
+
+    fn store_frame_time(
+        &mut self,
+        room_id: u128,
+        log_index: u64,
+        stored_at: u64,
+    ) -> Result<(), StorageError> {
+        let mut table =
+            self.txn.open_table(FRAME_TIMES).map_err(|e| StorageError::Io(e.to_string()))?;
+
+        let key = encode_frame_key(room_id, log_index);
+        table
+            .insert(key.as_slice(), stored_at.to_be_bytes().as_slice())
+            .map_err(|e| StorageError::Io(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Write `checkpoint` as `room_id`'s entry in the SEQUENCER table.
+fn insert_checkpoint(
+    table: &mut redb::Table<'_, &'static [u8], &'static [u8]>,
+    room_id: u128,
+    checkpoint: RoomCheckpoint,
+) -> Result<(), StorageError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(&checkpoint, &mut bytes)
+        .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+    table
+        .insert(encode_room_key(room_id).as_slice(), bytes.as_slice())
+        .map_err(|e| StorageError::Io(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Decode a SEQUENCER table value.
+fn decode_checkpoint(bytes: &[u8]) -> Result<RoomCheckpoint, StorageError> {
+    ciborium::from_reader(bytes).map_err(|e| StorageError::Serialization(e.to_string()))
 }
 
 /// Encode (`room_id`, `log_index`) as 24-byte big-endian key.
@@ -359,11 +924,58 @@ fn decode_frame_key(key: &[u8]) -> (u128, u64) {
     (room_id, log_index)
 }
 
+/// Encode (`room_id`, `user_id`) as 24-byte big-endian key.
+///
+/// Same layout as frame keys, so a room's members form a contiguous range.
+fn encode_member_key(room_id: u128, user_id: u64) -> [u8; 24] {
+    encode_frame_key(room_id, user_id)
+}
+
+/// Encode (`user_id`, `room_id`) as 24-byte big-endian key.
+///
+/// Layout: [`user_id`: 8 bytes BE][`room_id`: 16 bytes BE]
+fn encode_member_room_key(user_id: u64, room_id: u128) -> [u8; 24] {
+    let mut key = [0u8; 24];
+    key[..8].copy_from_slice(&user_id.to_be_bytes());
+    key[8..].copy_from_slice(&room_id.to_be_bytes());
+    key
+}
+
+/// Decode a member room key back to its `room_id`.
+#[allow(clippy::expect_used)]
+fn decode_member_room_key(key: &[u8]) -> u128 {
+    debug_assert_eq!(key.len(), 24);
+    u128::from_be_bytes(key[8..].try_into().expect("bounds checked by assert above"))
+}
+
 /// Encode `room_id` as 16-byte big-endian key.
 fn encode_room_key(room_id: u128) -> [u8; 16] {
     room_id.to_be_bytes()
 }
 
+/// Encode (`user_id`, sequence) as 16-byte big-endian key.
+fn encode_delivery_key(user_id: u64, sequence: u64) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    key[..8].copy_from_slice(&user_id.to_be_bytes());
+    key[8..].copy_from_slice(&sequence.to_be_bytes());
+    key
+}
+
+/// Decode delivery key back to (`user_id`, sequence).
+#[allow(clippy::expect_used)]
+fn decode_delivery_key(key: &[u8]) -> (u64, u64) {
+    debug_assert_eq!(key.len(), 16);
+    let user_id = u64::from_be_bytes(key[..8].try_into().expect("bounds checked by assert above"));
+    let sequence = u64::from_be_bytes(key[8..].try_into().expect("bounds checked by assert above"));
+    (user_id, sequence)
+}
+
+/// Decode an audit key back to its sequence number.
+#[allow(clippy::expect_used)]
+fn decode_audit_key(key: &[u8]) -> u64 {
+    u64::from_be_bytes(key.try_into().expect("audit keys are 8 bytes"))
+}
+
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
@@ -597,7 +1209,11 @@ mod tests {
         assert_eq!(storage.list_rooms().unwrap(), vec![]);
 
         for room_id in [100u128, 200, 300] {
-            let metadata = StoredRoomMetadata { creator: room_id as u64, created_at_secs: 0 };
+            let metadata = StoredRoomMetadata {
+                creator: room_id as u64,
+                created_at_secs: 0,
+                ..Default::default()
+            };
             storage.create_room(room_id, &metadata).unwrap();
         }
 
@@ -612,7 +1228,11 @@ mod tests {
         let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
 
         let room_id = 100u128;
-        let metadata = StoredRoomMetadata { creator: 42, created_at_secs: 1_234_567_890 };
+        let metadata = StoredRoomMetadata {
+            creator: 42,
+            created_at_secs: 1_234_567_890,
+            ..Default::default()
+        };
 
         storage.create_room(room_id, &metadata).unwrap();
 
@@ -627,8 +1247,10 @@ mod tests {
         let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
 
         let room_id = 100u128;
-        let metadata1 = StoredRoomMetadata { creator: 42, created_at_secs: 100 };
-        let metadata2 = StoredRoomMetadata { creator: 99, created_at_secs: 200 };
+        let metadata1 =
+            StoredRoomMetadata { creator: 42, created_at_secs: 100, ..Default::default() };
+        let metadata2 =
+            StoredRoomMetadata { creator: 99, created_at_secs: 200, ..Default::default() };
 
         storage.create_room(room_id, &metadata1).unwrap();
         storage.create_room(room_id, &metadata2).unwrap(); // Should not overwrite
@@ -637,6 +1259,21 @@ mod tests {
         assert_eq!(loaded.creator, 42); // Original creator preserved
     }
 
+    #[test]
+    fn test_update_room_metadata() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
+        let metadata = StoredRoomMetadata { creator: 42, ..Default::default() };
+        let renamed = StoredRoomMetadata { name: "lobby".into(), ..metadata.clone() };
+
+        storage.update_room_metadata(100, &renamed).unwrap();
+        assert!(storage.load_room_metadata(100).unwrap().is_none());
+
+        storage.create_room(100, &metadata).unwrap();
+        storage.update_room_metadata(100, &renamed).unwrap();
+        assert_eq!(storage.load_room_metadata(100).unwrap(), Some(renamed));
+    }
+
     #[test]
     fn test_load_room_metadata_not_found() {
         let dir = tempdir().unwrap();
@@ -644,4 +1281,214 @@ mod tests {
 
         assert!(storage.load_room_metadata(999).unwrap().is_none());
     }
+
+    #[test]
+    fn test_prune_frame_keeps_header() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
+        let room_id = 100;
+        let frame = create_test_frame(room_id, 0, b"secret");
+        storage.store_frame(room_id, 0, &frame).unwrap();
+
+        storage.prune_frame(room_id, 0).unwrap();
+
+        let pruned = &storage.load_frames(room_id, 0, 1).unwrap()[0];
+        assert!(pruned.payload.is_empty());
+        assert_eq!(pruned.header.log_index(), 0);
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(0));
+        assert!(matches!(storage.prune_frame(room_id, 1), Err(StorageError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_prune_frames_keeps_latest_index() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
+        let room_id = 100;
+        for i in 0..5u64 {
+            let mut frame = create_test_frame(room_id, i, b"msg");
+            frame.header.set_hlc_timestamp(i * 10);
+            storage.store_frame(room_id, i, &frame).unwrap();
+        }
+        assert_eq!(storage.load_snapshot(room_id).unwrap(), None);
+
+        let snapshot = RoomSnapshot { first_log_index: 3, epoch: 2, group_info: b"info".to_vec() };
+        storage.prune_frames(room_id, &snapshot).unwrap();
+
+        let frames = storage.load_frames(room_id, 0, 10).unwrap();
+        let indices: Vec<u64> = frames.iter().map(|f| f.header.log_index()).collect();
+        assert_eq!(indices, vec![3, 4]);
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(4));
+        assert_eq!(storage.index_at_or_after(room_id, 0).unwrap(), Some(3));
+        assert_eq!(storage.load_snapshot(room_id).unwrap(), Some(snapshot));
+
+        storage.store_frame(room_id, 5, &create_test_frame(room_id, 5, b"msg")).unwrap();
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(5));
+    }
+
+    #[test]
+    fn test_delivery_queue_is_bounded_and_drained() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
+        for i in 0..3u64 {
+            let queued =
+                storage.queue_delivery(42, &create_test_frame(7, i, b"welcome"), 2).unwrap();
+            assert_eq!(queued, i < 2);
+        }
+        assert!(storage.queue_delivery(43, &create_test_frame(7, 9, b"welcome"), 2).unwrap());
+
+        let frames = storage.take_deliveries(42).unwrap();
+        let indices: Vec<u64> = frames.iter().map(|f| f.header.log_index()).collect();
+        assert_eq!(indices, vec![0, 1]);
+        assert!(storage.take_deliveries(42).unwrap().is_empty());
+        assert_eq!(storage.take_deliveries(43).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_room_checkpoints_survive_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.redb");
+        {
+            let storage = RedbStorage::open(&path).unwrap();
+            for (room_id, checkpoint) in [
+                (100, RoomCheckpoint { next_log_index: 3, epoch: None }),
+                (200, RoomCheckpoint { next_log_index: 7, epoch: Some(1) }),
+                (200, RoomCheckpoint { next_log_index: 9, epoch: Some(2) }),
+            ] {
+                storage.batch(|batch| batch.store_room_checkpoint(room_id, checkpoint)).unwrap();
+            }
+        }
+
+        let storage = RedbStorage::open(&path).unwrap();
+        assert_eq!(
+            storage.load_room_checkpoint(100).unwrap(),
+            Some(RoomCheckpoint { next_log_index: 3, epoch: None })
+        );
+        assert_eq!(
+            storage.load_room_checkpoint(200).unwrap(),
+            Some(RoomCheckpoint { next_log_index: 9, epoch: Some(2) })
+        );
+        assert_eq!(storage.load_room_checkpoint(300).unwrap(), None);
+    }
+
+    #[test]
+    fn test_index_at_or_after() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
+        let room_id = 100;
+
+        // Non-application frames carry HLC 0, so the log isn't HLC-ordered
+        for (i, hlc) in [10u64, 20, 0, 0, 20, 0, 30].into_iter().enumerate() {
+            let mut frame = create_test_frame(room_id, i as u64, b"msg");
+            frame.header.set_hlc_timestamp(hlc);
+            storage.store_frame(room_id, i as u64, &frame).unwrap();
+        }
+
+        assert_eq!(storage.index_at_or_after(room_id, 0).unwrap(), Some(0));
+        assert_eq!(storage.index_at_or_after(room_id, 20).unwrap(), Some(1));
+        assert_eq!(storage.index_at_or_after(room_id, 21).unwrap(), Some(6));
+        assert_eq!(storage.index_at_or_after(room_id, 31).unwrap(), None);
+        assert_eq!(storage.index_at_or_after(999, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_last_index_stored_before() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
+        let room_id = 100;
+
+        storage
+            .batch(|batch| {
+                for (i, stored_at) in [10u64, 20, 20, 30].into_iter().enumerate() {
+                    let frame = create_test_frame(room_id, i as u64, b"msg");
+                    batch.store_frame(room_id, i as u64, &frame)?;
+                    batch.store_frame_time(room_id, i as u64, stored_at)?;
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(storage.last_index_stored_before(room_id, 10).unwrap(), None);
+        assert_eq!(storage.last_index_stored_before(room_id, 21).unwrap(), Some(2));
+        assert_eq!(storage.last_index_stored_before(room_id, 31).unwrap(), Some(3));
+        assert_eq!(storage.last_index_stored_before(999, 31).unwrap(), None);
+
+        let snapshot = RoomSnapshot { first_log_index: 2, epoch: 1, group_info: vec![] };
+        storage.prune_frames(room_id, &snapshot).unwrap();
+        assert_eq!(storage.last_index_stored_before(room_id, 20).unwrap(), None);
+        assert_eq!(storage.last_index_stored_before(room_id, 21).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_members_survive_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.redb");
+
+        {
+            let storage = RedbStorage::open(&path).unwrap();
+            storage.add_member(100, 7).unwrap();
+            storage.add_member(100, 3).unwrap();
+            storage.add_member(101, 9).unwrap();
+            storage.remove_member(100, 7).unwrap();
+        }
+
+        let storage = RedbStorage::open(&path).unwrap();
+        assert_eq!(storage.members(100).unwrap(), vec![3]);
+        assert_eq!(storage.members(101).unwrap(), vec![9]);
+        assert!(storage.members(102).unwrap().is_empty());
+        assert_eq!(storage.member_rooms(3).unwrap(), vec![100]);
+        assert_eq!(storage.member_rooms(9).unwrap(), vec![101]);
+        assert!(storage.member_rooms(7).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_batch_commits_atomically() {
+        let dir = tempdir().unwrap();
+        let storage = RedbStorage::open(dir.path().join("test.redb")).unwrap();
+        let room_id = 100u128;
+
+        storage
+            .batch(|batch| {
+                batch.store_frame(room_id, 0, &create_test_frame(room_id, 0, b"a"))?;
+                batch.store_frame(room_id, 1, &create_test_frame(room_id, 1, b"b"))?;
+                batch.add_member(room_id, 7)
+            })
+            .unwrap();
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(1));
+        assert_eq!(storage.members(room_id).unwrap(), vec![7]);
+
+        // A failing batch rolls back the writes staged before the failure
+        let result = storage.batch(|batch| {
+            batch.store_frame(room_id, 2, &create_test_frame(room_id, 2, b"c"))?;
+            batch.add_member(room_id, 8)?;
+            batch.store_frame(room_id, 2, &create_test_frame(room_id, 2, b"d"))
+        });
+        assert!(matches!(result, Err(StorageError::Conflict { expected: 3, got: 2 })));
+        assert_eq!(storage.latest_log_index(room_id).unwrap(), Some(1));
+        assert_eq!(storage.members(room_id).unwrap(), vec![7]);
+    }
+
+    #[test]
+    fn test_audit_appends_in_order_and_survives_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.redb");
+        let entry = |code| AuditEntry {
+            code,
+            session_id: 1,
+            room_id: 100,
+            reason: format!("error {code}"),
+            at_millis: 1_000,
+        };
+
+        {
+            let storage = RedbStorage::open(&path).unwrap();
+            storage.append_audit(&[entry(1)]).unwrap();
+            storage.append_audit(&[entry(2), entry(3)]).unwrap();
+        }
+
+        let storage = RedbStorage::open(&path).unwrap();
+        storage.append_audit(&[entry(4)]).unwrap();
+        assert_eq!(storage.load_audit(0, 10).unwrap(), (1..=4).map(entry).collect::<Vec<_>>());
+        assert_eq!(storage.load_audit(2, 1).unwrap(), vec![entry(3)]);
+        assert!(storage.load_audit(4, 10).unwrap().is_empty());
+    }
 }
@@ -11,25 +11,56 @@
 //! - Stream multiplexing (multiple logical streams over one connection)
 //! - Connection migration support (IP address changes)
 //!
+//! # Connection Migration
+//!
+//! A client that changes networks keeps its QUIC connection: Quinn validates
+//! the new path and moves the connection over. The server tracks sessions by
+//! [`QuinnConnection`] and its streams, never by remote address, so a migrated
+//! session keeps its outbound stream and room subscriptions.
+//! [`QuinnConnection::remote_addr`] reports the current path.
+//!
 //! # Security
 //!
 //! The transport enforces TLS 1.3 via the `rustls` crate. ALPN
-//! (Application-Layer Protocol Negotiation) is set to "lockframe" to ensure
+//! (Application-Layer Protocol Negotiation) defaults to "lockframe" to ensure
 //! protocol compatibility. Self-signed certificates are only suitable for local
-//! testing - production deployments MUST use proper TLS certificates from a
-//! trusted CA.
+//! testing or for clients that pin [`QuinnTransport::cert_fingerprint`] -
+//! production deployments MUST use proper TLS certificates from a trusted CA.
 
 use std::{net::SocketAddr, sync::Arc};
 
 use lockframe_proto::ALPN_PROTOCOL;
 use quinn::{Endpoint, RecvStream, SendStream, ServerConfig};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use sha2::{Digest, Sha256};
 
 use crate::error::ServerError;
 
+/// TLS options for [`QuinnTransport::bind_with_options`].
+#[derive(Debug, Clone)]
+pub struct TransportOptions {
+    /// ALPN protocol IDs the server accepts.
+    pub alpn_protocols: Vec<Vec<u8>>,
+    /// Accept 0-RTT early data from resuming clients.
+    ///
+    /// Early data can be replayed by a network attacker. Resumption tickets
+    /// are single-use within this process, which stops replays against it,
+    /// but nothing stops a replay after a restart. Clients only send
+    /// idempotent reads as early data; the server does not re-check.
+    pub enable_0rtt: bool,
+}
+
+impl Default for TransportOptions {
+    fn default() -> Self {
+        Self { alpn_protocols: vec![ALPN_PROTOCOL.to_vec()], enable_0rtt: false }
+    }
+}
+
 /// QUIC transport using Quinn.
 ///
 /// Provides a QUIC endpoint that can accept incoming connections. The endpoint
-/// is configured with TLS 1.3 and ALPN protocol "lockframe".
+/// is configured with TLS 1.3 and ALPN protocol "lockframe" unless
+/// [`QuinnTransport::bind_with_alpn`] overrides it.
 ///
 /// # Security
 ///
@@ -40,6 +71,8 @@ use crate::error::ServerError;
 pub struct QuinnTransport {
     /// Quinn endpoint
     endpoint: Endpoint,
+    /// SHA-256 of the leaf certificate (DER)
+    cert_fingerprint: [u8; 32],
 }
 
 impl QuinnTransport {
@@ -51,22 +84,61 @@ impl QuinnTransport {
         address: &str,
         cert_path: Option<String>,
         key_path: Option<String>,
+    ) -> Result<Self, ServerError> {
+        Self::bind_with_alpn(address, cert_path, key_path, vec![ALPN_PROTOCOL.to_vec()])
+    }
+
+    /// Create and bind a new QUIC transport that negotiates `alpn_protocols`.
+    ///
+    /// Clients must offer one of these protocol IDs or the handshake fails.
+    /// Certificates are chosen as in [`Self::bind`].
+    pub fn bind_with_alpn(
+        address: &str,
+        cert_path: Option<String>,
+        key_path: Option<String>,
+        alpn_protocols: Vec<Vec<u8>>,
+    ) -> Result<Self, ServerError> {
+        let options = TransportOptions { alpn_protocols, ..TransportOptions::default() };
+        Self::bind_with_options(address, cert_path, key_path, options)
+    }
+
+    /// Create and bind a new QUIC transport with explicit TLS options.
+    ///
+    /// Certificates are chosen as in [`Self::bind`].
+    pub fn bind_with_options(
+        address: &str,
+        cert_path: Option<String>,
+        key_path: Option<String>,
+        options: TransportOptions,
     ) -> Result<Self, ServerError> {
         let addr: SocketAddr = address
             .parse()
             .map_err(|e| ServerError::Config(format!("invalid bind address '{address}': {e}")))?;
 
-        let server_config = match (cert_path, key_path) {
-            (Some(cert), Some(key)) => load_tls_config(&cert, &key)?,
-            _ => generate_self_signed_config()?,
+        let (cert_chain, key) = match (cert_path, key_path) {
+            (Some(cert), Some(key)) => load_cert_and_key(&cert, &key)?,
+            _ => generate_self_signed_cert()?,
         };
+        let cert_fingerprint: [u8; 32] = cert_chain
+            .first()
+            .map(|leaf| Sha256::digest(leaf).into())
+            .ok_or_else(|| ServerError::Config("certificate chain is empty".to_string()))?;
+        let server_config = tls_config(cert_chain, key, options)?;
 
         let endpoint = Endpoint::server(server_config, addr)
             .map_err(|e| ServerError::Transport(format!("failed to create endpoint: {e}")))?;
 
         tracing::info!("QUIC transport bound to {}", addr);
+        tracing::info!("TLS certificate SHA-256 fingerprint: {}", hex(&cert_fingerprint));
+
+        Ok(Self { endpoint, cert_fingerprint })
+    }
 
-        Ok(Self { endpoint })
+    /// SHA-256 fingerprint of the certificate this transport presents.
+    ///
+    /// Clients can pin it to trust a self-signed certificate.
+    pub fn cert_fingerprint(&self) -> [u8; 32] {
+        self.cert_fingerprint
     }
 
     /// Accept a new QUIC connection.
@@ -86,6 +158,12 @@ impl QuinnTransport {
         Ok(QuinnConnection { connection: conn })
     }
 
+    /// Wait until every connection has closed and finished its close
+    /// handshake.
+    pub async fn wait_idle(&self) {
+        self.endpoint.wait_idle().await;
+    }
+
     /// Local address the transport is bound to.
     pub fn local_addr(&self) -> Result<SocketAddr, ServerError> {
         self.endpoint
@@ -144,8 +222,11 @@ impl QuinnConnection {
     }
 }
 
-/// Load TLS configuration from certificate and key files.
-fn load_tls_config(cert_path: &str, key_path: &str) -> Result<ServerConfig, ServerError> {
+/// Load a certificate chain and private key from PEM files.
+fn load_cert_and_key(
+    cert_path: &str,
+    key_path: &str,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ServerError> {
     use std::fs;
 
     let cert_pem = fs::read(cert_path)
@@ -162,49 +243,61 @@ fn load_tls_config(cert_path: &str, key_path: &str) -> Result<ServerConfig, Serv
         .map_err(|e| ServerError::Config(format!("failed to parse private key: {e}")))?
         .ok_or_else(|| ServerError::Config("no private key found".to_string()))?;
 
-    let mut tls_config = rustls::ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)
-        .map_err(|e| ServerError::Config(format!("invalid TLS config: {e}")))?;
-
-    tls_config.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
-
-    let server_config = ServerConfig::with_crypto(Arc::new(
-        quinn::crypto::rustls::QuicServerConfig::try_from(tls_config)
-            .map_err(|e| ServerError::Config(format!("QUIC config error: {e}")))?,
-    ));
-
-    Ok(server_config)
+    Ok((certs, key))
 }
 
 /// Generate a self-signed certificate for testing.
-fn generate_self_signed_config() -> Result<ServerConfig, ServerError> {
+fn generate_self_signed_cert()
+-> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ServerError> {
     let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
         .map_err(|e| ServerError::Config(format!("failed to generate self-signed cert: {e}")))?;
 
     let cert_der = cert.cert.der().clone();
     let key_der = cert.key_pair.serialize_der();
-
-    let cert_chain = vec![cert_der];
     let key = rustls::pki_types::PrivatePkcs8KeyDer::from(key_der);
 
+    tracing::warn!("Using self-signed certificate - not for production use!");
+
+    Ok((vec![cert_der], key.into()))
+}
+
+/// Build the QUIC server config for a certificate and TLS options.
+fn tls_config(
+    cert_chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    options: TransportOptions,
+) -> Result<ServerConfig, ServerError> {
     let mut tls_config = rustls::ServerConfig::builder()
         .with_no_client_auth()
-        .with_single_cert(cert_chain, key.into())
+        .with_single_cert(cert_chain, key)
         .map_err(|e| ServerError::Config(format!("invalid TLS config: {e}")))?;
 
-    tls_config.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+    tls_config.alpn_protocols = options.alpn_protocols;
+    if options.enable_0rtt {
+        // QUIC requires all-or-nothing; the stream limits bound early data
+        tls_config.max_early_data_size = u32::MAX;
+    }
 
-    let server_config = ServerConfig::with_crypto(Arc::new(
+    let mut server_config = ServerConfig::with_crypto(Arc::new(
         quinn::crypto::rustls::QuicServerConfig::try_from(tls_config)
             .map_err(|e| ServerError::Config(format!("QUIC config error: {e}")))?,
     ));
-
-    tracing::warn!("Using self-signed certificate - not for production use!");
+    // Quinn's default, pinned here because sessions rely on it
+    server_config.migration(true);
 
     Ok(server_config)
 }
 
+/// Lowercase hex for logging fingerprints.
+fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,6 +312,13 @@ mod tests {
         assert_ne!(addr.port(), 0, "Should have assigned a port");
     }
 
+    #[tokio::test]
+    async fn self_signed_fingerprint_matches_fresh_certificate() {
+        let a = QuinnTransport::bind("127.0.0.1:0", None, None).unwrap();
+        let b = QuinnTransport::bind("127.0.0.1:0", None, None).unwrap();
+        assert_ne!(a.cert_fingerprint(), b.cert_fingerprint());
+    }
+
     #[tokio::test]
     async fn transport_rejects_invalid_address() {
         let result = QuinnTransport::bind("invalid:address:format", None, None);
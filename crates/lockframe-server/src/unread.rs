@@ -0,0 +1,123 @@
+//! Unread message counts for offline members.
+//!
+//! The server counts application messages sequenced in a room while a member
+//! has no session in it. The counts are handed out once, in the member's next
+//! `HelloReply`, so the client knows which rooms to sync first. They live in
+//! memory only: after a restart clients fall back to syncing every room.
+//!
+//! The tracker holds a bounded number of users. Once full, the user who has
+//! been tracked longest is forgotten to make room; like after a restart, they
+//! simply get no hint and sync every room.
+
+use std::collections::{BTreeMap, HashMap};
+
+use lockframe_proto::payloads::session::RoomUnread;
+
+/// Per-user unread counts, keyed by room.
+#[derive(Debug, Clone)]
+pub struct UnreadTracker {
+    /// `user_id` → (order tracked, `room_id` → messages missed)
+    counts: HashMap<u64, (u64, BTreeMap<u128, u64>)>,
+    /// Order tracked → `user_id`, oldest first
+    order: BTreeMap<u64, u64>,
+    /// Order given to the next user tracked
+    next_order: u64,
+    /// Most users tracked at once
+    max_users: usize,
+}
+
+impl UnreadTracker {
+    /// Create an empty tracker holding at most `max_users` users (at least
+    /// one).
+    pub fn new(max_users: usize) -> Self {
+        Self {
+            counts: HashMap::new(),
+            order: BTreeMap::new(),
+            next_order: 0,
+            max_users: max_users.max(1),
+        }
+    }
+
+    /// Count one message in `room_id` that `user_id` missed.
+    ///
+    /// Tracking a new user when the tracker is full forgets the user tracked
+    /// longest.
+    pub fn record(&mut self, user_id: u64, room_id: u128) {
+        if !self.counts.contains_key(&user_id) {
+            if self.counts.len() >= self.max_users
+                && let Some((_, oldest)) = self.order.pop_first()
+            {
+                self.counts.remove(&oldest);
+            }
+            self.order.insert(self.next_order, user_id);
+            self.counts.insert(user_id, (self.next_order, BTreeMap::new()));
+            self.next_order += 1;
+        }
+        if let Some((_, rooms)) = self.counts.get_mut(&user_id) {
+            *rooms.entry(room_id).or_default() += 1;
+        }
+    }
+
+    /// `user_id`'s counts, ordered by room ID.
+    pub fn counts(&self, user_id: u64) -> Vec<RoomUnread> {
+        self.counts
+            .get(&user_id)
+            .map(|(_, rooms)| {
+                rooms.iter().map(|(&room_id, &count)| RoomUnread { room_id, count }).collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forget `user_id`'s counts once they have been reported.
+    pub fn clear(&mut self, user_id: u64) {
+        if let Some((order, _)) = self.counts.remove(&user_id) {
+            self.order.remove(&order);
+        }
+    }
+
+    /// Number of users with unread messages.
+    pub fn user_count(&self) -> usize {
+        self.counts.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_per_room_and_clears_once_reported() {
+        let mut tracker = UnreadTracker::new(8);
+        tracker.record(1, 0x200);
+        tracker.record(1, 0x100);
+        tracker.record(1, 0x200);
+        tracker.record(2, 0x100);
+
+        assert_eq!(tracker.counts(1), vec![RoomUnread { room_id: 0x100, count: 1 }, RoomUnread {
+            room_id: 0x200,
+            count: 2
+        }]);
+        tracker.clear(1);
+        assert!(tracker.counts(1).is_empty());
+        assert_eq!(tracker.user_count(), 1);
+    }
+
+    #[test]
+    fn forgets_the_longest_tracked_user_when_full() {
+        let mut tracker = UnreadTracker::new(2);
+        tracker.record(1, 0x100);
+        tracker.record(2, 0x100);
+        tracker.record(1, 0x100);
+
+        tracker.record(3, 0x100);
+        assert_eq!(tracker.user_count(), 2);
+        assert!(tracker.counts(1).is_empty());
+        assert_eq!(tracker.counts(2), vec![RoomUnread { room_id: 0x100, count: 1 }]);
+
+        // A cleared user frees their slot without evicting anyone
+        tracker.clear(2);
+        tracker.record(4, 0x100);
+        assert_eq!(tracker.counts(3), vec![RoomUnread { room_id: 0x100, count: 1 }]);
+        assert_eq!(tracker.user_count(), 2);
+    }
+}
@@ -0,0 +1,243 @@
+//! WebSocket listener for clients that can't use QUIC.
+//!
+//! Corporate proxies and browsers often pass only HTTP, so UDP-based QUIC
+//! never connects. [`WsListener`] accepts the client's WebSocket transport
+//! instead. A WebSocket has no stream multiplexing: each [`WsConnection`]
+//! carries one stream of inbound frames, and the session's outbound frames
+//! are sent back over the same socket.
+//!
+//! # Framing
+//!
+//! Each binary WebSocket message holds one frame: a big-endian `u32` length
+//! followed by the encoded [`lockframe_proto::Frame`], as the client sends
+//! them. A message whose prefix doesn't match its length, or a text message,
+//! ends the connection.
+//!
+//! # TLS
+//!
+//! The listener speaks `ws://` only. Terminate TLS in front of it for clients
+//! connecting with `wss://`.
+
+use std::{io, net::SocketAddr, time::Duration};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::{
+    SinkExt, StreamExt,
+    stream::{SplitSink, SplitStream},
+};
+use lockframe_proto::FrameHeader;
+use tokio::{
+    io::{AsyncWriteExt, DuplexStream},
+    net::{TcpListener, TcpStream},
+    task::AbortHandle,
+};
+use tokio_tungstenite::{
+    WebSocketStream,
+    tungstenite::{
+        Message,
+        protocol::{CloseFrame, WebSocketConfig, frame::coding::CloseCode},
+    },
+};
+
+use crate::outbound::OutboundQueue;
+
+/// Bytes buffered between the socket and the inbound stream.
+const STREAM_BUFFER: usize = 64 * 1024;
+
+/// Size of the frame length prefix in each message.
+const LENGTH_PREFIX: usize = 4;
+
+/// How long a client may take to complete the WebSocket upgrade.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listens for WebSocket connections.
+#[derive(Debug)]
+pub struct WsListener {
+    listener: TcpListener,
+}
+
+impl WsListener {
+    /// Listen for WebSocket connections on `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound.
+    pub async fn bind(address: &str) -> io::Result<Self> {
+        Ok(Self { listener: TcpListener::bind(address).await? })
+    }
+
+    /// Listen on an already bound socket.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket can't be registered with the runtime.
+    pub fn from_std(listener: std::net::TcpListener) -> io::Result<Self> {
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener: TcpListener::from_std(listener)? })
+    }
+
+    /// Local address the listener is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket's address can't be read.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept the next TCP connection, without upgrading it.
+    ///
+    /// Upgrade it with [`WsConnection::accept`], off the accept loop, so a
+    /// slow client can't hold up other connections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if accepting fails.
+    pub async fn accept(&self) -> io::Result<TcpStream> {
+        Ok(self.listener.accept().await?.0)
+    }
+}
+
+/// An upgraded WebSocket connection.
+///
+/// A background task writes inbound frames to the stream returned by
+/// [`Self::into_parts`]; the returned [`WsSink`] sends outbound frames.
+#[derive(Debug)]
+pub struct WsConnection {
+    sink: SplitSink<WebSocketStream<TcpStream>, Message>,
+    inbound: DuplexStream,
+    closer: WsCloser,
+}
+
+/// Ends a [`WsConnection`]'s inbound stream.
+#[derive(Debug, Clone)]
+pub struct WsCloser {
+    inbound: AbortHandle,
+}
+
+impl WsCloser {
+    /// Stop reading from the socket.
+    ///
+    /// The inbound stream ends, which ends the session. The socket itself is
+    /// closed once the session's outbound frames are sent.
+    pub fn close(&self) {
+        self.inbound.abort();
+    }
+}
+
+impl WsConnection {
+    /// Upgrade `tcp` to a WebSocket.
+    ///
+    /// Messages are capped at the largest frame the protocol allows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the handshake fails or takes longer than
+    /// [`HANDSHAKE_TIMEOUT`].
+    pub async fn accept(tcp: TcpStream) -> io::Result<Self> {
+        let max_message =
+            LENGTH_PREFIX + FrameHeader::SIZE + FrameHeader::MAX_PAYLOAD_SIZE as usize;
+        let config = WebSocketConfig::default().max_message_size(Some(max_message));
+        let handshake = tokio_tungstenite::accept_async_with_config(tcp, Some(config));
+        let ws = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "WebSocket handshake timed out"))?
+            .map_err(io::Error::other)?;
+
+        let (sink, messages) = ws.split();
+        let (inbound, to_session) = tokio::io::duplex(STREAM_BUFFER);
+        let pump = tokio::spawn(pump_inbound(messages, to_session));
+
+        Ok(Self { sink, inbound, closer: WsCloser { inbound: pump.abort_handle() } })
+    }
+
+    /// Handle that ends the inbound stream.
+    pub fn closer(&self) -> WsCloser {
+        self.closer.clone()
+    }
+
+    /// Split into the inbound frame stream and the outbound sink.
+    pub fn into_parts(self) -> (DuplexStream, WsSink) {
+        (self.inbound, WsSink { sink: self.sink })
+    }
+}
+
+/// Outbound side of a [`WsConnection`].
+#[derive(Debug)]
+pub struct WsSink {
+    sink: SplitSink<WebSocketStream<TcpStream>, Message>,
+}
+
+impl WsSink {
+    /// Send every frame from `queue` until it is closed and empty, then close
+    /// the WebSocket.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a message can't be sent.
+    pub async fn drain(mut self, queue: &OutboundQueue) -> io::Result<()> {
+        while let Some(frame) = queue.next().await {
+            let message = encode_message(&frame)?;
+            self.sink.send(Message::Binary(message)).await.map_err(io::Error::other)?;
+        }
+
+        let close = CloseFrame { code: CloseCode::Normal, reason: "".into() };
+        self.sink.send(Message::Close(Some(close))).await.map_err(io::Error::other)?;
+        self.sink.close().await.map_err(io::Error::other)
+    }
+}
+
+/// Write the frames from incoming messages to the inbound stream.
+async fn pump_inbound(
+    mut messages: SplitStream<WebSocketStream<TcpStream>>,
+    mut to_session: DuplexStream,
+) {
+    while let Some(Ok(message)) = messages.next().await {
+        match message {
+            Message::Binary(data) => {
+                let Some(frame) = decode_message(&data) else { break };
+                if to_session.write_all(frame).await.is_err() {
+                    break;
+                }
+            },
+            Message::Text(_) | Message::Close(_) => break,
+            // Pings are answered by tungstenite itself
+            Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {},
+        }
+    }
+
+    let _ = to_session.shutdown().await;
+}
+
+/// Prefix an encoded frame with its length.
+fn encode_message(frame: &[u8]) -> io::Result<Bytes> {
+    let len = u32::try_from(frame.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "frame too large"))?;
+    let mut message = BytesMut::with_capacity(LENGTH_PREFIX + frame.len());
+    message.put_u32(len);
+    message.put_slice(frame);
+    Ok(message.freeze())
+}
+
+/// Strip and check a message's length prefix.
+fn decode_message(message: &[u8]) -> Option<&[u8]> {
+    let (prefix, frame) = message.split_first_chunk::<LENGTH_PREFIX>()?;
+    (usize::try_from(u32::from_be_bytes(*prefix)).ok() == Some(frame.len())).then_some(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_length_prefix_must_match_frame() {
+        let message = encode_message(b"frame").unwrap();
+        assert_eq!(&message[..LENGTH_PREFIX], &5u32.to_be_bytes());
+        assert_eq!(decode_message(&message), Some(&b"frame"[..]));
+
+        assert_eq!(decode_message(&message[..message.len() - 1]), None);
+        let mut padded = message.to_vec();
+        padded.push(0);
+        assert_eq!(decode_message(&padded), None);
+    }
+}
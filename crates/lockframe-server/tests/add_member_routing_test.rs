@@ -6,6 +6,7 @@
 //! 3. `KeyPackage` publish/fetch works via registry
 //! 4. Welcome frames are routed to correct recipients
 
+use lockframe_core::mls::MlsGroup;
 use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::session::Hello};
 use lockframe_server::{
     DriverConfig, MemoryStorage, ServerAction, ServerDriver, ServerEvent, SystemEnv,
@@ -91,10 +92,12 @@ fn test_add_member_welcome_routing() {
     assert_eq!(bob_replies[0].header.opcode_enum(), Some(Opcode::HelloReply));
 
     // Step 3: Bob publishes KeyPackage
+    let (key_package_bytes, hash_ref, _) =
+        MlsGroup::generate_key_package(SystemEnv::new(), bob_user_id).unwrap();
     let kp_publish =
         Payload::KeyPackagePublish(lockframe_proto::payloads::mls::KeyPackagePublishRequest {
-            key_package_bytes: vec![1, 2, 3, 4], // Fake KeyPackage
-            hash_ref: vec![5, 6, 7, 8],
+            key_package_bytes,
+            hash_ref,
         })
         .into_frame(FrameHeader::new(Opcode::KeyPackagePublish))
         .unwrap();
@@ -228,3 +231,81 @@ fn test_welcome_routing_fails_without_hello() {
     let bob_frames = frames_for_session(&actions, session_2);
     assert!(bob_frames.is_empty(), "Bob should NOT receive Welcome when not authenticated");
 }
+
+#[test]
+fn test_welcome_for_offline_recipient_is_delivered_on_hello() {
+    let env = SystemEnv::new();
+    let storage = MemoryStorage::new();
+    let mut server = ServerDriver::new(env, storage, DriverConfig::default());
+
+    let session_1 = 1001;
+    let session_2 = 1002;
+    let alice_user_id = 42;
+    let bob_user_id = 99;
+
+    let hello = |sender_id| {
+        Payload::Hello(Hello {
+            version: 1,
+            capabilities: vec![],
+            sender_id: Some(sender_id),
+            auth_token: None,
+        })
+        .into_frame(FrameHeader::new(Opcode::Hello))
+        .unwrap()
+    };
+
+    server.process_event(ServerEvent::ConnectionAccepted { session_id: session_1 }).unwrap();
+    server
+        .process_event(ServerEvent::FrameReceived {
+            session_id: session_1,
+            frame: hello(alice_user_id),
+        })
+        .unwrap();
+
+    let room_id = 0x1234_u128;
+    server.create_room(room_id, session_1).unwrap();
+
+    // Bob is offline when Alice invites him
+    let mut welcome_header = FrameHeader::new(Opcode::Welcome);
+    welcome_header.set_room_id(room_id);
+    welcome_header.set_sender_id(alice_user_id);
+    welcome_header.set_recipient_id(bob_user_id);
+    let welcome_frame = Frame::new(welcome_header, vec![1, 2, 3]);
+
+    server
+        .process_event(ServerEvent::FrameReceived { session_id: session_1, frame: welcome_frame })
+        .unwrap();
+
+    // Bob's next Hello delivers the queued Welcome and subscribes him
+    server.process_event(ServerEvent::ConnectionAccepted { session_id: session_2 }).unwrap();
+    let actions = server
+        .process_event(ServerEvent::FrameReceived {
+            session_id: session_2,
+            frame: hello(bob_user_id),
+        })
+        .unwrap();
+
+    let welcomes: Vec<Frame> = frames_for_session(&actions, session_2)
+        .into_iter()
+        .filter(|f| f.header.opcode_enum() == Some(Opcode::Welcome))
+        .collect();
+    assert_eq!(
+        welcomes.len(),
+        1,
+        "Bob should receive the queued Welcome. Got actions: {actions:?}"
+    );
+    assert_eq!(welcomes[0].header.recipient_id(), bob_user_id);
+    assert_eq!(welcomes[0].payload.as_ref(), &[1, 2, 3]);
+    assert!(server.sessions_in_room(room_id).any(|s| s == session_2));
+
+    // The queue is drained, so a reconnect doesn't deliver it again
+    server.process_event(ServerEvent::ConnectionAccepted { session_id: 1003 }).unwrap();
+    let actions = server
+        .process_event(ServerEvent::FrameReceived { session_id: 1003, frame: hello(bob_user_id) })
+        .unwrap();
+    assert!(
+        frames_for_session(&actions, 1003)
+            .iter()
+            .all(|f| f.header.opcode_enum() != Some(Opcode::Welcome))
+    );
+}
@@ -184,7 +184,7 @@ fn prop_sequencer_never_creates_gaps() {
             // Even with failures, total work should be O(n) not O(n²)
             // We expect at most 3 storage ops per attempt: load_mls_state, store_frame, store_mls_state
             let max_expected_ops = attempt_count * 3;
-            let actual_ops = storage.operation_count();
+            let actual_ops = storage.operation_count().expect("operation count readable");
 
             prop_assert!(
                 actual_ops <= max_expected_ops,
@@ -5,10 +5,17 @@
 //! 2. Client A fetches `KeyPackage` for `user_id` B
 //! 3. Server routes Welcome to B after A adds them
 
-use lockframe_core::env::test_utils::MockEnv;
+use lockframe_core::{
+    env::test_utils::MockEnv,
+    mls::{MlsGroup, MlsProvider, validate_key_package},
+};
+use lockframe_harness::SimEnv;
 use lockframe_proto::{
     FrameHeader, Opcode, Payload,
-    payloads::mls::{KeyPackageFetchPayload, KeyPackagePublishRequest},
+    payloads::{
+        ErrorPayload,
+        mls::{KeyPackageFetchPayload, KeyPackagePublishRequest},
+    },
 };
 use lockframe_server::{DriverConfig, MemoryStorage, ServerAction, ServerDriver, ServerEvent};
 
@@ -124,10 +131,11 @@ fn keypackage_consumed_after_fetch() {
         .expect("auth B");
 
     // B publishes KeyPackage
-    let publish = Payload::KeyPackagePublish(KeyPackagePublishRequest {
-        key_package_bytes: vec![1, 2, 3],
-        hash_ref: vec![4, 5],
-    });
+    let (key_package_bytes, hash_ref, _) =
+        MlsGroup::generate_key_package(MockEnv::with_crypto_rng(), user_id_b)
+            .expect("generate KeyPackage");
+    let publish =
+        Payload::KeyPackagePublish(KeyPackagePublishRequest { key_package_bytes, hash_ref });
     driver
         .process_event(ServerEvent::FrameReceived {
             session_id: session_b,
@@ -185,3 +193,111 @@ fn keypackage_consumed_after_fetch() {
         "Second fetch should return error (KeyPackage consumed)"
     );
 }
+
+/// Test that forged and misattributed `KeyPackage`s are refused at publish.
+#[test]
+fn keypackage_publish_rejects_invalid_packages() {
+    let mut driver = create_driver();
+
+    let session_id = 1001;
+    let user_id = 2000;
+
+    driver.process_event(ServerEvent::ConnectionAccepted { session_id }).expect("accept");
+    let hello = Payload::Hello(lockframe_proto::payloads::session::Hello {
+        version: 1,
+        capabilities: vec![],
+        sender_id: Some(user_id),
+        auth_token: None,
+    });
+    driver
+        .process_event(ServerEvent::FrameReceived {
+            session_id,
+            frame: hello.into_frame(FrameHeader::new(Opcode::Hello)).unwrap(),
+        })
+        .expect("auth");
+
+    let mut publish = |key_package_bytes: Vec<u8>| {
+        let publish = Payload::KeyPackagePublish(KeyPackagePublishRequest {
+            key_package_bytes,
+            hash_ref: vec![],
+        });
+        let actions = driver
+            .process_event(ServerEvent::FrameReceived {
+                session_id,
+                frame: publish.into_frame(FrameHeader::new(Opcode::KeyPackagePublish)).unwrap(),
+            })
+            .expect("publish");
+        actions.iter().find_map(|a| match a {
+            ServerAction::SendToSession { frame, .. } => match Payload::from_frame(frame) {
+                Ok(Payload::Error(error)) => Some(error.code),
+                _ => None,
+            },
+            _ => None,
+        })
+    };
+
+    let (mut tampered, _, _) =
+        MlsGroup::generate_key_package(MockEnv::with_crypto_rng(), user_id).expect("generate");
+    *tampered.last_mut().expect("non-empty") ^= 0x01;
+    assert_eq!(publish(tampered), Some(ErrorPayload::MLS_ERROR));
+
+    let (someone_else, _, _) =
+        MlsGroup::generate_key_package(MockEnv::with_crypto_rng(), 3000).expect("generate");
+    assert_eq!(publish(someone_else), Some(ErrorPayload::FORBIDDEN));
+
+    let (own, _, _) =
+        MlsGroup::generate_key_package(MockEnv::with_crypto_rng(), user_id).expect("generate");
+    assert_eq!(publish(own), None);
+}
+
+/// Test that a `KeyPackage` past its lifetime on the server's clock is
+/// refused at publish.
+#[test]
+fn keypackage_publish_rejects_expired_package() {
+    let (key_package_bytes, hash_ref, _) =
+        MlsGroup::generate_key_package(MockEnv::with_crypto_rng(), 2000).expect("generate");
+    let provider = MlsProvider::new(MockEnv::new());
+    let expires_at_secs =
+        validate_key_package(&provider, &key_package_bytes).expect("valid").expires_at_secs;
+
+    let env = SimEnv::with_seed(1);
+    env.set_wall_clock(expires_at_secs * 1000);
+    let mut driver = ServerDriver::new(env, MemoryStorage::new(), DriverConfig::default());
+
+    let session_id = 1001;
+    driver.process_event(ServerEvent::ConnectionAccepted { session_id }).expect("accept");
+    let hello = Payload::Hello(lockframe_proto::payloads::session::Hello {
+        version: 1,
+        capabilities: vec![],
+        sender_id: Some(2000),
+        auth_token: None,
+    });
+    driver
+        .process_event(ServerEvent::FrameReceived {
+            session_id,
+            frame: hello.into_frame(FrameHeader::new(Opcode::Hello)).unwrap(),
+        })
+        .expect("auth");
+
+    let publish =
+        Payload::KeyPackagePublish(KeyPackagePublishRequest { key_package_bytes, hash_ref });
+    let actions = driver
+        .process_event(ServerEvent::FrameReceived {
+            session_id,
+            frame: publish.into_frame(FrameHeader::new(Opcode::KeyPackagePublish)).unwrap(),
+        })
+        .expect("publish");
+
+    let error = actions
+        .iter()
+        .find_map(|a| match a {
+            ServerAction::SendToSession { frame, .. } => match Payload::from_frame(frame) {
+                Ok(Payload::Error(error)) => Some(error),
+                _ => None,
+            },
+            _ => None,
+        })
+        .expect("publish refused");
+    assert_eq!(error.code, ErrorPayload::MLS_ERROR);
+    assert!(error.message.contains("expired"), "{}", error.message);
+}
@@ -35,7 +35,7 @@ proptest! {
         let mut manager = RoomManager::new();
         let storage = MemoryStorage::new();
 
-        manager.create_room(room_id, creator, &env, &storage)?;
+        manager.create_room(room_id, creator, None, &env, &storage)?;
         prop_assert!(manager.has_room(room_id));
     }
 
@@ -50,9 +50,9 @@ proptest! {
         let mut manager = RoomManager::new();
         let storage = MemoryStorage::new();
 
-        manager.create_room(room_id, creator, &env, &storage)?;
+        manager.create_room(room_id, creator, None, &env, &storage)?;
 
-        let result = manager.create_room(room_id, creator, &env, &storage);
+        let result = manager.create_room(room_id, creator, None, &env, &storage);
         prop_assert!(matches!(result, Err(RoomError::RoomAlreadyExists(_))));
     }
 
@@ -70,7 +70,7 @@ proptest! {
         let unique_ids: HashSet<u128> = room_ids.into_iter().collect();
 
         for room_id in &unique_ids {
-            manager.create_room(*room_id, 0, &env, &storage)?;
+            manager.create_room(*room_id, 0, None, &env, &storage)?;
         }
 
         for room_id in &unique_ids {
@@ -111,7 +111,7 @@ proptest! {
         let mut manager = RoomManager::new();
         let storage = MemoryStorage::new();
 
-        manager.create_room(room_id, creator, &env, &storage)?;
+        manager.create_room(room_id, creator, None, &env, &storage)?;
 
         for i in 0..total_frames {
             let mut header = FrameHeader::new(Opcode::AppMessage);
@@ -132,6 +132,7 @@ proptest! {
             start,
             page_size,
             &env,
+            0,
             &storage
         );
 
@@ -173,6 +174,7 @@ proptest! {
             start_index,
             limit,
             &env,
+            0,
             &storage
         );
 
@@ -192,7 +194,7 @@ proptest! {
         let mut manager = RoomManager::new();
         let storage = MemoryStorage::new();
 
-        manager.create_room(room_id, creator, &env, &storage)?;
+        manager.create_room(room_id, creator, None, &env, &storage)?;
 
         let frame = create_test_frame(room_id, creator, epoch, payload);
 
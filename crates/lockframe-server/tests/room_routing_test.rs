@@ -12,7 +12,7 @@
 use bytes::Bytes;
 use lockframe_harness::SimServer;
 use lockframe_proto::{Frame, FrameHeader, Opcode};
-use lockframe_server::ServerEvent;
+use lockframe_server::{ServerEvent, Storage};
 use turmoil::Builder;
 
 /// Test room IDs
@@ -337,8 +337,12 @@ fn empty_room_after_all_disconnect() {
         // Oracle: Room should be empty (but still exists)
         verify_room_membership(&server, ROOM_1, 0, "after all disconnect");
 
-        // Room should still exist even with no members
-        assert!(server.has_room(ROOM_1), "Room should still exist after all members leave");
+        // Empty room is evicted from memory but kept in storage
+        assert!(!server.has_room(ROOM_1), "Empty room should be evicted from memory");
+        assert!(
+            server.driver().storage().load_room_metadata(ROOM_1).unwrap().is_some(),
+            "Room should still be persisted after all members leave"
+        );
 
         Ok(())
     });
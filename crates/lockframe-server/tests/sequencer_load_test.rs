@@ -0,0 +1,167 @@
+//! Sequencer ordering under load.
+//!
+//! Many sessions submit messages to one room, interleaved by a seeded
+//! `SimEnv`. Some sends are retried before the original has been sequenced,
+//! so the retry races the original for the same position. Whatever the
+//! interleaving, the room's log must be contiguous from zero, hold every
+//! message exactly once, and follow arrival order: ties go to whichever
+//! frame reached the room's queue first.
+
+use std::collections::HashSet;
+
+use lockframe_core::env::Environment;
+use lockframe_harness::SimEnv;
+use lockframe_proto::{Frame, FrameHeader, Opcode, Payload, payloads::app::EncryptedMessage};
+use lockframe_server::{MemoryStorage, RoomAction, RoomManager, Storage};
+use proptest::prelude::*;
+
+const ROOM_ID: u128 = 0x100;
+const SESSIONS: u64 = 8;
+const MESSAGES_PER_SESSION: u64 = 64;
+const TOTAL_MESSAGES: u64 = SESSIONS * MESSAGES_PER_SESSION;
+
+/// `AppMessage` from `session_id` carrying `message_id`.
+///
+/// Every frame claims log index 0, as a client that has not seen any of the
+/// other sessions' messages would.
+#[allow(clippy::expect_used)]
+fn message_frame(session_id: u64, message_id: u128) -> Frame {
+    let mut header = FrameHeader::new(Opcode::AppMessage);
+    header.set_room_id(ROOM_ID);
+    header.set_sender_id(session_id);
+    header.set_log_index(0);
+    Payload::AppMessage(EncryptedMessage {
+        epoch: 0,
+        sender_index: 0,
+        generation: 0,
+        nonce: [0; 24],
+        ciphertext: message_id.to_be_bytes().to_vec(),
+        push_keys: None,
+        message_id: Some(message_id),
+        expires_at: None,
+    })
+    .into_frame(header)
+    .expect("encode AppMessage")
+}
+
+/// Message ID of a stored `AppMessage` frame.
+#[allow(clippy::expect_used, clippy::panic)]
+fn message_id(frame: &Frame) -> u128 {
+    match Payload::from_frame(frame).expect("decode stored frame") {
+        Payload::AppMessage(message) => message.message_id.expect("stored message has an ID"),
+        other => panic!("unexpected payload in log: {other:?}"),
+    }
+}
+
+/// Outcome of one load run.
+#[derive(Debug, PartialEq, Eq)]
+struct Run {
+    /// Stored frames in log order
+    log: Vec<Frame>,
+    /// Message IDs in the order their first send reached the queue
+    arrivals: Vec<u128>,
+    /// Retries enqueued
+    retries: usize,
+    /// Frames the room manager rejected
+    rejected: usize,
+}
+
+/// Submit every session's messages to one room in an order chosen by
+/// `seed`, sequencing queued frames in between, until all are processed.
+#[allow(clippy::expect_used)]
+fn run(seed: u64) -> Run {
+    let env = SimEnv::with_seed(seed);
+    let storage = MemoryStorage::new();
+    let mut manager = RoomManager::new();
+    manager.create_room(ROOM_ID, 1, None, &env, &storage).expect("create room");
+
+    let mut sent = [0u64; SESSIONS as usize];
+    let mut arrivals = Vec::new();
+    let mut retries = 0;
+    let mut rejected = 0;
+
+    loop {
+        let roll = env.random_u64();
+        let pick = roll >> 8;
+        let unsent = (arrivals.len() as u64) < TOTAL_MESSAGES;
+
+        match roll % 4 {
+            // A burst of sends from one session
+            0 | 1 if unsent => {
+                let session_id = pick % SESSIONS;
+                for _ in 0..=(pick >> 16) % 4 {
+                    let seq = &mut sent[session_id as usize];
+                    if *seq == MESSAGES_PER_SESSION {
+                        break;
+                    }
+                    let message_id = u128::from(session_id) << 64 | u128::from(*seq);
+                    *seq += 1;
+                    manager
+                        .enqueue(session_id, message_frame(session_id, message_id))
+                        .expect("queue has room");
+                    arrivals.push(message_id);
+                }
+            },
+            // A retry of an earlier send, possibly still queued
+            2 if unsent && !arrivals.is_empty() => {
+                let message_id = arrivals[(pick % arrivals.len() as u64) as usize];
+                let session_id = (message_id >> 64) as u64;
+                manager
+                    .enqueue(session_id, message_frame(session_id, message_id))
+                    .expect("queue has room");
+                retries += 1;
+            },
+            _ => {
+                let Some(processed) = manager.process_next(ROOM_ID, env.now(), &storage) else {
+                    if unsent {
+                        continue;
+                    }
+                    break;
+                };
+                for action in processed.result.expect("frame processed") {
+                    match action {
+                        RoomAction::PersistFrame { room_id, log_index, frame, .. } => {
+                            storage.store_frame(room_id, log_index, &frame).expect("store frame");
+                        },
+                        RoomAction::Reject { .. } => rejected += 1,
+                        _ => {},
+                    }
+                }
+            },
+        }
+    }
+
+    let log = storage.load_frames(ROOM_ID, 0, TOTAL_MESSAGES as usize + 1).expect("load frames");
+    Run { log, arrivals, retries, rejected }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    /// Property: the log is gap-free, duplicate-free and in arrival order for
+    /// every interleaving of sends and retries.
+    #[test]
+    fn prop_sequencer_orders_racing_sessions_by_arrival(seed in any::<u64>()) {
+        let run = run(seed);
+
+        prop_assert_eq!(run.log.len() as u64, TOTAL_MESSAGES);
+        for (position, frame) in run.log.iter().enumerate() {
+            prop_assert_eq!(frame.header.log_index(), position as u64);
+        }
+
+        let logged: Vec<u128> = run.log.iter().map(message_id).collect();
+        let unique: HashSet<u128> = logged.iter().copied().collect();
+        prop_assert_eq!(unique.len(), logged.len());
+        prop_assert_eq!(&logged, &run.arrivals);
+
+        // Every retry lost to its original
+        prop_assert_eq!(run.rejected, run.retries);
+    }
+}
+
+#[test]
+fn sequencer_load_is_deterministic_per_seed() {
+    let first = run(7);
+    assert!(first.retries > 0, "seed 7 should race some retries");
+    assert_eq!(first, run(7));
+}
@@ -23,7 +23,7 @@ fn create_test_frame(room_id: u128, sender_id: u64, epoch: u64, payload: Vec<u8>
 #[allow(clippy::expect_used)]
 fn execute_actions(actions: Vec<SequencerAction>, storage: &MemoryStorage) {
     for action in actions {
-        if let SequencerAction::StoreFrame { room_id, log_index, frame } = action {
+        if let SequencerAction::StoreFrame { room_id, log_index, frame, .. } = action {
             storage.store_frame(room_id, log_index, &frame).expect("store_frame failed");
         }
     }
@@ -1,6 +1,6 @@
 //! Server storage tests for external join flow.
 
-use lockframe_client::{Client, ClientAction, ClientEvent, ClientIdentity};
+use lockframe_client::{Client, ClientAction, ClientConfig, ClientEvent, ClientIdentity};
 use lockframe_core::mls::RoomId;
 use lockframe_harness::{SimEnv, SimServer};
 use lockframe_proto::{Frame, Opcode};
@@ -39,10 +39,11 @@ fn server_stores_and_retrieves_group_info() {
         let _ =
             server.driver_mut().process_event(ServerEvent::ConnectionAccepted { session_id: 1 });
 
-        // Alice creates room
+        // Alice creates room; only members may publish its GroupInfo
+        server.create_room(ROOM_ID, 1)?;
         let env = SimEnv::new();
         let alice_id = ClientIdentity::new(1);
-        let mut alice = Client::new(env.clone(), alice_id);
+        let mut alice = Client::new(env.clone(), alice_id, ClientConfig::default());
 
         let create_actions =
             alice.handle(ClientEvent::CreateRoom { room_id: ROOM_ID }).expect("alice create room");
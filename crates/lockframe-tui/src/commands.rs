@@ -3,6 +3,7 @@
 //! This module parses command strings into structured [`Command`] values.
 
 use lockframe_core::mls::RoomId;
+use lockframe_proto::ids;
 
 /// Parsed command from user input.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,6 +35,27 @@ pub enum Command {
         user_id: u64,
     },
 
+    /// Open a direct-message room with a user.
+    DirectMessage {
+        /// User ID to message.
+        user_id: u64,
+    },
+
+    /// Search the active room's history.
+    Search {
+        /// Text to search for.
+        term: String,
+    },
+
+    /// End the current search.
+    ClearSearch,
+
+    /// Write the active room's history to a file.
+    Export {
+        /// Destination path.
+        path: String,
+    },
+
     /// Quit the application.
     Quit,
 
@@ -60,7 +82,10 @@ pub enum Command {
 
 /// Parse a user input string into a command.
 ///
-/// Commands start with `/`. Anything else is treated as a message.
+/// Commands start with `/`. Anything else is treated as a message. Room and
+/// user IDs may be decimal or `0x`-prefixed hex, and room IDs may also be the
+/// 32 hex digits logs print. `/search` takes the rest of
+/// the line as its term; without one it ends the search.
 pub fn parse(input: &str) -> Command {
     let input = input.trim();
 
@@ -79,12 +104,9 @@ pub fn parse(input: &str) -> Command {
         "connect" => Command::Connect,
 
         "create" => match parts.get(1) {
-            Some(id_str) => match id_str.parse::<u128>() {
-                Ok(room_id) => Command::CreateRoom { room_id },
-                Err(_) => Command::InvalidArgs {
-                    command: "create".into(),
-                    error: "Invalid room ID".into(),
-                },
+            Some(id_str) => match id_str.parse::<ids::RoomId>() {
+                Ok(room_id) => Command::CreateRoom { room_id: room_id.get() },
+                Err(e) => Command::InvalidArgs { command: "create".into(), error: e.to_string() },
             },
             None => Command::InvalidArgs {
                 command: "create".into(),
@@ -93,11 +115,9 @@ pub fn parse(input: &str) -> Command {
         },
 
         "join" => match parts.get(1) {
-            Some(id_str) => match id_str.parse::<u128>() {
-                Ok(room_id) => Command::JoinRoom { room_id },
-                Err(_) => {
-                    Command::InvalidArgs { command: "join".into(), error: "Invalid room ID".into() }
-                },
+            Some(id_str) => match id_str.parse::<ids::RoomId>() {
+                Ok(room_id) => Command::JoinRoom { room_id: room_id.get() },
+                Err(e) => Command::InvalidArgs { command: "join".into(), error: e.to_string() },
             },
             None => Command::InvalidArgs {
                 command: "join".into(),
@@ -110,11 +130,9 @@ pub fn parse(input: &str) -> Command {
         "publish" => Command::PublishKeyPackage,
 
         "add" => match parts.get(1) {
-            Some(id_str) => match id_str.parse::<u64>() {
-                Ok(user_id) => Command::AddMember { user_id },
-                Err(_) => {
-                    Command::InvalidArgs { command: "add".into(), error: "Invalid user ID".into() }
-                },
+            Some(id_str) => match id_str.parse::<ids::MemberId>() {
+                Ok(user_id) => Command::AddMember { user_id: user_id.get() },
+                Err(e) => Command::InvalidArgs { command: "add".into(), error: e.to_string() },
             },
             None => Command::InvalidArgs {
                 command: "add".into(),
@@ -122,6 +140,34 @@ pub fn parse(input: &str) -> Command {
             },
         },
 
+        "msg" => match parts.get(1) {
+            Some(id_str) => match id_str.parse::<ids::MemberId>() {
+                Ok(user_id) => Command::DirectMessage { user_id: user_id.get() },
+                Err(e) => Command::InvalidArgs { command: "msg".into(), error: e.to_string() },
+            },
+            None => Command::InvalidArgs {
+                command: "msg".into(),
+                error: "Usage: /msg <user_id>".into(),
+            },
+        },
+
+        "search" => {
+            let term = cmd_str.strip_prefix("search").unwrap_or_default().trim();
+            if term.is_empty() {
+                Command::ClearSearch
+            } else {
+                Command::Search { term: term.to_string() }
+            }
+        },
+
+        "export" => match parts.get(1) {
+            Some(path) => Command::Export { path: (*path).to_string() },
+            None => Command::InvalidArgs {
+                command: "export".into(),
+                error: "Usage: /export <path>".into(),
+            },
+        },
+
         "quit" | "q" => Command::Quit,
 
         _ => Command::Unknown { input: input.to_string() },
@@ -132,6 +178,20 @@ pub fn parse(input: &str) -> Command {
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_search() {
+        assert_eq!(parse("/search  hello world "), Command::Search { term: "hello world".into() });
+        assert_eq!(parse("/search"), Command::ClearSearch);
+    }
+
+    #[test]
+    fn parse_export() {
+        assert_eq!(parse("/export chat.jsonl"), Command::Export { path: "chat.jsonl".into() });
+        assert!(
+            matches!(parse("/export"), Command::InvalidArgs { command, .. } if command == "export")
+        );
+    }
+
     #[test]
     fn parse_message() {
         assert_eq!(parse("hello world"), Command::Message { content: "hello world".into() });
@@ -159,6 +219,25 @@ mod tests {
         assert_eq!(parse("/join 200"), Command::JoinRoom { room_id: 200 });
     }
 
+    #[test]
+    fn parse_join_accepts_decimal_and_hex() {
+        assert_eq!(parse("/join 0x1234"), parse("/join 4660"));
+        assert_eq!(parse("/join 0x1234"), Command::JoinRoom { room_id: 0x1234 });
+        assert_eq!(parse("/add 0x2a"), Command::AddMember { user_id: 42 });
+        // Room IDs as logs print them
+        assert_eq!(parse("/join 00000000000000000000000000001234"), parse("/join 4660"));
+    }
+
+    #[test]
+    fn parse_join_invalid_id_explains_format() {
+        assert_eq!(parse("/join lobby"), Command::InvalidArgs {
+            command: "join".into(),
+            error: "invalid room ID \"lobby\": expected decimal, 0x-prefixed hex, or 32 hex \
+                    digits"
+                .into(),
+        });
+    }
+
     #[test]
     fn parse_leave() {
         assert_eq!(parse("/leave"), Command::LeaveActiveRoom);
@@ -169,6 +248,15 @@ mod tests {
         assert_eq!(parse("/add 42"), Command::AddMember { user_id: 42 });
     }
 
+    #[test]
+    fn parse_direct_message() {
+        assert_eq!(parse("/msg 42"), Command::DirectMessage { user_id: 42 });
+        assert!(matches!(parse("/msg"), Command::InvalidArgs { command, .. } if command == "msg"));
+        assert!(
+            matches!(parse("/msg bob"), Command::InvalidArgs { command, .. } if command == "msg")
+        );
+    }
+
     #[test]
     fn parse_quit() {
         assert_eq!(parse("/quit"), Command::Quit);
@@ -2,40 +2,16 @@
 //!
 //! This module owns all text input state (buffer, cursor) and handles
 //! character-level key events. Command parsing happens here on Enter.
+//! While a search is active and the buffer is empty, `n` and `N` step to the
+//! next older and newer match before any other handling. Otherwise keys bound
+//! in the App's [`lockframe_app::KeyBindings`] are dispatched as commands
+//! before any text editing.
 
-use lockframe_app::{App, AppAction};
+use lockframe_app::{App, AppAction, AppCommand};
+pub use lockframe_app::{KeyInput, KeyModifiers};
 
 use crate::commands::{self, Command};
 
-/// Key input events from the terminal.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum KeyInput {
-    /// Character input.
-    Char(char),
-    /// Enter/Return key.
-    Enter,
-    /// Backspace key.
-    Backspace,
-    /// Delete key.
-    Delete,
-    /// Tab key.
-    Tab,
-    /// Escape key.
-    Esc,
-    /// Left arrow.
-    Left,
-    /// Right arrow.
-    Right,
-    /// Up arrow.
-    Up,
-    /// Down arrow.
-    Down,
-    /// Home key.
-    Home,
-    /// End key.
-    End,
-}
-
 /// Input state for the TUI.
 ///
 /// Manages the text input buffer and cursor position.
@@ -64,11 +40,32 @@ impl InputState {
         self.cursor
     }
 
-    /// Handle a key input event.
+    /// Handle a key input event with no modifiers held.
     ///
     /// Returns actions to process (may be empty for input-only keys,
     /// or contain protocol actions for commands).
     pub fn handle_key(&mut self, key: KeyInput, app: &mut App) -> Vec<AppAction> {
+        self.handle_key_with_modifiers(key, KeyModifiers::NONE, app)
+    }
+
+    /// Handle a key input event with the given modifiers held.
+    ///
+    /// Search navigation comes first, then bound keys run their
+    /// [`AppCommand`]; anything else edits the buffer.
+    pub fn handle_key_with_modifiers(
+        &mut self,
+        key: KeyInput,
+        modifiers: KeyModifiers,
+        app: &mut App,
+    ) -> Vec<AppAction> {
+        if let Some(actions) = self.handle_search_key(key, modifiers, app) {
+            return actions;
+        }
+
+        if let Some(command) = app.key_bindings().command_for(key, modifiers) {
+            return self.handle_command(command, app);
+        }
+
         match key {
             KeyInput::Char(c) => {
                 self.buffer.insert(self.cursor, c);
@@ -107,9 +104,46 @@ impl InputState {
                 vec![AppAction::Render]
             },
             KeyInput::Enter => self.handle_enter(app),
-            KeyInput::Tab => self.handle_tab(app),
-            KeyInput::Esc => vec![AppAction::Quit],
-            KeyInput::Up | KeyInput::Down => vec![],
+            KeyInput::Tab | KeyInput::Esc | KeyInput::Up | KeyInput::Down => vec![],
+        }
+    }
+
+    /// Step through search matches on `n` / `N`, or `None` if `key` is not a
+    /// search step.
+    ///
+    /// Only applies while a search is active and nothing has been typed.
+    /// Terminals report `N` with Shift held, so Shift is accepted on either
+    /// case; Ctrl or Alt make it an ordinary key.
+    fn handle_search_key(
+        &self,
+        key: KeyInput,
+        modifiers: KeyModifiers,
+        app: &mut App,
+    ) -> Option<Vec<AppAction>> {
+        if !self.buffer.is_empty() || app.active_search().is_none() {
+            return None;
+        }
+        if modifiers.ctrl || modifiers.alt {
+            return None;
+        }
+
+        match key {
+            KeyInput::Char('n') if !modifiers.shift => Some(app.older_search_hit()),
+            KeyInput::Char('n' | 'N') => Some(app.newer_search_hit()),
+            _ => None,
+        }
+    }
+
+    /// Execute a bound command.
+    fn handle_command(&mut self, command: AppCommand, app: &mut App) -> Vec<AppAction> {
+        match command {
+            AppCommand::Quit => app.quit(),
+            AppCommand::CycleRoom => self.handle_tab(app),
+            AppCommand::ClearInput => {
+                self.buffer.clear();
+                self.cursor = 0;
+                vec![AppAction::Render]
+            },
         }
     }
 
@@ -146,6 +180,10 @@ impl InputState {
                     vec![AppAction::Render]
                 }
             },
+            Command::DirectMessage { user_id } => app.direct_message(user_id),
+            Command::Search { term } => app.search(&term),
+            Command::ClearSearch => app.clear_search(),
+            Command::Export { path } => app.export_transcript(path.into()),
             Command::Quit => app.quit(),
             Command::Message { content } => {
                 if let Some(room_id) = app.active_room() {
@@ -166,7 +204,7 @@ impl InputState {
         }
     }
 
-    /// Handle Tab key - cycle through rooms.
+    /// Cycle through rooms (Tab by default).
     ///
     /// Cycles to the next room in sorted order, wrapping around.
     fn handle_tab(&self, app: &mut App) -> Vec<AppAction> {
@@ -281,4 +319,110 @@ mod tests {
         input.handle_key(KeyInput::Tab, &mut app);
         assert_eq!(app.active_room(), Some(1));
     }
+
+    #[test]
+    fn default_esc_quits() {
+        let mut input = InputState::new();
+        let mut app = App::new("localhost:4433".into());
+
+        let actions = input.handle_key(KeyInput::Esc, &mut app);
+        assert_eq!(actions, vec![AppAction::Quit]);
+    }
+
+    #[test]
+    fn remapped_quit_key_quits() {
+        use lockframe_app::KeyBindings;
+
+        let mut bindings = KeyBindings::default();
+        bindings.bind(KeyInput::Esc, KeyModifiers::NONE, AppCommand::ClearInput);
+        bindings.bind(KeyInput::Char('q'), KeyModifiers::CTRL, AppCommand::Quit);
+
+        let mut input = InputState::new();
+        let mut app = App::new_with_bindings("localhost:4433".into(), bindings);
+
+        input.handle_key(KeyInput::Char('h'), &mut app);
+        input.handle_key(KeyInput::Char('i'), &mut app);
+
+        // Esc now clears input instead of quitting
+        let actions = input.handle_key(KeyInput::Esc, &mut app);
+        assert_eq!(actions, vec![AppAction::Render]);
+        assert!(input.buffer().is_empty());
+        assert_eq!(input.cursor(), 0);
+
+        // Plain 'q' is still text input
+        input.handle_key(KeyInput::Char('q'), &mut app);
+        assert_eq!(input.buffer(), "q");
+
+        let actions =
+            input.handle_key_with_modifiers(KeyInput::Char('q'), KeyModifiers::CTRL, &mut app);
+        assert_eq!(actions, vec![AppAction::Quit]);
+    }
+
+    #[test]
+    fn n_steps_through_search_hits_until_typing() {
+        use lockframe_app::{AppEvent, Search};
+
+        // A binding on `N` doesn't shadow search navigation
+        let mut bindings = lockframe_app::KeyBindings::default();
+        bindings.bind(KeyInput::Char('N'), KeyModifiers::SHIFT, AppCommand::ClearInput);
+        let mut input = InputState::new();
+        let mut app = App::new_with_bindings("localhost:4433".into(), bindings);
+        app.handle(AppEvent::RoomJoined { room_id: 1 });
+        for (log_index, text) in ["find me", "other", "find me too"].into_iter().enumerate() {
+            app.handle(AppEvent::MessageReceived {
+                room_id: 1,
+                sender_id: 2,
+                content: text.as_bytes().to_vec(),
+                log_index: Some(log_index as u64),
+                timestamp: None,
+            });
+        }
+
+        for c in "/search find".chars() {
+            input.handle_key(KeyInput::Char(c), &mut app);
+        }
+        input.handle_key(KeyInput::Enter, &mut app);
+        assert_eq!(app.active_search().and_then(Search::current_hit), Some(2));
+
+        input.handle_key(KeyInput::Char('n'), &mut app);
+        assert_eq!(app.active_search().and_then(Search::current_hit), Some(0));
+        input.handle_key_with_modifiers(KeyInput::Char('N'), KeyModifiers::SHIFT, &mut app);
+        assert_eq!(app.active_search().and_then(Search::current_hit), Some(2));
+        assert!(input.buffer().is_empty());
+
+        // Modified keys aren't search steps
+        input.handle_key_with_modifiers(KeyInput::Char('n'), KeyModifiers::CTRL, &mut app);
+        assert_eq!(app.active_search().and_then(Search::current_hit), Some(2));
+        input.handle_key(KeyInput::Backspace, &mut app);
+
+        // With text in the buffer, n is just a character
+        input.handle_key(KeyInput::Char('a'), &mut app);
+        input.handle_key(KeyInput::Char('n'), &mut app);
+        assert_eq!(input.buffer(), "an");
+        assert_eq!(app.active_search().and_then(Search::current_hit), Some(2));
+    }
+
+    #[test]
+    fn msg_command_creates_room_then_adds_member() {
+        use lockframe_app::AppEvent;
+
+        let mut input = InputState::new();
+        let mut app = App::new("localhost:4433".into());
+        app.handle(AppEvent::Connected { session_id: 1, sender_id: 7 });
+
+        for c in "/msg 42".chars() {
+            input.handle_key(KeyInput::Char(c), &mut app);
+        }
+        let actions = input.handle_key(KeyInput::Enter, &mut app);
+
+        let [
+            AppAction::CreateRoom { room_id: created },
+            AppAction::AddMember { room_id: invited, user_id: 42 },
+            AppAction::Render,
+        ] = actions.as_slice()
+        else {
+            panic!("unexpected actions: {actions:?}");
+        };
+        assert_eq!(created, invited);
+    }
 }
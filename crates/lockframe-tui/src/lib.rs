@@ -9,6 +9,8 @@ pub mod terminal;
 pub mod ui;
 
 pub use commands::Command;
-pub use input::{InputState, KeyInput};
-pub use lockframe_app::{App, AppAction, AppEvent, Bridge, Driver, Runtime};
+pub use input::{InputState, KeyInput, KeyModifiers};
+pub use lockframe_app::{
+    App, AppAction, AppCommand, AppEvent, Bridge, Driver, KeyBindings, Runtime,
+};
 pub use terminal::{TerminalDriver, TerminalError};
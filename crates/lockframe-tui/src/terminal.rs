@@ -10,7 +10,7 @@ use std::{
 
 use crossterm::{
     ExecutableCommand,
-    event::{Event, EventStream, KeyCode, KeyEventKind},
+    event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers as CrosstermModifiers},
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use futures::StreamExt;
@@ -20,7 +20,7 @@ use lockframe_proto::Frame;
 use ratatui::{Terminal, backend::CrosstermBackend};
 use thiserror::Error;
 
-use crate::{InputState, KeyInput, ui};
+use crate::{InputState, KeyInput, KeyModifiers, ui};
 
 /// Terminal driver errors.
 #[derive(Debug, Error)]
@@ -87,6 +87,15 @@ impl TerminalDriver {
             _ => None,
         }
     }
+
+    /// Convert crossterm modifiers to [`KeyModifiers`].
+    fn convert_modifiers(modifiers: CrosstermModifiers) -> KeyModifiers {
+        KeyModifiers {
+            ctrl: modifiers.contains(CrosstermModifiers::CONTROL),
+            alt: modifiers.contains(CrosstermModifiers::ALT),
+            shift: modifiers.contains(CrosstermModifiers::SHIFT),
+        }
+    }
 }
 
 impl Driver for TerminalDriver {
@@ -104,7 +113,10 @@ impl Driver for TerminalDriver {
                 match maybe_event {
                     Some(Ok(Event::Key(key_event))) if key_event.kind == KeyEventKind::Press => {
                         match Self::convert_key(key_event.code) {
-                            Some(key_input) => Ok(self.input_state.handle_key(key_input, app)),
+                            Some(key_input) => {
+                                let modifiers = Self::convert_modifiers(key_event.modifiers);
+                                Ok(self.input_state.handle_key_with_modifiers(key_input, modifiers, app))
+                            },
                             None => Ok(vec![]),
                         }
                     },
@@ -1,6 +1,6 @@
 //! Chat area
 //!
-//! Displays messages in the active room.
+//! Displays messages in the active room, highlighting search matches.
 
 use lockframe_app::App;
 use ratatui::{
@@ -13,31 +13,69 @@ use ratatui::{
 
 const BORDER_SIZE: u16 = 2;
 
+const MILLIS_PER_SECOND: u64 = 1_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Format a millisecond timestamp as `HH:MM:SS` (UTC).
+fn format_time(timestamp_ms: u64) -> String {
+    let secs_of_day = (timestamp_ms / MILLIS_PER_SECOND) % SECONDS_PER_DAY;
+    format!("{:02}:{:02}:{:02}", secs_of_day / 3600, (secs_of_day / 60) % 60, secs_of_day % 60)
+}
+
 /// Render the chat area.
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let title = if let Some(room_id) = app.active_room() {
-        format!(" #{:04x} ", room_id as u16)
+        let room = app.active_room_state();
+        let name =
+            room.and_then(|r| r.name.clone()).unwrap_or_else(|| format!("#{:04x}", room_id as u16));
+        match room.and_then(|r| r.topic.as_deref()) {
+            Some(topic) => format!(" {name} - {topic} "),
+            None => format!(" {name} "),
+        }
     } else {
         " No Room ".to_string()
     };
 
     let block = Block::default().borders(Borders::ALL).title(title);
 
-    let items: Vec<ListItem> = if let Some(room) = app.active_room_state() {
+    let search = app.active_search();
+    let current_hit = search.and_then(lockframe_app::Search::current_hit);
+
+    let mut items: Vec<ListItem> = if let Some(room) = app.active_room_state() {
         room.messages
             .iter()
-            .map(|msg| {
+            .enumerate()
+            .map(|(index, msg)| {
+                let time = msg.timestamp.map_or_else(|| "--:--:--".to_string(), format_time);
                 let sender = format!("<{:04x}>", msg.sender_id as u16);
-                let content = msg.content_str();
+                let content = if msg.redacted {
+                    Span::styled(
+                        "[redacted]",
+                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                    )
+                } else {
+                    Span::raw(msg.content_str().into_owned())
+                };
 
-                ListItem::new(Line::from(vec![
+                let line = Line::from(vec![
+                    Span::styled(time, Style::default().fg(Color::DarkGray)),
+                    Span::raw(" "),
                     Span::styled(
                         sender,
                         Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
                     ),
                     Span::raw(" "),
-                    Span::raw(content.into_owned()),
-                ]))
+                    content,
+                ]);
+
+                let item = ListItem::new(line);
+                if current_hit == Some(index) {
+                    item.style(Style::default().add_modifier(Modifier::REVERSED))
+                } else if search.is_some_and(|s| s.hits.contains(&index)) {
+                    item.style(Style::default().bg(Color::Yellow).fg(Color::Black))
+                } else {
+                    item
+                }
             })
             .collect()
     } else {
@@ -48,7 +86,18 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     };
 
     let visible_height = area.height.saturating_sub(BORDER_SIZE) as usize;
-    let skip = items.len().saturating_sub(visible_height);
+    let truncated = app.active_room_state().is_some_and(|room| room.truncated);
+    if truncated && items.len() < visible_height {
+        items.insert(
+            0,
+            ListItem::new(Line::from(Span::styled(
+                "history truncated",
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            ))),
+        );
+    }
+    let tail = items.len().saturating_sub(visible_height);
+    let skip = current_hit.map_or(tail, |hit| tail.min(hit));
     let visible_items: Vec<_> = items.into_iter().skip(skip).collect();
 
     let list = List::new(visible_items).block(block);
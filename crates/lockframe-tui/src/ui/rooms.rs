@@ -1,6 +1,6 @@
 //! Rooms sidebar
 //!
-//! Displays the list of joined rooms with unread indicators.
+//! Displays the list of joined rooms with unread count badges.
 
 use lockframe_app::App;
 use ratatui::{
@@ -14,16 +14,24 @@ use ratatui::{
 const ACTIVE_PREFIX: &str = ">";
 const INACTIVE_PREFIX: &str = " ";
 const ROOM_ID_PREFIX: &str = "#";
-const UNREAD_MARKER: &str = "*";
-const EMPTY_MARKER: &str = "";
 const ROOM_ID_HEX_WIDTH: usize = 4;
+const UNREAD_DISPLAY_CAP: usize = 99;
 
 enum RoomDisplayState {
     Active,
-    Unread,
+    Unread(usize),
     Normal,
 }
 
+/// Unread badge text, capped at `99+`.
+fn unread_badge(count: usize) -> String {
+    if count > UNREAD_DISPLAY_CAP {
+        format!(" ({UNREAD_DISPLAY_CAP}+)")
+    } else {
+        format!(" ({count})")
+    }
+}
+
 /// Render the rooms sidebar.
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let mut room_ids: Vec<_> = app.rooms().keys().copied().collect();
@@ -32,10 +40,11 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let items: Vec<ListItem> = room_ids
         .iter()
         .map(|&room_id| {
+            let unread_count = app.rooms().get(&room_id).map_or(0, |r| r.unread_count);
             let state = if app.active_room() == Some(room_id) {
                 RoomDisplayState::Active
-            } else if app.rooms().get(&room_id).is_some_and(|r| r.unread) {
-                RoomDisplayState::Unread
+            } else if unread_count > 0 {
+                RoomDisplayState::Unread(unread_count)
             } else {
                 RoomDisplayState::Normal
             };
@@ -47,13 +56,13 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
             let (prefix, suffix, style) = match state {
                 RoomDisplayState::Active => (
                     ACTIVE_PREFIX,
-                    EMPTY_MARKER,
+                    String::new(),
                     Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
                 ),
-                RoomDisplayState::Unread => {
-                    (INACTIVE_PREFIX, UNREAD_MARKER, Style::default().fg(Color::Cyan))
+                RoomDisplayState::Unread(count) => {
+                    (INACTIVE_PREFIX, unread_badge(count), Style::default().fg(Color::Cyan))
                 },
-                RoomDisplayState::Normal => (INACTIVE_PREFIX, EMPTY_MARKER, Style::default()),
+                RoomDisplayState::Normal => (INACTIVE_PREFIX, String::new(), Style::default()),
             };
 
             let unread_style = Style::default().fg(Color::Red);
@@ -71,3 +80,15 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
 
     frame.render_widget(list, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unread_badge_caps_display() {
+        assert_eq!(unread_badge(1), " (1)");
+        assert_eq!(unread_badge(99), " (99)");
+        assert_eq!(unread_badge(100), " (99+)");
+    }
+}
@@ -214,6 +214,7 @@ fn create_frame_from_fuzzed(fuzzed: &FuzzedFrame) -> Frame {
                 session_id: *session_id,
                 capabilities: vec![],
                 challenge: None,
+                unread: vec![],
             });
             reply
                 .into_frame(FrameHeader::new(Opcode::HelloReply))
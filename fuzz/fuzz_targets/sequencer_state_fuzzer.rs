@@ -79,7 +79,7 @@ fuzz_target!(|ops: Vec<SequencerOp>| {
                                 SequencerAction::RejectFrame { .. } => {
                                     panic!("RejectFrame action in Ok result!");
                                 }
-                                SequencerAction::StoreFrame { room_id: action_room, log_index, frame } => {
+                                SequencerAction::StoreFrame { room_id: action_room, log_index, frame, .. } => {
                                     assert_eq!(action_room, room_id_value);
                                     let _ = storage.store_frame(action_room, log_index, &frame);
                                 }